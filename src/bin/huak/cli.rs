@@ -1,29 +1,55 @@
 use crate::error::{CliResult, Error};
-use clap::{Command, CommandFactory, Parser, Subcommand};
+use clap::{
+    error::ErrorKind, Command, CommandFactory, Parser, Subcommand, ValueEnum,
+};
 use clap_complete::{self, Shell};
 use huak::{
+    canonical_path, discover_workspace_root, is_interactive,
     ops::{
-        activate_python_environment, add_project_dependencies,
-        add_project_optional_dependencies, build_project, clean_project,
-        display_project_version, format_project, init_app_project,
-        init_lib_project, install_project_dependencies, lint_project,
-        list_python, new_app_project, new_lib_project, publish_project,
-        remove_project_dependencies, run_command_str, test_project,
-        update_project_dependencies, use_python, AddOptions, BuildOptions,
-        CleanOptions, FormatOptions, LintOptions, PublishOptions,
-        RemoveOptions, TestOptions, UpdateOptions,
-    },
-    Config, Error as HuakError, HuakResult, InstallOptions, TerminalOptions,
-    Verbosity, Version, WorkspaceOptions,
+        activate_python_environment, add_project_classifiers,
+        add_project_dependencies, add_project_optional_dependencies,
+        bench_project, build_docker_image, build_project, check_project,
+        ci_project, clean_project, display_project_version, export_environment,
+        format_project, generate_devcontainer, generate_envrc,
+        generate_ide_config, generate_project_stubs,
+        hoist_workspace_dependencies, import_environment, init_app_project,
+        init_lib_project, install_project_dependencies, install_project_hooks,
+        install_project_kernel, lint_project, list_python, list_scripts,
+        list_workspace_members, lock_project, new_app_project, new_lib_project,
+        print_cache_stats, print_environment_path, print_environment_python,
+        print_history, print_package_info, print_python_info, profile_project,
+        prune_cache, publish_project, record_history, release_project,
+        remove_project_dependencies, remove_project_kernel, rollback_operation,
+        run_command, run_script, run_tool, run_workspace_members,
+        search_trove_classifiers, serve_project, test_project,
+        uninstall_project_hooks, update_project_changelog,
+        update_project_dependencies, upgrade_project_tools, use_python,
+        validate_project_classifiers, verify_environment, AddOptions,
+        BenchOptions, BuildOptions, CacheOptions, ChangelogOptions,
+        CheckOptions, CiOptions, ClassifiersOptions, CleanOptions,
+        DockerOptions, Editor, EnvOptions, FormatOptions, HistoryOptions,
+        HoistOptions, HooksOptions, IdeOptions, InfoOptions, KernelOptions,
+        LintOptions, LintOutput, LockMode, LockOptions, MembersOptions,
+        ProfileOptions, PublishOptions, PythonInfoOptions, PythonListOptions,
+        ReleaseOptions, RemoveOptions, RollbackOptions, ScriptOptions,
+        ServeOptions, StubsOptions, TestJobs, TestOptions, TestShard,
+        ToolOptions, UpdateOptions, UpgradeOptions,
+    },
+    project_root, Bump, CacheAge, CacheSize, Config, Error as HuakError,
+    HuakResult, InstallOptions, PythonRequest, TerminalOptions, VenvOptions,
+    Verbosity, WorkspaceOptions, EXIT_SUCCESS, EXIT_TOOL_FAILURE,
 };
 use std::{
     fs::File,
     io::Write,
     path::{Path, PathBuf},
-    process::ExitCode,
     str::FromStr,
+    time::SystemTime,
 };
 
+/// An environment variable naming a directory that workspace discovery won't search past.
+const WORKSPACE_BOUNDARY_ENV_VAR: &str = "HUAK_WORKSPACE_BOUNDARY";
+
 /// A Python package manager written in Rust inspired by Cargo.
 #[derive(Parser)]
 #[command(version, author, about, arg_required_else_help = true)]
@@ -32,6 +58,81 @@ pub struct Cli {
     command: Commands,
     #[arg(short, long, global = true)]
     quiet: bool,
+    /// Answer "yes" to confirmation prompts. Required for destructive operations in
+    /// non-interactive contexts.
+    #[arg(short, long, global = true)]
+    yes: bool,
+    /// Run as if huak was started in <PROJECT>. Sets the project's root and the working
+    /// directory for the invocation. Defaults to the nearest parent directory containing
+    /// a pyproject.toml.
+    #[arg(short = 'C', long, global = true)]
+    project: Option<PathBuf>,
+    /// How errors should be reported.
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+    /// Run tools with a minimal set of environment variables for reproducible behavior.
+    #[arg(long, global = true)]
+    isolated_env: bool,
+    /// Wait for other huak processes modifying the project to finish instead of failing.
+    #[arg(long, global = true)]
+    wait: bool,
+    /// Print long output directly instead of through a pager.
+    #[arg(long, global = true)]
+    no_pager: bool,
+    /// Suppress the output of tools and report only a one-line summary of the command.
+    /// Useful for CI logs.
+    #[arg(long, global = true)]
+    quiet_errors: bool,
+    /// Write the full output of the tools huak runs to <LOG_FILE>, even when it's
+    /// suppressed in the terminal.
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ErrorFormat {
+    /// Colored, human-readable diagnostics.
+    #[default]
+    Human,
+    /// One JSON object per error for editors and other tools.
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum LintOutputFormat {
+    /// The output of the lint tools.
+    Text,
+    /// A SARIF 2.1.0 log for code scanning tools.
+    Sarif,
+    /// GitHub Actions annotations.
+    Github,
+}
+
+impl From<LintOutputFormat> for LintOutput {
+    fn from(value: LintOutputFormat) -> Self {
+        match value {
+            LintOutputFormat::Text => LintOutput::Text,
+            LintOutputFormat::Sarif => LintOutput::Sarif,
+            LintOutputFormat::Github => LintOutput::Github,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum IdeEditor {
+    /// Visual Studio Code.
+    Vscode,
+    /// PyCharm.
+    Pycharm,
+}
+
+impl From<IdeEditor> for Editor {
+    fn from(value: IdeEditor) -> Self {
+        match value {
+            IdeEditor::Vscode => Editor::VsCode,
+            IdeEditor::Pycharm => Editor::PyCharm,
+        }
+    }
 }
 
 // List of commands.
@@ -42,21 +143,87 @@ enum Commands {
     Activate,
     /// Add dependencies to the project.
     Add {
+        /// Requirements, wheel or sdist files, or wheel or sdist URLs.
         #[arg(num_args = 1.., required = true)]
         dependencies: Vec<Dependency>,
         /// Adds an optional dependency group.
         #[arg(long)]
         group: Option<String>,
+        /// Allow pinning releases yanked from the package index.
+        #[arg(long)]
+        allow_yanked: bool,
+        /// Only edit the pyproject.toml without installing.
+        #[arg(long)]
+        no_sync: bool,
+        /// List each dependency's extras and choose which to add it with.
+        #[arg(long)]
+        list_extras: bool,
         /// Pass trailing arguments with `--`.
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
     },
+    /// Run the project's benchmarks.
+    Bench {
+        /// Save the results as a baseline with a name.
+        #[arg(long)]
+        save: Option<String>,
+        /// Compare the results to a saved baseline, failing if the mean regresses.
+        #[arg(long, value_name = "BASELINE")]
+        compare: Option<String>,
+        /// The percentage the mean can regress by [default: 10].
+        #[arg(long, requires = "compare")]
+        threshold: Option<f64>,
+        /// Pass trailing arguments with `--` to `pytest`.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
+    },
     /// Build tarball and wheel for the project.
     Build {
+        /// Check that the built wheel contains the project's code.
+        #[arg(long)]
+        check_contents: bool,
+        /// Build each of the workspace's members into the root's dist directory.
+        #[arg(long)]
+        workspace: bool,
         /// Pass trailing arguments with `--`.
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
     },
+    /// Manage huak's cache of downloaded packages and tool environments.
+    Cache {
+        #[command(subcommand)]
+        command: Cache,
+    },
+    /// Update CHANGELOG.md from the Conventional Commits since the last tag.
+    Changelog {
+        /// Write the section for a release version instead of the Unreleased section.
+        #[arg(long, value_name = "VERSION")]
+        release: Option<String>,
+        /// Print the section without writing it.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check the pyproject.toml is valid for publishing.
+    Check {
+        /// Also warn if the project's name is already taken on the package index.
+        #[arg(long)]
+        check_name: bool,
+        /// Also check the huak, mypy, pytest, and ruff tables under [tool].
+        #[arg(long)]
+        schema: bool,
+    },
+    /// Manage the project's trove classifiers.
+    Classifiers {
+        #[command(subcommand)]
+        command: Classifiers,
+    },
+    /// Run the project's CI pipeline: fmt --check, lint, typecheck, test, and build
+    /// --check-contents, or the steps in `[tool.huak.ci] steps`.
+    Ci {
+        /// Pass trailing arguments with `--` to the installer.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
+    },
     /// Remove tarball and wheel from the built project.
     Clean {
         #[arg(long, required = false)]
@@ -65,6 +232,10 @@ enum Commands {
         #[arg(long, required = false)]
         /// Remove all __pycache__ directories.
         include_pycache: bool,
+        #[arg(long, required = false)]
+        /// Remove all build artifacts and caches, including .pyc files, __pycache__,
+        /// build, *.egg-info, and tool cache directories.
+        all: bool,
     },
     /// Generates a shell completion script for supported shells.
     Completion {
@@ -81,6 +252,12 @@ enum Commands {
     },
     /// Auto-fix fixable lint conflicts
     Fix {
+        /// Only fix Python files staged in the git index.
+        #[arg(long)]
+        staged: bool,
+        /// Re-stage the staged files after they're fixed.
+        #[arg(long, requires = "staged")]
+        update_index: bool,
         /// Pass trailing arguments with `--`.
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
@@ -90,10 +267,80 @@ enum Commands {
         /// Check if Python code is formatted.
         #[arg(long)]
         check: bool,
+        /// Only target Python files changed relative to a git ref [default: the merge-base
+        /// with the default branch].
+        #[arg(long, value_name = "REF", num_args = 0..=1, require_equals = true)]
+        changed: Option<Option<String>>,
+        /// Only target Python files staged in the git index.
+        #[arg(long, conflicts_with = "changed")]
+        staged: bool,
+        /// Re-stage the staged files after they're formatted.
+        #[arg(long, requires = "staged")]
+        update_index: bool,
+        /// Files, directories, or glob patterns to format [default: the project].
+        paths: Vec<String>,
+        /// Run for each of the workspace's members in parallel.
+        #[arg(long)]
+        workspace: bool,
+        /// Stop running workspace members once one of them fails.
+        #[arg(long, requires = "workspace")]
+        fail_fast: bool,
         /// Pass trailing arguments with `--`.
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
     },
+    /// Containerize the project with Docker.
+    Docker {
+        #[command(subcommand)]
+        command: Docker,
+    },
+    /// Export and import snapshots of the project's environment.
+    Env {
+        #[command(subcommand)]
+        command: Env,
+    },
+    /// Generate files for tools used with the project.
+    Generate {
+        #[command(subcommand)]
+        command: Generate,
+    },
+    /// Show the commands run in the project, what they changed, and how they exited.
+    History {
+        /// Only show the most recent commands.
+        #[arg(long, short)]
+        limit: Option<usize>,
+        /// Print the history as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Report dependencies required by more than one workspace member.
+    Hoist {
+        /// Write the constraints shared by the members to a pip constraints file.
+        #[arg(long, value_name = "PATH")]
+        write_constraints: Option<PathBuf>,
+    },
+    /// Manage git hooks that run huak commands.
+    Hooks {
+        #[command(subcommand)]
+        command: Hooks,
+    },
+    /// Configure an editor to use the project's environment.
+    Ide {
+        #[arg(value_enum)]
+        editor: IdeEditor,
+    },
+    /// Display a package's installed and published metadata.
+    #[command(visible_alias = "show")]
+    Info {
+        /// The name of the package.
+        name: String,
+        /// List the files installed with the package.
+        #[arg(long)]
+        files: bool,
+        /// List the extras the package provides and what each one requires.
+        #[arg(long)]
+        extras: bool,
+    },
     /// Initialize the existing project.
     Init {
         /// Use an application template.
@@ -105,16 +352,37 @@ enum Commands {
         /// Don't initialize VCS in the project
         #[arg(long)]
         no_vcs: bool,
+        /// Prompt for the project's details.
+        #[arg(short, long)]
+        interactive: bool,
+        /// Add dependencies from requirements*.txt files, or from the active
+        /// environment's top-level packages if there aren't any.
+        #[arg(long)]
+        from_requirements: bool,
+        /// Add a tests directory and .gitignore if they're missing.
+        #[arg(long)]
+        scaffold: bool,
     },
     /// Install the dependencies of an existing project.
     Install {
         /// Install optional dependency groups
         #[arg(long, num_args = 1..)]
         groups: Option<Vec<String>>,
+        /// Install from huak.lock, failing if it's missing or out of date.
+        #[arg(long, conflicts_with_all = ["groups", "frozen"])]
+        locked: bool,
+        /// Install from huak.lock without checking if it's up to date.
+        #[arg(long, conflicts_with = "groups")]
+        frozen: bool,
         /// Pass trailing arguments with `--`.
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
     },
+    /// Manage the project's Jupyter kernel.
+    Kernel {
+        #[command(subcommand)]
+        command: Kernel,
+    },
     /// Lint the project's Python code.
     Lint {
         /// Address any fixable lints.
@@ -123,10 +391,61 @@ enum Commands {
         /// Perform type-checking.
         #[arg(long)]
         no_types: bool,
+        /// Run a security pass with `bandit`.
+        #[arg(long)]
+        security: bool,
+        /// Find unused code with `vulture`.
+        #[arg(long)]
+        dead_code: bool,
+        /// The format to report findings in.
+        #[arg(long, value_enum, default_value_t = LintOutputFormat::Text)]
+        output: LintOutputFormat,
+        /// Write a SARIF or GitHub report to a file instead of stdout.
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Only target Python files changed relative to a git ref [default: the merge-base
+        /// with the default branch].
+        #[arg(long, value_name = "REF", num_args = 0..=1, require_equals = true)]
+        changed: Option<Option<String>>,
+        /// Only target Python files staged in the git index.
+        #[arg(long, conflicts_with = "changed")]
+        staged: bool,
+        /// Re-stage the staged files after they're fixed.
+        #[arg(long, requires = "staged")]
+        update_index: bool,
+        /// Files, directories, or glob patterns to lint [default: the project].
+        paths: Vec<String>,
+        /// Run for each of the workspace's members in parallel.
+        #[arg(long)]
+        workspace: bool,
+        /// Stop running workspace members once one of them fails.
+        #[arg(long, requires = "workspace")]
+        fail_fast: bool,
         /// Pass trailing arguments with `--` to `ruff`.
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
     },
+    /// Resolve the project's dependencies and pin them in huak.lock.
+    Lock {
+        /// Lock the lowest versions satisfying the project's requirements.
+        #[arg(long)]
+        minimal: bool,
+        /// Also lock for a Python version (for example 3.10).
+        #[arg(long)]
+        python: Option<String>,
+        /// Also lock for a platform (linux, macos, or windows, optionally with an
+        /// architecture like linux-arm64).
+        #[arg(long)]
+        platform: Option<String>,
+        /// Only update a package, keeping the other locked versions where possible.
+        #[arg(long, value_name = "PACKAGE")]
+        update: Vec<String>,
+        /// Pass trailing arguments with `--` to `pip`.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
+    },
+    /// List the workspace's members with their kind and manifest path.
+    LsMembers,
     /// Create a new project at <path>.
     New {
         /// Use an application template.
@@ -135,14 +454,42 @@ enum Commands {
         /// Use a library template [default].
         #[arg(long, conflicts_with = "app")]
         lib: bool,
-        /// Path and name of the python package
-        path: String,
+        /// Path and name of the python package. Prompts for the project's details if
+        /// omitted on an interactive terminal.
+        path: Option<String>,
         /// Don't initialize VCS in the new project
         #[arg(long)]
         no_vcs: bool,
+        /// Prompt for the project's details.
+        #[arg(short, long)]
+        interactive: bool,
+        /// Check that the project's name isn't already taken on PyPI.
+        #[arg(long)]
+        check_pypi: bool,
+    },
+    /// Profile a script, entry point, or module.
+    Profile {
+        /// A path to a script, one of the project's scripts, or a module.
+        target: String,
+        /// Record a flamegraph with `py-spy`.
+        #[arg(long)]
+        flamegraph: bool,
+        /// The path to write the profile to [default: .huak/profiles/<target>.prof, or
+        /// .svg with --flamegraph].
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Pass trailing arguments with `--` to the target.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
     },
     /// Builds and uploads current project to a registry.
     Publish {
+        /// Publish each of the workspace's members that isn't already on PyPI.
+        #[arg(long)]
+        workspace: bool,
+        /// Print which workspace members would be published without publishing them.
+        #[arg(long, requires = "workspace")]
+        dry_run: bool,
         /// Pass trailing arguments with `--`.
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
@@ -152,21 +499,119 @@ enum Commands {
         #[command(subcommand)]
         command: Python,
     },
+    /// Release the next version from the Conventional Commits since the last tag.
+    Release {
+        /// The version bump to release (major, minor, or patch) [default: suggested by
+        /// the commits].
+        #[arg(long, value_parser = Bump::from_str)]
+        bump: Option<Bump>,
+        /// Don't update CHANGELOG.md.
+        #[arg(long)]
+        no_changelog: bool,
+        /// Don't commit the release (implies --no-tag).
+        #[arg(long)]
+        no_commit: bool,
+        /// Don't tag the release commit.
+        #[arg(long)]
+        no_tag: bool,
+        /// Don't build the release.
+        #[arg(long)]
+        no_build: bool,
+        /// Publish the release once it's built.
+        #[arg(long)]
+        publish: bool,
+        /// Print the next version and its changelog without changing anything.
+        #[arg(long)]
+        dry_run: bool,
+        /// Pass trailing arguments with `--` to the installer.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
+    },
     /// Remove dependencies from the project.
     Remove {
         #[arg(num_args = 1.., required = true)]
         dependencies: Vec<String>,
+        /// Only edit the pyproject.toml without uninstalling.
+        #[arg(long)]
+        no_sync: bool,
+        /// Uninstall dependencies even if other declared dependencies still require them.
+        #[arg(long)]
+        force: bool,
+        /// Pass trailing arguments with `--`.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
+    },
+    /// Roll back the last operation that changed the project or its environment.
+    Rollback {
         /// Pass trailing arguments with `--`.
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
     },
-    /// Run a command within the project's environment context.
+    /// Run a command within the project's environment context, or a script from the
+    /// project's scripts directory in its own environment.
     Run {
         #[arg(trailing_var_arg = true)]
         command: Vec<String>,
+        /// List the scripts in the project's scripts directory.
+        #[arg(long, conflicts_with_all = ["command", "script"])]
+        list: bool,
+        /// Run the script in the project's scripts directory named by the first argument,
+        /// even if a command has the same name.
+        #[arg(long, requires = "command")]
+        script: bool,
+    },
+    /// Run the project's long-running entry point, such as a dev server.
+    Serve {
+        /// Restart the entry point when the project's files change.
+        #[arg(long)]
+        watch: bool,
+        /// Pass trailing arguments with `--` to the entry point.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
+    },
+    /// Generate type stubs for the project's package.
+    Stubs {
+        /// The directory to write stubs to [default: stubs, or <name>-stubs with
+        /// --package].
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Generate a companion stubs package with its own pyproject.toml.
+        #[arg(long)]
+        package: bool,
+        /// Pass trailing arguments with `--` to `stubgen`.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
     },
     /// Test the project's Python code.
     Test {
+        /// Test files, directories, or glob patterns to run [default: the project's
+        /// tests].
+        paths: Vec<String>,
+        /// Only run tests for Python files changed relative to a git ref [default: the
+        /// merge-base with the default branch].
+        #[arg(long, value_name = "REF", num_args = 0..=1, require_equals = true)]
+        changed: Option<Option<String>>,
+        /// Run tests in parallel with a number of workers, or `auto` for one per CPU.
+        #[arg(short, long, value_name = "N", value_parser = TestJobs::from_str)]
+        jobs: Option<TestJobs>,
+        /// Only run a shard of the test files, such as `2/4` for the second of four.
+        #[arg(long, value_name = "INDEX/TOTAL", value_parser = TestShard::from_str)]
+        shard: Option<TestShard>,
+        /// Only run the tests that failed during the previous run.
+        #[arg(long, visible_alias = "lf", conflicts_with = "failed_first")]
+        last_failed: bool,
+        /// Run the tests that failed during the previous run first.
+        #[arg(long, visible_alias = "ff")]
+        failed_first: bool,
+        /// Only run tests matching a keyword expression.
+        #[arg(short, value_name = "EXPRESSION")]
+        keyword: Option<String>,
+        /// Run for each of the workspace's members in parallel.
+        #[arg(long)]
+        workspace: bool,
+        /// Stop running workspace members once one of them fails.
+        #[arg(long, requires = "workspace")]
+        fail_fast: bool,
         /// Pass trailing arguments with `--`.
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
@@ -179,62 +624,388 @@ enum Commands {
         #[arg(last = true)]
         trailing: Option<Vec<String>>,
     },
+    /// Upgrade huak's dev tools (ruff, black, mypy, pytest, build, and twine) to their
+    /// latest allowed versions.
+    Upgrade {
+        /// The tools to upgrade [default: every dev tool the project uses].
+        #[arg(num_args = 0..)]
+        tools: Option<Vec<String>>,
+        /// Pass trailing arguments with `--`.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
+    },
+    /// Verify the integrity of the packages installed in the environment.
+    Verify,
     /// Display the version of the project.
     Version,
+    /// Run a command from a package in a cached tool environment, outside the project.
+    X {
+        /// The command to run.
+        tool: String,
+        /// The package providing the command, optionally with a version (for example
+        /// httpie==3.2.2) [default: the command's name].
+        #[arg(long, value_name = "REQUIREMENT")]
+        from: Option<String>,
+        /// Arguments passed on to the command.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
 }
 
 #[derive(Subcommand)]
 enum Python {
-    /// List the installed Python interpreters.
-    List,
+    /// Display the version, ABI and platform tags, and sysconfig paths of the Python
+    /// interpreter the project uses.
+    Info {
+        /// Print the interpreter's details as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the installed Python interpreters and the one the project uses.
+    List {
+        /// Print the interpreters as JSON.
+        #[arg(long)]
+        json: bool,
+    },
     /// Use a specific Python interpreter.
     Use {
-        /// A Python interpreter version number.
+        /// A Python interpreter version number, optionally with an implementation
+        /// (for example 3.11 or pypy@3.10).
         #[arg(required = true)]
         version: PythonVersion,
+        /// Copy the Python executable into the environment instead of symlinking it.
+        #[arg(long)]
+        copies: bool,
+        /// Give the environment access to the interpreter's site-packages.
+        #[arg(long)]
+        system_site_packages: bool,
+        /// Don't install pip into the environment.
+        #[arg(long)]
+        without_pip: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum Cache {
+    /// Remove entries from the cache that are too old or don't fit in its maximum size.
+    Prune {
+        /// The size to shrink the cache to, such as 5GB [default: HUAK_CACHE_MAX_SIZE].
+        #[arg(long, value_name = "SIZE", value_parser = CacheSize::from_str)]
+        max_size: Option<CacheSize>,
+        /// Remove entries unused for this long, such as 30d [default: HUAK_CACHE_MAX_AGE].
+        #[arg(long, value_name = "AGE", value_parser = CacheAge::from_str)]
+        max_age: Option<CacheAge>,
+    },
+    /// Show the cache's disk usage, hit rate, and last use by package.
+    Stats {
+        /// Print the statistics as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum Classifiers {
+    /// Add trove classifiers to the project.
+    Add {
+        /// The classifiers to add.
+        classifiers: Vec<String>,
+        /// Search the trove classifiers and choose which matches to add.
+        #[arg(long)]
+        search: Option<String>,
+        /// Add the Python version classifiers allowed by `requires-python`.
+        #[arg(long)]
+        python: bool,
+        /// Add classifiers that aren't known trove classifiers.
+        #[arg(long)]
+        allow_unknown: bool,
+    },
+    /// Search the trove classifiers.
+    Search {
+        /// The terms every classifier has to contain.
+        #[arg(required = true)]
+        query: Vec<String>,
+    },
+    /// Check the project's classifiers are known trove classifiers.
+    Validate,
+}
+
+#[derive(Subcommand)]
+enum Docker {
+    /// Build an image with a layer for the locked dependencies and a layer for the project.
+    Build {
+        /// Tag the image [default: <name>:<version> and <name>:latest].
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// The repository the default tags are in, such as ghcr.io/me/app [default: the
+        /// project's name].
+        #[arg(long, conflicts_with = "tags")]
+        repository: Option<String>,
+        /// Push the image's tags after it's built.
+        #[arg(long)]
+        push: bool,
+        /// Pass trailing arguments with `--` to `docker build`.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
+    },
+}
+
+#[derive(Subcommand)]
+enum Env {
+    /// Export the exact packages installed in the environment to a snapshot.
+    Export {
+        /// The path to write the snapshot to [default: huak-env.toml].
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Recreate the environment from a snapshot.
+    Import {
+        /// The path to the snapshot [default: huak-env.toml].
+        path: Option<PathBuf>,
+        /// Pass trailing arguments with `--`.
+        #[arg(last = true)]
+        trailing: Option<Vec<String>>,
+    },
+    /// Print the path to the environment's root.
+    Path,
+    /// Print the path to the environment's Python interpreter.
+    Python,
+}
+
+#[derive(Subcommand)]
+enum Generate {
+    /// Generate a .devcontainer that installs the project's locked dependencies.
+    Devcontainer,
+    /// Generate a direnv .envrc that activates the project's environment.
+    Envrc,
+}
+
+#[derive(Subcommand)]
+enum Hooks {
+    /// Install the git hooks configured under [tool.huak.hooks.git].
+    Install {
+        /// Replace existing hooks that weren't installed by huak.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove the git hooks installed by huak.
+    Uninstall,
+}
+
+#[derive(Subcommand)]
+enum Kernel {
+    /// Register a Jupyter kernel that runs the project's environment.
+    Install {
+        /// The name of the kernel [default: the project's name].
+        #[arg(long)]
+        name: Option<String>,
+        /// The name of the kernel displayed by Jupyter [default: Python (<name>)].
+        #[arg(long)]
+        display_name: Option<String>,
+    },
+    /// Remove the project's Jupyter kernel.
+    Remove {
+        /// The name of the kernel [default: the project's name].
+        #[arg(long)]
+        name: Option<String>,
     },
 }
 
+impl Commands {
+    /// Check if the command can change the project or its environment. Only these
+    /// commands are logged to the project's history.
+    fn is_mutating(&self) -> bool {
+        match self {
+            Commands::Add { .. }
+            | Commands::Build { .. }
+            | Commands::Clean { .. }
+            | Commands::Fix { .. }
+            | Commands::Generate { .. }
+            | Commands::Hooks { .. }
+            | Commands::Ide { .. }
+            | Commands::Init { .. }
+            | Commands::Install { .. }
+            | Commands::Kernel { .. }
+            | Commands::Lock { .. }
+            | Commands::New { .. }
+            | Commands::Remove { .. }
+            | Commands::Rollback { .. }
+            | Commands::Stubs { .. }
+            | Commands::Update { .. }
+            | Commands::Upgrade { .. } => true,
+            Commands::Bench { save, .. } => save.is_some(),
+            Commands::Changelog { dry_run, .. }
+            | Commands::Publish { dry_run, .. }
+            | Commands::Release { dry_run, .. } => !dry_run,
+            Commands::Classifiers { command } => {
+                matches!(command, Classifiers::Add { .. })
+            }
+            Commands::Env { command } => {
+                matches!(command, Env::Export { .. } | Env::Import { .. })
+            }
+            Commands::Fmt { check, .. } => !check,
+            Commands::Hoist { write_constraints } => {
+                write_constraints.is_some()
+            }
+            Commands::Lint { fix, .. } => *fix,
+            Commands::Python { command } => {
+                matches!(command, Python::Use { .. })
+            }
+            Commands::Activate
+            | Commands::Cache { .. }
+            | Commands::Check { .. }
+            | Commands::Ci { .. }
+            | Commands::Completion { .. }
+            | Commands::Docker { .. }
+            | Commands::History { .. }
+            | Commands::Info { .. }
+            | Commands::LsMembers
+            | Commands::Profile { .. }
+            | Commands::Run { .. }
+            | Commands::Serve { .. }
+            | Commands::Test { .. }
+            | Commands::Verify
+            | Commands::Version
+            | Commands::X { .. } => false,
+        }
+    }
+}
+
 // Command gating for Huak.
 impl Cli {
+    /// Get the `ErrorFormat` requested for the invocation.
+    pub fn error_format(&self) -> ErrorFormat {
+        self.error_format
+    }
+
+    /// Check if only a one-line summary of the invocation should be reported.
+    pub fn quiet_errors(&self) -> bool {
+        self.quiet_errors
+    }
+
     pub fn run(self) -> CliResult<i32> {
-        let cwd = std::env::current_dir()?;
-        let verbosity = match self.quiet {
+        let verbosity = match self.quiet || self.quiet_errors {
             true => Verbosity::Quiet,
             false => Verbosity::Normal,
         };
-        let mut config = Config {
-            workspace_root: cwd.to_path_buf(),
-            cwd,
-            terminal_options: TerminalOptions { verbosity },
+        // Paths are resolved canonically so that a project reached through a symlink
+        // behaves the same as the directory it points to.
+        let mut cwd = canonical_path(std::env::current_dir()?);
+        // Start the log fresh for each invocation since the output of every command is
+        // appended to it.
+        let log_file = match self.log_file.as_ref() {
+            Some(it) => {
+                let path = cwd.join(it);
+                File::create(&path)?;
+                Some(path)
+            }
+            None => None,
         };
+        let workspace_root = match self.project.as_ref() {
+            Some(it) => {
+                cwd = project_root(&cwd, it)?;
+                cwd.clone()
+            }
+            None => discover_workspace_root(
+                &cwd,
+                std::env::var_os(WORKSPACE_BOUNDARY_ENV_VAR)
+                    .map(canonical_path)
+                    .as_deref(),
+            )
+            .unwrap_or(cwd.to_path_buf()),
+        };
+        let mut config = Config::new(
+            workspace_root,
+            cwd,
+            TerminalOptions {
+                verbosity,
+                assume_yes: self.yes,
+            },
+        );
+        config.isolated_env = self.isolated_env;
+        config.wait_for_lock = self.wait;
+        config.no_pager = self.no_pager;
+        config.quiet_errors = self.quiet_errors;
+        config.log_file = log_file;
+        let started = SystemTime::now();
+        let record = self.command.is_mutating();
+        // Commands that run the user's own command exit with its code.
+        let passthrough = matches!(
+            self.command,
+            Commands::Run { .. } | Commands::Serve { .. } | Commands::X { .. }
+        );
 
         let res = match self.command {
             Commands::Activate => activate(&config),
             Commands::Add {
                 dependencies,
                 group,
+                allow_yanked,
+                no_sync,
+                list_extras,
                 trailing,
             } => {
                 let options = AddOptions {
+                    allow_yanked,
+                    no_sync,
+                    list_extras,
                     install_options: InstallOptions { values: trailing },
                 };
                 add(dependencies, group, &config, &options)
             }
-            Commands::Build { trailing } => {
+            Commands::Bench {
+                save,
+                compare,
+                threshold,
+                trailing,
+            } => {
+                let options = BenchOptions {
+                    values: trailing,
+                    save,
+                    compare,
+                    threshold,
+                    install_options: InstallOptions { values: None },
+                };
+                bench(&config, &options)
+            }
+            Commands::Build {
+                check_contents,
+                workspace,
+                trailing,
+            } => {
                 let options = BuildOptions {
                     values: trailing,
                     install_options: InstallOptions { values: None },
+                    check_contents,
+                    workspace,
                 };
                 build(&config, &options)
             }
+            Commands::Cache { command } => cache(command, &config),
+            Commands::Changelog { release, dry_run } => {
+                let options = ChangelogOptions { release, dry_run };
+                changelog(&config, &options)
+            }
+            Commands::Check { check_name, schema } => {
+                let options = CheckOptions { check_name, schema };
+                check(&config, &options)
+            }
+            Commands::Classifiers { command } => classifiers(command, &config),
+            Commands::Ci { trailing } => {
+                let options = CiOptions {
+                    install_options: InstallOptions { values: trailing },
+                };
+                ci(&config, &options)
+            }
             Commands::Clean {
                 include_pyc,
                 include_pycache,
+                all,
             } => {
                 let options = CleanOptions {
                     include_pycache,
                     include_compiled_bytecode: include_pyc,
+                    include_all: all,
                 };
                 clean(&config, &options)
             }
@@ -250,15 +1021,41 @@ impl Cli {
                 };
                 completion(&options)
             }
-            Commands::Fix { trailing } => {
+            Commands::Fix {
+                staged,
+                update_index,
+                trailing,
+            } => {
                 let options = LintOptions {
                     values: trailing,
                     include_types: false,
+                    only_types: false,
+                    include_security: false,
+                    include_dead_code: false,
                     install_options: InstallOptions { values: None },
+                    output: LintOutput::Text,
+                    output_path: None,
+                    changed: None,
+                    staged,
+                    update_index,
+                    paths: Vec::new(),
                 };
                 fix(&config, &options)
             }
-            Commands::Fmt { check, trailing } => {
+            Commands::Fmt {
+                workspace: true,
+                fail_fast,
+                ..
+            } => members(&config, fail_fast),
+            Commands::Fmt {
+                check,
+                changed,
+                staged,
+                update_index,
+                paths,
+                trailing,
+                ..
+            } => {
                 let mut args = if check {
                     vec!["--check".to_string()]
                 } else {
@@ -270,22 +1067,119 @@ impl Cli {
                 let options = FormatOptions {
                     values: Some(args),
                     install_options: InstallOptions { values: None },
+                    changed,
+                    staged,
+                    update_index,
+                    paths,
                 };
                 fmt(&config, &options)
             }
-            Commands::Init { app, lib, no_vcs } => {
+            Commands::Docker { command } => docker(command, &config),
+            Commands::Env { command } => env(command, &config),
+            Commands::Generate { command } => generate(command, &config),
+            Commands::History { limit, json } => {
+                let options = HistoryOptions { limit, json };
+                history(&config, &options)
+            }
+            Commands::Hoist { write_constraints } => {
+                let options = HoistOptions {
+                    constraints_path: write_constraints,
+                };
+                hoist_workspace_dependencies(&config, &options)
+            }
+            Commands::Hooks { command } => hooks(command, &config),
+            Commands::Ide { editor } => {
+                let options = IdeOptions {
+                    editor: editor.into(),
+                };
+                ide(&config, &options)
+            }
+            Commands::Info {
+                name,
+                files,
+                extras,
+            } => {
+                let options = InfoOptions {
+                    name,
+                    files,
+                    extras,
+                };
+                info(&config, &options)
+            }
+            Commands::Init {
+                app,
+                lib,
+                no_vcs,
+                interactive,
+                from_requirements,
+                scaffold,
+            } => {
                 config.workspace_root = config.cwd.clone();
-                let options = WorkspaceOptions { uses_git: !no_vcs };
-                init(app, lib, &config, &options)
+                if interactive {
+                    let name = config
+                        .cwd
+                        .file_name()
+                        .map(|it| it.to_string_lossy().to_string());
+                    project_wizard(name, app, no_vcs, &config).and_then(
+                        |(_, app, mut options)| {
+                            options.from_requirements = from_requirements
+                                || config
+                                    .terminal()
+                                    .prompt(
+                                        "Import existing requirements (y, n)",
+                                        Some("n"),
+                                    )?
+                                    .to_lowercase()
+                                    .starts_with('y');
+                            options.scaffold = scaffold;
+                            init(app, lib, &config, &options)
+                        },
+                    )
+                } else {
+                    let options = WorkspaceOptions {
+                        uses_git: !no_vcs,
+                        from_requirements,
+                        scaffold,
+                        ..Default::default()
+                    };
+                    init(app, lib, &config, &options)
+                }
             }
-            Commands::Install { groups, trailing } => {
+            Commands::Install {
+                groups,
+                locked,
+                frozen,
+                trailing,
+            } => {
+                let lock_mode = if locked {
+                    LockMode::Locked
+                } else if frozen {
+                    LockMode::Frozen
+                } else {
+                    LockMode::Auto
+                };
                 let options = InstallOptions { values: trailing };
-                install(groups, &config, &options)
+                install(groups, lock_mode, &config, &options)
             }
+            Commands::Kernel { command } => kernel(command, &config),
+            Commands::Lint {
+                workspace: true,
+                fail_fast,
+                ..
+            } => members(&config, fail_fast),
             Commands::Lint {
                 fix,
                 no_types,
+                security,
+                dead_code,
+                output,
+                output_file,
+                changed,
+                staged,
+                update_index,
+                paths,
                 trailing,
+                ..
             } => {
                 let mut args = if fix {
                     vec!["--fix".to_string()]
@@ -298,42 +1192,189 @@ impl Cli {
                 let options = LintOptions {
                     values: Some(args),
                     include_types: !no_types,
+                    only_types: false,
+                    include_security: security,
+                    include_dead_code: dead_code,
                     install_options: InstallOptions { values: None },
+                    output: output.into(),
+                    output_path: output_file,
+                    changed,
+                    staged,
+                    update_index,
+                    paths,
                 };
                 lint(&config, &options)
             }
+            Commands::Lock {
+                minimal,
+                python,
+                platform,
+                update,
+                trailing,
+            } => {
+                let options = LockOptions {
+                    minimal,
+                    python,
+                    platform,
+                    update,
+                    install_options: InstallOptions { values: trailing },
+                };
+                lock(&config, &options)
+            }
+            Commands::LsMembers => list_workspace_members(&config),
             Commands::New {
                 path,
                 app,
                 lib,
                 no_vcs,
+                interactive,
+                check_pypi,
+            } => {
+                let res = if interactive || (path.is_none() && is_interactive())
+                {
+                    project_wizard(path, app, no_vcs, &config)
+                } else {
+                    path.ok_or_else(|| {
+                        HuakError::ClapError(Cli::command().error(
+                            ErrorKind::MissingRequiredArgument,
+                            "a path is required for a new project",
+                        ))
+                    })
+                    .map(|path| {
+                        let options = WorkspaceOptions {
+                            uses_git: !no_vcs,
+                            ..Default::default()
+                        };
+                        (path, app, options)
+                    })
+                };
+                res.and_then(|(path, app, mut options)| {
+                    options.check_index = check_pypi;
+                    config.workspace_root = config.cwd.join(path);
+                    new(app, lib, &config, &options)
+                })
+            }
+            Commands::Profile {
+                target,
+                flamegraph,
+                output,
+                trailing,
             } => {
-                config.workspace_root = PathBuf::from(path);
-                let options = WorkspaceOptions { uses_git: !no_vcs };
-                new(app, lib, &config, &options)
+                let options = ProfileOptions {
+                    target,
+                    values: trailing,
+                    flamegraph,
+                    output,
+                    install_options: InstallOptions { values: None },
+                };
+                profile(&config, &options)
             }
-            Commands::Publish { trailing } => {
+            Commands::Publish {
+                workspace,
+                dry_run,
+                trailing,
+            } => {
                 let options = PublishOptions {
                     values: trailing,
                     install_options: InstallOptions { values: None },
+                    workspace,
+                    dry_run,
                 };
                 publish(&config, &options)
             }
             Commands::Python { command } => python(command, &config),
+            Commands::Release {
+                bump,
+                no_changelog,
+                no_commit,
+                no_tag,
+                no_build,
+                publish,
+                dry_run,
+                trailing,
+            } => {
+                let options = ReleaseOptions {
+                    bump,
+                    no_changelog,
+                    no_commit,
+                    no_tag,
+                    no_build,
+                    publish,
+                    dry_run,
+                    install_options: InstallOptions { values: trailing },
+                };
+                release(&config, &options)
+            }
             Commands::Remove {
                 dependencies,
+                no_sync,
+                force,
                 trailing,
             } => {
                 let options = RemoveOptions {
+                    no_sync,
+                    force,
                     install_options: InstallOptions { values: trailing },
                 };
                 remove(dependencies, &config, &options)
             }
-            Commands::Run { command } => run(command, &config),
-            Commands::Test { trailing } => {
+            Commands::Rollback { trailing } => {
+                let options = RollbackOptions {
+                    install_options: InstallOptions { values: trailing },
+                };
+                rollback(&config, &options)
+            }
+            Commands::Run {
+                command,
+                list,
+                script,
+            } => run(command, list, script, &config),
+            Commands::Serve { watch, trailing } => {
+                let options = ServeOptions {
+                    watch,
+                    values: trailing,
+                };
+                serve(&config, &options)
+            }
+            Commands::Stubs {
+                output,
+                package,
+                trailing,
+            } => {
+                let options = StubsOptions {
+                    values: trailing,
+                    output,
+                    package,
+                    install_options: InstallOptions { values: None },
+                };
+                stubs(&config, &options)
+            }
+            Commands::Test {
+                workspace: true,
+                fail_fast,
+                ..
+            } => members(&config, fail_fast),
+            Commands::Test {
+                paths,
+                changed,
+                jobs,
+                shard,
+                last_failed,
+                failed_first,
+                keyword,
+                trailing,
+                ..
+            } => {
                 let options = TestOptions {
                     values: trailing,
                     install_options: InstallOptions { values: None },
+                    changed,
+                    paths,
+                    jobs,
+                    shard,
+                    last_failed,
+                    failed_first,
+                    keyword,
                 };
                 test(&config, &options)
             }
@@ -346,16 +1387,49 @@ impl Cli {
                 };
                 update(dependencies, &config, &options)
             }
+            Commands::Upgrade { tools, trailing } => {
+                let options = UpgradeOptions {
+                    tools,
+                    install_options: InstallOptions { values: trailing },
+                };
+                upgrade(&config, &options)
+            }
+            Commands::Verify => verify(&config),
             Commands::Version => version(&config),
+            Commands::X { tool, from, args } => {
+                let options = ToolOptions {
+                    tool,
+                    from,
+                    values: args,
+                    install_options: InstallOptions { values: None },
+                };
+                x(&config, &options)
+            }
         };
 
+        if record {
+            let (status, error) = match res.as_ref() {
+                Ok(_) => (0, None),
+                Err(HuakError::SubprocessFailure(e)) => {
+                    (e.code().unwrap_or_default(), None)
+                }
+                Err(e) => (1, Some(e.to_string())),
+            };
+            let args = std::env::args().skip(1).collect::<Vec<_>>();
+            // The command already ran, so failing to log it shouldn't fail it.
+            record_history(&config, &args, started, status, error).ok();
+        }
+
         match res {
-            Ok(_) => Ok(0),
-            // TODO: Implement our own ExitCode or status handler.
-            Err(HuakError::SubprocessFailure(e)) => {
-                Ok(e.code().unwrap_or_default())
+            Ok(_) => Ok(EXIT_SUCCESS as i32),
+            Err(HuakError::SubprocessFailure(e)) if passthrough => {
+                Ok(e.code().unwrap_or(EXIT_TOOL_FAILURE as i32))
+            }
+            // The tool already reported its failure.
+            Err(HuakError::SubprocessFailure(_)) => {
+                Ok(EXIT_TOOL_FAILURE as i32)
             }
-            Err(e) => Err(Error::new(e, ExitCode::FAILURE)),
+            Err(e) => Err(Error::from(e)),
         }
     }
 }
@@ -382,10 +1456,70 @@ fn add(
     }
 }
 
+fn bench(config: &Config, options: &BenchOptions) -> HuakResult<()> {
+    bench_project(config, options)
+}
+
 fn build(config: &Config, options: &BuildOptions) -> HuakResult<()> {
     build_project(config, options)
 }
 
+fn cache(command: Cache, config: &Config) -> HuakResult<()> {
+    match command {
+        Cache::Prune { max_size, max_age } => {
+            let options = CacheOptions {
+                max_size,
+                max_age,
+                json: false,
+            };
+            prune_cache(config, &options)
+        }
+        Cache::Stats { json } => {
+            let options = CacheOptions {
+                max_size: None,
+                max_age: None,
+                json,
+            };
+            print_cache_stats(config, &options)
+        }
+    }
+}
+
+fn changelog(config: &Config, options: &ChangelogOptions) -> HuakResult<()> {
+    update_project_changelog(config, options)
+}
+
+fn check(config: &Config, options: &CheckOptions) -> HuakResult<()> {
+    check_project(config, options)
+}
+
+fn classifiers(command: Classifiers, config: &Config) -> HuakResult<()> {
+    match command {
+        Classifiers::Add {
+            classifiers,
+            search,
+            python,
+            allow_unknown,
+        } => {
+            let options = ClassifiersOptions {
+                classifiers,
+                search,
+                python,
+                allow_unknown,
+            };
+            add_project_classifiers(config, &options)
+        }
+        Classifiers::Search { query } => {
+            search_trove_classifiers(config, &query.join(" "))
+        }
+        Classifiers::Validate => validate_project_classifiers(config),
+    }
+}
+
+fn ci(config: &Config, options: &CiOptions) -> HuakResult<()> {
+    ci_project(config, options)
+}
+
 fn clean(config: &Config, options: &CleanOptions) -> HuakResult<()> {
     clean_project(config, options)
 }
@@ -398,6 +1532,75 @@ fn fmt(config: &Config, options: &FormatOptions) -> HuakResult<()> {
     format_project(config, options)
 }
 
+fn docker(command: Docker, config: &Config) -> HuakResult<()> {
+    match command {
+        Docker::Build {
+            tags,
+            repository,
+            push,
+            trailing,
+        } => {
+            let options = DockerOptions {
+                tags,
+                repository,
+                push,
+                values: trailing,
+            };
+            build_docker_image(config, &options)
+        }
+    }
+}
+
+fn env(command: Env, config: &Config) -> HuakResult<()> {
+    match command {
+        Env::Export { output } => {
+            let options = EnvOptions {
+                path: output,
+                install_options: InstallOptions { values: None },
+            };
+            export_environment(config, &options)
+        }
+        Env::Import { path, trailing } => {
+            let options = EnvOptions {
+                path,
+                install_options: InstallOptions { values: trailing },
+            };
+            import_environment(config, &options)
+        }
+        Env::Path => print_environment_path(config),
+        Env::Python => print_environment_python(config),
+    }
+}
+
+fn generate(command: Generate, config: &Config) -> HuakResult<()> {
+    match command {
+        Generate::Devcontainer => generate_devcontainer(config),
+        Generate::Envrc => generate_envrc(config),
+    }
+}
+
+fn history(config: &Config, options: &HistoryOptions) -> HuakResult<()> {
+    print_history(config, options)
+}
+
+fn hooks(command: Hooks, config: &Config) -> HuakResult<()> {
+    match command {
+        Hooks::Install { force } => {
+            let options = HooksOptions { force };
+            install_project_hooks(config, &options)
+        }
+        Hooks::Uninstall => uninstall_project_hooks(config),
+    }
+}
+
+fn ide(config: &Config, options: &IdeOptions) -> HuakResult<()> {
+    generate_ide_config(config, options)
+}
+
+fn info(config: &Config, options: &InfoOptions) -> HuakResult<()> {
+    print_package_info(config, options)
+}
+
 fn init(
     app: bool,
     _lib: bool,
@@ -413,16 +1616,49 @@ fn init(
 
 fn install(
     groups: Option<Vec<String>>,
+    lock_mode: LockMode,
     config: &Config,
     options: &InstallOptions,
 ) -> HuakResult<()> {
-    install_project_dependencies(groups.as_ref(), config, options)
+    install_project_dependencies(groups.as_ref(), lock_mode, config, options)
 }
 
 fn lint(config: &Config, options: &LintOptions) -> HuakResult<()> {
     lint_project(config, options)
 }
 
+fn lock(config: &Config, options: &LockOptions) -> HuakResult<()> {
+    lock_project(config, options)
+}
+
+/// Run the invoked command again from each workspace member's directory. Arguments
+/// selecting the workspace or project are dropped so each run targets its member.
+fn members(config: &Config, fail_fast: bool) -> HuakResult<()> {
+    let mut args = Vec::new();
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            // Trailing arguments are passed on as-is.
+            "--" => {
+                args.push(arg);
+                args.extend(argv.by_ref());
+            }
+            "--workspace" | "--fail-fast" => (),
+            "-C" | "--project" => {
+                argv.next();
+            }
+            it if it.starts_with("--project=") || it.starts_with("-C") => (),
+            _ => args.push(arg),
+        }
+    }
+    let options = MembersOptions {
+        program: std::env::current_exe()?,
+        args,
+        fail_fast,
+    };
+    run_workspace_members(config, &options)
+}
+
 fn new(
     app: bool,
     _lib: bool,
@@ -436,17 +1672,132 @@ fn new(
     }
 }
 
+/// Prompt for a project's details, returning its path, whether it's an app, and the
+/// `WorkspaceOptions` to create it with.
+fn project_wizard(
+    path: Option<String>,
+    app: bool,
+    no_vcs: bool,
+    config: &Config,
+) -> HuakResult<(String, bool, WorkspaceOptions)> {
+    let mut terminal = config.terminal();
+    let default_name = path.as_ref().and_then(|it| {
+        Path::new(it)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    });
+    let name = terminal.prompt("Project name", default_name.as_deref())?;
+    if name.is_empty() {
+        return Err(HuakError::HuakConfigurationError(
+            "a project name is required".to_string(),
+        ));
+    }
+    let app = match terminal
+        .prompt(
+            "Project type (app, lib)",
+            Some(if app { "app" } else { "lib" }),
+        )?
+        .as_str()
+    {
+        "app" => true,
+        "lib" => false,
+        it => {
+            return Err(HuakError::HuakConfigurationError(format!(
+                "{it} is not a project type"
+            )))
+        }
+    };
+    let python_version =
+        terminal.prompt("Minimum Python version (blank for none)", None)?;
+    let license = terminal.prompt("License (blank for none)", Some("MIT"))?;
+    let tools = terminal.prompt(
+        "Development tools (blank for none)",
+        Some("pytest, ruff, black, mypy"),
+    )?;
+    let uses_git = terminal
+        .prompt(
+            "Initialize a git repository (y, n)",
+            Some(if no_vcs { "n" } else { "y" }),
+        )?
+        .to_lowercase()
+        .starts_with('y');
+
+    let options = WorkspaceOptions {
+        uses_git,
+        name: Some(name.clone()),
+        python_version: Some(python_version).filter(|it| !it.is_empty()),
+        license: Some(license).filter(|it| !it.is_empty()),
+        dev_dependencies: tools
+            .split([',', ' '])
+            .filter(|it| !it.is_empty())
+            .map(str::to_string)
+            .collect(),
+        from_requirements: false,
+        scaffold: false,
+        check_index: false,
+    };
+
+    Ok((path.unwrap_or(name), app, options))
+}
+
+fn profile(config: &Config, options: &ProfileOptions) -> HuakResult<()> {
+    profile_project(config, options)
+}
+
 fn publish(config: &Config, options: &PublishOptions) -> HuakResult<()> {
     publish_project(config, options)
 }
 
+fn kernel(command: Kernel, config: &Config) -> HuakResult<()> {
+    match command {
+        Kernel::Install { name, display_name } => {
+            let options = KernelOptions {
+                name,
+                display_name,
+                install_options: InstallOptions { values: None },
+            };
+            install_project_kernel(config, &options)
+        }
+        Kernel::Remove { name } => {
+            let options = KernelOptions {
+                name,
+                display_name: None,
+                install_options: InstallOptions { values: None },
+            };
+            remove_project_kernel(config, &options)
+        }
+    }
+}
+
 fn python(command: Python, config: &Config) -> HuakResult<()> {
     match command {
-        Python::List => list_python(config),
-        Python::Use { version } => use_python(version.0.as_str(), config),
+        Python::Info { json } => {
+            print_python_info(config, &PythonInfoOptions { json })
+        }
+        Python::List { json } => {
+            list_python(config, &PythonListOptions { json })
+        }
+        Python::Use {
+            version,
+            copies,
+            system_site_packages,
+            without_pip,
+        } => {
+            let options = VenvOptions {
+                copies,
+                system_site_packages,
+                without_pip,
+                install_dependencies: false,
+            };
+            use_python(version.0.as_str(), &options, config)
+        }
     }
 }
 
+fn release(config: &Config, options: &ReleaseOptions) -> HuakResult<()> {
+    release_project(config, options)
+}
+
 fn remove(
     dependencies: Vec<String>,
     config: &Config,
@@ -455,8 +1806,38 @@ fn remove(
     remove_project_dependencies(&dependencies, config, options)
 }
 
-fn run(command: Vec<String>, config: &Config) -> HuakResult<()> {
-    run_command_str(&command.join(" "), config)
+fn rollback(config: &Config, options: &RollbackOptions) -> HuakResult<()> {
+    rollback_operation(config, options)
+}
+
+fn run(
+    command: Vec<String>,
+    list: bool,
+    script: bool,
+    config: &Config,
+) -> HuakResult<()> {
+    if list {
+        return list_scripts(config);
+    }
+    match command.split_first() {
+        Some((name, args)) if script => {
+            let options = ScriptOptions {
+                script: name.clone(),
+                values: args.to_vec(),
+                install_options: InstallOptions { values: None },
+            };
+            run_script(config, &options)
+        }
+        _ => run_command(&command, config),
+    }
+}
+
+fn serve(config: &Config, options: &ServeOptions) -> HuakResult<()> {
+    serve_project(config, options)
+}
+
+fn stubs(config: &Config, options: &StubsOptions) -> HuakResult<()> {
+    generate_project_stubs(config, options)
 }
 
 fn test(config: &Config, options: &TestOptions) -> HuakResult<()> {
@@ -471,10 +1852,22 @@ fn update(
     update_project_dependencies(dependencies, config, options)
 }
 
+fn upgrade(config: &Config, options: &UpgradeOptions) -> HuakResult<()> {
+    upgrade_project_tools(config, options)
+}
+
+fn verify(config: &Config) -> HuakResult<()> {
+    verify_environment(config)
+}
+
 fn version(config: &Config) -> HuakResult<()> {
     display_project_version(config)
 }
 
+fn x(config: &Config, options: &ToolOptions) -> HuakResult<()> {
+    run_tool(config, options)
+}
+
 fn completion(options: &CompletionOptions) -> HuakResult<()> {
     if (options.install || options.uninstall) && options.shell.is_none() {
         Err(HuakError::HuakConfigurationError(
@@ -633,13 +2026,17 @@ impl FromStr for Dependency {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // URLs and archive files are kept as they are since they can contain `@`s.
+        if s.contains("://") || Path::new(s).is_file() {
+            return Ok(Self(s.to_string()));
+        }
         Ok(Self(s.replace('@', "==")))
     }
 }
 
-impl ToString for Dependency {
-    fn to_string(&self) -> String {
-        self.0.to_owned()
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
@@ -650,13 +2047,12 @@ impl FromStr for PythonVersion {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let version = Version::from_str(s).map_err(|_| {
-            Error::new(
-                HuakError::InternalError("failed to parse version".to_string()),
-                ExitCode::FAILURE,
-            )
+        let request = PythonRequest::from_str(s).map_err(|_| {
+            Error::from(HuakError::HuakConfigurationError(
+                "failed to parse version".to_string(),
+            ))
         })?;
 
-        Ok(Self(version.to_string()))
+        Ok(Self(request.to_string()))
     }
 }
@@ -26,26 +26,33 @@ impl std::fmt::Display for Error {
     }
 }
 
+impl From<huak::Error> for Error {
+    fn from(e: huak::Error) -> Error {
+        let exit_code = ExitCode::from(e.exit_code());
+        Error::new(e, exit_code)
+    }
+}
+
 impl From<clap::Error> for Error {
     fn from(e: clap::Error) -> Error {
-        Error::new(huak::Error::ClapError(e), ExitCode::FAILURE)
+        Error::from(huak::Error::ClapError(e))
     }
 }
 
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Error {
-        Error::new(huak::Error::IOError(e), ExitCode::FAILURE)
+        Error::from(huak::Error::IOError(e))
     }
 }
 
 impl From<std::io::ErrorKind> for Error {
     fn from(e: std::io::ErrorKind) -> Error {
-        Error::new(huak::Error::InternalError(e.to_string()), ExitCode::FAILURE)
+        Error::from(huak::Error::InternalError(e.to_string()))
     }
 }
 
 impl From<std::env::VarError> for Error {
     fn from(e: std::env::VarError) -> Error {
-        Error::new(huak::Error::EnvVarError(e), ExitCode::FAILURE)
+        Error::from(huak::Error::EnvVarError(e))
     }
 }
@@ -2,29 +2,130 @@
 //!
 //! Huak implements a cli application with various subcommands.
 mod cli;
-use clap::Parser;
-use cli::Cli;
+use clap::{CommandFactory, FromArgMatches};
+use cli::{Cli, ErrorFormat};
 use colored::Colorize;
-use human_panic::setup_panic;
+use human_panic::{handle_dump, print_msg, Metadata};
 use std::process::{exit, ExitCode};
 
 mod error;
 
 /// Launch Huak's cli process.
 pub fn main() -> ExitCode {
-    setup_panic!();
+    setup_panic_hook();
 
-    match Cli::parse().run() {
-        Ok(0) => ExitCode::SUCCESS,
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches)
+        .unwrap_or_else(|e| e.format(&mut Cli::command()).exit());
+    let error_format = cli.error_format();
+    let quiet_errors = cli.quiet_errors();
+    let ci = huak::CiProvider::detect().is_some();
+    // Successful commands are only summarized when asked for, so that output meant for
+    // other programs (such as `huak env path`) stays clean in CI.
+    let summary = (quiet_errors || ci)
+        .then(|| matches.subcommand_name().unwrap_or_default().to_string());
+    if let Some(it) = huak::color_override() {
+        colored::control::set_override(it);
+    }
+
+    match cli.run() {
+        Ok(0) => {
+            if let Some(op) = summary.as_ref().filter(|_| quiet_errors) {
+                eprintln!("huak {op}: ok");
+            }
+            ExitCode::SUCCESS
+        }
         // Lazy-like exit of a subprocess failure. TODO: https://github.com/cnpryer/huak/issues/631
-        Ok(code) => exit(code),
+        Ok(code) => {
+            if let Some(op) = summary.as_ref() {
+                eprintln!("huak {op}: failed (exit {code})");
+            }
+            exit(code)
+        }
         Err(e) => {
-            if e.error.to_string().is_empty() {
-                eprintln!("{}", e.error);
-            } else {
-                eprintln!("{}{} {}", "error".red(), ":".bold(), e.error);
+            match summary.as_ref() {
+                // In CI the summary follows the full error rather than replacing it.
+                Some(op) if matches!(error_format, ErrorFormat::Human) => {
+                    if ci && !quiet_errors {
+                        report_error(&e.error, error_format);
+                    }
+                    report_summary(op, &e.error)
+                }
+                _ => report_error(&e.error, error_format),
             }
             e.exit_code
         }
     }
 }
+
+/// Print a one-line summary of an op's failure to stderr.
+fn report_summary(op: &str, error: &huak::Error) {
+    let diagnostic = error.diagnostic();
+    let message = diagnostic
+        .message
+        .lines()
+        .next()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| error.to_string());
+    eprintln!(
+        "huak {op}: {} error {} (exit {}): {message}",
+        diagnostic.category, diagnostic.code, diagnostic.exit_code
+    );
+}
+
+/// Report panics with human-panic's crash dump and message in release builds.
+///
+/// This mirrors `human_panic::setup_panic!` without naming `std::panic::PanicInfo`,
+/// which is deprecated on current toolchains.
+fn setup_panic_hook() {
+    if cfg!(debug_assertions) || std::env::var_os("RUST_BACKTRACE").is_some() {
+        return;
+    }
+
+    let meta = Metadata {
+        version: env!("CARGO_PKG_VERSION").into(),
+        name: env!("CARGO_PKG_NAME").into(),
+        authors: env!("CARGO_PKG_AUTHORS").replace(':', ", ").into(),
+        homepage: env!("CARGO_PKG_HOMEPAGE").into(),
+    };
+    std::panic::set_hook(Box::new(move |info| {
+        let file_path = handle_dump(&meta, info);
+        print_msg(file_path, &meta)
+            .expect("human-panic: printing error message to console failed");
+    }));
+}
+
+/// Print an error's `Diagnostic` to stderr using the requested `ErrorFormat`.
+fn report_error(error: &huak::Error, format: ErrorFormat) {
+    let diagnostic = error.diagnostic();
+
+    match format {
+        ErrorFormat::Json => match serde_json::to_string(&diagnostic) {
+            Ok(it) => eprintln!("{it}"),
+            Err(_) => eprintln!("{error}"),
+        },
+        ErrorFormat::Human => {
+            if diagnostic.message.is_empty() {
+                eprintln!("{error}");
+                return;
+            }
+            eprintln!(
+                "{}{} {}",
+                format!("error[{}]", diagnostic.code).red().bold(),
+                ":".bold(),
+                diagnostic.message
+            );
+            if let Some(it) = diagnostic.context.as_ref() {
+                eprintln!("  {} {it}", "-->".blue().bold());
+            }
+            if let Some(it) = diagnostic.snippet.as_ref() {
+                for line in it.lines() {
+                    eprintln!("  {line}");
+                }
+            }
+            if let Some(it) = diagnostic.hint.as_ref() {
+                eprintln!("  {} {it}", "help:".cyan().bold());
+            }
+        }
+    }
+}
@@ -0,0 +1,510 @@
+use crate::{fs, wheel::WheelFilename, Error, HuakResult};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The directories in huak's cache that hold `CacheEntry`s.
+const CACHE_KINDS: [&str; 4] = ["wheels", "sdists", "tools", "scripts"];
+/// The name of the file `CacheIndex` is written to in huak's cache directory.
+const CACHE_INDEX: &str = "index.json";
+const CACHE_INDEX_VERSION: u32 = 1;
+/// How long to wait between applying the `CachePolicy` automatically.
+const AUTO_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Limits on huak's cache. Entries older than `max_age` are removed first, then the least
+/// recently used entries are removed until the cache fits in `max_size`.
+///
+/// The policy is read from `HUAK_CACHE_MAX_SIZE` and `HUAK_CACHE_MAX_AGE`, and is applied
+/// automatically after huak uses its cache.
+pub struct CachePolicy {
+    pub max_size: Option<CacheSize>,
+    pub max_age: Option<CacheAge>,
+}
+
+impl CachePolicy {
+    /// Read the `CachePolicy` from the environment.
+    pub fn from_env() -> HuakResult<CachePolicy> {
+        let var = |key: &str| {
+            std::env::var(key).ok().filter(|it| !it.trim().is_empty())
+        };
+
+        Ok(CachePolicy {
+            max_size: var("HUAK_CACHE_MAX_SIZE")
+                .map(|it| CacheSize::from_str(&it))
+                .transpose()?,
+            max_age: var("HUAK_CACHE_MAX_AGE")
+                .map(|it| CacheAge::from_str(&it))
+                .transpose()?,
+        })
+    }
+
+    /// Check if the `CachePolicy` doesn't limit the cache.
+    pub fn is_empty(&self) -> bool {
+        self.max_size.is_none() && self.max_age.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+/// A number of bytes such as "500MB" or "2GiB". Decimal units (KB, MB, GB) are powers of
+/// 1000 and binary units (KiB, MiB, GiB) are powers of 1024.
+pub struct CacheSize(pub u64);
+
+impl FromStr for CacheSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|it: char| !(it.is_ascii_digit() || it == '.'))
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let multiplier = match unit.trim().to_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kb" => 1000,
+            "m" | "mb" => 1000u64.pow(2),
+            "g" | "gb" => 1000u64.pow(3),
+            "kib" => 1024,
+            "mib" => 1024u64.pow(2),
+            "gib" => 1024u64.pow(3),
+            _ => 0,
+        };
+        match number.parse::<f64>() {
+            Ok(it) if multiplier > 0 && it >= 0.0 => {
+                Ok(CacheSize((it * multiplier as f64) as u64))
+            }
+            _ => Err(Error::HuakConfigurationError(format!(
+                "{s} is not a size (use a number of bytes or a unit such as 500MB or 2GiB)"
+            ))),
+        }
+    }
+}
+
+impl Display for CacheSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+        let mut size = self.0 as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < units.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        match unit {
+            0 => write!(f, "{} B", self.0),
+            _ => write!(f, "{size:.1} {}", units[unit]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// A length of time such as "30d" or "12h", in seconds (s), minutes (m), hours (h),
+/// days (d), or weeks (w).
+pub struct CacheAge(pub Duration);
+
+impl FromStr for CacheAge {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|it: char| !it.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let multiplier = match unit.trim() {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 60 * 60 * 24,
+            "w" => 60 * 60 * 24 * 7,
+            _ => 0,
+        };
+        match number.parse::<u64>() {
+            Ok(it) if multiplier > 0 => {
+                Ok(CacheAge(Duration::from_secs(it * multiplier)))
+            }
+            _ => Err(Error::HuakConfigurationError(format!(
+                "{s} is not an age (use a number with a unit such as 30d or 12h)"
+            ))),
+        }
+    }
+}
+
+impl Display for CacheAge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.0.as_secs();
+        let units = [
+            (60 * 60 * 24 * 7, "w"),
+            (60 * 60 * 24, "d"),
+            (60 * 60, "h"),
+            (60, "m"),
+        ];
+        match units.iter().find(|(it, _)| secs >= *it && secs % it == 0) {
+            Some((it, unit)) => write!(f, "{}{unit}", secs / it),
+            None => write!(f, "{secs}s"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+/// The `CacheIndex` records how huak's cache is used so that it can be pruned and
+/// reported on. Entries are keyed by their path relative to the cache directory.
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "last-pruned": 1697040000,
+///   "entries": {
+///     "wheels/click-8.1.7-py3-none-any.whl": { "hits": 3, "misses": 1, "last-used": 1697040000 }
+///   }
+/// }
+/// ```
+struct CacheIndex {
+    version: u32,
+    /// When the `CachePolicy` was last applied, in seconds since the Unix epoch.
+    #[serde(default)]
+    last_pruned: Option<u64>,
+    #[serde(default)]
+    entries: BTreeMap<String, EntryUsage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+struct EntryUsage {
+    /// How many times the entry was reused.
+    hits: u64,
+    /// How many times the entry had to be downloaded or created.
+    misses: u64,
+    /// When the entry was last used, in seconds since the Unix epoch.
+    last_used: u64,
+}
+
+impl CacheIndex {
+    /// Read the `CacheIndex` from the cache directory. A missing or unreadable index is
+    /// treated as empty since it only holds statistics.
+    fn read(cache_dir: &Path) -> CacheIndex {
+        std::fs::read_to_string(cache_dir.join(CACHE_INDEX))
+            .ok()
+            .and_then(|it| serde_json::from_str::<CacheIndex>(&it).ok())
+            .filter(|it| it.version == CACHE_INDEX_VERSION)
+            .unwrap_or(CacheIndex {
+                version: CACHE_INDEX_VERSION,
+                ..Default::default()
+            })
+    }
+
+    /// Write the `CacheIndex` to the cache directory. It's written to a temporary file
+    /// first so that concurrent huak processes never read a partial index.
+    fn write(&self, cache_dir: &Path) -> HuakResult<()> {
+        std::fs::create_dir_all(cache_dir)?;
+        let path = cache_dir.join(CACHE_INDEX);
+        let temp =
+            cache_dir.join(format!(".{CACHE_INDEX}.{}", std::process::id()));
+        std::fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(temp, path)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A file or directory in huak's cache, such as a downloaded wheel or a tool's
+/// environment.
+pub struct CacheEntry {
+    /// The path to the entry.
+    pub path: PathBuf,
+    /// The kind of entry ("wheels", "sdists", "tools", or "scripts").
+    pub kind: String,
+    /// The name of the package the entry was cached for.
+    pub package: String,
+    /// The entry's size on disk in bytes.
+    pub size: u64,
+    /// How many times the entry was reused.
+    pub hits: u64,
+    /// How many times the entry had to be downloaded or created.
+    pub misses: u64,
+    /// When the entry was last used, in seconds since the Unix epoch. The entry's
+    /// modification time is used if its use wasn't recorded.
+    pub last_used: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// What was removed by pruning huak's cache.
+pub struct PruneSummary {
+    pub entries: usize,
+    pub size: CacheSize,
+}
+
+/// Get the entries in huak's cache, most recently used first.
+pub fn cache_entries() -> HuakResult<Vec<CacheEntry>> {
+    Ok(entries_in(&fs::cache_dir()?))
+}
+
+fn entries_in(cache_dir: &Path) -> Vec<CacheEntry> {
+    let index = CacheIndex::read(cache_dir);
+    let mut entries = Vec::new();
+    for kind in CACHE_KINDS {
+        let Ok(paths) = std::fs::read_dir(cache_dir.join(kind)) else {
+            continue;
+        };
+        for path in paths.flatten().map(|it| it.path()) {
+            let Some(name) = path.file_name().and_then(|it| it.to_str()) else {
+                continue;
+            };
+            // Skip partial downloads and staging directories.
+            if name.starts_with('.') {
+                continue;
+            }
+            let usage = index
+                .entries
+                .get(&format!("{kind}/{name}"))
+                .copied()
+                .unwrap_or_default();
+            let last_used = match usage.last_used {
+                0 => modified_secs(&path),
+                it => it,
+            };
+            entries.push(CacheEntry {
+                package: entry_package(kind, name),
+                kind: kind.to_string(),
+                size: disk_usage(&path),
+                hits: usage.hits,
+                misses: usage.misses,
+                last_used,
+                path,
+            });
+        }
+    }
+    entries.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+
+    entries
+}
+
+/// Record the use of paths in huak's cache. Each path is a hit if it was reused or a miss
+/// if it had to be downloaded or created. The `CachePolicy` is applied afterwards when
+/// one is configured, keeping the paths that were just used.
+pub fn record_cache_use<'a>(
+    uses: impl IntoIterator<Item = (&'a Path, bool)>,
+) -> HuakResult<()> {
+    let cache_dir = fs::cache_dir()?;
+    let mut index = CacheIndex::read(&cache_dir);
+    let now = now_secs();
+    let mut keep = Vec::new();
+    for (path, hit) in uses {
+        let Some(key) = entry_key(&cache_dir, path) else {
+            continue;
+        };
+        let usage = index.entries.entry(key.clone()).or_default();
+        if hit {
+            usage.hits += 1;
+        } else {
+            usage.misses += 1;
+        }
+        usage.last_used = now;
+        keep.push(key);
+    }
+
+    let policy = CachePolicy::from_env()?;
+    let due = index.last_pruned.map_or(true, |it| {
+        now.saturating_sub(it) >= AUTO_PRUNE_INTERVAL.as_secs()
+    });
+    if policy.is_empty() || !due {
+        return index.write(&cache_dir);
+    }
+    index.last_pruned = Some(now);
+    index.write(&cache_dir)?;
+    prune_dir(&cache_dir, &policy, &keep)?;
+
+    Ok(())
+}
+
+/// Remove entries from huak's cache according to a `CachePolicy`. Entries whose keys are
+/// in `keep` aren't removed.
+pub fn prune_cache_entries(
+    policy: &CachePolicy,
+    keep: &[String],
+) -> HuakResult<PruneSummary> {
+    prune_dir(&fs::cache_dir()?, policy, keep)
+}
+
+fn prune_dir(
+    cache_dir: &Path,
+    policy: &CachePolicy,
+    keep: &[String],
+) -> HuakResult<PruneSummary> {
+    let entries = entries_in(cache_dir);
+    let now = now_secs();
+    let mut total = entries.iter().map(|it| it.size).sum::<u64>();
+    let mut removed = Vec::new();
+
+    // Entries are sorted most recently used first, so the least recently used entries
+    // are removed first when the cache is too large.
+    for entry in entries.iter().rev() {
+        let key = entry_key(cache_dir, &entry.path).unwrap_or_default();
+        if keep.contains(&key) {
+            continue;
+        }
+        let expired = policy.max_age.map_or(false, |it| {
+            now.saturating_sub(entry.last_used) > it.0.as_secs()
+        });
+        let too_large = policy.max_size.map_or(false, |it| total > it.0);
+        if !(expired || too_large) {
+            continue;
+        }
+        if entry.path.is_dir() {
+            fs::remove_dir_all(&entry.path)?;
+        } else {
+            fs::remove_file(&entry.path)?;
+        }
+        total = total.saturating_sub(entry.size);
+        removed.push((key, entry.size));
+    }
+
+    if !removed.is_empty() {
+        let mut index = CacheIndex::read(cache_dir);
+        for (key, _) in removed.iter() {
+            index.entries.remove(key);
+        }
+        index.write(cache_dir)?;
+    }
+
+    Ok(PruneSummary {
+        entries: removed.len(),
+        size: CacheSize(removed.iter().map(|(_, size)| size).sum()),
+    })
+}
+
+/// Get the `CacheIndex` key for a path in the cache directory.
+fn entry_key(cache_dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(cache_dir).ok()?;
+    let mut components = relative.components();
+    let kind = components.next()?.as_os_str().to_str()?;
+    let name = components.next()?.as_os_str().to_str()?;
+
+    Some(format!("{kind}/{name}"))
+}
+
+/// Get the name of the package a cache entry was cached for from its file name. Tool and
+/// script environments are named after their packages or scripts followed by a hash.
+fn entry_package(kind: &str, name: &str) -> String {
+    match kind {
+        "wheels" => WheelFilename::from_str(name)
+            .map(|it| it.name)
+            .unwrap_or_else(|_| name.to_string()),
+        _ => {
+            let stem = [".tar.gz", ".zip", ".tar.bz2"]
+                .iter()
+                .find_map(|it| name.strip_suffix(it))
+                .unwrap_or(name);
+            stem.rsplit_once('-').map_or(stem, |(it, _)| it).to_string()
+        }
+    }
+}
+
+/// Get the size of a file or directory on disk without following symlinks.
+fn disk_usage(path: &Path) -> u64 {
+    let mut size = 0;
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(path) = stack.pop() {
+        let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() && !fs::is_link(&metadata) {
+            if let Ok(it) = std::fs::read_dir(&path) {
+                stack.extend(it.flatten().map(|it| it.path()));
+            }
+        } else {
+            size += metadata.len();
+        }
+    }
+
+    size
+}
+
+fn modified_secs(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|it| it.modified())
+        .ok()
+        .and_then(|it| it.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |it| it.as_secs())
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |it| it.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_cache_size() {
+        assert_eq!(CacheSize::from_str("500MB").unwrap().0, 500_000_000);
+        assert_eq!(CacheSize::from_str("2GiB").unwrap().0, 2 * 1024u64.pow(3));
+        assert_eq!(CacheSize::from_str("1.5k").unwrap().0, 1500);
+        assert_eq!(CacheSize::from_str("42").unwrap().0, 42);
+        assert!(CacheSize::from_str("lots").is_err());
+        assert_eq!(CacheSize(1536).to_string(), "1.5 KiB");
+    }
+
+    #[test]
+    fn test_cache_age() {
+        assert_eq!(
+            CacheAge::from_str("30d").unwrap().0,
+            Duration::from_secs(30 * 24 * 60 * 60)
+        );
+        assert!(CacheAge::from_str("30").is_err());
+        assert_eq!(CacheAge::from_str("2w").unwrap().to_string(), "2w");
+        assert_eq!(CacheAge::from_str("90m").unwrap().to_string(), "90m");
+    }
+
+    #[test]
+    fn test_entry_package() {
+        assert_eq!(
+            entry_package("wheels", "click-8.1.7-py3-none-any.whl"),
+            "click"
+        );
+        assert_eq!(entry_package("sdists", "numpy-1.26.0.tar.gz"), "numpy");
+        assert_eq!(entry_package("tools", "ruff-0123456789abcdef"), "ruff");
+    }
+
+    #[test]
+    fn test_prune_dir() {
+        let dir = tempdir().unwrap();
+        let cache_dir = dir.path();
+        let wheels = cache_dir.join("wheels");
+        std::fs::create_dir_all(&wheels).unwrap();
+        for name in ["a-1.0-py3-none-any.whl", "b-1.0-py3-none-any.whl"] {
+            std::fs::write(wheels.join(name), vec![0; 100]).unwrap();
+        }
+        let mut index = CacheIndex::read(cache_dir);
+        index.entries.insert(
+            "wheels/a-1.0-py3-none-any.whl".to_string(),
+            EntryUsage {
+                hits: 1,
+                misses: 1,
+                last_used: 1,
+            },
+        );
+        index.write(cache_dir).unwrap();
+        let policy = CachePolicy {
+            max_size: Some(CacheSize(150)),
+            max_age: None,
+        };
+
+        let summary = prune_dir(cache_dir, &policy, &[]).unwrap();
+        let entries = entries_in(cache_dir);
+
+        assert_eq!(summary.entries, 1);
+        assert_eq!(summary.size, CacheSize(100));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].package, "b");
+        assert!(CacheIndex::read(cache_dir).entries.is_empty());
+    }
+}
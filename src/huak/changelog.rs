@@ -0,0 +1,552 @@
+use crate::{metadata::Metadata, Error, HuakResult};
+use pep440_rs::Version;
+use regex::Regex;
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The introduction written to a new CHANGELOG.md.
+const CHANGELOG_HEADER: &str = r#"# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+"#;
+
+/// A commit message following the Conventional Commits specification, such as
+/// "feat(cli): add a flag".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConventionalCommit {
+    /// The type of change, such as "feat" or "fix".
+    pub kind: String,
+    pub scope: Option<String>,
+    /// If the commit is marked with "!" or a "BREAKING CHANGE" footer.
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl ConventionalCommit {
+    /// Parse a commit message. Messages that don't follow the specification return
+    /// `None`.
+    pub fn parse(message: &str) -> Option<ConventionalCommit> {
+        let re = Regex::new(r"^(\w+)(?:\(([^)]*)\))?(!)?: (.+)$")
+            .expect("conventional commit regex should be valid");
+        let captures = re.captures(message.lines().next()?.trim())?;
+        let breaking = captures.get(3).is_some()
+            || message.lines().skip(1).any(|it| {
+                it.starts_with("BREAKING CHANGE:")
+                    || it.starts_with("BREAKING-CHANGE:")
+            });
+
+        Some(ConventionalCommit {
+            kind: captures[1].to_lowercase(),
+            scope: captures.get(2).map(|it| it.as_str().to_string()),
+            breaking,
+            description: captures[4].trim().to_string(),
+        })
+    }
+
+    /// Get the version bump the commit calls for, if any.
+    pub fn bump(&self) -> Option<Bump> {
+        if self.breaking {
+            Some(Bump::Major)
+        } else if self.kind == "feat" {
+            Some(Bump::Minor)
+        } else if ["fix", "perf"].contains(&self.kind.as_str()) {
+            Some(Bump::Patch)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// A semantic version bump.
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// Apply the bump to a version. Pre-release, post-release, and local segments are
+    /// dropped. Until 1.0.0 a major bump only increments the minor version.
+    pub fn apply(&self, version: &Version) -> HuakResult<Version> {
+        let mut release = version.release.clone();
+        release.resize(3, 0);
+        let [major, minor, patch] = [release[0], release[1], release[2]];
+        let next = match self {
+            Bump::Major if major > 0 => [major + 1, 0, 0],
+            Bump::Major | Bump::Minor => [major, minor + 1, 0],
+            Bump::Patch => [major, minor, patch + 1],
+        };
+
+        Version::from_str(&format!("{}.{}.{}", next[0], next[1], next[2]))
+            .map_err(Error::InvalidVersionString)
+    }
+}
+
+impl FromStr for Bump {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "major" => Ok(Bump::Major),
+            "minor" => Ok(Bump::Minor),
+            "patch" => Ok(Bump::Patch),
+            _ => Err(Error::HuakConfigurationError(format!(
+                "{s} is not a version bump (use major, minor, or patch)"
+            ))),
+        }
+    }
+}
+
+impl Display for Bump {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Bump::Major => "major",
+            Bump::Minor => "minor",
+            Bump::Patch => "patch",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Get the largest version bump called for by commit messages.
+pub fn suggested_bump(messages: &[String]) -> Option<Bump> {
+    messages
+        .iter()
+        .filter_map(|it| ConventionalCommit::parse(it)?.bump())
+        .max()
+}
+
+/// Settings for changelog sections from the `[tool.huak.changelog]` table. Groups are
+/// listed in the order they're configured.
+///
+/// ```toml
+/// [tool.huak.changelog]
+/// breaking = "Changed"
+/// pr-url = "https://github.com/owner/project/pull/{number}"
+///
+/// [tool.huak.changelog.groups]
+/// Added = ["feat"]
+/// Fixed = ["fix"]
+/// ```
+pub struct ChangelogConfig {
+    /// Group titles and the commit types listed under them.
+    pub groups: Vec<(String, Vec<String>)>,
+    /// The group breaking changes are listed under.
+    pub breaking: String,
+    /// A URL for pull request references like "#123", with a `{number}` placeholder.
+    pub pr_url: Option<String>,
+}
+
+impl Default for ChangelogConfig {
+    fn default() -> Self {
+        let group = |title: &str, kinds: &[&str]| {
+            (
+                title.to_string(),
+                kinds.iter().map(|it| it.to_string()).collect(),
+            )
+        };
+        ChangelogConfig {
+            groups: vec![
+                group("Added", &["feat"]),
+                group("Changed", &["perf", "refactor"]),
+                group("Fixed", &["fix"]),
+            ],
+            breaking: "Changed".to_string(),
+            pr_url: None,
+        }
+    }
+}
+
+impl ChangelogConfig {
+    pub fn from_metadata(metadata: &Metadata) -> HuakResult<ChangelogConfig> {
+        let mut config = ChangelogConfig::default();
+        let invalid = |key: &str, expected: &str| {
+            Error::HuakConfigurationError(format!(
+                "[tool.huak.changelog] {key} must be {expected}"
+            ))
+        };
+        if let Some(value) = metadata.huak_setting("changelog", "groups") {
+            let table = value
+                .as_table()
+                .ok_or_else(|| invalid("groups", "a table of commit types"))?;
+            config.groups = Vec::new();
+            for (title, kinds) in table {
+                let kinds = kinds
+                    .as_array()
+                    .and_then(|it| {
+                        it.iter()
+                            .map(|kind| kind.as_str().map(str::to_lowercase))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .ok_or_else(|| {
+                        invalid("groups", "a table of commit type lists")
+                    })?;
+                config.groups.push((title.to_string(), kinds));
+            }
+        }
+        if let Some(value) = metadata.huak_setting("changelog", "breaking") {
+            config.breaking = value
+                .as_str()
+                .ok_or_else(|| invalid("breaking", "a group title"))?
+                .to_string();
+        }
+        if let Some(value) = metadata.huak_setting("changelog", "pr-url") {
+            config.pr_url = Some(
+                value
+                    .as_str()
+                    .ok_or_else(|| invalid("pr-url", "a URL"))?
+                    .to_string(),
+            );
+        }
+
+        Ok(config)
+    }
+}
+
+/// Get a release section's heading, such as "[1.0.0] - 2023-06-01".
+pub fn release_heading(version: &str, date: &str) -> String {
+    format!("[{version}] - {date}")
+}
+
+/// The heading of the section for changes that haven't been released yet.
+pub const UNRELEASED_HEADING: &str = "[Unreleased]";
+
+/// Render a Keep a Changelog section from commit messages. Conventional Commits are
+/// listed under the configured groups, breaking changes first, and other commits are
+/// left out. Merge commits of pull requests are listed by the pull request's title.
+pub fn changelog_section(
+    heading: &str,
+    messages: &[String],
+    config: &ChangelogConfig,
+) -> HuakResult<String> {
+    let mut groups = config
+        .groups
+        .iter()
+        .map(|(title, _)| (title.as_str(), Vec::new(), Vec::new()))
+        .collect::<Vec<_>>();
+    if !groups.iter().any(|(title, _, _)| *title == config.breaking) {
+        groups.push((config.breaking.as_str(), Vec::new(), Vec::new()));
+    }
+    let merge = Regex::new(r"^Merge pull request (#\d+)")?;
+    let reference = Regex::new(r"#(\d+)\b")?;
+    for message in messages {
+        let merged = merge.captures(message).map(|it| {
+            (
+                it[1].to_string(),
+                message.lines().skip(1).collect::<Vec<_>>().join("\n"),
+            )
+        });
+        let commit = match merged.as_ref() {
+            Some((_, body)) => ConventionalCommit::parse(body.trim_start()),
+            None => ConventionalCommit::parse(message),
+        };
+        let Some(commit) = commit else {
+            continue;
+        };
+        let mut entry = match commit.scope.as_ref() {
+            Some(scope) => format!("**{scope}:** {}", commit.description),
+            None => commit.description.clone(),
+        };
+        if let Some((number, _)) = merged {
+            if !entry.contains(&number) {
+                entry.push_str(&format!(" ({number})"));
+            }
+        }
+        if let Some(url) = config.pr_url.as_ref() {
+            entry = reference
+                .replace_all(&entry, |it: &regex::Captures| {
+                    format!(
+                        "[#{}]({})",
+                        &it[1],
+                        url.replace("{number}", &it[1])
+                    )
+                })
+                .to_string();
+        }
+        let title = match commit.breaking {
+            true => Some(config.breaking.as_str()),
+            false => config
+                .groups
+                .iter()
+                .find(|(_, kinds)| kinds.contains(&commit.kind))
+                .map(|(title, _)| title.as_str()),
+        };
+        let Some(group) =
+            groups.iter_mut().find(|(it, _, _)| Some(*it) == title)
+        else {
+            continue;
+        };
+        match commit.breaking {
+            true => group.1.push(format!("**BREAKING:** {entry}")),
+            false => group.2.push(entry),
+        }
+    }
+
+    let mut section = format!("## {heading}\n");
+    for (title, breaking, entries) in groups.iter() {
+        if breaking.is_empty() && entries.is_empty() {
+            continue;
+        }
+        section.push_str(&format!("\n### {title}\n\n"));
+        for entry in breaking.iter().chain(entries.iter()) {
+            section.push_str(&format!("- {entry}\n"));
+        }
+    }
+
+    Ok(section)
+}
+
+/// Add a section to a changelog's contents, creating the changelog if it doesn't exist
+/// yet. A section with the same name is replaced, and otherwise the section goes above
+/// the most recent release. Adding a release clears the "Unreleased" section since its
+/// changes are part of the release.
+pub fn update_changelog(contents: Option<&str>, section: &str) -> String {
+    let section = format!("{}\n", section.trim_end());
+    let Some(contents) = contents else {
+        return format!("{CHANGELOG_HEADER}\n{section}");
+    };
+    let name = section_name(&section);
+    let unreleased = section_name(UNRELEASED_HEADING);
+    let is_release = !name.eq_ignore_ascii_case(&unreleased);
+
+    // Split the changelog into its introduction and its "## " sections.
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        if line.starts_with("## ") && offset > start {
+            parts.push(&contents[start..offset]);
+            start = offset;
+        }
+        offset += line.len();
+    }
+    parts.push(&contents[start..]);
+
+    let mut updated = String::new();
+    let mut inserted = false;
+    for part in parts {
+        let part_name = part.starts_with("## ").then(|| section_name(part));
+        match part_name {
+            Some(it) if it == name => {
+                push_section(&mut updated, &section);
+                inserted = true;
+                continue;
+            }
+            Some(it) if is_release && it.eq_ignore_ascii_case(&unreleased) => {
+                push_section(
+                    &mut updated,
+                    &format!("## {UNRELEASED_HEADING}\n"),
+                );
+                continue;
+            }
+            Some(_) if !inserted => {
+                push_section(&mut updated, &section);
+                inserted = true;
+            }
+            _ => (),
+        }
+        updated.push_str(part);
+    }
+    if !inserted {
+        push_section(&mut updated, &section);
+    }
+
+    format!("{}\n", updated.trim_end())
+}
+
+/// Append a section to a changelog, separated from what comes before it by a blank
+/// line.
+fn push_section(contents: &mut String, section: &str) {
+    if !contents.is_empty() && !contents.ends_with("\n\n") {
+        contents.push_str(if contents.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        });
+    }
+    contents.push_str(section);
+    contents.push('\n');
+}
+
+/// Get the name of a changelog section from its heading, such as "1.0.0" for
+/// "## [1.0.0] - 2023-06-01".
+fn section_name(heading: &str) -> String {
+    let heading = heading.lines().next().unwrap_or_default();
+    let heading = heading.trim_start_matches('#').trim();
+    match heading.strip_prefix('[').and_then(|it| it.split_once(']')) {
+        Some((name, _)) => name.to_string(),
+        None => heading.split(" - ").next().unwrap_or(heading).to_string(),
+    }
+}
+
+/// Get today's date (UTC) formatted as YYYY-MM-DD.
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |it| it.as_secs());
+    civil_date(secs / 86400)
+}
+
+/// Format a number of days since the Unix epoch as a date (Howard Hinnant's
+/// `civil_from_days`).
+pub fn civil_date(days: u64) -> String {
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conventional_commit() {
+        let commit =
+            ConventionalCommit::parse("feat(cli)!: add release\n\nbody")
+                .unwrap();
+        assert_eq!(commit.kind, "feat");
+        assert_eq!(commit.scope.as_deref(), Some("cli"));
+        assert!(commit.breaking);
+        assert_eq!(commit.description, "add release");
+        assert!(
+            ConventionalCommit::parse("fix: a\n\nBREAKING CHANGE: b")
+                .unwrap()
+                .breaking
+        );
+        assert!(ConventionalCommit::parse("Merge branch 'main'").is_none());
+
+        let messages =
+            ["docs: a", "fix: b", "feat: c"].map(String::from).to_vec();
+        assert_eq!(suggested_bump(&messages), Some(Bump::Minor));
+        assert_eq!(suggested_bump(&messages[..1]), None);
+    }
+
+    #[test]
+    fn test_bump_apply() {
+        let version = |it| Version::from_str(it).unwrap();
+
+        assert_eq!(
+            Bump::Patch.apply(&version("1.2")).unwrap(),
+            version("1.2.1")
+        );
+        assert_eq!(
+            Bump::Minor.apply(&version("1.2.3rc1")).unwrap(),
+            version("1.3.0")
+        );
+        assert_eq!(
+            Bump::Major.apply(&version("1.2.3")).unwrap(),
+            version("2.0.0")
+        );
+        assert_eq!(
+            Bump::Major.apply(&version("0.2.3")).unwrap(),
+            version("0.3.0")
+        );
+    }
+
+    #[test]
+    fn test_changelog_section() {
+        let messages = [
+            "feat(cli): add release",
+            "fix: b (#12)",
+            "chore: c",
+            "feat!: d",
+            "Merge pull request #34 from owner/branch\n\nfix: e",
+        ]
+        .map(String::from)
+        .to_vec();
+        let mut config = ChangelogConfig::default();
+
+        assert_eq!(
+            changelog_section(
+                &release_heading("0.2.0", "2023-06-01"),
+                &messages,
+                &config
+            )
+            .unwrap(),
+            r#"## [0.2.0] - 2023-06-01
+
+### Added
+
+- **cli:** add release
+
+### Changed
+
+- **BREAKING:** d
+
+### Fixed
+
+- b (#12)
+- e (#34)
+"#
+        );
+
+        config.groups = vec![("Chores".to_string(), vec!["chore".to_string()])];
+        config.breaking = "Breaking".to_string();
+        config.pr_url = Some("https://example.com/pull/{number}".to_string());
+        assert_eq!(
+            changelog_section(UNRELEASED_HEADING, &messages, &config).unwrap(),
+            r#"## [Unreleased]
+
+### Chores
+
+- c
+
+### Breaking
+
+- **BREAKING:** d
+"#
+        );
+        let messages = vec!["fix: b (#12)".to_string()];
+        config.groups = vec![("Fixed".to_string(), vec!["fix".to_string()])];
+        assert!(changelog_section(UNRELEASED_HEADING, &messages, &config)
+            .unwrap()
+            .contains("- b ([#12](https://example.com/pull/12))"));
+    }
+
+    #[test]
+    fn test_update_changelog() {
+        let contents = update_changelog(None, "## [0.1.0] - 2023-01-01\n");
+        assert!(contents.starts_with(CHANGELOG_HEADER));
+        assert!(contents.ends_with("\n\n## [0.1.0] - 2023-01-01\n"));
+
+        let contents = "# Changelog\n\n## [0.1.0] - 2023-01-01\n\n- a\n";
+        let contents =
+            update_changelog(Some(contents), "## [Unreleased]\n\n- b\n");
+        assert_eq!(
+            contents,
+            "# Changelog\n\n## [Unreleased]\n\n- b\n\n## [0.1.0] - 2023-01-01\n\n- a\n"
+        );
+        // Regenerating a section replaces it.
+        let contents =
+            update_changelog(Some(&contents), "## [Unreleased]\n\n- c\n");
+        assert_eq!(
+            contents,
+            "# Changelog\n\n## [Unreleased]\n\n- c\n\n## [0.1.0] - 2023-01-01\n\n- a\n"
+        );
+        // Releasing clears the unreleased section.
+        let contents = update_changelog(
+            Some(&contents),
+            "## [0.2.0] - 2023-06-01\n\n- c\n",
+        );
+        assert_eq!(
+            contents,
+            "# Changelog\n\n## [Unreleased]\n\n## [0.2.0] - 2023-06-01\n\n- c\n\n## [0.1.0] - 2023-01-01\n\n- a\n"
+        );
+        assert_eq!(civil_date(19509), "2023-06-01");
+    }
+}
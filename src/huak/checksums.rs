@@ -0,0 +1,212 @@
+use crate::{
+    fs, lockfile::LockedPackage, package::canonical_package_name,
+    sys::Terminal, HuakResult,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The name of the `ChecksumDatabase` file in huak's configuration directory.
+const CHECKSUM_DB_FILE_NAME: &str = "checksums.json";
+const CHECKSUM_DB_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+/// A `ChecksumDatabase` records the hash of every distribution file huak sees the first
+/// time it's seen. A file that's later served with a different hash for the same version
+/// may have been tampered with, even if a lockfile was regenerated to match it.
+///
+/// The database is per-user and opt-in: set `HUAK_CHECKSUM_DB=1` to keep it in huak's
+/// configuration directory, or set it to the path of a database file to use instead.
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "files": {
+///     "click==8.1.7": { "click-8.1.7-py3-none-any.whl": "sha256:ae74..." }
+///   }
+/// }
+/// ```
+pub struct ChecksumDatabase {
+    version: u32,
+    /// The hashes of the files of each `<name>==<version>`, keyed by file name.
+    #[serde(default)]
+    files: BTreeMap<String, BTreeMap<String, String>>,
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A file served with a different hash than the one first recorded for it.
+pub struct ChecksumMismatch {
+    pub package: String,
+    pub file_name: String,
+    pub recorded: String,
+    pub served: String,
+}
+
+impl ChecksumDatabase {
+    /// Open the `ChecksumDatabase` configured with `HUAK_CHECKSUM_DB`, or `None` if it's
+    /// not enabled.
+    pub fn open() -> HuakResult<Option<ChecksumDatabase>> {
+        let path = match std::env::var("HUAK_CHECKSUM_DB") {
+            Ok(it) if it.is_empty() || it == "0" || it == "false" => {
+                return Ok(None)
+            }
+            Ok(it) if it == "1" || it == "true" => {
+                fs::config_dir()?.join(CHECKSUM_DB_FILE_NAME)
+            }
+            Ok(it) => PathBuf::from(it),
+            Err(_) => return Ok(None),
+        };
+
+        ChecksumDatabase::read(path).map(Some)
+    }
+
+    /// Read a `ChecksumDatabase` from a file. A missing file is an empty database.
+    pub fn read<T: Into<PathBuf>>(path: T) -> HuakResult<ChecksumDatabase> {
+        let path = path.into();
+        let mut db = match std::fs::read_to_string(&path) {
+            Ok(it) => serde_json::from_str::<ChecksumDatabase>(&it)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                ChecksumDatabase {
+                    version: CHECKSUM_DB_VERSION,
+                    ..Default::default()
+                }
+            }
+            Err(e) => return Err(e.into()),
+        };
+        db.path = path;
+
+        Ok(db)
+    }
+
+    /// Check a file's hash against the one first recorded for it, recording the hash if
+    /// the file hasn't been seen before. Hashes made with different algorithms can't be
+    /// compared, so they're not reported.
+    pub fn check(
+        &mut self,
+        name: &str,
+        version: &str,
+        file_name: &str,
+        hash: &str,
+    ) -> HuakResult<Option<ChecksumMismatch>> {
+        let package = format!(
+            "{}=={version}",
+            canonical_package_name(name)?.to_lowercase()
+        );
+        let hash = hash.replacen('=', ":", 1).to_lowercase();
+        let files = self.files.entry(package.clone()).or_default();
+        let Some(recorded) = files.get(file_name) else {
+            files.insert(file_name.to_string(), hash);
+            self.changed = true;
+            return Ok(None);
+        };
+
+        let algorithm = |it: &str| it.split(':').next().map(str::to_string);
+        if algorithm(recorded) != algorithm(&hash) || *recorded == hash {
+            return Ok(None);
+        }
+
+        Ok(Some(ChecksumMismatch {
+            package,
+            file_name: file_name.to_string(),
+            recorded: recorded.clone(),
+            served: hash,
+        }))
+    }
+
+    /// Write the `ChecksumDatabase` if new hashes were recorded. It's written to a
+    /// temporary file first so that concurrent huak processes never read a partial
+    /// database.
+    pub fn write_file(&self) -> HuakResult<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        if let Some(it) = self.path.parent() {
+            std::fs::create_dir_all(it)?;
+        }
+        let temp = self.path.with_file_name(format!(
+            ".{CHECKSUM_DB_FILE_NAME}.{}",
+            std::process::id()
+        ));
+        std::fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(temp, &self.path)?;
+
+        Ok(())
+    }
+}
+
+/// Check the hashes of the wheels pinned for `LockedPackage`s against the
+/// `ChecksumDatabase`, if it's enabled, and warn about any that changed since they were
+/// first seen.
+pub fn check_locked_checksums(
+    packages: &[LockedPackage],
+    terminal: &mut Terminal,
+) -> HuakResult<()> {
+    let Some(mut db) = ChecksumDatabase::open()? else {
+        return Ok(());
+    };
+
+    for pkg in packages {
+        for wheel in pkg.wheels.iter().filter(|it| !it.hash.is_empty()) {
+            let Some(it) = db.check(
+                &pkg.name,
+                &pkg.version,
+                wheel.file_name(),
+                &wheel.hash,
+            )?
+            else {
+                continue;
+            };
+            terminal.print_warning(format!(
+                "{} ({}) is being served with a different hash than when it was first seen \
+                ({} now, {} recorded in {}); its contents may have been tampered with",
+                it.package,
+                it.file_name,
+                it.served,
+                it.recorded,
+                db.path.display()
+            ))?;
+        }
+    }
+
+    db.write_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_checksum_database() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CHECKSUM_DB_FILE_NAME);
+        let file_name = "click-8.1.7-py3-none-any.whl";
+
+        let mut db = ChecksumDatabase::read(&path).unwrap();
+        assert!(db
+            .check("Click", "8.1.7", file_name, "sha256:AA")
+            .unwrap()
+            .is_none());
+        db.write_file().unwrap();
+        let mut db = ChecksumDatabase::read(&path).unwrap();
+
+        assert!(db
+            .check("click", "8.1.7", file_name, "sha256=aa")
+            .unwrap()
+            .is_none());
+        assert!(db
+            .check("click", "8.1.7", file_name, "sha512:bb")
+            .unwrap()
+            .is_none());
+        let mismatch = db
+            .check("click", "8.1.7", file_name, "sha256:bb")
+            .unwrap()
+            .unwrap();
+        assert_eq!(mismatch.package, "click==8.1.7");
+        assert_eq!(mismatch.recorded, "sha256:aa");
+        assert_eq!(mismatch.served, "sha256:bb");
+    }
+}
@@ -0,0 +1,296 @@
+use pep440_rs::{Version, VersionSpecifiers};
+use std::str::FromStr;
+
+/// The most commonly used trove classifiers (https://pypi.org/classifiers/).
+const TROVE_CLASSIFIERS: &str = r#"
+Development Status :: 1 - Planning
+Development Status :: 2 - Pre-Alpha
+Development Status :: 3 - Alpha
+Development Status :: 4 - Beta
+Development Status :: 5 - Production/Stable
+Development Status :: 6 - Mature
+Development Status :: 7 - Inactive
+Environment :: Console
+Environment :: Console :: Curses
+Environment :: GPU
+Environment :: GPU :: NVIDIA CUDA
+Environment :: MacOS X
+Environment :: No Input/Output (Daemon)
+Environment :: Plugins
+Environment :: Web Environment
+Environment :: Win32 (MS Windows)
+Environment :: X11 Applications
+Environment :: X11 Applications :: GTK
+Environment :: X11 Applications :: Qt
+Framework :: AsyncIO
+Framework :: Celery
+Framework :: Dash
+Framework :: Django
+Framework :: Django :: 3.2
+Framework :: Django :: 4.0
+Framework :: Django :: 4.1
+Framework :: Django :: 4.2
+Framework :: FastAPI
+Framework :: Flake8
+Framework :: Flask
+Framework :: Hatch
+Framework :: IPython
+Framework :: Jupyter
+Framework :: Jupyter :: JupyterLab
+Framework :: Matplotlib
+Framework :: Pydantic
+Framework :: Pydantic :: 1
+Framework :: Pydantic :: 2
+Framework :: Pyramid
+Framework :: Pytest
+Framework :: Sphinx
+Framework :: Sphinx :: Extension
+Framework :: Sphinx :: Theme
+Framework :: Trio
+Framework :: Twisted
+Framework :: tox
+Intended Audience :: Customer Service
+Intended Audience :: Developers
+Intended Audience :: Education
+Intended Audience :: End Users/Desktop
+Intended Audience :: Financial and Insurance Industry
+Intended Audience :: Healthcare Industry
+Intended Audience :: Information Technology
+Intended Audience :: Legal Industry
+Intended Audience :: Manufacturing
+Intended Audience :: Other Audience
+Intended Audience :: Religion
+Intended Audience :: Science/Research
+Intended Audience :: System Administrators
+Intended Audience :: Telecommunications Industry
+License :: Freeware
+License :: OSI Approved
+License :: OSI Approved :: Academic Free License (AFL)
+License :: OSI Approved :: Apache Software License
+License :: OSI Approved :: Artistic License
+License :: OSI Approved :: BSD License
+License :: OSI Approved :: Boost Software License 1.0 (BSL-1.0)
+License :: OSI Approved :: Eclipse Public License 2.0 (EPL-2.0)
+License :: OSI Approved :: European Union Public Licence 1.2 (EUPL 1.2)
+License :: OSI Approved :: GNU Affero General Public License v3
+License :: OSI Approved :: GNU Affero General Public License v3 or later (AGPLv3+)
+License :: OSI Approved :: GNU General Public License (GPL)
+License :: OSI Approved :: GNU General Public License v2 (GPLv2)
+License :: OSI Approved :: GNU General Public License v2 or later (GPLv2+)
+License :: OSI Approved :: GNU General Public License v3 (GPLv3)
+License :: OSI Approved :: GNU General Public License v3 or later (GPLv3+)
+License :: OSI Approved :: GNU Lesser General Public License v2 (LGPLv2)
+License :: OSI Approved :: GNU Lesser General Public License v2 or later (LGPLv2+)
+License :: OSI Approved :: GNU Lesser General Public License v3 (LGPLv3)
+License :: OSI Approved :: GNU Lesser General Public License v3 or later (LGPLv3+)
+License :: OSI Approved :: ISC License (ISCL)
+License :: OSI Approved :: MIT License
+License :: OSI Approved :: MIT No Attribution License (MIT-0)
+License :: OSI Approved :: Mozilla Public License 2.0 (MPL 2.0)
+License :: OSI Approved :: Python Software Foundation License
+License :: OSI Approved :: The Unlicense (Unlicense)
+License :: OSI Approved :: zlib/libpng License
+License :: Other/Proprietary License
+License :: Public Domain
+Natural Language :: Chinese (Simplified)
+Natural Language :: English
+Natural Language :: French
+Natural Language :: German
+Natural Language :: Japanese
+Natural Language :: Portuguese (Brazilian)
+Natural Language :: Russian
+Natural Language :: Spanish
+Operating System :: MacOS
+Operating System :: MacOS :: MacOS X
+Operating System :: Microsoft
+Operating System :: Microsoft :: Windows
+Operating System :: Microsoft :: Windows :: Windows 10
+Operating System :: Microsoft :: Windows :: Windows 11
+Operating System :: OS Independent
+Operating System :: POSIX
+Operating System :: POSIX :: BSD
+Operating System :: POSIX :: Linux
+Operating System :: Unix
+Programming Language :: C
+Programming Language :: C++
+Programming Language :: Cython
+Programming Language :: JavaScript
+Programming Language :: Python
+Programming Language :: Python :: 3
+Programming Language :: Python :: 3 :: Only
+Programming Language :: Python :: 3.7
+Programming Language :: Python :: 3.8
+Programming Language :: Python :: 3.9
+Programming Language :: Python :: 3.10
+Programming Language :: Python :: 3.11
+Programming Language :: Python :: 3.12
+Programming Language :: Python :: 3.13
+Programming Language :: Python :: Implementation
+Programming Language :: Python :: Implementation :: CPython
+Programming Language :: Python :: Implementation :: PyPy
+Programming Language :: Rust
+Programming Language :: Unix Shell
+Topic :: Communications
+Topic :: Communications :: Chat
+Topic :: Communications :: Email
+Topic :: Database
+Topic :: Database :: Database Engines/Servers
+Topic :: Database :: Front-Ends
+Topic :: Documentation
+Topic :: Documentation :: Sphinx
+Topic :: Education
+Topic :: Games/Entertainment
+Topic :: Internet
+Topic :: Internet :: File Transfer Protocol (FTP)
+Topic :: Internet :: Proxy Servers
+Topic :: Internet :: WWW/HTTP
+Topic :: Internet :: WWW/HTTP :: Browsers
+Topic :: Internet :: WWW/HTTP :: Dynamic Content
+Topic :: Internet :: WWW/HTTP :: HTTP Servers
+Topic :: Internet :: WWW/HTTP :: Indexing/Search
+Topic :: Internet :: WWW/HTTP :: Session
+Topic :: Internet :: WWW/HTTP :: WSGI
+Topic :: Internet :: WWW/HTTP :: WSGI :: Application
+Topic :: Internet :: WWW/HTTP :: WSGI :: Middleware
+Topic :: Internet :: WWW/HTTP :: WSGI :: Server
+Topic :: Multimedia
+Topic :: Multimedia :: Graphics
+Topic :: Multimedia :: Sound/Audio
+Topic :: Multimedia :: Video
+Topic :: Office/Business
+Topic :: Office/Business :: Financial
+Topic :: Scientific/Engineering
+Topic :: Scientific/Engineering :: Artificial Intelligence
+Topic :: Scientific/Engineering :: Bio-Informatics
+Topic :: Scientific/Engineering :: Image Processing
+Topic :: Scientific/Engineering :: Information Analysis
+Topic :: Scientific/Engineering :: Mathematics
+Topic :: Scientific/Engineering :: Physics
+Topic :: Scientific/Engineering :: Visualization
+Topic :: Security
+Topic :: Security :: Cryptography
+Topic :: Software Development
+Topic :: Software Development :: Build Tools
+Topic :: Software Development :: Code Generators
+Topic :: Software Development :: Compilers
+Topic :: Software Development :: Debuggers
+Topic :: Software Development :: Documentation
+Topic :: Software Development :: Libraries
+Topic :: Software Development :: Libraries :: Application Frameworks
+Topic :: Software Development :: Libraries :: Python Modules
+Topic :: Software Development :: Quality Assurance
+Topic :: Software Development :: Testing
+Topic :: Software Development :: Testing :: Mocking
+Topic :: Software Development :: Testing :: Unit
+Topic :: Software Development :: User Interfaces
+Topic :: Software Development :: Version Control
+Topic :: Software Development :: Version Control :: Git
+Topic :: System
+Topic :: System :: Archiving
+Topic :: System :: Archiving :: Packaging
+Topic :: System :: Distributed Computing
+Topic :: System :: Installation/Setup
+Topic :: System :: Logging
+Topic :: System :: Monitoring
+Topic :: System :: Networking
+Topic :: System :: Shells
+Topic :: System :: Software Distribution
+Topic :: System :: Systems Administration
+Topic :: Terminals
+Topic :: Text Processing
+Topic :: Text Processing :: Linguistic
+Topic :: Text Processing :: Markup
+Topic :: Text Processing :: Markup :: HTML
+Topic :: Text Processing :: Markup :: Markdown
+Topic :: Utilities
+Typing :: Stubs Only
+Typing :: Typed
+"#;
+
+/// Get the trove classifiers huak knows about.
+pub fn trove_classifiers() -> impl Iterator<Item = &'static str> {
+    TROVE_CLASSIFIERS.lines().filter(|it| !it.is_empty())
+}
+
+/// Check if a classifier is one of the trove classifiers huak knows about. Private
+/// classifiers (`Private :: ...`) are always allowed.
+pub fn is_known_classifier(classifier: &str) -> bool {
+    classifier.starts_with("Private ::")
+        || trove_classifiers().any(|it| it == classifier)
+}
+
+/// Search the trove classifiers for every whitespace-separated term of a query,
+/// ignoring case.
+pub fn search_classifiers(query: &str) -> Vec<&'static str> {
+    let terms = query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    trove_classifiers()
+        .filter(|it| {
+            let it = it.to_lowercase();
+            terms.iter().all(|term| it.contains(term))
+        })
+        .collect()
+}
+
+/// Get the Python version classifiers for the Python 3 versions a `requires-python`
+/// allows.
+pub fn python_version_classifiers(
+    requires_python: &VersionSpecifiers,
+) -> Vec<String> {
+    let prefix = "Programming Language :: Python :: 3.";
+    let versions = trove_classifiers()
+        .filter_map(|it| it.strip_prefix(prefix))
+        .filter(|minor| {
+            // Some patch releases of a minor version need to be allowed.
+            (0..=20).any(|patch| {
+                Version::from_str(&format!("3.{minor}.{patch}"))
+                    .map_or(false, |it| requires_python.contains(&it))
+            })
+        })
+        .map(|minor| format!("{prefix}{minor}"))
+        .collect::<Vec<_>>();
+    if versions.is_empty() {
+        return versions;
+    }
+
+    let mut classifiers = vec![
+        "Programming Language :: Python :: 3".to_string(),
+        "Programming Language :: Python :: 3 :: Only".to_string(),
+    ];
+    classifiers.extend(versions);
+    classifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_classifiers() {
+        assert_eq!(
+            search_classifiers("wsgi SERVER"),
+            vec!["Topic :: Internet :: WWW/HTTP :: WSGI :: Server"]
+        );
+        assert!(search_classifiers("django").len() > 1);
+        assert!(is_known_classifier("Typing :: Typed"));
+        assert!(is_known_classifier("Private :: Do Not Upload"));
+        assert!(!is_known_classifier("Typing :: Untyped"));
+    }
+
+    #[test]
+    fn test_python_version_classifiers() {
+        let specifiers = VersionSpecifiers::from_str(">=3.10.2,<3.12").unwrap();
+
+        assert_eq!(
+            python_version_classifiers(&specifiers),
+            vec![
+                "Programming Language :: Python :: 3",
+                "Programming Language :: Python :: 3 :: Only",
+                "Programming Language :: Python :: 3.10",
+                "Programming Language :: Python :: 3.11",
+            ]
+        );
+    }
+}
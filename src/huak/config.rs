@@ -1,6 +1,9 @@
-use std::path::PathBuf;
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
 
-use crate::{sys::Terminal, workspace::Workspace, TerminalOptions};
+use crate::{
+    network::Client, sys::Terminal, workspace::Workspace, HuakResult,
+    NetworkOptions, TerminalOptions,
+};
 
 #[derive(Clone)]
 /// The main `Config` for Huak.
@@ -12,13 +15,14 @@ use crate::{sys::Terminal, workspace::Workspace, TerminalOptions};
 /// ```
 /// use huak::{Config, sys::{TerminalOptions, Verbosity};
 ///
-/// let config = Config {
-///     workspace_root: PathBuf::from("."),
-///     cwd: PathBuf::from("."),
-///     terminal_options: TerminalOptions {
+/// let config = Config::new(
+///     PathBuf::from("."),
+///     PathBuf::from("."),
+///     TerminalOptions {
 ///         verbosity: Verbosity::Normal,
-///     }
-/// };
+///         assume_yes: false,
+///     },
+/// );
 ///
 /// let workspace = config.workspace();
 /// ```
@@ -29,19 +33,90 @@ pub struct Config {
     pub cwd: PathBuf,
     /// `Terminal` options to use.
     pub terminal_options: TerminalOptions,
+    /// `NetworkOptions` to use.
+    pub network_options: NetworkOptions,
+    /// Run tools in the Python environment with only a minimal set of the current
+    /// environment variables.
+    pub isolated_env: bool,
+    /// Wait for other huak processes to release the `Workspace`'s lock instead of failing.
+    pub wait_for_lock: bool,
+    /// Don't page long output (see `Terminal::print_paged`).
+    pub no_pager: bool,
+    /// Leave out the output of failed tools so that only a one-line summary of the command
+    /// is reported, such as in CI logs. Used with `Verbosity::Quiet`.
+    pub quiet_errors: bool,
+    /// A file the full output of the tools huak runs is appended to.
+    pub log_file: Option<PathBuf>,
+    /// The `Workspace` resolved from the `Config` data. It's shared between clones of the
+    /// `Config` so that a single invocation only resolves its `Workspace` state once.
+    workspace: Rc<RefCell<Option<Rc<Workspace>>>>,
 }
 
 impl Config {
-    /// Resolve the current `Workspace` based on the `Config` data.
-    pub fn workspace(&self) -> Workspace {
-        Workspace::new(&self.workspace_root, self)
+    pub fn new(
+        workspace_root: PathBuf,
+        cwd: PathBuf,
+        terminal_options: TerminalOptions,
+    ) -> Config {
+        Config {
+            workspace_root,
+            cwd,
+            terminal_options,
+            network_options: NetworkOptions::default(),
+            isolated_env: false,
+            wait_for_lock: false,
+            no_pager: false,
+            quiet_errors: false,
+            log_file: None,
+            workspace: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Resolve the current `Workspace` based on the `Config` data. The `Workspace` is
+    /// cached until the `Config`'s workspace root or current working directory changes.
+    pub fn workspace(&self) -> Rc<Workspace> {
+        let mut cache = self.workspace.borrow_mut();
+        if let Some(it) = cache.as_ref() {
+            if it.root() == &self.workspace_root && it.cwd() == &self.cwd {
+                return Rc::clone(it);
+            }
+        }
+
+        let workspace = Rc::new(Workspace::new(&self.workspace_root, self));
+        *cache = Some(Rc::clone(&workspace));
+
+        workspace
+    }
+
+    /// Get the `NetworkOptions` to use, with the current project's `[tool.huak.network]`
+    /// settings applied.
+    pub fn network_options(&self) -> HuakResult<NetworkOptions> {
+        let Ok(metadata) = self.workspace().current_local_metadata() else {
+            return Ok(self.network_options.clone());
+        };
+        let mut options =
+            self.network_options.with_metadata(metadata.metadata())?;
+        // The CA bundle is relative to the project's pyproject.toml.
+        if let (Some(it), Some(dir)) =
+            (options.ca_bundle.as_mut(), metadata.path().parent())
+        {
+            *it = dir.join(&it);
+        }
+
+        Ok(options)
+    }
+
+    /// Get a network `Client` based on the `Config` data.
+    pub fn client(&self) -> HuakResult<Client> {
+        Client::new(&self.network_options()?)
     }
 
     /// Get a `Terminal` based on the `Config` data.
     pub fn terminal(&self) -> Terminal {
-        let mut terminal = Terminal::new();
-        let verbosity = *self.terminal_options.verbosity();
-        terminal.set_verbosity(verbosity);
+        let mut terminal = self.terminal_options.terminal();
+        terminal.set_pager(!self.no_pager);
+        terminal.set_quiet_errors(self.quiet_errors);
+        terminal.set_log_file(self.log_file.clone());
 
         terminal
     }
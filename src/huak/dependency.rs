@@ -1,7 +1,7 @@
 use std::{ffi::OsStr, fmt::Display, str::FromStr};
 
-use pep440_rs::VersionSpecifiers;
-use pep508_rs::{Requirement, VersionOrUrl};
+use pep440_rs::{Operator, Version, VersionSpecifiers};
+use pep508_rs::{MarkerEnvironment, Requirement, VersionOrUrl};
 
 use crate::Error;
 
@@ -35,14 +35,47 @@ impl Dependency {
         &self.requirement().name
     }
 
+    /// Check if the `Dependency` applies to an environment by evaluating its PEP 508
+    /// markers (`; python_version < "3.11"`). `Dependency`s without markers always apply.
+    pub fn applies_to(&self, env: &MarkerEnvironment) -> bool {
+        self.0.evaluate_markers(env, Vec::new())
+    }
+
+    /// Check if the `Dependency` has PEP 508 markers.
+    pub fn has_markers(&self) -> bool {
+        self.0.marker.is_some()
+    }
+
     /// Get a reference to the `Dependency`'s `VersionSpecifiers`.
-    #[allow(dead_code)]
     fn version_specifiers(&self) -> Option<&VersionSpecifiers> {
         match self.0.version_or_url.as_ref() {
             Some(VersionOrUrl::VersionSpecifier(it)) => Some(it),
             _ => None,
         }
     }
+
+    /// Check if the `Dependency`'s `VersionSpecifiers` allow a version. A `Dependency`
+    /// without `VersionSpecifiers` allows any version.
+    pub fn allows(&self, version: &Version) -> bool {
+        self.version_specifiers()
+            .map_or(true, |it| it.contains(version))
+    }
+
+    /// Get the version the `Dependency` is pinned to if it's an exact `==` or `===`
+    /// requirement without wildcards.
+    pub fn pinned_version(&self) -> Option<String> {
+        let specifiers = self.version_specifiers()?;
+        match specifiers.iter().collect::<Vec<_>>().as_slice() {
+            [it] if matches!(
+                it.operator(),
+                Operator::Equal | Operator::ExactEqual
+            ) =>
+            {
+                Some(it.version().to_string())
+            }
+            _ => None,
+        }
+    }
 }
 
 impl From<Requirement> for Dependency {
@@ -113,6 +146,40 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pep440_rs::Version;
+
+    fn test_marker_environment() -> MarkerEnvironment {
+        let version = |s: &str| (s.to_string(), Version::from_str(s).unwrap());
+        MarkerEnvironment {
+            implementation_name: "cpython".to_string(),
+            implementation_version: version("3.10.4"),
+            os_name: "posix".to_string(),
+            platform_machine: "x86_64".to_string(),
+            platform_python_implementation: "CPython".to_string(),
+            platform_release: String::new(),
+            platform_system: "Linux".to_string(),
+            platform_version: String::new(),
+            python_full_version: version("3.10.4"),
+            python_version: version("3.10"),
+            sys_platform: "linux".to_string(),
+        }
+    }
+
+    #[test]
+    fn dependency_applies_to() {
+        let env = test_marker_environment();
+        let applies =
+            Dependency::from_str("tomli >=1.1.0; python_version < \"3.11\"")
+                .unwrap();
+        let skipped =
+            Dependency::from_str("pywin32; sys_platform == \"win32\"").unwrap();
+        let plain = Dependency::from_str("click").unwrap();
+
+        assert!(applies.applies_to(&env));
+        assert!(!skipped.applies_to(&env));
+        assert!(plain.applies_to(&env));
+        assert!(!plain.has_markers());
+    }
 
     #[test]
     fn dependency_from_str() {
@@ -125,4 +192,32 @@ mod tests {
             pep440_rs::VersionSpecifiers::from_str("==0.0.0").unwrap()
         );
     }
+
+    #[test]
+    fn dependency_pinned_version() {
+        let pinned =
+            |s: &str| Dependency::from_str(s).unwrap().pinned_version();
+
+        assert_eq!(pinned("click==8.1.3"), Some("8.1.3".to_string()));
+        assert_eq!(pinned("click===8.1.3"), Some("8.1.3".to_string()));
+        assert_eq!(pinned("click==8.1.*"), None);
+        assert_eq!(pinned("click>=8.1.3"), None);
+        assert_eq!(pinned("click==8.1.3,!=8.1.4"), None);
+        assert_eq!(pinned("click"), None);
+    }
+
+    #[test]
+    fn dependency_allows() {
+        let allows = |s: &str, v: &str| {
+            Dependency::from_str(s)
+                .unwrap()
+                .allows(&Version::from_str(v).unwrap())
+        };
+
+        assert!(allows("ruff==0.1.6", "0.1.6"));
+        assert!(!allows("ruff==0.1.6", "0.1.7"));
+        assert!(allows("black>=23,<24", "23.11.0"));
+        assert!(!allows("black>=23,<24", "24.1.0"));
+        assert!(allows("mypy", "1.7.1"));
+    }
 }
@@ -0,0 +1,422 @@
+use crate::{wheel::hex, Error, HuakResult};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use pep440_rs::Version;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+const DIST_INFO_EXTENSION: &str = "dist-info";
+const EGG_INFO_EXTENSION: &str = "egg-info";
+
+/// The `DistInfo` is the installation metadata of a distribution found in a
+/// site-packages directory.
+///
+/// Distributions installed from wheels have a `name-version.dist-info` directory
+/// containing a METADATA file and a RECORD of installed files. Legacy installs use an
+/// `.egg-info` directory with a PKG-INFO file instead.
+///
+/// See https://packaging.python.org/en/latest/specifications/recording-installed-packages/
+///
+/// ```
+/// use huak::DistInfo;
+///
+/// let dist_info = DistInfo::new(".venv/lib/python3.11/site-packages/click-8.1.3.dist-info");
+/// ```
+pub struct DistInfo {
+    /// The path to the `DistInfo` directory.
+    path: PathBuf,
+    /// The distribution's name as written in its metadata.
+    name: String,
+    /// The distribution's PEP 440 `Version`.
+    version: Version,
+    /// The distribution's PEP 508 requirements (its Requires-Dist metadata).
+    requires_dist: Vec<String>,
+    /// The extras the distribution provides (its Provides-Extra metadata).
+    provides_extra: Vec<String>,
+    /// The distribution's one-line summary.
+    summary: Option<String>,
+    /// The distribution's license.
+    license: Option<String>,
+    /// The Python versions the distribution supports (its Requires-Python metadata).
+    requires_python: Option<String>,
+}
+
+impl DistInfo {
+    /// Initialize a `DistInfo` from the path to its directory.
+    pub fn new<T: AsRef<Path>>(path: T) -> HuakResult<DistInfo> {
+        let path = path.as_ref();
+        let metadata_path = match path.extension().and_then(OsStr::to_str) {
+            Some(DIST_INFO_EXTENSION) => path.join("METADATA"),
+            Some(EGG_INFO_EXTENSION) => path.join("PKG-INFO"),
+            _ => {
+                return Err(Error::InternalError(format!(
+                    "{} is not a distribution metadata directory",
+                    path.display()
+                )))
+            }
+        };
+        let contents = std::fs::read_to_string(metadata_path)?;
+        let headers = parse_metadata_headers(&contents);
+        let header = |key: &str| {
+            headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.to_string())
+                .ok_or(Error::InternalError(format!(
+                    "{} is missing {key} metadata",
+                    path.display()
+                )))
+        };
+        let name = header("Name")?;
+        let version = Version::from_str(&header("Version")?)
+            .map_err(Error::InvalidVersionString)?;
+        let all_headers = |key: &str| {
+            headers
+                .iter()
+                .filter(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.to_string())
+                .collect::<Vec<_>>()
+        };
+        let optional_header = |key: &str| {
+            header(key)
+                .ok()
+                .filter(|it| !it.is_empty() && it != "UNKNOWN")
+        };
+
+        Ok(DistInfo {
+            path: path.to_path_buf(),
+            name,
+            version,
+            requires_dist: all_headers("Requires-Dist"),
+            provides_extra: all_headers("Provides-Extra"),
+            summary: optional_header("Summary"),
+            license: optional_header("License"),
+            requires_python: optional_header("Requires-Python"),
+        })
+    }
+
+    /// Get a reference to the distribution's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get a reference to the distribution's `Version`.
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Get a reference to the distribution's PEP 508 requirements.
+    pub fn requires_dist(&self) -> &[String] {
+        &self.requires_dist
+    }
+
+    /// Get a reference to the extras the distribution provides.
+    pub fn provides_extra(&self) -> &[String] {
+        &self.provides_extra
+    }
+
+    /// Get the distribution's one-line summary.
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    /// Get the distribution's license.
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+
+    /// Get the Python versions the distribution supports.
+    pub fn requires_python(&self) -> Option<&str> {
+        self.requires_python.as_deref()
+    }
+
+    /// Get a reference to the path to the `DistInfo` directory.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Check if the distribution was explicitly requested (it has a REQUESTED marker)
+    /// rather than installed as a dependency of another distribution.
+    pub fn requested(&self) -> bool {
+        self.path.join("REQUESTED").exists()
+    }
+
+    /// Mark or unmark the distribution as explicitly requested.
+    ///
+    /// The REQUESTED marker isn't added to the RECORD, so changing whether a
+    /// distribution is a direct dependency doesn't change its content hash.
+    pub fn set_requested(&self, requested: bool) -> HuakResult<()> {
+        let path = self.path.join("REQUESTED");
+        match (requested, path.exists()) {
+            (true, false) => std::fs::write(path, "")?,
+            (false, true) => std::fs::remove_file(path)?,
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Read the RECORD of files installed with the distribution.
+    pub fn record(&self) -> HuakResult<Vec<RecordEntry>> {
+        let path = self.path.join("RECORD");
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = std::fs::read_to_string(path)?;
+
+        Ok(contents.lines().filter_map(RecordEntry::parse).collect())
+    }
+
+    /// Get a SHA256 hash of the distribution's installed files from their RECORD hashes.
+    /// Files installed outside site-packages (such as scripts) and the `DistInfo`
+    /// directory differ between environments, so they're skipped to give identical
+    /// distributions identical hashes.
+    pub fn content_hash(&self) -> HuakResult<String> {
+        let dir_name = self
+            .path
+            .file_name()
+            .map(|it| it.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut lines = self
+            .record()?
+            .into_iter()
+            .filter(|it| {
+                !it.path.starts_with("..")
+                    && !it.path.starts_with(&format!("{dir_name}/"))
+            })
+            .filter_map(|it| Some(format!("{},{}", it.path, it.hash?)))
+            .collect::<Vec<_>>();
+        lines.sort();
+
+        Ok(format!(
+            "sha256:{}",
+            hex(&Sha256::digest(lines.join("\n").as_bytes()))
+        ))
+    }
+
+    /// Remove the distribution's installed files listed in its RECORD, along with the
+    /// `DistInfo` directory.
+    pub fn remove(&self) -> HuakResult<()> {
+        let site_packages = self.path.parent().ok_or(Error::InternalError(
+            "failed to establish parent directory".to_string(),
+        ))?;
+        for entry in self.record()? {
+            let path = site_packages.join(&entry.path);
+            if path.is_file() {
+                std::fs::remove_file(&path)?;
+                // Clean up any package directories left empty.
+                if let Some(parent) = path.parent() {
+                    if parent != site_packages
+                        && parent.starts_with(site_packages)
+                        && std::fs::read_dir(parent)?.next().is_none()
+                    {
+                        std::fs::remove_dir(parent)?;
+                    }
+                }
+            }
+        }
+        if self.path.exists() {
+            std::fs::remove_dir_all(&self.path)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A row of a distribution's RECORD file.
+pub struct RecordEntry {
+    /// The installed file's path relative to site-packages.
+    pub path: String,
+    /// The file's hash as `<algorithm>=<urlsafe-base64-digest>`.
+    pub hash: Option<String>,
+    /// The file's size in bytes.
+    pub size: Option<u64>,
+}
+
+impl RecordEntry {
+    /// Parse a `RecordEntry` from a line of CSV.
+    fn parse(line: &str) -> Option<RecordEntry> {
+        let mut fields = parse_csv_line(line).into_iter();
+        let path = fields.next().filter(|it| !it.is_empty())?;
+        let hash = fields.next().filter(|it| !it.is_empty());
+        let size = fields.next().and_then(|it| it.parse::<u64>().ok());
+
+        Some(RecordEntry { path, hash, size })
+    }
+
+    /// Check if a file's contents match the `RecordEntry`'s hash. Entries without a
+    /// hash match any contents.
+    pub fn matches(&self, contents: &[u8]) -> HuakResult<bool> {
+        let Some(hash) = self.hash.as_ref() else {
+            return Ok(true);
+        };
+        let (algorithm, expected) = hash.split_once('=').ok_or(
+            Error::InternalError(format!("{hash} is not a valid hash")),
+        )?;
+        let digest = match algorithm {
+            "sha256" => URL_SAFE_NO_PAD.encode(Sha256::digest(contents)),
+            "sha384" => URL_SAFE_NO_PAD.encode(Sha384::digest(contents)),
+            "sha512" => URL_SAFE_NO_PAD.encode(Sha512::digest(contents)),
+            _ => {
+                return Err(Error::Unimplemented(format!(
+                    "{algorithm} hashes are not supported"
+                )))
+            }
+        };
+
+        Ok(digest == expected.trim_end_matches('='))
+    }
+}
+
+/// Scan a site-packages directory for installed distributions.
+///
+/// Directories that can't be parsed are skipped.
+pub fn scan_site_packages<T: AsRef<Path>>(
+    path: T,
+) -> HuakResult<Vec<DistInfo>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut dists = std::fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|it| it.path()))
+        .filter(|it| {
+            it.is_dir()
+                && matches!(
+                    it.extension().and_then(OsStr::to_str),
+                    Some(DIST_INFO_EXTENSION) | Some(EGG_INFO_EXTENSION)
+                )
+        })
+        .filter_map(|it| DistInfo::new(it).ok())
+        .collect::<Vec<_>>();
+    dists.sort_by_key(|it| it.name.to_lowercase());
+
+    Ok(dists)
+}
+
+/// Parse the RFC 822-style headers from a METADATA (or PKG-INFO) file. Parsing stops
+/// at the first blank line since everything after it is the description body.
+pub fn parse_metadata_headers(contents: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            break;
+        }
+        // Continuation lines are indented.
+        if line.starts_with(char::is_whitespace) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    headers
+}
+
+/// A minimal CSV line parser supporting double-quoted fields.
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_scan_site_packages() {
+        let dir = tempdir().unwrap();
+        let dist_info = dir.path().join("click-8.1.3.dist-info");
+        std::fs::create_dir(&dist_info).unwrap();
+        std::fs::write(
+            dist_info.join("METADATA"),
+            "Metadata-Version: 2.1\nName: click\nVersion: 8.1.3\nSummary: Composable command line interface toolkit\nRequires-Dist: colorama; platform_system == \"Windows\"\n\nDescription body\nName: not-a-header\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dist_info.join("RECORD"),
+            "click/__init__.py,sha256=abc,3247\n\"click/a,b.py\",sha256=def,10\nclick-8.1.3.dist-info/RECORD,,\n",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("click")).unwrap();
+        let content_hash = hex(&Sha256::digest(
+            "click/__init__.py,sha256=abc\nclick/a,b.py,sha256=def".as_bytes(),
+        ));
+
+        let dists = scan_site_packages(dir.path()).unwrap();
+
+        assert_eq!(dists.len(), 1);
+        assert_eq!(dists[0].name(), "click");
+        assert_eq!(dists[0].version(), &Version::from_str("8.1.3").unwrap());
+        assert_eq!(
+            dists[0].requires_dist(),
+            ["colorama; platform_system == \"Windows\""]
+        );
+        assert_eq!(
+            dists[0].record().unwrap(),
+            vec![
+                RecordEntry {
+                    path: "click/__init__.py".to_string(),
+                    hash: Some("sha256=abc".to_string()),
+                    size: Some(3247)
+                },
+                RecordEntry {
+                    path: "click/a,b.py".to_string(),
+                    hash: Some("sha256=def".to_string()),
+                    size: Some(10)
+                },
+                RecordEntry {
+                    path: "click-8.1.3.dist-info/RECORD".to_string(),
+                    hash: None,
+                    size: None
+                },
+            ]
+        );
+        assert!(RecordEntry::parse(
+            "a.py,sha256=LCa0a2j_xo_5m0U8HTBBNBNCLXBkg7-g-YpeiGJm564,3"
+        )
+        .unwrap()
+        .matches(b"foo")
+        .unwrap());
+        assert_eq!(
+            dists[0].content_hash().unwrap(),
+            format!("sha256:{content_hash}")
+        );
+        assert!(!dists[0].requested());
+        dists[0].set_requested(true).unwrap();
+        assert!(dists[0].requested());
+        assert_eq!(
+            dists[0].content_hash().unwrap(),
+            format!("sha256:{content_hash}")
+        );
+        dists[0].set_requested(false).unwrap();
+        assert!(!dists[0].requested());
+    }
+}
@@ -1,31 +1,83 @@
 use crate::sys;
-use std::{io, path::PathBuf};
+use serde::Serialize;
+use std::{
+    fmt::Display,
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use thiserror::Error as ThisError;
 
 pub type HuakResult<T> = Result<T, Error>;
 
+// huak's exit codes. Every `Error` exits with one of them (see `Error::exit_code`), so
+// scripts and CI can tell why a command failed without parsing its output. Commands
+// that run a command of your own, like `huak run`, exit with that command's code instead.
+// Panics exit with 101.
+
+/// The command succeeded.
+pub const EXIT_SUCCESS: u8 = 0;
+/// A tool huak ran reported a failure, such as lint errors, failing tests, or a failed
+/// check.
+pub const EXIT_TOOL_FAILURE: u8 = 1;
+/// huak was used incorrectly, such as an invalid argument or setting, or a confirmation
+/// that couldn't be given.
+pub const EXIT_USAGE: u8 = 2;
+/// The project, its dependencies, its Python environment, or the filesystem kept huak from
+/// finishing.
+pub const EXIT_PROJECT: u8 = 3;
+/// The package index or another network resource couldn't be reached.
+pub const EXIT_NETWORK: u8 = 4;
+/// huak hit an internal error.
+pub const EXIT_INTERNAL: u8 = 5;
+
 #[derive(ThisError, Debug)]
 pub enum Error {
+    #[error("tools needed to build from source are missing: {0}")]
+    BuildToolsMissing(String),
+    #[error("a check failed: {0}")]
+    CheckFailed(String),
     #[error("a problem with argument parsing occurred: {0}")]
     ClapError(#[from] clap::Error),
+    #[error("a command was denied by the run policy: {0}")]
+    CommandDenied(String),
     #[error("a directory already exists: {0}")]
     DirectoryExists(PathBuf),
     #[error("a problem with the environment occurred: {0}")]
     EnvVarError(#[from] std::env::VarError),
+    #[error(
+        "refusing to modify an externally managed python environment: {0}"
+    )]
+    ExternallyManagedEnvironment(String),
+    #[error("a file is in use by {1}")]
+    FileInUse(PathBuf, String),
+    #[error("a file's hash didn't match its expected hash: {0}")]
+    HashMismatch(PathBuf),
+    #[error("confirmation is required to {0}")]
+    ConfirmationRequired(String),
     #[error("a problem with git occurred: {0}")]
     GitError(#[from] git2::Error),
     #[error("a problem occurred with the glob package: {0}")]
     GlobError(#[from] glob::GlobError),
     #[error("a problem occurred with a glob pattern: {0}")]
     GlobPatternError(#[from] glob::PatternError),
+    #[error("the installer couldn't reach the package index: {0}")]
+    InstallerNetworkError(String),
     #[error("a problem with huak configuration occurred: {0}")]
     HuakConfigurationError(String),
     #[error("a problem with huak's internals occurred: {0}")]
     InternalError(String),
+    #[error("the project name can't be used: {0}")]
+    InvalidProjectName(String),
     #[error("a version number could not be parsed: {0}")]
     InvalidVersionString(String),
+    #[error("a metadata file is invalid: {1}")]
+    InvalidMetadataFile(SourceLocation, String),
     #[error("a problem occurred with json deserialization: {0}")]
     JSONSerdeError(#[from] serde_json::Error),
+    #[error("an interactive terminal is required to answer: {0}")]
+    InteractiveTerminalRequired(String),
     #[error("a problem with io occurred: {0}")]
     IOError(#[from] io::Error),
     #[error("a problem occurred with PEP440 parsing: {0}")]
@@ -33,19 +85,29 @@ pub enum Error {
     #[error("a problem occurred with PEP508 parsing: {0}")]
     PEP508Error(#[from] pep508_rs::Pep508Error),
     #[error("a metadata file already exists")]
-    MetadataFileFound,
+    MetadataFileFound(PathBuf),
+    #[error("an operation timed out after {0}")]
+    OperationTimedOut(String),
+    #[error("the lockfile is out of date: {0}")]
+    OutdatedLockfile(String),
     #[error("a metadata file could not be found")]
-    MetadataFileNotFound,
+    MetadataFileNotFound(PathBuf),
+    #[error("a problem with the network occurred: {0}")]
+    NetworkError(#[from] reqwest::Error),
+    #[error("a package could not be found: {0}")]
+    PackageNotFound(String),
     #[error("a package version could not be found")]
     PackageVersionNotFound,
     #[error("a project already exists")]
-    ProjectFound,
+    ProjectFound(PathBuf),
     #[error("a python interpreter could not be found")]
     PythonNotFound,
     #[error("a python environment could not be found")]
     PythonEnvironmentNotFound,
     #[error("a regex error occurred: {0}")]
     RegexError(#[from] regex::Error),
+    #[error("the installer couldn't resolve the requirements: {0}")]
+    ResolutionError(String),
     #[error("a subprocess exited with {0}")]
     SubprocessFailure(sys::SubprocessError),
     #[error("a problem with toml deserialization occurred: {0}")]
@@ -56,8 +118,536 @@ pub enum Error {
     TOMLEditDeserializationError(#[from] toml_edit::de::Error),
     #[error("a problem with toml serialization occurred {0}")]
     TOMLEditSerializationError(#[from] toml_edit::ser::Error),
+    #[error("trusted publishing failed: {0}")]
+    TrustedPublishingFailed(String),
+    #[error("the package index rejected an upload: {0}")]
+    UploadFailed(String),
     #[error("a feature is unimplemented: {0}")]
     Unimplemented(String),
     #[error("a problem with utf-8 parsing occurred: {0}")]
     Utf8Error(#[from] std::str::Utf8Error),
+    #[error("a compatible wheel could not be found for {0}")]
+    WheelNotFound(String),
+    #[error("a yanked release was selected: {0}")]
+    YankedRelease(String),
+    #[error("another huak process is running (pid {0})")]
+    WorkspaceLocked(u32),
+    #[error("a problem with a zip archive occurred: {0}")]
+    ZipError(#[from] zip::result::ZipError),
+}
+
+impl Error {
+    /// Get the numeric code for the `Error`. Codes are stable and grouped by `ErrorCategory`.
+    pub fn code(&self) -> u16 {
+        match self {
+            // Usage
+            Error::ClapError(_) => 1,
+            Error::HuakConfigurationError(_) => 2,
+            Error::Unimplemented(_) => 3,
+            // Filesystem
+            Error::DirectoryExists(_) => 4,
+            Error::IOError(_) => 5,
+            Error::GlobError(_) => 6,
+            Error::GlobPatternError(_) => 7,
+            Error::EnvVarError(_) => 8,
+            Error::GitError(_) => 9,
+            // Project
+            Error::ProjectFound(_) => 10,
+            Error::MetadataFileNotFound(_) => 11,
+            Error::MetadataFileFound(_) => 12,
+            Error::PackageVersionNotFound => 13,
+            Error::TOMLDeserializationError(_) => 14,
+            Error::TOMLSerializationError(_) => 15,
+            Error::TOMLEditDeserializationError(_) => 16,
+            Error::TOMLEditSerializationError(_) => 17,
+            Error::JSONSerdeError(_) => 18,
+            // Dependency
+            Error::InvalidVersionString(_) => 19,
+            Error::PEP440Error(_) => 20,
+            Error::PEP508Error(_) => 21,
+            // Python
+            Error::PythonNotFound => 22,
+            Error::PythonEnvironmentNotFound => 23,
+            Error::SubprocessFailure(_) => 24,
+            // Internal
+            Error::InternalError(_) => 25,
+            Error::RegexError(_) => 26,
+            Error::Utf8Error(_) => 27,
+            // Network
+            Error::NetworkError(_) => 28,
+            // Dependency
+            Error::WheelNotFound(_) => 29,
+            Error::HashMismatch(_) => 30,
+            // Filesystem
+            Error::ZipError(_) => 31,
+            // Usage
+            Error::ConfirmationRequired(_) => 32,
+            Error::InteractiveTerminalRequired(_) => 33,
+            // Project
+            Error::CheckFailed(_) => 34,
+            // Dependency
+            Error::YankedRelease(_) => 35,
+            // Project
+            Error::OutdatedLockfile(_) => 36,
+            // Python
+            Error::ExternallyManagedEnvironment(_) => 37,
+            // Usage
+            Error::CommandDenied(_) => 38,
+            // Project
+            Error::WorkspaceLocked(_) => 39,
+            // Network
+            Error::InstallerNetworkError(_) => 40,
+            Error::OperationTimedOut(_) => 41,
+            // Dependency
+            Error::ResolutionError(_) => 42,
+            Error::PackageNotFound(_) => 43,
+            // Project
+            Error::InvalidProjectName(_) => 44,
+            // Dependency
+            Error::BuildToolsMissing(_) => 45,
+            // Project
+            Error::InvalidMetadataFile(..) => 46,
+            // Filesystem
+            Error::FileInUse(..) => 47,
+            // Network
+            Error::TrustedPublishingFailed(_) => 48,
+            Error::UploadFailed(_) => 49,
+        }
+    }
+
+    /// Get the `ErrorCategory` of the `Error`.
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::ClapError(_)
+            | Error::CommandDenied(_)
+            | Error::ConfirmationRequired(_)
+            | Error::HuakConfigurationError(_)
+            | Error::InteractiveTerminalRequired(_)
+            | Error::Unimplemented(_) => ErrorCategory::Usage,
+            Error::DirectoryExists(_)
+            | Error::EnvVarError(_)
+            | Error::FileInUse(..)
+            | Error::GitError(_)
+            | Error::GlobError(_)
+            | Error::GlobPatternError(_)
+            | Error::IOError(_)
+            | Error::ZipError(_) => ErrorCategory::Filesystem,
+            Error::CheckFailed(_)
+            | Error::InvalidMetadataFile(..)
+            | Error::InvalidProjectName(_)
+            | Error::JSONSerdeError(_)
+            | Error::MetadataFileFound(_)
+            | Error::MetadataFileNotFound(_)
+            | Error::OutdatedLockfile(_)
+            | Error::PackageVersionNotFound
+            | Error::ProjectFound(_)
+            | Error::TOMLDeserializationError(_)
+            | Error::TOMLEditDeserializationError(_)
+            | Error::TOMLEditSerializationError(_)
+            | Error::TOMLSerializationError(_)
+            | Error::WorkspaceLocked(_) => ErrorCategory::Project,
+            Error::BuildToolsMissing(_)
+            | Error::HashMismatch(_)
+            | Error::InvalidVersionString(_)
+            | Error::PackageNotFound(_)
+            | Error::PEP440Error(_)
+            | Error::PEP508Error(_)
+            | Error::ResolutionError(_)
+            | Error::WheelNotFound(_)
+            | Error::YankedRelease(_) => ErrorCategory::Dependency,
+            Error::ExternallyManagedEnvironment(_)
+            | Error::PythonEnvironmentNotFound
+            | Error::PythonNotFound
+            | Error::SubprocessFailure(_) => ErrorCategory::Python,
+            Error::InstallerNetworkError(_)
+            | Error::NetworkError(_)
+            | Error::OperationTimedOut(_)
+            | Error::TrustedPublishingFailed(_)
+            | Error::UploadFailed(_) => ErrorCategory::Network,
+            Error::InternalError(_)
+            | Error::RegexError(_)
+            | Error::Utf8Error(_) => ErrorCategory::Internal,
+        }
+    }
+
+    /// Get the code huak exits with because of the `Error` (see `EXIT_SUCCESS` and the other
+    /// exit codes).
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::SubprocessFailure(_) | Error::CheckFailed(_) => {
+                EXIT_TOOL_FAILURE
+            }
+            _ => match self.category() {
+                ErrorCategory::Usage => EXIT_USAGE,
+                ErrorCategory::Network => EXIT_NETWORK,
+                ErrorCategory::Internal => EXIT_INTERNAL,
+                ErrorCategory::Filesystem
+                | ErrorCategory::Project
+                | ErrorCategory::Dependency
+                | ErrorCategory::Python => EXIT_PROJECT,
+            },
+        }
+    }
+
+    /// Get the offending path, dependency string, or exit status associated with the `Error`.
+    pub fn context(&self) -> Option<String> {
+        match self {
+            Error::DirectoryExists(path)
+            | Error::FileInUse(path, _)
+            | Error::HashMismatch(path)
+            | Error::MetadataFileFound(path)
+            | Error::ProjectFound(path) => Some(format!("{}", path.display())),
+            Error::MetadataFileNotFound(path) => {
+                Some(format!("searched from {}", path.display()))
+            }
+            Error::PEP508Error(e) => Some(e.input.clone()),
+            Error::InvalidMetadataFile(location, _) => Some(format!(
+                "{}:{}:{}",
+                location.path.display(),
+                location.line,
+                location.column
+            )),
+            Error::NetworkError(e) => e.url().map(|it| it.to_string()),
+            Error::SubprocessFailure(e) => {
+                e.code().map(|c| format!("exit code {c}"))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get a "did you mean" suggestion or a next step for resolving the `Error`.
+    pub fn hint(&self) -> Option<String> {
+        let hint = match self {
+            Error::ConfirmationRequired(_) => {
+                "pass `--yes` to confirm in non-interactive contexts".to_string()
+            }
+            Error::InteractiveTerminalRequired(_) => {
+                "pass the options as arguments instead of running interactively".to_string()
+            }
+            Error::CommandDenied(_) => {
+                "update the `allowed` and `denied` patterns in [tool.huak.run] to permit it".to_string()
+            }
+            Error::DirectoryExists(_) => {
+                "use `huak init` to initialize an existing directory".to_string()
+            }
+            Error::MetadataFileFound(_) => {
+                "the project is already initialized; use `huak add` to manage its dependencies".to_string()
+            }
+            Error::MetadataFileNotFound(_) => {
+                "run `huak init` to create a pyproject.toml or `huak new <path>` to create a new project".to_string()
+            }
+            Error::PackageVersionNotFound => {
+                "add a `version` to the [project] table of your pyproject.toml"
+                    .to_string()
+            }
+            Error::ProjectFound(_) => {
+                "choose a different path for the new project".to_string()
+            }
+            Error::InvalidProjectName(_) => {
+                "choose a different path, or run `huak new --interactive` to name the project".to_string()
+            }
+            Error::PythonNotFound => {
+                "install Python and make sure it's available on your PATH (see `huak python list`)".to_string()
+            }
+            Error::PythonEnvironmentNotFound => {
+                "create one with `huak python use <version>` or `huak install`"
+                    .to_string()
+            }
+            Error::ExternallyManagedEnvironment(_) => {
+                "create a project environment with `huak python use <version>` or unset VIRTUAL_ENV".to_string()
+            }
+            Error::PEP508Error(e) => {
+                return suggest_requirement(&e.input).map(|it| {
+                    format!("did you mean `{it}`?")
+                })
+            }
+            Error::InvalidVersionString(_) | Error::PEP440Error(_) => {
+                "versions must follow PEP 440, for example `1.0.0`".to_string()
+            }
+            Error::TOMLDeserializationError(_)
+            | Error::TOMLEditDeserializationError(_) => {
+                "check your pyproject.toml for invalid TOML".to_string()
+            }
+            Error::InvalidMetadataFile(..) => {
+                "fix the highlighted part of the file and try again".to_string()
+            }
+            Error::HashMismatch(_) => {
+                "the file may be corrupted or tampered with; remove it from huak's cache and try again".to_string()
+            }
+            Error::OutdatedLockfile(_) => {
+                "run `huak lock` to update it".to_string()
+            }
+            Error::YankedRelease(_) => {
+                "pick another version or pass `--allow-yanked` to use it anyway"
+                    .to_string()
+            }
+            Error::WheelNotFound(_) => {
+                "the lockfile may have been created for another platform or Python version".to_string()
+            }
+            Error::BuildToolsMissing(_) => {
+                "install the missing tools, or pin a version with a wheel for your platform".to_string()
+            }
+            Error::NetworkError(e) if e.status().is_some() => return None,
+            Error::NetworkError(_) | Error::InstallerNetworkError(_) => {
+                "check your network connection and proxy settings (HTTPS_PROXY, NO_PROXY)".to_string()
+            }
+            Error::OperationTimedOut(_) => {
+                "raise `operation-timeout` in [tool.huak.network] for slow networks".to_string()
+            }
+            Error::PackageNotFound(_) => {
+                "it isn't installed in the environment or published to the package index; check the name's spelling".to_string()
+            }
+            Error::ResolutionError(_) => {
+                "check that the packages exist and that their version constraints are compatible".to_string()
+            }
+            Error::WorkspaceLocked(_) => {
+                "wait for it to finish or pass `--wait` to wait for it".to_string()
+            }
+            Error::TrustedPublishingFailed(_) => {
+                "check the trusted publisher configured for the project on the package index matches this workflow, or pass credentials to twine".to_string()
+            }
+            Error::UploadFailed(_) => {
+                "check the project's metadata with `huak check` and your credentials for the package index".to_string()
+            }
+            Error::FileInUse(..) => {
+                "stop the process, such as a Python process running from the project's environment, and try again".to_string()
+            }
+            Error::InternalError(_) | Error::RegexError(_) => {
+                "this is likely a bug; please report it at https://github.com/cnpryer/huak/issues".to_string()
+            }
+            _ => return None,
+        };
+
+        Some(hint)
+    }
+
+    /// Get a `Diagnostic` describing the `Error`.
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            code: format!("HUAK{:04}", self.code()),
+            category: self.category(),
+            exit_code: self.exit_code(),
+            message: self.to_string(),
+            context: self.context(),
+            snippet: self.snippet(),
+            hint: self.hint(),
+        }
+    }
+
+    /// Get the offending part of a file, marked under the line it's on.
+    pub fn snippet(&self) -> Option<String> {
+        match self {
+            Error::InvalidMetadataFile(location, _) => Some(location.snippet()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+/// A location in a file that caused an `Error`.
+pub struct SourceLocation {
+    /// The path to the file.
+    pub path: PathBuf,
+    /// The 1-based line number.
+    pub line: usize,
+    /// The 1-based column number, counted in characters.
+    pub column: usize,
+    /// The line's text.
+    pub text: String,
+    /// The number of characters marked, starting at the column.
+    pub width: usize,
+}
+
+impl SourceLocation {
+    /// Locate a byte `span` of a file's `contents`. Spans covering several lines are
+    /// marked to the end of their first line.
+    pub fn from_span(
+        path: &Path,
+        contents: &str,
+        span: Range<usize>,
+    ) -> SourceLocation {
+        let start = span.start.min(contents.len());
+        let end = span.end.clamp(start, contents.len());
+        let line_start = contents[..start].rfind('\n').map_or(0, |it| it + 1);
+        let line_end = contents[start..]
+            .find('\n')
+            .map_or(contents.len(), |it| start + it);
+        let text = contents[line_start..line_end].trim_end_matches('\r');
+
+        SourceLocation {
+            path: path.to_path_buf(),
+            line: contents[..start].matches('\n').count() + 1,
+            column: contents[line_start..start].chars().count() + 1,
+            text: text.to_string(),
+            width: contents[start..end.min(line_end)].chars().count().max(1),
+        }
+    }
+
+    /// Render the line with a marker under the located characters.
+    ///
+    /// ```text
+    ///   |
+    /// 7 | version = "0.0.1
+    ///   |                 ^
+    /// ```
+    pub fn snippet(&self) -> String {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        format!(
+            "{gutter} |\n{number} | {}\n{gutter} | {}{}",
+            self.text,
+            " ".repeat(self.column - 1),
+            "^".repeat(self.width)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+/// Categories used to group huak's error codes.
+pub enum ErrorCategory {
+    Usage,
+    Filesystem,
+    Project,
+    Dependency,
+    Python,
+    Network,
+    Internal,
+}
+
+impl Display for ErrorCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ErrorCategory::Usage => "usage",
+            ErrorCategory::Filesystem => "filesystem",
+            ErrorCategory::Project => "project",
+            ErrorCategory::Dependency => "dependency",
+            ErrorCategory::Python => "python",
+            ErrorCategory::Network => "network",
+            ErrorCategory::Internal => "internal",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A `Diagnostic` is a renderable report of an `Error`.
+///
+/// ```
+/// use huak::Error;
+///
+/// let diagnostic = Error::PythonNotFound.diagnostic();
+///
+/// assert_eq!(diagnostic.code, "HUAK0022");
+/// ```
+pub struct Diagnostic {
+    /// The error code (HUAKNNNN).
+    pub code: String,
+    /// The `ErrorCategory` the code belongs to.
+    pub category: ErrorCategory,
+    /// The code huak exits with.
+    pub exit_code: u8,
+    /// The error message.
+    pub message: String,
+    /// The offending path, dependency string, etc.
+    pub context: Option<String>,
+    /// The offending part of a file, marked under the line it's on.
+    pub snippet: Option<String>,
+    /// A suggestion or next step.
+    pub hint: Option<String>,
+}
+
+/// Attempt to correct a requirement string that failed PEP 508 parsing.
+///
+/// Common mistakes include using a single `=` or `@` instead of `==`.
+fn suggest_requirement(input: &str) -> Option<String> {
+    let input = input.trim();
+    for (pat, fix) in [("@", "=="), ("=", "=="), (" ", "==")] {
+        if let Some((name, version)) = input.split_once(pat) {
+            let (name, version) = (name.trim(), version.trim());
+            if !name.is_empty()
+                && !version.is_empty()
+                && !version.starts_with('=')
+                && pep508_rs::Requirement::from_str(&format!(
+                    "{name}{fix}{version}"
+                ))
+                .is_ok()
+            {
+                return Some(format!("{name}{fix}{version}"));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        let err = Error::MetadataFileFound(PathBuf::from("pyproject.toml"));
+        let diagnostic = err.diagnostic();
+
+        assert_eq!(diagnostic.code, "HUAK0012");
+        assert_eq!(diagnostic.category, ErrorCategory::Project);
+        assert_eq!(diagnostic.exit_code, EXIT_PROJECT);
+        assert_eq!(diagnostic.context.unwrap(), "pyproject.toml");
+        assert!(diagnostic.hint.is_some());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(
+            Error::CheckFailed("lint".to_string()).exit_code(),
+            EXIT_TOOL_FAILURE
+        );
+        assert_eq!(
+            Error::HuakConfigurationError(String::new()).exit_code(),
+            EXIT_USAGE
+        );
+        assert_eq!(
+            Error::InstallerNetworkError(String::new()).exit_code(),
+            EXIT_NETWORK
+        );
+        assert_eq!(
+            Error::InternalError(String::new()).exit_code(),
+            EXIT_INTERNAL
+        );
+        assert_eq!(Error::PythonNotFound.exit_code(), EXIT_PROJECT);
+    }
+
+    #[test]
+    fn test_requirement_suggestion() {
+        let err = Error::from(
+            pep508_rs::Requirement::from_str("click=8.1.3").unwrap_err(),
+        );
+
+        assert_eq!(err.hint().unwrap(), "did you mean `click==8.1.3`?");
+        assert_eq!(err.context().unwrap(), "click=8.1.3");
+    }
+
+    #[test]
+    fn test_source_location() {
+        let contents = "[project]\nname = \"mock-project\"\nversion = 1\n";
+        let start = contents.find('1').unwrap();
+
+        let location = SourceLocation::from_span(
+            Path::new("pyproject.toml"),
+            contents,
+            start..start + 1,
+        );
+        let err = Error::InvalidMetadataFile(
+            location.clone(),
+            "invalid type: integer `1`, expected a string".to_string(),
+        );
+
+        assert_eq!((location.line, location.column), (3, 11));
+        assert_eq!(err.context().unwrap(), "pyproject.toml:3:11");
+        assert_eq!(
+            err.snippet().unwrap(),
+            "  |\n3 | version = 1\n  |           ^"
+        );
+    }
 }
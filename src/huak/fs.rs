@@ -1,9 +1,71 @@
-use crate::error::{Error, HuakResult};
+use crate::{
+    error::{Error, HuakResult},
+    sys::{self, CiProvider},
+};
 use std::{
-    fs,
-    path::{Path, PathBuf},
+    fs, io,
+    path::{Component, Path, PathBuf},
+    time::Duration,
 };
 
+/// How many times an operation on a file that's in use by another process is attempted.
+const IN_USE_ATTEMPTS: u32 = 10;
+/// How long to wait before retrying an operation on a file that's in use.
+const IN_USE_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Get the path to huak's cache directory. `HUAK_CACHE_DIR` takes precedence over the
+/// platform's default cache location.
+pub fn cache_dir() -> HuakResult<PathBuf> {
+    let var = |key: &str| {
+        std::env::var_os(key)
+            .filter(|it| !it.is_empty())
+            .map(PathBuf::from)
+    };
+    if let Some(it) = var("HUAK_CACHE_DIR") {
+        return Ok(it);
+    }
+    if let Some(it) = CiProvider::detect().and_then(|ci| ci.cache_dir()) {
+        return Ok(it);
+    }
+
+    #[cfg(windows)]
+    let dir = var("LOCALAPPDATA").map(|it| it.join("huak").join("cache"));
+    #[cfg(not(windows))]
+    let dir = var("XDG_CACHE_HOME")
+        .or_else(|| var("HOME").map(|it| it.join(".cache")))
+        .map(|it| it.join("huak"));
+
+    dir.ok_or(Error::HuakConfigurationError(
+        "a cache directory could not be resolved; set HUAK_CACHE_DIR"
+            .to_string(),
+    ))
+}
+
+/// Get the path to huak's configuration directory. `HUAK_CONFIG_DIR` takes precedence
+/// over the platform's default configuration location.
+pub fn config_dir() -> HuakResult<PathBuf> {
+    let var = |key: &str| {
+        std::env::var_os(key)
+            .filter(|it| !it.is_empty())
+            .map(PathBuf::from)
+    };
+    if let Some(it) = var("HUAK_CONFIG_DIR") {
+        return Ok(it);
+    }
+
+    #[cfg(windows)]
+    let dir = var("APPDATA").map(|it| it.join("huak"));
+    #[cfg(not(windows))]
+    let dir = var("XDG_CONFIG_HOME")
+        .or_else(|| var("HOME").map(|it| it.join(".config")))
+        .map(|it| it.join("huak"));
+
+    dir.ok_or(Error::HuakConfigurationError(
+        "a configuration directory could not be resolved; set HUAK_CONFIG_DIR"
+            .to_string(),
+    ))
+}
+
 #[allow(dead_code)]
 /// Copy contents from one directory into a new directory at a provided `to` full path.
 /// If the `to` directory doesn't exist this function creates it.
@@ -100,7 +162,7 @@ pub fn last_path_component<T: AsRef<Path>>(path: T) -> HuakResult<String> {
     let path = path.as_ref();
     let path = path
         .components()
-        .last()
+        .next_back()
         .ok_or(Error::InternalError(format!(
             "failed to parse path {}",
             path.display()
@@ -115,6 +177,301 @@ pub fn last_path_component<T: AsRef<Path>>(path: T) -> HuakResult<String> {
     Ok(path)
 }
 
+/// Resolve a path canonically so that a project reached through a symlink (or a junction
+/// on Windows) is treated the same as the directory it points to. The `\\?\` prefix
+/// Windows adds is removed from local paths since not every tool understands it. Paths
+/// that can't be resolved, like ones that don't exist yet, are returned as-is.
+pub fn canonical_path<T: AsRef<Path>>(path: T) -> PathBuf {
+    let path = path.as_ref();
+    let Ok(canonical) = path.canonicalize() else {
+        return path.to_path_buf();
+    };
+    match canonical.to_str().and_then(|it| it.strip_prefix(r"\\?\")) {
+        Some(it) if !it.starts_with("UNC") => PathBuf::from(it),
+        _ => canonical,
+    }
+}
+
+/// Check if a path is reached from `root` through a symlink or junction, either because
+/// it is one or because one of its parent directories under `root` is. Walks of the
+/// workspace skip these paths so that they don't leave it or enter another project's
+/// files. Paths outside of `root` are treated as linked.
+pub fn is_linked(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return true;
+    };
+    let mut current = root.to_path_buf();
+    for it in relative.components() {
+        current.push(it);
+        let linked =
+            fs::symlink_metadata(&current).map_or(false, |it| is_link(&it));
+        if linked {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Check if file metadata read without following links is for a symlink or, on Windows,
+/// a junction.
+pub fn is_link(metadata: &fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        // FILE_ATTRIBUTE_REPARSE_POINT, which junctions are.
+        if metadata.file_attributes() & 0x400 != 0 {
+            return true;
+        }
+    }
+
+    metadata.file_type().is_symlink()
+}
+
+/// Get a path that isn't limited to `MAX_PATH` on Windows by giving absolute paths the
+/// `\\?\` prefix. Paths that are relative, already prefixed, or contain `.` or `..`
+/// components are returned as-is, as are all paths on other platforms.
+pub fn long_path<T: AsRef<Path>>(path: T) -> PathBuf {
+    let path = path.as_ref();
+    if !cfg!(windows)
+        || !path.is_absolute()
+        || path
+            .components()
+            .any(|it| matches!(it, Component::CurDir | Component::ParentDir))
+    {
+        return path.to_path_buf();
+    }
+    let Some(s) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    if s.starts_with(r"\\?\") {
+        return path.to_path_buf();
+    }
+    let s = s.replace('/', r"\");
+    match s.strip_prefix(r"\\") {
+        Some(unc) => PathBuf::from(format!(r"\\?\UNC\{unc}")),
+        None => PathBuf::from(format!(r"\\?\{s}")),
+    }
+}
+
+/// Remove a directory and everything in it. On Windows, long paths are supported and
+/// the removal is retried while files are in use by another process (see
+/// `Error::FileInUse`).
+pub fn remove_dir_all<T: AsRef<Path>>(path: T) -> HuakResult<()> {
+    let path = path.as_ref();
+    retry_in_use(path, || fs::remove_dir_all(long_path(path)))
+}
+
+/// Remove a file. On Windows, long paths are supported and the removal is retried while
+/// the file is in use by another process (see `Error::FileInUse`).
+pub fn remove_file<T: AsRef<Path>>(path: T) -> HuakResult<()> {
+    let path = path.as_ref();
+    retry_in_use(path, || fs::remove_file(long_path(path)))
+}
+
+/// Run an operation on `path`, retrying it while a file is in use by another process.
+/// If the file is still in use after the last attempt, an `Error::FileInUse` naming the
+/// processes running from `path` is returned.
+fn retry_in_use<F>(path: &Path, mut op: F) -> HuakResult<()>
+where
+    F: FnMut() -> io::Result<()>,
+{
+    let mut attempts = 0;
+    loop {
+        let e = match op() {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        if !is_in_use(&e, path) {
+            return Err(Error::IOError(e));
+        }
+        attempts += 1;
+        if attempts < IN_USE_ATTEMPTS {
+            std::thread::sleep(IN_USE_RETRY_DELAY);
+            continue;
+        }
+        let processes = sys::processes_in(path);
+        let holder = if processes.is_empty() {
+            "another process".to_string()
+        } else {
+            processes.join(", ")
+        };
+        return Err(Error::FileInUse(path.to_path_buf(), holder));
+    }
+}
+
+/// Check if an `io::Error` for `path` is a Windows sharing or lock violation. Access
+/// denials are also reported for files that are open while they're being deleted, but
+/// since they're usually real permission errors they only count if a process is found
+/// running from `path`.
+fn is_in_use(e: &io::Error, path: &Path) -> bool {
+    if !cfg!(windows) {
+        return false;
+    }
+    match e.raw_os_error() {
+        // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION.
+        Some(32 | 33) => true,
+        // ERROR_ACCESS_DENIED.
+        Some(5) => !sys::processes_in(path).is_empty(),
+        _ => false,
+    }
+}
+
+/// A directory that's built at a temporary path next to its target and moved into place
+/// once it's complete, so that a failed or interrupted command doesn't leave a
+/// half-created directory at the target. The temporary directory is removed if the
+/// `StagedDir` is dropped before it's committed.
+pub struct StagedDir {
+    path: PathBuf,
+    target: PathBuf,
+    committed: bool,
+}
+
+impl StagedDir {
+    /// Create an empty `StagedDir` for `target`. Staged directories left behind by
+    /// interrupted huak processes are removed.
+    pub fn new<T: AsRef<Path>>(target: T) -> HuakResult<StagedDir> {
+        let target = target.as_ref().to_path_buf();
+        let prefix = sibling_name(&target, "huak-staged-")?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+            for it in fs::read_dir(parent)?.filter_map(|it| it.ok()) {
+                let file_name = it.file_name();
+                let Some(pid) = file_name
+                    .to_str()
+                    .and_then(|it| it.strip_prefix(&prefix))
+                    .and_then(|it| it.parse::<u32>().ok())
+                else {
+                    continue;
+                };
+                if !sys::process_is_running(pid) {
+                    remove_dir_all(it.path()).ok();
+                }
+            }
+        }
+        let path =
+            target.with_file_name(format!("{prefix}{}", std::process::id()));
+        if path.exists() {
+            remove_dir_all(&path)?;
+        }
+        fs::create_dir(&path)?;
+
+        Ok(StagedDir {
+            path,
+            target,
+            committed: false,
+        })
+    }
+
+    /// Get the path the directory is built at.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Move the directory to its target. The target must not exist.
+    pub fn commit(mut self) -> HuakResult<()> {
+        if self.target.exists() {
+            return Err(Error::DirectoryExists(self.target.clone()));
+        }
+        fs::rename(&self.path, &self.target)?;
+        self.committed = true;
+
+        Ok(())
+    }
+}
+
+impl Drop for StagedDir {
+    fn drop(&mut self) {
+        if !self.committed {
+            remove_dir_all(&self.path).ok();
+        }
+    }
+}
+
+/// A directory that's being replaced. The existing directory is moved aside to a backup
+/// until the replacement is committed. If the `DirReplacement` is dropped before then,
+/// whatever was created at the path is removed and the backup is restored. A backup left
+/// behind by an interrupted huak process is restored by `recover_replaced_dir`.
+pub struct DirReplacement {
+    path: PathBuf,
+    backup: PathBuf,
+    committed: bool,
+}
+
+impl DirReplacement {
+    /// Move the directory at `path` aside so that a new one can be created there.
+    pub fn new<T: AsRef<Path>>(path: T) -> HuakResult<DirReplacement> {
+        let path = path.as_ref().to_path_buf();
+        recover_replaced_dir(&path)?;
+        let backup = path.with_file_name(sibling_name(&path, "huak-backup")?);
+        if path.exists() {
+            retry_in_use(&path, || fs::rename(&path, &backup))?;
+        }
+
+        Ok(DirReplacement {
+            path,
+            backup,
+            committed: false,
+        })
+    }
+
+    /// Keep the new directory and remove the backup of the one it replaced.
+    pub fn commit(mut self) -> HuakResult<()> {
+        self.committed = true;
+        remove_backup(&self.backup)
+    }
+}
+
+impl Drop for DirReplacement {
+    fn drop(&mut self) {
+        if !self.committed {
+            if self.path.exists() {
+                remove_dir_all(&self.path).ok();
+            }
+            if self.backup.exists() {
+                fs::rename(&self.backup, &self.path).ok();
+            }
+        }
+    }
+}
+
+/// Restore the directory at `path` if a huak process was interrupted while replacing it
+/// (see `DirReplacement`). Whatever was partially created at the path is removed.
+pub fn recover_replaced_dir<T: AsRef<Path>>(path: T) -> HuakResult<()> {
+    let path = path.as_ref();
+    let backup = path.with_file_name(sibling_name(path, "huak-backup")?);
+    if backup.exists() {
+        if path.exists() {
+            remove_dir_all(path)?;
+        }
+        fs::rename(&backup, path)?;
+    }
+    let trash = path.with_file_name(sibling_name(path, "huak-trash")?);
+    if trash.exists() {
+        remove_dir_all(trash)?;
+    }
+
+    Ok(())
+}
+
+/// Remove a backup, moving it to the trash first so that an interrupted removal isn't
+/// mistaken for a backup that should be restored.
+fn remove_backup(backup: &Path) -> HuakResult<()> {
+    if !backup.exists() {
+        return Ok(());
+    }
+    let trash = backup.with_extension("huak-trash");
+    retry_in_use(backup, || fs::rename(backup, &trash))?;
+    remove_dir_all(trash)?;
+
+    Ok(())
+}
+
+/// Get the name of a hidden sibling of `path` used while it's staged or replaced.
+fn sibling_name(path: &Path, suffix: &str) -> HuakResult<String> {
+    Ok(format!(".{}.{suffix}", last_path_component(path)?))
+}
+
 #[cfg(test)]
 mod tests {
     use tempfile::tempdir;
@@ -144,4 +501,99 @@ mod tests {
 
         assert!(res.unwrap().unwrap().exists());
     }
+
+    #[test]
+    fn test_staged_dir() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("project");
+
+        let staged = StagedDir::new(&target).unwrap();
+        fs::write(staged.path().join("pyproject.toml"), "").unwrap();
+        drop(staged);
+
+        assert!(!target.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let staged = StagedDir::new(&target).unwrap();
+        fs::write(staged.path().join("pyproject.toml"), "").unwrap();
+        staged.commit().unwrap();
+
+        assert!(target.join("pyproject.toml").exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_dir_replacement() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".venv");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("old"), "").unwrap();
+
+        let replacement = DirReplacement::new(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("new"), "").unwrap();
+        drop(replacement);
+
+        assert!(path.join("old").exists());
+        assert!(!path.join("new").exists());
+
+        // An interrupted replacement is recovered.
+        let replacement = DirReplacement::new(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        std::mem::forget(replacement);
+        recover_replaced_dir(&path).unwrap();
+
+        assert!(path.join("old").exists());
+
+        let replacement = DirReplacement::new(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("new"), "").unwrap();
+        replacement.commit().unwrap();
+
+        assert!(path.join("new").exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_long_path() {
+        let path = std::env::temp_dir().join("project");
+
+        if cfg!(windows) {
+            assert!(long_path(&path).to_str().unwrap().starts_with(r"\\?\"));
+        } else {
+            assert_eq!(long_path(&path), path);
+        }
+        assert_eq!(long_path("project"), PathBuf::from("project"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_linked() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("project");
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("linked")).unwrap();
+
+        assert!(!is_linked(&root, &root.join("src").join("app.py")));
+        assert!(is_linked(&root, &root.join("linked")));
+        assert!(is_linked(&root, &root.join("linked").join("app.py")));
+        assert!(is_linked(&root, &outside));
+        assert_eq!(
+            canonical_path(root.join("linked")),
+            outside.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_is_in_use() {
+        let dir = tempdir().unwrap();
+        let access_denied = io::Error::from_raw_os_error(5);
+        let sharing_violation = io::Error::from_raw_os_error(32);
+
+        // Nothing is running from the directory, so access is really denied.
+        assert!(!is_in_use(&access_denied, dir.path()));
+        assert_eq!(is_in_use(&sharing_violation, dir.path()), cfg!(windows));
+    }
 }
@@ -1,6 +1,9 @@
 use crate::{error::HuakResult, Error};
-use git2::Repository;
-use std::path::Path;
+use git2::{Delta, DiffOptions, Oid, Repository, Sort};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 /// From https://github.com/github/gitignore/blob/main/Python.gitignore
 const DEFAULT_PYTHON_GITIGNORE: &str = r#"
@@ -91,6 +94,246 @@ pub fn default_python_gitignore() -> &'static str {
     DEFAULT_PYTHON_GITIGNORE
 }
 
+/// Get the name and email configured for the current user (`user.name` and
+/// `user.email`) in git's global configuration.
+pub fn user_identity() -> (Option<String>, Option<String>) {
+    let Ok(config) = git2::Config::open_default() else {
+        return (None, None);
+    };
+
+    (
+        config.get_string("user.name").ok(),
+        config.get_string("user.email").ok(),
+    )
+}
+
+/// Get the paths to files changed relative to a git reference, including uncommitted and
+/// untracked files. Without a reference, changes are compared against the merge-base of
+/// `HEAD` and the repository's default branch.
+pub fn changed_files<T: AsRef<Path>>(
+    path: T,
+    reference: Option<&str>,
+) -> HuakResult<Vec<PathBuf>> {
+    let repo = Repository::discover(path)?;
+    let base = match reference {
+        Some(it) => repo.revparse_single(it)?.peel_to_commit()?,
+        None => {
+            let head = repo.head()?.peel_to_commit()?.id();
+            repo.find_commit(repo.merge_base(head, default_branch(&repo)?)?)?
+        }
+    };
+    let workdir = repo.workdir().ok_or_else(|| {
+        Error::HuakConfigurationError(
+            "a bare git repository has no changed files".to_string(),
+        )
+    })?;
+    let workdir = workdir.canonicalize()?;
+
+    let mut options = DiffOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    let diff = repo.diff_tree_to_workdir_with_index(
+        Some(&base.tree()?),
+        Some(&mut options),
+    )?;
+
+    Ok(diff
+        .deltas()
+        .filter(|it| it.status() != Delta::Deleted)
+        .filter_map(|it| it.new_file().path().map(|path| workdir.join(path)))
+        .collect())
+}
+
+/// Get the paths to files staged in the git index, excluding staged deletions.
+pub fn staged_files<T: AsRef<Path>>(path: T) -> HuakResult<Vec<PathBuf>> {
+    let repo = Repository::discover(path)?;
+    let workdir = workdir(&repo)?;
+    // Before the first commit everything in the index is staged.
+    let head = repo.head().ok().and_then(|it| it.peel_to_tree().ok());
+    let diff = repo.diff_tree_to_index(head.as_ref(), None, None)?;
+
+    Ok(diff
+        .deltas()
+        .filter(|it| it.status() != Delta::Deleted)
+        .filter_map(|it| it.new_file().path().map(|path| workdir.join(path)))
+        .collect())
+}
+
+/// Get the paths to tracked files with changes that aren't staged in the git index.
+pub fn unstaged_files<T: AsRef<Path>>(path: T) -> HuakResult<Vec<PathBuf>> {
+    let repo = Repository::discover(path)?;
+    let workdir = workdir(&repo)?;
+    let diff = repo.diff_index_to_workdir(None, None)?;
+
+    Ok(diff
+        .deltas()
+        .filter_map(|it| it.new_file().path().map(|path| workdir.join(path)))
+        .collect())
+}
+
+/// Stage files in the git index.
+pub fn stage_paths<T: AsRef<Path>>(
+    path: T,
+    paths: &[PathBuf],
+) -> HuakResult<()> {
+    let repo = Repository::discover(path)?;
+    let workdir = workdir(&repo)?;
+    let mut index = repo.index()?;
+    for it in paths {
+        let it = it.canonicalize()?;
+        index.add_path(it.strip_prefix(&workdir).map_err(|_| {
+            Error::HuakConfigurationError(format!(
+                "{} isn't in the git repository",
+                it.display()
+            ))
+        })?)?;
+    }
+    index.write()?;
+
+    Ok(())
+}
+
+/// Get the canonical path to the working directory of the repository containing a path.
+pub fn workdir_path<T: AsRef<Path>>(path: T) -> HuakResult<PathBuf> {
+    workdir(&Repository::discover(path)?)
+}
+
+/// Get the path to the directory git runs hooks from, honoring `core.hooksPath`.
+pub fn hooks_dir<T: AsRef<Path>>(path: T) -> HuakResult<PathBuf> {
+    let repo = Repository::discover(path)?;
+    match repo.config()?.get_path("core.hooksPath") {
+        Ok(it) if it.is_absolute() => Ok(it),
+        // Relative hook paths are relative to where hooks run: the working directory.
+        Ok(it) => Ok(workdir(&repo)?.join(it)),
+        Err(_) => Ok(repo.path().join("hooks")),
+    }
+}
+
+/// Get the name of the most recent tag reachable from `HEAD`.
+pub fn latest_tag<T: AsRef<Path>>(path: T) -> HuakResult<Option<String>> {
+    let repo = Repository::discover(path)?;
+    let mut tags = HashMap::new();
+    for name in repo.tag_names(None)?.iter().flatten() {
+        if let Ok(commit) = repo
+            .revparse_single(&format!("refs/tags/{name}"))
+            .and_then(|it| it.peel_to_commit())
+        {
+            tags.insert(commit.id(), name.to_string());
+        }
+    }
+    if tags.is_empty() {
+        return Ok(None);
+    }
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    walk.push_head()?;
+    for oid in walk {
+        if let Some(name) = tags.remove(&oid?) {
+            return Ok(Some(name));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Get the messages of the commits reachable from `HEAD` but not from a reference,
+/// newest first. Without a reference every commit's message is returned.
+pub fn commit_messages<T: AsRef<Path>>(
+    path: T,
+    since: Option<&str>,
+) -> HuakResult<Vec<String>> {
+    let repo = Repository::discover(path)?;
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    walk.push_head()?;
+    if let Some(it) = since {
+        walk.hide(repo.revparse_single(it)?.peel_to_commit()?.id())?;
+    }
+
+    let mut messages = Vec::new();
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        messages
+            .push(String::from_utf8_lossy(commit.message_bytes()).to_string());
+    }
+
+    Ok(messages)
+}
+
+/// Commit files to `HEAD` with the repository's configured signature.
+pub fn commit_paths<T: AsRef<Path>>(
+    path: T,
+    paths: &[PathBuf],
+    message: &str,
+) -> HuakResult<()> {
+    stage_paths(&path, paths)?;
+    let repo = Repository::discover(path)?;
+    let mut index = repo.index()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = repo.signature()?;
+    let parent = repo.head()?.peel_to_commit()?;
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &[&parent],
+    )?;
+
+    Ok(())
+}
+
+/// Create an annotated tag pointing at `HEAD`.
+pub fn tag_head<T: AsRef<Path>>(
+    path: T,
+    name: &str,
+    message: &str,
+) -> HuakResult<()> {
+    let repo = Repository::discover(path)?;
+    let head = repo.head()?.peel_to_commit()?;
+    repo.tag(name, head.as_object(), &repo.signature()?, message, false)?;
+
+    Ok(())
+}
+
+/// Get the canonical path to a repository's working directory.
+fn workdir(repo: &Repository) -> HuakResult<PathBuf> {
+    let workdir = repo.workdir().ok_or_else(|| {
+        Error::HuakConfigurationError(
+            "a bare git repository has no working directory".to_string(),
+        )
+    })?;
+
+    Ok(workdir.canonicalize()?)
+}
+
+/// Get the commit the repository's default branch points to. The remote's `HEAD` is
+/// preferred, followed by local and remote "main" and "master" branches.
+fn default_branch(repo: &Repository) -> HuakResult<Oid> {
+    if let Some(oid) = repo
+        .find_reference("refs/remotes/origin/HEAD")
+        .and_then(|it| it.resolve())
+        .ok()
+        .and_then(|it| it.target())
+    {
+        return Ok(oid);
+    }
+    for name in ["main", "master", "origin/main", "origin/master"] {
+        if let Ok(commit) = repo
+            .revparse_single(name)
+            .and_then(|it| it.peel_to_commit())
+        {
+            return Ok(commit.id());
+        }
+    }
+
+    Err(Error::HuakConfigurationError(
+        "a default git branch could not be found; pass a reference to compare against"
+            .to_string(),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,4 +345,85 @@ mod tests {
         init(&dir).unwrap();
         assert!(dir.path().join(".git").is_dir());
     }
+
+    #[test]
+    fn test_changed_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let repo = init(&root).unwrap();
+        std::fs::write(root.join("a.py"), "").unwrap();
+        std::fs::write(root.join("b.py"), "").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a.py")).unwrap();
+        index.add_path(Path::new("b.py")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature =
+            git2::Signature::now("huak", "huak@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        std::fs::write(root.join("a.py"), "import os").unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src").join("c.py"), "").unwrap();
+
+        let mut files = changed_files(&root, Some("HEAD")).unwrap();
+        files.sort();
+
+        assert_eq!(
+            files,
+            vec![root.join("a.py"), root.join("src").join("c.py")]
+        );
+        // With no other branches, `HEAD`'s branch is the default branch.
+        assert_eq!(changed_files(&root, None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_staged_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        init(&root).unwrap();
+        std::fs::write(root.join("a.py"), "").unwrap();
+        std::fs::write(root.join("b.py"), "").unwrap();
+        std::fs::write(root.join("c.py"), "").unwrap();
+
+        stage_paths(&root, &[root.join("a.py"), root.join("b.py")]).unwrap();
+        std::fs::write(root.join("b.py"), "import os").unwrap();
+
+        let mut files = staged_files(&root).unwrap();
+        files.sort();
+        assert_eq!(files, vec![root.join("a.py"), root.join("b.py")]);
+        assert_eq!(unstaged_files(&root).unwrap(), vec![root.join("b.py")]);
+        stage_paths(&root, &[root.join("b.py")]).unwrap();
+        assert!(unstaged_files(&root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_release_history() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let repo = init(&root).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "huak").unwrap();
+        config.set_str("user.email", "huak@example.com").unwrap();
+        let signature = repo.signature().unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+
+        assert!(latest_tag(&root).unwrap().is_none());
+        tag_head(&root, "v0.1.0", "v0.1.0").unwrap();
+        std::fs::write(root.join("a.py"), "").unwrap();
+        commit_paths(&root, &[root.join("a.py")], "feat: add a").unwrap();
+        std::fs::write(root.join("b.py"), "").unwrap();
+        commit_paths(&root, &[root.join("b.py")], "fix: add b").unwrap();
+
+        assert_eq!(latest_tag(&root).unwrap().as_deref(), Some("v0.1.0"));
+        assert_eq!(
+            commit_messages(&root, Some("v0.1.0")).unwrap(),
+            vec!["fix: add b", "feat: add a"]
+        );
+        assert_eq!(commit_messages(&root, None).unwrap().len(), 3);
+    }
 }
@@ -0,0 +1,289 @@
+use crate::{Client, Error, HuakResult};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+const PYPI_JSON_API_URL: &str = "https://pypi.org/pypi";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A release that was yanked from the package index (PEP 592). Yanked releases are
+/// still installable when pinned exactly, but shouldn't be selected otherwise.
+pub struct YankedRelease {
+    /// The name of the package.
+    pub name: String,
+    /// The yanked version.
+    pub version: String,
+    /// The reason the release was yanked, if one was given.
+    pub reason: Option<String>,
+}
+
+impl std::fmt::Display for YankedRelease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}=={}", self.name, self.version)?;
+        if let Some(reason) = self.reason.as_ref() {
+            write!(f, " ({reason})")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+/// A project's metadata from the package index, as of its latest release.
+pub struct ProjectInfo {
+    /// The project's name.
+    pub name: String,
+    /// The project's latest version.
+    pub version: String,
+    /// The project's one-line summary.
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// The project's license.
+    #[serde(default)]
+    pub license: Option<String>,
+    /// The Python versions the latest release supports.
+    #[serde(default)]
+    pub requires_python: Option<String>,
+    /// The latest release's PEP 508 requirements.
+    #[serde(default)]
+    pub requires_dist: Option<Vec<String>>,
+    /// The extras the latest release provides.
+    #[serde(default)]
+    pub provides_extra: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct ProjectResponse {
+    info: ProjectInfo,
+}
+
+#[derive(Deserialize)]
+/// The part of the index's JSON API response for a release used to detect yanks.
+struct ReleaseResponse {
+    info: ReleaseInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+/// A file published for a release, such as a wheel or an sdist.
+pub struct ReleaseFile {
+    /// The file's name.
+    pub filename: String,
+    /// The kind of distribution the file is, such as "bdist_wheel" or "sdist".
+    pub packagetype: String,
+    /// The URL the file is downloaded from.
+    #[serde(default)]
+    pub url: String,
+}
+
+impl ReleaseFile {
+    /// Check if the file is a source distribution.
+    pub fn is_sdist(&self) -> bool {
+        self.packagetype == "sdist"
+    }
+}
+
+#[derive(Deserialize)]
+/// The part of the index's JSON API response for a release listing its files.
+struct ReleaseFilesResponse {
+    #[serde(default)]
+    urls: Vec<ReleaseFile>,
+}
+
+#[derive(Deserialize)]
+struct ReleaseInfo {
+    #[serde(default)]
+    yanked: bool,
+    #[serde(default)]
+    yanked_reason: Option<String>,
+}
+
+/// Get the releases of `(name, version)` pairs that were yanked from the package index.
+/// Releases the index doesn't have, such as local packages, aren't considered yanked.
+pub fn yanked_releases(
+    client: &Client,
+    releases: &[(String, String)],
+) -> HuakResult<Vec<YankedRelease>> {
+    let urls = releases
+        .iter()
+        .map(|(name, version)| {
+            format!("{PYPI_JSON_API_URL}/{name}/{version}/json")
+        })
+        .collect();
+
+    let mut yanked = Vec::new();
+    for ((name, version), res) in releases.iter().zip(client.get_all(urls)) {
+        let bytes = match res {
+            Ok(it) => it,
+            Err(Error::NetworkError(e))
+                if e.status() == Some(StatusCode::NOT_FOUND) =>
+            {
+                continue
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(reason) = yanked_reason(&bytes)? {
+            yanked.push(YankedRelease {
+                name: name.clone(),
+                version: version.clone(),
+                reason,
+            });
+        }
+    }
+
+    Ok(yanked)
+}
+
+/// Get the files published for each of the releases of `(name, version)` pairs. Releases
+/// the index doesn't have, such as local packages, have no files.
+pub fn release_files(
+    client: &Client,
+    releases: &[(String, String)],
+) -> HuakResult<Vec<Option<Vec<ReleaseFile>>>> {
+    let urls = releases
+        .iter()
+        .map(|(name, version)| {
+            format!("{PYPI_JSON_API_URL}/{name}/{version}/json")
+        })
+        .collect();
+
+    client
+        .get_all(urls)
+        .into_iter()
+        .map(|res| match res {
+            Ok(it) => Ok(Some(parse_release_files(&it)?)),
+            Err(Error::NetworkError(e))
+                if e.status() == Some(StatusCode::NOT_FOUND) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        })
+        .collect()
+}
+
+/// Check if a project with a name has been published to the package index.
+pub fn project_exists(client: &Client, name: &str) -> HuakResult<bool> {
+    match client.get_bytes(&format!("{PYPI_JSON_API_URL}/{name}/json")) {
+        Ok(_) => Ok(true),
+        Err(Error::NetworkError(e))
+            if e.status() == Some(StatusCode::NOT_FOUND) =>
+        {
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Check if a version of a project has been published to the package index.
+pub fn release_exists(
+    client: &Client,
+    name: &str,
+    version: &str,
+) -> HuakResult<bool> {
+    match client
+        .get_bytes(&format!("{PYPI_JSON_API_URL}/{name}/{version}/json"))
+    {
+        Ok(_) => Ok(true),
+        Err(Error::NetworkError(e))
+            if e.status() == Some(StatusCode::NOT_FOUND) =>
+        {
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Get a project's metadata from the package index, or `None` if the index doesn't have
+/// the project.
+pub fn project_info(
+    client: &Client,
+    name: &str,
+) -> HuakResult<Option<ProjectInfo>> {
+    match client.get_bytes(&format!("{PYPI_JSON_API_URL}/{name}/json")) {
+        Ok(it) => Ok(Some(parse_project_info(&it)?)),
+        Err(Error::NetworkError(e))
+            if e.status() == Some(StatusCode::NOT_FOUND) =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Parse a project's JSON API response. The index uses empty strings for metadata that
+/// wasn't given, so they're treated as missing.
+fn parse_project_info(bytes: &[u8]) -> HuakResult<ProjectInfo> {
+    let mut info = serde_json::from_slice::<ProjectResponse>(bytes)?.info;
+    for it in [
+        &mut info.summary,
+        &mut info.license,
+        &mut info.requires_python,
+    ] {
+        *it = it.take().filter(|it| !it.trim().is_empty());
+    }
+
+    Ok(info)
+}
+
+/// Parse a release's JSON API response, returning its files.
+fn parse_release_files(bytes: &[u8]) -> HuakResult<Vec<ReleaseFile>> {
+    Ok(serde_json::from_slice::<ReleaseFilesResponse>(bytes)?.urls)
+}
+
+/// Parse a release's JSON API response, returning its yank reason if it was yanked.
+/// Empty reasons are treated as no reason.
+fn yanked_reason(bytes: &[u8]) -> HuakResult<Option<Option<String>>> {
+    let res: ReleaseResponse = serde_json::from_slice(bytes)?;
+    if !res.info.yanked {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        res.info.yanked_reason.filter(|it| !it.trim().is_empty()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_project_info() {
+        let bytes = br#"{"info": {"name": "requests", "version": "2.31.0", "summary": "Python HTTP for Humans.", "license": "", "requires_python": ">=3.7", "requires_dist": ["idna<4,>=2.5", "PySocks!=1.5.7,>=1.5.6; extra == \"socks\""], "provides_extra": ["socks"], "yanked": false}, "urls": []}"#;
+
+        let info = parse_project_info(bytes).unwrap();
+
+        assert_eq!(info.name, "requests");
+        assert_eq!(info.version, "2.31.0");
+        assert_eq!(info.summary.as_deref(), Some("Python HTTP for Humans."));
+        assert_eq!(info.license, None);
+        assert_eq!(info.requires_dist.map(|it| it.len()), Some(2));
+        assert_eq!(info.provides_extra, Some(vec!["socks".to_string()]));
+    }
+
+    #[test]
+    fn test_parse_release_files() {
+        let bytes = br#"{"info": {"name": "pkg"}, "urls": [{"filename": "pkg-1.0-cp311-cp311-manylinux_2_17_x86_64.whl", "packagetype": "bdist_wheel"}, {"filename": "pkg-1.0.tar.gz", "packagetype": "sdist"}]}"#;
+
+        let files = parse_release_files(bytes).unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert!(!files[0].is_sdist());
+        assert!(files[1].is_sdist());
+        assert_eq!(files[1].filename, "pkg-1.0.tar.gz");
+    }
+
+    #[test]
+    fn test_yanked_reason() {
+        let yanked = br#"{"info": {"name": "pkg", "yanked": true, "yanked_reason": "broken wheel"}, "urls": []}"#;
+        let unexplained = br#"{"info": {"yanked": true, "yanked_reason": ""}}"#;
+        let available =
+            br#"{"info": {"yanked": false, "yanked_reason": null}}"#;
+
+        assert_eq!(
+            yanked_reason(yanked).unwrap(),
+            Some(Some("broken wheel".to_string()))
+        );
+        assert_eq!(yanked_reason(unexplained).unwrap(), Some(None));
+        assert_eq!(yanked_reason(available).unwrap(), None);
+    }
+}
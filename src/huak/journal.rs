@@ -0,0 +1,527 @@
+use crate::{
+    changelog::civil_date, dist_info::scan_site_packages,
+    package::canonical_package_name, workspace::Workspace, Error, HuakResult,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The path to the directory `JournalEntry`s are written to relative to the `Workspace`
+/// root.
+const HISTORY_DIR: &str = ".huak/history";
+/// The number of `JournalEntry`s kept. Older entries are removed as new ones are written.
+const HISTORY_LIMIT: usize = 50;
+const JOURNAL_VERSION: u32 = 1;
+/// The path to the log of commands run in a `Workspace` relative to its root. Each line is
+/// a `HistoryRecord` serialized as JSON.
+const HISTORY_LOG: &str = ".huak/history.log";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A `JournalEntry` records what an operation changed in a `Workspace`, so that it can be
+/// rolled back.
+///
+/// ```toml
+/// version = 1
+/// operation = "update"
+/// timestamp = 1697040000
+/// metadata-file = "pyproject.toml"
+/// metadata = "[project]\nname = \"my-project\"\n..."
+///
+/// [[package]]
+/// name = "click"
+/// before = "8.1.3"
+/// after = "8.1.7"
+/// ```
+#[serde(rename_all = "kebab-case")]
+pub struct JournalEntry {
+    /// The version of the journal format.
+    pub version: u32,
+    /// The name of the operation, such as "add".
+    pub operation: String,
+    /// When the operation ran, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The path to the metadata file relative to the `Workspace` root, if the operation
+    /// changed it.
+    #[serde(default)]
+    pub metadata_file: Option<PathBuf>,
+    /// The metadata file's contents before the operation changed it.
+    #[serde(default)]
+    pub metadata: Option<String>,
+    /// The packages the operation installed, removed, or changed the version of.
+    #[serde(default, rename = "package")]
+    pub packages: Vec<PackageChange>,
+    /// The path to the entry.
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl JournalEntry {
+    /// Read a `JournalEntry` from a path.
+    pub fn from_path<T: AsRef<Path>>(path: T) -> HuakResult<JournalEntry> {
+        let contents = std::fs::read_to_string(&path)?;
+        let mut entry: JournalEntry = toml::from_str(&contents)?;
+        if entry.version > JOURNAL_VERSION {
+            return Err(Error::HuakConfigurationError(format!(
+                "{} uses an unsupported journal version ({})",
+                path.as_ref().display(),
+                entry.version
+            )));
+        }
+        entry.path = path.as_ref().to_path_buf();
+
+        Ok(entry)
+    }
+
+    /// Get a reference to the path to the `JournalEntry`.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Get a description of each change recorded by the `JournalEntry`.
+    pub fn changes(&self) -> Vec<String> {
+        let mut changes = Vec::new();
+        if let Some(path) = self.metadata_file.as_ref() {
+            changes.push(format!("changed {}", path.display()));
+        }
+        changes.extend(self.packages.iter().map(ToString::to_string));
+
+        changes
+    }
+
+    /// Write the `JournalEntry` to its path.
+    pub fn write_file(&self) -> HuakResult<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let string = toml::to_string_pretty(self)?;
+        Ok(std::fs::write(&self.path, string)?)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A change to a package installed in the `Workspace`'s Python environment.
+pub struct PackageChange {
+    /// The name of the package.
+    pub name: String,
+    /// The version installed before the operation, if it was installed.
+    #[serde(default)]
+    pub before: Option<String>,
+    /// The version installed after the operation, if it's still installed.
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+impl Display for PackageChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.before.as_ref(), self.after.as_ref()) {
+            (Some(before), Some(after)) => {
+                write!(f, "updated {} {before} -> {after}", self.name)
+            }
+            (None, Some(after)) => write!(f, "installed {} {after}", self.name),
+            (Some(before), None) => {
+                write!(f, "uninstalled {} {before}", self.name)
+            }
+            (None, None) => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A `HistoryRecord` logs a command run in a `Workspace`, so that teams can reconstruct
+/// how its environment got into its current state.
+pub struct HistoryRecord {
+    /// When the command started, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The command line, such as "huak add click".
+    pub command: String,
+    /// The command's exit status.
+    pub status: i32,
+    /// The error the command failed with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Descriptions of what the command changed in the `Workspace`.
+    #[serde(default)]
+    pub changes: Vec<String>,
+}
+
+impl HistoryRecord {
+    /// Get the `HistoryRecord`'s timestamp formatted as a UTC date and time.
+    pub fn time(&self) -> String {
+        let secs = self.timestamp % 86400;
+        format!(
+            "{} {:02}:{:02}:{:02}",
+            civil_date(self.timestamp / 86400),
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        )
+    }
+}
+
+/// A `Journal` records what an operation changes in a `Workspace` while it's alive. The
+/// changes are written to the `Workspace`'s history as a `JournalEntry` when it's
+/// dropped, whether the operation succeeded or not, so partial changes can be rolled
+/// back too. Nothing is written if nothing changed.
+pub struct Journal<'a> {
+    workspace: &'a Workspace,
+    operation: String,
+    timestamp: SystemTime,
+    metadata_path: Option<PathBuf>,
+    metadata: Option<String>,
+    packages: Vec<(String, String)>,
+}
+
+impl<'a> Journal<'a> {
+    /// Start recording an operation's changes to a `Workspace`.
+    pub fn begin(
+        workspace: &'a Workspace,
+        operation: &str,
+    ) -> HuakResult<Journal<'a>> {
+        let metadata_path = workspace
+            .current_local_metadata()
+            .ok()
+            .map(|it| it.path().clone());
+        let metadata = metadata_path
+            .as_ref()
+            .and_then(|it| std::fs::read_to_string(it).ok());
+
+        Ok(Journal {
+            workspace,
+            operation: operation.to_string(),
+            timestamp: SystemTime::now(),
+            metadata_path,
+            metadata,
+            packages: installed_versions(workspace)?,
+        })
+    }
+
+    /// Write the changes made since the `Journal` began to the `Workspace`'s history,
+    /// returning the `JournalEntry` if anything changed.
+    pub fn finish(&self) -> HuakResult<Option<JournalEntry>> {
+        let metadata_changed =
+            self.metadata_path.as_ref().map_or(false, |it| {
+                std::fs::read_to_string(it).ok() != self.metadata
+            });
+        let packages = package_changes(
+            &self.packages,
+            &installed_versions(self.workspace)?,
+        );
+        if !metadata_changed && packages.is_empty() {
+            return Ok(None);
+        }
+
+        let elapsed = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let root = self.workspace.root();
+        let entry = JournalEntry {
+            version: JOURNAL_VERSION,
+            operation: self.operation.clone(),
+            timestamp: elapsed.as_secs(),
+            metadata_file: match metadata_changed {
+                true => self.metadata_path.as_ref().map(|it| {
+                    it.strip_prefix(root).unwrap_or(it).to_path_buf()
+                }),
+                false => None,
+            },
+            metadata: match metadata_changed {
+                true => self.metadata.clone(),
+                false => None,
+            },
+            packages,
+            path: history_dir(root).join(format!(
+                "{:015}-{}.toml",
+                elapsed.as_millis(),
+                self.operation
+            )),
+        };
+        entry.write_file()?;
+
+        // Keep the history from growing without bound.
+        let entries = journal_entries(root)?;
+        for it in entries
+            .iter()
+            .take(entries.len().saturating_sub(HISTORY_LIMIT))
+        {
+            std::fs::remove_file(it.path())?;
+        }
+
+        Ok(Some(entry))
+    }
+}
+
+impl Drop for Journal<'_> {
+    fn drop(&mut self) {
+        self.finish().ok();
+    }
+}
+
+/// Get the path to the directory a `Workspace`'s `JournalEntry`s are written to.
+pub fn history_dir<T: AsRef<Path>>(root: T) -> PathBuf {
+    root.as_ref().join(HISTORY_DIR)
+}
+
+/// Get a `Workspace`'s `JournalEntry`s from oldest to newest.
+pub fn journal_entries<T: AsRef<Path>>(
+    root: T,
+) -> HuakResult<Vec<JournalEntry>> {
+    let dir = history_dir(root);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = std::fs::read_dir(dir)?
+        .filter_map(|it| it.ok().map(|it| it.path()))
+        .filter(|it| it.extension().map_or(false, |it| it == "toml"))
+        .collect::<Vec<_>>();
+    // Entries are named by their timestamp, so they sort by name.
+    paths.sort();
+
+    paths.iter().map(JournalEntry::from_path).collect()
+}
+
+/// Append a `HistoryRecord` for a command that started at `started` to a `Workspace`'s
+/// log. The changes recorded by `JournalEntry`s written since the command started are
+/// included.
+pub fn record_command<T: AsRef<Path>>(
+    root: T,
+    command: &str,
+    started: SystemTime,
+    status: i32,
+    error: Option<String>,
+) -> HuakResult<()> {
+    let elapsed = started.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let changes = journal_entries(&root)?
+        .iter()
+        .filter(|it| {
+            entry_millis(it).map_or(false, |it| it >= elapsed.as_millis())
+        })
+        .flat_map(JournalEntry::changes)
+        .collect();
+    let record = HistoryRecord {
+        timestamp: elapsed.as_secs(),
+        command: command.to_string(),
+        status,
+        error,
+        changes,
+    };
+
+    let path = root.as_ref().join(HISTORY_LOG);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(&record)?)?;
+
+    Ok(())
+}
+
+/// Get the `HistoryRecord`s logged for a `Workspace` from oldest to newest. Lines that
+/// can't be parsed, such as one cut short by an interrupted write, are skipped.
+pub fn history_records<T: AsRef<Path>>(
+    root: T,
+) -> HuakResult<Vec<HistoryRecord>> {
+    let path = root.as_ref().join(HISTORY_LOG);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .filter_map(|it| serde_json::from_str(it).ok())
+        .collect())
+}
+
+/// Get the time a `JournalEntry` was written in milliseconds since the Unix epoch from
+/// its file name.
+fn entry_millis(entry: &JournalEntry) -> Option<u128> {
+    entry
+        .path()
+        .file_name()?
+        .to_str()?
+        .split('-')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Get the name and version of each package installed in a `Workspace`'s Python
+/// environment. Workspaces without an environment have no packages installed.
+fn installed_versions(
+    workspace: &Workspace,
+) -> HuakResult<Vec<(String, String)>> {
+    let python_env = match workspace.current_python_environment() {
+        Ok(it) => it,
+        Err(Error::PythonEnvironmentNotFound) => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(scan_site_packages(python_env.site_packages_dir_path())?
+        .iter()
+        .map(|it| (it.name().to_string(), it.version().to_string()))
+        .collect())
+}
+
+/// Get the `PackageChange`s between the packages installed before and after an
+/// operation, sorted by name.
+fn package_changes(
+    before: &[(String, String)],
+    after: &[(String, String)],
+) -> Vec<PackageChange> {
+    let key = |name: &str| {
+        canonical_package_name(name)
+            .unwrap_or_else(|_| name.to_string())
+            .to_lowercase()
+    };
+    let find = |packages: &[(String, String)], name: &str| {
+        packages
+            .iter()
+            .find(|(it, _)| key(it) == key(name))
+            .map(|(_, version)| version.clone())
+    };
+
+    let mut changes = Vec::new();
+    for (name, version) in before {
+        let after = find(after, name);
+        if after.as_ref() != Some(version) {
+            changes.push(PackageChange {
+                name: name.clone(),
+                before: Some(version.clone()),
+                after,
+            });
+        }
+    }
+    for (name, version) in after {
+        if find(before, name).is_none() {
+            changes.push(PackageChange {
+                name: name.clone(),
+                before: None,
+                after: Some(version.clone()),
+            });
+        }
+    }
+    changes.sort_by_key(|it| key(&it.name));
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_package_changes() {
+        let package =
+            |name: &str, version: &str| (name.to_string(), version.to_string());
+        let before = [package("click", "8.1.3"), package("Black", "22.8.0")];
+        let after = [package("click", "8.1.7"), package("ruff", "0.1.6")];
+
+        let changes = package_changes(&before, &after);
+
+        assert_eq!(
+            changes,
+            [
+                PackageChange {
+                    name: "Black".to_string(),
+                    before: Some("22.8.0".to_string()),
+                    after: None,
+                },
+                PackageChange {
+                    name: "click".to_string(),
+                    before: Some("8.1.3".to_string()),
+                    after: Some("8.1.7".to_string()),
+                },
+                PackageChange {
+                    name: "ruff".to_string(),
+                    before: None,
+                    after: Some("0.1.6".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_journal_entries() {
+        let dir = tempdir().unwrap();
+        for (millis, operation) in [(2, "update"), (1, "add")] {
+            let entry = JournalEntry {
+                version: JOURNAL_VERSION,
+                operation: operation.to_string(),
+                timestamp: 0,
+                metadata_file: Some(PathBuf::from("pyproject.toml")),
+                metadata: Some(
+                    "[project]\nname = \"mock-project\"\n".to_string(),
+                ),
+                packages: Vec::new(),
+                path: history_dir(dir.path())
+                    .join(format!("{millis:015}-{operation}.toml")),
+            };
+            entry.write_file().unwrap();
+        }
+
+        let entries = journal_entries(dir.path()).unwrap();
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|it| it.operation.as_str())
+                .collect::<Vec<_>>(),
+            ["add", "update"]
+        );
+        assert_eq!(
+            entries[0],
+            JournalEntry::from_path(entries[0].path()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_record_command() {
+        let dir = tempdir().unwrap();
+        let started = UNIX_EPOCH + std::time::Duration::from_millis(2000);
+        for millis in [1000, 3000] {
+            let entry = JournalEntry {
+                version: JOURNAL_VERSION,
+                operation: "add".to_string(),
+                timestamp: 0,
+                metadata_file: None,
+                metadata: None,
+                packages: vec![PackageChange {
+                    name: format!("package-{millis}"),
+                    before: None,
+                    after: Some("1.0.0".to_string()),
+                }],
+                path: history_dir(dir.path())
+                    .join(format!("{millis:015}-add.toml")),
+            };
+            entry.write_file().unwrap();
+        }
+
+        record_command(dir.path(), "huak add package-3000", started, 0, None)
+            .unwrap();
+        record_command(
+            dir.path(),
+            "huak update",
+            started,
+            1,
+            Some("oops".to_string()),
+        )
+        .unwrap();
+        let records = history_records(dir.path()).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].command, "huak add package-3000");
+        assert_eq!(records[0].changes, ["installed package-3000 1.0.0"]);
+        assert_eq!(records[0].time(), "1970-01-01 00:00:02");
+        assert_eq!(records[1].status, 1);
+        assert_eq!(records[1].error.as_deref(), Some("oops"));
+    }
+}
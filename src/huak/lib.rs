@@ -1,74 +1,100 @@
-///! # Huak
-///!
-///! A Python package manager written in Rust inspired by Cargo.
-///!
-///! ## About
-///!
-///! Huak is considered a package manager but focuses on supporting development workflows
-///! useful for building both Python packages and projects in general.
-///!
-///! Workflows supported consist of the following life-cycle:
-///! 1. Initialization and setup
-///! 2. Making some change to the project
-///! 3. Running tests
-///! 4. Distributing the project
-///!
-///!```zsh
-///! ❯ huak help
-///!
-///! A Python package manager written in Rust inspired by Cargo.
-///!
-///! Usage: huak [OPTIONS] <COMMAND>
-///!
-///! Commands:
-///!   activate    Activate the virtual environment
-///!   add         Add dependencies to the project
-///!   build       Build tarball and wheel for the project
-///!   completion  Generates a shell completion script for supported shells
-///!   clean       Remove tarball and wheel from the built project
-///!   fix         Auto-fix fixable lint conflicts
-///!   fmt         Format the project's Python code
-///!   init        Initialize the existing project
-///!   install     Install the dependencies of an existing project
-///!   lint        Lint the project's Python code
-///!   new         Create a new project at <path>
-///!   lish        Builds and uploads current project to a registry
-///!   python      Manage Python installations
-///!   remove      Remove dependencies from the project
-///!   run         Run a command within the project's environment context
-///!   test        Test the project's Python code
-///!   update      Update the project's dependencies
-///!   version     Display the version of the project
-///!   help        Print this message or the help of the given subcommand(s)
-///!
-///!  Options:
-///!    -q, --quiet    
-///!    -h, --help     Print help
-///!    -V, --version  Print version
-///!```
+//! # Huak
+//!
+//! A Python package manager written in Rust inspired by Cargo.
+//!
+//! ## About
+//!
+//! Huak is considered a package manager but focuses on supporting development workflows
+//! useful for building both Python packages and projects in general.
+//!
+//! Workflows supported consist of the following life-cycle:
+//! 1. Initialization and setup
+//! 2. Making some change to the project
+//! 3. Running tests
+//! 4. Distributing the project
+//!
+//!```zsh
+//! ❯ huak help
+//!
+//! A Python package manager written in Rust inspired by Cargo.
+//!
+//! Usage: huak [OPTIONS] <COMMAND>
+//!
+//! Commands:
+//!   activate    Activate the virtual environment
+//!   add         Add dependencies to the project
+//!   build       Build tarball and wheel for the project
+//!   completion  Generates a shell completion script for supported shells
+//!   clean       Remove tarball and wheel from the built project
+//!   fix         Auto-fix fixable lint conflicts
+//!   fmt         Format the project's Python code
+//!   init        Initialize the existing project
+//!   install     Install the dependencies of an existing project
+//!   lint        Lint the project's Python code
+//!   new         Create a new project at <path>
+//!   lish        Builds and uploads current project to a registry
+//!   python      Manage Python installations
+//!   remove      Remove dependencies from the project
+//!   run         Run a command within the project's environment context
+//!   test        Test the project's Python code
+//!   update      Update the project's dependencies
+//!   version     Display the version of the project
+//!   help        Print this message or the help of the given subcommand(s)
+//!
+//!  Options:
+//!    -q, --quiet    
+//!    -h, --help     Print help
+//!    -V, --version  Print version
+//!```
+mod cache;
+mod changelog;
+mod checksums;
+mod classifiers;
 mod config;
 mod dependency;
+mod dist_info;
 mod environment;
 mod error;
 mod fs;
 mod git;
+mod index;
+mod journal;
+mod lockfile;
 mod metadata;
+mod network;
 pub mod ops;
 mod package;
 mod python_environment;
+mod schema;
+mod snapshot;
 mod sys;
+mod template;
+mod toolchain;
 mod version;
+mod watch;
+mod wheel;
 mod workspace;
 
+pub use cache::{CacheAge, CacheSize};
+pub use changelog::Bump;
 pub use config::Config;
-pub use error::{Error, HuakResult};
-pub use python_environment::InstallOptions;
+pub use error::{
+    Diagnostic, Error, ErrorCategory, HuakResult, SourceLocation,
+    EXIT_INTERNAL, EXIT_NETWORK, EXIT_PROJECT, EXIT_SUCCESS, EXIT_TOOL_FAILURE,
+    EXIT_USAGE,
+};
+pub use fs::canonical_path;
+pub use network::{Client, Download, NetworkOptions, Upload, UploadStatus};
 use python_environment::PythonEnvironment;
+pub use python_environment::{InstallOptions, PythonRequest, VenvOptions};
 #[allow(unused_imports)]
 use std::path::PathBuf;
-pub use sys::{SubprocessError, TerminalOptions, Verbosity};
+pub use sys::{
+    color_override, is_interactive, CiProvider, CommandOutput, SubprocessError,
+    TerminalOptions, Verbosity,
+};
 pub use version::Version;
-pub use workspace::WorkspaceOptions;
+pub use workspace::{discover_workspace_root, project_root, WorkspaceOptions};
 
 #[cfg(test)]
 /// The resource directory found in the Huak repo used for testing purposes.
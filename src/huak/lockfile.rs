@@ -0,0 +1,215 @@
+use crate::{Error, HuakResult};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const LOCKFILE_FILE_NAME: &str = "huak.lock";
+const LOCKFILE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// The `Lockfile` pins the exact distributions installed for a project.
+///
+/// ```toml
+/// version = 1
+/// requirements = ["click>=8"]
+/// constraints = ["colorama ==0.4.6"]
+///
+/// [[target]]
+/// python = "3.11"
+/// platform = "linux"
+///
+/// [[package]]
+/// name = "click"
+/// version = "8.1.3"
+///
+/// [[package.wheels]]
+/// url = "https://files.pythonhosted.org/packages/.../click-8.1.3-py3-none-any.whl"
+/// hash = "sha256:bb4d8133cb15a609f44e8213d9b391b0809795062913b383c62be0ee95b1db48"
+/// ```
+pub struct Lockfile {
+    /// The version of the lockfile format.
+    pub version: u32,
+    /// The project requirements the packages were resolved from.
+    #[serde(default)]
+    pub requirements: Vec<String>,
+    /// The project's `[tool.huak.constraints]` the packages were resolved with.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<String>,
+    /// The environments the packages were resolved for.
+    #[serde(default, rename = "target")]
+    pub targets: Vec<LockTarget>,
+    /// The locked packages.
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+    /// The path to the lockfile.
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Lockfile {
+    /// Initialize an empty `Lockfile` to be written to a path.
+    pub fn new<T: AsRef<Path>>(path: T) -> Lockfile {
+        Lockfile {
+            version: LOCKFILE_VERSION,
+            requirements: Vec::new(),
+            constraints: Vec::new(),
+            targets: Vec::new(),
+            packages: Vec::new(),
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Read a `Lockfile` from a path.
+    pub fn from_path<T: AsRef<Path>>(path: T) -> HuakResult<Lockfile> {
+        let contents = std::fs::read_to_string(&path)?;
+        let mut lockfile: Lockfile = toml::from_str(&contents)?;
+        if lockfile.version > LOCKFILE_VERSION {
+            return Err(Error::HuakConfigurationError(format!(
+                "{} uses an unsupported lockfile version ({})",
+                path.as_ref().display(),
+                lockfile.version
+            )));
+        }
+        lockfile.path = path.as_ref().to_path_buf();
+
+        Ok(lockfile)
+    }
+
+    /// Get a reference to the path to the `Lockfile`.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Check if every `LockedPackage` has at least one hash-pinned wheel.
+    pub fn is_hash_pinned(&self) -> bool {
+        self.packages.iter().all(|pkg| {
+            !pkg.wheels.is_empty()
+                && pkg.wheels.iter().all(|wheel| !wheel.hash.is_empty())
+        })
+    }
+
+    /// Write the `Lockfile` to its path.
+    pub fn write_file(&self) -> HuakResult<()> {
+        let string = toml::to_string_pretty(self)?;
+        Ok(std::fs::write(&self.path, string)?)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A package pinned to an exact version by the `Lockfile`.
+pub struct LockedPackage {
+    /// The name of the package.
+    pub name: String,
+    /// The pinned version of the package.
+    pub version: String,
+    /// PEP 508 markers limiting the package to some of the `Lockfile`'s targets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub markers: Option<String>,
+    /// The wheels that can be installed for the pinned version.
+    #[serde(default)]
+    pub wheels: Vec<LockedWheel>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A wheel file pinned by its hash.
+pub struct LockedWheel {
+    /// The URL to download the wheel from.
+    pub url: String,
+    /// The wheel's hash as `<algorithm>:<hex digest>`.
+    pub hash: String,
+}
+
+impl LockedWheel {
+    /// Get the wheel's file name from its URL.
+    pub fn file_name(&self) -> &str {
+        let url = self.url.split(['#', '?']).next().unwrap_or_default();
+        url.rsplit('/').next().unwrap_or(url)
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord,
+)]
+/// An environment a `Lockfile` was resolved for.
+pub struct LockTarget {
+    /// The "<major>.<minor>" version of Python.
+    pub python: String,
+    /// The platform as "<os>" or "<os>-<arch>", such as "linux" or "macos-arm64". The
+    /// architecture defaults to x86_64.
+    pub platform: String,
+}
+
+impl LockTarget {
+    /// Get the `sys.platform` of the `LockTarget`'s platform.
+    pub fn sys_platform(&self) -> HuakResult<&'static str> {
+        match self.platform.split('-').next().unwrap_or_default() {
+            "linux" => Ok("linux"),
+            "macos" => Ok("darwin"),
+            "windows" => Ok("win32"),
+            _ => Err(Error::HuakConfigurationError(format!(
+                "unsupported platform {} (use linux, macos, or windows)",
+                self.platform
+            ))),
+        }
+    }
+
+    /// Get the PEP 508 markers matching environments of the `LockTarget`.
+    pub fn markers(&self) -> HuakResult<String> {
+        Ok(format!(
+            r#"python_version == "{}" and sys_platform == "{}""#,
+            self.python,
+            self.sys_platform()?
+        ))
+    }
+}
+
+impl std::fmt::Display for LockTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Python {} on {}", self.python, self.platform)
+    }
+}
+
+/// Get the file name used for `Lockfile`s.
+pub fn lockfile_file_name() -> &'static str {
+    LOCKFILE_FILE_NAME
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_lockfile_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(lockfile_file_name());
+        let mut lockfile = Lockfile::new(&path);
+        lockfile.targets.push(LockTarget {
+            python: "3.11".to_string(),
+            platform: "linux".to_string(),
+        });
+        lockfile.packages.push(LockedPackage {
+            name: "click".to_string(),
+            version: "8.1.3".to_string(),
+            markers: None,
+            wheels: vec![LockedWheel {
+                url: "https://files.example.com/click-8.1.3-py3-none-any.whl#sha256=bb4d"
+                    .to_string(),
+                hash: "sha256:bb4d".to_string(),
+            }],
+        });
+        lockfile.write_file().unwrap();
+
+        let read = Lockfile::from_path(&path).unwrap();
+
+        assert_eq!(read, lockfile);
+        assert!(read.is_hash_pinned());
+        assert_eq!(
+            read.packages[0].wheels[0].file_name(),
+            "click-8.1.3-py3-none-any.whl"
+        );
+        assert_eq!(
+            read.targets[0].markers().unwrap(),
+            r#"python_version == "3.11" and sys_platform == "linux""#
+        );
+    }
+}
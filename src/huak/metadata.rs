@@ -6,17 +6,19 @@ use std::{
 };
 
 use indexmap::IndexMap;
-use pep440_rs::Version;
+use pep440_rs::{Version, VersionSpecifiers};
 use pep508_rs::Requirement;
-use pyproject_toml::{BuildSystem, Project, PyProjectToml as ProjectToml};
+use pyproject_toml::{
+    BuildSystem, License, Project, PyProjectToml as ProjectToml,
+};
 use serde::{Deserialize, Serialize};
-use toml::Table;
+use toml::{Table, Value};
 
-use crate::{dependency::Dependency, Error, HuakResult};
+use crate::{dependency::Dependency, error::SourceLocation, Error, HuakResult};
 
 const DEFAULT_METADATA_FILE_NAME: &str = "pyproject.toml";
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// A `LocalMetadata` struct used to manage local `Metadata` files such as
 /// the pyproject.toml (https://peps.python.org/pep-0621/).
 pub struct LocalMetadata {
@@ -65,6 +67,11 @@ impl LocalMetadata {
         &self.metadata
     }
 
+    /// Get a reference to the path to the `LocalMetadata` file.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
     /// Get a mutable reference to the core `Metadata`.
     pub fn metadata_mut(&mut self) -> &mut Metadata {
         &mut self.metadata
@@ -147,6 +154,82 @@ impl Metadata {
         self.project.name = name
     }
 
+    pub fn set_requires_python(&mut self, specifiers: VersionSpecifiers) {
+        self.project.requires_python = Some(specifiers)
+    }
+
+    pub fn set_license_text(&mut self, text: String) {
+        self.project.license = Some(License {
+            file: None,
+            text: Some(text),
+        })
+    }
+
+    pub fn classifiers(&self) -> &[String] {
+        self.project.classifiers.as_deref().unwrap_or_default()
+    }
+
+    /// Add a trove classifier to the project if it doesn't already have it.
+    pub fn add_classifier(&mut self, classifier: &str) {
+        let classifiers = self.project.classifiers.get_or_insert_with(Vec::new);
+        if !classifiers.iter().any(|it| it == classifier) {
+            classifiers.push(classifier.to_string());
+        }
+    }
+
+    /// Get a tool's table from the `tool` table, such as `[tool.ruff]`.
+    pub fn tool_table(&self, name: &str) -> Option<&Table> {
+        self.tool.as_ref()?.get(name)?.as_table()
+    }
+
+    /// Set the package directories hatchling includes in the project's wheel
+    /// (`[tool.hatch.build.targets.wheel]`), such as "src/my_project".
+    pub fn set_wheel_packages(&mut self, packages: Vec<String>) {
+        let mut table = self.tool.take().unwrap_or_default();
+        let mut wheel = &mut table;
+        for key in ["hatch", "build", "targets", "wheel"] {
+            let value = wheel
+                .entry(key)
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !value.is_table() {
+                *value = Value::Table(Table::new());
+            }
+            wheel = value.as_table_mut().expect("value should be a table");
+        }
+        wheel.insert(
+            "packages".to_string(),
+            Value::Array(packages.into_iter().map(Value::String).collect()),
+        );
+        self.tool = Some(table);
+    }
+
+    /// Get a setting from one of huak's `[tool.huak.<table>]` tables.
+    pub fn huak_setting(&self, table: &str, key: &str) -> Option<&Value> {
+        self.tool_table("huak")?.get(table)?.get(key)
+    }
+
+    /// Set a setting in one of huak's `[tool.huak.<table>]` tables, creating the table if
+    /// needed.
+    pub fn set_huak_setting(&mut self, table: &str, key: &str, value: Value) {
+        let mut tool = self.tool.take().unwrap_or_default();
+        let mut setting = &mut tool;
+        for name in ["huak", table] {
+            let value = setting
+                .entry(name)
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !value.is_table() {
+                *value = Value::Table(Table::new());
+            }
+            setting = value.as_table_mut().expect("value should be a table");
+        }
+        setting.insert(key.to_string(), value);
+        self.tool = Some(tool);
+    }
+
+    pub fn set_project_version(&mut self, version: Version) {
+        self.project.version = Some(version)
+    }
+
     pub fn project_version(&self) -> Option<&Version> {
         self.project.version.as_ref()
     }
@@ -244,7 +327,7 @@ impl Metadata {
             .optional_dependencies
             .get_or_insert_with(IndexMap::new)
             .entry(group.to_string())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(dependency.requirement().to_owned());
     }
 
@@ -272,6 +355,10 @@ impl Metadata {
             });
     }
 
+    pub fn scripts(&self) -> Option<&IndexMap<String, String>> {
+        self.project.scripts.as_ref()
+    }
+
     pub fn add_script(&mut self, name: &str, entrypoint: &str) {
         self.project
             .scripts
@@ -335,8 +422,9 @@ impl std::ops::DerefMut for PyProjectToml {
 impl PyProjectToml {
     /// Initialize a `PyProjectToml` from its path.
     pub fn new<T: AsRef<Path>>(path: T) -> HuakResult<PyProjectToml> {
-        let contents = std::fs::read_to_string(path)?;
-        let pyproject_toml: PyProjectToml = toml::from_str(&contents)?;
+        let contents = std::fs::read_to_string(path.as_ref())?;
+        let pyproject_toml: PyProjectToml = toml::from_str(&contents)
+            .map_err(|e| located_toml_error(path.as_ref(), &contents, e))?;
 
         Ok(pyproject_toml)
     }
@@ -352,6 +440,22 @@ impl Default for PyProjectToml {
     }
 }
 
+/// Point a TOML error at the part of the file it's about. Errors the parser doesn't
+/// have a location for are returned as they are.
+fn located_toml_error(
+    path: &Path,
+    contents: &str,
+    e: toml::de::Error,
+) -> Error {
+    match e.span() {
+        Some(span) => Error::InvalidMetadataFile(
+            SourceLocation::from_span(path, contents, span),
+            e.message().to_string(),
+        ),
+        None => Error::TOMLDeserializationError(e),
+    }
+}
+
 pub fn default_pyproject_toml_contents(name: &str) -> String {
     format!(
         r#"[build-system]
@@ -371,23 +475,31 @@ pub fn default_entrypoint_string(importable_name: &str) -> String {
     format!("{importable_name}.main:main")
 }
 
-pub fn default_test_file_contents(importable_name: &str) -> String {
-    format!(
-        r#"from {importable_name} import __version__
-
-
-def test_version():
-    assert isinstance(__version__, str)
-"#
-    )
-}
-
 #[cfg(test)]
 mod tests {
     use std::ops::Deref;
 
     use super::*;
 
+    #[test]
+    fn toml_error_location() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pyproject.toml");
+        let contents = default_pyproject_toml_contents("mock-project")
+            .replace("version = \"0.0.1\"", "version = \"0.0.1");
+        std::fs::write(&path, contents).unwrap();
+
+        let Err(Error::InvalidMetadataFile(location, _)) =
+            LocalMetadata::new(&path)
+        else {
+            panic!("expected an invalid metadata file error");
+        };
+
+        assert_eq!(location.path, path);
+        assert_eq!(location.line, 7);
+        assert_eq!(location.text, "version = \"0.0.1");
+    }
+
     #[test]
     fn toml_from_path() {
         let path = crate::test_resources_dir_path()
@@ -444,7 +556,7 @@ dev = [
         let local_metadata = LocalMetadata::new(path).unwrap();
 
         assert_eq!(
-            local_metadata.metadata.dependencies().unwrap().deref(),
+            local_metadata.metadata.dependencies().unwrap(),
             vec![Requirement::from_str("click==8.1.3").unwrap()]
         );
     }
@@ -627,4 +739,37 @@ dev = [
 "#
         )
     }
+
+    #[test]
+    fn toml_huak_setting() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pyproject.toml");
+        std::fs::write(
+            &path,
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "mock_project"
+
+[tool.huak.fmt]
+docstrings = true
+"#,
+        )
+        .unwrap();
+        let local_metadata = LocalMetadata::new(path).unwrap();
+
+        assert_eq!(
+            local_metadata
+                .metadata
+                .huak_setting("fmt", "docstrings")
+                .and_then(|it| it.as_bool()),
+            Some(true)
+        );
+        assert!(local_metadata
+            .metadata
+            .huak_setting("lint", "docstrings")
+            .is_none());
+    }
 }
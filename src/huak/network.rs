@@ -0,0 +1,840 @@
+use crate::{metadata::Metadata, wheel::hex, Error, HuakResult};
+use reqwest::{Certificate, NoProxy, Proxy, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{runtime::Runtime, sync::Semaphore, task::JoinSet};
+
+const DEFAULT_PARALLELISM: usize = 8;
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const BACKOFF_BASE: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone)]
+/// Options used to configure a network `Client`.
+pub struct NetworkOptions {
+    /// The maximum number of requests made concurrently.
+    pub parallelism: usize,
+    /// The number of times a failed request is retried.
+    pub retries: u32,
+    /// The timeout for an entire request.
+    pub timeout: Duration,
+    /// The timeout for establishing a connection.
+    pub connect_timeout: Duration,
+    /// The timeout for an entire install, update, or download operation, including its
+    /// retries.
+    pub operation_timeout: Option<Duration>,
+    /// A PEM file of CA certificates to trust in addition to the built-in roots, such as a
+    /// corporate CA or a self-signed certificate.
+    pub ca_bundle: Option<PathBuf>,
+    /// Hosts whose certificates aren't verified.
+    pub trusted_hosts: Vec<String>,
+}
+
+impl Default for NetworkOptions {
+    fn default() -> Self {
+        Self {
+            parallelism: DEFAULT_PARALLELISM,
+            retries: DEFAULT_RETRIES,
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            operation_timeout: None,
+            ca_bundle: None,
+            trusted_hosts: Vec::new(),
+        }
+    }
+}
+
+impl NetworkOptions {
+    /// Apply the settings configured with a project's `[tool.huak.network]` table.
+    /// Timeouts are given in seconds, and the `ca-bundle` path is used as written.
+    pub fn with_metadata(
+        &self,
+        metadata: &Metadata,
+    ) -> HuakResult<NetworkOptions> {
+        let setting = |key: &str| -> HuakResult<Option<u64>> {
+            match metadata.huak_setting("network", key) {
+                Some(value) => value
+                    .as_integer()
+                    .and_then(|it| u64::try_from(it).ok())
+                    .map(Some)
+                    .ok_or_else(|| {
+                        Error::HuakConfigurationError(format!(
+                            "[tool.huak.network] {key} must be a non-negative integer"
+                        ))
+                    }),
+                None => Ok(None),
+            }
+        };
+        let mut options = self.clone();
+        if let Some(it) = setting("retries")? {
+            options.retries = u32::try_from(it).unwrap_or(u32::MAX);
+        }
+        if let Some(it) = setting("timeout")? {
+            options.timeout = Duration::from_secs(it);
+        }
+        if let Some(it) = setting("connect-timeout")? {
+            options.connect_timeout = Duration::from_secs(it);
+        }
+        if let Some(it) = setting("operation-timeout")? {
+            options.operation_timeout = Some(Duration::from_secs(it));
+        }
+        if let Some(value) = metadata.huak_setting("network", "ca-bundle") {
+            options.ca_bundle =
+                Some(PathBuf::from(value.as_str().ok_or_else(|| {
+                    Error::HuakConfigurationError(
+                        "[tool.huak.network] ca-bundle must be a path"
+                            .to_string(),
+                    )
+                })?));
+        }
+        if let Some(value) = metadata.huak_setting("network", "trusted-hosts") {
+            options.trusted_hosts = value
+                .as_array()
+                .and_then(|it| {
+                    it.iter()
+                        .map(|it| it.as_str().map(str::to_string))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| {
+                    Error::HuakConfigurationError(
+                        "[tool.huak.network] trusted-hosts must be a list of hosts"
+                            .to_string(),
+                    )
+                })?;
+        }
+
+        Ok(options)
+    }
+
+    /// Get the CA bundle to trust. Defaults to the `SSL_CERT_FILE` or `REQUESTS_CA_BUNDLE`
+    /// environment variables when `ca_bundle` isn't set.
+    pub fn ca_bundle(&self) -> Option<PathBuf> {
+        self.ca_bundle.clone().or_else(|| {
+            ["SSL_CERT_FILE", "REQUESTS_CA_BUNDLE"]
+                .iter()
+                .filter_map(std::env::var_os)
+                .find(|it| !it.is_empty())
+                .map(PathBuf::from)
+        })
+    }
+}
+
+/// A `Download` is a request to fetch a URL's content into a file.
+pub struct Download {
+    /// The URL to fetch.
+    pub url: String,
+    /// The path to write the content to.
+    pub path: PathBuf,
+}
+
+/// An `Upload` is a distribution file to upload to a package index with the fields of its
+/// upload form, such as its metadata and digests.
+pub struct Upload {
+    /// The path to the file.
+    pub path: PathBuf,
+    /// The form's fields. Fields that can have several values are repeated.
+    pub fields: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The outcome of an `Upload`.
+pub enum UploadStatus {
+    Uploaded,
+    /// The package index already has the file, such as when an interrupted publish is
+    /// resumed.
+    Existing,
+}
+
+/// The `Client` is used to make HTTP requests for huak's network-bound operations, such as
+/// querying package indexes and downloading distributions.
+///
+/// Requests are run on a tokio `Runtime` so that batches can be made concurrently while
+/// the `Client`'s API stays blocking. Failed requests are retried with an exponential
+/// backoff, and proxies are configured from `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and
+/// `NO_PROXY`. Certificates from the `NetworkOptions`' CA bundle are trusted, and the
+/// certificates of trusted hosts aren't verified.
+///
+/// ```
+/// use huak::{Client, NetworkOptions};
+///
+/// let client = Client::new(&NetworkOptions::default()).unwrap();
+/// let bytes = client.get_bytes("https://pypi.org/pypi/huak/json").unwrap();
+/// ```
+pub struct Client {
+    /// The inner async client.
+    inner: reqwest::Client,
+    /// The inner async client used for trusted hosts, if any are configured.
+    insecure: Option<reqwest::Client>,
+    /// The `Runtime` requests are run on.
+    runtime: Runtime,
+    /// The `NetworkOptions` used by the `Client`.
+    options: NetworkOptions,
+}
+
+impl Client {
+    /// Initialize a `Client` configured with `NetworkOptions`.
+    pub fn new(options: &NetworkOptions) -> HuakResult<Client> {
+        let proxies = proxies_from_env()?;
+        let certificates = match options.ca_bundle() {
+            Some(it) => certificates_from_pem_file(&it)?,
+            None => Vec::new(),
+        };
+        let builder = || {
+            let mut builder = reqwest::Client::builder()
+                .user_agent(concat!("huak/", env!("CARGO_PKG_VERSION")))
+                .timeout(options.timeout)
+                .connect_timeout(options.connect_timeout)
+                .no_proxy();
+            for proxy in proxies.iter() {
+                builder = builder.proxy(proxy.clone());
+            }
+            for certificate in certificates.iter() {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            builder
+        };
+        let insecure = match options.trusted_hosts.is_empty() {
+            true => None,
+            false => Some(builder().danger_accept_invalid_certs(true).build()?),
+        };
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+
+        Ok(Client {
+            inner: builder().build()?,
+            insecure,
+            runtime,
+            options: options.clone(),
+        })
+    }
+
+    /// Get the inner async client to request a URL with.
+    fn inner_for(&self, url: &str) -> &reqwest::Client {
+        let trusted = Url::parse(url).ok().map_or(false, |it| {
+            it.host_str().map_or(false, |host| {
+                self.options.trusted_hosts.iter().any(|it| it == host)
+            })
+        });
+        match (trusted, self.insecure.as_ref()) {
+            (true, Some(it)) => it,
+            _ => &self.inner,
+        }
+    }
+
+    /// Get a reference to the `Client`'s `NetworkOptions`.
+    pub fn options(&self) -> &NetworkOptions {
+        &self.options
+    }
+
+    /// Fetch the content of a URL.
+    pub fn get_bytes(&self, url: &str) -> HuakResult<Vec<u8>> {
+        self.runtime.block_on(get(
+            self.inner_for(url),
+            url,
+            self.options.retries,
+        ))
+    }
+
+    /// Fetch and deserialize the JSON content of a URL.
+    pub fn get_json<T: DeserializeOwned>(&self, url: &str) -> HuakResult<T> {
+        let bytes = self.get_bytes(url)?;
+
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Fetch and deserialize the JSON content of a URL, authorized with a bearer token.
+    pub fn get_json_authorized<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        token: &str,
+    ) -> HuakResult<T> {
+        let request = self.inner_for(url).get(url).bearer_auth(token);
+
+        self.runtime.block_on(send_json(request))
+    }
+
+    /// Post a JSON body to a URL and deserialize the JSON response. The bodies of error
+    /// responses are deserialized too, since APIs explain their failures in them. Posts
+    /// aren't retried.
+    pub fn post_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &serde_json::Value,
+    ) -> HuakResult<T> {
+        let request = self.inner_for(url).post(url).json(body);
+
+        self.runtime.block_on(send_json(request))
+    }
+
+    /// Run a batch of `Upload`s to a package index's upload URL concurrently, returning
+    /// each `Upload`'s result in the order they were given. `on_done` is called as each
+    /// `Upload` finishes so that progress can be reported. Files the index already has
+    /// fail to upload unless `skip_existing` is set, in which case they're skipped.
+    ///
+    /// The legacy upload API takes each file in a single multipart request, so files are
+    /// read into memory rather than uploaded in chunks.
+    pub fn upload_all(
+        &self,
+        url: &str,
+        credentials: (&str, &str),
+        uploads: Vec<Upload>,
+        skip_existing: bool,
+        mut on_done: impl FnMut(&Upload, &HuakResult<UploadStatus>),
+    ) -> Vec<HuakResult<UploadStatus>> {
+        let permits = Arc::new(Semaphore::new(self.options.parallelism.max(1)));
+        let retries = self.options.retries;
+        let client = self.inner_for(url).clone();
+        let len = uploads.len();
+        let (username, password) =
+            (credentials.0.to_string(), credentials.1.to_string());
+
+        self.runtime.block_on(async {
+            let mut tasks = JoinSet::new();
+            for (i, it) in uploads.into_iter().enumerate() {
+                let client = client.clone();
+                let permits = Arc::clone(&permits);
+                let (url, username, password) =
+                    (url.to_string(), username.clone(), password.clone());
+                tasks.spawn(async move {
+                    let res = match permits.acquire_owned().await {
+                        Ok(_permit) => {
+                            upload(
+                                &client,
+                                &url,
+                                (&username, &password),
+                                &it,
+                                skip_existing,
+                                retries,
+                            )
+                            .await
+                        }
+                        Err(e) => Err(Error::InternalError(e.to_string())),
+                    };
+
+                    (i, it, res)
+                });
+            }
+
+            let mut results = (0..len)
+                .map(|_| {
+                    Err(Error::InternalError(
+                        "upload wasn't completed".to_string(),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            // Uploads whose task panicked keep the placeholder error.
+            while let Some(res) = tasks.join_next().await {
+                if let Ok((i, it, res)) = res {
+                    on_done(&it, &res);
+                    results[i] = res;
+                }
+            }
+
+            results
+        })
+    }
+
+    /// Fetch the content of a batch of URLs concurrently, returning each URL's result in
+    /// the order the URLs were given.
+    pub fn get_all(&self, urls: Vec<String>) -> Vec<HuakResult<Vec<u8>>> {
+        let permits = Arc::new(Semaphore::new(self.options.parallelism.max(1)));
+        let retries = self.options.retries;
+        let len = urls.len();
+
+        self.runtime.block_on(async {
+            let mut tasks = JoinSet::new();
+            for (i, url) in urls.into_iter().enumerate() {
+                let client = self.inner_for(&url).clone();
+                let permits = Arc::clone(&permits);
+                tasks.spawn(async move {
+                    let res = match permits.acquire_owned().await {
+                        Ok(_permit) => get(&client, &url, retries).await,
+                        Err(e) => Err(Error::InternalError(e.to_string())),
+                    };
+
+                    (i, res)
+                });
+            }
+
+            let mut results = (0..len)
+                .map(|_| {
+                    Err(Error::InternalError(
+                        "request wasn't completed".to_string(),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            // Requests whose task panicked keep the placeholder error.
+            while let Some(res) = tasks.join_next().await {
+                if let Ok((i, it)) = res {
+                    results[i] = it;
+                }
+            }
+
+            results
+        })
+    }
+
+    /// Run a batch of `Download`s concurrently, returning the paths written to in the
+    /// order the `Download`s were given. The batch fails if it doesn't finish within the
+    /// `operation_timeout`.
+    pub fn download_all(
+        &self,
+        downloads: Vec<Download>,
+    ) -> HuakResult<Vec<PathBuf>> {
+        let permits = Arc::new(Semaphore::new(self.options.parallelism.max(1)));
+        let retries = self.options.retries;
+
+        let batch = async {
+            let mut tasks = JoinSet::new();
+            for (i, download) in downloads.into_iter().enumerate() {
+                let client = self.inner_for(&download.url).clone();
+                let permits = Arc::clone(&permits);
+                tasks.spawn(async move {
+                    let _permit = permits
+                        .acquire_owned()
+                        .await
+                        .map_err(|e| Error::InternalError(e.to_string()))?;
+                    let bytes = get(&client, &download.url, retries).await?;
+                    if let Some(parent) = download.path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&download.path, bytes)?;
+
+                    Ok::<_, Error>((i, download.path))
+                });
+            }
+
+            let mut paths = Vec::new();
+            while let Some(res) = tasks.join_next().await {
+                let it =
+                    res.map_err(|e| Error::InternalError(e.to_string()))?;
+                paths.push(it?);
+            }
+            paths.sort_by_key(|(i, _)| *i);
+
+            Ok(paths.into_iter().map(|(_, path)| path).collect())
+        };
+        match self.options.operation_timeout {
+            Some(timeout) => self.runtime.block_on(async {
+                tokio::time::timeout(timeout, batch).await.map_err(|_| {
+                    Error::OperationTimedOut(format!("{}s", timeout.as_secs()))
+                })?
+            }),
+            None => self.runtime.block_on(batch),
+        }
+    }
+}
+
+/// Upload a file with a multipart form, retrying with an exponential backoff when the
+/// failure is likely to be transient.
+async fn upload(
+    client: &reqwest::Client,
+    url: &str,
+    credentials: (&str, &str),
+    upload: &Upload,
+    skip_existing: bool,
+    retries: u32,
+) -> HuakResult<UploadStatus> {
+    let file_name = upload
+        .path
+        .file_name()
+        .map(|it| it.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let contents = std::fs::read(&upload.path)?;
+    // The file's digest can't appear in the file, so it's used as the form's boundary.
+    let boundary = hex(&Sha256::digest(&contents));
+    let body = multipart_body(&boundary, &upload.fields, &file_name, &contents);
+    drop(contents);
+    // The body is shared by the request's clones rather than copied for each attempt.
+    let request = client
+        .post(url)
+        .basic_auth(credentials.0, Some(credentials.1))
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(body);
+    let mut attempt = 0;
+    loop {
+        let res = request
+            .try_clone()
+            .ok_or(Error::InternalError(
+                "an upload request couldn't be retried".to_string(),
+            ))?
+            .send()
+            .await;
+        let err = match res {
+            Ok(it) if it.status().is_success() => {
+                return Ok(UploadStatus::Uploaded)
+            }
+            // Indexes explain why they rejected an upload in their response.
+            Ok(it) if it.status().is_client_error() => {
+                let status = it.status();
+                let text = it.text().await.unwrap_or_default();
+                // PyPI rejects files it already has with a 400 and other indexes with a
+                // 409.
+                let existing = status == StatusCode::CONFLICT
+                    || (status == StatusCode::BAD_REQUEST
+                        && text.to_lowercase().contains("already exist"));
+                if existing && skip_existing {
+                    return Ok(UploadStatus::Existing);
+                }
+                if existing {
+                    return Err(Error::UploadFailed(format!(
+                        "{file_name} already exists on the package index; bump the project's version, or pass --skip-existing to resume an interrupted publish"
+                    )));
+                }
+                return Err(Error::UploadFailed(format!(
+                    "{file_name}: {status} {}",
+                    upload_error_message(&text)
+                )));
+            }
+            Ok(it) => match it.error_for_status() {
+                Ok(_) => return Ok(UploadStatus::Uploaded),
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+        if attempt >= retries || !is_transient(&err) {
+            return Err(Error::NetworkError(err));
+        }
+        tokio::time::sleep(backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// Encode a multipart form with a file field named "content".
+fn multipart_body(
+    boundary: &str,
+    fields: &[(String, String)],
+    file_name: &str,
+    contents: &[u8],
+) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (name, value) in fields {
+        body.extend(format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+        ).as_bytes());
+    }
+    body.extend(format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"content\"; filename=\"{file_name}\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+    ).as_bytes());
+    body.extend(contents);
+    body.extend(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    body
+}
+
+/// Get the message from an upload's error response. PyPI's responses are HTML pages
+/// with the message in their title, and other indexes respond with plain text.
+fn upload_error_message(text: &str) -> String {
+    let message = text
+        .split_once("<title>")
+        .and_then(|(_, it)| it.split_once("</title>"))
+        .map_or(text, |(it, _)| it);
+
+    message
+        .trim()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Make a GET request, retrying with an exponential backoff when the failure is likely
+/// to be transient.
+async fn get(
+    client: &reqwest::Client,
+    url: &str,
+    retries: u32,
+) -> HuakResult<Vec<u8>> {
+    let mut attempt = 0;
+    loop {
+        let res = client
+            .get(url)
+            .send()
+            .await
+            .and_then(|it| it.error_for_status());
+        let err = match res {
+            Ok(it) => match it.bytes().await {
+                Ok(bytes) => return Ok(bytes.to_vec()),
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+        if attempt >= retries || !is_transient(&err) {
+            return Err(Error::NetworkError(err));
+        }
+        tokio::time::sleep(backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// Send a request and deserialize its JSON response, whatever its status.
+async fn send_json<T: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+) -> HuakResult<T> {
+    let bytes = request.send().await?.bytes().await?;
+
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Check if a request's error is worth retrying.
+fn is_transient(err: &reqwest::Error) -> bool {
+    match err.status() {
+        Some(status) => {
+            status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+        }
+        None => err.is_timeout() || err.is_connect() || err.is_body(),
+    }
+}
+
+/// Get the delay before the next retry of a request or operation.
+pub(crate) fn backoff(attempt: u32) -> Duration {
+    BACKOFF_BASE * 2u32.saturating_pow(attempt)
+}
+
+/// Read the certificates from a PEM file, such as a CA bundle.
+fn certificates_from_pem_file(path: &Path) -> HuakResult<Vec<Certificate>> {
+    let invalid = |e: &dyn std::fmt::Display| {
+        Error::HuakConfigurationError(format!(
+            "failed to load CA bundle {}: {e}",
+            path.display()
+        ))
+    };
+    let contents = std::fs::read_to_string(path).map_err(|e| invalid(&e))?;
+    let end = "-----END CERTIFICATE-----";
+    let certificates = contents
+        .split_inclusive(end)
+        .filter(|it| it.contains("-----BEGIN CERTIFICATE-----"))
+        .map(|it| Certificate::from_pem(it.as_bytes()).map_err(|e| invalid(&e)))
+        .collect::<HuakResult<Vec<_>>>()?;
+    if certificates.is_empty() {
+        return Err(invalid(&"no certificates found"));
+    }
+
+    Ok(certificates)
+}
+
+/// Get the `Proxy`s configured by environment variables.
+fn proxies_from_env() -> HuakResult<Vec<Proxy>> {
+    let var = |keys: [&str; 2]| {
+        keys.iter()
+            .find_map(|key| std::env::var(key).ok().filter(|it| !it.is_empty()))
+    };
+    let mut proxies = Vec::new();
+    if let Some(url) = var(["HTTPS_PROXY", "https_proxy"]) {
+        proxies.push(Proxy::https(url)?);
+    }
+    if let Some(url) = var(["HTTP_PROXY", "http_proxy"]) {
+        proxies.push(Proxy::http(url)?);
+    }
+    if let Some(url) = var(["ALL_PROXY", "all_proxy"]) {
+        proxies.push(Proxy::all(url)?);
+    }
+
+    Ok(proxies
+        .into_iter()
+        .map(|it| it.no_proxy(NoProxy::from_env()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+    use tempfile::tempdir;
+
+    /// Serve each response to one connection on a local port.
+    fn serve(responses: Vec<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 1024];
+                let _ = stream.read(&mut buf).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn test_download_all_retries() {
+        let url = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+        ]);
+        let dir = tempdir().unwrap();
+        let options = NetworkOptions {
+            parallelism: 1,
+            ..Default::default()
+        };
+        let client = Client::new(&options).unwrap();
+
+        let paths = client
+            .download_all(vec![Download {
+                url: format!("{url}/hello.txt"),
+                path: dir.path().join("hello.txt"),
+            }])
+            .unwrap();
+
+        assert_eq!(paths, vec![dir.path().join("hello.txt")]);
+        assert_eq!(std::fs::read_to_string(&paths[0]).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_upload_all_existing() {
+        let url = serve(vec![
+            "HTTP/1.1 400 Bad Request\r\nContent-Length: 43\r\nConnection: close\r\n\r\n<title>400 File already exists.</title>\r\n\r\n",
+        ]);
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mock-0.1.0.tar.gz");
+        std::fs::write(&path, "mock").unwrap();
+        let client = Client::new(&NetworkOptions::default()).unwrap();
+        let mut done = Vec::new();
+
+        let results = client.upload_all(
+            &format!("{url}/legacy/"),
+            ("__token__", "secret"),
+            vec![Upload {
+                path: path.clone(),
+                fields: vec![("name".to_string(), "mock".to_string())],
+            }],
+            true,
+            |it, _| done.push(it.path.clone()),
+        );
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(*results[0].as_ref().unwrap(), UploadStatus::Existing);
+        assert_eq!(done, vec![path]);
+    }
+
+    #[test]
+    fn test_upload_all_existing_fails() {
+        let url = serve(vec![
+            "HTTP/1.1 409 Conflict\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 403 Forbidden\r\nContent-Length: 20\r\nConnection: close\r\n\r\nFile already exists.",
+        ]);
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mock-0.1.0.tar.gz");
+        std::fs::write(&path, "mock").unwrap();
+        let client = Client::new(&NetworkOptions::default()).unwrap();
+        let upload = || Upload {
+            path: path.clone(),
+            fields: vec![("name".to_string(), "mock".to_string())],
+        };
+
+        let conflict = client.upload_all(
+            &format!("{url}/legacy/"),
+            ("__token__", "secret"),
+            vec![upload()],
+            false,
+            |_, _| (),
+        );
+        // Only 400 and 409 responses mean the file exists.
+        let forbidden = client.upload_all(
+            &format!("{url}/legacy/"),
+            ("__token__", "secret"),
+            vec![upload()],
+            true,
+            |_, _| (),
+        );
+
+        assert!(matches!(conflict[0], Err(Error::UploadFailed(_))));
+        assert!(matches!(forbidden[0], Err(Error::UploadFailed(_))));
+    }
+
+    #[test]
+    fn test_network_options_with_metadata() {
+        let metadata: Metadata = toml::from_str(
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "mock-project"
+
+[tool.huak.network]
+retries = 5
+operation-timeout = 600
+ca-bundle = "certs/corporate.pem"
+trusted-hosts = ["pypi.internal"]
+"#,
+        )
+        .unwrap();
+
+        let options =
+            NetworkOptions::default().with_metadata(&metadata).unwrap();
+
+        assert_eq!(options.retries, 5);
+        assert_eq!(options.timeout, DEFAULT_TIMEOUT);
+        assert_eq!(options.operation_timeout, Some(Duration::from_secs(600)));
+        assert_eq!(
+            options.ca_bundle(),
+            Some(PathBuf::from("certs/corporate.pem"))
+        );
+        assert_eq!(options.trusted_hosts, ["pypi.internal"]);
+    }
+
+    #[test]
+    fn test_invalid_ca_bundle() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bundle.pem");
+        std::fs::write(&path, "not a certificate").unwrap();
+        let options = NetworkOptions {
+            ca_bundle: Some(path),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            Client::new(&options),
+            Err(Error::HuakConfigurationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_not_found() {
+        let url = serve(vec![
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        let client = Client::new(&NetworkOptions::default()).unwrap();
+
+        let err = client.get_bytes(&url).unwrap_err();
+
+        assert_eq!(err.code(), 28);
+    }
+
+    #[test]
+    fn test_get_all() {
+        let url = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        let options = NetworkOptions {
+            parallelism: 1,
+            ..Default::default()
+        };
+        let client = Client::new(&options).unwrap();
+
+        let results = client.get_all(vec![
+            format!("{url}/hello.txt"),
+            format!("{url}/missing.txt"),
+        ]);
+
+        assert_eq!(results[0].as_ref().unwrap(), b"hello");
+        assert_eq!(results[1].as_ref().unwrap_err().code(), 28);
+    }
+}
@@ -4,7 +4,7 @@ use crate::{Config, HuakResult};
 
 pub fn activate_python_environment(config: &Config) -> HuakResult<()> {
     let workspace = config.workspace();
-    let python_env = workspace.current_python_environment()?;
+    let python_env = workspace.resolve_python_environment()?;
 
     if python_env.active() {
         return Ok(());
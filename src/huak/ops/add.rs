@@ -1,12 +1,29 @@
+use super::{
+    check_wheel_compatibility, info::package_extras, with_constraints,
+    yanked_releases,
+};
 use crate::{
+    cache::record_cache_use,
     dependency::{dependency_iter, Dependency},
-    Config, HuakResult, InstallOptions,
+    fs, index,
+    package::canonical_package_name,
+    wheel::{hex, verify_hash, WheelFilename},
+    Config, Error, HuakResult, InstallOptions,
 };
 use pep440_rs::VersionSpecifiers;
 use pep508_rs::VersionOrUrl;
-use std::str::FromStr;
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::{path::Path, str::FromStr};
 
 pub struct AddOptions {
+    /// Allow adding dependencies pinned to releases yanked from the package index.
+    pub allow_yanked: bool,
+    /// Only edit the pyproject.toml, leaving the Python environment untouched until the
+    /// next `huak install`.
+    pub no_sync: bool,
+    /// List the extras each dependency provides and choose which to add it with.
+    pub list_extras: bool,
     pub install_options: InstallOptions,
 }
 
@@ -16,8 +33,11 @@ pub fn add_project_dependencies(
     options: &AddOptions,
 ) -> HuakResult<()> {
     let workspace = config.workspace();
+    let _lock = workspace.lock()?;
+    let _journal = workspace.journal("add")?;
     let package = workspace.current_package()?;
     let mut metadata = workspace.current_local_metadata()?;
+    let dependencies = direct_references(dependencies, config)?;
 
     // Collect all dependencies that need to be added to the metadata file.
     let mut deps: Vec<Dependency> = dependency_iter(dependencies)
@@ -33,11 +53,24 @@ pub fn add_project_dependencies(
         return Ok(());
     }
 
-    let python_env = workspace.resolve_python_environment()?;
-    python_env.install_packages(&deps, &options.install_options, config)?;
+    if options.list_extras {
+        select_extras(&mut deps, config)?;
+    }
+    check_yanked_dependencies(&deps, options.allow_yanked, config)?;
+
+    let packages = if options.no_sync {
+        Vec::new()
+    } else {
+        let python_env = workspace.resolve_python_environment()?;
+        check_wheel_compatibility(&deps, &python_env, &metadata, config)?;
+        with_constraints(&metadata, &options.install_options, config, |it| {
+            python_env.install_packages(&deps, it, config)
+        })?;
+        // TODO: Only run if versions weren't provided.
+        python_env.installed_packages()?
+    };
 
     // If there's no version data then get the installed version and add to metadata file.
-    let packages = python_env.installed_packages()?; // TODO: Only run if versions weren't provided.
     for dep in deps.iter_mut() {
         if dep.requirement().version_or_url.is_none() {
             // TODO: Optimize this .find
@@ -73,8 +106,11 @@ pub fn add_project_optional_dependencies(
     options: &AddOptions,
 ) -> HuakResult<()> {
     let workspace = config.workspace();
+    let _lock = workspace.lock()?;
+    let _journal = workspace.journal("add")?;
     let package = workspace.current_package()?;
     let mut metadata = workspace.current_local_metadata()?;
+    let dependencies = direct_references(dependencies, config)?;
 
     // Collect all dependencies that need to be added.
     let mut deps = dependency_iter(dependencies)
@@ -90,11 +126,24 @@ pub fn add_project_optional_dependencies(
         return Ok(());
     };
 
-    let python_env = workspace.resolve_python_environment()?;
-    python_env.install_packages(&deps, &options.install_options, config)?;
+    if options.list_extras {
+        select_extras(&mut deps, config)?;
+    }
+    check_yanked_dependencies(&deps, options.allow_yanked, config)?;
+
+    let packages = if options.no_sync {
+        Vec::new()
+    } else {
+        let python_env = workspace.resolve_python_environment()?;
+        check_wheel_compatibility(&deps, &python_env, &metadata, config)?;
+        with_constraints(&metadata, &options.install_options, config, |it| {
+            python_env.install_packages(&deps, it, config)
+        })?;
+        // TODO: Only run if versions weren't provided.
+        python_env.installed_packages()?
+    };
 
     // If there's no version data then get the installed version and add to metadata file.
-    let packages = python_env.installed_packages()?; // TODO: Only run if versions weren't provided.
     for dep in deps.iter_mut() {
         if dep.requirement().version_or_url.is_none() {
             // TODO: Optimize this .find
@@ -128,10 +177,207 @@ pub fn add_project_optional_dependencies(
     Ok(())
 }
 
+/// Replace the archive files and archive URLs in `dependencies` with PEP 508 direct
+/// references pinned to the archives' sha256 hashes, such as
+/// `pkg @ file:///home/me/pkg-1.0-py3-none-any.whl#sha256=...`. Archives at URLs are
+/// downloaded to huak's cache to hash them, and a hash already in the URL has to match.
+/// Other dependencies are kept as they are.
+fn direct_references(
+    dependencies: &[String],
+    config: &Config,
+) -> HuakResult<Vec<String>> {
+    let mut references = Vec::new();
+    let mut uses = Vec::new();
+    for item in dependencies {
+        let (url, fragment) = match item.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (item.as_str(), None),
+        };
+        let is_url = url.starts_with("https://") || url.starts_with("http://");
+        let file_name = match is_url {
+            true => url.split('?').next().and_then(|it| it.rsplit('/').next()),
+            false => Path::new(url).file_name().and_then(|it| it.to_str()),
+        };
+        let Some((file_name, name)) =
+            file_name.and_then(|it| Some((it, archive_name(it)?)))
+        else {
+            references.push(item.clone());
+            continue;
+        };
+
+        let (url, path) = if is_url {
+            let dir =
+                fs::cache_dir()?.join(match file_name.ends_with(".whl") {
+                    true => "wheels",
+                    false => "sdists",
+                });
+            let path = dir.join(file_name);
+            let expected = fragment
+                .and_then(|it| it.strip_prefix("sha256="))
+                .map(|it| format!("sha256:{it}"));
+            let cached = match expected.as_ref() {
+                Some(it) => path.exists() && verify_hash(&path, it)?,
+                None => false,
+            };
+            if !cached {
+                let bytes = config.client()?.get_bytes(url)?;
+                std::fs::create_dir_all(&dir)?;
+                std::fs::write(&path, bytes)?;
+            }
+            uses.push((path.clone(), cached));
+            if let Some(it) = expected.as_ref() {
+                if !verify_hash(&path, it)? {
+                    std::fs::remove_file(&path)?;
+                    return Err(Error::HashMismatch(path));
+                }
+            }
+            (url.to_string(), path)
+        } else {
+            // A requirement like `pkg.whl` names a package rather than a file.
+            if !Path::new(url).is_file() {
+                references.push(item.clone());
+                continue;
+            }
+            let path = Path::new(url).canonicalize()?;
+            let url = Url::from_file_path(&path).map_err(|_| {
+                Error::InternalError(format!(
+                    "failed to make a URL of {}",
+                    path.display()
+                ))
+            })?;
+            (url.to_string(), path)
+        };
+        let hash = hex(&Sha256::digest(std::fs::read(&path)?));
+        references.push(format!("{name} @ {url}#sha256={hash}"));
+    }
+    if !uses.is_empty() {
+        record_cache_use(
+            uses.iter().map(|(path, hit)| (path.as_path(), *hit)),
+        )?;
+    }
+
+    Ok(references)
+}
+
+/// Get the name of the package an archive's file name is for, or `None` if it isn't a
+/// wheel or sdist.
+fn archive_name(file_name: &str) -> Option<String> {
+    let name = match WheelFilename::from_str(file_name) {
+        Ok(it) => it.name,
+        Err(_) => {
+            let stem = [".tar.gz", ".zip"]
+                .iter()
+                .find_map(|it| file_name.strip_suffix(it))?;
+            stem.rsplit_once('-')?.0.to_string()
+        }
+    };
+
+    canonical_package_name(&name).ok()
+}
+
+/// List the extras each dependency's latest release provides and let the user choose
+/// which to add it with. Extras the dependency was given with are kept.
+fn select_extras(deps: &mut [Dependency], config: &Config) -> HuakResult<()> {
+    let client = config.client()?;
+    let mut terminal = config.terminal();
+    for dep in deps.iter_mut() {
+        let Some(info) = index::project_info(&client, dep.name())? else {
+            return Err(Error::PackageNotFound(dep.name().to_string()));
+        };
+        let extras = package_extras(
+            info.provides_extra.as_deref().unwrap_or_default(),
+            info.requires_dist.as_deref().unwrap_or_default(),
+        )?;
+        if extras.is_empty() {
+            terminal.print_warning(format!(
+                "{} doesn't provide any extras",
+                dep.name()
+            ))?;
+            continue;
+        }
+
+        for (i, (extra, requires)) in extras.iter().enumerate() {
+            terminal.print_stdout(&format!(
+                "{:>3}. {extra} ({})\n",
+                i + 1,
+                requires.join(", ")
+            ))?;
+        }
+        let answer = terminal.prompt(
+            format!(
+                "Extras to add {} with (numbers separated by spaces)",
+                dep.name()
+            ),
+            None,
+        )?;
+        let mut selected = dep.requirement().extras.clone().unwrap_or_default();
+        for it in answer.split(|c: char| c == ',' || c.is_whitespace()) {
+            if it.is_empty() {
+                continue;
+            }
+            match it.parse::<usize>() {
+                Ok(n) if (1..=extras.len()).contains(&n) => {
+                    let extra = &extras[n - 1].0;
+                    if !selected.contains(extra) {
+                        selected.push(extra.clone());
+                    }
+                }
+                _ => {
+                    return Err(Error::HuakConfigurationError(format!(
+                        "{it:?} isn't one of the listed extras"
+                    )))
+                }
+            }
+        }
+        if !selected.is_empty() {
+            dep.requirement_mut().extras = Some(selected);
+        }
+    }
+
+    Ok(())
+}
+
+/// Refuse to add dependencies pinned to releases yanked from the package index unless
+/// `allow_yanked` is set, in which case they're only warned about.
+fn check_yanked_dependencies(
+    deps: &[Dependency],
+    allow_yanked: bool,
+    config: &Config,
+) -> HuakResult<()> {
+    let releases = deps
+        .iter()
+        .filter_map(|dep| Some((dep.name().to_string(), dep.pinned_version()?)))
+        .collect::<Vec<_>>();
+    let yanked = yanked_releases(&releases, config)?;
+    if yanked.is_empty() {
+        return Ok(());
+    }
+
+    if !allow_yanked {
+        return Err(Error::YankedRelease(
+            yanked
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ));
+    }
+
+    let mut terminal = config.terminal();
+    for it in yanked {
+        terminal.print_warning(format!(
+            "adding {it}, which was yanked from the package index"
+        ))?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        dist_info::{scan_site_packages, DistInfo},
         fs,
         ops::{test_config, test_venv},
         test_resources_dir_path, Verbosity,
@@ -153,6 +399,9 @@ mod tests {
         test_venv(&ws);
         let venv = ws.resolve_python_environment().unwrap();
         let options = AddOptions {
+            allow_yanked: false,
+            no_sync: false,
+            list_extras: false,
             install_options: InstallOptions { values: None },
         };
 
@@ -166,6 +415,72 @@ mod tests {
         assert!(metadata.metadata().contains_dependency(&dep).unwrap());
     }
 
+    #[test]
+    fn test_add_project_dependencies_requested() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let ws = config.workspace();
+        test_venv(&ws);
+        let venv = ws.resolve_python_environment().unwrap();
+        let options = AddOptions {
+            allow_yanked: false,
+            no_sync: false,
+            list_extras: false,
+            install_options: InstallOptions { values: None },
+        };
+        let requested = || {
+            scan_site_packages(venv.site_packages_dir_path())
+                .unwrap()
+                .iter()
+                .find(|it| it.name().eq_ignore_ascii_case("six"))
+                .map(DistInfo::requested)
+        };
+        // Install six as if another package depended on it.
+        venv.install_packages(&["six"], &options.install_options, &config)
+            .unwrap();
+        venv.set_requested(&["six"], false).unwrap();
+
+        add_project_dependencies(&[String::from("six")], &config, &options)
+            .unwrap();
+
+        assert_eq!(requested(), Some(true));
+    }
+
+    #[test]
+    fn test_add_project_dependencies_no_sync() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let options = AddOptions {
+            allow_yanked: false,
+            no_sync: true,
+            list_extras: false,
+            install_options: InstallOptions { values: None },
+        };
+
+        add_project_dependencies(&[String::from("ruff")], &config, &options)
+            .unwrap();
+
+        let dep = Dependency::from_str("ruff").unwrap();
+        let metadata = config.workspace().current_local_metadata().unwrap();
+
+        assert!(metadata.metadata().contains_dependency(&dep).unwrap());
+        assert!(!root.join(".venv").exists());
+    }
+
     #[test]
     fn test_add_optional_project_dependencies() {
         let dir = tempdir().unwrap();
@@ -182,6 +497,9 @@ mod tests {
         test_venv(&ws);
         let venv = ws.resolve_python_environment().unwrap();
         let options = AddOptions {
+            allow_yanked: false,
+            no_sync: false,
+            list_extras: false,
             install_options: InstallOptions { values: None },
         };
 
@@ -202,4 +520,42 @@ mod tests {
             .contains_optional_dependency(&dep, "dev")
             .unwrap());
     }
+
+    #[test]
+    fn test_direct_references() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let wheel = root.join("My_Pkg-1.0-py3-none-any.whl");
+        std::fs::write(&wheel, "wheel").unwrap();
+        let dependencies = [
+            wheel.display().to_string(),
+            "click==8.1.7".to_string(),
+            "pkg.whl".to_string(),
+        ];
+
+        let references = direct_references(&dependencies, &config).unwrap();
+        let url = Url::from_file_path(wheel.canonicalize().unwrap()).unwrap();
+        let hash = hex(&Sha256::digest("wheel"));
+
+        assert_eq!(references[0], format!("My-Pkg @ {url}#sha256={hash}"));
+        assert_eq!(references[1..], dependencies[1..]);
+        let dep = Dependency::from_str(&references[0]).unwrap();
+        assert_eq!(dep.name(), "My-Pkg");
+    }
+
+    #[test]
+    fn test_archive_name() {
+        assert_eq!(
+            archive_name("my_pkg-1.0-py3-none-any.whl").as_deref(),
+            Some("my-pkg")
+        );
+        assert_eq!(
+            archive_name("my_pkg-1.0.tar.gz").as_deref(),
+            Some("my-pkg")
+        );
+        assert_eq!(archive_name("my-pkg-1.0.zip").as_deref(), Some("my-pkg"));
+        assert_eq!(archive_name("pkg.whl"), None);
+        assert_eq!(archive_name("click"), None);
+    }
 }
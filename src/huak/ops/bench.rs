@@ -0,0 +1,121 @@
+use super::{make_venv_command, op_env_vars};
+use crate::{dependency::Dependency, Config, HuakResult, InstallOptions};
+use std::{process::Command, str::FromStr};
+
+const BENCHMARKS_DIR: &str = ".huak/benchmarks";
+const DEFAULT_REGRESSION_THRESHOLD: f64 = 10.0;
+
+pub struct BenchOptions {
+    /// A values vector of benchmark options typically used for passing on arguments.
+    pub values: Option<Vec<String>>,
+    /// A name to save the run's results as a baseline with. Results are saved with an
+    /// automatic name otherwise.
+    pub save: Option<String>,
+    /// A saved baseline to compare the run against. The baseline can be its name or
+    /// its number (see `pytest-benchmark compare --list`).
+    pub compare: Option<String>,
+    /// The percentage the mean can regress by before the comparison fails.
+    pub threshold: Option<f64>,
+    pub install_options: InstallOptions,
+}
+
+/// Run the project's benchmarks with `pytest-benchmark`, saving results as baselines under
+/// ".huak/benchmarks".
+pub fn bench_project(
+    config: &Config,
+    options: &BenchOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let package = workspace.current_package()?;
+    let mut metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+
+    // Install `pytest-benchmark` if it isn't already installed.
+    let bench_dep = Dependency::from_str("pytest-benchmark")?;
+    if !python_env.contains_module(bench_dep.name())? {
+        python_env.install_packages(
+            &[&bench_dep],
+            &options.install_options,
+            config,
+        )?;
+    }
+
+    // Add the installed `pytest-benchmark` package to the metadata file if it isn't already there.
+    if !metadata.metadata().contains_dependency_any(&bench_dep)? {
+        for pkg in python_env
+            .installed_packages()?
+            .iter()
+            .filter(|pkg| pkg.name() == bench_dep.name())
+        {
+            metadata.metadata_mut().add_optional_dependency(
+                Dependency::from_str(&pkg.to_string())?,
+                "dev",
+            );
+        }
+    }
+
+    if package.metadata() != metadata.metadata() {
+        metadata.write_file()?;
+    }
+
+    // Run `pytest` with only the benchmarks and the package directory added to the
+    // command's `PYTHONPATH`.
+    let mut cmd = Command::new(python_env.python_path());
+    make_venv_command(&mut cmd, &python_env, config)?;
+    cmd.envs(op_env_vars(&metadata, "bench")?);
+    let python_path = if workspace.root().join("src").exists() {
+        workspace.root().join("src")
+    } else {
+        workspace.root().to_path_buf()
+    };
+    let storage = workspace.root().join(BENCHMARKS_DIR);
+    cmd.args(["-m", "pytest", "--benchmark-only"])
+        .arg(format!("--benchmark-storage=file://{}", storage.display()))
+        .args(bench_args(options))
+        .args(options.values.iter().flatten())
+        .env("PYTHONPATH", python_path)
+        .current_dir(workspace.root());
+
+    config.terminal().run_command(&mut cmd)
+}
+
+/// Get the `pytest-benchmark` arguments for saving and comparing baselines.
+fn bench_args(options: &BenchOptions) -> Vec<String> {
+    let mut args = vec![match options.save.as_ref() {
+        Some(name) => format!("--benchmark-save={name}"),
+        None => "--benchmark-autosave".to_string(),
+    }];
+    if let Some(baseline) = options.compare.as_ref() {
+        let threshold =
+            options.threshold.unwrap_or(DEFAULT_REGRESSION_THRESHOLD);
+        args.push(format!("--benchmark-compare={baseline}"));
+        args.push(format!("--benchmark-compare-fail=mean:{threshold}%"));
+    }
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_args() {
+        let options = BenchOptions {
+            values: None,
+            save: None,
+            compare: Some("0001".to_string()),
+            threshold: Some(5.0),
+            install_options: InstallOptions { values: None },
+        };
+
+        assert_eq!(
+            bench_args(&options),
+            vec![
+                "--benchmark-autosave",
+                "--benchmark-compare=0001",
+                "--benchmark-compare-fail=mean:5%"
+            ]
+        );
+    }
+}
@@ -1,60 +1,244 @@
-use super::make_venv_command;
-use crate::{dependency::Dependency, Config, HuakResult, InstallOptions};
-use std::{process::Command, str::FromStr};
+use super::{
+    make_venv_command,
+    members::{dependency_order, python_workspace_members},
+    op_env_vars, tool_python_environment,
+};
+use crate::{
+    metadata::LocalMetadata,
+    package::{importable_package_name, Package},
+    sys::Terminal,
+    Config, Error, HuakResult, InstallOptions,
+};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use termcolor::Color;
+use zip::ZipArchive;
 
 pub struct BuildOptions {
     /// A values vector of build options typically used for passing on arguments.
     pub values: Option<Vec<String>>,
     pub install_options: InstallOptions,
+    /// Check that the built wheel contains more than its metadata.
+    pub check_contents: bool,
+    /// Build each of the workspace's members into the root's dist directory.
+    pub workspace: bool,
 }
 
 pub fn build_project(
     config: &Config,
     options: &BuildOptions,
 ) -> HuakResult<()> {
+    if options.workspace {
+        return build_workspace_members(config, options);
+    }
+
     let workspace = config.workspace();
     let package = workspace.current_package()?;
     let mut metadata = workspace.current_local_metadata()?;
     let python_env = workspace.resolve_python_environment()?;
+    let build_env = tool_python_environment(
+        &["build"],
+        &python_env,
+        &mut metadata,
+        &options.install_options,
+        config,
+    )?;
 
-    // Install the `build` package if it isn't already installed.
-    let build_dep = Dependency::from_str("build")?;
-    if !python_env.contains_module(build_dep.name())? {
-        python_env.install_packages(
-            &[&build_dep],
-            &options.install_options,
-            config,
+    // Run `build`.
+    let mut cmd = Command::new(build_env.python_path());
+    let mut args = vec!["-m", "build"];
+    if let Some(it) = options.values.as_ref() {
+        args.extend(it.iter().map(|item| item.as_str()));
+    }
+    make_venv_command(&mut cmd, &build_env, config)?;
+    cmd.envs(op_env_vars(&metadata, "build")?);
+    cmd.args(args).current_dir(workspace.root());
+
+    let mut terminal = config.terminal();
+    terminal.run_command(&mut cmd)?;
+
+    if options.check_contents {
+        check_wheel_contents(
+            &workspace.root().join("dist"),
+            &package,
+            &mut terminal,
         )?;
     }
 
-    // Add the installed `build` package to the metadata file.
-    if !metadata.metadata().contains_dependency_any(&build_dep)? {
-        for pkg in python_env
-            .installed_packages()?
+    Ok(())
+}
+
+/// Build each of the workspace's Python members into its own directory of the root's
+/// dist directory, named after the member's project. Members are built after the
+/// members they depend on, and the artifacts produced are listed once all are built.
+fn build_workspace_members(
+    config: &Config,
+    options: &BuildOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut metadata = workspace.current_local_metadata()?;
+    let members =
+        python_workspace_members(workspace.root(), metadata.metadata())?
             .iter()
-            .filter(|pkg| pkg.name() == build_dep.name())
-        {
-            metadata.metadata_mut().add_optional_dependency(
-                Dependency::from_str(&pkg.to_string())?,
-                "dev",
-            );
+            .map(|it| LocalMetadata::new(it.join("pyproject.toml")))
+            .collect::<HuakResult<Vec<_>>>()?;
+    if members.is_empty() {
+        return Err(Error::HuakConfigurationError(
+            "no Python workspace members are listed in [tool.huak.workspace]"
+                .to_string(),
+        ));
+    }
+    let python_env = workspace.resolve_python_environment()?;
+    let build_env = tool_python_environment(
+        &["build"],
+        &python_env,
+        &mut metadata,
+        &options.install_options,
+        config,
+    )?;
+
+    let dist = workspace.root().join("dist");
+    let mut terminal = config.terminal();
+    let mut artifacts = Vec::new();
+    for index in dependency_order(&members)? {
+        let member = &members[index];
+        let package = Package::from(member.metadata().clone());
+        let root = member.path().parent().unwrap_or(workspace.root());
+        // Stale artifacts are cleared so that only this build's are collected.
+        let out_dir = dist.join(package.name());
+        if out_dir.exists() {
+            std::fs::remove_dir_all(&out_dir)?;
+        }
+
+        terminal.print_custom(
+            "Building",
+            package.name(),
+            Color::Green,
+            true,
+        )?;
+        let mut cmd = Command::new(build_env.python_path());
+        make_venv_command(&mut cmd, &build_env, config)?;
+        cmd.envs(op_env_vars(member, "build")?);
+        cmd.args(["-m", "build", "--outdir"])
+            .arg(&out_dir)
+            .args(options.values.iter().flatten())
+            .current_dir(root);
+        terminal.run_command(&mut cmd)?;
+
+        if options.check_contents {
+            check_wheel_contents(&out_dir, &package, &mut terminal)?;
         }
+        let mut files = std::fs::read_dir(&out_dir)?
+            .map(|it| it.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        files.sort();
+        artifacts.extend(files);
     }
 
-    if package.metadata() != metadata.metadata() {
-        metadata.write_file()?;
+    for it in artifacts.iter() {
+        terminal.print_custom(
+            "Built",
+            it.strip_prefix(workspace.root()).unwrap_or(it).display(),
+            Color::Green,
+            true,
+        )?;
     }
+    terminal.print_custom(
+        "Finished",
+        format!(
+            "{} artifact(s) from {} member(s) in dist",
+            artifacts.len(),
+            members.len()
+        ),
+        Color::Green,
+        true,
+    )
+}
 
-    // Run `build`.
-    let mut cmd = Command::new(python_env.python_path());
-    let mut args = vec!["-m", "build"];
-    if let Some(it) = options.values.as_ref() {
-        args.extend(it.iter().map(|item| item.as_str()));
+/// Check that the wheel built for a package in a dist directory contains more than its
+/// metadata.
+fn check_wheel_contents(
+    dist: &Path,
+    package: &Package,
+    terminal: &mut Terminal,
+) -> HuakResult<()> {
+    let wheel =
+        wheel_path(dist, package.name(), &package.version().to_string())?
+            .ok_or_else(|| {
+                Error::CheckFailed(format!(
+                    "a wheel for {} {} wasn't found in {}",
+                    package.name(),
+                    package.version(),
+                    dist.display()
+                ))
+            })?;
+    let files = wheel_files(&wheel)?;
+    if files.is_empty() {
+        return Err(Error::CheckFailed(format!(
+            "{} only contains its metadata; check the build backend's \
+            package configuration",
+            file_name(&wheel)
+        )));
     }
-    make_venv_command(&mut cmd, &python_env)?;
-    cmd.args(args).current_dir(workspace.root());
 
-    config.terminal().run_command(&mut cmd)
+    terminal.print_custom(
+        "Checked",
+        format!("{} file(s) in {}", files.len(), file_name(&wheel)),
+        Color::Green,
+        true,
+    )
+}
+
+/// Find the built wheel for a version of a project in a dist directory.
+fn wheel_path(
+    dist: &Path,
+    name: &str,
+    version: &str,
+) -> HuakResult<Option<PathBuf>> {
+    if !dist.exists() {
+        return Ok(None);
+    }
+    let prefix = format!(
+        "{}-{version}-",
+        importable_package_name(name)?.to_lowercase()
+    );
+    for entry in std::fs::read_dir(dist)? {
+        let path = entry?.path();
+        let name = file_name(&path).to_lowercase();
+        if name.starts_with(&prefix) && name.ends_with(".whl") {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Get the files a wheel installs other than its .dist-info metadata.
+fn wheel_files(path: &Path) -> HuakResult<Vec<String>> {
+    let archive = ZipArchive::new(File::open(path)?)?;
+    let files = archive
+        .file_names()
+        .filter(|it| {
+            !it.ends_with('/')
+                && !it
+                    .split('/')
+                    .next()
+                    .unwrap_or_default()
+                    .ends_with(".dist-info")
+        })
+        .map(ToString::to_string)
+        .collect();
+
+    Ok(files)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|it| it.to_string_lossy().to_string())
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
@@ -83,8 +267,51 @@ mod tests {
         let options = BuildOptions {
             values: None,
             install_options: InstallOptions { values: None },
+            check_contents: false,
+            workspace: false,
         };
 
         build_project(&config, &options).unwrap();
     }
+
+    #[test]
+    fn test_wheel_files() {
+        let dir = tempdir().unwrap();
+        let dist = dir.path().join("dist");
+        std::fs::create_dir_all(&dist).unwrap();
+        let write_wheel = |name: &str, files: &[&str]| {
+            let mut zip =
+                zip::ZipWriter::new(File::create(dist.join(name)).unwrap());
+            for file in files {
+                zip.start_file(*file, zip::write::FileOptions::default())
+                    .unwrap();
+            }
+            zip.finish().unwrap();
+        };
+        write_wheel(
+            "mock_project-0.0.1-py3-none-any.whl",
+            &[
+                "mock_project/__init__.py",
+                "mock_project-0.0.1.dist-info/METADATA",
+            ],
+        );
+        write_wheel(
+            "empty_project-0.0.1-py3-none-any.whl",
+            &["empty_project-0.0.1.dist-info/METADATA"],
+        );
+
+        let wheel =
+            wheel_path(&dist, "mock-project", "0.0.1").unwrap().unwrap();
+        assert_eq!(
+            wheel_files(&wheel).unwrap(),
+            vec!["mock_project/__init__.py"]
+        );
+        let wheel = wheel_path(&dist, "Empty.Project", "0.0.1")
+            .unwrap()
+            .unwrap();
+        assert!(wheel_files(&wheel).unwrap().is_empty());
+        assert!(wheel_path(&dist, "mock-project", "0.0.2")
+            .unwrap()
+            .is_none());
+    }
 }
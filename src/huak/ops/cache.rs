@@ -0,0 +1,147 @@
+use crate::{
+    cache::{
+        cache_entries, prune_cache_entries, CacheAge, CacheEntry, CachePolicy,
+        CacheSize,
+    },
+    changelog::civil_date,
+    sys::Table,
+    Config, Error, HuakResult,
+};
+use indexmap::IndexMap;
+use termcolor::Color;
+
+pub struct CacheOptions {
+    /// Remove the least recently used entries until the cache fits in this size instead of
+    /// `HUAK_CACHE_MAX_SIZE`.
+    pub max_size: Option<CacheSize>,
+    /// Remove entries that haven't been used for this long instead of `HUAK_CACHE_MAX_AGE`.
+    pub max_age: Option<CacheAge>,
+    /// Print the statistics as JSON.
+    pub json: bool,
+}
+
+/// Remove entries from huak's cache according to its `CachePolicy`. Limits passed with
+/// the `CacheOptions` take precedence over the ones read from the environment.
+pub fn prune_cache(config: &Config, options: &CacheOptions) -> HuakResult<()> {
+    let policy = CachePolicy::from_env()?;
+    let policy = CachePolicy {
+        max_size: options.max_size.or(policy.max_size),
+        max_age: options.max_age.or(policy.max_age),
+    };
+    if policy.is_empty() {
+        return Err(Error::HuakConfigurationError(
+            "no cache limits are configured; pass --max-size or --max-age, or set HUAK_CACHE_MAX_SIZE or HUAK_CACHE_MAX_AGE"
+                .to_string(),
+        ));
+    }
+
+    let summary = prune_cache_entries(&policy, &[])?;
+    config.terminal().print_custom(
+        "Pruned",
+        format!("{} cache entries ({})", summary.entries, summary.size),
+        Color::Green,
+        true,
+    )
+}
+
+/// Print huak's cache usage by package: the disk space used, the rate entries were reused
+/// at rather than downloaded or created, and when they were last used.
+pub fn print_cache_stats(
+    config: &Config,
+    options: &CacheOptions,
+) -> HuakResult<()> {
+    let mut terminal = config.terminal();
+    let entries = cache_entries()?;
+
+    // Entries are sorted most recently used first, so packages are too.
+    let mut packages: IndexMap<String, Vec<&CacheEntry>> = IndexMap::new();
+    for it in entries.iter() {
+        packages.entry(it.package.clone()).or_default().push(it);
+    }
+    let stats = packages
+        .iter()
+        .map(|(name, entries)| PackageStats::new(name, entries))
+        .collect::<Vec<_>>();
+
+    if options.json {
+        let value = stats
+            .iter()
+            .map(|it| {
+                serde_json::json!({
+                    "package": it.name,
+                    "entries": it.entries,
+                    "size": it.size.0,
+                    "hits": it.hits,
+                    "misses": it.misses,
+                    "last_used": it.last_used,
+                })
+            })
+            .collect::<Vec<_>>();
+        return terminal.print_stdout(&format!(
+            "{}\n",
+            serde_json::to_string_pretty(&value)?
+        ));
+    }
+
+    if stats.is_empty() {
+        return terminal.print_custom(
+            "Cache",
+            "huak's cache is empty",
+            Color::Green,
+            true,
+        );
+    }
+
+    let mut table =
+        Table::new(["package", "entries", "size", "hit rate", "last used"]);
+    for it in stats.iter() {
+        let hit_rate = match it.hits + it.misses {
+            0 => "-".to_string(),
+            uses => format!("{}%", it.hits * 100 / uses),
+        };
+        table.push_row([
+            it.name.clone(),
+            it.entries.to_string(),
+            it.size.to_string(),
+            hit_rate,
+            civil_date(it.last_used / 86400),
+        ]);
+    }
+    table.set_summary(
+        "Found",
+        format!(
+            "{} cache entries ({})",
+            entries.len(),
+            CacheSize(entries.iter().map(|it| it.size).sum())
+        ),
+    );
+
+    terminal.print_table(&table)
+}
+
+/// The `CacheEntry`s of a package summed up.
+struct PackageStats {
+    name: String,
+    entries: usize,
+    size: CacheSize,
+    hits: u64,
+    misses: u64,
+    last_used: u64,
+}
+
+impl PackageStats {
+    fn new(name: &str, entries: &[&CacheEntry]) -> PackageStats {
+        PackageStats {
+            name: name.to_string(),
+            entries: entries.len(),
+            size: CacheSize(entries.iter().map(|it| it.size).sum()),
+            hits: entries.iter().map(|it| it.hits).sum(),
+            misses: entries.iter().map(|it| it.misses).sum(),
+            last_used: entries
+                .iter()
+                .map(|it| it.last_used)
+                .max()
+                .unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    changelog::{
+        changelog_section, release_heading, today, update_changelog,
+        ChangelogConfig, UNRELEASED_HEADING,
+    },
+    git, Config, HuakResult,
+};
+use termcolor::Color;
+
+pub struct ChangelogOptions {
+    /// The version to write a release section for. Defaults to the "Unreleased" section.
+    pub release: Option<String>,
+    /// Print the section instead of writing it to CHANGELOG.md.
+    pub dry_run: bool,
+}
+
+/// Write the CHANGELOG.md section for the pending release from the commits since the
+/// last tag, grouped by `[tool.huak.changelog]`. Regenerating a section replaces it.
+pub fn update_project_changelog(
+    config: &Config,
+    options: &ChangelogOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let mut terminal = config.terminal();
+    let root = workspace.root();
+
+    let tag = git::latest_tag(root)?;
+    let messages = git::commit_messages(root, tag.as_deref())?;
+    let heading = match options.release.as_ref() {
+        Some(it) => release_heading(it, &today()),
+        None => UNRELEASED_HEADING.to_string(),
+    };
+    let section = changelog_section(
+        &heading,
+        &messages,
+        &ChangelogConfig::from_metadata(metadata.metadata())?,
+    )?;
+    if options.dry_run {
+        return terminal.print_stdout(&section);
+    }
+
+    let path = root.join("CHANGELOG.md");
+    let contents = std::fs::read_to_string(&path).ok();
+    std::fs::write(&path, update_changelog(contents.as_deref(), &section))?;
+    terminal.print_custom(
+        "Updated",
+        format!(
+            "CHANGELOG.md from {} commit(s) since {}",
+            messages.len(),
+            tag.as_deref().unwrap_or("the first commit")
+        ),
+        Color::Green,
+        true,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use git2::{Repository, Signature};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_update_project_changelog() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project").canonicalize().unwrap();
+        let repo = Repository::init(&root).unwrap();
+        let mut git_config = repo.config().unwrap();
+        git_config.set_str("user.name", "huak").unwrap();
+        git_config
+            .set_str("user.email", "huak@example.com")
+            .unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let signature = Signature::now("huak", "huak@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        git::tag_head(&root, "v0.0.1", "Release 0.0.1").unwrap();
+        std::fs::write(root.join("a.py"), "").unwrap();
+        git::commit_paths(&root, &[root.join("a.py")], "docs: add a").unwrap();
+        let path = root.join("pyproject.toml");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(
+            &path,
+            format!("{contents}\n[tool.huak.changelog.groups]\nDocumentation = [\"docs\"]\n"),
+        )
+        .unwrap();
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let options = ChangelogOptions {
+            release: None,
+            dry_run: false,
+        };
+
+        update_project_changelog(&config, &options).unwrap();
+
+        let changelog =
+            std::fs::read_to_string(root.join("CHANGELOG.md")).unwrap();
+        assert!(changelog
+            .ends_with("## [Unreleased]\n\n### Documentation\n\n- add a\n"));
+    }
+}
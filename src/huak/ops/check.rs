@@ -0,0 +1,461 @@
+use crate::{
+    classifiers::is_known_classifier,
+    index,
+    metadata::Metadata,
+    schema::{tool_table_problems, SchemaIssue},
+    Config, Error, HuakResult,
+};
+use pyproject_toml::ReadMe;
+use regex::Regex;
+use std::path::Path;
+use termcolor::Color;
+
+/// The top-level categories of trove classifiers (https://pypi.org/classifiers/).
+const CLASSIFIER_CATEGORIES: [&str; 11] = [
+    "Development Status",
+    "Environment",
+    "Framework",
+    "Intended Audience",
+    "License",
+    "Natural Language",
+    "Operating System",
+    "Private",
+    "Programming Language",
+    "Topic",
+    "Typing",
+];
+
+/// Commonly used SPDX license identifiers. Other identifiers are reported as warnings
+/// since they may still be valid.
+const SPDX_LICENSE_IDS: [&str; 40] = [
+    "0BSD",
+    "AFL-3.0",
+    "AGPL-3.0-only",
+    "AGPL-3.0-or-later",
+    "Apache-2.0",
+    "Artistic-2.0",
+    "BSD-1-Clause",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "BSD-3-Clause-Clear",
+    "BSL-1.0",
+    "CC-BY-4.0",
+    "CC-BY-SA-4.0",
+    "CC0-1.0",
+    "CDDL-1.0",
+    "EPL-1.0",
+    "EPL-2.0",
+    "EUPL-1.2",
+    "GPL-2.0-only",
+    "GPL-2.0-or-later",
+    "GPL-3.0-only",
+    "GPL-3.0-or-later",
+    "ISC",
+    "LGPL-2.1-only",
+    "LGPL-2.1-or-later",
+    "LGPL-3.0-only",
+    "LGPL-3.0-or-later",
+    "MIT",
+    "MIT-0",
+    "MPL-1.1",
+    "MPL-2.0",
+    "MS-PL",
+    "NCSA",
+    "OFL-1.1",
+    "PSF-2.0",
+    "Python-2.0",
+    "Unicode-3.0",
+    "Unlicense",
+    "WTFPL",
+    "Zlib",
+];
+
+/// Common SPDX license exceptions used with `WITH`.
+const SPDX_EXCEPTION_IDS: [&str; 5] = [
+    "Classpath-exception-2.0",
+    "GCC-exception-3.1",
+    "LLVM-exception",
+    "OCaml-LGPL-linking-exception",
+    "Qt-LGPL-exception-1.1",
+];
+
+pub struct CheckOptions {
+    /// Warn if the project's name is already taken on the package index.
+    pub check_name: bool,
+    /// Check the `[tool.*]` tables huak has schemas for.
+    pub schema: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A problem found in the project's metadata. Errors would make the project's
+/// distributions invalid, while warnings are recommendations for publishing.
+enum Problem {
+    Error(String),
+    Warning(String),
+}
+
+/// Check the project's pyproject.toml against PEP 621 before it's published: required
+/// and dynamic fields, the readme and license files, the license expression, and the
+/// classifiers. The package index can also be checked for the project's name, and the
+/// huak, mypy, pytest, and ruff tool tables against their bundled schemas.
+pub fn check_project(
+    config: &Config,
+    options: &CheckOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let mut terminal = config.terminal();
+
+    let mut problems = metadata_problems(metadata.metadata(), workspace.root());
+    if options.check_name {
+        let name = metadata.metadata().project_name();
+        match config
+            .client()
+            .and_then(|client| index::project_exists(&client, name))
+        {
+            Ok(true) => problems.push(Problem::Warning(format!(
+                "{name} is already taken on the package index; choose another name unless you own it"
+            ))),
+            Ok(false) => {}
+            Err(e) => problems.push(Problem::Warning(format!(
+                "couldn't check if {name} is available: {e}"
+            ))),
+        }
+    }
+    if options.schema {
+        let contents = std::fs::read_to_string(metadata.path())?;
+        for it in tool_table_problems(metadata.metadata(), &contents) {
+            let message = format!(
+                "{}:{}:{}: {it}",
+                metadata.path().display(),
+                it.line,
+                it.column
+            );
+            problems.push(match it.issue {
+                SchemaIssue::UnknownKey(_) => Problem::Warning(message),
+                SchemaIssue::InvalidType(_) => Problem::Error(message),
+            });
+        }
+    }
+
+    let mut errors = 0;
+    for problem in problems.iter() {
+        match problem {
+            Problem::Error(it) => {
+                errors += 1;
+                terminal.print_custom("Invalid", it, Color::Red, true)?;
+            }
+            Problem::Warning(it) => terminal.print_warning(it)?,
+        }
+    }
+    if errors > 0 {
+        return Err(Error::CheckFailed(format!(
+            "{errors} problem(s) found in {}",
+            metadata.path().display()
+        )));
+    }
+
+    terminal.print_custom(
+        "Checked",
+        format!(
+            "{} ({} warning(s))",
+            metadata.path().display(),
+            problems.len()
+        ),
+        Color::Green,
+        true,
+    )
+}
+
+/// Get the problems with a project's `[project]` table. Paths are relative to `root`.
+fn metadata_problems(metadata: &Metadata, root: &Path) -> Vec<Problem> {
+    let project = metadata.project();
+    let mut problems = Vec::new();
+    let error = |it: String| Problem::Error(it);
+    let warning = |it: String| Problem::Warning(it);
+
+    let name = Regex::new(r"(?i)^([A-Z0-9]|[A-Z0-9][A-Z0-9._-]*[A-Z0-9])$")
+        .expect("name regex should be valid");
+    if !name.is_match(&project.name) {
+        problems.push(error(format!(
+            "name {:?} must start and end with a letter or digit and only contain letters, digits, '-', '_', and '.'",
+            project.name
+        )));
+    }
+
+    // Fields are either set statically or listed as `dynamic`.
+    let dynamic = project.dynamic.clone().unwrap_or_default();
+    let is_set = |field: &str| match field {
+        "version" => project.version.is_some(),
+        "description" => project.description.is_some(),
+        "readme" => project.readme.is_some(),
+        "requires-python" => project.requires_python.is_some(),
+        "license" => {
+            project.license.is_some() || project.license_expression.is_some()
+        }
+        "authors" => project.authors.is_some(),
+        "maintainers" => project.maintainers.is_some(),
+        "keywords" => project.keywords.is_some(),
+        "classifiers" => project.classifiers.is_some(),
+        "urls" => project.urls.is_some(),
+        "entry-points" => project.entry_points.is_some(),
+        "scripts" => project.scripts.is_some(),
+        "gui-scripts" => project.gui_scripts.is_some(),
+        "dependencies" => project.dependencies.is_some(),
+        "optional-dependencies" => project.optional_dependencies.is_some(),
+        _ => false,
+    };
+    for field in dynamic.iter() {
+        if field == "name" {
+            problems.push(error("name can't be dynamic".to_string()));
+        } else if is_set(field) {
+            problems.push(error(format!(
+                "{field} is listed as dynamic but is also set"
+            )));
+        }
+    }
+    if !is_set("version") && !dynamic.contains(&"version".to_string()) {
+        problems.push(error(
+            "version must be set or listed as dynamic".to_string(),
+        ));
+    }
+    for field in ["description", "readme", "license", "requires-python"] {
+        if !is_set(field) && !dynamic.iter().any(|it| it == field) {
+            problems.push(warning(format!(
+                "{field} isn't set; the package index will show it as missing"
+            )));
+        }
+    }
+
+    if let Some(readme) = project.readme.as_ref() {
+        problems.extend(readme_problems(readme, root));
+    }
+
+    if let Some(license) = project.license.as_ref() {
+        match (license.file.as_ref(), license.text.as_ref()) {
+            (Some(_), Some(_)) => problems.push(error(
+                "license can't have both a file and text".to_string(),
+            )),
+            (Some(file), None) if !root.join(file).is_file() => problems
+                .push(error(format!("license file {file} doesn't exist"))),
+            _ => {}
+        }
+        if project.license_expression.is_some() {
+            problems.push(error(
+                "license can't be a table when a license expression is used"
+                    .to_string(),
+            ));
+        }
+    }
+    if let Some(expression) = project.license_expression.as_ref() {
+        problems.extend(license_expression_problems(expression));
+    }
+
+    for classifier in project.classifiers.iter().flatten() {
+        let category = classifier.split("::").next().unwrap_or_default().trim();
+        if !CLASSIFIER_CATEGORIES.contains(&category)
+            || classifier.split("::").any(|it| it.trim().is_empty())
+        {
+            problems.push(error(format!(
+                "classifier {classifier:?} isn't a valid trove classifier"
+            )));
+        } else if category == "Private" {
+            problems.push(warning(format!(
+                "classifier {classifier:?} makes the package index reject uploads"
+            )));
+        } else if category == "License" && project.license_expression.is_some()
+        {
+            problems.push(error(format!(
+                "license classifier {classifier:?} can't be used with a license expression"
+            )));
+        } else if !is_known_classifier(classifier) {
+            problems.push(warning(format!(
+                "classifier {classifier:?} isn't a known trove classifier"
+            )));
+        }
+    }
+
+    problems
+}
+
+/// Get the problems with the project's readme.
+fn readme_problems(readme: &ReadMe, root: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let (file, text, content_type) = match readme {
+        ReadMe::RelativePath(it) => (Some(it), None, None),
+        ReadMe::Table {
+            file,
+            text,
+            content_type,
+        } => (file.as_ref(), text.as_ref(), content_type.as_ref()),
+    };
+    if let ReadMe::Table { .. } = readme {
+        if file.is_some() == text.is_some() {
+            problems.push(Problem::Error(
+                "readme must have either a file or text".to_string(),
+            ));
+        }
+        match content_type.map(String::as_str) {
+            Some("text/markdown" | "text/x-rst" | "text/plain") => {}
+            Some(it) => problems.push(Problem::Error(format!(
+                "readme content-type {it} must be text/markdown, text/x-rst, or text/plain"
+            ))),
+            None => problems.push(Problem::Error(
+                "readme content-type must be set".to_string(),
+            )),
+        }
+    }
+    if let Some(file) = file {
+        if std::fs::read_to_string(root.join(file)).is_err() {
+            problems.push(Problem::Error(format!(
+                "readme {file} doesn't exist or isn't readable text"
+            )));
+        }
+        let extension = Path::new(file)
+            .extension()
+            .map(|it| it.to_string_lossy().to_lowercase());
+        if content_type.is_none()
+            && !matches!(extension.as_deref(), Some("md" | "rst"))
+        {
+            problems.push(Problem::Error(format!(
+                "readme {file} needs a content-type since it isn't a .md or .rst file"
+            )));
+        }
+    }
+
+    problems
+}
+
+/// Get the problems with an SPDX license expression, such as "MIT OR Apache-2.0".
+fn license_expression_problems(expression: &str) -> Vec<Problem> {
+    let spaced = expression.replace('(', " ( ").replace(')', " ) ");
+    let tokens = spaced.split_whitespace().collect::<Vec<_>>();
+    let invalid = || {
+        vec![Problem::Error(format!(
+            "license expression {expression:?} isn't a valid SPDX expression"
+        ))]
+    };
+    if tokens.is_empty() {
+        return invalid();
+    }
+
+    let mut problems = Vec::new();
+    let mut depth = 0;
+    // An identifier or `)` must come next, rather than an operator.
+    let mut expect_operand = true;
+    let mut after_with = false;
+    for token in tokens {
+        match token {
+            "(" if expect_operand => depth += 1,
+            ")" if !expect_operand && depth > 0 => depth -= 1,
+            "AND" | "OR" | "WITH" if !expect_operand => {
+                expect_operand = true;
+                after_with = token == "WITH";
+            }
+            _ if expect_operand && !["(", ")"].contains(&token) => {
+                let id = token.strip_suffix('+').unwrap_or(token);
+                let known = match after_with {
+                    true => SPDX_EXCEPTION_IDS.contains(&id),
+                    false => {
+                        SPDX_LICENSE_IDS
+                            .iter()
+                            .any(|it| it.eq_ignore_ascii_case(id))
+                            || id.starts_with("LicenseRef-")
+                    }
+                };
+                if !id
+                    .chars()
+                    .all(|it| it.is_ascii_alphanumeric() || ".-".contains(it))
+                {
+                    return invalid();
+                }
+                if !known {
+                    problems.push(Problem::Warning(format!(
+                        "{id} in the license expression isn't a common SPDX identifier"
+                    )));
+                }
+                expect_operand = false;
+                after_with = false;
+            }
+            _ => return invalid(),
+        }
+    }
+    if expect_operand || depth != 0 {
+        return invalid();
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_check_project() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let options = CheckOptions {
+            check_name: false,
+            schema: true,
+        };
+
+        check_project(&config, &options).unwrap();
+
+        let path = root.join("pyproject.toml");
+        let contents = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace(
+                "description = \"\"",
+                "description = \"\"\nreadme = \"README.txt\"\nlicense-expression = \"MIT OR\"\nclassifiers = [\"Programing Language :: Python\"]\ndynamic = [\"version\"]",
+            );
+        std::fs::write(&path, contents).unwrap();
+        let ws = config.workspace();
+        let metadata = ws.current_local_metadata().unwrap();
+
+        let problems = metadata_problems(metadata.metadata(), ws.root());
+
+        assert_eq!(
+            problems
+                .iter()
+                .filter(|it| matches!(it, Problem::Error(_)))
+                .count(),
+            5
+        );
+        assert!(check_project(&config, &options).is_err());
+    }
+
+    #[test]
+    fn test_license_expression_problems() {
+        for it in [
+            "MIT",
+            "MIT OR Apache-2.0",
+            "(MIT AND BSD-3-Clause) OR Apache-2.0",
+            "GPL-2.0-or-later WITH Classpath-exception-2.0",
+            "LicenseRef-Proprietary",
+        ] {
+            assert!(license_expression_problems(it).is_empty(), "{it}");
+        }
+        for it in ["", "MIT OR", "(MIT", "MIT Apache-2.0", "AND MIT", "M!T"] {
+            assert!(
+                matches!(
+                    license_expression_problems(it).as_slice(),
+                    [Problem::Error(_)]
+                ),
+                "{it}"
+            );
+        }
+        assert!(matches!(
+            license_expression_problems("Made-Up-1.0").as_slice(),
+            [Problem::Warning(_)]
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+use super::{
+    build_project, format_project, lint_project, test_project, BuildOptions,
+    FormatOptions, LintOptions, LintOutput, TestOptions,
+};
+use crate::{metadata::Metadata, Config, Error, HuakResult, InstallOptions};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+    time::Instant,
+};
+use termcolor::Color;
+
+pub struct CiOptions {
+    pub install_options: InstallOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A step of the CI pipeline.
+pub enum CiStep {
+    /// Check the code is formatted (`huak fmt --check`).
+    Fmt,
+    /// Lint the code without type-checking.
+    Lint,
+    /// Type-check the code with `mypy`.
+    Typecheck,
+    /// Run the tests.
+    Test,
+    /// Build the project and check the wheel's contents (`huak build --check-contents`).
+    Build,
+}
+
+impl CiStep {
+    /// The steps run when `[tool.huak.ci] steps` isn't set.
+    pub const DEFAULT: [CiStep; 5] = [
+        CiStep::Fmt,
+        CiStep::Lint,
+        CiStep::Typecheck,
+        CiStep::Test,
+        CiStep::Build,
+    ];
+}
+
+impl FromStr for CiStep {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fmt" => Ok(CiStep::Fmt),
+            "lint" => Ok(CiStep::Lint),
+            "typecheck" => Ok(CiStep::Typecheck),
+            "test" => Ok(CiStep::Test),
+            "build" => Ok(CiStep::Build),
+            _ => Err(Error::HuakConfigurationError(format!(
+                "unknown CI step {s:?} (expected fmt, lint, typecheck, test, or build)"
+            ))),
+        }
+    }
+}
+
+impl Display for CiStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CiStep::Fmt => "fmt",
+            CiStep::Lint => "lint",
+            CiStep::Typecheck => "typecheck",
+            CiStep::Test => "test",
+            CiStep::Build => "build",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Run the project's verification pipeline. Every step runs even if an earlier one
+/// fails, and a summary of the steps is printed at the end. The steps default to
+/// `CiStep::DEFAULT` and can be configured with `[tool.huak.ci] steps`.
+pub fn ci_project(config: &Config, options: &CiOptions) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let steps = ci_steps(metadata.metadata())?;
+    let mut terminal = config.terminal();
+
+    let mut results = Vec::new();
+    for step in steps.iter() {
+        terminal.print_custom("Running", step, Color::Cyan, true)?;
+        let start = Instant::now();
+        let res = run_step(*step, config, options);
+        if let Err(e) = res.as_ref() {
+            terminal.print_error(e)?;
+        }
+        results.push((step, res.is_ok(), start.elapsed()));
+    }
+
+    let width = steps
+        .iter()
+        .map(|it| it.to_string().len())
+        .max()
+        .unwrap_or_default();
+    for (step, passed, elapsed) in results.iter() {
+        let (title, color) = match passed {
+            true => ("Passed", Color::Green),
+            false => ("Failed", Color::Red),
+        };
+        terminal.print_custom(
+            title,
+            format!(
+                "{:width$}  {:.2}s",
+                step.to_string(),
+                elapsed.as_secs_f64()
+            ),
+            color,
+            true,
+        )?;
+    }
+
+    let failed = results.iter().filter(|(_, passed, _)| !passed).count();
+    if failed > 0 {
+        return Err(Error::CheckFailed(format!(
+            "{failed} of {} CI step(s) failed",
+            results.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Get the CI steps configured with `[tool.huak.ci] steps`.
+fn ci_steps(metadata: &Metadata) -> HuakResult<Vec<CiStep>> {
+    let Some(value) = metadata.huak_setting("ci", "steps") else {
+        return Ok(CiStep::DEFAULT.to_vec());
+    };
+    let invalid = || {
+        Error::HuakConfigurationError(
+            "[tool.huak.ci] steps must be a list of step names".to_string(),
+        )
+    };
+    value
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|it| CiStep::from_str(it.as_str().ok_or_else(invalid)?))
+        .collect()
+}
+
+fn run_step(
+    step: CiStep,
+    config: &Config,
+    options: &CiOptions,
+) -> HuakResult<()> {
+    let install_options = || InstallOptions {
+        values: options.install_options.values.clone(),
+    };
+    let lint_options = |only_types| LintOptions {
+        values: None,
+        include_types: false,
+        only_types,
+        include_security: false,
+        include_dead_code: false,
+        install_options: install_options(),
+        output: LintOutput::Text,
+        output_path: None,
+        changed: None,
+        staged: false,
+        update_index: false,
+        paths: Vec::new(),
+    };
+    match step {
+        CiStep::Fmt => format_project(
+            config,
+            &FormatOptions {
+                values: Some(vec!["--check".to_string()]),
+                install_options: install_options(),
+                changed: None,
+                staged: false,
+                update_index: false,
+                paths: Vec::new(),
+            },
+        ),
+        CiStep::Lint => lint_project(config, &lint_options(false)),
+        CiStep::Typecheck => lint_project(config, &lint_options(true)),
+        CiStep::Test => test_project(
+            config,
+            &TestOptions {
+                values: None,
+                install_options: install_options(),
+                changed: None,
+                paths: Vec::new(),
+                jobs: None,
+                shard: None,
+                last_failed: false,
+                failed_first: false,
+                keyword: None,
+            },
+        ),
+        CiStep::Build => build_project(
+            config,
+            &BuildOptions {
+                values: None,
+                install_options: install_options(),
+                check_contents: true,
+                workspace: false,
+            },
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_ci_steps() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let ws = config.workspace();
+        let mut metadata = ws.current_local_metadata().unwrap();
+
+        assert_eq!(
+            ci_steps(metadata.metadata()).unwrap(),
+            CiStep::DEFAULT.to_vec()
+        );
+
+        let path = root.join("pyproject.toml");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(
+            &path,
+            format!(
+                "{contents}\n[tool.huak.ci]\nsteps = [\"lint\", \"test\"]\n"
+            ),
+        )
+        .unwrap();
+        metadata = ws.current_local_metadata().unwrap();
+        assert_eq!(
+            ci_steps(metadata.metadata()).unwrap(),
+            vec![CiStep::Lint, CiStep::Test]
+        );
+
+        std::fs::write(
+            &path,
+            format!("{contents}\n[tool.huak.ci]\nsteps = [\"deploy\"]\n"),
+        )
+        .unwrap();
+        metadata = ws.current_local_metadata().unwrap();
+        assert!(ci_steps(metadata.metadata()).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    classifiers::{
+        is_known_classifier, python_version_classifiers, search_classifiers,
+    },
+    Config, Error, HuakResult,
+};
+use termcolor::Color;
+
+pub struct ClassifiersOptions {
+    /// The classifiers to add.
+    pub classifiers: Vec<String>,
+    /// Search the trove classifiers and choose which matches to add.
+    pub search: Option<String>,
+    /// Add the Python version classifiers allowed by the project's `requires-python`.
+    pub python: bool,
+    /// Add classifiers that aren't known trove classifiers.
+    pub allow_unknown: bool,
+}
+
+/// Print the trove classifiers matching every term of a query.
+pub fn search_trove_classifiers(
+    config: &Config,
+    query: &str,
+) -> HuakResult<()> {
+    let mut terminal = config.terminal();
+    let matches = search_classifiers(query);
+    if matches.is_empty() {
+        return terminal
+            .print_warning(format!("no classifiers match {query:?}"));
+    }
+
+    terminal.print_stdout(&format!("{}\n", matches.join("\n")))
+}
+
+/// Add trove classifiers to the project's `project.classifiers`. Classifiers can be
+/// given explicitly, chosen from search results, or derived from `requires-python`.
+pub fn add_project_classifiers(
+    config: &Config,
+    options: &ClassifiersOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut metadata = workspace.current_local_metadata()?;
+    let mut terminal = config.terminal();
+
+    let mut classifiers = options.classifiers.clone();
+    if let Some(query) = options.search.as_ref() {
+        let matches = search_classifiers(query);
+        if matches.is_empty() {
+            return Err(Error::HuakConfigurationError(format!(
+                "no classifiers match {query:?}"
+            )));
+        }
+        for (i, it) in matches.iter().enumerate() {
+            terminal.print_stdout(&format!("{:>3}. {it}\n", i + 1))?;
+        }
+        let answer = terminal
+            .prompt("Classifiers to add (numbers separated by spaces)", None)?;
+        for it in answer.split(|c: char| c == ',' || c.is_whitespace()) {
+            if it.is_empty() {
+                continue;
+            }
+            match it.parse::<usize>() {
+                Ok(n) if (1..=matches.len()).contains(&n) => {
+                    classifiers.push(matches[n - 1].to_string())
+                }
+                _ => {
+                    return Err(Error::HuakConfigurationError(format!(
+                        "{it:?} isn't one of the listed classifiers"
+                    )))
+                }
+            }
+        }
+    }
+    if options.python {
+        let Some(requires_python) =
+            metadata.metadata().project().requires_python.as_ref()
+        else {
+            return Err(Error::HuakConfigurationError(
+                "requires-python must be set to derive Python version classifiers"
+                    .to_string(),
+            ));
+        };
+        classifiers.extend(python_version_classifiers(requires_python));
+    }
+
+    if !options.allow_unknown {
+        let unknown = classifiers
+            .iter()
+            .filter(|it| !is_known_classifier(it))
+            .map(|it| format!("{it:?}"))
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            return Err(Error::HuakConfigurationError(format!(
+                "unknown trove classifier(s) {} (use --allow-unknown to add them anyway)",
+                unknown.join(", ")
+            )));
+        }
+    }
+
+    let mut added = 0;
+    for it in classifiers.iter() {
+        if !metadata.metadata().classifiers().contains(it) {
+            metadata.metadata_mut().add_classifier(it);
+            added += 1;
+        }
+    }
+    if added > 0 {
+        metadata.write_file()?;
+    }
+
+    terminal.print_custom(
+        "Added",
+        format!("{added} classifier(s)"),
+        Color::Green,
+        true,
+    )
+}
+
+/// Check that every classifier in the project's `project.classifiers` is a known trove
+/// classifier.
+pub fn validate_project_classifiers(config: &Config) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let mut terminal = config.terminal();
+
+    let classifiers = metadata.metadata().classifiers();
+    let unknown = classifiers
+        .iter()
+        .filter(|it| !is_known_classifier(it))
+        .collect::<Vec<_>>();
+    for it in unknown.iter() {
+        terminal.print_custom("Unknown", it, Color::Red, true)?;
+    }
+    if !unknown.is_empty() {
+        return Err(Error::CheckFailed(format!(
+            "{} of {} classifier(s) aren't known trove classifiers",
+            unknown.len(),
+            classifiers.len()
+        )));
+    }
+
+    terminal.print_custom(
+        "Validated",
+        format!("{} classifier(s)", classifiers.len()),
+        Color::Green,
+        true,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_add_project_classifiers() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let ws = config.workspace();
+        let mut metadata = ws.current_local_metadata().unwrap();
+        metadata
+            .metadata_mut()
+            .set_requires_python(">=3.11".parse().unwrap());
+        metadata.write_file().unwrap();
+        let options = ClassifiersOptions {
+            classifiers: vec!["Typing :: Typed".to_string()],
+            search: None,
+            python: true,
+            allow_unknown: false,
+        };
+
+        add_project_classifiers(&config, &options).unwrap();
+        validate_project_classifiers(&config).unwrap();
+
+        let metadata = ws.current_local_metadata().unwrap();
+        let classifiers = metadata.metadata().classifiers();
+        assert_eq!(classifiers[0], "Typing :: Typed");
+        assert!(classifiers
+            .contains(&"Programming Language :: Python :: 3.11".to_string()));
+        assert!(!classifiers
+            .contains(&"Programming Language :: Python :: 3.10".to_string()));
+
+        let options = ClassifiersOptions {
+            classifiers: vec!["Typing :: Untyped".to_string()],
+            search: None,
+            python: false,
+            allow_unknown: false,
+        };
+        assert!(add_project_classifiers(&config, &options).is_err());
+    }
+}
@@ -1,56 +1,119 @@
-use crate::{Config, HuakResult};
+use super::{exclude_patterns, is_excluded};
+use crate::{
+    fs, python_environment::venv_config_file_name, Config, Error, HuakResult,
+};
+use std::path::{Path, PathBuf};
 
 pub struct CleanOptions {
     pub include_pycache: bool,
     pub include_compiled_bytecode: bool,
+    /// Remove everything that can be cleaned, including build directories and tool caches.
+    /// Confirmation is required since this can't be undone.
+    pub include_all: bool,
 }
 
+/// Directories in the workspace root removed when cleaning everything.
+const ALL_DIRECTORIES: [&str; 4] =
+    ["build", ".pytest_cache", ".mypy_cache", ".ruff_cache"];
+
 pub fn clean_project(
     config: &Config,
     options: &CleanOptions,
 ) -> HuakResult<()> {
     let workspace = config.workspace();
+    // Excluded paths, such as vendored trees, are left alone.
+    let exclude = match workspace.current_local_metadata() {
+        Ok(it) => exclude_patterns(&it)?,
+        Err(_) => Vec::new(),
+    };
+    let cleanable = |path: &Path| {
+        // Don't follow symlinks out of the workspace.
+        !fs::is_linked(workspace.root(), path)
+            && path
+                .strip_prefix(workspace.root())
+                .map_or(true, |it| !is_excluded(it, &exclude))
+            && !in_python_environment(workspace.root(), path)
+    };
+    let find = |pattern: &Path| -> HuakResult<Vec<PathBuf>> {
+        Ok(glob::glob(&format!("{}", pattern.display()))?
+            .flatten()
+            .filter(|it| cleanable(it))
+            .collect())
+    };
+
+    if options.include_all {
+        let prompt = format!(
+            "remove all build artifacts and caches from {}",
+            workspace.root().display()
+        );
+        if !config.terminal().confirm(prompt)? {
+            return Ok(());
+        }
+        for name in ALL_DIRECTORIES {
+            let path = workspace.root().join(name);
+            if path.exists() && cleanable(&path) {
+                fs::remove_dir_all(path)?;
+            }
+        }
+        for path in find(&workspace.root().join("**").join("*.egg-info"))? {
+            remove(&path)?;
+        }
+    }
 
     // Remove everything from the dist directory if it exists.
     if workspace.root().join("dist").exists() {
-        std::fs::read_dir(workspace.root().join("dist"))?
+        for item in std::fs::read_dir(workspace.root().join("dist"))?
             .filter_map(|x| x.ok().map(|item| item.path()))
-            .for_each(|item| {
-                if item.is_dir() {
-                    std::fs::remove_dir_all(item).ok();
-                } else if item.is_file() {
-                    std::fs::remove_file(item).ok();
-                }
-            });
+            .filter(|it| cleanable(it))
+        {
+            remove(&item)?;
+        }
     }
 
     // Remove all __pycache__ directories in the workspace if they exist.
-    if options.include_pycache {
-        let pattern = format!(
-            "{}",
-            workspace.root().join("**").join("__pycache__").display()
-        );
-        glob::glob(&pattern)?.for_each(|item| {
-            if let Ok(it) = item {
-                std::fs::remove_dir_all(it).ok();
-            }
-        })
+    if options.include_pycache || options.include_all {
+        for path in find(&workspace.root().join("**").join("__pycache__"))? {
+            remove(&path)?;
+        }
     }
 
     // Remove all .pyc files in the workspace if they exist.
-    if options.include_compiled_bytecode {
-        let pattern =
-            format!("{}", workspace.root().join("**").join("*.pyc").display());
-        glob::glob(&pattern)?.for_each(|item| {
-            if let Ok(it) = item {
-                std::fs::remove_file(it).ok();
-            }
-        })
+    if options.include_compiled_bytecode || options.include_all {
+        for path in find(&workspace.root().join("**").join("*.pyc"))? {
+            remove(&path)?;
+        }
     }
 
     Ok(())
 }
 
+/// Check if a path is inside a Python environment in the workspace, either in a virtual
+/// environment's directory or in a `site-packages` directory. Installed packages' metadata
+/// (such as `*.egg-info`) is never cleaned.
+fn in_python_environment(root: &Path, path: &Path) -> bool {
+    path.ancestors()
+        .skip(1)
+        .take_while(|it| it.starts_with(root) && *it != root)
+        .any(|it| {
+            it.file_name().map_or(false, |it| it == "site-packages")
+                || it.join(venv_config_file_name()).exists()
+        })
+}
+
+/// Remove a file or directory. Only failures caused by another process using it are
+/// reported, since anything else that can't be removed is left alone.
+fn remove(path: &Path) -> HuakResult<()> {
+    let res = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match res {
+        Err(e @ Error::FileInUse(..)) => Err(e),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +134,7 @@ mod tests {
         let options = CleanOptions {
             include_pycache: true,
             include_compiled_bytecode: true,
+            include_all: false,
         };
 
         clean_project(&config, &options).unwrap();
@@ -105,4 +169,93 @@ mod tests {
         assert!(pycaches.is_empty());
         assert!(bytecode.is_empty());
     }
+
+    #[test]
+    fn test_clean_project_all() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        std::fs::create_dir_all(root.join(".ruff_cache")).unwrap();
+        std::fs::create_dir_all(root.join("src").join("mock.egg-info"))
+            .unwrap();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let options = CleanOptions {
+            include_pycache: false,
+            include_compiled_bytecode: false,
+            include_all: true,
+        };
+
+        clean_project(&config, &options).unwrap();
+
+        assert!(!root.join(".ruff_cache").exists());
+        assert!(!root.join("src").join("mock.egg-info").exists());
+    }
+
+    #[test]
+    fn test_clean_project_all_keeps_environment() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let site_packages = root
+            .join(".venv")
+            .join("lib")
+            .join("python3.11")
+            .join("site-packages");
+        std::fs::create_dir_all(site_packages.join("x.egg-info")).unwrap();
+        std::fs::create_dir_all(site_packages.join("x").join("__pycache__"))
+            .unwrap();
+        std::fs::create_dir_all(root.join("src").join("mock.egg-info"))
+            .unwrap();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let options = CleanOptions {
+            include_pycache: false,
+            include_compiled_bytecode: false,
+            include_all: true,
+        };
+
+        clean_project(&config, &options).unwrap();
+
+        assert!(site_packages.join("x.egg-info").exists());
+        assert!(site_packages.join("x").join("__pycache__").exists());
+        assert!(!root.join("src").join("mock.egg-info").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clean_project_all_keeps_linked() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let outside = dir.path().join("outside");
+        std::fs::create_dir_all(outside.join("lib")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("build")).unwrap();
+        std::fs::create_dir_all(root.join(".pytest_cache")).unwrap();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let options = CleanOptions {
+            include_pycache: false,
+            include_compiled_bytecode: false,
+            include_all: true,
+        };
+
+        clean_project(&config, &options).unwrap();
+
+        assert!(root.join("build").exists());
+        assert!(outside.join("lib").exists());
+        assert!(!root.join(".pytest_cache").exists());
+    }
 }
@@ -0,0 +1,231 @@
+use super::generate::python_image;
+use crate::{
+    lockfile::lockfile_file_name, metadata::Metadata, Config, Error, HuakResult,
+};
+use std::{io::ErrorKind, process::Command};
+use termcolor::Color;
+
+/// Paths left out of the build context when the project doesn't have a .dockerignore.
+const DEFAULT_DOCKERIGNORE: [&str; 12] = [
+    ".git",
+    ".huak",
+    ".mypy_cache",
+    ".pytest_cache",
+    ".ruff_cache",
+    ".venv",
+    "**/__pycache__",
+    "**/*.pyc",
+    "build",
+    "dist",
+    "*.egg-info",
+    ".devcontainer",
+];
+
+pub struct DockerOptions {
+    /// Tag the image with these tags instead of the ones derived from the project.
+    pub tags: Vec<String>,
+    /// The repository the derived tags are in [default: the project's name].
+    pub repository: Option<String>,
+    /// Push the image's tags after it's built.
+    pub push: bool,
+    /// A values vector of arguments passed on to `docker build`.
+    pub values: Option<Vec<String>>,
+}
+
+/// Build a Docker image for the project. The Dockerfile is rendered into a temporary
+/// directory with a layer for the dependencies pinned by the project's lockfile, which is
+/// only rebuilt when the lockfile changes, and a layer for the project itself. The image
+/// is tagged `<name>:<version>` and `<name>:latest` unless tags are given.
+pub fn build_docker_image(
+    config: &Config,
+    options: &DockerOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let mut terminal = config.terminal();
+
+    if workspace.current_lockfile()?.is_none() {
+        return Err(Error::HuakConfigurationError(format!(
+            "there's no {} for the image to install from, run `huak lock` first",
+            lockfile_file_name()
+        )));
+    }
+    let environment = workspace.environment();
+    let (interpreter, _) = workspace
+        .select_python_interpreter(environment.interpreters().interpreters())?;
+    let tags = match options.tags.is_empty() {
+        true => image_tags(metadata.metadata(), options.repository.as_deref()),
+        false => options.tags.clone(),
+    };
+
+    let dir = std::env::temp_dir()
+        .join(format!("huak-docker-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let dockerfile = dir.join("Dockerfile");
+    std::fs::write(
+        &dockerfile,
+        dockerfile_contents(
+            &python_image(&interpreter),
+            lockfile_file_name(),
+            default_command(metadata.metadata()),
+        ),
+    )?;
+    // BuildKit reads a Dockerfile's own ignore file before the context's .dockerignore,
+    // so it's only written when the project doesn't have one.
+    if !workspace.root().join(".dockerignore").exists() {
+        std::fs::write(
+            dir.join("Dockerfile.dockerignore"),
+            DEFAULT_DOCKERIGNORE.join("\n") + "\n",
+        )?;
+    }
+
+    let mut cmd = Command::new("docker");
+    cmd.arg("build").arg("-f").arg(&dockerfile);
+    for it in tags.iter() {
+        cmd.args(["-t", it]);
+    }
+    cmd.args(options.values.iter().flatten())
+        .arg(workspace.root())
+        .current_dir(workspace.root());
+    let res = docker(&mut cmd, config);
+    std::fs::remove_dir_all(&dir).ok();
+    res?;
+    for it in tags.iter() {
+        terminal.print_custom("Built", it, Color::Green, true)?;
+    }
+
+    if options.push {
+        for it in tags.iter() {
+            docker(Command::new("docker").args(["push", it]), config)?;
+            terminal.print_custom("Pushed", it, Color::Green, true)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Run a `docker` command, failing with a hint if Docker isn't installed.
+fn docker(cmd: &mut Command, config: &Config) -> HuakResult<()> {
+    match config.terminal().run_command(cmd) {
+        Err(Error::IOError(e)) if e.kind() == ErrorKind::NotFound => {
+            Err(Error::HuakConfigurationError(
+                "docker could not be found; install Docker and make sure it's on your PATH"
+                    .to_string(),
+            ))
+        }
+        res => res,
+    }
+}
+
+/// Get the tags for the project's image: `<repository>:<version>` and
+/// `<repository>:latest`. The repository defaults to the project's name, lowercased with
+/// characters Docker doesn't allow replaced.
+fn image_tags(metadata: &Metadata, repository: Option<&str>) -> Vec<String> {
+    let repository = match repository {
+        Some(it) => it.to_string(),
+        None => metadata
+            .project_name()
+            .to_lowercase()
+            .chars()
+            .map(|it| match it {
+                'a'..='z' | '0'..='9' | '.' | '_' | '-' => it,
+                _ => '-',
+            })
+            .collect(),
+    };
+    let mut tags = Vec::new();
+    if let Some(it) = metadata.project_version() {
+        // Local versions like 1.0+local use a character tags can't contain.
+        tags.push(format!("{repository}:{}", it.to_string().replace('+', "-")));
+    }
+    tags.push(format!("{repository}:latest"));
+
+    tags
+}
+
+/// Get the command the image runs: the project's script if it has exactly one.
+fn default_command(metadata: &Metadata) -> Option<&str> {
+    match metadata.scripts() {
+        Some(it) if it.len() == 1 => it.keys().next().map(String::as_str),
+        _ => None,
+    }
+}
+
+/// Get the contents of the image's Dockerfile. The project's dependencies are installed
+/// from its lockfile before its source is copied so that source changes don't invalidate
+/// the dependency layer.
+fn dockerfile_contents(
+    image: &str,
+    lockfile_name: &str,
+    command: Option<&str>,
+) -> String {
+    let version = env!("CARGO_PKG_VERSION");
+    let mut contents = format!(
+        r#"# Generated by huak (huak docker build).
+FROM {image}
+
+RUN pip install --no-cache-dir huak=={version} \
+    && python -m venv /opt/venv
+ENV VIRTUAL_ENV=/opt/venv
+ENV PATH="$VIRTUAL_ENV/bin:$PATH"
+WORKDIR /app
+
+# Dependencies
+COPY pyproject.toml {lockfile_name} ./
+RUN huak install --frozen
+
+# Application
+COPY . .
+RUN pip install --no-cache-dir --no-deps .
+"#
+    );
+    if let Some(it) = command {
+        contents.push_str(&format!("CMD [\"{it}\"]\n"));
+    }
+
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::LocalMetadata;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_image_tags() {
+        let dir = tempdir().unwrap();
+        let mut metadata =
+            LocalMetadata::template(dir.path().join("pyproject.toml"));
+        metadata
+            .metadata_mut()
+            .set_project_name("My_Project".to_string());
+
+        assert_eq!(
+            image_tags(metadata.metadata(), None),
+            ["my_project:0.0.1", "my_project:latest"]
+        );
+        assert_eq!(
+            image_tags(metadata.metadata(), Some("ghcr.io/me/app")),
+            ["ghcr.io/me/app:0.0.1", "ghcr.io/me/app:latest"]
+        );
+    }
+
+    #[test]
+    fn test_dockerfile_contents() {
+        let contents = dockerfile_contents(
+            "python:3.11-slim",
+            "huak.lock",
+            Some("my-project"),
+        );
+
+        assert!(contents.starts_with(
+            "# Generated by huak (huak docker build).\nFROM python:3.11-slim\n"
+        ));
+        assert!(
+            contents.find("COPY pyproject.toml huak.lock ./").unwrap()
+                < contents.find("COPY . .").unwrap()
+        );
+        assert!(contents.ends_with("CMD [\"my-project\"]\n"));
+    }
+}
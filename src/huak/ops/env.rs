@@ -0,0 +1,247 @@
+use crate::{
+    dependency::Dependency,
+    dist_info::scan_site_packages,
+    package::canonical_package_name,
+    snapshot::{snapshot_file_name, Snapshot, SnapshotPackage},
+    Config, HuakResult, InstallOptions,
+};
+use std::{path::PathBuf, str::FromStr};
+use termcolor::Color;
+
+pub struct EnvOptions {
+    /// The path to the snapshot. Defaults to "huak-env.toml" in the workspace root.
+    pub path: Option<PathBuf>,
+    pub install_options: InstallOptions,
+}
+
+/// Export a `Snapshot` of the exact packages installed in the project's Python
+/// environment.
+pub fn export_environment(
+    config: &Config,
+    options: &EnvOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+
+    let path = snapshot_path(config, options);
+    let mut snapshot =
+        Snapshot::new(&path, python_env.python_version().to_string());
+    let project_name =
+        canonical_package_name(metadata.metadata().project_name())?;
+    for dist in scan_site_packages(python_env.site_packages_dir_path())? {
+        // The project itself can't be installed from a package index.
+        if canonical_package_name(dist.name())?
+            .eq_ignore_ascii_case(&project_name)
+        {
+            continue;
+        }
+        let direct = metadata
+            .metadata()
+            .contains_dependency_any(&Dependency::from_str(dist.name())?)?;
+        snapshot.packages.push(SnapshotPackage {
+            name: dist.name().to_string(),
+            version: dist.version().to_string(),
+            hash: dist.content_hash()?,
+            direct,
+        });
+    }
+    snapshot.write_file()?;
+
+    config.terminal().print_custom(
+        "Exported",
+        format!(
+            "{} package(s) to {}",
+            snapshot.packages.len(),
+            snapshot
+                .path()
+                .strip_prefix(&config.cwd)
+                .unwrap_or(snapshot.path())
+                .display()
+        ),
+        Color::Green,
+        true,
+    )
+}
+
+/// Recreate the environment recorded by a `Snapshot` in the project's Python
+/// environment. Packages are installed at their recorded versions without resolving
+/// dependencies, packages missing from the `Snapshot` are removed, and packages whose
+/// installed files differ from the recorded ones are reported.
+pub fn import_environment(
+    config: &Config,
+    options: &EnvOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let _lock = workspace.lock()?;
+    let _journal = workspace.journal("env-import")?;
+    let metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+    let mut terminal = config.terminal();
+
+    let snapshot = Snapshot::from_path(snapshot_path(config, options))?;
+    let python_version = python_env.python_version().to_string();
+    if minor_version(&python_version) != minor_version(&snapshot.python) {
+        terminal.print_warning(format!(
+            "the snapshot was exported with Python {} but the environment uses Python {python_version}",
+            snapshot.python
+        ))?;
+    }
+
+    let project_name =
+        canonical_package_name(metadata.metadata().project_name())?;
+    let installed = scan_site_packages(python_env.site_packages_dir_path())?;
+    let find = |name: &str| -> HuakResult<Option<&SnapshotPackage>> {
+        let name = canonical_package_name(name)?;
+        for pkg in snapshot.packages.iter() {
+            if canonical_package_name(&pkg.name)?.eq_ignore_ascii_case(&name) {
+                return Ok(Some(pkg));
+            }
+        }
+        Ok(None)
+    };
+
+    // Remove installed packages the snapshot doesn't have.
+    let mut extra = Vec::new();
+    for dist in installed.iter() {
+        let name = canonical_package_name(dist.name())?;
+        if find(dist.name())?.is_none()
+            && !name.eq_ignore_ascii_case(&project_name)
+        {
+            extra.push(dist.name().to_string());
+        }
+    }
+    if !extra.is_empty() {
+        let prompt = format!(
+            "remove {} package(s) the snapshot doesn't have ({})",
+            extra.len(),
+            extra.join(", ")
+        );
+        if !terminal.confirm(prompt)? {
+            return Ok(());
+        }
+        python_env.uninstall_packages(
+            &extra,
+            &options.install_options,
+            config,
+        )?;
+    }
+
+    // Install packages that are missing or installed at other versions.
+    let mut missing = Vec::new();
+    for pkg in snapshot.packages.iter() {
+        let name = canonical_package_name(&pkg.name)?;
+        let dist = installed.iter().find(|it| {
+            canonical_package_name(it.name())
+                .map_or(false, |it| it.eq_ignore_ascii_case(&name))
+        });
+        if dist.map_or(true, |it| it.version().to_string() != pkg.version) {
+            missing.push(format!("{}=={}", pkg.name, pkg.version));
+        }
+    }
+    if !missing.is_empty() {
+        let mut install_options = InstallOptions {
+            values: options.install_options.values.clone(),
+        };
+        install_options
+            .values
+            .get_or_insert_with(Vec::new)
+            .push("--no-deps".to_string());
+        python_env.install_packages(&missing, &install_options, config)?;
+    }
+
+    // Report packages whose files differ from the snapshot's, such as builds for other
+    // platforms or locally modified packages.
+    for dist in scan_site_packages(python_env.site_packages_dir_path())? {
+        if let Some(pkg) = find(dist.name())? {
+            if dist.content_hash()? != pkg.hash {
+                terminal.print_warning(format!(
+                    "{}=={} has different files than the snapshot",
+                    pkg.name, pkg.version
+                ))?;
+            }
+        }
+    }
+
+    terminal.print_custom(
+        "Imported",
+        format!(
+            "{} package(s) ({} installed, {} removed)",
+            snapshot.packages.len(),
+            missing.len(),
+            extra.len()
+        ),
+        Color::Green,
+        true,
+    )
+}
+
+/// Get the path to the snapshot from `EnvOptions`.
+fn snapshot_path(config: &Config, options: &EnvOptions) -> PathBuf {
+    match options.path.as_ref() {
+        Some(it) => config.cwd.join(it),
+        None => config.workspace().root().join(snapshot_file_name()),
+    }
+}
+
+/// Print the root of the project's Python environment with no decoration, for shell
+/// scripts and editor settings. The environment is created if it doesn't exist.
+pub fn print_environment_path(config: &Config) -> HuakResult<()> {
+    let python_env = config.workspace().resolve_python_environment()?;
+
+    config
+        .terminal()
+        .print_stdout(&format!("{}\n", python_env.root().display()))
+}
+
+/// Print the path to the project's Python environment's interpreter with no decoration,
+/// for shell scripts and editor settings. The environment is created if it doesn't exist.
+pub fn print_environment_python(config: &Config) -> HuakResult<()> {
+    let python_env = config.workspace().resolve_python_environment()?;
+
+    config
+        .terminal()
+        .print_stdout(&format!("{}\n", python_env.python_path().display()))
+}
+
+/// Get the "<major>.<minor>" of a Python version.
+fn minor_version(version: &str) -> String {
+    version.split('.').take(2).collect::<Vec<_>>().join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_print_environment_path() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.join("src");
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+
+        // The environment is created for scripts that ask for it before it exists.
+        print_environment_path(&config).unwrap();
+        print_environment_python(&config).unwrap();
+
+        let python_env =
+            config.workspace().resolve_python_environment().unwrap();
+
+        assert_eq!(python_env.root(), root.join(".venv"));
+        assert!(python_env.python_path().starts_with(python_env.root()));
+        assert!(python_env.python_path().exists());
+    }
+
+    #[test]
+    fn test_minor_version() {
+        assert_eq!(minor_version("3.11.4"), "3.11");
+        assert_eq!(minor_version("3.11"), "3.11");
+    }
+}
@@ -1,11 +1,30 @@
-use super::make_venv_command;
-use crate::{dependency::Dependency, Config, HuakResult, InstallOptions};
+use super::{
+    check_output, check_output_with_codes, exclude_patterns, exclude_regex,
+    isolated_tools, make_venv_command, nbqa_command, notebook_paths,
+    op_env_vars, target_paths, tool_config_args, tool_dependencies,
+    tool_dependencies_environment, tool_dependency, unstaged_paths,
+    update_index,
+};
+use crate::{
+    dependency::Dependency, Config, Error, HuakResult, InstallOptions,
+};
 use std::{process::Command, str::FromStr};
+use termcolor::Color;
 
 pub struct FormatOptions {
     /// A values vector of format options typically used for passing on arguments.
     pub values: Option<Vec<String>>,
     pub install_options: InstallOptions,
+    /// Only target Python files changed relative to a git reference. `Some(None)` compares
+    /// against the merge-base with the default branch.
+    pub changed: Option<Option<String>>,
+    /// Only target Python files staged in the git index.
+    pub staged: bool,
+    /// Re-stage the staged files after they're formatted.
+    pub update_index: bool,
+    /// Paths or glob patterns to target instead of the whole workspace, relative to the
+    /// current working directory.
+    pub paths: Vec<String>,
 }
 
 pub fn format_project(
@@ -16,36 +35,85 @@ pub fn format_project(
     let package = workspace.current_package()?;
     let mut metadata = workspace.current_local_metadata()?;
     let python_env = workspace.resolve_python_environment()?;
+    let mut terminal = config.terminal();
 
-    // Install `ruff` and `black` if they aren't already installed.
-    let format_deps = [
-        Dependency::from_str("black")?,
-        Dependency::from_str("ruff")?,
-    ];
-
-    let new_format_deps = format_deps
-        .iter()
-        .filter(|dep| {
-            !python_env.contains_module(dep.name()).unwrap_or_default()
-        })
+    let exclude = exclude_patterns(&metadata)?;
+    let targets = target_paths(
+        &workspace,
+        &options.changed,
+        options.staged,
+        &options.paths,
+        &exclude,
+    )?;
+    if targets.is_empty() {
+        return terminal.print_custom(
+            "Skipped",
+            match options.staged {
+                true => "no Python files are staged",
+                false => "no Python files have changed",
+            },
+            Color::Green,
+            true,
+        );
+    }
+    let staged = targets.clone();
+    let unstaged = match options.update_index {
+        true => unstaged_paths(&workspace, &staged)?,
+        false => Vec::new(),
+    };
+    let setting = |key| {
+        metadata
+            .metadata()
+            .huak_setting("fmt", key)
+            .and_then(|it| it.as_bool())
+    };
+    let docstrings = setting("docstrings").unwrap_or_default();
+    let notebooks = match setting("notebooks").unwrap_or(true) {
+        true => {
+            notebook_paths(&workspace, &targets, python_env.root(), &exclude)?
+        }
+        false => Vec::new(),
+    };
+    // Notebooks are formatted separately with `nbqa`.
+    let targets = targets
+        .into_iter()
+        .filter(|it| !it.ends_with(".ipynb"))
         .collect::<Vec<_>>();
 
-    if !new_format_deps.is_empty() {
-        python_env.install_packages(
-            &new_format_deps,
-            &options.install_options,
-            config,
-        )?;
+    // Install `ruff` and `black` (and `docformatter` and `nbqa` if docstrings and notebooks
+    // are formatted) if they aren't already installed, at their pinned versions.
+    let tools = tool_dependencies(&metadata)?;
+    let mut format_deps = vec![
+        tool_dependency("black", &tools)?,
+        tool_dependency("ruff", &tools)?,
+    ];
+    if docstrings {
+        format_deps.push(tool_dependency("docformatter", &tools)?);
     }
+    if !notebooks.is_empty() {
+        format_deps.push(tool_dependency("nbqa", &tools)?);
+    }
+
+    let isolated = isolated_tools(&metadata)?;
+    let tool_env = tool_dependencies_environment(
+        &format_deps,
+        isolated,
+        &python_env,
+        &options.install_options,
+        config,
+    )?;
 
-    // Add the installed `ruff` and `black` packages to the metadata file if not already there.
+    // Add the installed format packages to the metadata file if not already there and
+    // not pinned. Tools run from a tool environment aren't the project's dependencies.
     let new_format_deps = format_deps
         .iter()
         .filter(|dep| {
-            !metadata
-                .metadata()
-                .contains_dependency_any(dep)
-                .unwrap_or_default()
+            !isolated
+                && !tools.iter().any(|it| it.name() == dep.name())
+                && !metadata
+                    .metadata()
+                    .contains_dependency_any(dep)
+                    .unwrap_or_default()
         })
         .map(|dep| dep.name())
         .collect::<Vec<_>>();
@@ -68,27 +136,254 @@ pub fn format_project(
     }
 
     // Run `ruff` and `black` for formatting imports and the rest of the Python code in the workspace.
-    let mut terminal = config.terminal();
-    let mut cmd = Command::new(python_env.python_path());
-    let mut ruff_cmd = Command::new(python_env.python_path());
-    let mut ruff_args =
-        vec!["-m", "ruff", "check", ".", "--select", "I001", "--fix"];
-    make_venv_command(&mut cmd, &python_env)?;
-    make_venv_command(&mut ruff_cmd, &python_env)?;
-    let mut args = vec!["-m", "black", "."];
+    let mut black_args = Vec::new();
     if let Some(v) = options.values.as_ref() {
-        args.extend(v.iter().map(|item| item.as_str()));
-        if v.contains(&"--check".to_string()) {
-            terminal.print_warning(
-                    "this check will exit early if imports aren't sorted (see https://github.com/cnpryer/huak/issues/510)",
+        black_args.extend(v.iter().map(|item| item.as_str()));
+    }
+    let check = black_args.contains(&"--check");
+    if check && !black_args.contains(&"--diff") {
+        black_args.push("--diff");
+    }
+    let black_config = tool_config_args("black", &metadata, &black_args);
+    let ruff_config = tool_config_args("ruff", &metadata, &[]);
+    let env_vars = op_env_vars(&metadata, "fmt")?;
+    let mut cmd = Command::new(tool_env.python_path());
+    let mut ruff_cmd = Command::new(tool_env.python_path());
+    make_venv_command(&mut cmd, &tool_env, config)?;
+    cmd.envs(env_vars.iter().cloned());
+    make_venv_command(&mut ruff_cmd, &tool_env, config)?;
+    ruff_cmd.envs(env_vars.iter().cloned());
+    cmd.args(["-m", "black"])
+        .args(&targets)
+        .args(&black_args)
+        .args(&black_config)
+        .current_dir(workspace.root());
+    if let Some(it) = exclude_regex(&exclude) {
+        cmd.args(["--extend-exclude", &it]);
+    }
+    ruff_cmd
+        .args(["-m", "ruff", "check"])
+        .args(&targets)
+        .args(["--select", "I001", if check { "--diff" } else { "--fix" }])
+        .args(&ruff_config)
+        .current_dir(workspace.root());
+    if !notebooks.is_empty() {
+        ruff_cmd.args(["--extend-exclude", "*.ipynb"]);
+    }
+    for it in exclude.iter() {
+        ruff_cmd.args(["--extend-exclude", it]);
+    }
+    // `docformatter`'s `--exclude` takes every argument that follows it, so it's added
+    // after the mode's arguments.
+    let docformatter_cmd = |mode: &[&str]| -> HuakResult<Command> {
+        let mut cmd = Command::new(tool_env.python_path());
+        make_venv_command(&mut cmd, &tool_env, config)?;
+        cmd.envs(env_vars.iter().cloned());
+        cmd.args(["-m", "docformatter", "-r"])
+            .args(&targets)
+            .args(mode)
+            .args(["--exclude", python_env.name()?.as_str()])
+            .args(&exclude)
+            .current_dir(workspace.root());
+        Ok(cmd)
+    };
+    let mut nbqa_ruff_cmd =
+        nbqa_command("ruff", &notebooks, &tool_env, config)?;
+    nbqa_ruff_cmd.envs(env_vars.iter().cloned());
+    nbqa_ruff_cmd
+        .args(["--select", "I001"])
+        .args(&ruff_config)
+        .current_dir(workspace.root());
+    let mut nbqa_black_cmd =
+        nbqa_command("black", &notebooks, &tool_env, config)?;
+    nbqa_black_cmd.envs(env_vars.iter().cloned());
+    nbqa_black_cmd
+        .args(&black_args)
+        .args(&black_config)
+        .current_dir(workspace.root());
+
+    if !check {
+        if !targets.is_empty() {
+            terminal.run_command(&mut ruff_cmd)?;
+            if docstrings {
+                // `docformatter` exits with 3 when it changes files.
+                check_output_with_codes(
+                    &mut docformatter_cmd(&["--in-place"])?,
+                    &[0, 3],
+                    &mut terminal,
+                )?;
+            }
+            terminal.run_command(&mut cmd)?;
+        }
+        if !notebooks.is_empty() {
+            nbqa_ruff_cmd.arg("--fix");
+            terminal.run_command(&mut nbqa_ruff_cmd)?;
+            terminal.run_command(&mut nbqa_black_cmd)?;
+        }
+        if options.update_index {
+            update_index(&workspace, &staged, &unstaged, &mut terminal)?;
+        }
+        return Ok(());
+    }
+
+    // Every tool is run in check mode so that every unformatted file is reported, rather
+    // than stopping at the first tool to fail.
+    let mut diffs = Vec::new();
+    let mut paths = Vec::new();
+    if !targets.is_empty() {
+        diffs.extend(parse_unified_diffs(&check_output(
+            &mut ruff_cmd,
+            &mut terminal,
+        )?));
+        if docstrings {
+            let output = check_output_with_codes(
+                &mut docformatter_cmd(&["--check", "--diff"])?,
+                &[0, 3],
+                &mut terminal,
+            )?;
+            // `docformatter` prefixes the paths in its diff headers with "before/".
+            diffs.extend(parse_unified_diffs(&output).into_iter().map(
+                |mut it| {
+                    if let Some(path) = it.path.strip_prefix("before/") {
+                        it.path = path.to_string();
+                    }
+                    it
+                },
+            ));
+        }
+        diffs.extend(parse_unified_diffs(&check_output(
+            &mut cmd,
+            &mut terminal,
+        )?));
+    }
+    if !notebooks.is_empty() {
+        // `nbqa` can't show the imports `ruff` would sort, so notebooks with unsorted
+        // imports are listed instead.
+        let output = check_output(&mut nbqa_ruff_cmd, &mut terminal)?;
+        for line in output.lines() {
+            let Some((path, _)) = line.split_once(".ipynb:") else {
+                continue;
+            };
+            let path = format!("{path}.ipynb");
+            if !paths.contains(&path) {
+                terminal.print_custom(
+                    "Unsorted",
+                    format!("{path} (imports)"),
+                    Color::Cyan,
+                    true,
                 )?;
-            ruff_args.retain(|item| *item != "--fix")
+                paths.push(path);
+            }
         }
+        // `nbqa` suffixes the paths in its diff headers with the cell, like ":cell_1".
+        let output = check_output(&mut nbqa_black_cmd, &mut terminal)?;
+        diffs.extend(parse_unified_diffs(&output).into_iter().map(|mut it| {
+            if let Some((path, _)) = it.path.split_once(".ipynb:") {
+                it.path = format!("{path}.ipynb");
+            }
+            it
+        }));
     }
-    ruff_cmd.args(ruff_args).current_dir(workspace.root());
-    terminal.run_command(&mut ruff_cmd)?;
-    cmd.args(args).current_dir(workspace.root());
-    terminal.run_command(&mut cmd)
+
+    for diff in diffs.iter() {
+        terminal.print_custom(
+            "Diff",
+            format!("{} (+{} -{})", diff.path, diff.added, diff.removed),
+            Color::Cyan,
+            true,
+        )?;
+        terminal.print_diff(&diff.text)?;
+        if !paths.contains(&diff.path) {
+            paths.push(diff.path.clone());
+        }
+    }
+
+    if paths.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::CheckFailed(format!(
+            "{} file(s) would be reformatted",
+            paths.len()
+        )))
+    }
+}
+
+/// A unified diff of the changes a formatter would make to a file.
+#[derive(Debug, PartialEq, Eq)]
+struct FileDiff {
+    /// The path to the file.
+    path: String,
+    /// The number of lines the formatter would add.
+    added: usize,
+    /// The number of lines the formatter would remove.
+    removed: usize,
+    /// The text of the diff.
+    text: String,
+}
+
+/// Parse the `FileDiff`s from unified diff output. Hunk line counts are tracked so that
+/// removed lines starting with "--" aren't mistaken for file headers.
+fn parse_unified_diffs(output: &str) -> Vec<FileDiff> {
+    let mut diffs: Vec<FileDiff> = Vec::new();
+    let mut lines = output.lines().peekable();
+    let (mut old, mut new) = (0, 0);
+
+    while let Some(line) = lines.next() {
+        if old == 0 && new == 0 {
+            let header = line.strip_prefix("--- ").filter(|_| {
+                lines.peek().map_or(false, |it| it.starts_with("+++ "))
+            });
+            if let Some(path) = header {
+                let path = path.split('\t').next().unwrap_or(path).trim();
+                let next = lines.next().unwrap_or_default();
+                diffs.push(FileDiff {
+                    path: path.to_string(),
+                    added: 0,
+                    removed: 0,
+                    text: format!("{line}\n{next}\n"),
+                });
+                continue;
+            }
+        }
+        let Some(diff) = diffs.last_mut() else {
+            continue;
+        };
+
+        if let Some(hunk) = line.strip_prefix("@@ ") {
+            (old, new) = hunk_line_counts(hunk);
+        } else if line.starts_with('+') {
+            diff.added += 1;
+            new = usize::saturating_sub(new, 1);
+        } else if line.starts_with('-') {
+            diff.removed += 1;
+            old = usize::saturating_sub(old, 1);
+        } else if line.starts_with(' ') || line.is_empty() {
+            old = usize::saturating_sub(old, 1);
+            new = usize::saturating_sub(new, 1);
+        } else if !line.starts_with('\\') {
+            // Skip output that isn't part of the diff.
+            continue;
+        }
+        diff.text.push_str(line);
+        diff.text.push('\n');
+    }
+
+    diffs
+}
+
+/// Get the old and new line counts from a hunk header like "-1,3 +1,4 @@".
+fn hunk_line_counts(hunk: &str) -> (usize, usize) {
+    let count = |range: Option<&str>| {
+        range
+            .and_then(|it| it.split(',').nth(1).or(Some("1")))
+            .and_then(|it| it.parse().ok())
+            .unwrap_or_default()
+    };
+    let mut ranges = hunk.split_whitespace();
+    let old = count(ranges.next().and_then(|it| it.strip_prefix('-')));
+    let new = count(ranges.next().and_then(|it| it.strip_prefix('+')));
+
+    (old, new)
 }
 
 #[cfg(test)]
@@ -99,6 +394,7 @@ mod tests {
         ops::{test_config, test_venv},
         test_resources_dir_path, Verbosity,
     };
+    use std::path::Path;
     use tempfile::tempdir;
 
     #[test]
@@ -123,6 +419,10 @@ def fn( ):
         let options = FormatOptions {
             values: None,
             install_options: InstallOptions { values: None },
+            changed: None,
+            staged: false,
+            update_index: false,
+            paths: Vec::new(),
         };
 
         format_project(&config, &options).unwrap();
@@ -136,4 +436,60 @@ def fn( ):
 "#
         );
     }
+
+    #[test]
+    fn test_parse_unified_diffs() {
+        let output = r#"--- src/a.py	2023-05-01 00:00:00.000000 +0000
++++ src/a.py	2023-05-01 00:00:01.000000 +0000
+@@ -1,3 +1,2 @@
+--- not a header
+-def fn( ):
++def fn():
+     pass
+--- src/b.py
++++ src/b.py
+@@ -1 +1,2 @@
+ import os
++import sys
+"#;
+
+        let diffs = parse_unified_diffs(output);
+
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].path, "src/a.py");
+        assert_eq!((diffs[0].added, diffs[0].removed), (1, 2));
+        assert!(diffs[0].text.contains("--- not a header"));
+        assert_eq!(diffs[1].path, "src/b.py");
+        assert_eq!((diffs[1].added, diffs[1].removed), (1, 0));
+    }
+
+    #[test]
+    fn test_notebook_paths() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("mock-project");
+        for path in [
+            root.join("notebooks").join("explore.ipynb"),
+            root.join("notebooks")
+                .join(".ipynb_checkpoints")
+                .join("explore-checkpoint.ipynb"),
+            root.join(".venv").join("share").join("example.ipynb"),
+        ] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "{}").unwrap();
+        }
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let ws = config.workspace();
+
+        let notebooks =
+            notebook_paths(&ws, &[".".to_string()], &root.join(".venv"), &[])
+                .unwrap();
+
+        assert_eq!(
+            notebooks,
+            vec![format!(
+                "{}",
+                Path::new("notebooks").join("explore.ipynb").display()
+            )]
+        );
+    }
 }
@@ -0,0 +1,226 @@
+use crate::{
+    lockfile::lockfile_file_name,
+    python_environment::{Interpreter, PythonImplementation},
+    Config, Error, HuakResult,
+};
+use serde_json::json;
+use std::path::Path;
+use termcolor::Color;
+
+/// Where the devcontainer's Python environment is created. It's outside of the workspace
+/// so that mounting the project doesn't hide it.
+const DEVCONTAINER_VENV: &str = "/opt/venv";
+
+/// Generate a ".devcontainer" with a "devcontainer.json" and a "Dockerfile" for the
+/// workspace. The image uses the Python version the project selects (see
+/// `Workspace::select_python_interpreter`) and installs the dependencies pinned by its
+/// lockfile with `huak install --frozen`.
+pub fn generate_devcontainer(config: &Config) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let mut terminal = config.terminal();
+
+    if workspace.current_lockfile()?.is_none() {
+        return Err(Error::HuakConfigurationError(format!(
+            "there's no {} for the devcontainer to install from, run `huak lock` first",
+            lockfile_file_name()
+        )));
+    }
+    let dir = workspace.root().join(".devcontainer");
+    if dir.exists() && !terminal.confirm("overwrite .devcontainer")? {
+        return Ok(());
+    }
+
+    let environment = workspace.environment();
+    let (interpreter, _) = workspace
+        .select_python_interpreter(environment.interpreters().interpreters())?;
+    if interpreter.implementation() == PythonImplementation::GraalPy {
+        terminal.print_warning(
+            "there's no GraalPy image for the devcontainer, so CPython is used",
+        )?;
+    }
+    // Only VS Code extensions for the tools the project configures are recommended.
+    let mut extensions = vec!["ms-python.python", "charliermarsh.ruff"];
+    if metadata.metadata().tool_table("mypy").is_some() {
+        extensions.push("ms-python.mypy-type-checker");
+    }
+
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(
+        dir.join("devcontainer.json"),
+        devcontainer_json(metadata.metadata().project_name(), &extensions)?,
+    )?;
+    std::fs::write(
+        dir.join("Dockerfile"),
+        dockerfile_contents(&python_image(&interpreter), lockfile_file_name()),
+    )?;
+
+    terminal.print_custom(
+        "Generated",
+        format!(".devcontainer with {}", python_image(&interpreter)),
+        Color::Green,
+        true,
+    )
+}
+
+/// Get the Docker image for an `Interpreter`'s implementation and minor version.
+pub(super) fn python_image(interpreter: &Interpreter) -> String {
+    let release = interpreter.version().release();
+    let version = format!("{}.{}", release[0], release.get(1).unwrap_or(&0));
+    match interpreter.implementation() {
+        PythonImplementation::PyPy => format!("pypy:{version}-slim"),
+        PythonImplementation::CPython | PythonImplementation::GraalPy => {
+            format!("python:{version}-slim")
+        }
+    }
+}
+
+/// Get the contents of a devcontainer's "devcontainer.json" building its "Dockerfile"
+/// with the workspace as the context.
+fn devcontainer_json(name: &str, extensions: &[&str]) -> HuakResult<String> {
+    let value = json!({
+        "name": name,
+        "build": {
+            "dockerfile": "Dockerfile",
+            "context": "..",
+        },
+        "containerEnv": {
+            "VIRTUAL_ENV": DEVCONTAINER_VENV,
+        },
+        "customizations": {
+            "vscode": {
+                "extensions": extensions,
+                "settings": {
+                    "python.defaultInterpreterPath": format!("{DEVCONTAINER_VENV}/bin/python"),
+                },
+            },
+        },
+    });
+
+    Ok(format!("{}\n", serde_json::to_string_pretty(&value)?))
+}
+
+/// Get the contents of a devcontainer's "Dockerfile". huak is installed with the image's
+/// Python and the project's dependencies are installed from its lockfile into an
+/// environment outside of the workspace.
+fn dockerfile_contents(image: &str, lockfile_name: &str) -> String {
+    let version = env!("CARGO_PKG_VERSION");
+
+    format!(
+        r#"# Generated by huak (huak generate devcontainer).
+FROM {image}
+
+RUN pip install --no-cache-dir huak=={version} \
+    && python -m venv {DEVCONTAINER_VENV}
+ENV VIRTUAL_ENV={DEVCONTAINER_VENV}
+ENV PATH="$VIRTUAL_ENV/bin:$PATH"
+
+WORKDIR /workspace
+COPY pyproject.toml {lockfile_name} ./
+RUN huak install --frozen
+"#
+    )
+}
+
+/// Generate a direnv ".envrc" for the workspace that activates its Python environment
+/// and loads its ".env" file whenever the workspace is entered.
+pub fn generate_envrc(config: &Config) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let python_env = workspace.resolve_python_environment()?;
+    let mut terminal = config.terminal();
+
+    let path = workspace.root().join(".envrc");
+    if path.exists() && !terminal.confirm("overwrite .envrc")? {
+        return Ok(());
+    }
+
+    let env_path = python_env.root();
+    let env_path = env_path.strip_prefix(workspace.root()).unwrap_or(env_path);
+    let executables_dir_name = python_env
+        .executables_dir_path()
+        .strip_prefix(python_env.root())
+        .unwrap_or(Path::new("bin"));
+    std::fs::write(&path, envrc_contents(env_path, executables_dir_name))?;
+
+    terminal.print_custom(
+        "Generated",
+        ".envrc (run `direnv allow` to enable it)",
+        Color::Green,
+        true,
+    )
+}
+
+/// Get the contents of a ".envrc" activating the Python environment at `env_path` like
+/// huak's commands do, setting `VIRTUAL_ENV` and adding its executables directory to
+/// `PATH`. Relative paths are relative to the ".envrc".
+fn envrc_contents(env_path: &Path, executables_dir_name: &Path) -> String {
+    let env_path = env_path.to_string_lossy().replace('\\', "/");
+    let executables_dir_name =
+        executables_dir_name.to_string_lossy().replace('\\', "/");
+
+    format!(
+        r#"# Generated by huak (huak generate envrc).
+export VIRTUAL_ENV="$(expand_path "{env_path}")"
+PATH_add "$VIRTUAL_ENV/{executables_dir_name}"
+unset PYTHONHOME
+
+dotenv_if_exists .env
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envrc_contents() {
+        assert_eq!(
+            envrc_contents(Path::new(".venv"), Path::new("bin")),
+            r#"# Generated by huak (huak generate envrc).
+export VIRTUAL_ENV="$(expand_path ".venv")"
+PATH_add "$VIRTUAL_ENV/bin"
+unset PYTHONHOME
+
+dotenv_if_exists .env
+"#
+        );
+    }
+
+    #[test]
+    fn test_dockerfile_contents() {
+        let version = env!("CARGO_PKG_VERSION");
+
+        assert_eq!(
+            dockerfile_contents("python:3.11-slim", "huak.lock"),
+            format!(
+                r#"# Generated by huak (huak generate devcontainer).
+FROM python:3.11-slim
+
+RUN pip install --no-cache-dir huak=={version} \
+    && python -m venv /opt/venv
+ENV VIRTUAL_ENV=/opt/venv
+ENV PATH="$VIRTUAL_ENV/bin:$PATH"
+
+WORKDIR /workspace
+COPY pyproject.toml huak.lock ./
+RUN huak install --frozen
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_devcontainer_json() {
+        let contents =
+            devcontainer_json("my-project", &["ms-python.python"]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+
+        assert_eq!(value["name"], "my-project");
+        assert_eq!(value["build"]["context"], "..");
+        assert_eq!(
+            value["customizations"]["vscode"]["extensions"][0],
+            "ms-python.python"
+        );
+    }
+}
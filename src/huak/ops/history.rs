@@ -0,0 +1,185 @@
+use crate::{
+    journal::{history_records, record_command},
+    sys::Table,
+    Config, HuakResult,
+};
+use std::time::SystemTime;
+use termcolor::Color;
+
+pub struct HistoryOptions {
+    /// Only show the most recent records.
+    pub limit: Option<usize>,
+    /// Print the records as JSON.
+    pub json: bool,
+}
+
+/// Print the commands logged for the workspace from oldest to newest with their exit
+/// status and what they changed.
+pub fn print_history(
+    config: &Config,
+    options: &HistoryOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut terminal = config.terminal();
+
+    let mut records = history_records(workspace.root())?;
+    if let Some(limit) = options.limit {
+        records.drain(..records.len().saturating_sub(limit));
+    }
+
+    if options.json {
+        return terminal.print_stdout(&format!(
+            "{}\n",
+            serde_json::to_string_pretty(&records)?
+        ));
+    }
+
+    if records.is_empty() {
+        return terminal.print_custom(
+            "History",
+            "no commands have been recorded",
+            Color::Green,
+            true,
+        );
+    }
+
+    let mut table = Table::new(["time", "command", "status", "changes"]);
+    for it in records.iter() {
+        let status = match it.status {
+            0 => "ok".to_string(),
+            code => format!("exit {code}"),
+        };
+        let mut changes = it.changes.clone();
+        if let Some(error) = it.error.as_ref() {
+            changes.insert(0, format!("error: {error}"));
+        }
+        table.push_row([
+            it.time(),
+            it.command.clone(),
+            status,
+            changes.join(", "),
+        ]);
+    }
+
+    terminal.print_table(&table)
+}
+
+/// Log a command that started at `started` to the workspace's history. `args` are the
+/// arguments huak was invoked with, which are logged with any passed on to other commands
+/// and the values of credential options redacted. Nothing is logged outside of a project.
+pub fn record_history(
+    config: &Config,
+    args: &[String],
+    started: SystemTime,
+    status: i32,
+    error: Option<String>,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    if workspace.current_local_metadata().is_err() {
+        return Ok(());
+    }
+
+    record_command(
+        workspace.root(),
+        &redacted_command(args),
+        started,
+        status,
+        error,
+    )
+}
+
+/// The options whose values are credentials.
+const CREDENTIAL_OPTIONS: [&str; 2] = ["--password", "--token"];
+
+/// The text logged in place of redacted arguments.
+const REDACTED: &str = "<redacted>";
+
+/// Get the huak command for `args` with everything after `--` and the values of
+/// credential options replaced, since they can contain secrets.
+fn redacted_command(args: &[String]) -> String {
+    let mut command = vec!["huak".to_string()];
+    let mut args = args.iter();
+    while let Some(it) = args.next() {
+        if it == "--" {
+            if args.next().is_some() {
+                command.extend(["--".to_string(), REDACTED.to_string()]);
+            }
+            break;
+        }
+        command.push(it.clone());
+        if CREDENTIAL_OPTIONS.contains(&it.as_str()) {
+            if args.next().is_some() {
+                command.push(REDACTED.to_string());
+            }
+        } else if let Some((option, _)) = it.split_once('=') {
+            if CREDENTIAL_OPTIONS.contains(&option) {
+                command.pop();
+                command.push(format!("{option}={REDACTED}"));
+            }
+        }
+    }
+
+    command.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_record_history() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+
+        record_history(
+            &config,
+            &["add".to_string(), "click".to_string()],
+            SystemTime::now(),
+            0,
+            None,
+        )
+        .unwrap();
+        let options = HistoryOptions {
+            limit: Some(1),
+            json: false,
+        };
+
+        print_history(&config, &options).unwrap();
+        let records = history_records(&root).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].command, "huak add click");
+        assert!(records[0].changes.is_empty());
+    }
+
+    #[test]
+    fn test_redacted_command() {
+        let args = |it: &str| {
+            it.split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            redacted_command(&args("publish -- --password secret")),
+            "huak publish -- <redacted>"
+        );
+        assert_eq!(
+            redacted_command(&args("x httpie --token secret --from=httpie")),
+            "huak x httpie --token <redacted> --from=httpie"
+        );
+        assert_eq!(
+            redacted_command(&args("x twine --password=secret")),
+            "huak x twine --password=<redacted>"
+        );
+        assert_eq!(redacted_command(&args("install --")), "huak install");
+    }
+}
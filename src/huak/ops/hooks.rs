@@ -0,0 +1,267 @@
+use crate::{git, metadata::Metadata, Config, Error, HuakResult};
+use std::path::Path;
+use termcolor::Color;
+
+/// The line identifying hook scripts huak manages.
+const HOOK_MARKER: &str = "# Managed by huak.";
+/// The git hooks installed when `[tool.huak.hooks.git]` isn't configured.
+const DEFAULT_HOOKS: [(&str, &[&str]); 2] = [
+    ("pre-commit", &["fmt --staged --update-index"]),
+    ("pre-push", &["lint --changed", "test --changed"]),
+];
+/// The client-side hooks git runs.
+const GIT_HOOKS: [&str; 13] = [
+    "applypatch-msg",
+    "commit-msg",
+    "post-applypatch",
+    "post-checkout",
+    "post-commit",
+    "post-merge",
+    "post-rewrite",
+    "pre-applypatch",
+    "pre-auto-gc",
+    "pre-commit",
+    "pre-merge-commit",
+    "pre-push",
+    "pre-rebase",
+];
+
+pub struct HooksOptions {
+    /// Replace existing hooks that weren't installed by huak.
+    pub force: bool,
+}
+
+/// Install git hooks running the huak commands configured under `[tool.huak.hooks.git]`,
+/// like `pre-commit = ["fmt --staged"]`. Hooks huak installed before that are no longer
+/// configured are removed.
+pub fn install_project_hooks(
+    config: &Config,
+    options: &HooksOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let mut terminal = config.terminal();
+
+    let root = workspace.root().canonicalize()?;
+    let hooks_dir = git::hooks_dir(&root)?;
+    let project_dir = root
+        .strip_prefix(git::workdir_path(&root)?)
+        .unwrap_or(Path::new(""))
+        .to_path_buf();
+    let hooks = configured_hooks(metadata.metadata())?;
+
+    for (name, _) in hooks.iter() {
+        let path = hooks_dir.join(name);
+        if path.exists() && !is_managed(&path) && !options.force {
+            return Err(Error::HuakConfigurationError(format!(
+                "{} already exists and wasn't installed by huak (use --force to replace it)",
+                path.display()
+            )));
+        }
+    }
+
+    std::fs::create_dir_all(&hooks_dir)?;
+    for (name, commands) in hooks.iter() {
+        let path = hooks_dir.join(name);
+        std::fs::write(&path, hook_script(commands, &project_dir))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(
+                &path,
+                std::fs::Permissions::from_mode(0o755),
+            )?;
+        }
+        terminal.print_custom(
+            "Installed",
+            format!("{name} hook ({})", commands.join(", ")),
+            Color::Green,
+            true,
+        )?;
+    }
+
+    // Remove hooks huak installed that are no longer configured.
+    for name in GIT_HOOKS {
+        let path = hooks_dir.join(name);
+        if !hooks.iter().any(|(it, _)| it == name) && is_managed(&path) {
+            std::fs::remove_file(&path)?;
+            terminal.print_custom(
+                "Removed",
+                format!("{name} hook"),
+                Color::Green,
+                true,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove the git hooks installed with `install_project_hooks`. Other hooks are left
+/// as-is.
+pub fn uninstall_project_hooks(config: &Config) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut terminal = config.terminal();
+    let hooks_dir = git::hooks_dir(workspace.root())?;
+
+    let mut removed = 0;
+    for name in GIT_HOOKS {
+        let path = hooks_dir.join(name);
+        if is_managed(&path) {
+            std::fs::remove_file(&path)?;
+            removed += 1;
+        }
+    }
+
+    terminal.print_custom(
+        "Removed",
+        format!("{removed} hook(s)"),
+        Color::Green,
+        true,
+    )
+}
+
+/// Get the git hooks to install and the huak commands each runs.
+fn configured_hooks(
+    metadata: &Metadata,
+) -> HuakResult<Vec<(String, Vec<String>)>> {
+    let Some(value) = metadata.huak_setting("hooks", "git") else {
+        return Ok(DEFAULT_HOOKS
+            .iter()
+            .map(|(name, commands)| {
+                (
+                    name.to_string(),
+                    commands.iter().map(|it| it.to_string()).collect(),
+                )
+            })
+            .collect());
+    };
+    let table = value.as_table().ok_or_else(|| {
+        Error::HuakConfigurationError(
+            "[tool.huak.hooks.git] must be a table of hooks".to_string(),
+        )
+    })?;
+
+    let mut hooks = Vec::new();
+    for (name, value) in table {
+        if !GIT_HOOKS.contains(&name.as_str()) {
+            return Err(Error::HuakConfigurationError(format!(
+                "[tool.huak.hooks.git] {name} isn't a git hook"
+            )));
+        }
+        let invalid = || {
+            Error::HuakConfigurationError(format!(
+                "[tool.huak.hooks.git] {name} must be a list of huak commands"
+            ))
+        };
+        let commands = value
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|it| {
+                it.as_str()
+                    .map(|it| it.trim().to_string())
+                    .filter(|it| !it.is_empty())
+                    .ok_or_else(invalid)
+            })
+            .collect::<HuakResult<Vec<_>>>()?;
+        // Hooks configured without commands aren't installed.
+        if !commands.is_empty() {
+            hooks.push((name.to_string(), commands));
+        }
+    }
+
+    Ok(hooks)
+}
+
+/// Generate a hook script running huak commands from the project's directory, relative
+/// to the root of the repository where git runs hooks. The script stops at the first
+/// command to fail.
+fn hook_script(commands: &[String], project_dir: &Path) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n{HOOK_MARKER} Configure it under [tool.huak.hooks.git] and rerun `huak hooks install`.\nset -e\n"
+    );
+    let project_dir = project_dir.to_string_lossy().replace('\\', "/");
+    if !project_dir.is_empty() {
+        script.push_str(&format!("cd \"{project_dir}\"\n"));
+    }
+    for it in commands {
+        script.push_str(&format!("huak {it}\n"));
+    }
+
+    script
+}
+
+/// Check if a hook script was installed by huak.
+fn is_managed(path: &Path) -> bool {
+    std::fs::read_to_string(path).map_or(false, |it| {
+        it.lines().any(|line| line.starts_with(HOOK_MARKER))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_install_project_hooks() {
+        let dir = tempdir().unwrap();
+        let repo = dir.path().canonicalize().unwrap();
+        git::init(&repo).unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            repo.join("mock-project"),
+        )
+        .unwrap();
+        let root = repo.join("mock-project");
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let hooks_dir = repo.join(".git").join("hooks");
+        std::fs::create_dir_all(&hooks_dir).unwrap();
+        std::fs::write(hooks_dir.join("pre-commit"), "#!/bin/sh\n").unwrap();
+
+        assert!(
+            install_project_hooks(&config, &HooksOptions { force: false })
+                .is_err()
+        );
+        install_project_hooks(&config, &HooksOptions { force: true }).unwrap();
+
+        let pre_commit =
+            std::fs::read_to_string(hooks_dir.join("pre-commit")).unwrap();
+        assert!(pre_commit.contains("cd \"mock-project\"\n"));
+        assert!(pre_commit.ends_with("huak fmt --staged --update-index\n"));
+        assert!(is_managed(&hooks_dir.join("pre-push")));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(hooks_dir.join("pre-push"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
+        // Hooks that are no longer configured are removed.
+        let ws = config.workspace();
+        let mut metadata = ws.current_local_metadata().unwrap();
+        std::fs::write(
+            metadata.path(),
+            format!(
+                "{}\n[tool.huak.hooks.git]\npre-commit = [\"lint --staged\"]\n",
+                std::fs::read_to_string(metadata.path()).unwrap()
+            ),
+        )
+        .unwrap();
+        metadata = ws.current_local_metadata().unwrap();
+        assert_eq!(
+            configured_hooks(metadata.metadata()).unwrap(),
+            [("pre-commit".to_string(), vec!["lint --staged".to_string()])]
+        );
+        install_project_hooks(&config, &HooksOptions { force: false }).unwrap();
+        assert!(!hooks_dir.join("pre-push").exists());
+
+        uninstall_project_hooks(&config).unwrap();
+        assert!(!hooks_dir.join("pre-commit").exists());
+    }
+}
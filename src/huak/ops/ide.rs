@@ -0,0 +1,206 @@
+use crate::{python_environment::PythonEnvironment, Config, Error, HuakResult};
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use termcolor::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An editor `generate_ide_config` can configure.
+pub enum Editor {
+    VsCode,
+    PyCharm,
+}
+
+pub struct IdeOptions {
+    /// The editor to configure.
+    pub editor: Editor,
+}
+
+/// Write or update an editor's configuration for the project so that it uses the
+/// project's Python environment and the tools huak installs into it.
+pub fn generate_ide_config(
+    config: &Config,
+    options: &IdeOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+    let mut terminal = config.terminal();
+
+    match options.editor {
+        Editor::VsCode => {
+            let path = workspace.root().join(".vscode").join("settings.json");
+            let mut settings = match path.exists() {
+                true => serde_json::from_str(&std::fs::read_to_string(&path)?)
+                    .map_err(|e| {
+                        Error::HuakConfigurationError(format!(
+                            "{} couldn't be updated ({e}), note that comments aren't supported",
+                            path.display()
+                        ))
+                    })?,
+                false => Value::Object(Map::new()),
+            };
+            update_vscode_settings(
+                &mut settings,
+                workspace.root(),
+                &python_env,
+            )?;
+            std::fs::create_dir_all(workspace.root().join(".vscode"))?;
+            std::fs::write(
+                &path,
+                format!("{}\n", serde_json::to_string_pretty(&settings)?),
+            )?;
+
+            terminal.print_custom(
+                "Updated",
+                ".vscode/settings.json",
+                Color::Green,
+                true,
+            )
+        }
+        Editor::PyCharm => {
+            let release = python_env.python_version().release();
+            let sdk_name = format!(
+                "Python {}.{} ({})",
+                release[0],
+                release[1],
+                metadata.metadata().project_name()
+            );
+            let path = workspace.root().join(".idea").join("misc.xml");
+            let contents = match path.exists() {
+                true => std::fs::read_to_string(&path)?,
+                false => DEFAULT_PYCHARM_MISC_XML.to_string(),
+            };
+            std::fs::create_dir_all(workspace.root().join(".idea"))?;
+            std::fs::write(&path, pycharm_misc_xml(&contents, &sdk_name)?)?;
+
+            terminal.print_custom(
+                "Updated",
+                ".idea/misc.xml",
+                Color::Green,
+                true,
+            )?;
+            // PyCharm keeps interpreters in a global table, so the interpreter can only be
+            // referred to by name.
+            terminal.print_custom(
+                "Hint",
+                format!(
+                    "add {} as an existing interpreter named \"{sdk_name}\" under Settings > Project > Python Interpreter",
+                    python_env.python_path().display()
+                ),
+                Color::Cyan,
+                true,
+            )
+        }
+    }
+}
+
+const DEFAULT_PYCHARM_MISC_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project version="4">
+</project>
+"#;
+
+/// Set the VS Code settings pointing at the Python environment's interpreter and tools.
+/// Paths inside the workspace are written relative to it so the settings can be shared.
+fn update_vscode_settings(
+    settings: &mut Value,
+    root: &Path,
+    python_env: &PythonEnvironment,
+) -> HuakResult<()> {
+    let Some(settings) = settings.as_object_mut() else {
+        return Err(Error::HuakConfigurationError(
+            ".vscode/settings.json isn't a JSON object".to_string(),
+        ));
+    };
+
+    let path = |it: &Path| match it.strip_prefix(root) {
+        Ok(relative) => format!(
+            "${{workspaceFolder}}/{}",
+            relative.to_string_lossy().replace('\\', "/")
+        ),
+        Err(_) => it.display().to_string(),
+    };
+    let executable = |name: &str| {
+        let mut it = python_env.executables_dir_path().join(name);
+        if cfg!(windows) {
+            it.set_extension("exe");
+        }
+        json!([path(&it)])
+    };
+
+    settings.insert(
+        "python.defaultInterpreterPath".to_string(),
+        json!(path(python_env.python_path())),
+    );
+    settings.insert("ruff.path".to_string(), executable("ruff"));
+    settings.insert("mypy-type-checker.path".to_string(), executable("mypy"));
+    settings.insert("black-formatter.path".to_string(), executable("black"));
+
+    // Keep a formatter the user already chose.
+    let python = settings
+        .entry("[python]")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(python) = python.as_object_mut() {
+        python
+            .entry("editor.defaultFormatter")
+            .or_insert_with(|| json!("ms-python.black-formatter"));
+    }
+
+    Ok(())
+}
+
+/// Get the contents of a PyCharm ".idea/misc.xml" using the Python SDK (interpreter)
+/// with the name `sdk_name`.
+fn pycharm_misc_xml(contents: &str, sdk_name: &str) -> HuakResult<String> {
+    let sdk_name = sdk_name
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;");
+    let component =
+        Regex::new(r#"<component name="ProjectRootManager"[^>]*?/?>"#)?;
+    let element = format!(
+        r#"<component name="ProjectRootManager" version="2" project-jdk-name="{sdk_name}" project-jdk-type="Python SDK" />"#
+    );
+
+    if let Some(it) = component.find(contents) {
+        // Keep the element open if it has children.
+        let element = match it.as_str().ends_with("/>") {
+            true => element,
+            false => element.replace(" />", ">"),
+        };
+        return Ok(component.replace(contents, element.as_str()).to_string());
+    }
+
+    match contents.rfind("</project>") {
+        Some(i) => {
+            Ok(format!("{}  {element}\n{}", &contents[..i], &contents[i..]))
+        }
+        None => Err(Error::HuakConfigurationError(format!(
+            "{} isn't a PyCharm project file",
+            PathBuf::from(".idea").join("misc.xml").display()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pycharm_misc_xml() {
+        let contents =
+            pycharm_misc_xml(DEFAULT_PYCHARM_MISC_XML, "Python 3.11 (proj)")
+                .unwrap();
+        let updated =
+            pycharm_misc_xml(&contents, "Python 3.12 (proj)").unwrap();
+
+        assert_eq!(
+            updated,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<project version="4">
+  <component name="ProjectRootManager" version="2" project-jdk-name="Python 3.12 (proj)" project-jdk-type="Python SDK" />
+</project>
+"#
+        );
+    }
+}
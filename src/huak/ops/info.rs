@@ -0,0 +1,393 @@
+use crate::{
+    dist_info::{scan_site_packages, DistInfo},
+    index::{self, ProjectInfo},
+    package::canonical_package_name,
+    Config, Error, HuakResult,
+};
+use pep440_rs::Version;
+use regex::Regex;
+use std::str::FromStr;
+use termcolor::Color;
+
+pub struct InfoOptions {
+    /// The name of the package to inspect.
+    pub name: String,
+    /// List the files installed with the package.
+    pub files: bool,
+    /// List the extras the package provides and what each one requires.
+    pub extras: bool,
+}
+
+/// Display a package's metadata, combining what's installed in the project's Python
+/// environment with the package index's latest release. Packages that are only
+/// installed, or only on the index, are displayed with what's available.
+pub fn print_package_info(
+    config: &Config,
+    options: &InfoOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut terminal = config.terminal();
+    let name = normalize(&options.name)?;
+
+    // Inspecting a package shouldn't create an environment.
+    let dists = match workspace.current_python_environment() {
+        Ok(it) => scan_site_packages(it.site_packages_dir_path())?,
+        Err(Error::PythonEnvironmentNotFound) => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    let installed = dists
+        .iter()
+        .find(|it| normalize(it.name()).map_or(false, |it| it == name));
+    let remote = match config
+        .client()
+        .and_then(|client| index::project_info(&client, &options.name))
+    {
+        Ok(it) => it,
+        Err(e) => {
+            terminal.print_warning(format!(
+                "failed to query the package index: {e}"
+            ))?;
+            None
+        }
+    };
+    if installed.is_none() && remote.is_none() {
+        return Err(Error::PackageNotFound(options.name.clone()));
+    }
+
+    let mut declared = Vec::new();
+    if let Ok(metadata) = workspace.current_local_metadata() {
+        let metadata = metadata.metadata();
+        if metadata
+            .dependencies()
+            .unwrap_or_default()
+            .iter()
+            .any(|it| normalize(&it.name).map_or(false, |it| it == name))
+        {
+            declared.push("dependencies".to_string());
+        }
+        for (group, deps) in
+            metadata.optional_dependencies().into_iter().flatten()
+        {
+            if deps
+                .iter()
+                .any(|it| normalize(&it.name).map_or(false, |it| it == name))
+            {
+                declared.push(format!("optional-dependencies.{group}"));
+            }
+        }
+    }
+    let mut lines =
+        package_info(installed, remote.as_ref(), &dists, !declared.is_empty());
+    if !declared.is_empty() {
+        lines.push(("declared-in", declared.join(", ")));
+    }
+    let width = lines.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    for (key, value) in lines {
+        terminal.print_stdout(&format!("{key:width$}  {value}\n"))?;
+    }
+
+    if options.files {
+        let Some(dist) = installed else {
+            return terminal.print_warning(format!(
+                "{} isn't installed, so it has no files to list",
+                options.name
+            ));
+        };
+        terminal.print_stdout("files:\n")?;
+        for it in dist.record()? {
+            terminal.print_stdout(&format!("  {}\n", it.path))?;
+        }
+    }
+
+    if options.extras {
+        let extras = match installed {
+            Some(it) => {
+                package_extras(it.provides_extra(), it.requires_dist())?
+            }
+            None => package_extras(
+                remote
+                    .as_ref()
+                    .and_then(|it| it.provides_extra.as_deref())
+                    .unwrap_or_default(),
+                remote
+                    .as_ref()
+                    .and_then(|it| it.requires_dist.as_deref())
+                    .unwrap_or_default(),
+            )?,
+        };
+        if extras.is_empty() {
+            return terminal.print_custom(
+                "extras",
+                format!("{} doesn't provide any extras", options.name),
+                Color::Cyan,
+                false,
+            );
+        }
+        terminal.print_stdout("extras:\n")?;
+        for (extra, requires) in extras {
+            terminal.print_stdout(&format!("  {extra}\n"))?;
+            for it in requires {
+                terminal.print_stdout(&format!("    {it}\n"))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the key and value pairs describing a package from its installed `DistInfo` and its
+/// `ProjectInfo` from the package index.
+///
+/// Installed packages are direct dependencies if they're declared by the project or were
+/// explicitly requested. Otherwise they're transitive, or orphaned when nothing installed
+/// requires them anymore.
+fn package_info(
+    installed: Option<&DistInfo>,
+    remote: Option<&ProjectInfo>,
+    dists: &[DistInfo],
+    declared: bool,
+) -> Vec<(&'static str, String)> {
+    let mut lines = Vec::new();
+    let name = installed
+        .map(|it| it.name().to_string())
+        .or(remote.map(|it| it.name.clone()))
+        .unwrap_or_default();
+    lines.push(("name", name.clone()));
+    let summary = installed
+        .and_then(DistInfo::summary)
+        .or(remote.and_then(|it| it.summary.as_deref()));
+    if let Some(it) = summary {
+        lines.push(("summary", it.to_string()));
+    }
+    match installed {
+        Some(it) => lines.push((
+            "installed",
+            format!(
+                "{} ({})",
+                it.version(),
+                it.path().parent().unwrap_or(it.path()).display()
+            ),
+        )),
+        None => lines.push(("installed", "no".to_string())),
+    }
+    if let Some(it) = remote {
+        let outdated = installed.map_or(false, |dist| {
+            Version::from_str(&it.version)
+                .map_or(false, |latest| &latest > dist.version())
+        });
+        lines.push((
+            "latest",
+            match outdated {
+                true => format!("{} (update available)", it.version),
+                false => it.version.clone(),
+            },
+        ));
+    }
+    let license = installed
+        .and_then(DistInfo::license)
+        .or(remote.and_then(|it| it.license.as_deref()));
+    if let Some(it) = license {
+        lines.push(("license", it.to_string()));
+    }
+    let requires_python = installed
+        .and_then(DistInfo::requires_python)
+        .or(remote.and_then(|it| it.requires_python.as_deref()));
+    if let Some(it) = requires_python {
+        lines.push(("requires-python", it.to_string()));
+    }
+    let requires = match installed {
+        Some(it) => it.requires_dist().to_vec(),
+        None => remote
+            .and_then(|it| it.requires_dist.clone())
+            .unwrap_or_default(),
+    };
+    if !requires.is_empty() {
+        lines.push(("requires", requires.join(", ")));
+    }
+    let required_by = required_by(&name, dists);
+    if !required_by.is_empty() {
+        lines.push(("required-by", required_by.join(", ")));
+    }
+    if let Some(it) = installed {
+        let dependency = if declared || it.requested() {
+            "direct"
+        } else if required_by.is_empty() {
+            "orphaned (nothing installed requires it)"
+        } else {
+            "transitive"
+        };
+        lines.push(("dependency", dependency.to_string()));
+    }
+
+    lines
+}
+
+/// Get the names of the installed distributions that require a package.
+fn required_by(name: &str, dists: &[DistInfo]) -> Vec<String> {
+    let Ok(name) = normalize(name) else {
+        return Vec::new();
+    };
+    dists
+        .iter()
+        .filter(|dist| {
+            dist.requires_dist().iter().any(|it| {
+                normalize(requirement_name(it)).map_or(false, |it| it == name)
+            })
+        })
+        .map(|it| it.name().to_string())
+        .collect()
+}
+
+/// Get the extras a package provides, paired with the requirements each one pulls in.
+/// Requirements are listed without their `extra` marker. Extras that are only named by
+/// a marker are included too, since older metadata doesn't always declare them.
+pub(super) fn package_extras(
+    provides_extra: &[String],
+    requires_dist: &[String],
+) -> HuakResult<Vec<(String, Vec<String>)>> {
+    let marker_re = Regex::new(
+        r#"(?:\s+and\s+)?\bextra\s*==\s*["']([^"']+)["'](?:\s+and\s+)?"#,
+    )?;
+    let key = |it: &str| normalize(it).unwrap_or_else(|_| it.to_lowercase());
+    let mut extras = provides_extra
+        .iter()
+        .map(|it| (it.to_string(), Vec::new()))
+        .collect::<Vec<(String, Vec<String>)>>();
+    for requirement in requires_dist {
+        let Some((spec, marker)) = requirement.split_once(';') else {
+            continue;
+        };
+        let Some(captures) = marker_re.captures(marker) else {
+            continue;
+        };
+        let extra = &captures[1];
+        let rest = marker_re.replace(marker, " and ");
+        let rest = rest.trim();
+        let rest = rest.strip_prefix("and").unwrap_or(rest);
+        let rest = rest.strip_suffix("and").unwrap_or(rest).trim();
+        let requirement = match rest.is_empty() {
+            true => spec.trim().to_string(),
+            false => format!("{}; {rest}", spec.trim()),
+        };
+        match extras.iter_mut().find(|(name, _)| key(name) == key(extra)) {
+            Some((_, requires)) => requires.push(requirement),
+            None => extras.push((extra.to_string(), vec![requirement])),
+        }
+    }
+
+    Ok(extras)
+}
+
+/// Get the name of a PEP 508 requirement string.
+fn requirement_name(requirement: &str) -> &str {
+    let end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "-_.".contains(c)))
+        .unwrap_or(requirement.len());
+
+    &requirement[..end]
+}
+
+fn normalize(name: &str) -> HuakResult<String> {
+    Ok(canonical_package_name(name)?.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_package_info() {
+        let dir = tempdir().unwrap();
+        for (name, version, requires) in [
+            ("requests", "2.30.0", "Requires-Dist: charset_normalizer<4,>=2\nRequires-Dist: PySocks!=1.5.7,>=1.5.6; extra == \"socks\"\n"),
+            ("Charset-Normalizer", "3.2.0", ""),
+        ] {
+            let path = dir.path().join(format!("{name}-{version}.dist-info"));
+            std::fs::create_dir(&path).unwrap();
+            std::fs::write(
+                path.join("METADATA"),
+                format!("Metadata-Version: 2.1\nName: {name}\nVersion: {version}\nSummary: The {name} package\n{requires}"),
+            )
+            .unwrap();
+        }
+        let dists = scan_site_packages(dir.path()).unwrap();
+        let remote = ProjectInfo {
+            name: "charset-normalizer".to_string(),
+            version: "3.3.0".to_string(),
+            summary: None,
+            license: Some("MIT".to_string()),
+            requires_python: Some(">=3.7.0".to_string()),
+            requires_dist: None,
+            provides_extra: None,
+        };
+
+        let lines = package_info(Some(&dists[0]), Some(&remote), &dists, false);
+
+        assert_eq!(
+            lines,
+            [
+                ("name", "Charset-Normalizer".to_string()),
+                ("summary", "The Charset-Normalizer package".to_string()),
+                ("installed", format!("3.2.0 ({})", dir.path().display())),
+                ("latest", "3.3.0 (update available)".to_string()),
+                ("license", "MIT".to_string()),
+                ("requires-python", ">=3.7.0".to_string()),
+                ("required-by", "requests".to_string()),
+                ("dependency", "transitive".to_string()),
+            ]
+        );
+        let dependency = |dist: &DistInfo, declared| {
+            package_info(Some(dist), None, &dists, declared)
+                .into_iter()
+                .find(|(key, _)| *key == "dependency")
+                .map(|(_, value)| value)
+                .unwrap()
+        };
+        assert_eq!(
+            dependency(&dists[1], false),
+            "orphaned (nothing installed requires it)"
+        );
+        assert_eq!(dependency(&dists[1], true), "direct");
+        dists[1].set_requested(true).unwrap();
+        assert_eq!(dependency(&dists[1], false), "direct");
+        assert!(
+            required_by("pysocks", &dists).contains(&"requests".to_string())
+        );
+        assert!(required_by("requests", &dists).is_empty());
+    }
+
+    #[test]
+    fn test_package_extras() {
+        let provides_extra =
+            ["socks".to_string(), "use_chardet_on_py3".to_string()];
+        let requires_dist = [
+            "charset-normalizer<4,>=2",
+            "PySocks!=1.5.7,>=1.5.6; extra == \"socks\"",
+            "chardet<6,>=3.0.2; extra == 'use-chardet-on-py3'",
+            "win-inet-pton; sys_platform == \"win32\" and extra == \"socks\"",
+            "pytest; extra == \"test\"",
+        ]
+        .map(String::from);
+
+        let extras = package_extras(&provides_extra, &requires_dist).unwrap();
+
+        assert_eq!(
+            extras,
+            [
+                (
+                    "socks".to_string(),
+                    vec![
+                        "PySocks!=1.5.7,>=1.5.6".to_string(),
+                        "win-inet-pton; sys_platform == \"win32\"".to_string()
+                    ]
+                ),
+                (
+                    "use_chardet_on_py3".to_string(),
+                    vec!["chardet<6,>=3.0.2".to_string()]
+                ),
+                ("test".to_string(), vec!["pytest".to_string()]),
+            ]
+        );
+    }
+}
@@ -1,12 +1,27 @@
-use super::init_git;
+use super::{
+    apply_workspace_options, init_git, template_vars, user_templates_dir,
+};
 use crate::{
     dependency::Dependency,
-    fs,
+    dist_info::{scan_site_packages, DistInfo},
+    fs, git,
     metadata::{default_entrypoint_string, LocalMetadata},
-    package::importable_package_name,
+    package::{canonical_package_name, importable_package_name},
+    template::{templates, TemplateKind},
     Config, Error, HuakResult, WorkspaceOptions,
 };
-use std::str::FromStr;
+use pep508_rs::Requirement;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use termcolor::Color;
+
+/// Packages that are part of every Python environment rather than project dependencies.
+const ENVIRONMENT_PACKAGES: [&str; 3] = ["pip", "setuptools", "wheel"];
+/// Directories with an `__init__.py` that aren't a flat layout's packages.
+const NON_PACKAGE_DIRS: [&str; 5] = ["tests", "test", "docs", "build", "dist"];
 
 pub fn init_app_project(
     config: &Config,
@@ -35,7 +50,9 @@ pub fn init_lib_project(
 
     // Create a metadata file or error if one already exists.
     let mut metadata = match workspace.current_local_metadata() {
-        Ok(_) => return Err(Error::MetadataFileFound),
+        Ok(it) => {
+            return Err(Error::MetadataFileFound(it.path().to_path_buf()))
+        }
         Err(_) => {
             LocalMetadata::template(workspace.root().join("pyproject.toml"))
         }
@@ -45,11 +62,324 @@ pub fn init_lib_project(
         init_git(&config.workspace_root)?;
     }
 
-    let name = fs::last_path_component(&config.workspace_root)?;
+    // Existing packages are named after the project and included in its wheel.
+    let packages = existing_packages(workspace.root())?;
+    let name = match packages.as_slice() {
+        [it] => package_dir_name(it).replace('_', "-"),
+        _ => fs::last_path_component(&config.workspace_root)?,
+    };
     metadata.metadata_mut().set_project_name(name);
+    if !packages.is_empty() {
+        let packages = packages
+            .iter()
+            .map(|it| it.to_string_lossy().replace('\\', "/"))
+            .collect::<Vec<_>>();
+        config.terminal().print_custom(
+            "Detected",
+            format!(
+                "{} layout ({})",
+                if packages[0].starts_with("src/") {
+                    "src"
+                } else {
+                    "flat"
+                },
+                packages.join(", ")
+            ),
+            Color::Green,
+            true,
+        )?;
+        metadata.metadata_mut().set_wheel_packages(packages);
+    }
+    apply_workspace_options(&mut metadata, options)?;
+    if options.from_requirements {
+        import_requirements(&mut metadata, config)?;
+    }
+    if options.scaffold {
+        let importable_name = match packages.first() {
+            Some(it) => package_dir_name(it),
+            None => {
+                importable_package_name(metadata.metadata().project_name())?
+            }
+        };
+        let vars = template_vars(
+            config,
+            options,
+            metadata.metadata(),
+            &importable_name,
+        );
+        scaffold_project(workspace.root(), &packages, &vars)?;
+    }
     metadata.write_file()
 }
 
+/// Get the paths to the packages in a project directory, relative to the directory.
+/// Packages in a "src" directory (the src layout) are preferred over packages in the
+/// directory itself (the flat layout).
+fn existing_packages(dir: &Path) -> HuakResult<Vec<PathBuf>> {
+    let src = dir.join("src");
+    let packages = package_dirs(&src)?;
+    if !packages.is_empty() {
+        return Ok(packages
+            .into_iter()
+            .map(|it| PathBuf::from("src").join(it))
+            .collect());
+    }
+
+    Ok(package_dirs(dir)?
+        .into_iter()
+        .filter(|it| !NON_PACKAGE_DIRS.contains(&package_dir_name(it).as_str()))
+        .collect())
+}
+
+/// Get the names of the directories containing an `__init__.py` in a directory.
+fn package_dirs(dir: &Path) -> HuakResult<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|it| it.to_str()) else {
+            continue;
+        };
+        if !name.starts_with('.') && path.join("__init__.py").is_file() {
+            dirs.push(PathBuf::from(name));
+        }
+    }
+    dirs.sort();
+
+    Ok(dirs)
+}
+
+/// Get the name of a package from its path.
+fn package_dir_name(path: &Path) -> String {
+    path.file_name()
+        .map(|it| it.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Add the tests directory and .gitignore to a project directory if they're missing,
+/// along with the other scaffolding templates' files that don't exist yet. The tests
+/// directory starts with a test importing the project's first package.
+fn scaffold_project(
+    dir: &Path,
+    packages: &[PathBuf],
+    vars: &[(&str, String)],
+) -> HuakResult<()> {
+    // Tests are only added to projects with packages and without a tests directory.
+    let skip_tests = packages.is_empty() || dir.join("tests").exists();
+    for it in
+        templates(TemplateKind::Scaffold, user_templates_dir().as_deref())?
+    {
+        if !(skip_tests && it.path.starts_with("tests/")) {
+            it.write(dir, vars, false)?;
+        }
+    }
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        std::fs::write(gitignore, git::default_python_gitignore())?;
+    }
+
+    Ok(())
+}
+
+/// Populate the project's dependencies from the requirements files in the workspace root
+/// (see `requirements_files`). If there aren't any, the top-level packages installed in
+/// the active Python environment are added at their installed versions instead.
+fn import_requirements(
+    metadata: &mut LocalMetadata,
+    config: &Config,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut terminal = config.terminal();
+
+    let files = requirements_files(workspace.root())?;
+    let (dependencies, source) = if files.is_empty() {
+        let python_env = match workspace.current_python_environment() {
+            Ok(it) => it,
+            Err(Error::PythonEnvironmentNotFound) => {
+                return terminal.print_warning(
+                    "no requirements files or Python environment to import dependencies from",
+                )
+            }
+            Err(e) => return Err(e),
+        };
+        let dists = scan_site_packages(python_env.site_packages_dir_path())?;
+        let mut deps = Vec::new();
+        for dist in top_level_dists(&dists)? {
+            deps.push((
+                Dependency::from_str(&format!(
+                    "{}=={}",
+                    dist.name(),
+                    dist.version()
+                ))?,
+                None,
+            ));
+        }
+        (deps, python_env.root().display().to_string())
+    } else {
+        let mut deps = Vec::new();
+        for (path, group) in files.iter() {
+            let (reqs, skipped) = parse_requirements_file(path)?;
+            for line in skipped {
+                terminal.print_warning(format!(
+                    "skipped `{line}` in {}",
+                    path.display()
+                ))?;
+            }
+            deps.extend(reqs.into_iter().map(|it| (it, group.clone())));
+        }
+        let names = files
+            .iter()
+            .filter_map(|(path, _)| path.file_name())
+            .map(|it| it.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        (deps, names.join(", "))
+    };
+
+    let mut count = 0;
+    for (dep, group) in dependencies {
+        // Requirements files commonly include each other, so dependencies are only
+        // added once.
+        let project = metadata.metadata_mut();
+        if project.contains_dependency(&dep)? {
+            continue;
+        }
+        match group {
+            Some(group) => {
+                if !project.contains_optional_dependency(&dep, &group)? {
+                    project.add_optional_dependency(dep, &group);
+                    count += 1;
+                }
+            }
+            None => {
+                project.add_dependency(dep);
+                count += 1;
+            }
+        }
+    }
+
+    terminal.print_custom(
+        "Imported",
+        format!("{count} dependencies from {source}"),
+        Color::Green,
+        true,
+    )
+}
+
+/// Get the requirements files in a directory along with the optional dependency group
+/// each belongs to. "requirements.txt" holds the project's dependencies, and files like
+/// "requirements-dev.txt" or "test-requirements.txt" hold the "dev" and "test" groups.
+fn requirements_files(
+    dir: &Path,
+) -> HuakResult<Vec<(PathBuf, Option<String>)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some(stem) = path
+            .file_name()
+            .and_then(|it| it.to_str())
+            .and_then(|it| it.strip_suffix(".txt"))
+        else {
+            continue;
+        };
+        let group = match stem
+            .strip_prefix("requirements")
+            .or(stem.strip_suffix("requirements"))
+        {
+            Some(it) => it.trim_matches(['-', '_', '.']).to_lowercase(),
+            None => continue,
+        };
+        files.push((path, Some(group).filter(|it| !it.is_empty())));
+    }
+    // The project's dependencies are imported before the groups'.
+    files.sort_by(|(a, a_group), (b, b_group)| {
+        a_group.is_some().cmp(&b_group.is_some()).then(a.cmp(b))
+    });
+
+    Ok(files)
+}
+
+/// Parse the requirements in a pip requirements file, following `-r` includes. Returns
+/// the requirements along with the lines that couldn't be imported, such as editable
+/// installs and pip options.
+fn parse_requirements_file(
+    path: &Path,
+) -> HuakResult<(Vec<Dependency>, Vec<String>)> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut dependencies = Vec::new();
+    let mut skipped = Vec::new();
+    for line in contents.replace("\\\n", "").lines() {
+        let line = match line.find(" #") {
+            Some(i) => &line[..i],
+            None => line,
+        }
+        .trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let include = line
+            .strip_prefix("--requirement")
+            .or(line.strip_prefix("-r"))
+            .map(|it| it.trim_start_matches([' ', '=']));
+        if let Some(include) = include {
+            let include = path.parent().unwrap_or(Path::new("")).join(include);
+            let (deps, lines) = parse_requirements_file(&include)?;
+            dependencies.extend(deps);
+            skipped.extend(lines);
+        } else if line.starts_with('-') {
+            skipped.push(line.to_string());
+        } else {
+            match Dependency::from_str(line) {
+                Ok(it) => dependencies.push(it),
+                Err(_) => skipped.push(line.to_string()),
+            }
+        }
+    }
+
+    Ok((dependencies, skipped))
+}
+
+/// Get the distributions that no other installed distribution requires, excluding the
+/// packages every environment has.
+fn top_level_dists(dists: &[DistInfo]) -> HuakResult<Vec<&DistInfo>> {
+    let mut required = HashSet::new();
+    for dist in dists.iter() {
+        for it in dist.requires_dist() {
+            // Extras' requirements aren't necessarily installed.
+            let Ok(requirement) = Requirement::from_str(it) else {
+                continue;
+            };
+            if requirement
+                .marker
+                .as_ref()
+                .map_or(false, |it| it.to_string().contains("extra"))
+            {
+                continue;
+            }
+            required.insert(
+                canonical_package_name(&requirement.name)?.to_lowercase(),
+            );
+        }
+    }
+
+    let mut top_level = Vec::new();
+    for dist in dists.iter() {
+        let name = canonical_package_name(dist.name())?.to_lowercase();
+        if !required.contains(&name)
+            && !ENVIRONMENT_PACKAGES.contains(&name.as_str())
+        {
+            top_level.push(dist);
+        }
+    }
+
+    Ok(top_level)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,7 +397,10 @@ mod tests {
         let root = dir.path().join("mock-project");
         let cwd = root.to_path_buf();
         let config = test_config(root, cwd, Verbosity::Quiet);
-        let options = WorkspaceOptions { uses_git: false };
+        let options = WorkspaceOptions {
+            uses_git: false,
+            ..Default::default()
+        };
         init_lib_project(&config, &options).unwrap();
 
         let ws = config.workspace();
@@ -86,7 +419,10 @@ mod tests {
         let root = dir.path().join("mock-project");
         let cwd = root.to_path_buf();
         let config = test_config(root, cwd, Verbosity::Quiet);
-        let options = WorkspaceOptions { uses_git: false };
+        let options = WorkspaceOptions {
+            uses_git: false,
+            ..Default::default()
+        };
 
         init_app_project(&config, &options).unwrap();
 
@@ -113,4 +449,172 @@ mock-project = "mock_project.main:main"
 "#
         );
     }
+
+    #[test]
+    fn test_init_project_from_requirements() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("mock-project");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(
+            root.join("requirements.txt"),
+            "# Runtime\nclick>=8  # CLI\nrequests==2.31.0 \\\n    ; python_version >= \"3.8\"\n-e ./vendored\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("requirements-dev.txt"),
+            "-r requirements.txt\n--index-url https://example.com/simple\npytest\n",
+        )
+        .unwrap();
+        let cwd = root.to_path_buf();
+        let config = test_config(root, cwd, Verbosity::Quiet);
+        let options = WorkspaceOptions {
+            uses_git: false,
+            from_requirements: true,
+            ..Default::default()
+        };
+
+        init_lib_project(&config, &options).unwrap();
+
+        let metadata = config.workspace().current_local_metadata().unwrap();
+        let dependencies = metadata
+            .metadata()
+            .dependencies()
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let dev = metadata
+            .metadata()
+            .optional_dependency_group("dev")
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            dependencies,
+            ["click >=8", "requests ==2.31.0 ; python_version >= '3.8'"]
+        );
+        assert_eq!(dev, ["pytest"]);
+    }
+
+    #[test]
+    fn test_requirements_files() {
+        let dir = tempdir().unwrap();
+        for name in [
+            "requirements-test.txt",
+            "requirements.txt",
+            "dev-requirements.txt",
+            "notes.txt",
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let files = requirements_files(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|(path, group)| {
+                (
+                    path.file_name().unwrap().to_string_lossy().to_string(),
+                    group,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            files,
+            [
+                ("requirements.txt".to_string(), None),
+                ("dev-requirements.txt".to_string(), Some("dev".to_string())),
+                (
+                    "requirements-test.txt".to_string(),
+                    Some("test".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_top_level_dists() {
+        let dir = tempdir().unwrap();
+        for (name, version, requires) in [
+            ("requests", "2.31.0", "Requires-Dist: charset-normalizer<4\nRequires-Dist: PySocks; extra == \"socks\"\n"),
+            ("charset_normalizer", "3.2.0", ""),
+            ("PySocks", "1.7.1", ""),
+            ("pip", "23.2.1", ""),
+        ] {
+            let dist_info = dir.path().join(format!("{name}-{version}.dist-info"));
+            std::fs::create_dir(&dist_info).unwrap();
+            std::fs::write(
+                dist_info.join("METADATA"),
+                format!("Metadata-Version: 2.1\nName: {name}\nVersion: {version}\n{requires}"),
+            )
+            .unwrap();
+        }
+        let dists = scan_site_packages(dir.path()).unwrap();
+
+        let top_level = top_level_dists(&dists)
+            .unwrap()
+            .iter()
+            .map(|it| it.name().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(top_level, ["PySocks", "requests"]);
+    }
+
+    #[test]
+    fn test_init_project_with_src_layout() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("existing");
+        std::fs::create_dir_all(root.join("src").join("my_pkg")).unwrap();
+        std::fs::write(root.join("src").join("my_pkg").join("__init__.py"), "")
+            .unwrap();
+        let cwd = root.to_path_buf();
+        let config = test_config(root.clone(), cwd, Verbosity::Quiet);
+        let options = WorkspaceOptions {
+            uses_git: false,
+            scaffold: true,
+            ..Default::default()
+        };
+
+        init_lib_project(&config, &options).unwrap();
+
+        let metadata = config.workspace().current_local_metadata().unwrap();
+
+        assert_eq!(metadata.metadata().project_name(), "my-pkg");
+        assert!(metadata.to_string_pretty().unwrap().contains(
+            "[tool.hatch.build.targets.wheel]\npackages = [\"src/my_pkg\"]"
+        ));
+        assert!(root.join("tests").join("test_my_pkg.py").exists());
+        assert!(root.join(".gitignore").exists());
+    }
+
+    #[test]
+    fn test_existing_packages() {
+        let dir = tempdir().unwrap();
+        for path in ["pkg_a", "pkg_b", "tests", ".hidden", "scripts"] {
+            std::fs::create_dir(dir.path().join(path)).unwrap();
+            if path != "scripts" {
+                std::fs::write(dir.path().join(path).join("__init__.py"), "")
+                    .unwrap();
+            }
+        }
+
+        assert_eq!(
+            existing_packages(dir.path()).unwrap(),
+            [PathBuf::from("pkg_a"), PathBuf::from("pkg_b")]
+        );
+
+        std::fs::create_dir_all(dir.path().join("src").join("pkg_c")).unwrap();
+        std::fs::write(
+            dir.path().join("src").join("pkg_c").join("__init__.py"),
+            "",
+        )
+        .unwrap();
+
+        assert_eq!(
+            existing_packages(dir.path()).unwrap(),
+            [PathBuf::from("src").join("pkg_c")]
+        );
+    }
 }
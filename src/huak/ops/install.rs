@@ -1,14 +1,86 @@
-use crate::{dependency::Dependency, Config, HuakResult, InstallOptions};
+use super::{
+    check_wheel_compatibility, installed_dependency_releases,
+    project_constraints, project_requirements, shared_dev_dependencies,
+    warn_yanked_releases, with_constraints,
+};
+use crate::{
+    dependency::Dependency,
+    lockfile::{lockfile_file_name, Lockfile},
+    metadata::LocalMetadata,
+    Config, Error, HuakResult, InstallOptions,
+};
+use pep508_rs::MarkerTree;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How `install_project_dependencies` uses the project's lockfile.
+pub enum LockMode {
+    /// Install from the lockfile if it pins every package with a hash and is up to date
+    /// with the pyproject.toml, otherwise install the project's requirements.
+    #[default]
+    Auto,
+    /// Install exactly what the lockfile pins, failing if it's missing or out of date
+    /// with the pyproject.toml.
+    Locked,
+    /// Install exactly what the lockfile pins without checking if it's up to date,
+    /// failing if it's missing.
+    Frozen,
+}
 
 pub fn install_project_dependencies(
     groups: Option<&Vec<String>>,
+    lock_mode: LockMode,
     config: &Config,
     options: &InstallOptions,
 ) -> HuakResult<()> {
     let workspace = config.workspace();
+    let _lock = workspace.lock()?;
+    let _journal = workspace.journal("install")?;
+
+    if lock_mode != LockMode::Auto {
+        if groups.is_some() {
+            return Err(Error::HuakConfigurationError(
+                "groups can't be installed from the lockfile".to_string(),
+            ));
+        }
+        let Some(lockfile) = workspace.current_lockfile()? else {
+            return Err(Error::HuakConfigurationError(format!(
+                "there's no {} to install from, run `huak lock` first",
+                lockfile_file_name()
+            )));
+        };
+        if lock_mode == LockMode::Locked {
+            let metadata = workspace.current_local_metadata()?;
+            check_project_lockfile(&lockfile, &metadata)?;
+        }
+        return install_lockfile(&lockfile, config, options);
+    }
+
     let package = workspace.current_package()?;
     let metadata = workspace.current_local_metadata()?;
 
+    // Install from a hash-pinned lockfile without pip when the whole project is requested.
+    // A lockfile that's out of date with the pyproject.toml would skip new requirements,
+    // so the project's requirements are installed instead.
+    // TODO: Native installs on Windows need launcher executables for entry points.
+    if cfg!(unix) && groups.is_none() && options.values.is_none() {
+        if let Some(lockfile) = workspace.current_lockfile()? {
+            if lockfile.is_hash_pinned() {
+                match check_project_lockfile(&lockfile, &metadata) {
+                    Ok(()) => {
+                        return install_lockfile(&lockfile, config, options)
+                    }
+                    Err(e @ Error::OutdatedLockfile(_)) => {
+                        config.terminal().print_warning(format!(
+                            "{e}; installing from pyproject.toml instead (run `huak lock` to update it)"
+                        ))?
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+
     let binding = Vec::new(); // TODO
     let mut dependencies = Vec::new();
 
@@ -51,6 +123,11 @@ pub fn install_project_dependencies(
         }
     }
 
+    // Members install the dev dependencies their workspace shares with the dev group.
+    if groups.map_or(true, |it| it.iter().any(|g| g == "dev")) {
+        dependencies.extend(shared_dev_dependencies(&metadata)?);
+    }
+
     dependencies.dedup();
 
     if dependencies.is_empty() {
@@ -58,7 +135,122 @@ pub fn install_project_dependencies(
     }
 
     let python_env = workspace.resolve_python_environment()?;
-    python_env.install_packages(&dependencies, options, config)
+
+    // Skip any dependencies with PEP 508 markers that don't apply to the environment.
+    if dependencies.iter().any(|dep| dep.has_markers()) {
+        let marker_env = python_env.marker_environment()?;
+        dependencies.retain(|dep| dep.applies_to(&marker_env));
+    }
+
+    if dependencies.is_empty() {
+        return Ok(());
+    }
+
+    check_wheel_compatibility(&dependencies, &python_env, &metadata, config)?;
+    with_constraints(&metadata, options, config, |options| {
+        python_env.install_packages(&dependencies, options, config)
+    })?;
+
+    // Releases can be yanked after they were added to the project.
+    let releases = installed_dependency_releases(&python_env, &metadata)?;
+    warn_yanked_releases(&releases, "installed", config)
+}
+
+/// Install exactly the packages a `Lockfile` pins. Hash-pinned lockfiles are installed
+/// without pip, and others are installed by pip without resolving dependencies.
+fn install_lockfile(
+    lockfile: &Lockfile,
+    config: &Config,
+    options: &InstallOptions,
+) -> HuakResult<()> {
+    let python_env = config.workspace().resolve_python_environment()?;
+    let releases = lockfile
+        .packages
+        .iter()
+        .map(|pkg| (pkg.name.clone(), pkg.version.clone()))
+        .collect::<Vec<_>>();
+    warn_yanked_releases(&releases, "locked", config)?;
+
+    if cfg!(unix) && options.values.is_none() && lockfile.is_hash_pinned() {
+        return python_env.install_locked(lockfile, config);
+    }
+
+    let marker_env = python_env.marker_environment()?;
+    let mut packages = Vec::new();
+    for pkg in lockfile.packages.iter() {
+        if let Some(markers) = pkg.markers.as_ref() {
+            if !MarkerTree::from_str(markers)?.evaluate(&marker_env, &[]) {
+                continue;
+            }
+        }
+        packages.push(format!("{}=={}", pkg.name, pkg.version));
+    }
+    if packages.is_empty() {
+        return Ok(());
+    }
+
+    let mut options = InstallOptions {
+        values: options.values.clone(),
+    };
+    options
+        .values
+        .get_or_insert_with(Vec::new)
+        .push("--no-deps".to_string());
+    python_env.install_packages(&packages, &options, config)?;
+    // Every locked package is named to pip, so only the project's requirements are left
+    // marked as requested.
+    python_env.mark_requested(lockfile)
+}
+
+/// Check that a `Lockfile` is up to date with the project's pyproject.toml.
+fn check_project_lockfile(
+    lockfile: &Lockfile,
+    metadata: &LocalMetadata,
+) -> HuakResult<()> {
+    let constraints = project_constraints(metadata)?
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    check_lockfile(lockfile, &project_requirements(metadata), &constraints)
+}
+
+/// Check that a `Lockfile` was resolved from the project's current requirements and
+/// constraints.
+fn check_lockfile(
+    lockfile: &Lockfile,
+    requirements: &[String],
+    constraints: &[String],
+) -> HuakResult<()> {
+    let added = requirements
+        .iter()
+        .filter(|it| !lockfile.requirements.contains(it))
+        .cloned()
+        .collect::<Vec<_>>();
+    let removed = lockfile
+        .requirements
+        .iter()
+        .filter(|it| !requirements.contains(it))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut changes = Vec::new();
+    if !added.is_empty() {
+        changes.push(format!("{} added to pyproject.toml", added.join(", ")));
+    }
+    if !removed.is_empty() {
+        changes.push(format!(
+            "{} removed from pyproject.toml",
+            removed.join(", ")
+        ));
+    }
+    if lockfile.constraints != constraints {
+        changes.push("[tool.huak.constraints] changed".to_string());
+    }
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    Err(Error::OutdatedLockfile(changes.join("; ")))
 }
 
 #[cfg(test)]
@@ -91,7 +283,8 @@ mod tests {
         let test_package = Package::from_str("click==8.1.3").unwrap();
         let had_package = venv.contains_package(&test_package);
 
-        install_project_dependencies(None, &config, &options).unwrap();
+        install_project_dependencies(None, LockMode::Auto, &config, &options)
+            .unwrap();
 
         assert!(!had_package);
         assert!(venv.contains_package(&test_package));
@@ -116,6 +309,7 @@ mod tests {
 
         install_project_dependencies(
             Some(&vec![String::from("dev")]),
+            LockMode::Auto,
             &config,
             &options,
         )
@@ -124,4 +318,70 @@ mod tests {
         assert!(!had_package);
         assert!(venv.contains_module("pytest").unwrap());
     }
+
+    #[test]
+    fn test_install_project_dependencies_outdated_lockfile() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let ws = config.workspace();
+        test_venv(&ws);
+        // A hash-pinned lockfile from before the project required anything.
+        Lockfile::new(root.join(lockfile_file_name()))
+            .write_file()
+            .unwrap();
+        let options = InstallOptions { values: None };
+        let venv = ws.resolve_python_environment().unwrap();
+        let test_package = Package::from_str("click==8.1.3").unwrap();
+
+        install_project_dependencies(None, LockMode::Auto, &config, &options)
+            .unwrap();
+
+        assert!(venv.contains_package(&test_package));
+        assert!(install_project_dependencies(
+            None,
+            LockMode::Locked,
+            &config,
+            &options
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_check_lockfile() {
+        let mut lockfile = Lockfile::new("huak.lock");
+        lockfile.requirements =
+            vec!["click>=8".to_string(), "rich".to_string()];
+
+        let err = check_lockfile(
+            &lockfile,
+            &["click>=8".to_string(), "httpx".to_string()],
+            &[],
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "the lockfile is out of date: httpx added to pyproject.toml; rich removed from pyproject.toml"
+        );
+        assert!(check_lockfile(&lockfile, &lockfile.requirements, &[]).is_ok());
+
+        let err = check_lockfile(
+            &lockfile,
+            &lockfile.requirements,
+            &["urllib3 <2".to_string()],
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "the lockfile is out of date: [tool.huak.constraints] changed"
+        );
+    }
 }
@@ -0,0 +1,141 @@
+use super::make_venv_command;
+use crate::{dependency::Dependency, Config, HuakResult, InstallOptions};
+use std::{process::Command, str::FromStr};
+use termcolor::Color;
+
+pub struct KernelOptions {
+    /// The name of the kernel. Defaults to the project's name.
+    pub name: Option<String>,
+    /// The name of the kernel displayed by Jupyter. Defaults to "Python (<name>)".
+    pub display_name: Option<String>,
+    pub install_options: InstallOptions,
+}
+
+/// Register a Jupyter kernel for the current user that runs the project's Python
+/// environment, installing `ipykernel` if it isn't already installed.
+pub fn install_project_kernel(
+    config: &Config,
+    options: &KernelOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let package = workspace.current_package()?;
+    let mut metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+
+    // Install `ipykernel` if it isn't already installed. It doesn't install an executable
+    // so its module is checked for.
+    let kernel_dep = Dependency::from_str("ipykernel")?;
+    if !python_env
+        .site_packages_dir_path()
+        .join("ipykernel")
+        .exists()
+    {
+        python_env.install_packages(
+            &[&kernel_dep],
+            &options.install_options,
+            config,
+        )?;
+    }
+
+    // Add the installed `ipykernel` package to the metadata file if it isn't already there.
+    if !metadata.metadata().contains_dependency_any(&kernel_dep)? {
+        for pkg in python_env
+            .installed_packages()?
+            .iter()
+            .filter(|pkg| pkg.name() == kernel_dep.name())
+        {
+            metadata.metadata_mut().add_optional_dependency(
+                Dependency::from_str(&pkg.to_string())?,
+                "dev",
+            );
+        }
+    }
+
+    if package.metadata() != metadata.metadata() {
+        metadata.write_file()?;
+    }
+
+    let name = kernel_name(options, metadata.metadata().project_name());
+    let display_name = options
+        .display_name
+        .clone()
+        .unwrap_or_else(|| format!("Python ({name})"));
+    let mut cmd = Command::new(python_env.python_path());
+    make_venv_command(&mut cmd, &python_env, config)?;
+    cmd.args(["-m", "ipykernel", "install", "--user", "--name", &name])
+        .args(["--display-name", &display_name]);
+    let mut terminal = config.terminal();
+    terminal.run_command(&mut cmd)?;
+
+    terminal.print_custom(
+        "Registered",
+        format!("kernel {name} ({display_name})"),
+        Color::Green,
+        true,
+    )
+}
+
+/// Remove a Jupyter kernel registered with `install_project_kernel`.
+pub fn remove_project_kernel(
+    config: &Config,
+    options: &KernelOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+
+    let name = kernel_name(options, metadata.metadata().project_name());
+    let mut cmd = Command::new(python_env.python_path());
+    make_venv_command(&mut cmd, &python_env, config)?;
+    cmd.args([
+        "-c",
+        "import sys; from jupyter_client.kernelspec import KernelSpecManager; \
+        KernelSpecManager().remove_kernel_spec(sys.argv[1])",
+        &name,
+    ]);
+    let mut terminal = config.terminal();
+    terminal.run_command(&mut cmd)?;
+
+    terminal.print_custom(
+        "Removed",
+        format!("kernel {name}"),
+        Color::Green,
+        true,
+    )
+}
+
+/// Get the name of the kernel to manage. Jupyter only allows kernel names with ASCII
+/// alphanumerics, "-", ".", and "_", so other characters are replaced.
+fn kernel_name(options: &KernelOptions, project_name: &str) -> String {
+    options
+        .name
+        .as_deref()
+        .unwrap_or(project_name)
+        .chars()
+        .map(|it| match it {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' | '_' => it,
+            _ => '-',
+        })
+        .collect::<String>()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernel_name() {
+        let mut options = KernelOptions {
+            name: None,
+            display_name: None,
+            install_options: InstallOptions { values: None },
+        };
+
+        assert_eq!(kernel_name(&options, "mock-project"), "mock-project");
+
+        options.name = Some("My Project".to_string());
+
+        assert_eq!(kernel_name(&options, "mock-project"), "my-project");
+    }
+}
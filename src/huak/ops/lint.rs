@@ -1,12 +1,62 @@
-use super::make_venv_command;
-use crate::{dependency::Dependency, Config, HuakResult, InstallOptions};
-use std::{process::Command, str::FromStr};
+use super::{
+    check_output, check_output_with_codes, exclude_patterns, exclude_regex,
+    isolated_tools, make_venv_command, nbqa_command, notebook_paths,
+    op_env_vars, target_paths, tool_config_args, tool_dependencies,
+    tool_dependencies_environment, tool_dependency, unstaged_paths,
+    update_index,
+};
+use crate::{
+    dependency::Dependency, metadata::Metadata, Config, Error, HuakResult,
+    InstallOptions,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    path::{Component, Path, PathBuf},
+    process::Command,
+    rc::Rc,
+    str::FromStr,
+};
+use termcolor::Color;
 
 pub struct LintOptions {
     /// A values vector of lint options typically used for passing on arguments.
     pub values: Option<Vec<String>>,
     pub include_types: bool,
+    /// Only type-check with `mypy`, skipping `ruff` and the other passes.
+    pub only_types: bool,
+    /// Run a security pass with `bandit`.
+    pub include_security: bool,
+    /// Find dead code with `vulture`. Dead code is also found if `[tool.huak.lint]` sets
+    /// `dead-code = true`.
+    pub include_dead_code: bool,
     pub install_options: InstallOptions,
+    /// The format findings are reported in.
+    pub output: LintOutput,
+    /// A path to write the report to instead of stdout.
+    pub output_path: Option<PathBuf>,
+    /// Only target Python files changed relative to a git reference. `Some(None)` compares
+    /// against the merge-base with the default branch.
+    pub changed: Option<Option<String>>,
+    /// Only target Python files staged in the git index.
+    pub staged: bool,
+    /// Re-stage the staged files after they're fixed.
+    pub update_index: bool,
+    /// Paths or glob patterns to target instead of the whole workspace, relative to the
+    /// current working directory.
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Formats `lint_project` can report findings in.
+pub enum LintOutput {
+    /// The tools' own output.
+    #[default]
+    Text,
+    /// A SARIF 2.1.0 log.
+    Sarif,
+    /// GitHub Actions workflow commands (`::error file=...`).
+    Github,
 }
 
 pub fn lint_project(config: &Config, options: &LintOptions) -> HuakResult<()> {
@@ -15,66 +65,250 @@ pub fn lint_project(config: &Config, options: &LintOptions) -> HuakResult<()> {
     let mut metadata = workspace.current_local_metadata()?;
     let python_env = workspace.resolve_python_environment()?;
 
-    // Install `ruff` if it isn't already installed.
-    let ruff_dep = Dependency::from_str("ruff")?;
-    let mut lint_deps = vec![ruff_dep.clone()];
-    if !python_env.contains_module("ruff")? {
-        python_env.install_packages(
-            &[&ruff_dep],
+    // Install `ruff` if it isn't already installed. Lint deps are installed at their
+    // pinned versions, in tool environments if the project uses isolated tools.
+    let tools = tool_dependencies(&metadata)?;
+    let isolated = isolated_tools(&metadata)?;
+    let tool_env = |deps: &[Dependency]| {
+        tool_dependencies_environment(
+            deps,
+            isolated,
+            &python_env,
             &options.install_options,
             config,
-        )?;
-    }
+        )
+    };
+    let ruff_dep = tool_dependency("ruff", &tools)?;
+    let mut lint_deps = vec![ruff_dep.clone()];
+    let ruff_env = match options.only_types {
+        true => Rc::clone(&python_env),
+        false => tool_env(&[ruff_dep.clone()])?,
+    };
 
     let mut terminal = config.terminal();
+    let mut findings = Vec::new();
+    let lint_config = LintConfig::from_metadata(metadata.metadata());
+    let exclude = exclude_patterns(&metadata)?;
+    let env_vars = op_env_vars(&metadata, "lint")?;
+    // `bandit` and `vulture` take comma-separated exclusions.
+    let excluded_paths = std::iter::once(python_env.name()?)
+        .chain(exclude.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(",");
+    let targets = target_paths(
+        &workspace,
+        &options.changed,
+        options.staged,
+        &options.paths,
+        &exclude,
+    )?;
+    if targets.is_empty() {
+        return terminal.print_custom(
+            "Skipped",
+            match options.staged {
+                true => "no Python files are staged",
+                false => "no Python files have changed",
+            },
+            Color::Green,
+            true,
+        );
+    }
+    let unstaged = match options.update_index {
+        true => unstaged_paths(&workspace, &targets)?,
+        false => Vec::new(),
+    };
+    let notebooks = match lint_config.notebooks {
+        true => {
+            notebook_paths(&workspace, &targets, python_env.root(), &exclude)?
+        }
+        false => Vec::new(),
+    };
+    // Notebooks are linted separately with `nbqa`.
+    let targets = targets
+        .into_iter()
+        .filter(|it| !it.ends_with(".ipynb"))
+        .collect::<Vec<_>>();
 
-    if options.include_types {
+    if (options.include_types || options.only_types) && !targets.is_empty() {
         // Install `mypy` if it isn't already installed.
-        let mypy_dep = Dependency::from_str("mypy")?;
-        if !python_env.contains_module("mypy")? {
-            python_env.install_packages(
-                &[&mypy_dep],
-                &options.install_options,
-                config,
-            )?;
-        }
+        let mypy_dep = tool_dependency("mypy", &tools)?;
+        let mypy_env = tool_env(&[mypy_dep.clone()])?;
 
         // Keep track of the fact that `mypy` is a needed lint dep.
         lint_deps.push(mypy_dep);
 
         // Run `mypy` excluding the workspace's Python environment directory.
-        let mut mypy_cmd = Command::new(python_env.python_path());
-        make_venv_command(&mut mypy_cmd, &python_env)?;
+        let mut mypy_cmd = Command::new(mypy_env.python_path());
+        make_venv_command(&mut mypy_cmd, &mypy_env, config)?;
+        mypy_cmd.envs(env_vars.iter().cloned());
         mypy_cmd
-            .args(vec![
-                "-m",
-                "mypy",
-                ".",
-                "--exclude",
-                python_env.name()?.as_str(),
-            ])
+            .args(["-m", "mypy"])
+            .args(&targets)
+            .args(["--exclude", python_env.name()?.as_str()])
+            .args(tool_config_args("mypy", &metadata, &[]))
             .current_dir(workspace.root());
-        terminal.run_command(&mut mypy_cmd)?;
+        if let Some(it) = exclude_regex(&exclude) {
+            mypy_cmd.args(["--exclude", &it]);
+        }
+        // `mypy` resolves imports against the project's environment rather than its own.
+        if isolated {
+            mypy_cmd
+                .arg("--python-executable")
+                .arg(python_env.python_path());
+        }
+        if options.output == LintOutput::Text {
+            terminal.run_command(&mut mypy_cmd)?;
+        } else {
+            mypy_cmd.args([
+                "--show-column-numbers",
+                "--show-error-codes",
+                "--no-error-summary",
+                "--no-pretty",
+            ]);
+            findings.extend(parse_mypy_findings(&check_output(
+                &mut mypy_cmd,
+                &mut terminal,
+            )?));
+        }
     }
 
     // Run `ruff`.
-    let mut cmd = Command::new(python_env.python_path());
-    let mut args = vec!["-m", "ruff", "check", "."];
+    let mut ruff_args = Vec::new();
+    if lint_config.docstrings {
+        ruff_args.extend(["--extend-select", "D"]);
+    }
     if let Some(v) = options.values.as_ref() {
-        args.extend(v.iter().map(|item| item.as_str()));
+        ruff_args.extend(v.iter().map(|item| item.as_str()));
+    }
+    let ruff_config = tool_config_args("ruff", &metadata, &ruff_args);
+    let fix = ruff_args.contains(&"--fix");
+    if !options.only_types && !targets.is_empty() {
+        let mut cmd = Command::new(ruff_env.python_path());
+        make_venv_command(&mut cmd, &ruff_env, config)?;
+        cmd.envs(env_vars.iter().cloned());
+        cmd.args(["-m", "ruff", "check"])
+            .args(&targets)
+            .args(&ruff_args)
+            .args(&ruff_config)
+            .current_dir(workspace.root());
+        if !notebooks.is_empty() {
+            cmd.args(["--extend-exclude", "*.ipynb"]);
+        }
+        for it in exclude.iter() {
+            cmd.args(["--extend-exclude", it]);
+        }
+        let result = if options.output == LintOutput::Text {
+            terminal.run_command(&mut cmd)
+        } else {
+            // Older versions of `ruff` read `RUFF_FORMAT` instead of `RUFF_OUTPUT_FORMAT`.
+            cmd.env("RUFF_OUTPUT_FORMAT", "json")
+                .env("RUFF_FORMAT", "json");
+            check_output(&mut cmd, &mut terminal).and_then(|output| {
+                findings
+                    .extend(parse_ruff_findings(&output, workspace.root())?);
+                Ok(())
+            })
+        };
+        // Fixes are re-staged even if unfixable findings remain.
+        if fix && options.update_index {
+            update_index(&workspace, &targets, &unstaged, &mut terminal)?;
+        }
+        result?;
+    }
+
+    if !options.only_types && !notebooks.is_empty() {
+        // Install `nbqa` if it isn't already installed, alongside the `ruff` it runs.
+        let nbqa_dep = tool_dependency("nbqa", &tools)?;
+        let nbqa_env = tool_env(&[ruff_dep.clone(), nbqa_dep.clone()])?;
+        lint_deps.push(nbqa_dep);
+
+        // Run `ruff` on the notebooks with `nbqa`.
+        let mut nbqa_cmd = nbqa_command("ruff", &notebooks, &nbqa_env, config)?;
+        nbqa_cmd.envs(env_vars.iter().cloned());
+        nbqa_cmd
+            .args(&ruff_args)
+            .args(&ruff_config)
+            .current_dir(workspace.root());
+        let result = if options.output == LintOutput::Text {
+            terminal.run_command(&mut nbqa_cmd)
+        } else {
+            check_output(&mut nbqa_cmd, &mut terminal).map(|output| {
+                findings.extend(parse_nbqa_ruff_findings(&output));
+            })
+        };
+        if fix && options.update_index {
+            update_index(&workspace, &notebooks, &unstaged, &mut terminal)?;
+        }
+        result?;
+    }
+
+    if options.include_security && !options.only_types && !targets.is_empty() {
+        // Install `bandit` if it isn't already installed.
+        let bandit_dep = tool_dependency("bandit[toml]", &tools)?;
+        let bandit_env = tool_env(&[bandit_dep.clone()])?;
+        lint_deps.push(bandit_dep);
+
+        // Run `bandit`, reporting findings below the configured severity as warnings.
+        let security = SecurityConfig::from_metadata(metadata.metadata())?;
+        let mut bandit_cmd = Command::new(bandit_env.python_path());
+        make_venv_command(&mut bandit_cmd, &bandit_env, config)?;
+        bandit_cmd.envs(env_vars.iter().cloned());
+        bandit_cmd
+            .args(["-m", "bandit", "-r"])
+            .args(&targets)
+            .args(["-f", "json", "-q", "-x", excluded_paths.as_str()])
+            .current_dir(workspace.root());
+        if !security.skip.is_empty() {
+            bandit_cmd.args(["-s", security.skip.join(",").as_str()]);
+        }
+        if metadata.metadata().tool_table("bandit").is_some() {
+            bandit_cmd.args(["-c", "pyproject.toml"]);
+        }
+        findings.extend(parse_bandit_findings(
+            &check_output(&mut bandit_cmd, &mut terminal)?,
+            workspace.root(),
+            security.severity,
+        )?);
+    }
+
+    if (options.include_dead_code || lint_config.dead_code)
+        && !options.only_types
+        && !targets.is_empty()
+    {
+        // Install `vulture` if it isn't already installed.
+        let vulture_dep = tool_dependency("vulture", &tools)?;
+        let vulture_env = tool_env(&[vulture_dep.clone()])?;
+        lint_deps.push(vulture_dep);
+
+        // Run `vulture` with the allowlist. It exits with 3 when it finds dead code.
+        let mut vulture_cmd = Command::new(vulture_env.python_path());
+        make_venv_command(&mut vulture_cmd, &vulture_env, config)?;
+        vulture_cmd.envs(env_vars.iter().cloned());
+        vulture_cmd
+            .args(vulture_args(
+                &targets,
+                lint_config.allowlist.as_deref(),
+                &excluded_paths,
+            ))
+            .current_dir(workspace.root());
+        findings.extend(parse_vulture_findings(&check_output_with_codes(
+            &mut vulture_cmd,
+            &[0, 3],
+            &mut terminal,
+        )?));
     }
-    make_venv_command(&mut cmd, &python_env)?;
-    cmd.args(args).current_dir(workspace.root());
-    terminal.run_command(&mut cmd)?;
 
-    // Add installed lint deps (potentially both `mypy` and `ruff`) to metadata file if not already there.
+    // Add installed lint deps to the metadata file if not already there and not pinned.
+    // Tools run from a tool environment aren't the project's dependencies.
     let new_lint_deps = lint_deps
         .iter()
         .filter(|dep| {
-            !metadata
-                .metadata()
-                .contains_dependency_any(dep)
-                .unwrap_or_default()
+            !isolated
+                && !tools.iter().any(|it| it.name() == dep.name())
+                && !metadata
+                    .metadata()
+                    .contains_dependency_any(dep)
+                    .unwrap_or_default()
         })
         .map(|dep| dep.name())
         .collect::<Vec<_>>();
@@ -96,7 +330,517 @@ pub fn lint_project(config: &Config, options: &LintOptions) -> HuakResult<()> {
         metadata.write_file()?;
     }
 
-    Ok(())
+    // Only findings from the security and dead code passes are collected in text mode.
+    let report = match options.output {
+        LintOutput::Text => findings.iter().map(text_finding).collect(),
+        LintOutput::Sarif => {
+            serde_json::to_string_pretty(&sarif_log(&findings))? + "\n"
+        }
+        LintOutput::Github => findings.iter().map(github_annotation).collect(),
+    };
+    match options.output_path.as_ref() {
+        Some(path) => std::fs::write(workspace.root().join(path), report)?,
+        None => terminal.print_stdout(&report)?,
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|it| it.level == Level::Error)
+        .count();
+    if errors == 0 {
+        Ok(())
+    } else {
+        Err(Error::CheckFailed(format!("{errors} lint error(s) found")))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The severity of a `Finding`.
+enum Level {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Level {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note => "note",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A problem reported by a lint tool.
+struct Finding {
+    /// The name of the tool that reported the `Finding`.
+    tool: &'static str,
+    /// The tool's code for the rule, such as "F401".
+    rule: Option<String>,
+    /// A URL documenting the rule.
+    rule_url: Option<String>,
+    level: Level,
+    message: String,
+    /// The path to the file, relative to the workspace root and using '/' separators.
+    path: String,
+    line: usize,
+    column: usize,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// The severity `bandit` assigns to an issue.
+enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl FromStr for Severity {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            _ => Err(Error::HuakConfigurationError(format!(
+                "{s} is not a security severity (use low, medium, or high)"
+            ))),
+        }
+    }
+}
+
+/// Settings for the security pass from the `[tool.huak.security]` table.
+///
+/// ```toml
+/// [tool.huak.security]
+/// severity = "medium"
+/// skip = ["B101"]
+/// ```
+struct SecurityConfig {
+    /// The minimum severity of issues that fail the lint.
+    severity: Severity,
+    /// The IDs of `bandit` tests to skip.
+    skip: Vec<String>,
+}
+
+impl SecurityConfig {
+    fn from_metadata(metadata: &Metadata) -> HuakResult<SecurityConfig> {
+        let severity = match metadata.huak_setting("security", "severity") {
+            Some(it) => Severity::from_str(it.as_str().unwrap_or_default())?,
+            None => Severity::Medium,
+        };
+        let skip = metadata
+            .huak_setting("security", "skip")
+            .and_then(|it| it.as_array())
+            .map(|it| {
+                it.iter()
+                    .filter_map(|id| id.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(SecurityConfig { severity, skip })
+    }
+}
+
+/// Settings for `lint_project` from the `[tool.huak.lint]` table.
+///
+/// ```toml
+/// [tool.huak.lint]
+/// dead-code = true
+/// allowlist = "allowlist.py"
+/// ```
+struct LintConfig {
+    /// Find dead code with `vulture`.
+    dead_code: bool,
+    /// A `vulture` allowlist of code that's used despite looking unused.
+    allowlist: Option<String>,
+    /// Lint docstrings with `ruff`'s pydocstyle (`D`) rules. Set by `[tool.huak.fmt]`
+    /// since docstrings are formatted alongside code.
+    docstrings: bool,
+    /// Lint Jupyter notebooks with `nbqa`.
+    notebooks: bool,
+}
+
+impl LintConfig {
+    fn from_metadata(metadata: &Metadata) -> LintConfig {
+        let dead_code = metadata
+            .huak_setting("lint", "dead-code")
+            .and_then(|it| it.as_bool())
+            .unwrap_or_default();
+        let allowlist = metadata
+            .huak_setting("lint", "allowlist")
+            .and_then(|it| it.as_str())
+            .map(str::to_string);
+
+        let docstrings = metadata
+            .huak_setting("fmt", "docstrings")
+            .and_then(|it| it.as_bool())
+            .unwrap_or_default();
+
+        let notebooks = metadata
+            .huak_setting("lint", "notebooks")
+            .and_then(|it| it.as_bool())
+            .unwrap_or(true);
+
+        LintConfig {
+            dead_code,
+            allowlist,
+            docstrings,
+            notebooks,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BanditReport {
+    results: Vec<BanditResult>,
+}
+
+#[derive(Deserialize)]
+struct BanditResult {
+    filename: PathBuf,
+    line_number: usize,
+    col_offset: Option<usize>,
+    issue_severity: String,
+    issue_text: String,
+    test_id: String,
+    more_info: Option<String>,
+}
+
+/// Parse the `Finding`s from `bandit`'s JSON output. Issues below the `threshold` severity
+/// are reported as warnings so that they don't fail the lint.
+fn parse_bandit_findings(
+    output: &str,
+    root: &Path,
+    threshold: Severity,
+) -> HuakResult<Vec<Finding>> {
+    if output.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let report: BanditReport = serde_json::from_str(output)?;
+
+    report
+        .results
+        .into_iter()
+        .map(|it| {
+            let severity = Severity::from_str(&it.issue_severity)?;
+            Ok(Finding {
+                tool: "bandit",
+                rule: Some(it.test_id),
+                rule_url: it.more_info,
+                level: if severity >= threshold {
+                    Level::Error
+                } else {
+                    Level::Warning
+                },
+                message: format!(
+                    "{} (severity: {})",
+                    it.issue_text,
+                    it.issue_severity.to_lowercase()
+                ),
+                path: relative_path(&it.filename, root),
+                line: it.line_number,
+                column: it.col_offset.unwrap_or_default() + 1,
+                end_line: None,
+                end_column: None,
+            })
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct RuffLocation {
+    row: usize,
+    column: usize,
+}
+
+#[derive(Deserialize)]
+struct RuffDiagnostic {
+    code: Option<String>,
+    message: String,
+    filename: PathBuf,
+    location: RuffLocation,
+    end_location: Option<RuffLocation>,
+    url: Option<String>,
+}
+
+/// Parse the `Finding`s from `ruff`'s JSON output.
+fn parse_ruff_findings(output: &str, root: &Path) -> HuakResult<Vec<Finding>> {
+    if output.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let diagnostics: Vec<RuffDiagnostic> = serde_json::from_str(output)?;
+
+    Ok(diagnostics
+        .into_iter()
+        .map(|it| Finding {
+            tool: "ruff",
+            rule: it.code,
+            rule_url: it.url,
+            level: Level::Error,
+            message: it.message,
+            path: relative_path(&it.filename, root),
+            line: it.location.row,
+            column: it.location.column,
+            end_line: it.end_location.as_ref().map(|loc| loc.row),
+            end_column: it.end_location.as_ref().map(|loc| loc.column),
+        })
+        .collect())
+}
+
+/// Parse the `Finding`s from `mypy`'s output, which has one finding per line like
+/// "src/a.py:1:5: error: Message  [code]".
+fn parse_mypy_findings(output: &str) -> Vec<Finding> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, ':');
+            let path = parts.next()?;
+            let line = parts.next()?.trim().parse().ok()?;
+            let column = parts.next()?.trim().parse().ok()?;
+            let (level, message) = parts.next()?.trim().split_once(": ")?;
+            let level = match level {
+                "error" => Level::Error,
+                "warning" => Level::Warning,
+                "note" => Level::Note,
+                _ => return None,
+            };
+            let (message, rule) = match message.rsplit_once("  [") {
+                Some((message, code)) if code.ends_with(']') => {
+                    (message, Some(code.trim_end_matches(']').to_string()))
+                }
+                _ => (message, None),
+            };
+
+            Some(Finding {
+                tool: "mypy",
+                rule_url: None,
+                rule,
+                level,
+                message: message.trim().to_string(),
+                path: path.replace('\\', "/"),
+                line,
+                column,
+                end_line: None,
+                end_column: None,
+            })
+        })
+        .collect()
+}
+
+/// Get the arguments passed to `python` to find dead code in the `targets` with
+/// `vulture`. The allowlist is passed as another path to scan, which is how `vulture`
+/// learns that the code it lists is used.
+fn vulture_args(
+    targets: &[String],
+    allowlist: Option<&str>,
+    excluded_paths: &str,
+) -> Vec<String> {
+    let mut args = vec!["-m".to_string(), "vulture".to_string()];
+    args.extend(targets.iter().cloned());
+    args.extend(allowlist.map(str::to_string));
+    args.extend(["--exclude".to_string(), excluded_paths.to_string()]);
+
+    args
+}
+
+/// Parse the `Finding`s from `vulture`'s output, which has one finding per line like
+/// "src/a.py:1: unused function 'fn' (60% confidence)".
+fn parse_vulture_findings(output: &str) -> Vec<Finding> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let path = parts.next()?;
+            let line = parts.next()?.trim().parse().ok()?;
+            let message = parts.next()?.trim();
+
+            Some(Finding {
+                tool: "vulture",
+                rule: None,
+                rule_url: None,
+                level: Level::Error,
+                message: message.to_string(),
+                path: path.replace('\\', "/"),
+                line,
+                column: 1,
+                end_line: None,
+                end_column: None,
+            })
+        })
+        .collect()
+}
+
+/// Parse the `Finding`s from `ruff`'s output when it's run on notebooks with `nbqa`, which
+/// has one finding per line like "a.ipynb:cell_2:1:8: F401 [*] Message". Lines and columns
+/// are relative to the cell.
+fn parse_nbqa_ruff_findings(output: &str) -> Vec<Finding> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(5, ':');
+            let path = parts.next()?;
+            let cell = parts.next()?;
+            let line = parts.next()?.trim().parse().ok()?;
+            let column = parts.next()?.trim().parse().ok()?;
+            let (rule, message) = parts.next()?.trim().split_once(' ')?;
+            let message = message.trim_start_matches("[*]").trim();
+
+            Some(Finding {
+                tool: "ruff",
+                rule: Some(rule.to_string()),
+                rule_url: None,
+                level: Level::Error,
+                message: format!("{cell}: {message}"),
+                path: path.replace('\\', "/"),
+                line,
+                column,
+                end_line: None,
+                end_column: None,
+            })
+        })
+        .collect()
+}
+
+/// Get a path relative to the workspace root with '/' separators.
+fn relative_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .filter(|it| it != &Component::CurDir)
+        .map(|it| it.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Build a SARIF 2.1.0 log with a run for each tool that reported `Finding`s.
+fn sarif_log(findings: &[Finding]) -> Value {
+    let mut tools = Vec::new();
+    for finding in findings {
+        if !tools.contains(&finding.tool) {
+            tools.push(finding.tool);
+        }
+    }
+
+    let runs = tools
+        .into_iter()
+        .map(|tool| {
+            let findings = findings.iter().filter(|it| it.tool == tool);
+            let mut rules: Vec<Value> = Vec::new();
+            for finding in findings.clone() {
+                let Some(rule) = finding.rule.as_ref() else {
+                    continue;
+                };
+                if rules.iter().all(|it| it["id"] != rule.as_str()) {
+                    let mut it = json!({ "id": rule });
+                    if let Some(url) = finding.rule_url.as_ref() {
+                        it["helpUri"] = json!(url);
+                    }
+                    rules.push(it);
+                }
+            }
+            let results = findings
+                .map(|it| {
+                    let mut region = json!({
+                        "startLine": it.line,
+                        "startColumn": it.column,
+                    });
+                    if let Some(line) = it.end_line {
+                        region["endLine"] = json!(line);
+                    }
+                    if let Some(column) = it.end_column {
+                        region["endColumn"] = json!(column);
+                    }
+                    let mut result = json!({
+                        "level": it.level.as_str(),
+                        "message": { "text": it.message },
+                        "locations": [{
+                            "physicalLocation": {
+                                "artifactLocation": { "uri": it.path },
+                                "region": region,
+                            }
+                        }],
+                    });
+                    if let Some(rule) = it.rule.as_ref() {
+                        result["ruleId"] = json!(rule);
+                    }
+                    result
+                })
+                .collect::<Vec<_>>();
+
+            json!({
+                "tool": { "driver": { "name": tool, "rules": rules } },
+                "results": results,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": runs,
+    })
+}
+
+/// Format a `Finding` as a line of text like "src/a.py:1:1: B101 Message".
+fn text_finding(finding: &Finding) -> String {
+    format!(
+        "{}:{}:{}: {} {}\n",
+        finding.path,
+        finding.line,
+        finding.column,
+        finding.rule.as_deref().unwrap_or(finding.tool),
+        finding.message
+    )
+}
+
+/// Format a `Finding` as a GitHub Actions workflow command.
+fn github_annotation(finding: &Finding) -> String {
+    let command = match finding.level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note => "notice",
+    };
+    let mut properties = vec![
+        format!("file={}", escape_property(&finding.path)),
+        format!("line={}", finding.line),
+        format!("col={}", finding.column),
+    ];
+    if let Some(line) = finding.end_line {
+        properties.push(format!("endLine={line}"));
+    }
+    if let Some(column) = finding.end_column {
+        properties.push(format!("endColumn={column}"));
+    }
+    let title = match finding.rule.as_ref() {
+        Some(rule) => format!("{} ({rule})", finding.tool),
+        None => finding.tool.to_string(),
+    };
+    properties.push(format!("title={}", escape_property(&title)));
+
+    format!(
+        "::{command} {}::{}\n",
+        properties.join(","),
+        escape_data(&finding.message)
+    )
+}
+
+/// Escape a workflow command's message.
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command's property value.
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
 }
 
 #[cfg(test)]
@@ -120,7 +864,16 @@ mod tests {
         let options = LintOptions {
             values: None,
             include_types: true,
+            only_types: false,
+            include_security: false,
+            include_dead_code: false,
             install_options: InstallOptions { values: None },
+            output: LintOutput::Text,
+            output_path: None,
+            changed: None,
+            staged: false,
+            update_index: false,
+            paths: Vec::new(),
         };
 
         lint_project(&config, &options).unwrap();
@@ -142,7 +895,16 @@ mod tests {
         let options = LintOptions {
             values: Some(vec![String::from("--fix")]),
             include_types: true,
+            only_types: false,
+            include_security: false,
+            include_dead_code: false,
             install_options: InstallOptions { values: None },
+            output: LintOutput::Text,
+            output_path: None,
+            changed: None,
+            staged: false,
+            update_index: false,
+            paths: Vec::new(),
         };
         let lint_fix_filepath =
             ws.root().join("src").join("mock_project").join("fix_me.py");
@@ -167,4 +929,136 @@ def fn():
 
         assert_eq!(post_fix_str, expected);
     }
+
+    #[test]
+    fn test_vulture_args() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let path = root.join("pyproject.toml");
+        let pyproject_toml = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(
+            &path,
+            format!(
+                "{pyproject_toml}\n[tool.huak.lint]\ndead-code = true\nallowlist = \"allowlist.py\"\n"
+            ),
+        )
+        .unwrap();
+        let config = test_config(root, cwd, Verbosity::Quiet);
+        let metadata = config.workspace().current_local_metadata().unwrap();
+        let lint_config = LintConfig::from_metadata(metadata.metadata());
+
+        assert!(lint_config.dead_code);
+        assert_eq!(
+            vulture_args(
+                &["src".to_string(), "tests".to_string()],
+                lint_config.allowlist.as_deref(),
+                ".venv,vendor",
+            ),
+            [
+                "-m",
+                "vulture",
+                "src",
+                "tests",
+                "allowlist.py",
+                "--exclude",
+                ".venv,vendor"
+            ]
+        );
+        assert_eq!(
+            vulture_args(&[".".to_string()], None, ".venv"),
+            ["-m", "vulture", ".", "--exclude", ".venv"]
+        );
+    }
+
+    #[test]
+    fn test_lint_findings_output() {
+        let root = Path::new("/mock-project");
+        let ruff_output = r#"[
+  {
+    "code": "F401",
+    "message": "`json` imported but unused",
+    "fix": null,
+    "location": {"row": 1, "column": 8},
+    "end_location": {"row": 1, "column": 12},
+    "filename": "/mock-project/src/mock_project/fix_me.py",
+    "noqa_row": 1,
+    "url": "https://beta.ruff.rs/docs/rules/unused-import"
+  }
+]"#;
+        let mypy_output = "src/mock_project/a.py:3:12: error: Incompatible return value type (got \"int\", expected \"str\")  [return-value]\n";
+
+        let mut findings = parse_ruff_findings(ruff_output, root).unwrap();
+        findings.extend(parse_mypy_findings(mypy_output));
+        let sarif = sarif_log(&findings);
+
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].path, "src/mock_project/fix_me.py");
+        assert_eq!(findings[1].rule.as_deref(), Some("return-value"));
+        assert_eq!(sarif["runs"].as_array().unwrap().len(), 2);
+        assert_eq!(sarif["runs"][0]["results"][0]["ruleId"], "F401");
+        assert_eq!(
+            sarif["runs"][1]["results"][0]["locations"][0]["physicalLocation"]
+                ["region"]["startLine"],
+            3
+        );
+        assert_eq!(
+            github_annotation(&findings[0]),
+            "::error file=src/mock_project/fix_me.py,line=1,col=8,endLine=1,endColumn=12,title=ruff (F401)::`json` imported but unused\n"
+        );
+    }
+
+    #[test]
+    fn test_text_findings() {
+        let root = Path::new("/mock-project");
+        let output = r#"{
+  "errors": [],
+  "results": [
+    {
+      "col_offset": 0,
+      "filename": "./src/mock_project/a.py",
+      "issue_confidence": "HIGH",
+      "issue_severity": "LOW",
+      "issue_text": "Use of assert detected.",
+      "line_number": 2,
+      "more_info": "https://bandit.readthedocs.io/en/latest/plugins/b101_assert_used.html",
+      "test_id": "B101",
+      "test_name": "assert_used"
+    },
+    {
+      "col_offset": 4,
+      "filename": "./src/mock_project/a.py",
+      "issue_confidence": "HIGH",
+      "issue_severity": "HIGH",
+      "issue_text": "subprocess call with shell=True identified, security issue.",
+      "line_number": 5,
+      "more_info": "https://bandit.readthedocs.io/en/latest/plugins/b602_subprocess_popen_with_shell_equals_true.html",
+      "test_id": "B602",
+      "test_name": "subprocess_popen_with_shell_equals_true"
+    }
+  ]
+}"#;
+
+        let mut findings =
+            parse_bandit_findings(output, root, Severity::Medium).unwrap();
+        findings.extend(parse_vulture_findings(
+            "src/mock_project/a.py:7: unused function 'fn' (60% confidence)\n",
+        ));
+
+        assert_eq!(findings[0].level, Level::Warning);
+        assert_eq!(findings[1].level, Level::Error);
+        assert_eq!(
+            text_finding(&findings[1]),
+            "src/mock_project/a.py:5:5: B602 subprocess call with shell=True identified, security issue. (severity: high)\n"
+        );
+        assert_eq!(
+            text_finding(&findings[2]),
+            "src/mock_project/a.py:7:1: vulture unused function 'fn' (60% confidence)\n"
+        );
+    }
 }
@@ -0,0 +1,933 @@
+use super::{
+    check_output_with_codes, project_constraints, project_dependencies,
+    project_requirements, report_constraints, write_constraints,
+};
+use crate::{
+    checksums::check_locked_checksums,
+    dependency::Dependency,
+    lockfile::{
+        lockfile_file_name, LockTarget, LockedPackage, LockedWheel, Lockfile,
+    },
+    package::canonical_package_name,
+    python_environment::PythonEnvironment,
+    sys::{SubprocessError, Terminal},
+    Config, Error, HuakResult, InstallOptions,
+};
+use pep440_rs::{Version, VersionSpecifiers};
+use pep508_rs::{MarkerEnvironment, MarkerTree, Requirement, VersionOrUrl};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    process::Command,
+    str::FromStr,
+};
+use termcolor::Color;
+
+pub struct LockOptions {
+    /// Lock the lowest versions of the project's dependencies that satisfy their
+    /// requirements instead of the highest.
+    pub minimal: bool,
+    /// A Python version ("<major>.<minor>") to add to the lockfile's targets.
+    pub python: Option<String>,
+    /// A platform to add to the lockfile's targets (see `LockTarget`).
+    pub platform: Option<String>,
+    /// Only re-resolve these packages, keeping the lockfile's other versions unless the
+    /// packages' requirements force them to change.
+    pub update: Vec<String>,
+    pub install_options: InstallOptions,
+}
+
+/// Resolve the project's dependencies, including every optional dependency group, and
+/// pin the resolved distributions and their hashes in the project's `Lockfile`.
+///
+/// Dependencies are resolved for the project's Python environment and every target the
+/// `Lockfile` already covers, along with the target requested by the `LockOptions`.
+/// Packages that are only needed by some targets are locked with markers. Resolution is
+/// delegated to pip, so index options passed with `InstallOptions` are respected.
+///
+/// When packages to update are given, every other package is constrained to its locked
+/// version. If that can't be resolved, the locked versions of the updated packages'
+/// dependencies are released too.
+pub fn lock_project(config: &Config, options: &LockOptions) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+    let mut terminal = config.terminal();
+
+    let requirements = project_requirements(&metadata);
+    let mut dependencies = project_dependencies(&metadata);
+    if options.minimal {
+        for dep in dependencies.iter_mut() {
+            pin_lowest_version(dep, &python_env, options, config)?;
+        }
+    }
+
+    let locked = workspace.current_lockfile()?;
+    let mut update = HashSet::new();
+    for name in options.update.iter() {
+        let Some(locked) = locked.as_ref() else {
+            return Err(Error::HuakConfigurationError(format!(
+                "there's no {} to update, run `huak lock` first",
+                lockfile_file_name()
+            )));
+        };
+        let name = normalize_name(name)?;
+        if !locked
+            .packages
+            .iter()
+            .any(|it| normalize_name(&it.name).map_or(false, |it| it == name))
+        {
+            return Err(Error::HuakConfigurationError(format!(
+                "{name} isn't in {}",
+                lockfile_file_name()
+            )));
+        }
+        update.insert(name);
+    }
+
+    let marker_env = python_env.marker_environment()?;
+    let current = current_target(&marker_env);
+    let mut targets = locked
+        .as_ref()
+        .map(|it| it.targets.clone())
+        .unwrap_or_default();
+    targets.push(current.clone());
+    if options.python.is_some() || options.platform.is_some() {
+        targets.push(LockTarget {
+            python: options.python.clone().unwrap_or(current.python.clone()),
+            platform: options
+                .platform
+                .clone()
+                .unwrap_or(current.platform.clone()),
+        });
+    }
+    targets.sort();
+    targets.dedup();
+
+    // Other targets can only be resolved with wheels. pip evaluates markers for its
+    // own interpreter, so the project's dependencies are filtered for them beforehand.
+    // pip also requires `--target` to resolve for another platform, but nothing is
+    // written to it in a dry run.
+    let mut requests = Vec::new();
+    for target in targets.iter() {
+        if *target == current {
+            requests.push((
+                dependencies.clone(),
+                Vec::new(),
+                marker_env.clone(),
+            ));
+            continue;
+        }
+        let mut args = cross_target_args(target)?;
+        args.push("--target".to_string());
+        args.push(
+            workspace
+                .root()
+                .join(".huak")
+                .join("lock-target")
+                .display()
+                .to_string(),
+        );
+        let env = target_marker_environment(&marker_env, target)?;
+        let deps = dependencies
+            .iter()
+            .filter(|it| it.applies_to(&env))
+            .map(|it| {
+                let mut it = it.clone();
+                it.requirement_mut().marker = None;
+                it
+            })
+            .collect::<Vec<_>>();
+        requests.push((deps, args, env));
+    }
+
+    let constraints = project_constraints(&metadata)?;
+    report_constraints(&constraints, &mut terminal)?;
+    let constrained = constraints
+        .iter()
+        .map(|it| normalize_name(it.name()))
+        .collect::<HuakResult<HashSet<_>>>()?;
+    let constraints = constraints
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    let mut lockfile =
+        Lockfile::new(workspace.root().join(lockfile_file_name()));
+    if !dependencies.is_empty() {
+        let mut resolutions = Vec::new();
+        for (target, (deps, args, env)) in targets.iter().zip(requests) {
+            if targets.len() > 1 {
+                terminal.print_custom(
+                    "Resolving",
+                    target,
+                    Color::Green,
+                    true,
+                )?;
+            }
+            if deps.is_empty() {
+                resolutions.push((target, Vec::new()));
+                continue;
+            }
+            let resolve = |constraints: &[String], quiet: bool| {
+                resolve(&python_env, &deps, &args, constraints, options, quiet)
+            };
+            let report = match locked.as_ref().filter(|_| !update.is_empty()) {
+                None => resolve(&constraints, false)?,
+                Some(locked) => {
+                    // Updated packages are pinned to the versions a fresh resolution
+                    // selects and every other package to its locked version, unless
+                    // it's constrained.
+                    let fresh = resolve(&constraints, false)?;
+                    let updated = report_pins(&fresh, &update)?;
+                    let pins = |released: &HashSet<String>| {
+                        let released =
+                            released.union(&constrained).cloned().collect();
+                        let mut pins = constraints.clone();
+                        pins.extend(locked_pins(locked, &env, &released)?);
+                        pins.extend(updated.iter().cloned());
+                        Ok::<_, Error>(pins)
+                    };
+                    match resolve(&pins(&update)?, true) {
+                        // Release the updated packages' dependencies if the new
+                        // versions need them to change.
+                        Err(Error::SubprocessFailure(_)) => {
+                            let released = dependency_closure(&fresh, &update)?;
+                            resolve(&pins(&released)?, false)?
+                        }
+                        res => res?,
+                    }
+                }
+            };
+            resolutions.push((target, packages_from_report(report)?));
+        }
+        lockfile.packages = merge_resolutions(resolutions)?;
+    }
+    lockfile.requirements = requirements;
+    lockfile.constraints = constraints;
+    lockfile.targets = targets;
+
+    // Packages without wheels can't be installed natively, so `huak install` falls back
+    // to pip for the whole lockfile.
+    for pkg in lockfile.packages.iter().filter(|it| it.wheels.is_empty()) {
+        terminal.print_warning(format!(
+            "{}=={} doesn't have a wheel, so `huak install` will use pip",
+            pkg.name, pkg.version
+        ))?;
+    }
+    check_locked_checksums(&lockfile.packages, &mut terminal)?;
+    lockfile.write_file()?;
+
+    if let Some(locked) = locked.as_ref().filter(|_| !update.is_empty()) {
+        for pkg in lockfile.packages.iter() {
+            let name = normalize_name(&pkg.name)?;
+            let previous = locked.packages.iter().find(|it| {
+                normalize_name(&it.name).map_or(false, |it| it == name)
+                    && it.markers == pkg.markers
+            });
+            match previous {
+                Some(it) if it.version == pkg.version => (),
+                Some(it) => terminal.print_custom(
+                    "Updated",
+                    format!("{} {} -> {}", pkg.name, it.version, pkg.version),
+                    Color::Green,
+                    true,
+                )?,
+                None => terminal.print_custom(
+                    "Added",
+                    format!("{} {}", pkg.name, pkg.version),
+                    Color::Green,
+                    true,
+                )?,
+            }
+        }
+    }
+
+    terminal.print_custom(
+        "Locked",
+        format!(
+            "{} package(s) for {} target(s){}",
+            lockfile.packages.len(),
+            lockfile.targets.len(),
+            if options.minimal {
+                " at their minimal versions"
+            } else {
+                ""
+            }
+        ),
+        Color::Green,
+        true,
+    )
+}
+
+/// Run pip's resolver for dependencies, returning its installation report. When `quiet`
+/// is set pip's error output isn't printed if the dependencies can't be resolved.
+fn resolve(
+    python_env: &PythonEnvironment,
+    dependencies: &[Dependency],
+    args: &[String],
+    constraints: &[String],
+    options: &LockOptions,
+    quiet: bool,
+) -> HuakResult<InstallationReport> {
+    let mut cmd = Command::new(python_env.python_path());
+    cmd.args([
+        "-m",
+        "pip",
+        "install",
+        "--dry-run",
+        "--ignore-installed",
+        "--quiet",
+        "--report",
+        "-",
+    ])
+    .args(dependencies.iter().map(ToString::to_string))
+    .args(args);
+    let constraints = match constraints.is_empty() {
+        true => None,
+        false => Some(write_constraints(constraints)?),
+    };
+    if let Some(it) = constraints.as_ref() {
+        cmd.arg("-c").arg(it.path());
+    }
+    if let Some(v) = options.install_options.values.as_ref() {
+        cmd.args(v);
+    }
+
+    let output = cmd.output()?;
+    if !output.status.success() {
+        let stderr = std::str::from_utf8(&output.stderr)?.trim();
+        if !quiet && !stderr.is_empty() {
+            Terminal::new().print_error(stderr)?;
+        }
+        return Err(Error::SubprocessFailure(SubprocessError::new(
+            output.status,
+        )));
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Get pins for the packages in a `Lockfile` that apply to an environment, except for
+/// the `unpinned` packages.
+fn locked_pins(
+    lockfile: &Lockfile,
+    env: &MarkerEnvironment,
+    unpinned: &HashSet<String>,
+) -> HuakResult<Vec<String>> {
+    let mut pins = Vec::new();
+    for pkg in lockfile.packages.iter() {
+        if unpinned.contains(&normalize_name(&pkg.name)?) {
+            continue;
+        }
+        if let Some(markers) = pkg.markers.as_ref() {
+            if !MarkerTree::from_str(markers)?.evaluate(env, &[]) {
+                continue;
+            }
+        }
+        pins.push(format!("{}=={}", pkg.name, pkg.version));
+    }
+
+    Ok(pins)
+}
+
+/// Get pins for the packages in an installation report with one of `names`.
+fn report_pins(
+    report: &InstallationReport,
+    names: &HashSet<String>,
+) -> HuakResult<Vec<String>> {
+    let mut pins = Vec::new();
+    for it in report.install.iter() {
+        if names.contains(&normalize_name(&it.metadata.name)?) {
+            pins.push(format!("{}=={}", it.metadata.name, it.metadata.version));
+        }
+    }
+
+    Ok(pins)
+}
+
+/// Get the packages in an installation report that `names` depend on, including
+/// themselves.
+fn dependency_closure(
+    report: &InstallationReport,
+    names: &HashSet<String>,
+) -> HuakResult<HashSet<String>> {
+    let mut closure = names.clone();
+    let mut queue = names.iter().cloned().collect::<Vec<_>>();
+    while let Some(name) = queue.pop() {
+        let mut item = None;
+        for it in report.install.iter() {
+            if normalize_name(&it.metadata.name)? == name {
+                item = Some(it);
+                break;
+            }
+        }
+        let Some(item) = item else {
+            continue;
+        };
+        for req in item.metadata.requires_dist.iter() {
+            let Ok(req) = Requirement::from_str(req) else {
+                continue;
+            };
+            let dep = normalize_name(&req.name)?;
+            if closure.insert(dep.clone()) {
+                queue.push(dep);
+            }
+        }
+    }
+
+    Ok(closure)
+}
+
+/// Normalize a package name for comparisons.
+fn normalize_name(name: &str) -> HuakResult<String> {
+    Ok(canonical_package_name(name)?.to_lowercase())
+}
+
+/// Get the `LockTarget` of a Python environment from its `MarkerEnvironment`.
+fn current_target(env: &MarkerEnvironment) -> LockTarget {
+    let os = match env.sys_platform.as_str() {
+        "darwin" => "macos",
+        "win32" => "windows",
+        it => it,
+    };
+    let platform = match env.platform_machine.to_lowercase().as_str() {
+        "x86_64" | "amd64" => os.to_string(),
+        "aarch64" | "arm64" => format!("{os}-arm64"),
+        it => format!("{os}-{it}"),
+    };
+
+    LockTarget {
+        python: env.python_version.0.clone(),
+        platform,
+    }
+}
+
+/// Get a `MarkerEnvironment` for a `LockTarget` from the Python environment's.
+fn target_marker_environment(
+    env: &MarkerEnvironment,
+    target: &LockTarget,
+) -> HuakResult<MarkerEnvironment> {
+    let version = |value: String| -> HuakResult<(String, Version)> {
+        let version =
+            Version::from_str(&value).map_err(Error::InvalidVersionString)?;
+        Ok((value, version))
+    };
+    let sys_platform = target.sys_platform()?;
+    let arm = matches!(
+        target.platform.split_once('-').map(|(_, arch)| arch),
+        Some("aarch64" | "arm64")
+    );
+    let (platform_system, os_name, platform_machine) = match sys_platform {
+        "darwin" => ("Darwin", "posix", if arm { "arm64" } else { "x86_64" }),
+        "win32" => ("Windows", "nt", if arm { "ARM64" } else { "AMD64" }),
+        _ => ("Linux", "posix", if arm { "aarch64" } else { "x86_64" }),
+    };
+    let full_version = format!("{}.0", target.python);
+
+    Ok(MarkerEnvironment {
+        implementation_version: version(full_version.clone())?,
+        os_name: os_name.to_string(),
+        platform_machine: platform_machine.to_string(),
+        platform_release: String::new(),
+        platform_system: platform_system.to_string(),
+        platform_version: String::new(),
+        python_full_version: version(full_version)?,
+        python_version: version(target.python.clone())?,
+        sys_platform: sys_platform.to_string(),
+        ..env.clone()
+    })
+}
+
+/// Get the pip arguments resolving wheels for a `LockTarget` other than the Python
+/// environment's.
+fn cross_target_args(target: &LockTarget) -> HuakResult<Vec<String>> {
+    if !target
+        .python
+        .split('.')
+        .all(|it| !it.is_empty() && it.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(Error::HuakConfigurationError(format!(
+            "invalid Python version {} (use <major>.<minor>, such as 3.10)",
+            target.python
+        )));
+    }
+
+    let mut args = vec![
+        "--python-version".to_string(),
+        target.python.clone(),
+        "--implementation".to_string(),
+        "cp".to_string(),
+        "--only-binary=:all:".to_string(),
+    ];
+    for tag in platform_tags(&target.platform)? {
+        args.push("--platform".to_string());
+        args.push(tag);
+    }
+
+    Ok(args)
+}
+
+/// Get the wheel platform tags for a platform as "<os>" or "<os>-<arch>". pip expands
+/// macOS tags to older releases itself, but manylinux tags are listed explicitly.
+fn platform_tags(platform: &str) -> HuakResult<Vec<String>> {
+    let (os, arch) = platform.split_once('-').unwrap_or((platform, "x86_64"));
+    let arm = match arch {
+        "x86_64" | "amd64" => false,
+        "aarch64" | "arm64" => true,
+        _ => {
+            return Err(Error::HuakConfigurationError(format!(
+                "unsupported architecture {arch} (use x86_64 or arm64)"
+            )))
+        }
+    };
+
+    let tags = match (os, arm) {
+        ("linux", _) => {
+            let arch = if arm { "aarch64" } else { "x86_64" };
+            let mut tags = (17..=39)
+                .rev()
+                .map(|minor| format!("manylinux_2_{minor}_{arch}"))
+                .collect::<Vec<_>>();
+            tags.push(format!("manylinux2014_{arch}"));
+            if !arm {
+                tags.push("manylinux2010_x86_64".to_string());
+                tags.push("manylinux1_x86_64".to_string());
+            }
+            tags
+        }
+        ("macos", false) => vec!["macosx_14_0_x86_64".to_string()],
+        ("macos", true) => vec!["macosx_14_0_arm64".to_string()],
+        ("windows", false) => vec!["win_amd64".to_string()],
+        ("windows", true) => vec!["win_arm64".to_string()],
+        _ => {
+            return Err(Error::HuakConfigurationError(format!(
+                "unsupported platform {platform} (use linux, macos, or windows)"
+            )))
+        }
+    };
+
+    Ok(tags)
+}
+
+/// Merge the packages resolved for each `LockTarget`. Packages resolved for every target
+/// apply everywhere, and the others are limited to their targets with markers.
+fn merge_resolutions(
+    resolutions: Vec<(&LockTarget, Vec<LockedPackage>)>,
+) -> HuakResult<Vec<LockedPackage>> {
+    let total = resolutions.len();
+    let mut merged: Vec<(LockedPackage, Vec<&LockTarget>)> = Vec::new();
+    for (target, packages) in resolutions {
+        for pkg in packages {
+            match merged.iter_mut().find(|(it, _)| {
+                it.name.eq_ignore_ascii_case(&pkg.name)
+                    && it.version == pkg.version
+            }) {
+                Some((it, targets)) => {
+                    for wheel in pkg.wheels {
+                        if !it.wheels.contains(&wheel) {
+                            it.wheels.push(wheel);
+                        }
+                    }
+                    targets.push(target);
+                }
+                None => merged.push((pkg, vec![target])),
+            }
+        }
+    }
+
+    let mut packages = Vec::new();
+    for (mut pkg, targets) in merged {
+        if targets.len() < total {
+            let mut markers = targets
+                .iter()
+                .map(|it| it.markers())
+                .collect::<HuakResult<Vec<_>>>()?;
+            markers.sort();
+            markers.dedup();
+            pkg.markers = Some(match markers.as_slice() {
+                [it] => it.clone(),
+                _ => markers
+                    .iter()
+                    .map(|it| format!("({it})"))
+                    .collect::<Vec<_>>()
+                    .join(" or "),
+            });
+        }
+        packages.push(pkg);
+    }
+    packages.sort_by_key(|it| it.name.to_lowercase());
+
+    Ok(packages)
+}
+
+/// Pin a `Dependency` to the lowest version on the package index satisfying its
+/// requirement. Dependencies on URLs are left as they are.
+fn pin_lowest_version(
+    dependency: &mut Dependency,
+    python_env: &PythonEnvironment,
+    options: &LockOptions,
+    config: &Config,
+) -> HuakResult<()> {
+    let specifiers = match dependency.requirement().version_or_url.as_ref() {
+        Some(VersionOrUrl::Url(_)) => return Ok(()),
+        Some(VersionOrUrl::VersionSpecifier(it)) => Some(it),
+        None => None,
+    };
+
+    let mut cmd = Command::new(python_env.python_path());
+    cmd.args(["-m", "pip", "index", "versions", dependency.name()])
+        .args(index_args(
+            options
+                .install_options
+                .values
+                .as_deref()
+                .unwrap_or_default(),
+        ));
+    let stdout =
+        check_output_with_codes(&mut cmd, &[0], &mut config.terminal())?;
+    let versions = available_versions(&stdout);
+    let Some(version) = lowest_version(&versions, specifiers) else {
+        return Err(Error::HuakConfigurationError(format!(
+            "no release on the package index satisfies {dependency}"
+        )));
+    };
+
+    dependency.requirement_mut().version_or_url =
+        Some(VersionOrUrl::VersionSpecifier(VersionSpecifiers::from_str(
+            &format!("=={version}"),
+        )?));
+
+    Ok(())
+}
+
+/// Get the arguments among pip install arguments that configure the package index, so
+/// that they can be passed to other pip commands.
+fn index_args(values: &[String]) -> Vec<String> {
+    const WITH_VALUE: [&str; 6] = [
+        "-i",
+        "--index-url",
+        "--extra-index-url",
+        "-f",
+        "--find-links",
+        "--trusted-host",
+    ];
+    const FLAGS: [&str; 2] = ["--no-index", "--pre"];
+
+    let mut args = Vec::new();
+    let mut values = values.iter();
+    while let Some(it) = values.next() {
+        let name = it.split('=').next().unwrap_or(it);
+        if FLAGS.contains(&it.as_str()) {
+            args.push(it.clone());
+        } else if WITH_VALUE.contains(&it.as_str()) {
+            args.push(it.clone());
+            args.extend(values.next().cloned());
+        } else if it.contains('=') && WITH_VALUE.contains(&name) {
+            args.push(it.clone());
+        }
+    }
+
+    args
+}
+
+/// Parse the versions listed by `pip index versions`.
+fn available_versions(stdout: &str) -> Vec<Version> {
+    stdout
+        .lines()
+        .find_map(|it| it.trim().strip_prefix("Available versions:"))
+        .map(|it| {
+            it.split(',')
+                .filter_map(|it| Version::from_str(it.trim()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Get the lowest version satisfying `specifiers`. Pre-releases are only selected when
+/// no final release satisfies them.
+fn lowest_version(
+    versions: &[Version],
+    specifiers: Option<&VersionSpecifiers>,
+) -> Option<Version> {
+    let mut versions = versions
+        .iter()
+        .filter(|it| specifiers.map_or(true, |s| s.contains(it)))
+        .collect::<Vec<_>>();
+    versions.sort();
+
+    versions
+        .iter()
+        .find(|it| !it.any_prerelease())
+        .or(versions.first())
+        .map(|it| (*it).clone())
+}
+
+#[derive(Deserialize)]
+/// The parts of pip's installation report used to lock its resolution.
+struct InstallationReport {
+    install: Vec<InstallationReportItem>,
+}
+
+#[derive(Deserialize)]
+struct InstallationReportItem {
+    download_info: DownloadInfo,
+    metadata: InstallationReportMetadata,
+}
+
+#[derive(Deserialize)]
+struct DownloadInfo {
+    url: String,
+    #[serde(default)]
+    archive_info: Option<ArchiveInfo>,
+}
+
+#[derive(Deserialize)]
+struct ArchiveInfo {
+    #[serde(default)]
+    hash: Option<String>,
+    #[serde(default)]
+    hashes: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+struct InstallationReportMetadata {
+    name: String,
+    version: String,
+    #[serde(default)]
+    requires_dist: Vec<String>,
+}
+
+/// Get the packages in pip's installation report. Distributions that aren't wheels are
+/// locked without wheels.
+fn packages_from_report(
+    report: InstallationReport,
+) -> HuakResult<Vec<LockedPackage>> {
+    let mut packages = Vec::new();
+    for item in report.install {
+        let hash = item.download_info.archive_info.and_then(|it| {
+            match it.hashes.and_then(|it| it.get("sha256").cloned()) {
+                Some(digest) => Some(format!("sha256:{digest}")),
+                None => it.hash.map(|it| it.replacen('=', ":", 1)),
+            }
+        });
+        let wheel = LockedWheel {
+            url: item.download_info.url,
+            hash: hash.unwrap_or_default(),
+        };
+        packages.push(LockedPackage {
+            name: item.metadata.name,
+            version: item.metadata.version,
+            markers: None,
+            wheels: match wheel.file_name().ends_with(".whl") {
+                true => vec![wheel],
+                false => Vec::new(),
+            },
+        });
+    }
+
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::LocalMetadata;
+
+    #[test]
+    fn test_packages_from_report() {
+        let report = r#"{
+  "version": "1",
+  "install": [
+    {
+      "download_info": {
+        "url": "https://files.example.com/click-8.1.3-py3-none-any.whl",
+        "archive_info": {"hashes": {"sha256": "bb4d"}}
+      },
+      "metadata": {"name": "click", "version": "8.1.3"}
+    },
+    {
+      "download_info": {
+        "url": "https://files.example.com/Babel-2.0.tar.gz",
+        "archive_info": {"hash": "sha256=a1b2"}
+      },
+      "metadata": {"name": "Babel", "version": "2.0"}
+    }
+  ]
+}"#;
+
+        let packages =
+            packages_from_report(serde_json::from_str(report).unwrap())
+                .unwrap();
+
+        assert_eq!(packages[0].wheels[0].hash, "sha256:bb4d");
+        assert_eq!(packages[1].name, "Babel");
+        assert!(packages[1].wheels.is_empty());
+    }
+
+    #[test]
+    fn test_merge_resolutions() {
+        let target = |python: &str, platform: &str| LockTarget {
+            python: python.to_string(),
+            platform: platform.to_string(),
+        };
+        let package = |name: &str, file_name: &str| LockedPackage {
+            name: name.to_string(),
+            version: "1.0".to_string(),
+            markers: None,
+            wheels: vec![LockedWheel {
+                url: format!("https://files.example.com/{file_name}"),
+                hash: "sha256:00".to_string(),
+            }],
+        };
+        let linux = target("3.11", "linux");
+        let macos = target("3.10", "macos");
+
+        let packages = merge_resolutions(vec![
+            (
+                &linux,
+                vec![
+                    package("numpy", "numpy-1.0-cp311-cp311-manylinux.whl"),
+                    package("uvloop", "uvloop-1.0-cp311-cp311-manylinux.whl"),
+                ],
+            ),
+            (
+                &macos,
+                vec![package("numpy", "numpy-1.0-cp310-cp310-macosx.whl")],
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].markers, None);
+        assert_eq!(packages[0].wheels.len(), 2);
+        assert_eq!(
+            packages[1].markers.as_deref(),
+            Some(r#"python_version == "3.11" and sys_platform == "linux""#)
+        );
+    }
+
+    #[test]
+    fn test_platform_tags() {
+        assert_eq!(
+            platform_tags("macos-arm64").unwrap(),
+            vec!["macosx_14_0_arm64"]
+        );
+        assert!(platform_tags("linux-aarch64")
+            .unwrap()
+            .contains(&"manylinux2014_aarch64".to_string()));
+        assert!(platform_tags("solaris").is_err());
+    }
+
+    #[test]
+    fn test_lowest_version() {
+        let versions = available_versions(
+            "click (8.1.3)\nAvailable versions: 8.1.3, 8.1.0, 8.0.0, 8.0.0rc1, 7.1.2\n",
+        );
+        let specifiers = VersionSpecifiers::from_str(">=8.0.0rc1").unwrap();
+
+        assert_eq!(
+            lowest_version(&versions, Some(&specifiers)),
+            Version::from_str("8.0.0").ok()
+        );
+        assert_eq!(
+            lowest_version(&versions, None),
+            Version::from_str("7.1.2").ok()
+        );
+    }
+
+    #[test]
+    fn test_index_args() {
+        let values = [
+            "--no-deps",
+            "-i",
+            "https://example.com/simple",
+            "--find-links=wheels",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            index_args(&values),
+            vec!["-i", "https://example.com/simple", "--find-links=wheels"]
+        );
+    }
+
+    #[test]
+    fn test_dependency_closure() {
+        let report = r#"{
+  "install": [
+    {
+      "download_info": {"url": "https://files.example.com/a-2.0-py3-none-any.whl"},
+      "metadata": {"name": "a", "version": "2.0", "requires_dist": ["B_Pkg>=2"]}
+    },
+    {
+      "download_info": {"url": "https://files.example.com/b_pkg-2.0-py3-none-any.whl"},
+      "metadata": {"name": "b-pkg", "version": "2.0", "requires_dist": ["c; extra == 'cli'"]}
+    },
+    {
+      "download_info": {"url": "https://files.example.com/d-1.0-py3-none-any.whl"},
+      "metadata": {"name": "d", "version": "1.0"}
+    }
+  ]
+}"#;
+        let report: InstallationReport = serde_json::from_str(report).unwrap();
+        let names = HashSet::from(["a".to_string()]);
+
+        let closure = dependency_closure(&report, &names).unwrap();
+
+        assert_eq!(
+            closure,
+            HashSet::from(["a", "b-pkg", "c"].map(String::from))
+        );
+        assert_eq!(report_pins(&report, &names).unwrap(), vec!["a==2.0"]);
+    }
+
+    #[test]
+    fn test_project_constraints() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pyproject.toml");
+        std::fs::write(
+            &path,
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "proj"
+version = "0.0.1"
+
+[tool.huak.constraints]
+urllib3 = "<2"
+charset-normalizer = "3.1.0"
+"#,
+        )
+        .unwrap();
+        let metadata = LocalMetadata::new(&path).unwrap();
+
+        let constraints = project_constraints(&metadata)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(constraints, ["urllib3 <2", "charset-normalizer ==3.1.0"]);
+
+        std::fs::write(
+            &path,
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "proj"
+
+[tool.huak.constraints]
+urllib3 = 2
+"#,
+        )
+        .unwrap();
+        let metadata = LocalMetadata::new(&path).unwrap();
+
+        assert!(project_constraints(&metadata).is_err());
+    }
+}
@@ -0,0 +1,927 @@
+use crate::{
+    metadata::{LocalMetadata, Metadata},
+    package::canonical_package_name,
+    sys::Table,
+    Config, Error, HuakResult,
+};
+use pep440_rs::{Operator, Version, VersionSpecifier};
+use pep508_rs::{Requirement, VersionOrUrl};
+use std::{
+    cmp,
+    collections::{BTreeMap, VecDeque},
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use termcolor::Color;
+
+/// How often a running member is polled for completion or cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The manifest files used to tell what kind of project a member is, in order of
+/// precedence. Only Python members (with a pyproject.toml) are run.
+const MEMBER_MANIFESTS: [(&str, &str); 7] = [
+    ("pyproject.toml", "python"),
+    ("package.json", "node"),
+    ("Cargo.toml", "rust"),
+    ("go.mod", "go"),
+    ("pom.xml", "java"),
+    ("build.gradle", "java"),
+    ("build.gradle.kts", "kotlin"),
+];
+
+pub struct MembersOptions {
+    /// The program to run from each workspace member's directory.
+    pub program: PathBuf,
+    /// The arguments passed to the program.
+    pub args: Vec<String>,
+    /// Stop running members once one of them fails.
+    pub fail_fast: bool,
+}
+
+pub struct HoistOptions {
+    /// Write the constraints shared by the members to this file.
+    pub constraints_path: Option<PathBuf>,
+}
+
+enum MemberStatus {
+    Passed,
+    Failed(Option<i32>),
+    Cancelled,
+}
+
+/// A directory matched by the workspace's member patterns.
+struct WorkspaceMember {
+    path: PathBuf,
+    /// The kind of project the member is, like "python" or "node".
+    kind: &'static str,
+    /// The member's manifest, if one was found.
+    manifest: Option<PathBuf>,
+}
+
+impl WorkspaceMember {
+    fn new(path: PathBuf) -> WorkspaceMember {
+        let found = MEMBER_MANIFESTS
+            .iter()
+            .find(|(file, _)| path.join(file).is_file());
+        WorkspaceMember {
+            kind: found.map_or("unknown", |(_, kind)| kind),
+            manifest: found.map(|(file, _)| path.join(file)),
+            path,
+        }
+    }
+
+    fn is_python(&self) -> bool {
+        self.kind == "python"
+    }
+}
+
+struct MemberReport {
+    name: String,
+    status: MemberStatus,
+    elapsed: Duration,
+}
+
+/// Run a command for each of the workspace's members (`[tool.huak.workspace] members`)
+/// in parallel. Output is prefixed with the member it came from, and a summary of each
+/// member's result is printed once every member has finished.
+pub fn run_workspace_members(
+    config: &Config,
+    options: &MembersOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let (members, skipped): (Vec<_>, Vec<_>) =
+        workspace_members(workspace.root(), metadata.metadata())?
+            .into_iter()
+            .partition(WorkspaceMember::is_python);
+    let mut terminal = config.terminal();
+    for member in skipped.iter() {
+        terminal.print_custom(
+            "Skipping",
+            format!(
+                "{} ({} project without a pyproject.toml)",
+                member_name(workspace.root(), &member.path),
+                member.kind
+            ),
+            Color::Yellow,
+            true,
+        )?;
+    }
+    if members.is_empty() {
+        return Err(Error::HuakConfigurationError(
+            "no Python workspace members are listed in [tool.huak.workspace]"
+                .to_string(),
+        ));
+    }
+    let members = members.into_iter().map(|it| it.path).collect::<Vec<_>>();
+    let names = members
+        .iter()
+        .map(|it| member_name(workspace.root(), it))
+        .collect::<Vec<_>>();
+    let width = names.iter().map(String::len).max().unwrap_or_default();
+
+    let queue = Mutex::new(
+        members
+            .iter()
+            .zip(names.iter())
+            .enumerate()
+            .collect::<VecDeque<_>>(),
+    );
+    let cancelled = AtomicBool::new(false);
+    let results = Mutex::new(Vec::new());
+    let workers = thread::available_parallelism()
+        .map_or(1, |it| it.get())
+        .min(members.len());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let Some((index, (path, name))) =
+                    queue.lock().unwrap().pop_front()
+                else {
+                    break;
+                };
+                let start = Instant::now();
+                let status = match cancelled.load(Ordering::SeqCst) {
+                    true => Ok(MemberStatus::Cancelled),
+                    false => run_member(
+                        path,
+                        &format!("{name:width$} |"),
+                        options,
+                        &cancelled,
+                    ),
+                };
+                if options.fail_fast
+                    && !matches!(status, Ok(MemberStatus::Passed))
+                {
+                    cancelled.store(true, Ordering::SeqCst);
+                }
+                let report = status.map(|status| MemberReport {
+                    name: name.to_string(),
+                    status,
+                    elapsed: start.elapsed(),
+                });
+                results.lock().unwrap().push((index, report));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    let mut reports = Vec::new();
+    for (_, report) in results {
+        reports.push(report?);
+    }
+
+    let mut failed = 0;
+    for report in reports.iter() {
+        let (title, color) = match report.status {
+            MemberStatus::Passed => ("Passed", Color::Green),
+            MemberStatus::Failed(_) => {
+                failed += 1;
+                ("Failed", Color::Red)
+            }
+            MemberStatus::Cancelled => ("Cancelled", Color::Yellow),
+        };
+        let mut message = format!(
+            "{:width$}  {:.2}s",
+            report.name,
+            report.elapsed.as_secs_f64()
+        );
+        if let MemberStatus::Failed(Some(code)) = report.status {
+            message.push_str(&format!(" (exit code {code})"));
+        }
+        terminal.print_custom(title, message, color, true)?;
+    }
+
+    if failed > 0 {
+        return Err(Error::CheckFailed(format!(
+            "{failed} of {} workspace member(s) failed",
+            reports.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// List the workspace's members with their kind and manifest path. Members that
+/// aren't Python projects are listed but skipped when running across the workspace.
+pub fn list_workspace_members(config: &Config) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let members = workspace_members(workspace.root(), metadata.metadata())?;
+    let mut table = Table::new(["member", "kind", "manifest"]);
+    for it in members.iter() {
+        let manifest = it.manifest.as_ref().map_or_else(
+            || "-".to_string(),
+            |path| member_name(workspace.root(), path),
+        );
+        table.push_row([
+            member_name(workspace.root(), &it.path),
+            it.kind.to_string(),
+            manifest,
+        ]);
+    }
+    table.set_summary("Found", format!("{} member(s)", members.len()));
+
+    config.terminal().print_table(&table)
+}
+
+/// Compare the dependencies the workspace's Python members declare (including optional
+/// dependencies) and report packages required by more than one member. Packages whose
+/// version specifiers can't all be satisfied at once are reported as conflicts; for the
+/// rest, the combined specifiers are suggested as a shared constraint, which can be
+/// written to a pip constraints file for the members' installs.
+pub fn hoist_workspace_dependencies(
+    config: &Config,
+    options: &HoistOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let members =
+        python_workspace_members(workspace.root(), metadata.metadata())?;
+    if members.is_empty() {
+        return Err(Error::HuakConfigurationError(
+            "no Python workspace members are listed in [tool.huak.workspace]"
+                .to_string(),
+        ));
+    }
+
+    let mut packages: BTreeMap<String, Vec<(String, Requirement)>> =
+        BTreeMap::new();
+    for member in members.iter() {
+        let name = member_name(workspace.root(), member);
+        let metadata = LocalMetadata::new(member.join("pyproject.toml"))?;
+        let metadata = metadata.metadata();
+        let optional = metadata
+            .optional_dependencies()
+            .into_iter()
+            .flat_map(|it| it.values().flatten());
+        for req in metadata
+            .dependencies()
+            .unwrap_or_default()
+            .iter()
+            .chain(optional)
+        {
+            let entry = packages
+                .entry(canonical_package_name(&req.name)?.to_lowercase())
+                .or_default();
+            if !entry.iter().any(|(it, other)| it == &name && other == req) {
+                entry.push((name.clone(), req.clone()));
+            }
+        }
+    }
+
+    let mut terminal = config.terminal();
+    let mut constraints = Vec::new();
+    let mut conflicts = 0;
+    for (package, reqs) in packages.iter() {
+        let mut declared_by = reqs.iter().map(|(it, _)| it).collect::<Vec<_>>();
+        declared_by.dedup();
+        if declared_by.len() < 2 {
+            continue;
+        }
+        let specifiers = reqs
+            .iter()
+            .filter_map(|(_, req)| match req.version_or_url.as_ref() {
+                Some(VersionOrUrl::VersionSpecifier(it)) => Some(it.iter()),
+                _ => None,
+            })
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        if !specifiers_compatible(&specifiers) {
+            conflicts += 1;
+            let requirements = reqs
+                .iter()
+                .map(|(member, req)| {
+                    let specifiers = match req.version_or_url.as_ref() {
+                        Some(VersionOrUrl::VersionSpecifier(it)) => {
+                            it.to_string()
+                        }
+                        _ => "any version".to_string(),
+                    };
+                    format!("{member} requires {specifiers}")
+                })
+                .collect::<Vec<_>>();
+            terminal.print_custom(
+                "Conflict",
+                format!("{package}: {}", requirements.join(", ")),
+                Color::Red,
+                true,
+            )?;
+            continue;
+        }
+        let mut shared = Vec::new();
+        for it in specifiers.iter().map(ToString::to_string) {
+            if !shared.contains(&it) {
+                shared.push(it);
+            }
+        }
+        if shared.is_empty() {
+            continue;
+        }
+        let constraint = format!("{package}{}", shared.join(","));
+        terminal.print_custom(
+            "Shared",
+            format!(
+                "{constraint} ({})",
+                declared_by
+                    .iter()
+                    .map(|it| it.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Color::Cyan,
+            true,
+        )?;
+        constraints.push(constraint);
+    }
+
+    if let Some(path) = options.constraints_path.as_ref() {
+        let mut contents = constraints.join("\n");
+        contents.push('\n');
+        std::fs::write(path, contents)?;
+        terminal.print_custom(
+            "Wrote",
+            format!(
+                "{} shared constraint(s) to {}",
+                constraints.len(),
+                path.display()
+            ),
+            Color::Green,
+            true,
+        )?;
+    }
+
+    if conflicts > 0 {
+        return Err(Error::CheckFailed(format!(
+            "{conflicts} package(s) have conflicting requirements across workspace members"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check whether some version could satisfy every specifier. Each specifier narrows the
+/// range of allowed versions; `!=` only matters when the range is a single version.
+/// Pre-release rules aren't considered.
+fn specifiers_compatible(specifiers: &[VersionSpecifier]) -> bool {
+    // Bounds are a version and whether the version itself is allowed.
+    let mut lower = Vec::new();
+    let mut upper = Vec::new();
+    for it in specifiers {
+        let version = it.version().clone();
+        match it.operator() {
+            Operator::Equal | Operator::ExactEqual => {
+                upper.push((version.clone(), true));
+                lower.push((version, true));
+            }
+            Operator::EqualStar => {
+                upper.push((next_release(&version.release), false));
+                lower.push((version, true));
+            }
+            Operator::TildeEqual => {
+                let prefix = &version.release[..version.release.len() - 1];
+                upper.push((next_release(prefix), false));
+                lower.push((version, true));
+            }
+            Operator::GreaterThan => lower.push((version, false)),
+            Operator::GreaterThanEqual => lower.push((version, true)),
+            Operator::LessThan => upper.push((version, false)),
+            Operator::LessThanEqual => upper.push((version, true)),
+            Operator::NotEqual | Operator::NotEqualStar => (),
+        }
+    }
+    let lower = lower
+        .into_iter()
+        .reduce(|a, b| tighter_bound(a, b, cmp::Ordering::Greater));
+    let upper = upper
+        .into_iter()
+        .reduce(|a, b| tighter_bound(a, b, cmp::Ordering::Less));
+
+    match (lower, upper) {
+        (Some((low, low_inclusive)), Some((high, high_inclusive))) => {
+            match low.cmp(&high) {
+                cmp::Ordering::Greater => false,
+                cmp::Ordering::Equal => {
+                    low_inclusive
+                        && high_inclusive
+                        && specifiers.iter().all(|it| it.contains(&low))
+                }
+                cmp::Ordering::Less => true,
+            }
+        }
+        _ => true,
+    }
+}
+
+/// Pick the narrower of two bounds, where `narrower` is how the narrower bound's version
+/// compares to the other's (greater for lower bounds, less for upper bounds).
+fn tighter_bound(
+    a: (Version, bool),
+    b: (Version, bool),
+    narrower: cmp::Ordering,
+) -> (Version, bool) {
+    match b.0.cmp(&a.0) {
+        it if it == narrower => b,
+        cmp::Ordering::Equal if !b.1 => b,
+        _ => a,
+    }
+}
+
+/// Get the first release after every release starting with `prefix`, like 2.0 for 1.*.
+fn next_release(prefix: &[usize]) -> Version {
+    let mut release = prefix.to_vec();
+    if let Some(last) = release.last_mut() {
+        *last += 1;
+    }
+    Version::from_release(release)
+}
+
+/// Get the directories of the workspace's members. Each entry of `[tool.huak.workspace]
+/// members` is a glob pattern relative to the workspace root, and every matched
+/// directory is classified by its manifest. Only Python members (with a pyproject.toml)
+/// are run; others, like a `frontend/` with a package.json, are skipped.
+fn workspace_members(
+    root: &Path,
+    metadata: &Metadata,
+) -> HuakResult<Vec<WorkspaceMember>> {
+    let Some(value) = metadata.huak_setting("workspace", "members") else {
+        return Ok(Vec::new());
+    };
+    let invalid = || {
+        Error::HuakConfigurationError(
+            "[tool.huak.workspace] members must be a list of glob patterns"
+                .to_string(),
+        )
+    };
+    let mut paths = Vec::new();
+    for pattern in value.as_array().ok_or_else(invalid)? {
+        let pattern = pattern.as_str().ok_or_else(invalid)?;
+        let pattern = format!("{}", root.join(pattern).display());
+        for path in glob::glob(&pattern)?.flatten() {
+            if path.is_dir() && path != root && !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    Ok(paths.into_iter().map(WorkspaceMember::new).collect())
+}
+
+/// Get the directories of the workspace's Python members (see `workspace_members`).
+pub(crate) fn python_workspace_members(
+    root: &Path,
+    metadata: &Metadata,
+) -> HuakResult<Vec<PathBuf>> {
+    Ok(workspace_members(root, metadata)?
+        .into_iter()
+        .filter(WorkspaceMember::is_python)
+        .map(|it| it.path)
+        .collect())
+}
+
+/// Order workspace members so that each comes after the members it depends on, such as
+/// when building or publishing them. Members depend on each other by listing another
+/// member's project name in their dependencies, including path dependencies like
+/// `other @ file:../other`.
+pub(crate) fn dependency_order(
+    members: &[LocalMetadata],
+) -> HuakResult<Vec<usize>> {
+    let names = members
+        .iter()
+        .map(|it| {
+            canonical_package_name(it.metadata().project_name())
+                .map(|it| it.to_lowercase())
+        })
+        .collect::<HuakResult<Vec<_>>>()?;
+    let mut dependencies = Vec::new();
+    for member in members {
+        let mut indexes = Vec::new();
+        for req in member.metadata().dependencies().unwrap_or_default() {
+            let name = canonical_package_name(&req.name)?.to_lowercase();
+            if let Some(index) = names.iter().position(|it| it == &name) {
+                indexes.push(index);
+            }
+        }
+        dependencies.push(indexes);
+    }
+
+    // A depth-first search from each member in order, tracking the members on the
+    // current path to report cycles.
+    fn visit(
+        index: usize,
+        dependencies: &[Vec<usize>],
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+        names: &[String],
+    ) -> HuakResult<()> {
+        if order.contains(&index) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|it| *it == index) {
+            let cycle = path[start..]
+                .iter()
+                .chain(std::iter::once(&index))
+                .map(|it| names[*it].as_str())
+                .collect::<Vec<_>>();
+            return Err(Error::HuakConfigurationError(format!(
+                "workspace members depend on each other in a cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+        path.push(index);
+        for it in dependencies[index].iter() {
+            visit(*it, dependencies, path, order, names)?;
+        }
+        path.pop();
+        order.push(index);
+
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    for index in 0..members.len() {
+        visit(index, &dependencies, &mut Vec::new(), &mut order, &names)?;
+    }
+
+    Ok(order)
+}
+
+/// Get the metadata of the workspace a project is a member of: the nearest ancestor
+/// pyproject.toml whose `[tool.huak.workspace] members` match the project's directory.
+/// A project that is itself a workspace root is its own workspace.
+pub(crate) fn parent_workspace_metadata(
+    metadata: &LocalMetadata,
+) -> HuakResult<Option<LocalMetadata>> {
+    let Some(dir) = metadata.path().parent() else {
+        return Ok(None);
+    };
+    if metadata
+        .metadata()
+        .huak_setting("workspace", "members")
+        .is_some()
+    {
+        return Ok(Some(metadata.clone()));
+    }
+    for root in dir.ancestors().skip(1) {
+        let path = root.join("pyproject.toml");
+        if path.is_file() {
+            // Unrelated or invalid pyproject.toml files above the project are skipped.
+            if let Ok(it) = LocalMetadata::new(&path) {
+                if workspace_members(root, it.metadata())?
+                    .iter()
+                    .any(|member| member.is_python() && member.path == dir)
+                {
+                    return Ok(Some(it));
+                }
+            }
+        }
+        if root.join(".git").exists() {
+            break;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Get the name a member's output is prefixed with: its path relative to the root.
+fn member_name(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Run the command from a member's directory, forwarding its output with a prefix
+/// until it exits or the run is cancelled.
+fn run_member(
+    path: &Path,
+    prefix: &str,
+    options: &MembersOptions,
+    cancelled: &AtomicBool,
+) -> HuakResult<MemberStatus> {
+    let mut child = Command::new(&options.program)
+        .args(&options.args)
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    thread::scope(|scope| {
+        if let Some(it) = stdout {
+            scope.spawn(move || forward_lines(it, prefix, std::io::stdout()));
+        }
+        if let Some(it) = stderr {
+            scope.spawn(move || forward_lines(it, prefix, std::io::stderr()));
+        }
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(match status.success() {
+                    true => MemberStatus::Passed,
+                    false => MemberStatus::Failed(status.code()),
+                });
+            }
+            if cancelled.load(Ordering::SeqCst) {
+                child.kill()?;
+                child.wait()?;
+                return Ok(MemberStatus::Cancelled);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    })
+}
+
+/// Write each line read from a member's output with its prefix. Lines are written
+/// whole so output from parallel members interleaves without mixing.
+fn forward_lines<R: Read, W: Write>(reader: R, prefix: &str, mut writer: W) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    while let Ok(n) = reader.read_until(b'\n', &mut buf) {
+        if n == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&buf);
+        let _ = writer.write_all(
+            format!("{prefix} {}\n", line.trim_end_matches(['\r', '\n']))
+                .as_bytes(),
+        );
+        buf.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use tempfile::tempdir;
+
+    fn mock_workspace(root: &Path, members: &[&str]) {
+        std::fs::create_dir_all(root).unwrap();
+        std::fs::write(
+            root.join("pyproject.toml"),
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "mock-workspace"
+version = "0.0.1"
+
+[tool.huak.workspace]
+members = ["packages/*"]
+"#,
+        )
+        .unwrap();
+        for member in members {
+            let path = root.join("packages").join(member);
+            std::fs::create_dir_all(&path).unwrap();
+            std::fs::write(
+                path.join("pyproject.toml"),
+                format!(
+                    "[project]\nname = \"{member}\"\nversion = \"0.0.1\"\n"
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_workspace_members() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("mock-workspace");
+        mock_workspace(&root, &["b", "a"]);
+        let frontend = root.join("packages").join("frontend");
+        std::fs::create_dir_all(&frontend).unwrap();
+        std::fs::write(frontend.join("package.json"), "{}").unwrap();
+        std::fs::create_dir_all(root.join("packages").join("docs")).unwrap();
+        std::fs::write(root.join("packages").join("notes.txt"), "").unwrap();
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let ws = config.workspace();
+        let metadata = ws.current_local_metadata().unwrap();
+
+        let members = workspace_members(ws.root(), metadata.metadata())
+            .unwrap()
+            .iter()
+            .map(|it| {
+                (
+                    member_name(ws.root(), &it.path),
+                    it.kind,
+                    it.manifest
+                        .as_ref()
+                        .map(|path| member_name(ws.root(), path)),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            members,
+            vec![
+                (
+                    "packages/a".to_string(),
+                    "python",
+                    Some("packages/a/pyproject.toml".to_string())
+                ),
+                (
+                    "packages/b".to_string(),
+                    "python",
+                    Some("packages/b/pyproject.toml".to_string())
+                ),
+                ("packages/docs".to_string(), "unknown", None),
+                (
+                    "packages/frontend".to_string(),
+                    "node",
+                    Some("packages/frontend/package.json".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_workspace_members() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("mock-workspace");
+        mock_workspace(&root, &["a"]);
+        let packages = root.join("packages");
+        std::fs::create_dir_all(packages.join("cli")).unwrap();
+        std::fs::write(packages.join("cli").join("Cargo.toml"), "").unwrap();
+        // A Python project with a frontend is still a Python member.
+        mock_workspace(&root, &["web"]);
+        std::fs::write(packages.join("web").join("package.json"), "{}")
+            .unwrap();
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let ws = config.workspace();
+        let metadata = ws.current_local_metadata().unwrap();
+
+        list_workspace_members(&config).unwrap();
+
+        let kinds = workspace_members(ws.root(), metadata.metadata())
+            .unwrap()
+            .iter()
+            .map(|it| (member_name(ws.root(), &it.path), it.kind))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                ("packages/a".to_string(), "python"),
+                ("packages/cli".to_string(), "rust"),
+                ("packages/web".to_string(), "python"),
+            ]
+        );
+        assert_eq!(
+            python_workspace_members(ws.root(), metadata.metadata()).unwrap(),
+            vec![packages.join("a"), packages.join("web")]
+        );
+    }
+
+    #[test]
+    fn test_workspace_members_without_workspace() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let ws = config.workspace();
+        let metadata = ws.current_local_metadata().unwrap();
+
+        assert!(workspace_members(ws.root(), metadata.metadata())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_specifiers_compatible() {
+        let compatible = |specifiers: &str| {
+            specifiers_compatible(
+                &pep440_rs::parse_version_specifiers(specifiers).unwrap(),
+            )
+        };
+
+        assert!(compatible(">=2.0,<3,>2.28"));
+        assert!(compatible("~=2.28,<2.31"));
+        assert!(compatible("==2.31,>=2.0"));
+        assert!(compatible(">=1.0,<=1.0"));
+        assert!(!compatible("==2.31,<2.30"));
+        assert!(!compatible("==2.31,!=2.31"));
+        assert!(!compatible(">=2.0,<2.0"));
+        assert!(!compatible("~=1.4,>=2"));
+        assert!(!compatible("==1.*,>=2"));
+    }
+
+    #[test]
+    fn test_hoist_workspace_dependencies() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("mock-workspace");
+        mock_workspace(&root, &["a", "b", "c"]);
+        for (member, dependencies) in [
+            ("a", r#"["requests==2.31", "Click>=8"]"#),
+            ("b", r#"["requests<2.30", "click<9"]"#),
+            ("c", r#"["click", "rich"]"#),
+        ] {
+            std::fs::write(
+                root.join("packages").join(member).join("pyproject.toml"),
+                format!(
+                    "[build-system]\nrequires = [\"hatchling\"]\nbuild-backend = \"hatchling.build\"\n\n[project]\nname = \"{member}\"\nversion = \"0.0.1\"\ndependencies = {dependencies}\n"
+                ),
+            )
+            .unwrap();
+        }
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let options = HoistOptions {
+            constraints_path: Some(root.join("constraints.txt")),
+        };
+
+        let res = hoist_workspace_dependencies(&config, &options);
+
+        assert!(matches!(res, Err(Error::CheckFailed(_))));
+        assert_eq!(
+            std::fs::read_to_string(root.join("constraints.txt")).unwrap(),
+            "click>=8,<9\n"
+        );
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let dir = tempdir().unwrap();
+        let member = |name: &str, dependencies: &str| {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            std::fs::write(
+                path.join("pyproject.toml"),
+                format!(
+                    r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "{name}"
+version = "0.0.1"
+dependencies = {dependencies}
+"#
+                ),
+            )
+            .unwrap();
+            LocalMetadata::new(path.join("pyproject.toml")).unwrap()
+        };
+        let members = vec![
+            member("app", r#"["core", "requests"]"#),
+            member("core", r#"["my_utils @ file:../my-utils"]"#),
+            member("my-utils", "[]"),
+        ];
+
+        assert_eq!(dependency_order(&members).unwrap(), vec![2, 1, 0]);
+
+        let members =
+            vec![member("app", r#"["core"]"#), member("core", r#"["app"]"#)];
+
+        assert!(matches!(
+            dependency_order(&members),
+            Err(Error::HuakConfigurationError(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_workspace_members() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("mock-workspace");
+        mock_workspace(&root, &["a", "b", "c"]);
+        std::fs::write(root.join("packages").join("b").join("fail"), "")
+            .unwrap();
+        std::fs::create_dir_all(root.join("packages").join("frontend"))
+            .unwrap();
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let options = MembersOptions {
+            program: PathBuf::from("sh"),
+            args: vec![
+                "-c".to_string(),
+                "test ! -f fail && touch ran".to_string(),
+            ],
+            fail_fast: false,
+        };
+
+        let res = run_workspace_members(&config, &options);
+
+        assert!(matches!(res, Err(Error::CheckFailed(_))));
+        for member in ["a", "c"] {
+            assert!(root.join("packages").join(member).join("ran").exists());
+        }
+        assert!(!root.join("packages").join("b").join("ran").exists());
+        assert!(!root.join("packages").join("frontend").join("ran").exists());
+    }
+}
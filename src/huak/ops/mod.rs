@@ -1,59 +1,170 @@
 mod activate;
 mod add;
+mod bench;
 mod build;
+mod cache;
+mod changelog;
+mod check;
+mod ci;
+mod classifiers;
 mod clean;
+mod docker;
+mod env;
 mod format;
+mod generate;
+mod history;
+mod hooks;
+mod ide;
+mod info;
 mod init;
 mod install;
+mod kernel;
 mod lint;
+mod lock;
+mod members;
 mod new;
+mod profile;
 mod publish;
 mod python;
+mod release;
 mod remove;
+mod rollback;
 mod run;
+mod serve;
+mod stubs;
 mod test;
+mod tool;
 mod update;
+mod upgrade;
+mod verify;
 mod version;
 
+use crate::{
+    cache::record_cache_use,
+    dependency::Dependency,
+    environment::env_path_values,
+    fs, git,
+    index::{self, ReleaseFile, YankedRelease},
+    metadata::{LocalMetadata, Metadata},
+    package::canonical_package_name,
+    python_environment::{
+        venv_config_file_name, Interpreter, PythonEnvironment,
+    },
+    sys::{SubprocessError, Terminal},
+    toolchain::SdistBuild,
+    wheel::{hex, WheelFilename},
+    Error, HuakResult, InstallOptions, WorkspaceOptions,
+};
 #[allow(unused_imports)]
 use crate::{
     config::Config,
     sys::{TerminalOptions, Verbosity},
     workspace::Workspace,
 };
-use crate::{
-    environment::env_path_values, git, python_environment::PythonEnvironment,
-    Error, HuakResult,
-};
 pub use activate::activate_python_environment;
 pub use add::{
     add_project_dependencies, add_project_optional_dependencies, AddOptions,
 };
+pub use bench::{bench_project, BenchOptions};
 pub use build::{build_project, BuildOptions};
+pub use cache::{print_cache_stats, prune_cache, CacheOptions};
+pub use changelog::{update_project_changelog, ChangelogOptions};
+pub use check::{check_project, CheckOptions};
+pub use ci::{ci_project, CiOptions, CiStep};
+pub use classifiers::{
+    add_project_classifiers, search_trove_classifiers,
+    validate_project_classifiers, ClassifiersOptions,
+};
 pub use clean::{clean_project, CleanOptions};
+pub use docker::{build_docker_image, DockerOptions};
+pub use env::{
+    export_environment, import_environment, print_environment_path,
+    print_environment_python, EnvOptions,
+};
 pub use format::{format_project, FormatOptions};
+pub use generate::{generate_devcontainer, generate_envrc};
+pub use history::{print_history, record_history, HistoryOptions};
+pub use hooks::{install_project_hooks, uninstall_project_hooks, HooksOptions};
+pub use ide::{generate_ide_config, Editor, IdeOptions};
+pub use info::{print_package_info, InfoOptions};
 pub use init::{init_app_project, init_lib_project};
-pub use install::install_project_dependencies;
-pub use lint::{lint_project, LintOptions};
+pub use install::{install_project_dependencies, LockMode};
+pub use kernel::{
+    install_project_kernel, remove_project_kernel, KernelOptions,
+};
+pub use lint::{lint_project, LintOptions, LintOutput};
+pub use lock::{lock_project, LockOptions};
+pub use members::{
+    hoist_workspace_dependencies, list_workspace_members,
+    run_workspace_members, HoistOptions, MembersOptions,
+};
 pub use new::{new_app_project, new_lib_project};
+use pep440_rs::VersionSpecifiers;
+pub use profile::{profile_project, ProfileOptions};
 pub use publish::{publish_project, PublishOptions};
-pub use python::{list_python, use_python};
+pub use python::{
+    list_python, print_python_info, use_python, PythonInfoOptions,
+    PythonListOptions,
+};
+pub use release::{release_project, ReleaseOptions};
 pub use remove::{remove_project_dependencies, RemoveOptions};
-pub use run::run_command_str;
-use std::{path::Path, process::Command};
-pub use test::{test_project, TestOptions};
+pub use rollback::{rollback_operation, RollbackOptions};
+pub use run::{
+    list_scripts, run_command, run_command_str, run_script, ScriptOptions,
+};
+pub use serve::{serve_project, ServeOptions};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+    str::FromStr,
+};
+pub use stubs::{generate_project_stubs, StubsOptions};
+use tempfile::NamedTempFile;
+use termcolor::Color;
+pub use test::{test_project, TestJobs, TestOptions, TestShard};
+pub use tool::{run_tool, ToolOptions};
 pub use update::{update_project_dependencies, UpdateOptions};
+pub use upgrade::{upgrade_project_tools, UpgradeOptions};
+pub use verify::verify_environment;
 pub use version::display_project_version;
 
-const DEFAULT_PYTHON_INIT_FILE_CONTENTS: &str = r#"__version__ = "0.0.1"
-"#;
-const DEFAULT_PYTHON_MAIN_FILE_CONTENTS: &str = r#"def main():
-    print("Hello, World!")
+/// Environment variables that conflict with a virtual environment.
+const CONFLICTING_ENV_VARS: [&str; 8] = [
+    "PYTHONHOME",
+    "CONDA_PREFIX",
+    "CONDA_DEFAULT_ENV",
+    "CONDA_PROMPT_MODIFIER",
+    "CONDA_SHLVL",
+    "CONDA_EXE",
+    "CONDA_PYTHON_EXE",
+    "_CE_CONDA",
+];
 
-
-if __name__ == "__main__":
-    main()
-"#;
+/// Environment variables passed through to commands run with an isolated environment.
+const ISOLATED_ENV_VARS: [&str; 18] = [
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TERM",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "USERNAME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "SYSTEMROOT",
+    "COMSPEC",
+    "PATHEXT",
+];
 
 /// Make a `process::Command` a command with *virtual environment context*.
 ///
@@ -61,13 +172,37 @@ if __name__ == "__main__":
 ///   `PATH` environment variable.
 /// - Adds `VIRTUAL_ENV` environment variable to the command pointing at the virtual environment's
 ///   root.
+/// - Removes variables that conflict with the virtual environment, such as `PYTHONHOME`
+///   and conda's, along with other environments' entries in `PATH`. `PATH` entries are
+///   de-duplicated.
+/// - Only passes through a minimal set of variables when `Config::isolated_env` is set.
 fn make_venv_command(
     cmd: &mut Command,
     venv: &PythonEnvironment,
+    config: &Config,
 ) -> HuakResult<()> {
-    let mut paths = env_path_values().unwrap_or(Vec::new());
+    if config.isolated_env {
+        cmd.env_clear().envs(std::env::vars_os().filter(|(key, _)| {
+            key.to_str()
+                .map_or(false, |it| ISOLATED_ENV_VARS.contains(&it))
+        }));
+    }
+    for it in CONFLICTING_ENV_VARS {
+        cmd.env_remove(it);
+    }
 
-    paths.insert(0, venv.executables_dir_path().clone());
+    // Other virtual environments and conda environments might be active.
+    let other_envs = ["VIRTUAL_ENV", "CONDA_PREFIX"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(PathBuf::from)
+        .filter(|it| it != venv.root())
+        .collect::<Vec<_>>();
+    let paths = venv_path_values(
+        venv.executables_dir_path(),
+        env_path_values().unwrap_or_default(),
+        &other_envs,
+    );
     cmd.env(
         "PATH",
         std::env::join_paths(paths)
@@ -78,19 +213,1209 @@ fn make_venv_command(
     Ok(())
 }
 
-/// Create a workspace directory on the system.
-fn create_workspace<T: AsRef<Path>>(path: T) -> HuakResult<()> {
-    let root = path.as_ref();
+/// Get the `PATH` values for a command run in a virtual environment. The environment's
+/// executables directory comes first, entries in `other_envs` are removed, and
+/// duplicate entries are removed.
+fn venv_path_values(
+    executables_dir: &Path,
+    paths: Vec<PathBuf>,
+    other_envs: &[PathBuf],
+) -> Vec<PathBuf> {
+    let mut values = vec![executables_dir.to_path_buf()];
+    for path in paths {
+        if !values.contains(&path)
+            && !other_envs.iter().any(|it| path.starts_with(it))
+        {
+            values.push(path);
+        }
+    }
+
+    values
+}
+
+/// Get the environment variables configured for an op's commands with a
+/// `[tool.huak.env.<op>]` table, such as `[tool.huak.env.test]`. Strings, numbers, and
+/// booleans are accepted as values.
+fn op_env_vars(
+    metadata: &LocalMetadata,
+    op: &str,
+) -> HuakResult<Vec<(String, String)>> {
+    let Some(value) = metadata.metadata().huak_setting("env", op) else {
+        return Ok(Vec::new());
+    };
+    let Some(table) = value.as_table() else {
+        return Err(Error::HuakConfigurationError(format!(
+            "[tool.huak.env.{op}] must be a table of environment variables"
+        )));
+    };
+    table
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(it) => it.clone(),
+                toml::Value::Integer(_)
+                | toml::Value::Float(_)
+                | toml::Value::Boolean(_) => value.to_string(),
+                _ => {
+                    return Err(Error::HuakConfigurationError(format!(
+                        "{key} in [tool.huak.env.{op}] must be a string, number, or boolean"
+                    )))
+                }
+            };
+            Ok((key.clone(), value))
+        })
+        .collect()
+}
+
+/// Run a tool in a check mode, returning its stdout. Tools exit with 1 when they find
+/// problems, so only other exit codes are treated as failures.
+fn check_output(
+    cmd: &mut Command,
+    terminal: &mut Terminal,
+) -> HuakResult<String> {
+    check_output_with_codes(cmd, &[0, 1], terminal)
+}
+
+/// Run a tool in a check mode, returning its stdout. Exit codes other than `codes` are
+/// treated as failures.
+fn check_output_with_codes(
+    cmd: &mut Command,
+    codes: &[i32],
+    terminal: &mut Terminal,
+) -> HuakResult<String> {
+    let output = cmd.output()?;
+
+    if !output.status.code().map_or(false, |it| codes.contains(&it)) {
+        let stderr = std::str::from_utf8(&output.stderr)?.trim();
+        if !stderr.is_empty() {
+            terminal.print_error(stderr)?;
+        }
+        return Err(Error::SubprocessFailure(SubprocessError::new(
+            output.status,
+        )));
+    }
+
+    Ok(std::str::from_utf8(&output.stdout)?.to_string())
+}
+
+/// Get the project's dependencies and the dependencies of each of its optional
+/// dependency groups.
+fn project_dependencies(metadata: &LocalMetadata) -> Vec<Dependency> {
+    let mut dependencies = Vec::new();
+    if let Some(reqs) = metadata.metadata().dependencies() {
+        dependencies.extend(reqs.iter().map(Dependency::from));
+    }
+    if let Some(groups) = metadata.metadata().optional_dependencies() {
+        dependencies.extend(groups.values().flatten().map(Dependency::from));
+    }
+    let mut seen = Vec::new();
+    dependencies.retain(|it| {
+        let it = it.to_string();
+        let unique = !seen.contains(&it);
+        seen.push(it);
+        unique
+    });
+
+    dependencies
+}
 
-    if !root.exists() {
-        std::fs::create_dir(root)?;
+/// Get the project's requirements recorded in its lockfile, sorted so that they can be
+/// compared.
+fn project_requirements(metadata: &LocalMetadata) -> Vec<String> {
+    let mut requirements = project_dependencies(metadata)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    requirements.sort();
+
+    requirements
+}
+
+/// Get the project's `[tool.huak.constraints]`, which constrain packages to version
+/// specifiers (or to a version) whenever dependencies are resolved. They're passed to pip
+/// as constraints, so they narrow the versions a package can resolve to but can't force a
+/// version that a dependency's requirements exclude.
+///
+/// ```toml
+/// [tool.huak.constraints]
+/// urllib3 = "<2"
+/// charset-normalizer = "3.1.0"
+/// ```
+fn project_constraints(
+    metadata: &LocalMetadata,
+) -> HuakResult<Vec<Dependency>> {
+    version_table(metadata, "constraints")
+}
+
+/// Get the project's `[tool.huak.tools]`, which pin the dev tools huak installs (like
+/// `ruff`, `black`, `mypy`, `pytest`, `build`, and `twine`) to version specifiers (or to
+/// a version) so that their results are reproducible.
+///
+/// ```toml
+/// [tool.huak.tools]
+/// ruff = "==0.4.4"
+/// mypy = "1.10.0"
+/// ```
+fn project_tools(metadata: &LocalMetadata) -> HuakResult<Vec<Dependency>> {
+    version_table(metadata, "tools")
+}
+
+/// Get the requirements of a `[tool.huak.<table>]` table mapping package names to
+/// version specifiers or to a version.
+fn version_table(
+    metadata: &LocalMetadata,
+    table: &str,
+) -> HuakResult<Vec<Dependency>> {
+    let Some(table_value) = metadata
+        .metadata()
+        .tool_table("huak")
+        .and_then(|it| it.get(table))
+        .and_then(|it| it.as_table())
+    else {
+        return Ok(Vec::new());
+    };
+
+    let mut requirements = Vec::new();
+    for (name, value) in table_value {
+        let Some(value) = value.as_str().map(str::trim) else {
+            return Err(Error::HuakConfigurationError(format!(
+                "{name} in [tool.huak.{table}] must be a version or version specifiers"
+            )));
+        };
+        let specifiers = match value.starts_with(|c: char| c.is_ascii_digit()) {
+            true => format!("=={value}"),
+            false => value.to_string(),
+        };
+        requirements
+            .push(Dependency::from_str(&format!("{name}{specifiers}"))?);
+    }
+
+    Ok(requirements)
+}
+
+/// Print the constraints applied to a resolution.
+fn report_constraints(
+    constraints: &[Dependency],
+    terminal: &mut Terminal,
+) -> HuakResult<()> {
+    for it in constraints {
+        terminal.print_custom("Constraining", it, Color::Cyan, true)?;
+    }
+
+    Ok(())
+}
+
+/// Write pip constraints to a temporary file. The file is removed when it's dropped, so it
+/// should be kept until pip has run.
+fn write_constraints<T: Display>(
+    constraints: &[T],
+) -> HuakResult<NamedTempFile> {
+    let mut file = tempfile::Builder::new()
+        .prefix("huak-constraints-")
+        .suffix(".txt")
+        .tempfile()?;
+    let contents = constraints
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    file.write_all(contents.as_bytes())?;
+
+    Ok(file)
+}
+
+/// Run a pip operation with `InstallOptions` constraining it to the project's constraints
+/// (see `project_constraints`) and its `BinaryPolicy`.
+fn with_constraints<T>(
+    metadata: &LocalMetadata,
+    options: &InstallOptions,
+    config: &Config,
+    f: impl FnOnce(&InstallOptions) -> HuakResult<T>,
+) -> HuakResult<T> {
+    let constraints = project_constraints(metadata)?;
+    let policy = BinaryPolicy::from_metadata(metadata.metadata())?;
+    if constraints.is_empty() && policy.is_empty() {
+        return f(options);
+    }
+
+    let mut values = options.values.clone().unwrap_or_default();
+    values.extend(policy.install_args());
+    let constraints_file = if constraints.is_empty() {
+        None
     } else {
-        return Err(Error::DirectoryExists(root.to_path_buf()));
+        report_constraints(&constraints, &mut config.terminal())?;
+        let file = write_constraints(&constraints)?;
+        values.push("-c".to_string());
+        values.push(file.path().display().to_string());
+        Some(file)
+    };
+    let res = f(&InstallOptions {
+        values: Some(values),
+    });
+    drop(constraints_file);
+
+    res
+}
+
+/// The packages the installer may only install from wheels, or must build from their
+/// sdists, from the `[tool.huak.install]` table. ":all:" applies to every package.
+///
+/// ```toml
+/// [tool.huak.install]
+/// only-binary = ["numpy", "scipy"]
+/// no-binary = ["psycopg2"]
+/// ```
+#[derive(Debug, Default, PartialEq, Eq)]
+struct BinaryPolicy {
+    only_binary: Vec<String>,
+    no_binary: Vec<String>,
+}
+
+impl BinaryPolicy {
+    fn from_metadata(metadata: &Metadata) -> HuakResult<BinaryPolicy> {
+        let names = |key: &str| -> HuakResult<Vec<String>> {
+            let Some(value) = metadata.huak_setting("install", key) else {
+                return Ok(Vec::new());
+            };
+            let invalid = || {
+                Error::HuakConfigurationError(format!(
+                    "[tool.huak.install] {key} must be a list of package names"
+                ))
+            };
+            value
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|it| {
+                    let name = it.as_str().ok_or_else(invalid)?;
+                    match name {
+                        ":all:" => Ok(name.to_string()),
+                        _ => Ok(canonical_package_name(name)?.to_lowercase()),
+                    }
+                })
+                .collect()
+        };
+
+        Ok(BinaryPolicy {
+            only_binary: names("only-binary")?,
+            no_binary: names("no-binary")?,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.only_binary.is_empty() && self.no_binary.is_empty()
+    }
+
+    /// Get the installer arguments enforcing the policy.
+    fn install_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.only_binary.is_empty() {
+            args.push("--only-binary".to_string());
+            args.push(self.only_binary.join(","));
+        }
+        if !self.no_binary.is_empty() {
+            args.push("--no-binary".to_string());
+            args.push(self.no_binary.join(","));
+        }
+
+        args
+    }
+
+    /// Check if a package may only be installed from a wheel.
+    fn only_binary(&self, name: &str) -> bool {
+        Self::applies(&self.only_binary, name) && !self.no_binary(name)
+    }
+
+    /// Check if a package must be built from its sdist.
+    fn no_binary(&self, name: &str) -> bool {
+        Self::applies(&self.no_binary, name)
+    }
+
+    fn applies(names: &[String], name: &str) -> bool {
+        let name = canonical_package_name(name)
+            .map(|it| it.to_lowercase())
+            .unwrap_or_default();
+        names.iter().any(|it| it == ":all:" || *it == name)
+    }
+}
+
+/// Check that the pinned `dependencies` have wheels the Python environment can install
+/// before the installer runs, warning about any that will be built from their sdists
+/// (see `check_build_tools`). Packages the project's `BinaryPolicy` only installs from
+/// wheels fail instead. The check is skipped with a warning when the index can't be
+/// reached.
+fn check_wheel_compatibility(
+    dependencies: &[Dependency],
+    python_env: &PythonEnvironment,
+    metadata: &LocalMetadata,
+    config: &Config,
+) -> HuakResult<()> {
+    let policy = BinaryPolicy::from_metadata(metadata.metadata())?;
+    let releases = dependencies
+        .iter()
+        .filter_map(|dep| Some((dep.name().to_string(), dep.pinned_version()?)))
+        .collect::<Vec<_>>();
+    if releases.is_empty() {
+        return Ok(());
+    }
+
+    let mut terminal = config.terminal();
+    let files = match config
+        .client()
+        .and_then(|client| index::release_files(&client, &releases))
+    {
+        Ok(it) => it,
+        Err(e) => {
+            return terminal.print_warning(format!(
+                "couldn't check for compatible wheels: {e}"
+            ))
+        }
+    };
+    let supported_tags = python_env.supported_tags()?;
+    let mut builds = Vec::new();
+    for ((name, version), files) in releases.iter().zip(files) {
+        let Some(files) = files else {
+            continue;
+        };
+        let sdist = files.iter().find(|it| it.is_sdist()).cloned();
+        if policy.no_binary(name) {
+            builds.extend(sdist.map(|it| (name, version, it)));
+            continue;
+        }
+        let compatible = files.iter().any(|it| {
+            WheelFilename::from_str(&it.filename).map_or(false, |wheel| {
+                wheel.priority(&supported_tags).is_some()
+            })
+        });
+        if compatible {
+            continue;
+        }
+        if policy.only_binary(name) {
+            return Err(Error::WheelNotFound(format!(
+                "{name}=={version} on this platform with Python {}",
+                python_env.python_version()
+            )));
+        }
+        let message = match sdist.is_some() {
+            true => "so it will be built from its sdist",
+            false => "and it has no sdist to build from",
+        };
+        terminal.print_warning(format!(
+            "no wheel of {name}=={version} is compatible with this platform and Python {}, {message}",
+            python_env.python_version()
+        ))?;
+        builds.extend(sdist.map(|it| (name, version, it)));
+    }
+
+    check_build_tools(&builds, python_env, config)
+}
+
+/// Check that the tools needed to build sdists' native code are installed, failing with
+/// what's missing before the installer fails partway through a build. Sdists are
+/// downloaded to huak's cache to find the native code they build.
+fn check_build_tools(
+    builds: &[(&String, &String, ReleaseFile)],
+    python_env: &PythonEnvironment,
+    config: &Config,
+) -> HuakResult<()> {
+    if builds.is_empty() {
+        return Ok(());
+    }
+
+    let client = config.client()?;
+    let cache_dir = fs::cache_dir()?.join("sdists");
+    std::fs::create_dir_all(&cache_dir)?;
+    let mut missing = Vec::new();
+    let mut uses = Vec::new();
+    for (name, version, sdist) in builds {
+        let path = cache_dir.join(&sdist.filename);
+        uses.push((path.clone(), path.exists()));
+        if !path.exists() {
+            match client.get_bytes(&sdist.url) {
+                Ok(it) => std::fs::write(&path, it)?,
+                Err(e) => {
+                    config.terminal().print_warning(format!(
+                        "couldn't check the tools needed to build {name}=={version}: {e}"
+                    ))?;
+                    continue;
+                }
+            }
+        }
+        let tools = SdistBuild::from_sdist(python_env.python_path(), &path)?
+            .missing_tools(python_env.python_path())?;
+        if !tools.is_empty() {
+            let tools =
+                tools.iter().map(ToString::to_string).collect::<Vec<_>>();
+            missing
+                .push(format!("{name}=={version} needs {}", tools.join(", ")));
+        }
+    }
+    record_cache_use(
+        uses.iter()
+            .filter(|(path, _)| path.exists())
+            .map(|(path, hit)| (path.as_path(), *hit)),
+    )?;
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::BuildToolsMissing(missing.join("; ")))
+    }
+}
+
+/// Get the releases among `(name, version)` pairs that were yanked from the package
+/// index. A warning is printed instead when the index can't be reached so that working
+/// offline isn't blocked.
+fn yanked_releases(
+    releases: &[(String, String)],
+    config: &Config,
+) -> HuakResult<Vec<YankedRelease>> {
+    if releases.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    match config
+        .client()
+        .and_then(|client| index::yanked_releases(&client, releases))
+    {
+        Ok(it) => Ok(it),
+        Err(e) => {
+            config.terminal().print_warning(format!(
+                "couldn't check for yanked releases: {e}"
+            ))?;
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Warn about any of the releases that were yanked from the package index. `source`
+/// describes where the releases come from, such as "installed" or "locked".
+fn warn_yanked_releases(
+    releases: &[(String, String)],
+    source: &str,
+    config: &Config,
+) -> HuakResult<()> {
+    let mut terminal = config.terminal();
+    for it in yanked_releases(releases, config)? {
+        terminal.print_warning(format!(
+            "{it} is {source} but was yanked from the package index; update {} to a release that wasn't yanked",
+            it.name
+        ))?;
     }
 
     Ok(())
 }
 
+/// Get the `(name, version)` of each package installed in the Python environment that
+/// the project depends on directly.
+fn installed_dependency_releases(
+    python_env: &PythonEnvironment,
+    metadata: &LocalMetadata,
+) -> HuakResult<Vec<(String, String)>> {
+    let mut releases = Vec::new();
+    for pkg in python_env.installed_packages()? {
+        if metadata
+            .metadata()
+            .contains_dependency_any(&Dependency::from_str(pkg.name())?)?
+        {
+            releases.push((pkg.name().to_string(), pkg.version().to_string()));
+        }
+    }
+
+    Ok(releases)
+}
+
+/// Get the configuration file a tool should use for a project: the first of the tool's
+/// own configuration files next to the pyproject.toml, or the pyproject.toml if it has a
+/// `[tool.<tool>]` table.
+fn tool_config_file(tool: &str, metadata: &LocalMetadata) -> Option<PathBuf> {
+    let files: &[&str] = match tool {
+        "ruff" => &["ruff.toml", ".ruff.toml"],
+        "black" => &[".black"],
+        "mypy" => &["mypy.ini", ".mypy.ini"],
+        _ => &[],
+    };
+    let root = metadata.path().parent()?;
+    files
+        .iter()
+        .map(|it| root.join(it))
+        .find(|it| it.is_file())
+        .or_else(|| {
+            metadata
+                .metadata()
+                .tool_table(tool)
+                .map(|_| metadata.path().to_path_buf())
+        })
+}
+
+/// Get the arguments passing a tool its configuration file (see `tool_config_file`), so
+/// that it's used no matter which files the tool targets. Nothing is passed if `values`
+/// already includes the tool's configuration option.
+fn tool_config_args(
+    tool: &str,
+    metadata: &LocalMetadata,
+    values: &[&str],
+) -> Vec<String> {
+    let option = match tool {
+        "mypy" => "--config-file",
+        _ => "--config",
+    };
+    if values.iter().any(|it| it.starts_with(option)) {
+        return Vec::new();
+    }
+
+    let file = tool_config_file(tool, metadata).or_else(|| {
+        // Members without their own configuration use their workspace's.
+        members::parent_workspace_metadata(metadata)
+            .ok()
+            .flatten()
+            .filter(|it| it.path() != metadata.path())
+            .and_then(|it| tool_config_file(tool, &it))
+    });
+    match file {
+        Some(it) => vec![option.to_string(), it.display().to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// Get the dev dependencies a project shares with the rest of its workspace (see
+/// `members::parent_workspace_metadata`): the root's `[tool.huak.workspace]
+/// dev-dependencies`, less any the project declares itself so that members can override
+/// them.
+///
+/// ```toml
+/// [tool.huak.workspace]
+/// members = ["packages/*"]
+/// dev-dependencies = ["ruff==0.1.6", "black>=23,<24", "mypy==1.7.1"]
+/// ```
+fn shared_dev_dependencies(
+    metadata: &LocalMetadata,
+) -> HuakResult<Vec<Dependency>> {
+    let Some(root) = members::parent_workspace_metadata(metadata)? else {
+        return Ok(Vec::new());
+    };
+    let Some(value) = root
+        .metadata()
+        .huak_setting("workspace", "dev-dependencies")
+    else {
+        return Ok(Vec::new());
+    };
+    let invalid = || {
+        Error::HuakConfigurationError(
+            "[tool.huak.workspace] dev-dependencies must be a list of requirements"
+                .to_string(),
+        )
+    };
+
+    let mut dependencies = Vec::new();
+    for it in value.as_array().ok_or_else(invalid)? {
+        let dependency =
+            Dependency::from_str(it.as_str().ok_or_else(invalid)?)?;
+        if !metadata.metadata().contains_dependency_any(&dependency)? {
+            dependencies.push(dependency);
+        }
+    }
+
+    Ok(dependencies)
+}
+
+/// Get the dependencies the dev tools huak installs are constrained to: the project's
+/// pins (see `project_tools`), then the dev dependencies its workspace shares (see
+/// `shared_dev_dependencies`) for tools that aren't pinned.
+fn tool_dependencies(metadata: &LocalMetadata) -> HuakResult<Vec<Dependency>> {
+    let mut tools = project_tools(metadata)?;
+    for it in shared_dev_dependencies(metadata)? {
+        if !tools.iter().any(|tool| tool.name() == it.name()) {
+            tools.push(it);
+        }
+    }
+
+    Ok(tools)
+}
+
+/// Get the dependency a dev tool is installed as: its entry in `tools` (see
+/// `tool_dependencies`), or else `requirement`.
+fn tool_dependency(
+    requirement: &str,
+    tools: &[Dependency],
+) -> HuakResult<Dependency> {
+    let dependency = Dependency::from_str(requirement)?;
+    match tools.iter().find(|it| it.name() == dependency.name()) {
+        Some(it) => Ok(it.clone()),
+        None => Ok(dependency),
+    }
+}
+
+/// Check if a dev tool is installed at a version its dependency doesn't allow, warning
+/// about the drift so that the tool can be reinstalled at an allowed version.
+fn tool_outdated(
+    python_env: &PythonEnvironment,
+    dependency: &Dependency,
+    config: &Config,
+) -> HuakResult<bool> {
+    let name = canonical_package_name(dependency.name())?.to_lowercase();
+    for it in python_env.installed_packages()? {
+        if canonical_package_name(it.name())?.to_lowercase() != name {
+            continue;
+        }
+        if dependency.allows(it.version()) {
+            return Ok(false);
+        }
+        config.terminal().print_warning(format!(
+            "{} {} is installed but {dependency} is required, so it will be \
+            reinstalled",
+            it.name(),
+            it.version()
+        ))?;
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Install a dev tool at the version it's constrained to (see `tool_dependencies`) if it
+/// isn't already installed at an allowed version, and add the installed version to the
+/// project's dev dependencies unless it's constrained or already declared.
+fn install_tool(
+    requirement: &str,
+    python_env: &PythonEnvironment,
+    metadata: &mut LocalMetadata,
+    install_options: &InstallOptions,
+    config: &Config,
+) -> HuakResult<Dependency> {
+    let tools = tool_dependencies(metadata)?;
+    let dependency = tool_dependency(requirement, &tools)?;
+    if !python_env.contains_module(dependency.name())?
+        || tool_outdated(python_env, &dependency, config)?
+    {
+        python_env.install_packages(&[&dependency], install_options, config)?;
+    }
+
+    if !tools.iter().any(|it| it.name() == dependency.name())
+        && !metadata.metadata().contains_dependency_any(&dependency)?
+    {
+        for pkg in python_env
+            .installed_packages()?
+            .iter()
+            .filter(|pkg| pkg.name() == dependency.name())
+        {
+            metadata.metadata_mut().add_optional_dependency(
+                Dependency::from_str(&pkg.to_string())?,
+                "dev",
+            );
+        }
+        metadata.write_file()?;
+    }
+
+    Ok(dependency)
+}
+
+/// Check if the project runs huak's dev tools from isolated environments
+/// (`isolated-tools` in `[tool.huak]`, see `tool_environment`) instead of installing them
+/// into its own environment.
+fn isolated_tools(metadata: &LocalMetadata) -> HuakResult<bool> {
+    match metadata
+        .metadata()
+        .tool_table("huak")
+        .and_then(|it| it.get("isolated-tools"))
+    {
+        None => Ok(false),
+        Some(it) => it.as_bool().ok_or_else(|| {
+            Error::HuakConfigurationError(
+                "[tool.huak] isolated-tools must be a boolean".to_string(),
+            )
+        }),
+    }
+}
+
+/// Get the environment to run dev tools from, installing the tools there if needed: a
+/// cached tool environment (see `tool_environment`) if the project uses isolated tools,
+/// or else the project's environment (see `install_tool`). Tools that run each other,
+/// like `nbqa` and `ruff`, are requested together so that they share an environment.
+fn tool_python_environment(
+    requirements: &[&str],
+    python_env: &Rc<PythonEnvironment>,
+    metadata: &mut LocalMetadata,
+    install_options: &InstallOptions,
+    config: &Config,
+) -> HuakResult<Rc<PythonEnvironment>> {
+    if !isolated_tools(metadata)? {
+        for it in requirements {
+            install_tool(it, python_env, metadata, install_options, config)?;
+        }
+        return Ok(Rc::clone(python_env));
+    }
+
+    let tools = tool_dependencies(metadata)?;
+    let dependencies = requirements
+        .iter()
+        .map(|it| tool_dependency(it, &tools))
+        .collect::<HuakResult<Vec<_>>>()?;
+    let tool_env = tool_environment(
+        &dependencies,
+        &env_interpreter(python_env),
+        install_options,
+        config,
+    )?;
+
+    Ok(Rc::new(tool_env))
+}
+
+/// Get the environment to run dev tools from with `dependencies` installed at allowed
+/// versions: a cached tool environment (see `tool_environment`) if `isolated`, or else the
+/// project's environment. Unlike `install_tool`, the project's dev dependencies aren't
+/// updated.
+fn tool_dependencies_environment(
+    dependencies: &[Dependency],
+    isolated: bool,
+    python_env: &Rc<PythonEnvironment>,
+    install_options: &InstallOptions,
+    config: &Config,
+) -> HuakResult<Rc<PythonEnvironment>> {
+    if isolated {
+        let tool_env = tool_environment(
+            dependencies,
+            &env_interpreter(python_env),
+            install_options,
+            config,
+        )?;
+        return Ok(Rc::new(tool_env));
+    }
+
+    let missing = dependencies
+        .iter()
+        .filter(|dep| {
+            !python_env.contains_module(dep.name()).unwrap_or_default()
+                || tool_outdated(python_env, dep, config).unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        python_env.install_packages(&missing, install_options, config)?;
+    }
+
+    Ok(Rc::clone(python_env))
+}
+
+/// Get the `Interpreter` a `PythonEnvironment` was created with.
+fn env_interpreter(python_env: &PythonEnvironment) -> Interpreter {
+    Interpreter::new(
+        python_env.python_path(),
+        python_env.python_version().clone(),
+    )
+}
+
+/// Get a tool environment: a virtual environment in huak's cache directory with only
+/// some tools installed, created with an `Interpreter` (usually the project environment's).
+/// Tool environments are keyed by the tools' requirements and the Python version, so
+/// they're shared by projects and reused until a requirement changes.
+fn tool_environment(
+    dependencies: &[Dependency],
+    interpreter: &Interpreter,
+    install_options: &InstallOptions,
+    config: &Config,
+) -> HuakResult<PythonEnvironment> {
+    let name = dependencies
+        .iter()
+        .map(|it| it.name())
+        .collect::<Vec<_>>()
+        .join("-");
+    cached_environment(
+        "tools",
+        &name,
+        dependencies,
+        interpreter,
+        install_options,
+        config,
+    )
+}
+
+/// Get a virtual environment in a directory of huak's cache (`kind`) with `dependencies`
+/// installed, named `name` followed by a hash of the requirements and the Python version.
+/// The environment is created if it doesn't exist yet.
+fn cached_environment(
+    kind: &str,
+    name: &str,
+    dependencies: &[Dependency],
+    interpreter: &Interpreter,
+    install_options: &InstallOptions,
+    config: &Config,
+) -> HuakResult<PythonEnvironment> {
+    let mut requirements = dependencies
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    requirements.sort();
+    let key = format!("{}\n{}", requirements.join("\n"), interpreter.version());
+    let name =
+        format!("{name}-{}", &hex(&Sha256::digest(key.as_bytes()))[..16]);
+    let root = fs::cache_dir()?.join(kind).join(name);
+    let cached = root.join(venv_config_file_name()).exists();
+
+    if !cached {
+        std::fs::create_dir_all(root.parent().unwrap_or(&root))?;
+        let mut cmd = Command::new(interpreter.path());
+        cmd.args(["-m", "venv"]).arg(&root);
+        config.terminal().run_command(&mut cmd)?;
+    }
+    let tool_env = PythonEnvironment::new(&root)?;
+    // An environment whose install was interrupted is completed. Distributions are
+    // checked rather than executables since a tool's command can be named differently.
+    let canonical_name =
+        |name: &str| canonical_package_name(name).map(|it| it.to_lowercase());
+    let installed = tool_env
+        .installed_packages()?
+        .iter()
+        .map(|it| canonical_name(it.name()))
+        .collect::<HuakResult<Vec<_>>>()?;
+    let missing = dependencies
+        .iter()
+        .filter(|it| {
+            canonical_name(it.name())
+                .map_or(true, |name| !installed.contains(&name))
+        })
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        tool_env.install_packages(&missing, install_options, config)?;
+    }
+    record_cache_use([(root.as_path(), cached)])?;
+
+    Ok(tool_env)
+}
+
+/// Get the project's exclusion patterns (`exclude` in `[tool.huak]`). Patterns are globs
+/// matched against paths relative to the workspace root, such as "generated/**" or
+/// "vendor", and are skipped by every op that walks the workspace.
+fn exclude_patterns(metadata: &LocalMetadata) -> HuakResult<Vec<String>> {
+    let Some(value) = metadata
+        .metadata()
+        .tool_table("huak")
+        .and_then(|it| it.get("exclude"))
+    else {
+        return Ok(Vec::new());
+    };
+    let patterns = value
+        .as_array()
+        .and_then(|it| {
+            it.iter()
+                .map(|it| it.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or(Error::HuakConfigurationError(
+            "`exclude` in [tool.huak] must be a list of glob patterns"
+                .to_string(),
+        ))?;
+    for it in patterns.iter() {
+        glob::Pattern::new(it)?;
+    }
+
+    Ok(patterns)
+}
+
+/// Check if a path relative to the workspace root is excluded by a pattern (see
+/// `exclude_patterns`), either itself or by one of its parent directories.
+fn is_excluded<T: AsRef<Path>>(path: T, patterns: &[String]) -> bool {
+    let path = path.as_ref();
+    let path = path.strip_prefix(".").unwrap_or(path);
+    let patterns = patterns
+        .iter()
+        .filter_map(|it| glob::Pattern::new(it).ok())
+        .collect::<Vec<_>>();
+    path.ancestors()
+        .filter(|it| !it.as_os_str().is_empty())
+        .any(|it| patterns.iter().any(|pattern| pattern.matches_path(it)))
+}
+
+/// Convert exclusion patterns (see `exclude_patterns`) to a regular expression for tools
+/// that exclude paths with one, such as `black` and `mypy`. Paths may start with "/".
+fn exclude_regex(patterns: &[String]) -> Option<String> {
+    if patterns.is_empty() {
+        return None;
+    }
+
+    let patterns = patterns
+        .iter()
+        .map(|pattern| {
+            let mut regex = String::new();
+            let mut chars = pattern.trim_matches('/').chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '*' if chars.peek() == Some(&'*') => {
+                        chars.next();
+                        regex.push_str(".*");
+                    }
+                    '*' => regex.push_str("[^/]*"),
+                    '?' => regex.push_str("[^/]"),
+                    c if c.is_ascii_alphanumeric()
+                        || matches!(c, '/' | '_' | '-') =>
+                    {
+                        regex.push(c)
+                    }
+                    c => regex.push_str(&format!("\\{c}")),
+                }
+            }
+            regex
+        })
+        .collect::<Vec<_>>();
+
+    Some(format!("^/?({})(/|$)", patterns.join("|")))
+}
+
+/// Get the paths for a tool to target, relative to the workspace root. When `paths` are
+/// given only they are targeted (see `workspace_paths`), otherwise the entire workspace
+/// is. When `changed` is set only the targeted Python files changed relative to the git
+/// reference are (see `git::changed_files`), and when `staged` is set only the targeted
+/// Python files staged in the git index are.
+fn target_paths(
+    workspace: &Workspace,
+    changed: &Option<Option<String>>,
+    staged: bool,
+    paths: &[String],
+    exclude: &[String],
+) -> HuakResult<Vec<String>> {
+    let targets = match paths.is_empty() {
+        true => vec![".".to_string()],
+        false => workspace_paths(workspace, paths)?
+            .into_iter()
+            .filter(|it| !is_excluded(it, exclude))
+            .collect(),
+    };
+    let root = workspace.root().canonicalize()?;
+    let files = match changed {
+        _ if staged => git::staged_files(&root)?,
+        Some(reference) => git::changed_files(&root, reference.as_deref())?,
+        None => return Ok(targets),
+    };
+
+    Ok(files
+        .iter()
+        .filter(|it| {
+            matches!(
+                it.extension().and_then(|ext| ext.to_str()),
+                Some("py" | "pyi" | "ipynb")
+            )
+        })
+        .filter_map(|it| it.strip_prefix(&root).ok())
+        .filter(|it| {
+            targets
+                .iter()
+                .any(|target| target == "." || it.starts_with(target))
+                && !is_excluded(it, exclude)
+        })
+        .map(|it| it.to_string_lossy().to_string())
+        .collect())
+}
+
+/// Get the targeted paths that have unstaged changes. Re-staging them after they're
+/// formatted or fixed would also stage those changes.
+fn unstaged_paths(
+    workspace: &Workspace,
+    targets: &[String],
+) -> HuakResult<Vec<String>> {
+    let root = workspace.root().canonicalize()?;
+    let unstaged = git::unstaged_files(&root)?;
+
+    Ok(targets
+        .iter()
+        .filter(|it| unstaged.contains(&root.join(it)))
+        .cloned()
+        .collect())
+}
+
+/// Re-stage the targeted paths in the git index after they've been formatted or fixed,
+/// skipping the paths that had unstaged changes beforehand (see `unstaged_paths`).
+fn update_index(
+    workspace: &Workspace,
+    targets: &[String],
+    unstaged: &[String],
+    terminal: &mut Terminal,
+) -> HuakResult<()> {
+    let root = workspace.root().canonicalize()?;
+    let mut paths = Vec::new();
+    for it in targets {
+        if unstaged.contains(it) {
+            terminal.print_warning(format!(
+                "{it} has unstaged changes, so it wasn't re-staged"
+            ))?;
+        } else if root.join(it).is_file() {
+            paths.push(root.join(it));
+        }
+    }
+
+    git::stage_paths(&root, &paths)
+}
+
+/// Resolve paths and glob patterns relative to the current working directory to paths
+/// relative to the workspace root. Paths must exist and be inside the workspace, and
+/// patterns must match something.
+fn workspace_paths(
+    workspace: &Workspace,
+    paths: &[String],
+) -> HuakResult<Vec<String>> {
+    let root = workspace.root().canonicalize()?;
+    let mut resolved = Vec::new();
+    for path in paths {
+        let matches = if path.contains(['*', '?', '[']) {
+            let pattern = workspace.cwd().join(path).display().to_string();
+            let matches = glob::glob(&pattern)?.flatten().collect::<Vec<_>>();
+            if matches.is_empty() {
+                return Err(Error::HuakConfigurationError(format!(
+                    "{path} doesn't match any files"
+                )));
+            }
+            matches
+        } else {
+            vec![workspace.cwd().join(path)]
+        };
+        for it in matches {
+            let Ok(canonical) = it.canonicalize() else {
+                return Err(Error::HuakConfigurationError(format!(
+                    "{path} doesn't exist"
+                )));
+            };
+            let Ok(relative) = canonical.strip_prefix(&root) else {
+                return Err(Error::HuakConfigurationError(format!(
+                    "{path} isn't in the workspace"
+                )));
+            };
+            let relative = match relative.as_os_str().is_empty() {
+                true => ".".to_string(),
+                false => relative.to_string_lossy().to_string(),
+            };
+            if !resolved.contains(&relative) {
+                resolved.push(relative);
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Get the Jupyter notebooks among the target paths, skipping checkpoint directories,
+/// paths under `exclude`, and paths reached through symlinks. Directory targets, like
+/// ".", select every notebook in them.
+fn notebook_paths(
+    workspace: &Workspace,
+    targets: &[String],
+    env_root: &Path,
+    patterns: &[String],
+) -> HuakResult<Vec<String>> {
+    let root = workspace.root();
+    let mut paths = Vec::new();
+    for target in targets {
+        if root.join(target).is_dir() {
+            let pattern = format!(
+                "{}",
+                root.join(target).join("**").join("*.ipynb").display()
+            );
+            for path in glob::glob(&pattern)?.flatten() {
+                if path.starts_with(env_root) || fs::is_linked(root, &path) {
+                    continue;
+                }
+                if let Ok(it) = path.strip_prefix(root) {
+                    let it = it.to_string_lossy().to_string();
+                    let it = it.strip_prefix("./").unwrap_or(&it).to_string();
+                    if !paths.contains(&it) {
+                        paths.push(it);
+                    }
+                }
+            }
+        } else if target.ends_with(".ipynb") {
+            paths.push(target.to_string());
+        }
+    }
+    paths.retain(|it| {
+        !Path::new(it)
+            .components()
+            .any(|component| component.as_os_str() == ".ipynb_checkpoints")
+            && !is_excluded(it, patterns)
+    });
+
+    Ok(paths)
+}
+
+/// Make a `Command` running a tool on Jupyter notebooks with `nbqa`.
+fn nbqa_command(
+    tool: &str,
+    notebooks: &[String],
+    python_env: &PythonEnvironment,
+    config: &Config,
+) -> HuakResult<Command> {
+    let mut cmd = Command::new(python_env.python_path());
+    make_venv_command(&mut cmd, python_env, config)?;
+    cmd.args(["-m", "nbqa", tool]).args(notebooks);
+
+    Ok(cmd)
+}
+
+/// Apply `WorkspaceOptions` to a new project's `LocalMetadata`.
+fn apply_workspace_options(
+    metadata: &mut LocalMetadata,
+    options: &WorkspaceOptions,
+) -> HuakResult<()> {
+    let metadata = metadata.metadata_mut();
+    if let Some(it) = options.name.as_ref() {
+        metadata.set_project_name(it.to_string());
+    }
+    if let Some(it) = options.python_version.as_ref() {
+        let specifiers = VersionSpecifiers::from_str(&format!(">={it}"))
+            .map_err(|_| Error::InvalidVersionString(it.to_string()))?;
+        metadata.set_requires_python(specifiers);
+    }
+    if let Some(it) = options.license.as_ref() {
+        metadata.set_license_text(it.to_string());
+    }
+    for it in options.dev_dependencies.iter() {
+        let dependency = Dependency::from_str(it)?;
+        if !metadata.contains_optional_dependency(&dependency, "dev")? {
+            metadata.add_optional_dependency(dependency, "dev");
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the variables available to the templates of files generated for a project.
+fn template_vars(
+    config: &Config,
+    options: &WorkspaceOptions,
+    metadata: &Metadata,
+    importable_name: &str,
+) -> Vec<(&'static str, String)> {
+    let python_version = options.python_version.clone().unwrap_or_else(|| {
+        config
+            .workspace()
+            .environment()
+            .interpreters()
+            .latest()
+            .map(|it| {
+                let release = it.version().release();
+                format!("{}.{}", release[0], release.get(1).unwrap_or(&0))
+            })
+            .unwrap_or_else(|| "3".to_string())
+    });
+    let (author, author_email) = git::user_identity();
+
+    vec![
+        ("project_name", metadata.project_name().to_string()),
+        ("importable_name", importable_name.to_string()),
+        (
+            "version",
+            metadata
+                .project_version()
+                .map_or("0.0.1".to_string(), |it| it.to_string()),
+        ),
+        ("python_version", python_version),
+        ("author", author.unwrap_or_default()),
+        ("author_email", author_email.unwrap_or_default()),
+        ("year", crate::changelog::today()[..4].to_string()),
+    ]
+}
+
+/// Get the path to the directory users can override generated files' templates in.
+fn user_templates_dir() -> Option<PathBuf> {
+    fs::config_dir().ok().map(|it| it.join("templates"))
+}
+
 /// Initialize a directory for git.
 ///
 /// - Initializes git
@@ -115,11 +1440,14 @@ fn test_config<T: AsRef<Path>>(
     cwd: T,
     verbosity: Verbosity,
 ) -> Config {
-    let config = Config {
-        workspace_root: root.as_ref().to_path_buf(),
-        cwd: cwd.as_ref().to_path_buf(),
-        terminal_options: TerminalOptions { verbosity },
-    };
+    let config = Config::new(
+        root.as_ref().to_path_buf(),
+        cwd.as_ref().to_path_buf(),
+        TerminalOptions {
+            verbosity,
+            assume_yes: true,
+        },
+    );
 
     config
 }
@@ -132,3 +1460,506 @@ fn test_venv(ws: &Workspace) {
     let mut cmd = Command::new(python_path);
     cmd.args(["-m", "venv", &venv_path]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_resources_dir_path;
+
+    #[test]
+    fn test_venv_path_values() {
+        let paths = vec![
+            PathBuf::from("/home/user/other/.venv/bin"),
+            PathBuf::from("/usr/bin"),
+            PathBuf::from("/opt/conda/envs/ds/bin"),
+            PathBuf::from("/project/.venv/bin"),
+            PathBuf::from("/usr/bin"),
+        ];
+        let other_envs = [
+            PathBuf::from("/home/user/other/.venv"),
+            PathBuf::from("/opt/conda/envs/ds"),
+        ];
+
+        let values = venv_path_values(
+            Path::new("/project/.venv/bin"),
+            paths,
+            &other_envs,
+        );
+
+        assert_eq!(
+            values,
+            [
+                PathBuf::from("/project/.venv/bin"),
+                PathBuf::from("/usr/bin")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_op_env_vars() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pyproject.toml");
+        std::fs::write(
+            &path,
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "proj"
+
+[tool.huak.env.test]
+DJANGO_SETTINGS_MODULE = "proj.settings.test"
+WORKERS = 4
+DEBUG = true
+
+[tool.huak.env.run]
+PATHS = ["a", "b"]
+"#,
+        )
+        .unwrap();
+        let metadata = LocalMetadata::new(&path).unwrap();
+
+        assert_eq!(
+            op_env_vars(&metadata, "test").unwrap(),
+            [
+                (
+                    "DJANGO_SETTINGS_MODULE".to_string(),
+                    "proj.settings.test".to_string()
+                ),
+                ("WORKERS".to_string(), "4".to_string()),
+                ("DEBUG".to_string(), "true".to_string()),
+            ]
+        );
+        assert!(op_env_vars(&metadata, "lint").unwrap().is_empty());
+        assert!(op_env_vars(&metadata, "run").is_err());
+    }
+
+    #[test]
+    fn test_tool_config_args() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pyproject.toml");
+        std::fs::write(
+            &path,
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "proj"
+
+[tool.black]
+line-length = 100
+"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("ruff.toml"), "").unwrap();
+        let metadata = LocalMetadata::new(&path).unwrap();
+        let ruff_toml = dir.path().join("ruff.toml").display().to_string();
+
+        assert_eq!(
+            tool_config_args("ruff", &metadata, &[]),
+            ["--config", ruff_toml.as_str()]
+        );
+        assert_eq!(
+            tool_config_args("black", &metadata, &["--check"]),
+            ["--config", path.display().to_string().as_str()]
+        );
+        assert!(tool_config_args("mypy", &metadata, &[]).is_empty());
+        assert!(tool_config_args(
+            "ruff",
+            &metadata,
+            &["--config", "other.toml"]
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_write_constraints() {
+        let file = write_constraints(&["urllib3 <2", "idna ==3.4"]).unwrap();
+        let path = file.path().to_path_buf();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "urllib3 <2\nidna ==3.4"
+        );
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_tool_environment_canonical_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = test_config(dir.path(), dir.path(), Verbosity::Quiet);
+        let ws = config.workspace();
+        let env = ws.environment();
+        let interpreter = env.interpreters().latest().unwrap();
+        // `pip` is installed with the environment, so it's never installed with these
+        // options, which would fail.
+        let install_options = InstallOptions {
+            values: Some(vec!["--not-an-option".to_string()]),
+        };
+
+        let tool_env = tool_environment(
+            &[Dependency::from_str("PIP").unwrap()],
+            interpreter,
+            &install_options,
+            &config,
+        )
+        .unwrap();
+
+        assert!(tool_env
+            .installed_packages()
+            .unwrap()
+            .iter()
+            .any(|it| it.name() == "pip"));
+    }
+
+    #[test]
+    fn test_tool_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let member = dir.path().join("packages").join("a");
+        std::fs::create_dir_all(&member).unwrap();
+        std::fs::write(
+            dir.path().join("pyproject.toml"),
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "workspace"
+
+[tool.huak.workspace]
+members = ["packages/*"]
+dev-dependencies = ["ruff==0.1.6", "black>=23,<24", "mypy==1.7.1"]
+
+[tool.mypy]
+strict = true
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            member.join("pyproject.toml"),
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "a"
+
+[project.optional-dependencies]
+dev = ["ruff==0.1.7"]
+
+[tool.huak.tools]
+mypy = "1.10.0"
+pytest = "<9"
+"#,
+        )
+        .unwrap();
+        std::fs::write(member.join("ruff.toml"), "").unwrap();
+        let metadata =
+            LocalMetadata::new(member.join("pyproject.toml")).unwrap();
+        let shared = shared_dev_dependencies(&metadata).unwrap();
+
+        assert_eq!(
+            shared.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["black >=23, <24", "mypy ==1.7.1"]
+        );
+        let tools = tool_dependencies(&metadata).unwrap();
+
+        assert_eq!(
+            tools.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["mypy ==1.10.0", "pytest <9", "black >=23, <24"]
+        );
+        assert_eq!(
+            tool_dependency("mypy", &tools).unwrap().to_string(),
+            "mypy ==1.10.0"
+        );
+        assert_eq!(
+            tool_dependency("ruff", &tools).unwrap().to_string(),
+            "ruff"
+        );
+        assert_eq!(
+            tool_config_args("mypy", &metadata, &[]),
+            [
+                "--config-file",
+                dir.path()
+                    .join("pyproject.toml")
+                    .display()
+                    .to_string()
+                    .as_str()
+            ]
+        );
+        assert_eq!(
+            tool_config_args("ruff", &metadata, &[]),
+            [
+                "--config",
+                member.join("ruff.toml").display().to_string().as_str()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tool_outdated() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let ws = config.workspace();
+        test_venv(&ws);
+        let venv = ws.resolve_python_environment().unwrap();
+        venv.install_packages(
+            &["six==1.15.0"],
+            &InstallOptions { values: None },
+            &config,
+        )
+        .unwrap();
+        let outdated = |requirement: &str| {
+            tool_outdated(
+                &venv,
+                &Dependency::from_str(requirement).unwrap(),
+                &config,
+            )
+            .unwrap()
+        };
+
+        assert!(outdated("six==1.16.0"));
+        assert!(outdated("Six>1.15"));
+        assert!(!outdated("six>=1.15"));
+        assert!(!outdated("six"));
+        assert!(!outdated("not-installed==1.0.0"));
+    }
+
+    #[test]
+    fn test_install_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let path = root.join("pyproject.toml");
+        let pyproject_toml = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(
+            &path,
+            format!("{pyproject_toml}\n[tool.huak.tools]\nsix = \"1.16.0\"\n"),
+        )
+        .unwrap();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let ws = config.workspace();
+        test_venv(&ws);
+        let venv = ws.resolve_python_environment().unwrap();
+        let install_options = InstallOptions { values: None };
+        let version = |name: &str| {
+            venv.installed_packages()
+                .unwrap()
+                .iter()
+                .find(|it| it.name() == name)
+                .map(|it| it.version().to_string())
+        };
+        let dev_dependencies = || {
+            LocalMetadata::new(&path)
+                .unwrap()
+                .metadata()
+                .optional_dependency_group("dev")
+                .map(|it| {
+                    it.iter().map(|it| it.name.to_string()).collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+        venv.install_packages(&["six==1.15.0"], &install_options, &config)
+            .unwrap();
+        let mut metadata = LocalMetadata::new(&path).unwrap();
+
+        // Pinned tools that drifted are reinstalled, but not added to dev dependencies.
+        let dependency = install_tool(
+            "six",
+            &venv,
+            &mut metadata,
+            &install_options,
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(dependency.to_string(), "six ==1.16.0");
+        assert_eq!(version("six").as_deref(), Some("1.16.0"));
+        assert!(!dev_dependencies().contains(&"six".to_string()));
+
+        // Unpinned tools are added to dev dependencies once.
+        std::fs::write(&path, &pyproject_toml).unwrap();
+        let mut metadata = LocalMetadata::new(&path).unwrap();
+        install_tool("six", &venv, &mut metadata, &install_options, &config)
+            .unwrap();
+
+        assert_eq!(
+            dev_dependencies()
+                .iter()
+                .filter(|it| it.as_str() == "six")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_binary_policy() {
+        let metadata: Metadata = toml::from_str(
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "a"
+
+[tool.huak.install]
+only-binary = ["NumPy", ":all:"]
+no-binary = ["psycopg2_binary"]
+"#,
+        )
+        .unwrap();
+        let policy = BinaryPolicy::from_metadata(&metadata).unwrap();
+
+        assert_eq!(
+            policy.install_args(),
+            [
+                "--only-binary",
+                "numpy,:all:",
+                "--no-binary",
+                "psycopg2-binary"
+            ]
+        );
+        assert!(policy.only_binary("numpy"));
+        assert!(policy.only_binary("requests"));
+        assert!(!policy.only_binary("psycopg2-binary"));
+        assert!(policy.no_binary("Psycopg2.Binary"));
+        assert!(BinaryPolicy::default().is_empty());
+    }
+
+    #[test]
+    fn test_isolated_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pyproject.toml");
+        let pyproject_toml = r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "a"
+"#;
+        std::fs::write(&path, pyproject_toml).unwrap();
+
+        assert!(!isolated_tools(&LocalMetadata::new(&path).unwrap()).unwrap());
+
+        std::fs::write(
+            &path,
+            format!("{pyproject_toml}\n[tool.huak]\nisolated-tools = true\n"),
+        )
+        .unwrap();
+
+        assert!(isolated_tools(&LocalMetadata::new(&path).unwrap()).unwrap());
+
+        std::fs::write(
+            &path,
+            format!(
+                "{pyproject_toml}\n[tool.huak]\nisolated-tools = \"yes\"\n"
+            ),
+        )
+        .unwrap();
+
+        assert!(isolated_tools(&LocalMetadata::new(&path).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_workspace_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(root.join("src").join("pkg")).unwrap();
+        for it in ["a.py", "b.py"] {
+            std::fs::write(root.join("src").join("pkg").join(it), "").unwrap();
+        }
+        let cwd = root.join("src");
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let workspace = config.workspace();
+
+        let paths = workspace_paths(
+            &workspace,
+            &[
+                "pkg/*.py".to_string(),
+                "pkg/a.py".to_string(),
+                "..".to_string(),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(paths, ["src/pkg/a.py", "src/pkg/b.py", "."]);
+        assert!(
+            workspace_paths(&workspace, &["missing.py".to_string()]).is_err()
+        );
+        assert!(workspace_paths(&workspace, &["*.txt".to_string()]).is_err());
+        assert!(workspace_paths(&workspace, &["../..".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_staged_target_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        git::init(&root).unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        for it in ["a.py", "b.py", "c.py", "notes.txt"] {
+            std::fs::write(root.join("src").join(it), "").unwrap();
+        }
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let workspace = config.workspace();
+        let mut terminal = config.terminal();
+        git::stage_paths(
+            &root,
+            &["a.py", "b.py", "notes.txt"].map(|it| root.join("src").join(it)),
+        )
+        .unwrap();
+        std::fs::write(root.join("src").join("b.py"), "import os").unwrap();
+
+        let mut targets =
+            target_paths(&workspace, &None, true, &[], &[]).unwrap();
+        targets.sort();
+        let unstaged = unstaged_paths(&workspace, &targets).unwrap();
+        std::fs::write(root.join("src").join("a.py"), "import os").unwrap();
+        update_index(&workspace, &targets, &unstaged, &mut terminal).unwrap();
+
+        assert_eq!(targets, ["src/a.py", "src/b.py"]);
+        assert_eq!(unstaged, ["src/b.py"]);
+        assert_eq!(
+            git::unstaged_files(&root).unwrap(),
+            [root.join("src").join("b.py")]
+        );
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let patterns =
+            vec!["vendor".to_string(), "src/*/generated".to_string()];
+
+        assert!(is_excluded("vendor", &patterns));
+        assert!(is_excluded("./vendor/lib/mod.py", &patterns));
+        assert!(is_excluded("src/pkg/generated/api.py", &patterns));
+        assert!(!is_excluded("src/pkg/api.py", &patterns));
+        assert!(!is_excluded("src/vendor.py", &patterns));
+    }
+
+    #[test]
+    fn test_exclude_regex() {
+        let patterns =
+            vec!["vendor".to_string(), "src/**/gen_*.py".to_string()];
+
+        assert_eq!(exclude_regex(&[]), None);
+        assert_eq!(
+            exclude_regex(&patterns).unwrap(),
+            r"^/?(vendor|src/.*/gen_[^/]*\.py)(/|$)"
+        );
+    }
+}
@@ -1,11 +1,14 @@
-use super::{create_workspace, init_git};
+use super::{
+    apply_workspace_options, init_git, template_vars, user_templates_dir,
+};
 use crate::{
     dependency::Dependency,
-    fs,
-    metadata::{
-        default_entrypoint_string, default_test_file_contents, LocalMetadata,
-    },
-    package::importable_package_name,
+    fs::{self, StagedDir},
+    index,
+    metadata::{default_entrypoint_string, LocalMetadata},
+    package::{importable_name_problem, importable_package_name},
+    sys::is_interactive,
+    template::{templates, TemplateKind},
     Config, Error, HuakResult, WorkspaceOptions,
 };
 use std::str::FromStr;
@@ -14,21 +17,99 @@ pub fn new_app_project(
     config: &Config,
     options: &WorkspaceOptions,
 ) -> HuakResult<()> {
-    new_lib_project(config, options)?;
+    new_project(config, options, |config| {
+        write_lib_project(config, options)?;
+        write_app_project(config, options)
+    })
+}
+
+pub fn new_lib_project(
+    config: &Config,
+    options: &WorkspaceOptions,
+) -> HuakResult<()> {
+    new_project(config, options, |config| write_lib_project(config, options))
+}
+
+/// Create a new project at the workspace root. The project is written to a `StagedDir`
+/// by `write` and moved into place once it's complete so that a failed or interrupted
+/// command doesn't leave a half-created project behind.
+fn new_project<F>(
+    config: &Config,
+    options: &WorkspaceOptions,
+    write: F,
+) -> HuakResult<()>
+where
+    F: FnOnce(&Config) -> HuakResult<()>,
+{
+    let root = config.workspace_root.clone();
+    if root.exists() {
+        return Err(Error::DirectoryExists(root));
+    }
+
+    // The project's name defaults to the name of its directory, not the staged one.
+    let name = match options.name.as_ref() {
+        Some(it) => it.to_string(),
+        None => fs::last_path_component(&root)?,
+    };
+    let options = WorkspaceOptions {
+        name: Some(checked_project_name(config, name, options.check_index)?),
+        check_index: false,
+        ..options.clone()
+    };
+    let staged = StagedDir::new(&root)?;
+    let mut staged_config = config.clone();
+    staged_config.workspace_root = staged.path().to_path_buf();
+    staged_config.cwd = staged.path().to_path_buf();
+
+    write(&staged_config)?;
 
+    staged.commit()
+}
+/// Write a new library project to the workspace root.
+fn write_lib_project(
+    config: &Config,
+    options: &WorkspaceOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut metadata =
+        LocalMetadata::template(workspace.root().join("pyproject.toml"));
+
+    if options.uses_git {
+        init_git(workspace.root())?;
+    }
+
+    apply_workspace_options(&mut metadata, options)?;
+    metadata.write_file()?;
+    let name = metadata.metadata().project_name();
+
+    let as_dep = Dependency::from_str(name)?;
+    let importable_name = importable_package_name(as_dep.name())?;
+    let vars =
+        template_vars(config, options, metadata.metadata(), &importable_name);
+    for it in templates(TemplateKind::Lib, user_templates_dir().as_deref())? {
+        it.write(workspace.root(), &vars, true)?;
+    }
+
+    Ok(())
+}
+
+/// Add an application's entry point to a new library project at the workspace root.
+fn write_app_project(
+    config: &Config,
+    options: &WorkspaceOptions,
+) -> HuakResult<()> {
     let workspace = config.workspace();
     let mut metadata = workspace.current_local_metadata()?;
 
-    let name = fs::last_path_component(workspace.root().as_path())?;
+    let name = metadata.metadata().project_name().to_string();
     let as_dep = Dependency::from_str(&name)?;
-    metadata.metadata_mut().set_project_name(name);
 
-    let src_path = workspace.root().join("src");
     let importable_name = importable_package_name(as_dep.name())?;
-    std::fs::write(
-        src_path.join(&importable_name).join("main.py"),
-        super::DEFAULT_PYTHON_MAIN_FILE_CONTENTS,
-    )?;
+    let vars =
+        template_vars(config, options, metadata.metadata(), &importable_name);
+    for it in templates(TemplateKind::App, user_templates_dir().as_deref())? {
+        it.write(workspace.root(), &vars, true)?;
+    }
     let entry_point = default_entrypoint_string(&importable_name);
     metadata
         .metadata_mut()
@@ -37,44 +118,59 @@ pub fn new_app_project(
     metadata.write_file()
 }
 
-pub fn new_lib_project(
+/// Check a new project's name, prompting for another on interactive terminals when it
+/// can't be used. Names with importable names that aren't valid identifiers or shadow
+/// standard library modules can't be used (see `importable_name_problem`). With
+/// `check_index`, names already taken on the package index can be kept or replaced.
+fn checked_project_name(
     config: &Config,
-    options: &WorkspaceOptions,
-) -> HuakResult<()> {
-    let workspace = config.workspace();
-
-    // Create a new metadata file or error if one exists.
-    let mut metadata = match workspace.current_local_metadata() {
-        Ok(_) => return Err(Error::ProjectFound),
-        Err(_) => {
-            LocalMetadata::template(workspace.root().join("pyproject.toml"))
+    name: String,
+    check_index: bool,
+) -> HuakResult<String> {
+    let mut terminal = config.terminal();
+    let interactive = is_interactive();
+    let mut name = name;
+    loop {
+        if let Some(problem) =
+            importable_name_problem(&importable_package_name(&name)?)
+        {
+            if !interactive {
+                return Err(Error::InvalidProjectName(problem));
+            }
+            terminal.print_warning(&problem)?;
+            name = terminal.prompt("Project name", None)?;
+            continue;
+        }
+        if !check_index {
+            return Ok(name);
         }
-    };
-
-    create_workspace(workspace.root())?;
 
-    if options.uses_git {
-        init_git(workspace.root())?;
+        match config
+            .client()
+            .and_then(|client| index::project_info(&client, &name))
+        {
+            Ok(Some(_)) => {
+                terminal.print_warning(format!(
+                    "{name:?} is already a project on the package index"
+                ))?;
+                if !interactive {
+                    return Ok(name);
+                }
+                let answer = terminal.prompt("Project name", Some(&name))?;
+                if answer == name {
+                    return Ok(name);
+                }
+                name = answer;
+            }
+            Ok(None) => return Ok(name),
+            Err(e) => {
+                terminal.print_warning(format!(
+                    "failed to check the package index for {name:?}: {e}"
+                ))?;
+                return Ok(name);
+            }
+        }
     }
-
-    let name = &fs::last_path_component(&config.workspace_root)?;
-    metadata.metadata_mut().set_project_name(name.to_string());
-    metadata.write_file()?;
-
-    let as_dep = Dependency::from_str(name)?;
-    let src_path = config.workspace_root.join("src");
-    let importable_name = importable_package_name(as_dep.name())?;
-    std::fs::create_dir_all(src_path.join(&importable_name))?;
-    std::fs::create_dir_all(config.workspace_root.join("tests"))?;
-    std::fs::write(
-        src_path.join(&importable_name).join("__init__.py"),
-        super::DEFAULT_PYTHON_INIT_FILE_CONTENTS,
-    )?;
-    std::fs::write(
-        config.workspace_root.join("tests").join("test_version.py"),
-        default_test_file_contents(&importable_name),
-    )
-    .map_err(Error::IOError)
 }
 
 #[cfg(test)]
@@ -89,7 +185,10 @@ mod tests {
         let root = dir.path().join("mock-project");
         let cwd = root.to_path_buf();
         let config = test_config(root, cwd, Verbosity::Quiet);
-        let options = WorkspaceOptions { uses_git: false };
+        let options = WorkspaceOptions {
+            uses_git: false,
+            ..Default::default()
+        };
 
         new_lib_project(&config, &options).unwrap();
 
@@ -116,6 +215,10 @@ def test_version():
         assert!(metadata.metadata().project().scripts.is_none());
         assert_eq!(test_file, expected_test_file);
         assert_eq!(init_file, expected_init_file);
+        assert_eq!(
+            std::fs::read_to_string(ws.root().join("README.md")).unwrap(),
+            "# mock-project\n"
+        );
     }
 
     #[test]
@@ -124,7 +227,10 @@ def test_version():
         let root = dir.path().join("mock-project");
         let cwd = root.to_path_buf();
         let config = test_config(root, cwd, Verbosity::Quiet);
-        let options = WorkspaceOptions { uses_git: false };
+        let options = WorkspaceOptions {
+            uses_git: false,
+            ..Default::default()
+        };
 
         new_app_project(&config, &options).unwrap();
 
@@ -148,4 +254,72 @@ if __name__ == "__main__":
         );
         assert_eq!(main_file, expected_main_file);
     }
+
+    #[test]
+    fn test_new_project_failure() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(root, cwd, Verbosity::Quiet);
+        let options = WorkspaceOptions {
+            uses_git: false,
+            ..Default::default()
+        };
+
+        let res = new_project(&config, &options, |config| {
+            write_lib_project(config, &options)?;
+            Err(Error::InternalError("interrupted".to_string()))
+        });
+
+        assert!(res.is_err());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_new_lib_project_with_options() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(root, cwd, Verbosity::Quiet);
+        let options = WorkspaceOptions {
+            uses_git: false,
+            name: Some("wizard-project".to_string()),
+            python_version: Some("3.9".to_string()),
+            license: Some("MIT".to_string()),
+            dev_dependencies: vec!["pytest".to_string(), "ruff".to_string()],
+            from_requirements: false,
+            scaffold: false,
+            check_index: false,
+        };
+
+        new_lib_project(&config, &options).unwrap();
+
+        let ws = config.workspace();
+        let metadata = ws.current_local_metadata().unwrap();
+        let project = metadata.metadata().project();
+
+        assert_eq!(metadata.metadata().project_name(), "wizard-project");
+        assert_eq!(
+            project.requires_python.as_ref().unwrap().to_string(),
+            ">=3.9"
+        );
+        assert_eq!(
+            project.license.as_ref().unwrap().text.as_deref(),
+            Some("MIT")
+        );
+        assert_eq!(
+            metadata
+                .metadata()
+                .optional_dependency_group("dev")
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(ws
+            .root()
+            .join("src")
+            .join("wizard_project")
+            .join("__init__.py")
+            .exists());
+    }
 }
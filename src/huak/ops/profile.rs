@@ -0,0 +1,295 @@
+use super::{make_venv_command, op_env_vars};
+use crate::{dependency::Dependency, Config, HuakResult, InstallOptions};
+use indexmap::IndexMap;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+use termcolor::Color;
+
+const PROFILES_DIR: &str = ".huak/profiles";
+const DISPLAYED_STATS_COUNT: &str = "20";
+
+pub struct ProfileOptions {
+    /// The script, entry point, or module to profile.
+    pub target: String,
+    /// A values vector of arguments passed on to the profiled target.
+    pub values: Option<Vec<String>>,
+    /// Record a flamegraph with `py-spy` instead of profiling with `cProfile`.
+    pub flamegraph: bool,
+    /// The path to write the profile to. Defaults to a file in ".huak/profiles".
+    pub output: Option<PathBuf>,
+    pub install_options: InstallOptions,
+}
+
+/// Something to profile, resolved from a `ProfileOptions` target.
+#[derive(Debug, PartialEq, Eq)]
+enum ProfileTarget {
+    /// A path to a Python script.
+    Script(String),
+    /// One of the project's `[project.scripts]` entry points.
+    EntryPoint {
+        name: String,
+        module: String,
+        attribute: String,
+    },
+    /// An importable module run like `python -m <module>`.
+    Module(String),
+}
+
+impl ProfileTarget {
+    /// Resolve a target to a script if it's a path to a Python file, an entry point if
+    /// it's one of the project's scripts, or a module otherwise.
+    fn resolve(
+        target: &str,
+        cwd: &Path,
+        scripts: Option<&IndexMap<String, String>>,
+    ) -> ProfileTarget {
+        if target.ends_with(".py") || cwd.join(target).is_file() {
+            return ProfileTarget::Script(target.to_string());
+        }
+
+        match scripts.and_then(|it| it.get(target)) {
+            Some(entry_point) => {
+                let (module, attribute) = entry_point
+                    .split_once(':')
+                    .unwrap_or((entry_point, "main"));
+                ProfileTarget::EntryPoint {
+                    name: target.to_string(),
+                    module: module.trim().to_string(),
+                    attribute: attribute.trim().to_string(),
+                }
+            }
+            None => ProfileTarget::Module(target.to_string()),
+        }
+    }
+
+    /// Get the name of the target used for its default profile file.
+    fn stem(&self) -> String {
+        match self {
+            ProfileTarget::Script(path) => Path::new(path)
+                .file_stem()
+                .map(|it| it.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string()),
+            ProfileTarget::EntryPoint { name, .. } => name.to_string(),
+            ProfileTarget::Module(module) => module.to_string(),
+        }
+    }
+
+    /// Get the `python` arguments that run the target. When `profile` is set the target
+    /// is run under `cProfile`, writing its stats to the path.
+    fn python_args(&self, profile: Option<&Path>) -> Vec<String> {
+        let mut args = Vec::new();
+        if let (Some(path), false) = (profile, self.is_entry_point()) {
+            args.extend([
+                "-m".to_string(),
+                "cProfile".to_string(),
+                "-o".to_string(),
+                path.display().to_string(),
+            ]);
+        }
+
+        match self {
+            ProfileTarget::Script(path) => args.push(path.to_string()),
+            ProfileTarget::Module(module) => {
+                args.extend(["-m".to_string(), module.to_string()])
+            }
+            // `cProfile` can't run entry points directly, so they're called from code
+            // run with `python -c`.
+            ProfileTarget::EntryPoint {
+                name,
+                module,
+                attribute,
+            } => {
+                let object = attribute.split('.').next().unwrap_or(attribute);
+                let setup = format!(
+                    "import sys; from {module} import {object}; sys.argv[0] = {name:?}"
+                );
+                let code = match profile {
+                    Some(path) => format!(
+                        "{setup}; import cProfile; cProfile.run({:?}, {:?})",
+                        format!("{attribute}()"),
+                        path.display().to_string()
+                    ),
+                    None => format!("{setup}; sys.exit({attribute}())"),
+                };
+                args.extend(["-c".to_string(), code]);
+            }
+        }
+
+        args
+    }
+
+    fn is_entry_point(&self) -> bool {
+        matches!(self, ProfileTarget::EntryPoint { .. })
+    }
+}
+
+/// Profile a script, entry point, or module within the project's environment context.
+/// Profiles are written to ".huak/profiles" by default. `cProfile` profiles have their
+/// top stats displayed, and `py-spy` is installed to record flamegraphs.
+pub fn profile_project(
+    config: &Config,
+    options: &ProfileOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let package = workspace.current_package()?;
+    let mut metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+    let env_vars = op_env_vars(&metadata, "profile")?;
+
+    if options.flamegraph {
+        // Install `py-spy` if it isn't already installed.
+        let profile_dep = Dependency::from_str("py-spy")?;
+        if !python_env.contains_module(profile_dep.name())? {
+            python_env.install_packages(
+                &[&profile_dep],
+                &options.install_options,
+                config,
+            )?;
+        }
+
+        // Add the installed `py-spy` package to the metadata file if it isn't already there.
+        if !metadata.metadata().contains_dependency_any(&profile_dep)? {
+            for pkg in python_env
+                .installed_packages()?
+                .iter()
+                .filter(|pkg| pkg.name() == profile_dep.name())
+            {
+                metadata.metadata_mut().add_optional_dependency(
+                    Dependency::from_str(&pkg.to_string())?,
+                    "dev",
+                );
+            }
+        }
+
+        if package.metadata() != metadata.metadata() {
+            metadata.write_file()?;
+        }
+    }
+
+    let target = ProfileTarget::resolve(
+        &options.target,
+        &config.cwd,
+        metadata.metadata().project().scripts.as_ref(),
+    );
+    let output = match options.output.as_ref() {
+        Some(it) => config.cwd.join(it),
+        None => workspace.root().join(PROFILES_DIR).join(format!(
+            "{}.{}",
+            target.stem(),
+            if options.flamegraph { "svg" } else { "prof" }
+        )),
+    };
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // Run the target with the package directory added to the command's `PYTHONPATH`.
+    let python_path = if workspace.root().join("src").exists() {
+        workspace.root().join("src")
+    } else {
+        workspace.root().to_path_buf()
+    };
+    let mut cmd = match options.flamegraph {
+        true => {
+            let mut cmd =
+                Command::new(python_env.executables_dir_path().join("py-spy"));
+            cmd.args(["record", "--format", "flamegraph", "-o"])
+                .arg(&output)
+                .arg("--")
+                .arg(python_env.python_path())
+                .args(target.python_args(None));
+            cmd
+        }
+        false => {
+            let mut cmd = Command::new(python_env.python_path());
+            cmd.args(target.python_args(Some(&output)));
+            cmd
+        }
+    };
+    make_venv_command(&mut cmd, &python_env, config)?;
+    cmd.envs(env_vars.iter().cloned());
+    cmd.args(options.values.iter().flatten())
+        .env("PYTHONPATH", python_path)
+        .current_dir(&config.cwd);
+    let mut terminal = config.terminal();
+    terminal.run_command(&mut cmd)?;
+
+    if !options.flamegraph {
+        let mut cmd = Command::new(python_env.python_path());
+        make_venv_command(&mut cmd, &python_env, config)?;
+        cmd.envs(env_vars.iter().cloned());
+        cmd.args([
+            "-c",
+            "import pstats, sys; \
+            pstats.Stats(sys.argv[1]).sort_stats('cumulative').print_stats(int(sys.argv[2]))",
+        ])
+        .arg(&output)
+        .arg(DISPLAYED_STATS_COUNT);
+        terminal.run_command(&mut cmd)?;
+    }
+
+    terminal.print_custom(
+        "Saved",
+        format!(
+            "{} to {}",
+            if options.flamegraph {
+                "flamegraph"
+            } else {
+                "profile"
+            },
+            output
+                .strip_prefix(&config.cwd)
+                .unwrap_or(&output)
+                .display()
+        ),
+        Color::Green,
+        true,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_profile_target() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("script"), "").unwrap();
+        let mut scripts = IndexMap::new();
+        scripts.insert("cli".to_string(), "pkg.cli:app.run".to_string());
+
+        let script = ProfileTarget::resolve("script", dir.path(), None);
+        let entry_point =
+            ProfileTarget::resolve("cli", dir.path(), Some(&scripts));
+        let module = ProfileTarget::resolve("pkg.cli", dir.path(), None);
+
+        assert_eq!(script, ProfileTarget::Script("script".to_string()));
+        assert_eq!(
+            script.python_args(Some(Path::new("out.prof"))),
+            vec!["-m", "cProfile", "-o", "out.prof", "script"]
+        );
+        assert_eq!(module.stem(), "pkg.cli");
+        assert_eq!(module.python_args(None), vec!["-m", "pkg.cli"]);
+        assert_eq!(entry_point.stem(), "cli");
+        assert_eq!(
+            entry_point.python_args(None),
+            vec![
+                "-c",
+                "import sys; from pkg.cli import app; sys.argv[0] = \"cli\"; \
+                sys.exit(app.run())"
+            ]
+        );
+        assert_eq!(
+            entry_point.python_args(Some(Path::new("out.prof"))),
+            vec![
+                "-c",
+                "import sys; from pkg.cli import app; sys.argv[0] = \"cli\"; \
+                import cProfile; cProfile.run(\"app.run()\", \"out.prof\")"
+            ]
+        );
+    }
+}
@@ -1,57 +1,675 @@
-use super::make_venv_command;
-use crate::{dependency::Dependency, Config, HuakResult, InstallOptions};
-use std::{process::Command, str::FromStr};
+use super::{
+    make_venv_command,
+    members::{dependency_order, python_workspace_members},
+    op_env_vars, tool_python_environment,
+};
+use crate::{
+    dist_info::parse_metadata_headers,
+    index,
+    metadata::LocalMetadata,
+    network::{Upload, UploadStatus},
+    package::{canonical_package_name, Package},
+    sys::CiProvider,
+    wheel::{hex, wheel_metadata, WheelFilename},
+    Config, Error, HuakResult, InstallOptions,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+use termcolor::Color;
+
+/// The package indexes twine knows by name, with their upload URLs and the base URLs of
+/// their trusted publishing APIs.
+const TRUSTED_PUBLISHING_INDEXES: [(&str, &str, &str); 2] = [
+    (
+        "pypi",
+        "https://upload.pypi.org/legacy/",
+        "https://pypi.org",
+    ),
+    (
+        "testpypi",
+        "https://test.pypi.org/legacy/",
+        "https://test.pypi.org",
+    ),
+];
 
 pub struct PublishOptions {
     /// A values vector of publish options typically used for passing on arguments.
     pub values: Option<Vec<String>>,
     pub install_options: InstallOptions,
+    /// Publish each of the workspace's members that isn't already on the package index.
+    pub workspace: bool,
+    /// Print which workspace members would be published without publishing them.
+    pub dry_run: bool,
 }
 
+/// Upload the project's built distributions.
+///
+/// When credentials are available from trusted publishing or `TWINE_USERNAME` and
+/// `TWINE_PASSWORD`, the artifacts are uploaded in parallel by huak, retrying transient
+/// failures. Files the package index already has fail the publish unless twine's
+/// `--skip-existing` is passed, so that an interrupted publish can be resumed. Otherwise,
+/// or when twine is passed arguments huak doesn't handle, twine uploads them.
+///
+/// When huak runs in CI that provides OpenID Connect identity tokens (GitHub Actions with
+/// `id-token: write`, or GitLab CI with a `PYPI_ID_TOKEN` ID token) and no credentials are
+/// passed to twine, a short-lived API token is minted with PyPI's trusted publishing so
+/// that no credentials need to be stored. Otherwise twine's usual tokens and keyring are
+/// used.
 pub fn publish_project(
     config: &Config,
     options: &PublishOptions,
 ) -> HuakResult<()> {
-    let workspace = config.workspace();
-    let package = workspace.current_package()?;
-    let mut metadata = workspace.current_local_metadata()?;
-    let python_env = workspace.resolve_python_environment()?;
-
-    // Install `twine` if it isn't already installed.
-    let pub_dep = Dependency::from_str("twine")?;
-    if !python_env.contains_module(pub_dep.name())? {
-        python_env.install_packages(
-            &[&pub_dep],
-            &options.install_options,
-            config,
-        )?;
+    if options.workspace {
+        return publish_workspace_members(config, options);
     }
 
-    // Add the installed `twine` package to the metadata file if it isn't already there.
-    if !metadata.metadata().contains_dependency_any(&pub_dep)? {
-        for pkg in python_env
-            .installed_packages()?
-            .iter()
-            .filter(|pkg| pkg.name() == pub_dep.name())
+    let workspace = config.workspace();
+    let mut metadata = workspace.current_local_metadata()?;
+    let env = op_env_vars(&metadata, "publish")?;
+    let token = trusted_publishing_token(options, &env, config)?;
+    if let Some(target) = upload_target(options, token.as_deref(), &env) {
+        if let Some(uploads) = artifact_uploads(&workspace.root().join("dist"))?
         {
-            metadata.metadata_mut().add_optional_dependency(
-                Dependency::from_str(&pkg.to_string())?,
-                "dev",
-            );
+            return upload_artifacts(uploads, &target, config);
         }
     }
 
-    if package.metadata() != metadata.metadata() {
-        metadata.write_file()?;
-    }
+    let python_env = workspace.resolve_python_environment()?;
+    let twine_env = tool_python_environment(
+        &["twine"],
+        &python_env,
+        &mut metadata,
+        &options.install_options,
+        config,
+    )?;
 
     // Run `twine`.
-    let mut cmd = Command::new(python_env.python_path());
+    let mut cmd = Command::new(twine_env.python_path());
     let mut args = vec!["-m", "twine", "upload", "dist/*"];
     if let Some(v) = options.values.as_ref() {
         args.extend(v.iter().map(|item| item.as_str()));
     }
-    make_venv_command(&mut cmd, &python_env)?;
+    make_venv_command(&mut cmd, &twine_env, config)?;
+    cmd.envs(env);
+    if let Some(token) = token {
+        cmd.env("TWINE_USERNAME", "__token__")
+            .env("TWINE_PASSWORD", token);
+    }
     cmd.args(args).current_dir(workspace.root());
     config.terminal().run_command(&mut cmd)
 }
+
+/// Publish the workspace's Python members whose versions aren't on the package index
+/// yet, in dependency order. Each member's artifacts are uploaded from its directory of
+/// the root's dist directory (see `huak build --workspace`). With `dry_run` the plan is
+/// printed without publishing anything.
+fn publish_workspace_members(
+    config: &Config,
+    options: &PublishOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut metadata = workspace.current_local_metadata()?;
+    let members =
+        python_workspace_members(workspace.root(), metadata.metadata())?
+            .iter()
+            .map(|it| LocalMetadata::new(it.join("pyproject.toml")))
+            .collect::<HuakResult<Vec<_>>>()?;
+    if members.is_empty() {
+        return Err(Error::HuakConfigurationError(
+            "no Python workspace members are listed in [tool.huak.workspace]"
+                .to_string(),
+        ));
+    }
+
+    // Plan which members to publish by checking the index for their versions.
+    let client = config.client()?;
+    let mut terminal = config.terminal();
+    let mut planned = Vec::new();
+    for index in dependency_order(&members)? {
+        let member = &members[index];
+        let package = Package::from(member.metadata().clone());
+        let version = package.version().to_string();
+        if index::release_exists(&client, package.name(), &version)? {
+            terminal.print_custom(
+                "Skipped",
+                format!("{} {version} (already published)", package.name()),
+                Color::Yellow,
+                true,
+            )?;
+            continue;
+        }
+        let dist = workspace.root().join("dist").join(package.name());
+        let has_artifacts = dist.is_dir() && dist.read_dir()?.next().is_some();
+        if !has_artifacts && !options.dry_run {
+            return Err(Error::HuakConfigurationError(format!(
+                "there are no artifacts for {} in {}, run `huak build --workspace` first",
+                package.name(),
+                dist.display()
+            )));
+        }
+        terminal.print_custom(
+            "Planned",
+            format!("{} {version}", package.name()),
+            Color::Green,
+            true,
+        )?;
+        planned.push((member, package));
+    }
+
+    if options.dry_run || planned.is_empty() {
+        return terminal.print_custom(
+            "Finished",
+            format!(
+                "{} of {} member(s) to publish",
+                planned.len(),
+                members.len()
+            ),
+            Color::Green,
+            true,
+        );
+    }
+
+    let python_env = workspace.resolve_python_environment()?;
+    let mut twine_env = None;
+    for (member, package) in planned.iter() {
+        terminal.print_custom(
+            "Publishing",
+            format!("{} {}", package.name(), package.version()),
+            Color::Green,
+            true,
+        )?;
+        let env = op_env_vars(member, "publish")?;
+        let token = trusted_publishing_token(options, &env, config)?;
+        if let Some(target) = upload_target(options, token.as_deref(), &env) {
+            let dist = workspace.root().join("dist").join(package.name());
+            if let Some(uploads) = artifact_uploads(&dist)? {
+                upload_artifacts(uploads, &target, config)?;
+                continue;
+            }
+        }
+        let twine_env = match twine_env {
+            Some(ref it) => it,
+            None => &*twine_env.insert(tool_python_environment(
+                &["twine"],
+                &python_env,
+                &mut metadata,
+                &options.install_options,
+                config,
+            )?),
+        };
+        let mut cmd = Command::new(twine_env.python_path());
+        make_venv_command(&mut cmd, twine_env, config)?;
+        cmd.envs(env);
+        if let Some(it) = token.as_ref() {
+            cmd.env("TWINE_USERNAME", "__token__")
+                .env("TWINE_PASSWORD", it);
+        }
+        cmd.args(["-m", "twine", "upload"])
+            .arg(format!("dist/{}/*", package.name()))
+            .args(options.values.iter().flatten())
+            .current_dir(workspace.root());
+        terminal.run_command(&mut cmd)?;
+    }
+
+    terminal.print_custom(
+        "Finished",
+        format!("published {} of {} member(s)", planned.len(), members.len()),
+        Color::Green,
+        true,
+    )
+}
+
+#[derive(Deserialize)]
+struct OidcAudience {
+    audience: String,
+}
+
+#[derive(Deserialize)]
+struct OidcToken {
+    value: String,
+}
+
+#[derive(Deserialize)]
+/// The response to minting an API token, with either the token or why it was refused.
+struct MintedToken {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    errors: Vec<MintError>,
+}
+
+#[derive(Deserialize)]
+struct MintError {
+    code: String,
+    description: String,
+}
+
+/// Mint a short-lived API token with trusted publishing. `None` is returned when twine
+/// has credentials, the repository doesn't support trusted publishing, or the CI service
+/// doesn't provide an identity token, so that twine falls back to its own credentials.
+fn trusted_publishing_token(
+    options: &PublishOptions,
+    env: &[(String, String)],
+    config: &Config,
+) -> HuakResult<Option<String>> {
+    let values = options.values.as_deref().unwrap_or_default();
+    if has_twine_credentials(values, env) {
+        return Ok(None);
+    }
+    let Some(index) = trusted_publishing_index(values, env) else {
+        return Ok(None);
+    };
+    let Some(provider) = CiProvider::detect() else {
+        return Ok(None);
+    };
+
+    let client = config.client()?;
+    let audience = client
+        .get_json::<OidcAudience>(&format!("{index}/_/oidc/audience"))?
+        .audience;
+    let identity = match provider {
+        CiProvider::GithubActions => {
+            let var = |key: &str| std::env::var(key).ok();
+            let (Some(url), Some(token)) = (
+                var("ACTIONS_ID_TOKEN_REQUEST_URL"),
+                var("ACTIONS_ID_TOKEN_REQUEST_TOKEN"),
+            ) else {
+                // The workflow doesn't have the `id-token: write` permission.
+                return Ok(None);
+            };
+            let url = format!("{url}&audience={audience}");
+            client.get_json_authorized::<OidcToken>(&url, &token)?.value
+        }
+        CiProvider::GitlabCi => match std::env::var("PYPI_ID_TOKEN") {
+            Ok(it) if !it.is_empty() => it,
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    let minted = client.post_json::<MintedToken>(
+        &format!("{index}/_/oidc/mint-token"),
+        &serde_json::json!({ "token": identity }),
+    )?;
+    let Some(token) = minted.token else {
+        let mut reasons = minted
+            .errors
+            .iter()
+            .map(|it| format!("{} ({})", it.description, it.code))
+            .collect::<Vec<_>>();
+        reasons.extend(minted.message);
+        return Err(Error::TrustedPublishingFailed(format!(
+            "{index} refused to mint a token: {}",
+            reasons.join("; ")
+        )));
+    };
+    config.terminal().print_custom(
+        "Authenticated",
+        format!("with trusted publishing from {provider} to {index}"),
+        Color::Green,
+        true,
+    )?;
+
+    Ok(Some(token))
+}
+
+/// Check if credentials are passed to twine with its arguments or environment variables.
+fn has_twine_credentials(values: &[String], env: &[(String, String)]) -> bool {
+    let flag = values.iter().any(|it| {
+        ["-u", "-p", "--username", "--password"]
+            .iter()
+            .any(|flag| it == flag || it.starts_with(&format!("{flag}=")))
+    });
+
+    flag || twine_var("TWINE_PASSWORD", env).is_some()
+}
+
+/// Get one of twine's environment variables from the op's environment variables (see
+/// `op_env_vars`) or huak's.
+fn twine_var(key: &str, env: &[(String, String)]) -> Option<String> {
+    env.iter()
+        .find(|(it, _)| it == key)
+        .map(|(_, value)| value.clone())
+        .or_else(|| std::env::var(key).ok())
+        .filter(|it| !it.is_empty())
+}
+
+/// Get the value of one of twine's arguments.
+fn twine_arg(values: &[String], flags: &[&str]) -> Option<String> {
+    values.iter().enumerate().find_map(|(i, it)| {
+        flags.iter().find_map(|flag| {
+            if it == flag {
+                values.get(i + 1).cloned()
+            } else {
+                it.strip_prefix(&format!("{flag}=")).map(str::to_string)
+            }
+        })
+    })
+}
+
+/// Get the URL twine uploads to. The repository is read from twine's `--repository-url`
+/// and `--repository` arguments, then `TWINE_REPOSITORY_URL` and `TWINE_REPOSITORY`, and
+/// defaults to PyPI. `None` is returned for repositories named in a .pypirc.
+fn repository_url(
+    values: &[String],
+    env: &[(String, String)],
+) -> Option<String> {
+    let url = twine_arg(values, &["--repository-url"])
+        .or_else(|| twine_var("TWINE_REPOSITORY_URL", env));
+    if url.is_some() {
+        return url;
+    }
+    let name = twine_arg(values, &["-r", "--repository"])
+        .or_else(|| twine_var("TWINE_REPOSITORY", env))
+        .unwrap_or_else(|| "pypi".to_string());
+
+    TRUSTED_PUBLISHING_INDEXES
+        .iter()
+        .find(|(it, _, _)| *it == name)
+        .map(|(_, upload, _)| upload.to_string())
+}
+
+/// Get the base URL of the package index twine uploads to, if it supports trusted
+/// publishing.
+fn trusted_publishing_index(
+    values: &[String],
+    env: &[(String, String)],
+) -> Option<&'static str> {
+    let url = repository_url(values, env)?;
+    let url = url.trim_end_matches('/');
+
+    TRUSTED_PUBLISHING_INDEXES
+        .iter()
+        .find(|(_, upload, _)| upload.trim_end_matches('/') == url)
+        .map(|(_, _, index)| *index)
+}
+
+/// Where huak uploads artifacts to without twine, and the credentials it uses.
+struct UploadTarget {
+    url: String,
+    username: String,
+    password: String,
+    /// Skip files the package index already has (twine's `--skip-existing`).
+    skip_existing: bool,
+}
+
+/// Get the `UploadTarget` for uploading without twine. `None` is returned when twine is
+/// needed because it's passed arguments other than the repository, or its credentials
+/// would come from its keyring or a .pypirc.
+fn upload_target(
+    options: &PublishOptions,
+    token: Option<&str>,
+    env: &[(String, String)],
+) -> Option<UploadTarget> {
+    let values = options.values.as_deref().unwrap_or_default();
+    let mut skip_existing = false;
+    let mut args = values.iter();
+    while let Some(it) = args.next() {
+        match it.as_str() {
+            "-r" | "--repository" | "--repository-url" => {
+                args.next();
+            }
+            "--skip-existing" => skip_existing = true,
+            "--non-interactive" => (),
+            it if it.starts_with("--repository=")
+                || it.starts_with("--repository-url=") => {}
+            _ => return None,
+        }
+    }
+    let (username, password) = match token {
+        Some(it) => ("__token__".to_string(), it.to_string()),
+        None => (
+            twine_var("TWINE_USERNAME", env)
+                .unwrap_or_else(|| "__token__".to_string()),
+            twine_var("TWINE_PASSWORD", env)?,
+        ),
+    };
+
+    Some(UploadTarget {
+        url: repository_url(values, env)?,
+        username,
+        password,
+        skip_existing,
+    })
+}
+
+/// Get the `Upload`s of the wheels and sdists in a dist directory. Sdists are uploaded
+/// with the metadata of the wheel built with them, so `None` is returned if an sdist
+/// doesn't have one and twine has to read it.
+fn artifact_uploads(dist: &Path) -> HuakResult<Option<Vec<Upload>>> {
+    let mut paths = match dist.read_dir() {
+        Ok(it) => it.flatten().map(|it| it.path()).collect::<Vec<_>>(),
+        Err(_) => Vec::new(),
+    };
+    paths.retain(|it| {
+        let name = it.to_string_lossy();
+        it.is_file()
+            && [".whl", ".tar.gz", ".zip"]
+                .iter()
+                .any(|ext| name.ends_with(ext))
+    });
+    paths.sort();
+    if paths.is_empty() {
+        return Err(Error::HuakConfigurationError(format!(
+            "there are no artifacts in {}, run `huak build` first",
+            dist.display()
+        )));
+    }
+
+    let mut wheels = Vec::new();
+    for path in paths.iter().filter(|it| has_extension(it, ".whl")) {
+        let name = WheelFilename::from_str(&file_name(path))?;
+        wheels.push((normalize_release(&name.name, &name.version)?, path));
+    }
+    let mut uploads = Vec::new();
+    for path in paths.iter() {
+        let (filetype, pyversion, wheel) = if has_extension(path, ".whl") {
+            let name = WheelFilename::from_str(&file_name(path))?;
+            ("bdist_wheel", name.python_tags.join("."), path)
+        } else {
+            let name = file_name(path);
+            let stem = [".tar.gz", ".zip"]
+                .iter()
+                .find_map(|it| name.strip_suffix(it))
+                .unwrap_or(&name);
+            let Some((name, version)) = stem.rsplit_once('-') else {
+                return Ok(None);
+            };
+            let release = normalize_release(name, version)?;
+            let Some((_, wheel)) = wheels.iter().find(|(it, _)| *it == release)
+            else {
+                return Ok(None);
+            };
+            ("sdist", "source".to_string(), *wheel)
+        };
+        let mut fields = vec![
+            (":action".to_string(), "file_upload".to_string()),
+            ("protocol_version".to_string(), "1".to_string()),
+            ("filetype".to_string(), filetype.to_string()),
+            ("pyversion".to_string(), pyversion),
+            (
+                "sha256_digest".to_string(),
+                hex(&Sha256::digest(std::fs::read(path)?)),
+            ),
+        ];
+        fields.extend(metadata_fields(&wheel_metadata(wheel)?));
+        uploads.push(Upload {
+            path: path.clone(),
+            fields,
+        });
+    }
+
+    Ok(Some(uploads))
+}
+
+/// Get the upload form's fields for a METADATA file's headers and description.
+fn metadata_fields(metadata: &str) -> Vec<(String, String)> {
+    let mut fields = parse_metadata_headers(metadata)
+        .into_iter()
+        .map(|(key, value)| {
+            let key = match key.to_lowercase().as_str() {
+                "classifier" => "classifiers".to_string(),
+                "project-url" => "project_urls".to_string(),
+                it => it.replace('-', "_"),
+            };
+            (key, value)
+        })
+        .collect::<Vec<_>>();
+    let metadata = metadata.replace("\r\n", "\n");
+    if let Some((_, description)) = metadata.split_once("\n\n") {
+        if !description.trim().is_empty() {
+            fields.push(("description".to_string(), description.to_string()));
+        }
+    }
+
+    fields
+}
+
+/// Upload artifacts in parallel, reporting each one as it finishes.
+fn upload_artifacts(
+    uploads: Vec<Upload>,
+    target: &UploadTarget,
+    config: &Config,
+) -> HuakResult<()> {
+    let client = config.client()?;
+    let mut terminal = config.terminal();
+    let total = uploads.len();
+    terminal.print_custom(
+        "Uploading",
+        format!("{total} artifact(s) to {}", target.url),
+        Color::Green,
+        true,
+    )?;
+
+    let mut finished = 0;
+    let results = client.upload_all(
+        &target.url,
+        (&target.username, &target.password),
+        uploads,
+        target.skip_existing,
+        |upload, res| {
+            finished += 1;
+            let name = file_name(&upload.path);
+            // Progress is best-effort; failures are reported once every upload is done.
+            let _ = match res {
+                Ok(UploadStatus::Uploaded) => terminal.print_custom(
+                    "Uploaded",
+                    format!("{name} ({finished}/{total})"),
+                    Color::Green,
+                    true,
+                ),
+                Ok(UploadStatus::Existing) => terminal.print_custom(
+                    "Skipped",
+                    format!("{name} (already uploaded) ({finished}/{total})"),
+                    Color::Yellow,
+                    true,
+                ),
+                Err(_) => terminal.print_custom(
+                    "Failed",
+                    format!("{name} ({finished}/{total})"),
+                    Color::Red,
+                    true,
+                ),
+            };
+        },
+    );
+    let uploaded = results
+        .into_iter()
+        .collect::<HuakResult<Vec<_>>>()?
+        .iter()
+        .filter(|it| **it == UploadStatus::Uploaded)
+        .count();
+
+    terminal.print_custom(
+        "Published",
+        format!("{uploaded} of {total} artifact(s)"),
+        Color::Green,
+        true,
+    )
+}
+
+/// Get a `<name>==<version>` key for comparing releases across file names.
+fn normalize_release(name: &str, version: &str) -> HuakResult<String> {
+    Ok(format!(
+        "{}=={version}",
+        canonical_package_name(name)?.to_lowercase()
+    ))
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.to_string_lossy().ends_with(extension)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|it| it.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trusted_publishing_index() {
+        let args = |it: &[&str]| {
+            it.iter().map(ToString::to_string).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            trusted_publishing_index(&args(&["-r", "testpypi"]), &[]),
+            Some("https://test.pypi.org")
+        );
+        assert_eq!(
+            trusted_publishing_index(
+                &args(&["--repository-url=https://upload.pypi.org/legacy"]),
+                &[]
+            ),
+            Some("https://pypi.org")
+        );
+        assert_eq!(
+            trusted_publishing_index(
+                &args(&[
+                    "--repository-url",
+                    "https://pypi.internal.example.com/simple/"
+                ]),
+                &[]
+            ),
+            None
+        );
+        assert!(has_twine_credentials(&args(&["--password=secret"]), &[]));
+        assert!(has_twine_credentials(
+            &[],
+            &[("TWINE_PASSWORD".to_string(), "secret".to_string())]
+        ));
+    }
+
+    #[test]
+    fn test_metadata_fields() {
+        let metadata = "Metadata-Version: 2.1\nName: mock-project\nVersion: 0.1.0\n\
+            Classifier: Typing :: Typed\nProject-URL: Homepage, https://example.com\n\n\
+            # mock-project\n";
+
+        let fields = metadata_fields(metadata);
+
+        assert_eq!(
+            fields,
+            [
+                ("metadata_version", "2.1"),
+                ("name", "mock-project"),
+                ("version", "0.1.0"),
+                ("classifiers", "Typing :: Typed"),
+                ("project_urls", "Homepage, https://example.com"),
+                ("description", "# mock-project\n"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+        );
+    }
+}
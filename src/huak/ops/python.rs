@@ -1,48 +1,342 @@
-use crate::{environment::Environment, Config, Error, HuakResult};
-use std::process::Command;
+use crate::{
+    environment::Environment,
+    fs::{self, DirReplacement},
+    python_environment::{
+        default_venv_name, parse_python_from_command, Interpreter, VenvOptions,
+    },
+    sys::{SubprocessError, Table},
+    Config, Error, HuakResult, PythonRequest,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
 use termcolor::Color;
 
-pub fn list_python(config: &Config) -> HuakResult<()> {
+/// A Python snippet used to describe an `Interpreter`'s wheel tags and sysconfig paths
+/// as JSON.
+const INTERPRETER_INFO_SCRIPT: &str = r#"
+import json, platform, sys, sysconfig
+try:
+    from packaging import tags
+except ImportError:
+    from pip._vendor.packaging import tags
+supported = list(tags.sys_tags())
+print(json.dumps({
+    "implementation": platform.python_implementation(),
+    "version": platform.python_version(),
+    "executable": sys.executable,
+    "interpreter_tag": tags.interpreter_name() + tags.interpreter_version(),
+    "abi_tag": supported[0].abi if supported else None,
+    "platform": sysconfig.get_platform(),
+    "platform_tags": list(tags.platform_tags()),
+    "supported_tags": len(supported),
+    "paths": sysconfig.get_paths(),
+}))
+"#;
+
+pub struct PythonListOptions {
+    /// Print the interpreters as JSON.
+    pub json: bool,
+}
+
+pub struct PythonInfoOptions {
+    /// Print the interpreter's details as JSON.
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+/// An `Interpreter`'s details as reported by `INTERPRETER_INFO_SCRIPT`.
+struct InterpreterInfo {
+    implementation: String,
+    version: String,
+    executable: PathBuf,
+    interpreter_tag: String,
+    abi_tag: Option<String>,
+    platform: String,
+    platform_tags: Vec<String>,
+    /// The number of wheel tags the interpreter supports.
+    supported_tags: usize,
+    paths: BTreeMap<String, PathBuf>,
+}
+
+/// A Python `Interpreter` found on `PATH` with the paths that resolve to it.
+struct Listing {
+    interpreter: Interpreter,
+    /// The interpreter's path with symlinks resolved.
+    real_path: PathBuf,
+    /// Other paths on `PATH` that resolve to the interpreter.
+    aliases: Vec<PathBuf>,
+}
+
+/// List the Python interpreters found on `PATH` in order with their versions and where
+/// they came from. Symlinks to the same interpreter are listed once, and the interpreter
+/// the workspace uses is marked along with the reason it's selected.
+pub fn list_python(
+    config: &Config,
+    options: &PythonListOptions,
+) -> HuakResult<()> {
     let env = Environment::new();
+    let mut terminal = config.terminal();
 
-    // Print enumerated Python paths as they exist in the `PATH` environment variable.
-    env.python_paths().enumerate().for_each(|(i, path)| {
-        config
-            .terminal()
-            .print_custom(i + 1, path.display(), Color::Blue, false)
-            .ok();
-    });
+    let mut listings: Vec<Listing> = Vec::new();
+    for interpreter in env.interpreters().interpreters() {
+        let path = interpreter.path();
+        let real_path = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if let Some(it) =
+            listings.iter_mut().find(|it| it.real_path == real_path)
+        {
+            it.aliases.push(path.clone());
+            continue;
+        }
+        // Versions parsed from file names (like python3.11) don't include the patch.
+        let interpreter = match parse_python_from_command(path) {
+            Ok(Some((implementation, version))) => {
+                Interpreter::new(path, version)
+                    .with_implementation(implementation)
+            }
+            _ => interpreter.clone(),
+        };
+        listings.push(Listing {
+            interpreter,
+            real_path,
+            aliases: Vec::new(),
+        });
+    }
+
+    let interpreters = listings
+        .iter()
+        .map(|it| it.interpreter.clone())
+        .collect::<Vec<_>>();
+    let selection = match config
+        .workspace()
+        .select_python_interpreter(&interpreters)
+    {
+        Ok((interpreter, reason)) => {
+            let path = interpreter.path();
+            Some((path.canonicalize().unwrap_or_else(|_| path.clone()), reason))
+        }
+        Err(Error::PythonNotFound) => None,
+        Err(e) => {
+            terminal.print_warning(e)?;
+            None
+        }
+    };
+    let selected = |listing: &Listing| match selection.as_ref() {
+        Some((path, reason)) if *path == listing.real_path => Some(reason),
+        _ => None,
+    };
+
+    if options.json {
+        let value = listings
+            .iter()
+            .map(|it| {
+                json!({
+                    "implementation": it.interpreter.implementation().to_string(),
+                    "version": it.interpreter.version().to_string(),
+                    "path": it.interpreter.path(),
+                    "real_path": it.real_path,
+                    "aliases": it.aliases,
+                    "source": interpreter_source(&it.real_path),
+                    "selected": selected(it).is_some(),
+                    "reason": selected(it).map(ToString::to_string),
+                })
+            })
+            .collect::<Vec<_>>();
+        return terminal.print_stdout(&format!(
+            "{}\n",
+            serde_json::to_string_pretty(&value)?
+        ));
+    }
+
+    let mut table = Table::new(["python", "path", "source", "notes"]);
+    for it in listings.iter() {
+        let mut notes = Vec::new();
+        if let Some(reason) = selected(it) {
+            notes.push(format!("selected by {reason}"));
+        }
+        if !it.aliases.is_empty() {
+            let aliases = it
+                .aliases
+                .iter()
+                .map(|it| it.display().to_string())
+                .collect::<Vec<_>>();
+            notes.push(format!("also {}", aliases.join(", ")));
+        }
+        table.push_row([
+            format!(
+                "{} {}",
+                it.interpreter.implementation(),
+                it.interpreter.version()
+            ),
+            it.interpreter.path().display().to_string(),
+            interpreter_source(&it.real_path).to_string(),
+            notes.join("; "),
+        ]);
+    }
+    table.set_summary("Found", format!("{} interpreter(s)", listings.len()));
+    terminal.print_table(&table)?;
+    if let Some((path, reason)) = selection.as_ref() {
+        if !listings.iter().any(|it| it.real_path == *path) {
+            terminal.print_custom(
+                "Selected",
+                format!("{} [selected by {reason}]", path.display()),
+                Color::Green,
+                true,
+            )?;
+        }
+    }
 
     Ok(())
 }
 
-pub fn use_python(version: &str, config: &Config) -> HuakResult<()> {
+/// Display the version, implementation, wheel tags, and sysconfig paths of the Python
+/// interpreter the workspace uses, for debugging which wheels it can install.
+pub fn print_python_info(
+    config: &Config,
+    options: &PythonInfoOptions,
+) -> HuakResult<()> {
+    let env = Environment::new();
+    let (interpreter, reason) = config
+        .workspace()
+        .select_python_interpreter(env.interpreters().interpreters())?;
+    let info = interpreter_info(&interpreter)?;
+    let mut terminal = config.terminal();
+
+    if options.json {
+        return terminal.print_stdout(&format!(
+            "{}\n",
+            serde_json::to_string_pretty(&info)?
+        ));
+    }
+
+    let mut lines = vec![
+        ("implementation", info.implementation.clone()),
+        ("version", info.version.clone()),
+        ("executable", info.executable.display().to_string()),
+        ("selected-by", reason.to_string()),
+        ("interpreter-tag", info.interpreter_tag.clone()),
+        ("abi-tag", info.abi_tag.clone().unwrap_or_default()),
+        ("platform", info.platform.clone()),
+        ("platform-tags", info.platform_tags.join(", ")),
+        ("supported-tags", info.supported_tags.to_string()),
+    ];
+    lines.extend(
+        info.paths
+            .iter()
+            .map(|(key, path)| (key.as_str(), path.display().to_string())),
+    );
+    let width = lines.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    for (key, value) in lines {
+        terminal.print_stdout(&format!("{key:width$}  {value}\n"))?;
+    }
+
+    Ok(())
+}
+
+/// Get an `Interpreter`'s `InterpreterInfo` by running `INTERPRETER_INFO_SCRIPT` with it.
+fn interpreter_info(interpreter: &Interpreter) -> HuakResult<InterpreterInfo> {
+    let output = Command::new(interpreter.path())
+        .args(["-c", INTERPRETER_INFO_SCRIPT])
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::SubprocessFailure(SubprocessError::new(
+            output.status,
+        )));
+    }
+
+    Ok(serde_json::from_str(std::str::from_utf8(&output.stdout)?)?)
+}
+
+/// Guess where a Python interpreter was installed from based on its real path.
+fn interpreter_source(path: &Path) -> &'static str {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if path.contains("/.pyenv/") {
+        "pyenv"
+    } else if path.contains("conda") || path.contains("mamba") {
+        "conda"
+    } else if path.contains("/Cellar/") || path.starts_with("/opt/homebrew/") {
+        "homebrew"
+    } else if path.contains("/.local/share/uv/") || path.contains("/uv/python/")
+    {
+        "uv"
+    } else if path.starts_with("/usr/local/") {
+        "local"
+    } else if path.starts_with("/usr/") || path.starts_with("/bin/") {
+        "system"
+    } else {
+        "other"
+    }
+}
+
+/// Create the project's environment with the first interpreter on `PATH` matching a
+/// `PythonRequest` such as "3.11" or "pypy@3.10". Options enabled in `[tool.huak.venv]`
+/// are combined with `options`.
+pub fn use_python(
+    version: &str,
+    options: &VenvOptions,
+    config: &Config,
+) -> HuakResult<()> {
     let interpreters = Environment::resolve_python_interpreters();
+    let request = PythonRequest::from_str(version)?;
 
     // Get a path to an interpreter based on the version provided.
     let path = match interpreters
         .interpreters()
         .iter()
-        .find(|py| py.version().to_string() == version)
+        .find(|py| request.matches(py))
         .map(|py| py.path())
     {
         Some(it) => it,
         None => return Err(Error::PythonNotFound),
     };
 
-    // Remove the current Python environment if one exists.
+    // Replace the current Python environment if one exists. It's restored if the new
+    // environment can't be created.
     let workspace = config.workspace();
-    match workspace.current_python_environment() {
-        Ok(it) => std::fs::remove_dir_all(it.root())?,
-        Err(Error::PythonEnvironmentNotFound) => (),
+    let _lock = workspace.lock()?;
+    let venv_path = config.workspace_root.join(default_venv_name());
+    let replacement = match workspace.current_python_environment() {
+        Ok(it) => {
+            let prompt = format!(
+                "remove {} and create a new environment",
+                it.root().display()
+            );
+            if !config.terminal().confirm(prompt)? {
+                return Ok(());
+            }
+            if it.root() == venv_path {
+                Some(DirReplacement::new(&venv_path)?)
+            } else {
+                fs::remove_dir_all(it.root())?;
+                None
+            }
+        }
+        Err(Error::PythonEnvironmentNotFound) => None,
         Err(e) => return Err(e),
     };
 
     // Create a new Python environment using the interpreter matching the version provided.
-    let mut cmd = Command::new(path);
-    cmd.args(["-m", "venv", ".venv"])
-        .current_dir(&config.workspace_root);
-    config.terminal().run_command(&mut cmd)
+    let options = match workspace.current_local_metadata() {
+        Ok(it) => options.union(&VenvOptions::from_metadata(it.metadata())?),
+        Err(_) => options.clone(),
+    };
+    let mut cmd = options.venv_command(path, default_venv_name());
+    cmd.current_dir(&config.workspace_root);
+    let res = config.terminal().run_command(&mut cmd);
+    if res.is_err() && replacement.is_none() && venv_path.exists() {
+        fs::remove_dir_all(&venv_path).ok();
+    }
+    res?;
+
+    match replacement {
+        Some(it) => it.commit(),
+        None => Ok(()),
+    }
 }
 
 #[cfg(test)]
@@ -51,6 +345,42 @@ mod tests {
     use crate::{ops::test_config, Verbosity};
     use tempfile::tempdir;
 
+    #[test]
+    fn test_interpreter_source() {
+        assert_eq!(
+            interpreter_source(Path::new(
+                "/home/me/.pyenv/versions/3.11.4/bin/python3.11"
+            )),
+            "pyenv"
+        );
+        assert_eq!(
+            interpreter_source(Path::new(
+                "/opt/homebrew/Cellar/python@3.11/3.11.4/bin/python3.11"
+            )),
+            "homebrew"
+        );
+        assert_eq!(
+            interpreter_source(Path::new("/usr/bin/python3.11")),
+            "system"
+        );
+    }
+
+    #[test]
+    fn test_interpreter_info() {
+        let interpreters = Environment::resolve_python_interpreters();
+        let interpreter = interpreters.latest().unwrap();
+        let info = interpreter_info(interpreter).unwrap();
+
+        assert!(info.version.starts_with(&format!(
+            "{}.{}",
+            interpreter.version().release()[0],
+            interpreter.version().release()[1]
+        )));
+        assert!(!info.platform_tags.is_empty());
+        assert!(info.supported_tags > 0);
+        assert!(info.paths.contains_key("purelib"));
+    }
+
     #[test]
     fn test_use_python() {
         let dir = tempdir().unwrap();
@@ -60,6 +390,7 @@ mod tests {
         let cwd = root;
         let config = test_config(root, cwd, Verbosity::Quiet);
 
-        use_python(&version.to_string(), &config).unwrap();
+        use_python(&version.to_string(), &VenvOptions::default(), &config)
+            .unwrap();
     }
 }
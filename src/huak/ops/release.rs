@@ -0,0 +1,224 @@
+use super::{build_project, publish_project, BuildOptions, PublishOptions};
+use crate::{
+    changelog::{
+        changelog_section, release_heading, suggested_bump, today,
+        update_changelog, ChangelogConfig,
+    },
+    git,
+    package::importable_package_name,
+    Bump, Config, Error, HuakResult, InstallOptions,
+};
+use termcolor::Color;
+
+pub struct ReleaseOptions {
+    /// The version bump to release. Defaults to the bump suggested by the Conventional
+    /// Commits since the last tag.
+    pub bump: Option<Bump>,
+    /// Don't add the release to CHANGELOG.md.
+    pub no_changelog: bool,
+    /// Don't commit the release. Uncommitted releases aren't tagged.
+    pub no_commit: bool,
+    /// Don't tag the release commit.
+    pub no_tag: bool,
+    /// Don't build the release.
+    pub no_build: bool,
+    /// Publish the release after building it.
+    pub publish: bool,
+    /// Only report the next version and its changelog section without changing anything.
+    pub dry_run: bool,
+    pub install_options: InstallOptions,
+}
+
+/// Release the next version of the project. The commits since the last tag determine
+/// the version bump and the CHANGELOG.md section, and the release is committed, tagged,
+/// built, and optionally published.
+pub fn release_project(
+    config: &Config,
+    options: &ReleaseOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut metadata = workspace.current_local_metadata()?;
+    let mut terminal = config.terminal();
+    let root = workspace.root();
+
+    let Some(version) = metadata.metadata().project_version().cloned() else {
+        return Err(Error::PackageVersionNotFound);
+    };
+    let tag = git::latest_tag(root)?;
+    let messages = git::commit_messages(root, tag.as_deref())?;
+    let since = tag
+        .as_ref()
+        .map_or("the first commit".to_string(), |it| it.to_string());
+    if messages.is_empty() && options.bump.is_none() {
+        return Err(Error::HuakConfigurationError(format!(
+            "there are no commits to release since {since}"
+        )));
+    }
+    let bump = options
+        .bump
+        .or_else(|| suggested_bump(&messages))
+        .unwrap_or(Bump::Patch);
+    let next = bump.apply(&version)?;
+    terminal.print_custom(
+        "Releasing",
+        format!(
+            "{version} -> {next} ({bump} bump from {} commit(s) since {since})",
+            messages.len()
+        ),
+        Color::Green,
+        true,
+    )?;
+
+    let section = changelog_section(
+        &release_heading(&next.to_string(), &today()),
+        &messages,
+        &ChangelogConfig::from_metadata(metadata.metadata())?,
+    )?;
+    if options.dry_run {
+        return terminal.print_stdout(&section);
+    }
+
+    // Update the version in the pyproject.toml and the package's `__version__`.
+    let mut changed = vec![metadata.path().to_path_buf()];
+    metadata.metadata_mut().set_project_version(next.clone());
+    metadata.write_file()?;
+    let name = importable_package_name(metadata.metadata().project_name())?;
+    for path in [
+        root.join("src").join(&name).join("__init__.py"),
+        root.join(&name).join("__init__.py"),
+    ] {
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let old = format!("__version__ = \"{version}\"");
+        if contents.contains(&old) {
+            std::fs::write(
+                &path,
+                contents.replace(&old, &format!("__version__ = \"{next}\"")),
+            )?;
+            changed.push(path);
+        }
+    }
+
+    if !options.no_changelog {
+        let path = root.join("CHANGELOG.md");
+        let contents = std::fs::read_to_string(&path).ok();
+        std::fs::write(&path, update_changelog(contents.as_deref(), &section))?;
+        terminal.print_custom("Updated", "CHANGELOG.md", Color::Green, true)?;
+        changed.push(path);
+    }
+
+    if !options.no_commit {
+        git::commit_paths(root, &changed, &format!("chore(release): {next}"))?;
+        terminal.print_custom(
+            "Committed",
+            format!("release {next}"),
+            Color::Green,
+            true,
+        )?;
+        if !options.no_tag {
+            let name = format!("v{next}");
+            git::tag_head(root, &name, &format!("Release {next}"))?;
+            terminal.print_custom("Tagged", name, Color::Green, true)?;
+        }
+    } else if !options.no_tag {
+        terminal.print_warning(
+            "the release isn't tagged since it wasn't committed",
+        )?;
+    }
+
+    if !options.no_build {
+        build_project(
+            config,
+            &BuildOptions {
+                values: None,
+                install_options: InstallOptions {
+                    values: options.install_options.values.clone(),
+                },
+                check_contents: true,
+                workspace: false,
+            },
+        )?;
+    }
+
+    if options.publish {
+        publish_project(
+            config,
+            &PublishOptions {
+                values: None,
+                install_options: InstallOptions {
+                    values: options.install_options.values.clone(),
+                },
+                workspace: false,
+                dry_run: false,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use git2::{Repository, Signature};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_release_project() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            test_resources_dir_path().join("mock-project"),
+            dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project").canonicalize().unwrap();
+        let repo = Repository::init(&root).unwrap();
+        let mut git_config = repo.config().unwrap();
+        git_config.set_str("user.name", "huak").unwrap();
+        git_config
+            .set_str("user.email", "huak@example.com")
+            .unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("huak", "huak@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        git::tag_head(&root, "v0.0.1", "Release 0.0.1").unwrap();
+        std::fs::write(root.join("new.py"), "").unwrap();
+        git::commit_paths(&root, &[root.join("new.py")], "feat: add new")
+            .unwrap();
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let options = ReleaseOptions {
+            bump: None,
+            no_changelog: false,
+            no_commit: false,
+            no_tag: false,
+            no_build: true,
+            publish: false,
+            dry_run: false,
+            install_options: InstallOptions { values: None },
+        };
+
+        release_project(&config, &options).unwrap();
+
+        let ws = config.workspace();
+        let metadata = ws.current_local_metadata().unwrap();
+        assert_eq!(
+            metadata.metadata().project_version().unwrap().to_string(),
+            "0.1.0"
+        );
+        let changelog =
+            std::fs::read_to_string(root.join("CHANGELOG.md")).unwrap();
+        assert!(changelog.contains("## [0.1.0] - "));
+        assert!(changelog.contains("- add new"));
+        assert_eq!(git::latest_tag(&root).unwrap().as_deref(), Some("v0.1.0"));
+        assert!(git::commit_messages(&root, Some("v0.1.0"))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,8 +1,21 @@
 use crate::{
-    dependency::dependency_iter, Config, Error, HuakResult, InstallOptions,
+    dependency::dependency_iter,
+    dist_info::{scan_site_packages, DistInfo},
+    package::canonical_package_name,
+    Config, Error, HuakResult, InstallOptions,
+};
+use pep508_rs::Requirement;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
 };
 
 pub struct RemoveOptions {
+    /// Only edit the pyproject.toml, leaving the dependencies installed in the Python
+    /// environment.
+    pub no_sync: bool,
+    /// Uninstall dependencies even if other declared dependencies still require them.
+    pub force: bool,
     pub install_options: InstallOptions,
 }
 
@@ -12,6 +25,8 @@ pub fn remove_project_dependencies(
     options: &RemoveOptions,
 ) -> HuakResult<()> {
     let workspace = config.workspace();
+    let _lock = workspace.lock()?;
+    let _journal = workspace.journal("remove")?;
     let package = workspace.current_package()?;
     let mut metadata = workspace.current_local_metadata()?;
 
@@ -47,14 +62,116 @@ pub fn remove_project_dependencies(
         metadata.write_file()?;
     }
 
+    if options.no_sync {
+        return Ok(());
+    }
+
     // Uninstall the dependencies from the Python environment if an environment is found.
-    match workspace.current_python_environment() {
-        Ok(it) => {
-            it.uninstall_packages(&deps, &options.install_options, config)
+    let python_env = match workspace.current_python_environment() {
+        Ok(it) => it,
+        Err(Error::PythonEnvironmentNotFound) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    // Dependencies that stay installed are only dependencies of other packages now.
+    let removed = deps
+        .iter()
+        .map(|it| it.name().to_string())
+        .collect::<Vec<_>>();
+
+    // Keep the dependencies the remaining declared dependencies still require installed.
+    let mut deps = deps;
+    if !options.force {
+        let mut declared = Vec::new();
+        declared.extend(metadata.metadata().dependencies().unwrap_or_default());
+        for it in metadata.metadata().optional_dependencies().into_iter() {
+            declared.extend(it.values().flatten());
+        }
+        let dists = scan_site_packages(python_env.site_packages_dir_path())?;
+        let required = required_by(&declared, &dists)?;
+        let mut terminal = config.terminal();
+        let mut kept = Vec::new();
+        for dep in deps {
+            let name = canonical_package_name(dep.name())?.to_lowercase();
+            match required.get(&name) {
+                Some(dependents) => {
+                    terminal.print_warning(format!(
+                        "{} is still required by {}, so it won't be uninstalled (use --force to uninstall it anyway)",
+                        dep.name(),
+                        dependents.join(", ")
+                    ))?;
+                }
+                None => kept.push(dep),
+            }
+        }
+        deps = kept;
+    }
+    if !deps.is_empty() {
+        python_env.uninstall_packages(
+            &deps,
+            &options.install_options,
+            config,
+        )?;
+    }
+
+    python_env.set_requested(&removed, false)
+}
+
+/// Get the installed packages required by declared dependencies, directly or through
+/// other installed packages. Packages are keyed by their normalized name, and map to
+/// the names of the declared dependencies requiring them.
+///
+/// Requirements only needed for an extra are followed when the declared dependency
+/// requests that extra.
+fn required_by(
+    declared: &[&Requirement],
+    dists: &[DistInfo],
+) -> HuakResult<HashMap<String, Vec<String>>> {
+    let mut installed = HashMap::new();
+    for dist in dists {
+        installed
+            .insert(canonical_package_name(dist.name())?.to_lowercase(), dist);
+    }
+
+    let mut required: HashMap<String, Vec<String>> = HashMap::new();
+    for root in declared {
+        let root_name = canonical_package_name(&root.name)?.to_lowercase();
+        let mut visited = HashSet::from([root_name.clone()]);
+        let mut stack =
+            vec![(root_name, root.extras.clone().unwrap_or_default())];
+        while let Some((name, extras)) = stack.pop() {
+            let Some(dist) = installed.get(&name) else {
+                continue;
+            };
+            for it in dist.requires_dist() {
+                let Ok(requirement) = Requirement::from_str(it) else {
+                    continue;
+                };
+                if let Some(marker) = requirement.marker.as_ref() {
+                    let marker = marker.to_string();
+                    if marker.contains("extra")
+                        && !extras.iter().any(|extra| {
+                            marker.contains(&format!("'{extra}'"))
+                                || marker.contains(&format!("\"{extra}\""))
+                        })
+                    {
+                        continue;
+                    }
+                }
+                let name =
+                    canonical_package_name(&requirement.name)?.to_lowercase();
+                if visited.insert(name.clone()) {
+                    required
+                        .entry(name.clone())
+                        .or_default()
+                        .push(root.name.clone());
+                    stack.push((name, requirement.extras.unwrap_or_default()));
+                }
+            }
         }
-        Err(Error::PythonEnvironmentNotFound) => Ok(()),
-        Err(e) => Err(e),
     }
+
+    Ok(required)
 }
 
 #[cfg(test)]
@@ -67,9 +184,39 @@ mod tests {
         package::Package,
         test_resources_dir_path, Verbosity,
     };
-    use std::str::FromStr;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_required_by() {
+        let dir = tempdir().unwrap();
+        for (name, requires) in [
+            ("black", "Requires-Dist: click>=8.0.0\nRequires-Dist: aiohttp>=3.7.4; extra == \"d\"\n"),
+            ("click", "Requires-Dist: colorama; platform_system == \"Windows\"\n"),
+            ("aiohttp", ""),
+            ("colorama", ""),
+        ] {
+            let path = dir.path().join(format!("{name}-1.0.0.dist-info"));
+            std::fs::create_dir(&path).unwrap();
+            std::fs::write(
+                path.join("METADATA"),
+                format!("Metadata-Version: 2.1\nName: {name}\nVersion: 1.0.0\n{requires}"),
+            )
+            .unwrap();
+        }
+        let dists = scan_site_packages(dir.path()).unwrap();
+        let black = Requirement::from_str("black").unwrap();
+        let black_d = Requirement::from_str("black[d]").unwrap();
+
+        let required = required_by(&[&black], &dists).unwrap();
+
+        assert_eq!(required["click"], ["black"]);
+        assert_eq!(required["colorama"], ["black"]);
+        assert!(!required.contains_key("black"));
+        assert!(!required.contains_key("aiohttp"));
+        let required = required_by(&[&black_d], &dists).unwrap();
+        assert_eq!(required["aiohttp"], ["black"]);
+    }
+
     #[test]
     fn test_remove_project_dependencies() {
         let dir = tempdir().unwrap();
@@ -82,6 +229,8 @@ mod tests {
         let cwd = root.to_path_buf();
         let config = test_config(&root, &cwd, Verbosity::Quiet);
         let options = RemoveOptions {
+            no_sync: false,
+            force: false,
             install_options: InstallOptions { values: None },
         };
         let ws = config.workspace();
@@ -123,6 +272,8 @@ mod tests {
         let cwd = root.to_path_buf();
         let config = test_config(&root, &cwd, Verbosity::Quiet);
         let options = RemoveOptions {
+            no_sync: false,
+            force: false,
             install_options: InstallOptions { values: None },
         };
         let ws = config.workspace();
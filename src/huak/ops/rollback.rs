@@ -0,0 +1,137 @@
+use crate::{
+    journal::journal_entries, Config, Error, HuakResult, InstallOptions,
+};
+use termcolor::Color;
+
+pub struct RollbackOptions {
+    pub install_options: InstallOptions,
+}
+
+/// Roll back the last operation recorded in the workspace's history. The metadata file
+/// is restored to its contents before the operation, packages it installed are removed,
+/// and packages it removed or changed are reinstalled at their previous versions.
+pub fn rollback_operation(
+    config: &Config,
+    options: &RollbackOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let _lock = workspace.lock()?;
+    let mut terminal = config.terminal();
+
+    let Some(entry) = journal_entries(workspace.root())?.pop() else {
+        return Err(Error::HuakConfigurationError(
+            "there's nothing to roll back".to_string(),
+        ));
+    };
+
+    let (uninstall, install): (Vec<_>, Vec<_>) =
+        entry.packages.iter().partition(|it| it.before.is_none());
+    let mut summary = Vec::new();
+    if let Some(path) = entry.metadata_file.as_ref() {
+        summary.push(format!("restore {}", path.display()));
+    }
+    if !install.is_empty() {
+        summary.push(format!("reinstall {} package(s)", install.len()));
+    }
+    if !uninstall.is_empty() {
+        summary.push(format!("remove {} package(s)", uninstall.len()));
+    }
+    let prompt = format!(
+        "roll back the last {} ({})",
+        entry.operation,
+        summary.join(", ")
+    );
+    if !terminal.confirm(prompt)? {
+        return Ok(());
+    }
+
+    if let (Some(path), Some(contents)) =
+        (entry.metadata_file.as_ref(), entry.metadata.as_ref())
+    {
+        std::fs::write(workspace.root().join(path), contents)?;
+    }
+
+    if !entry.packages.is_empty() {
+        let python_env = workspace.resolve_python_environment()?;
+        if !uninstall.is_empty() {
+            let packages = uninstall
+                .iter()
+                .map(|it| it.name.as_str())
+                .collect::<Vec<_>>();
+            python_env.uninstall_packages(
+                &packages,
+                &options.install_options,
+                config,
+            )?;
+        }
+        if !install.is_empty() {
+            let packages = install
+                .iter()
+                .filter_map(|it| {
+                    it.before
+                        .as_ref()
+                        .map(|version| format!("{}=={version}", it.name))
+                })
+                .collect::<Vec<_>>();
+            // The previous versions are known exactly, so their dependencies are
+            // rolled back with them rather than resolved again.
+            let mut install_options = InstallOptions {
+                values: options.install_options.values.clone(),
+            };
+            install_options
+                .values
+                .get_or_insert_with(Vec::new)
+                .push("--no-deps".to_string());
+            python_env.install_packages(&packages, &install_options, config)?;
+        }
+    }
+
+    std::fs::remove_file(entry.path())?;
+
+    terminal.print_custom(
+        "Rolled back",
+        format!(
+            "{} ({} package(s) changed)",
+            entry.operation,
+            entry.packages.len()
+        ),
+        Color::Green,
+        true,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rollback_operation() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(&root, &cwd, Verbosity::Quiet);
+        let ws = config.workspace();
+        let path = ws.current_local_metadata().unwrap().path().clone();
+        let original = std::fs::read_to_string(&path).unwrap();
+        {
+            let _journal = ws.journal("add").unwrap();
+            std::fs::write(&path, format!("{original}\n# changed\n")).unwrap();
+        }
+        let options = RollbackOptions {
+            install_options: InstallOptions { values: None },
+        };
+
+        rollback_operation(&config, &options).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+        assert!(journal_entries(ws.root()).unwrap().is_empty());
+        assert!(rollback_operation(&config, &options).is_err());
+    }
+}
@@ -1,21 +1,430 @@
-use super::make_venv_command;
-use crate::{sys, Config, HuakResult};
-use std::{env::consts::OS, process::Command};
+use super::{cached_environment, make_venv_command, op_env_vars};
+use crate::{
+    dependency::Dependency,
+    environment::env_path_values,
+    metadata::Metadata,
+    sys::{self, Table},
+    version::Version,
+    Config, Error, HuakResult, InstallOptions,
+};
+use glob::Pattern;
+use pep440_rs::VersionSpecifiers;
+use serde::Deserialize;
+use std::{
+    env::consts::OS,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+use termcolor::Color;
+
+/// The scripts directory used when `scripts-dir` isn't set in `[tool.huak]`.
+const DEFAULT_SCRIPTS_DIR: &str = "scripts";
+
+pub struct ScriptOptions {
+    /// The name of the script in the project's scripts directory, with or without its
+    /// `.py` extension.
+    pub script: String,
+    /// A values vector of arguments passed on to the script.
+    pub values: Vec<String>,
+    pub install_options: InstallOptions,
+}
 
 pub fn run_command_str(command: &str, config: &Config) -> HuakResult<()> {
     let workspace = config.workspace();
-    let python_env = workspace.current_python_environment()?;
+    // Projects without a pyproject.toml have no policy or variables to apply.
+    let metadata = workspace.current_local_metadata().ok();
+    if let Some(it) = metadata.as_ref() {
+        RunPolicy::from_metadata(it.metadata())?.check(command)?;
+    }
+    let python_env = workspace.resolve_python_environment()?;
 
     let mut cmd = Command::new(sys::shell_name()?);
     let flag = match OS {
         "windows" => "/C",
         _ => "-c",
     };
-    make_venv_command(&mut cmd, &python_env)?;
+    make_venv_command(&mut cmd, &python_env, config)?;
+    if let Some(it) = metadata.as_ref() {
+        cmd.envs(op_env_vars(it, "run")?);
+    }
     cmd.args([flag, command]).current_dir(&config.cwd);
     config.terminal().run_command(&mut cmd)
 }
 
+/// Run a command, or the script in the project's scripts directory named by the command's
+/// first word (see `run_script`). A script named like a command in the project's
+/// environment or on `PATH` doesn't shadow it: the command is run, with a warning.
+pub fn run_command(command: &[String], config: &Config) -> HuakResult<()> {
+    if let Some((name, args)) = command.split_first() {
+        let mut paths = env_path_values().unwrap_or_default();
+        if let Ok(it) = config.workspace().current_python_environment() {
+            paths.insert(0, it.executables_dir_path().clone());
+        }
+        if command_script(name, &paths, config)?.is_some() {
+            let options = ScriptOptions {
+                script: name.clone(),
+                values: args.to_vec(),
+                install_options: InstallOptions { values: None },
+            };
+            return run_script(config, &options);
+        }
+    }
+
+    run_command_str(&command.join(" "), config)
+}
+
+/// Get the script `huak run` runs for a command named `name`, unless a command with the
+/// same name is in one of the `paths` directories. Shadowing scripts are warned about.
+fn command_script(
+    name: &str,
+    paths: &[PathBuf],
+    config: &Config,
+) -> HuakResult<Option<PathBuf>> {
+    let Some(path) = find_script(name, config)? else {
+        return Ok(None);
+    };
+    let file_name = match cfg!(windows) {
+        true => format!("{name}.exe"),
+        false => name.to_string(),
+    };
+    if !paths.iter().any(|it| it.join(&file_name).is_file()) {
+        return Ok(Some(path));
+    }
+
+    config.terminal().print_warning(format!(
+        "{} has the same name as the {name} command, so the command is run; use `huak run --script {name}` to run the script",
+        path.display()
+    ))?;
+    Ok(None)
+}
+
+/// Find a script in the project's scripts directory (`scripts-dir` in `[tool.huak]`) by
+/// its name, with or without its `.py` extension.
+fn find_script(name: &str, config: &Config) -> HuakResult<Option<PathBuf>> {
+    let dir = scripts_dir(config)?;
+    Ok(script_paths(&dir)?.into_iter().find(|it| {
+        it.file_name().map_or(false, |it| it == name)
+            || it.file_stem().map_or(false, |it| it == name)
+    }))
+}
+
+/// Run a standalone script from a cached environment with the dependencies declared in
+/// its PEP 723 `# /// script` block. The environment is created with an interpreter
+/// satisfying the block's `requires-python`, preferring the one the workspace uses, and
+/// reused until the script's requirements or the Python version change.
+pub fn run_script(config: &Config, options: &ScriptOptions) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let Some(path) = find_script(&options.script, config)? else {
+        return Err(Error::HuakConfigurationError(format!(
+            "there's no script named {} in {}",
+            options.script,
+            scripts_dir(config)?.display()
+        )));
+    };
+    // Scripts are subject to the same `RunPolicy` as commands.
+    let metadata = workspace.current_local_metadata().ok();
+    if let Some(it) = metadata.as_ref() {
+        let command = std::iter::once(&options.script)
+            .chain(options.values.iter())
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        RunPolicy::from_metadata(it.metadata())?.check(&command)?;
+    }
+    let script = read_script_metadata(&path)?;
+    let dependencies = script
+        .dependencies
+        .iter()
+        .map(|it| Dependency::from_str(it))
+        .collect::<HuakResult<Vec<_>>>()?;
+
+    let env = workspace.environment();
+    let interpreters = env.interpreters().interpreters();
+    let selected = workspace.select_python_interpreter(interpreters);
+    let interpreter = match script.requires_python.as_deref() {
+        Some(it) => {
+            let specifiers = VersionSpecifiers::from_str(it).map_err(|_| {
+                Error::HuakConfigurationError(format!(
+                    "{} has an invalid requires-python {it:?}",
+                    path.display()
+                ))
+            })?;
+            let satisfies = |version: &Version| {
+                pep440_rs::Version::from_str(&version.to_string())
+                    .map_or(false, |it| specifiers.contains(&it))
+            };
+            match selected {
+                Ok((it, _)) if satisfies(it.version()) => it,
+                _ => interpreters
+                    .iter()
+                    .filter(|it| satisfies(it.version()))
+                    .max()
+                    .cloned()
+                    .ok_or(Error::HuakConfigurationError(format!(
+                        "no Python interpreter found for requires-python {specifiers} of {}",
+                        path.display()
+                    )))?,
+            }
+        }
+        None => selected?.0,
+    };
+
+    let name = path
+        .file_stem()
+        .map(|it| it.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let script_env = cached_environment(
+        "scripts",
+        &name,
+        &dependencies,
+        &interpreter,
+        &options.install_options,
+        config,
+    )?;
+    let mut cmd = Command::new(script_env.python_path());
+    make_venv_command(&mut cmd, &script_env, config)?;
+    if let Some(it) = metadata.as_ref() {
+        cmd.envs(op_env_vars(it, "run")?);
+    }
+    cmd.arg(path).args(&options.values).current_dir(&config.cwd);
+    config.terminal().run_command(&mut cmd)
+}
+
+/// Print the scripts in the project's scripts directory with the Python versions and
+/// dependencies their PEP 723 `# /// script` blocks declare.
+pub fn list_scripts(config: &Config) -> HuakResult<()> {
+    let mut terminal = config.terminal();
+    let dir = scripts_dir(config)?;
+    let paths = script_paths(&dir)?;
+    if paths.is_empty() {
+        return terminal.print_custom(
+            "Scripts",
+            format!("there are no scripts in {}", dir.display()),
+            Color::Green,
+            true,
+        );
+    }
+
+    let mut table = Table::new(["script", "requires-python", "dependencies"]);
+    for path in paths.iter() {
+        let script = read_script_metadata(path)?;
+        table.push_row([
+            path.file_stem()
+                .map(|it| it.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            script.requires_python.unwrap_or_else(|| "-".to_string()),
+            match script.dependencies.is_empty() {
+                true => "-".to_string(),
+                false => script.dependencies.join(", "),
+            },
+        ]);
+    }
+    table.set_summary(
+        "Found",
+        format!("{} script(s) in {}", paths.len(), dir.display()),
+    );
+
+    terminal.print_table(&table)
+}
+
+/// Get the project's scripts directory, configured with `scripts-dir` in `[tool.huak]`
+/// relative to the workspace root.
+fn scripts_dir(config: &Config) -> HuakResult<PathBuf> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata().ok();
+    let value = metadata.as_ref().and_then(|it| {
+        it.metadata()
+            .tool_table("huak")?
+            .get("scripts-dir")
+            .cloned()
+    });
+    let dir = match value {
+        Some(it) => it.as_str().map(str::to_string).ok_or(
+            Error::HuakConfigurationError(
+                "`scripts-dir` in [tool.huak] must be a path".to_string(),
+            ),
+        )?,
+        None => DEFAULT_SCRIPTS_DIR.to_string(),
+    };
+
+    Ok(workspace.root().join(dir))
+}
+
+/// Get the Python scripts directly in a directory, sorted by name.
+fn script_paths(dir: &Path) -> HuakResult<Vec<PathBuf>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut paths = entries
+        .flatten()
+        .map(|it| it.path())
+        .filter(|it| {
+            it.is_file() && it.extension().map_or(false, |it| it == "py")
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths)
+}
+
+#[derive(Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// The metadata of a script's PEP 723 `# /// script` block.
+///
+/// ```python
+/// # /// script
+/// # requires-python = ">=3.11"
+/// # dependencies = ["httpx"]
+/// # ///
+/// ```
+struct ScriptMetadata {
+    #[serde(default)]
+    dependencies: Vec<String>,
+    #[serde(default)]
+    requires_python: Option<String>,
+}
+
+/// Read the PEP 723 `# /// script` block of a script file. Scripts without one have no
+/// dependencies.
+fn read_script_metadata(path: &Path) -> HuakResult<ScriptMetadata> {
+    let contents = std::fs::read_to_string(path)?;
+    let metadata = script_metadata(&contents).map_err(|e| {
+        Error::HuakConfigurationError(format!("{}: {e}", path.display()))
+    })?;
+
+    Ok(metadata.unwrap_or_default())
+}
+
+/// Parse the PEP 723 `# /// script` block of a script's contents, or `None` if it doesn't
+/// have one. Each line of the block is a comment whose content is TOML.
+fn script_metadata(contents: &str) -> Result<Option<ScriptMetadata>, String> {
+    let mut lines = contents.lines();
+    if !lines.any(|it| it.trim_end() == "# /// script") {
+        return Ok(None);
+    }
+
+    let mut block = String::new();
+    for line in lines {
+        if line.trim_end() == "# ///" {
+            return toml::from_str(&block).map(Some).map_err(|e| {
+                format!("the `# /// script` block isn't valid: {e}")
+            });
+        }
+        let Some(it) = line.strip_prefix('#') else {
+            break;
+        };
+        block.push_str(it.strip_prefix(' ').unwrap_or(it));
+        block.push('\n');
+    }
+
+    Err("the `# /// script` block isn't closed with `# ///`".to_string())
+}
+
+/// The commands `huak run` may execute, configured with `[tool.huak.run] allowed` and
+/// `[tool.huak.run] denied` glob patterns.
+///
+/// Patterns are matched against the whole command and each command of a pipeline or
+/// list (split on `|`, `;`, `&&`, etc.), with whitespace collapsed. A command is denied
+/// if any part of it matches a denied pattern, and when allowed patterns are configured
+/// every part of it must match one of them.
+#[derive(Default)]
+struct RunPolicy {
+    allowed: Vec<Pattern>,
+    denied: Vec<Pattern>,
+}
+
+impl RunPolicy {
+    fn from_metadata(metadata: &Metadata) -> HuakResult<RunPolicy> {
+        let patterns = |key: &str| -> HuakResult<Vec<Pattern>> {
+            let Some(value) = metadata.huak_setting("run", key) else {
+                return Ok(Vec::new());
+            };
+            let invalid = || {
+                Error::HuakConfigurationError(format!(
+                    "[tool.huak.run] {key} must be a list of command patterns"
+                ))
+            };
+            value
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|it| {
+                    Ok(Pattern::new(&normalize_command(
+                        it.as_str().ok_or_else(invalid)?,
+                    ))?)
+                })
+                .collect()
+        };
+
+        Ok(RunPolicy {
+            allowed: patterns("allowed")?,
+            denied: patterns("denied")?,
+        })
+    }
+
+    /// Check that the policy permits a command to run.
+    fn check(&self, command: &str) -> HuakResult<()> {
+        let command = normalize_command(command);
+        let parts = command_parts(&command);
+
+        for it in std::iter::once(command.as_str()).chain(parts.iter().copied())
+        {
+            if let Some(pattern) = self.denied.iter().find(|p| p.matches(it)) {
+                return Err(Error::CommandDenied(format!(
+                    "{it:?} matches denied pattern {:?}",
+                    pattern.as_str()
+                )));
+            }
+        }
+        if self.allowed.is_empty() {
+            return Ok(());
+        }
+        for it in parts {
+            if !self.allowed.iter().any(|p| p.matches(it)) {
+                return Err(Error::CommandDenied(format!(
+                    "{it:?} doesn't match any allowed pattern"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn normalize_command(command: &str) -> String {
+    command.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Split a command on the shell's pipeline and list operators (`|`, `||`, `&&`, `&`, `;`,
+/// and newlines). An `&` in a redirection like `2>&1` or `&>out.log` isn't an operator.
+fn command_parts(command: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c) in command.char_indices() {
+        let is_operator = match c {
+            '|' | ';' | '\n' => true,
+            '&' => {
+                let previous = command[..i].chars().next_back();
+                let next = command[i + 1..].chars().next();
+                !matches!(previous, Some('>' | '<')) && next != Some('>')
+            }
+            _ => false,
+        };
+        if is_operator {
+            parts.push(&command[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&command[start..]);
+
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|it| !it.is_empty())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -25,6 +434,168 @@ mod tests {
     };
     use tempfile::tempdir;
 
+    #[test]
+    fn test_run_policy() {
+        let metadata: Metadata = toml::from_str(
+            r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "mock-project"
+
+[tool.huak.run]
+allowed = ["pytest*", "python -m *", "curl *", "sh"]
+denied = ["curl * | sh", "*rm -rf*"]
+"#,
+        )
+        .unwrap();
+        let policy = RunPolicy::from_metadata(&metadata).unwrap();
+
+        assert!(policy.check("pytest  tests/").is_ok());
+        assert!(policy.check("python -m pip list").is_ok());
+        assert!(policy.check("curl https://example.com").is_ok());
+        assert!(policy.check("curl -sSf https://example.com | sh").is_err());
+        assert!(policy.check("pytest && rm -rf /").is_err());
+        assert!(policy.check("pytest; black .").is_err());
+        assert!(policy.check("black .").is_err());
+        assert!(RunPolicy::default().check("black .").is_ok());
+        assert!(policy.check("pytest 2>&1").is_ok());
+        assert!(policy.check("pytest &> pytest.log").is_ok());
+        assert!(policy.check("pytest && python -m pip list").is_ok());
+        assert!(policy.check("pytest && black .").is_err());
+        assert!(policy.check("pytest & black .").is_err());
+    }
+
+    #[test]
+    fn test_command_parts() {
+        assert_eq!(command_parts("pytest 2>&1"), ["pytest 2>&1"]);
+        assert_eq!(command_parts("a && b || c"), ["a", "b", "c"]);
+        assert_eq!(command_parts("a |& b; c\nd &"), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_script_metadata() {
+        let script = r#"#!/usr/bin/env python
+# /// script
+# requires-python = ">=3.11"
+# dependencies = [
+#   "httpx",
+#   "rich>=13",
+# ]
+# ///
+import httpx
+"#;
+
+        assert_eq!(
+            script_metadata(script).unwrap(),
+            Some(ScriptMetadata {
+                dependencies: vec!["httpx".to_string(), "rich>=13".to_string()],
+                requires_python: Some(">=3.11".to_string()),
+            })
+        );
+        assert_eq!(script_metadata("import httpx\n").unwrap(), None);
+        assert!(script_metadata("# /// script\n# dependencies = []\n").is_err());
+    }
+
+    #[test]
+    fn test_find_script() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let scripts = root.join(DEFAULT_SCRIPTS_DIR);
+        std::fs::create_dir_all(&scripts).unwrap();
+        std::fs::write(scripts.join("deploy.py"), "print('deploy')").unwrap();
+        std::fs::write(scripts.join("README.md"), "").unwrap();
+        let config = test_config(&root, &root, Verbosity::Quiet);
+
+        assert_eq!(
+            find_script("deploy", &config).unwrap(),
+            Some(scripts.join("deploy.py"))
+        );
+        assert_eq!(
+            find_script("deploy.py", &config).unwrap(),
+            Some(scripts.join("deploy.py"))
+        );
+        assert_eq!(find_script("README", &config).unwrap(), None);
+        assert_eq!(find_script("pytest", &config).unwrap(), None);
+    }
+
+    #[test]
+    fn test_run_script_policy() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let pyproject = root.join("pyproject.toml");
+        let contents = std::fs::read_to_string(&pyproject).unwrap();
+        std::fs::write(
+            &pyproject,
+            format!("{contents}\n[tool.huak.run]\ndenied = [\"deploy *\"]\n"),
+        )
+        .unwrap();
+        let scripts = root.join(DEFAULT_SCRIPTS_DIR);
+        std::fs::create_dir_all(&scripts).unwrap();
+        std::fs::write(scripts.join("deploy.py"), "print('deploy')").unwrap();
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let options = ScriptOptions {
+            script: "deploy".to_string(),
+            values: vec!["--env".to_string(), "prod".to_string()],
+            install_options: InstallOptions { values: None },
+        };
+
+        assert!(matches!(
+            run_script(&config, &options),
+            Err(Error::CommandDenied(_))
+        ));
+        let options = ScriptOptions {
+            script: "missing".to_string(),
+            ..options
+        };
+        assert!(matches!(
+            run_script(&config, &options),
+            Err(Error::HuakConfigurationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_command_script() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let scripts = root.join(DEFAULT_SCRIPTS_DIR);
+        std::fs::create_dir_all(&scripts).unwrap();
+        std::fs::write(scripts.join("pytest.py"), "").unwrap();
+        std::fs::write(scripts.join("deploy.py"), "").unwrap();
+        let bin = dir.path().join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        let file_name = match cfg!(windows) {
+            true => "pytest.exe",
+            false => "pytest",
+        };
+        std::fs::write(bin.join(file_name), "").unwrap();
+        let config = test_config(&root, &root, Verbosity::Quiet);
+        let paths = [bin];
+
+        assert_eq!(command_script("pytest", &paths, &config).unwrap(), None);
+        assert_eq!(
+            command_script("deploy", &paths, &config).unwrap(),
+            Some(scripts.join("deploy.py"))
+        );
+        assert_eq!(command_script("black", &paths, &config).unwrap(), None);
+    }
+
     #[test]
     fn test_run_command_str() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,292 @@
+use super::{exclude_patterns, is_excluded, make_venv_command, op_env_vars};
+use crate::{
+    metadata::Metadata, sys::SubprocessError, watch::FileWatcher, Config,
+    Error, HuakResult,
+};
+use std::{
+    path::PathBuf,
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+use termcolor::Color;
+
+/// How often watched files and the served process are polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a served process is given to exit after it's asked to stop before it's
+/// killed.
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+pub struct ServeOptions {
+    /// Restart the target when the project's files change, overriding
+    /// `[tool.huak.serve] watch`.
+    pub watch: bool,
+    /// A values vector of arguments passed on to the target after `[tool.huak.serve]
+    /// args`.
+    pub values: Option<Vec<String>>,
+}
+
+/// Run the project's long-running entry point, such as a dev server, in its environment.
+/// The target is configured with `[tool.huak.serve] target` and defaults to the project's
+/// only script. With `--watch` (or `[tool.huak.serve] watch`) the target is restarted
+/// whenever a watched file changes.
+///
+/// ```toml
+/// [tool.huak.serve]
+/// target = "my_app.main:main"
+/// args = ["--port", "8000"]
+/// watch = true
+/// paths = ["src", "config"]
+/// ```
+pub fn serve_project(
+    config: &Config,
+    options: &ServeOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+    let settings = ServeSettings::from_metadata(metadata.metadata())?;
+
+    let mut cmd = Command::new(python_env.python_path());
+    make_venv_command(&mut cmd, &python_env, config)?;
+    cmd.envs(op_env_vars(&metadata, "serve")?)
+        .args(target_args(&settings.target))
+        .args(&settings.args)
+        .args(options.values.iter().flatten())
+        .current_dir(&config.cwd);
+
+    let mut terminal = config.terminal();
+    if !(options.watch || settings.watch) {
+        let status = terminal.spawn_command(&mut cmd)?.wait()?;
+        if !status.success() {
+            return Err(Error::SubprocessFailure(SubprocessError::new(status)));
+        }
+        return Ok(());
+    }
+
+    let root = workspace.root().clone();
+    let exclude = exclude_patterns(&metadata)?;
+    let paths = match settings.paths.is_empty() {
+        true => vec![root.clone()],
+        false => settings.paths.iter().map(|it| root.join(it)).collect(),
+    };
+    let mut watcher = FileWatcher::new(paths, move |it| {
+        it.strip_prefix(&root)
+            .map_or(false, |it| is_excluded(it, &exclude))
+    })?;
+
+    terminal.print_custom("Serving", &settings.target, Color::Green, true)?;
+    let mut child = Some(terminal.spawn_command(&mut cmd)?);
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        if let Some(status) =
+            child.as_mut().map(Child::try_wait).transpose()?.flatten()
+        {
+            child = None;
+            terminal.print_custom(
+                "Exited",
+                format!("{status}, waiting for changes"),
+                Color::Yellow,
+                true,
+            )?;
+        }
+
+        let changes = watcher.changes()?;
+        let Some(changed) = changes.first() else {
+            continue;
+        };
+        if let Some(it) = child.take() {
+            stop(it)?;
+        }
+        let changed = changed
+            .strip_prefix(workspace.root())
+            .unwrap_or(changed)
+            .display()
+            .to_string();
+        let message = match changes.len() {
+            1 => format!("{changed} changed"),
+            n => format!("{changed} and {} other file(s) changed", n - 1),
+        };
+        terminal.print_custom("Restarting", message, Color::Cyan, true)?;
+        child = Some(terminal.spawn_command(&mut cmd)?);
+    }
+}
+
+/// The `[tool.huak.serve]` settings.
+struct ServeSettings {
+    target: String,
+    args: Vec<String>,
+    watch: bool,
+    paths: Vec<PathBuf>,
+}
+
+impl ServeSettings {
+    fn from_metadata(metadata: &Metadata) -> HuakResult<ServeSettings> {
+        let setting = |key: &str| metadata.huak_setting("serve", key);
+        let strings = |key: &str| -> HuakResult<Vec<String>> {
+            let Some(value) = setting(key) else {
+                return Ok(Vec::new());
+            };
+            value
+                .as_array()
+                .and_then(|it| {
+                    it.iter()
+                        .map(|it| it.as_str().map(str::to_string))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| {
+                    Error::HuakConfigurationError(format!(
+                        "[tool.huak.serve] {key} must be a list of strings"
+                    ))
+                })
+        };
+
+        let target = match setting("target") {
+            Some(it) => it.as_str().map(str::to_string).ok_or_else(|| {
+                Error::HuakConfigurationError(
+                    "[tool.huak.serve] target must be a string".to_string(),
+                )
+            })?,
+            None => default_target(metadata)?,
+        };
+        let watch = match setting("watch") {
+            Some(it) => it.as_bool().ok_or_else(|| {
+                Error::HuakConfigurationError(
+                    "[tool.huak.serve] watch must be a boolean".to_string(),
+                )
+            })?,
+            None => false,
+        };
+
+        Ok(ServeSettings {
+            target,
+            args: strings("args")?,
+            watch,
+            paths: strings("paths")?.into_iter().map(PathBuf::from).collect(),
+        })
+    }
+}
+
+/// Get the target served when `[tool.huak.serve] target` isn't set: the entry point of
+/// the project's only script, like the one `huak new --app` creates.
+fn default_target(metadata: &Metadata) -> HuakResult<String> {
+    match metadata.scripts().map(|it| it.values().collect::<Vec<_>>()) {
+        Some(it) if it.len() == 1 => Ok(it[0].clone()),
+        _ => Err(Error::HuakConfigurationError(
+            "set [tool.huak.serve] target to the entry point to serve"
+                .to_string(),
+        )),
+    }
+}
+
+/// Get the Python arguments that run a target: an entry point like "my_app.main:main"
+/// is imported and called, and anything else is run as a module like "my_app" or
+/// "http.server".
+fn target_args(target: &str) -> Vec<String> {
+    let Some((module, attr)) = target.split_once(':') else {
+        return vec!["-m".to_string(), target.trim().to_string()];
+    };
+    let module = module.trim();
+    let attr = attr.trim();
+    // Arguments after the script are left in sys.argv for the entry point to parse.
+    let script = format!(
+        "import sys; sys.argv[0] = {target:?}; import {module} as m; sys.exit(m.{attr}())",
+        target = target.trim()
+    );
+
+    vec!["-c".to_string(), script]
+}
+
+/// Ask a served process to exit, killing it if it hasn't exited after the
+/// `GRACE_PERIOD`. Unix processes are sent SIGTERM so that servers can shut down
+/// gracefully.
+fn stop(mut child: Child) -> HuakResult<()> {
+    if child.try_wait()?.is_some() {
+        return Ok(());
+    }
+
+    if cfg!(unix) {
+        Command::new("kill")
+            .args(["-TERM", &child.id().to_string()])
+            .status()?;
+        let start = Instant::now();
+        while start.elapsed() < GRACE_PERIOD {
+            if child.try_wait()?.is_some() {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+    // The process may have exited since it was last checked.
+    child.kill().ok();
+    child.wait()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::LocalMetadata;
+    use std::process::Stdio;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_serve_settings() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("pyproject.toml");
+        std::fs::write(
+            &path,
+            r#"[project]
+name = "my-app"
+version = "0.0.1"
+
+[project.scripts]
+my-app = "my_app.main:main"
+
+[tool.huak.serve]
+args = ["--port", "8000"]
+watch = true
+"#,
+        )
+        .unwrap();
+        let metadata = LocalMetadata::new(&path).unwrap();
+
+        let settings =
+            ServeSettings::from_metadata(metadata.metadata()).unwrap();
+
+        assert_eq!(settings.target, "my_app.main:main");
+        assert_eq!(settings.args, ["--port", "8000"]);
+        assert!(settings.watch);
+        assert!(settings.paths.is_empty());
+    }
+
+    #[test]
+    fn test_target_args() {
+        assert_eq!(target_args("http.server"), ["-m", "http.server"]);
+        assert_eq!(
+            target_args("my_app.main:main"),
+            [
+                "-c",
+                "import sys; sys.argv[0] = \"my_app.main:main\"; import my_app.main as m; sys.exit(m.main())"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stop() {
+        let child = Command::new(if cfg!(windows) { "ping" } else { "sleep" })
+            .args(if cfg!(windows) {
+                ["-n", "30", "127.0.0.1"].as_slice()
+            } else {
+                ["30"].as_slice()
+            })
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let start = Instant::now();
+
+        stop(child).unwrap();
+
+        assert!(start.elapsed() < GRACE_PERIOD);
+    }
+}
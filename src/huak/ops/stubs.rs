@@ -0,0 +1,268 @@
+use super::{install_tool, make_venv_command, op_env_vars};
+use crate::{
+    package::importable_package_name, Config, Error, HuakResult, InstallOptions,
+};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+use termcolor::Color;
+
+pub struct StubsOptions {
+    /// A values vector of stubgen options typically used for passing on arguments.
+    pub values: Option<Vec<String>>,
+    /// The directory to write stubs to. Defaults to "stubs", or "<name>-stubs" for a
+    /// companion package.
+    pub output: Option<PathBuf>,
+    /// Generate a PEP 561 companion stubs package with its own pyproject.toml.
+    pub package: bool,
+    pub install_options: InstallOptions,
+}
+
+/// Generate type stubs for the project's package with `mypy`'s `stubgen`. Existing stubs
+/// are replaced so that stubs for removed modules don't linger.
+pub fn generate_project_stubs(
+    config: &Config,
+    options: &StubsOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let mut metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+
+    // Install `mypy` (which provides `stubgen`) if it isn't already installed.
+    install_tool(
+        "mypy",
+        &python_env,
+        &mut metadata,
+        &options.install_options,
+        config,
+    )?;
+
+    let name = metadata.metadata().project_name().to_string();
+    let importable_name = importable_package_name(&name)?;
+    let output = workspace.root().join(match options.output.as_ref() {
+        Some(it) => it.clone(),
+        None if options.package => PathBuf::from(format!("{name}-stubs")),
+        None => PathBuf::from("stubs"),
+    });
+    // A companion package's stubs live in "<package>-stubs" (see PEP 561).
+    let stubs_dir = match options.package {
+        true => output.join(format!("{importable_name}-stubs")),
+        false => output.join(&importable_name),
+    };
+    let stubgen_dir = match options.package {
+        true => output.join(".stubgen"),
+        false => output.clone(),
+    };
+    // Single-module projects get a `<module>.pyi` stub instead of a directory.
+    let stubs_module = output.join(format!("{importable_name}.pyi"));
+    for it in stale_stubs(&output, &importable_name, options.package) {
+        if it.is_dir() {
+            std::fs::remove_dir_all(it)?;
+        } else if it.exists() {
+            std::fs::remove_file(it)?;
+        }
+    }
+
+    // Run `stubgen` with the package directory added to the command's `PYTHONPATH`.
+    let mut cmd = Command::new(python_env.python_path());
+    make_venv_command(&mut cmd, &python_env, config)?;
+    cmd.envs(op_env_vars(&metadata, "stubs")?);
+    let python_path = if workspace.root().join("src").exists() {
+        workspace.root().join("src")
+    } else {
+        workspace.root().to_path_buf()
+    };
+    cmd.args(["-m", "mypy.stubgen", "-p", &importable_name, "-o"])
+        .arg(&stubgen_dir)
+        .args(options.values.iter().flatten())
+        .env("PYTHONPATH", python_path)
+        .current_dir(workspace.root());
+    let mut terminal = config.terminal();
+    terminal.run_command(&mut cmd)?;
+
+    let generated = if options.package {
+        move_package_stubs(&stubgen_dir, &importable_name, &stubs_dir)?;
+        stubs_dir
+    } else if stubs_dir.exists() {
+        stubs_dir
+    } else {
+        stubs_module
+    };
+
+    if options.package {
+        let pyproject_path = output.join("pyproject.toml");
+        if !pyproject_path.exists() {
+            let version = metadata
+                .metadata()
+                .project_version()
+                .map(|it| it.to_string())
+                .unwrap_or_else(|| "0.0.1".to_string());
+            std::fs::write(
+                pyproject_path,
+                stubs_pyproject_toml_contents(
+                    &name,
+                    &importable_name,
+                    &version,
+                ),
+            )?;
+        }
+    }
+
+    terminal.print_custom(
+        "Generated",
+        format!(
+            "stubs for {importable_name} in {}",
+            generated
+                .strip_prefix(workspace.root())
+                .unwrap_or(&generated)
+                .display()
+        ),
+        Color::Green,
+        true,
+    )
+}
+
+/// Get the paths of the stubs a previous run generated in `output`, which are removed so
+/// that stubs for removed modules don't linger. A companion package's staging directory
+/// is included since stubs left there by an interrupted run would be packaged too.
+fn stale_stubs(
+    output: &Path,
+    importable_name: &str,
+    package: bool,
+) -> Vec<PathBuf> {
+    match package {
+        true => vec![
+            output.join(format!("{importable_name}-stubs")),
+            output.join(".stubgen"),
+        ],
+        false => vec![
+            output.join(importable_name),
+            output.join(format!("{importable_name}.pyi")),
+        ],
+    }
+}
+
+/// Move the stubs `stubgen` wrote to its staging directory into a companion package's
+/// `<package>-stubs` directory, then remove the staging directory. A single module's
+/// `<module>.pyi` stub becomes the package's `__init__.pyi`.
+fn move_package_stubs(
+    stubgen_dir: &Path,
+    importable_name: &str,
+    stubs_dir: &Path,
+) -> HuakResult<()> {
+    let package = stubgen_dir.join(importable_name);
+    let module = stubgen_dir.join(format!("{importable_name}.pyi"));
+    if package.is_dir() {
+        std::fs::rename(package, stubs_dir)?;
+    } else if module.is_file() {
+        std::fs::create_dir_all(stubs_dir)?;
+        std::fs::rename(module, stubs_dir.join("__init__.pyi"))?;
+    } else {
+        return Err(Error::InternalError(format!(
+            "stubgen didn't generate stubs for {importable_name}"
+        )));
+    }
+
+    Ok(std::fs::remove_dir_all(stubgen_dir)?)
+}
+
+/// Get the contents of a companion stubs package's pyproject.toml.
+fn stubs_pyproject_toml_contents(
+    name: &str,
+    importable_name: &str,
+    version: &str,
+) -> String {
+    format!(
+        r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "{name}-stubs"
+version = "{version}"
+description = "Type stubs for {name}"
+dependencies = []
+
+[tool.hatch.build.targets.wheel]
+packages = ["{importable_name}-stubs"]
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::PyProjectToml;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_stubs_pyproject_toml_contents() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("pyproject.toml");
+        std::fs::write(
+            &path,
+            stubs_pyproject_toml_contents(
+                "mock-project",
+                "mock_project",
+                "0.0.1",
+            ),
+        )
+        .unwrap();
+
+        let pyproject_toml = PyProjectToml::new(&path).unwrap();
+        let project = pyproject_toml.project.as_ref().unwrap();
+
+        assert_eq!(project.name, "mock-project-stubs");
+        assert_eq!(project.version.as_ref().unwrap().to_string(), "0.0.1");
+    }
+
+    #[test]
+    fn test_move_package_stubs_module() {
+        let dir = tempdir().unwrap();
+        let stubgen_dir = dir.path().join(".stubgen");
+        let stubs_dir = dir.path().join("mock_project-stubs");
+        std::fs::create_dir_all(&stubgen_dir).unwrap();
+        std::fs::write(stubgen_dir.join("mock_project.pyi"), "x: int").unwrap();
+
+        move_package_stubs(&stubgen_dir, "mock_project", &stubs_dir).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(stubs_dir.join("__init__.pyi")).unwrap(),
+            "x: int"
+        );
+        assert!(!stubgen_dir.exists());
+    }
+
+    #[test]
+    fn test_move_package_stubs_package() {
+        let dir = tempdir().unwrap();
+        let stubgen_dir = dir.path().join(".stubgen");
+        let stubs_dir = dir.path().join("mock_project-stubs");
+        let package = stubgen_dir.join("mock_project");
+        std::fs::create_dir_all(&package).unwrap();
+        std::fs::write(package.join("__init__.pyi"), "").unwrap();
+
+        move_package_stubs(&stubgen_dir, "mock_project", &stubs_dir).unwrap();
+
+        assert!(stubs_dir.join("__init__.pyi").exists());
+        assert!(!stubgen_dir.exists());
+        assert!(move_package_stubs(&stubgen_dir, "mock_project", &stubs_dir)
+            .is_err());
+    }
+
+    #[test]
+    fn test_stale_stubs() {
+        let dir = tempdir().unwrap();
+        let output = dir.path();
+
+        assert_eq!(
+            stale_stubs(output, "mock_project", true),
+            [output.join("mock_project-stubs"), output.join(".stubgen")]
+        );
+        assert_eq!(
+            stale_stubs(output, "mock_project", false),
+            [output.join("mock_project"), output.join("mock_project.pyi")]
+        );
+    }
+}
@@ -1,11 +1,126 @@
-use super::make_venv_command;
-use crate::{dependency::Dependency, Config, HuakResult, InstallOptions};
-use std::{process::Command, str::FromStr};
+use super::{
+    exclude_patterns, make_venv_command, op_env_vars, target_paths,
+    tool_dependencies, tool_dependency, tool_outdated,
+};
+use crate::{
+    dependency::Dependency, fs, metadata::Metadata, Config, Error, HuakResult,
+    InstallOptions,
+};
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+    process::Command,
+    str::FromStr,
+};
+use termcolor::Color;
+
+/// The directory `pytest` caches runs in, including the previous run's failures.
+const PYTEST_CACHE_DIR: &str = ".huak/pytest_cache";
 
 pub struct TestOptions {
     /// A values vector of test options typically used for passing on arguments.
     pub values: Option<Vec<String>>,
     pub install_options: InstallOptions,
+    /// Only run tests for Python files changed relative to a git reference. `Some(None)`
+    /// compares against the merge-base with the default branch.
+    pub changed: Option<Option<String>>,
+    /// Paths or glob patterns to target instead of the whole workspace, relative to the
+    /// current working directory.
+    pub paths: Vec<String>,
+    /// Run tests in parallel with `pytest-xdist`.
+    pub jobs: Option<TestJobs>,
+    /// Only run the test files in a shard of the project's tests.
+    pub shard: Option<TestShard>,
+    /// Only run the tests that failed during the previous run.
+    pub last_failed: bool,
+    /// Run the tests that failed during the previous run before the rest.
+    pub failed_first: bool,
+    /// Only run tests matching a keyword expression.
+    pub keyword: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The number of workers to run tests with.
+pub enum TestJobs {
+    /// One worker per CPU.
+    Auto,
+    Count(usize),
+}
+
+impl FromStr for TestJobs {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(TestJobs::Auto),
+            _ => match s.parse::<usize>() {
+                Ok(it) if it > 0 => Ok(TestJobs::Count(it)),
+                _ => Err(Error::HuakConfigurationError(format!(
+                    "{s} is not a number of jobs (use a positive number or auto)"
+                ))),
+            },
+        }
+    }
+}
+
+impl Display for TestJobs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TestJobs::Auto => write!(f, "auto"),
+            TestJobs::Count(it) => write!(f, "{it}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A shard of a project's tests expressed as `<index>/<total>`, such as "2/4" for the
+/// second of four shards. Test files are assigned to shards round-robin in sorted order
+/// so that every CI job computes the same shards.
+pub struct TestShard {
+    /// The 1-based index of the shard.
+    pub index: usize,
+    /// The total number of shards.
+    pub total: usize,
+}
+
+impl TestShard {
+    /// Get the test files in the shard.
+    fn select(&self, tests: &[String]) -> Vec<String> {
+        let mut tests = tests.to_vec();
+        tests.sort();
+        tests
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| i % self.total == self.index - 1)
+            .map(|(_, it)| it)
+            .collect()
+    }
+}
+
+impl FromStr for TestShard {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let shard = s.split_once('/').and_then(|(index, total)| {
+            Some(TestShard {
+                index: index.trim().parse().ok()?,
+                total: total.trim().parse().ok()?,
+            })
+        });
+
+        match shard {
+            Some(it) if it.index >= 1 && it.index <= it.total => Ok(it),
+            _ => Err(Error::HuakConfigurationError(format!(
+                "{s} is not a shard (use <index>/<total>, such as 2/4)"
+            ))),
+        }
+    }
+}
+
+impl Display for TestShard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.total)
+    }
 }
 
 pub fn test_project(config: &Config, options: &TestOptions) -> HuakResult<()> {
@@ -13,49 +128,359 @@ pub fn test_project(config: &Config, options: &TestOptions) -> HuakResult<()> {
     let package = workspace.current_package()?;
     let mut metadata = workspace.current_local_metadata()?;
     let python_env = workspace.resolve_python_environment()?;
+    let mut terminal = config.terminal();
 
-    // Install `pytest` if it isn't already installed.
-    let test_dep = Dependency::from_str("pytest")?;
-    if !python_env.contains_module(test_dep.name())? {
-        python_env.install_packages(
-            &[&test_dep],
-            &options.install_options,
-            config,
-        )?;
-    }
-
-    // Add the installed `pytest` package to the metadata file if it isn't already there.
-    if !metadata.metadata().contains_dependency_any(&test_dep)? {
-        for pkg in python_env
-            .installed_packages()?
-            .iter()
-            .filter(|pkg| pkg.name() == test_dep.name())
-        {
-            metadata.metadata_mut().add_optional_dependency(
-                Dependency::from_str(&pkg.to_string())?,
-                "dev",
-            );
+    let runner = match metadata.metadata().huak_setting("test", "runner") {
+        Some(it) => TestRunner::from_str(it.as_str().unwrap_or_default())?,
+        None => {
+            let runner = detect_test_runner(
+                workspace.root(),
+                metadata.metadata(),
+                python_env.root(),
+            )?;
+            if runner == TestRunner::Unittest {
+                terminal.print_custom(
+                    "Detected",
+                    "unittest-style tests (set `runner` in [tool.huak.test] to choose a runner)",
+                    Color::Cyan,
+                    true,
+                )?;
+            }
+            runner
         }
+    };
+
+    if options.jobs.is_some() && runner != TestRunner::Pytest {
+        return Err(Error::HuakConfigurationError(
+            "running tests in parallel requires the pytest runner".to_string(),
+        ));
     }
+    if (options.last_failed || options.failed_first)
+        && runner != TestRunner::Pytest
+    {
+        return Err(Error::HuakConfigurationError(
+            "rerunning failed tests requires the pytest runner".to_string(),
+        ));
+    }
+
+    if runner == TestRunner::Pytest {
+        // Install `pytest`, and `pytest-xdist` for parallel runs, if they aren't already
+        // installed at their pinned versions.
+        let tools = tool_dependencies(&metadata)?;
+        let mut test_deps = vec![tool_dependency("pytest", &tools)?];
+        if options.jobs.is_some() {
+            test_deps.push(tool_dependency("pytest-xdist", &tools)?);
+        }
+        for test_dep in test_deps.iter() {
+            // `pytest-xdist` doesn't install an executable so its module is checked for.
+            let installed = match test_dep.name() {
+                "pytest-xdist" => {
+                    python_env.site_packages_dir_path().join("xdist").exists()
+                }
+                name => python_env.contains_module(name)?,
+            };
+            if !installed || tool_outdated(&python_env, test_dep, config)? {
+                python_env.install_packages(
+                    &[test_dep],
+                    &options.install_options,
+                    config,
+                )?;
+            }
+        }
+
+        // Add the installed packages to the metadata file if they aren't already there.
+        let installed_packages = python_env.installed_packages()?;
+        for test_dep in test_deps.iter() {
+            if tools.iter().any(|it| it.name() == test_dep.name())
+                || metadata.metadata().contains_dependency_any(test_dep)?
+            {
+                continue;
+            }
+            for pkg in installed_packages
+                .iter()
+                .filter(|pkg| pkg.name() == test_dep.name())
+            {
+                metadata.metadata_mut().add_optional_dependency(
+                    Dependency::from_str(&pkg.to_string())?,
+                    "dev",
+                );
+            }
+        }
 
-    if package.metadata() != metadata.metadata() {
-        metadata.write_file()?;
+        if package.metadata() != metadata.metadata() {
+            metadata.write_file()?;
+        }
     }
 
-    // Run `pytest` with the package directory added to the command's `PYTHONPATH`.
+    // Run the tests with the package directory added to the command's `PYTHONPATH`.
     let mut cmd = Command::new(python_env.python_path());
-    make_venv_command(&mut cmd, &python_env)?;
+    make_venv_command(&mut cmd, &python_env, config)?;
+    cmd.envs(op_env_vars(&metadata, "test")?);
     let python_path = if workspace.root().join("src").exists() {
         workspace.root().join("src")
     } else {
         workspace.root().to_path_buf()
     };
-    let mut args = vec!["-m", "pytest"];
+    let exclude = exclude_patterns(&metadata)?;
+    // Explicit paths are passed to the runner as-is unless only changed files are tested.
+    let mut tests = match options.changed.as_ref() {
+        Some(_) => select_tests(
+            workspace.root(),
+            &target_paths(
+                &workspace,
+                &options.changed,
+                false,
+                &options.paths,
+                &exclude,
+            )?,
+            python_env.root(),
+        )?,
+        None if !options.paths.is_empty() => {
+            target_paths(&workspace, &None, false, &options.paths, &exclude)?
+        }
+        None => Vec::new(),
+    };
+    if options.changed.is_some() && tests.is_empty() {
+        return terminal.print_custom(
+            "Skipped",
+            "no tests cover the changed Python files",
+            Color::Green,
+            true,
+        );
+    }
+    if let Some(shard) = options.shard.as_ref() {
+        if tests.is_empty() {
+            tests = test_files(workspace.root(), python_env.root())?;
+        }
+        tests = shard.select(&tests);
+        if tests.is_empty() {
+            return terminal.print_custom(
+                "Skipped",
+                format!("no test files are in shard {shard}"),
+                Color::Green,
+                true,
+            );
+        }
+    }
+    cmd.args(test_args(
+        runner,
+        options,
+        &tests,
+        workspace.root(),
+        metadata.metadata(),
+    ))
+    .env("PYTHONPATH", python_path);
+    // Test file paths are relative to the workspace root, and `unittest` discovers and
+    // imports tests relative to the working directory.
+    if runner == TestRunner::Unittest || !tests.is_empty() {
+        cmd.current_dir(workspace.root());
+    }
+    terminal.run_command(&mut cmd)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The framework used to run a project's tests.
+enum TestRunner {
+    Pytest,
+    Unittest,
+}
+
+impl FromStr for TestRunner {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pytest" => Ok(TestRunner::Pytest),
+            "unittest" => Ok(TestRunner::Unittest),
+            _ => Err(Error::HuakConfigurationError(format!(
+                "{s} is not a test runner (use pytest or unittest)"
+            ))),
+        }
+    }
+}
+
+/// Detect the `TestRunner` for a project. `unittest` is only used for projects without
+/// any `pytest` configuration whose tests import `unittest` and never `pytest`. Files
+/// under `exclude` are ignored.
+fn detect_test_runner(
+    root: &Path,
+    metadata: &Metadata,
+    exclude: &Path,
+) -> HuakResult<TestRunner> {
+    let has_pytest_config = metadata.tool_table("pytest").is_some()
+        || root.join("pytest.ini").exists()
+        || root.join("conftest.py").exists()
+        || root.join("tests").join("conftest.py").exists()
+        || file_contains(&root.join("setup.cfg"), "[tool:pytest]")
+        || file_contains(&root.join("tox.ini"), "[pytest]");
+    if has_pytest_config {
+        return Ok(TestRunner::Pytest);
+    }
+
+    let mut imports_unittest = false;
+    for path in test_files(root, exclude)? {
+        let Ok(contents) = std::fs::read_to_string(root.join(path)) else {
+            continue;
+        };
+        for line in contents.lines().map(str::trim) {
+            if imports_module(line, "pytest") {
+                return Ok(TestRunner::Pytest);
+            }
+            if imports_module(line, "unittest") {
+                imports_unittest = true;
+            }
+        }
+    }
+
+    match imports_unittest {
+        true => Ok(TestRunner::Unittest),
+        false => Ok(TestRunner::Pytest),
+    }
+}
+
+/// Get the arguments passed to `python` to run the `tests` with a `TestRunner`. The
+/// whole project is tested if `tests` is empty.
+fn test_args(
+    runner: TestRunner,
+    options: &TestOptions,
+    tests: &[String],
+    root: &Path,
+    metadata: &Metadata,
+) -> Vec<String> {
+    let mut args = match runner {
+        TestRunner::Pytest => vec!["-m".to_string(), "pytest".to_string()],
+        TestRunner::Unittest => vec!["-m".to_string(), "unittest".to_string()],
+    };
+    if let Some(it) = options.jobs.as_ref() {
+        args.extend(["-n".to_string(), it.to_string()]);
+    }
+    // Record runs under ".huak" unless the project configures its own cache directory.
+    if runner == TestRunner::Pytest && !has_pytest_cache_dir(metadata) {
+        args.extend([
+            "-o".to_string(),
+            format!("cache_dir={}", root.join(PYTEST_CACHE_DIR).display()),
+        ]);
+    }
+    if options.last_failed {
+        args.push("--last-failed".to_string());
+    }
+    if options.failed_first {
+        args.push("--failed-first".to_string());
+    }
+    if !tests.is_empty() {
+        args.extend(tests.iter().cloned());
+    } else if runner == TestRunner::Unittest {
+        args.push("discover".to_string());
+        if root.join("tests").is_dir() {
+            args.extend(["-s".to_string(), "tests".to_string()]);
+        }
+    }
+    // `unittest` only accepts `-k` after the tests to run.
+    if let Some(it) = options.keyword.as_ref() {
+        args.extend(["-k".to_string(), it.clone()]);
+    }
     if let Some(v) = options.values.as_ref() {
-        args.extend(v.iter().map(|item| item.as_str()));
+        args.extend(v.iter().cloned());
     }
-    cmd.args(args).env("PYTHONPATH", python_path);
-    config.terminal().run_command(&mut cmd)
+
+    args
+}
+
+/// Check if the project configures a cache directory for `pytest` in its pyproject.toml.
+fn has_pytest_cache_dir(metadata: &Metadata) -> bool {
+    metadata
+        .tool_table("pytest")
+        .and_then(|it| it.get("ini_options"))
+        .and_then(|it| it.as_table())
+        .map_or(false, |it| it.contains_key("cache_dir"))
+}
+
+/// Check if a line of Python code imports a module.
+fn imports_module(line: &str, module: &str) -> bool {
+    ["import ", "from "].iter().any(|keyword| {
+        line.strip_prefix(keyword)
+            .and_then(|it| it.strip_prefix(module))
+            .map_or(false, |it| {
+                it.is_empty() || it.starts_with([' ', '.', ','])
+            })
+    })
+}
+
+/// Check if a file exists and contains a pattern.
+fn file_contains(path: &Path, pattern: &str) -> bool {
+    std::fs::read_to_string(path)
+        .map_or(false, |contents| contents.contains(pattern))
+}
+
+/// Select the test files to run for changed Python files. Changed test files are selected
+/// as-is, and other changed files select the tests named after them, such as
+/// "test_<module>.py" and "<module>_test.py". Files under `exclude` are never selected.
+fn select_tests(
+    root: &Path,
+    changed: &[String],
+    exclude: &Path,
+) -> HuakResult<Vec<String>> {
+    let mut tests = Vec::new();
+    let mut modules = Vec::new();
+    for path in changed {
+        let stem = Path::new(path)
+            .file_stem()
+            .map(|it| it.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if test_module_name(&stem).is_some() {
+            tests.push(path.to_string());
+        } else {
+            modules.push(stem);
+        }
+    }
+
+    if !modules.is_empty() {
+        for path in test_files(root, exclude)? {
+            let stem = Path::new(&path)
+                .file_stem()
+                .map(|it| it.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let Some(module) = test_module_name(&stem) else {
+                continue;
+            };
+            if modules.iter().any(|it| it == module) && !tests.contains(&path) {
+                tests.push(path);
+            }
+        }
+    }
+
+    Ok(tests)
+}
+
+/// Get the paths to the test files under `root` relative to it, skipping files under
+/// `exclude` and files reached through symlinks.
+fn test_files(root: &Path, exclude: &Path) -> HuakResult<Vec<String>> {
+    let pattern = format!("{}", root.join("**").join("*.py").display());
+    let mut paths = Vec::new();
+    for path in glob::glob(&pattern)?.flatten() {
+        if path.starts_with(exclude) || fs::is_linked(root, &path) {
+            continue;
+        }
+        let is_test = path
+            .file_stem()
+            .and_then(|it| it.to_str())
+            .and_then(test_module_name)
+            .is_some();
+        if !is_test {
+            continue;
+        }
+        if let Ok(it) = path.strip_prefix(root) {
+            paths.push(it.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Get the name of the module a test file is named after, if the file is a test file.
+fn test_module_name(stem: &str) -> Option<&str> {
+    stem.strip_prefix("test_")
+        .or_else(|| stem.strip_suffix("_test"))
 }
 
 #[cfg(test)]
@@ -63,6 +488,7 @@ mod tests {
     use super::*;
     use crate::{
         fs,
+        metadata::LocalMetadata,
         ops::{test_config, test_venv},
         test_resources_dir_path, Verbosity,
     };
@@ -84,8 +510,210 @@ mod tests {
         let options = TestOptions {
             values: None,
             install_options: InstallOptions { values: None },
+            changed: None,
+            paths: Vec::new(),
+            jobs: None,
+            shard: None,
+            last_failed: false,
+            failed_first: false,
+            keyword: None,
         };
 
         test_project(&config, &options).unwrap();
+
+        assert!(ws.root().join(PYTEST_CACHE_DIR).is_dir());
+        assert!(!ws.root().join(".pytest_cache").exists());
+    }
+
+    #[test]
+    fn test_test_args() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let mut options = TestOptions {
+            values: Some(vec!["-x".to_string()]),
+            install_options: InstallOptions { values: None },
+            changed: None,
+            paths: Vec::new(),
+            jobs: None,
+            shard: None,
+            last_failed: true,
+            failed_first: false,
+            keyword: Some("cli and not slow".to_string()),
+        };
+        let cache_dir =
+            format!("cache_dir={}", root.join(PYTEST_CACHE_DIR).display());
+
+        let pytest_args = test_args(
+            TestRunner::Pytest,
+            &options,
+            &[],
+            root,
+            &Metadata::default(),
+        );
+        options.last_failed = false;
+        options.failed_first = true;
+        options.keyword = None;
+        let failed_first_args = test_args(
+            TestRunner::Pytest,
+            &options,
+            &["tests/test_cli.py".to_string()],
+            root,
+            &Metadata::default(),
+        );
+
+        assert_eq!(
+            pytest_args,
+            [
+                "-m",
+                "pytest",
+                "-o",
+                cache_dir.as_str(),
+                "--last-failed",
+                "-k",
+                "cli and not slow",
+                "-x",
+            ]
+        );
+        assert_eq!(
+            failed_first_args,
+            [
+                "-m",
+                "pytest",
+                "-o",
+                cache_dir.as_str(),
+                "--failed-first",
+                "tests/test_cli.py",
+                "-x",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_test_args_pytest_cache_dir() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let path = root.join("pyproject.toml");
+        std::fs::write(
+            &path,
+            r#"[project]
+name = "mock_project"
+version = "0.0.1"
+
+[tool.pytest.ini_options]
+cache_dir = ".cache/pytest"
+"#,
+        )
+        .unwrap();
+        let metadata = LocalMetadata::new(&path).unwrap();
+        let options = TestOptions {
+            values: None,
+            install_options: InstallOptions { values: None },
+            changed: None,
+            paths: Vec::new(),
+            jobs: None,
+            shard: None,
+            last_failed: true,
+            failed_first: false,
+            keyword: None,
+        };
+
+        let args = test_args(
+            TestRunner::Pytest,
+            &options,
+            &[],
+            root,
+            metadata.metadata(),
+        );
+
+        assert_eq!(args, ["-m", "pytest", "--last-failed"]);
+    }
+
+    #[test]
+    fn test_select_tests() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let venv = root.join(".venv");
+        for path in [
+            root.join("tests").join("test_version.py"),
+            root.join("tests").join("test_cli.py"),
+            root.join("tests").join("utils_test.py"),
+            venv.join("lib").join("test_utils.py"),
+        ] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let changed = vec![
+            "src/mock_project/utils.py".to_string(),
+            "tests/test_version.py".to_string(),
+        ];
+
+        let mut tests = select_tests(root, &changed, &venv).unwrap();
+        tests.sort();
+
+        assert_eq!(
+            tests,
+            vec![
+                format!(
+                    "{}",
+                    Path::new("tests").join("test_version.py").display()
+                ),
+                format!(
+                    "{}",
+                    Path::new("tests").join("utils_test.py").display()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_test_runner() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let venv = root.join(".venv");
+        let metadata = Metadata::default();
+        std::fs::create_dir_all(root.join("tests")).unwrap();
+        std::fs::create_dir_all(venv.join("lib")).unwrap();
+        std::fs::write(
+            root.join("tests").join("test_cli.py"),
+            "import unittest\n\nclass TestCli(unittest.TestCase):\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(
+            venv.join("lib").join("test_pytest.py"),
+            "import pytest",
+        )
+        .unwrap();
+
+        let unittest_runner =
+            detect_test_runner(root, &metadata, &venv).unwrap();
+        std::fs::write(
+            root.join("tests").join("utils_test.py"),
+            "from pytest import fixture\n",
+        )
+        .unwrap();
+        let pytest_runner = detect_test_runner(root, &metadata, &venv).unwrap();
+
+        assert_eq!(unittest_runner, TestRunner::Unittest);
+        assert_eq!(pytest_runner, TestRunner::Pytest);
+        assert!(imports_module("import unittest.mock", "unittest"));
+        assert!(!imports_module("import pytest_mock", "pytest"));
+    }
+
+    #[test]
+    fn test_test_shard() {
+        let tests = ["tests/test_d.py", "tests/test_a.py", "tests/test_c.py"]
+            .map(str::to_string);
+        let shard = TestShard::from_str("2/2").unwrap();
+
+        assert_eq!(shard, TestShard { index: 2, total: 2 });
+        assert_eq!(shard.select(&tests), vec!["tests/test_c.py"]);
+        assert_eq!(
+            TestShard::from_str("1/1").unwrap().select(&tests),
+            vec!["tests/test_a.py", "tests/test_c.py", "tests/test_d.py"]
+        );
+        assert!(TestShard::from_str("0/2").is_err());
+        assert!(TestShard::from_str("3/2").is_err());
+        assert_eq!(TestJobs::from_str("4").unwrap(), TestJobs::Count(4));
+        assert!(TestJobs::from_str("0").is_err());
     }
 }
@@ -0,0 +1,109 @@
+use super::{make_venv_command, tool_environment};
+use crate::{
+    dependency::Dependency, Config, Error, HuakResult, InstallOptions,
+};
+use std::{process::Command, str::FromStr};
+
+pub struct ToolOptions {
+    /// The command to run.
+    pub tool: String,
+    /// The requirement for the distribution providing the command. Defaults to the
+    /// command's name.
+    pub from: Option<String>,
+    /// A values vector of arguments passed on to the command.
+    pub values: Vec<String>,
+    pub install_options: InstallOptions,
+}
+
+/// Run a command provided by a package from a cached tool environment without touching
+/// the project or its environment. The tool environment is created with the Python the
+/// workspace would use, and reused until its requirement or the Python version changes.
+pub fn run_tool(config: &Config, options: &ToolOptions) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let requirement = options.from.as_deref().unwrap_or(&options.tool);
+    let dependency = Dependency::from_str(requirement)?;
+    let env = workspace.environment();
+    let (interpreter, _) = workspace
+        .select_python_interpreter(env.interpreters().interpreters())?;
+    let tool_env = tool_environment(
+        &[dependency],
+        &interpreter,
+        &options.install_options,
+        config,
+    )?;
+
+    // Tool names can contain dots, so the suffix is appended rather than set.
+    let executable = match cfg!(windows) {
+        true => format!("{}.exe", options.tool),
+        false => options.tool.clone(),
+    };
+    let path = tool_env.executables_dir_path().join(executable);
+    if !path.exists() {
+        return Err(Error::HuakConfigurationError(format!(
+            "{requirement} doesn't provide a {} command",
+            options.tool
+        )));
+    }
+    let mut cmd = Command::new(path);
+    make_venv_command(&mut cmd, &tool_env, config)?;
+    cmd.args(&options.values).current_dir(&config.cwd);
+    config.terminal().run_command(&mut cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs, ops::test_config, test_resources_dir_path, Verbosity};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_run_tool() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(root, cwd, Verbosity::Quiet);
+        // `pip` is installed in new virtual environments, so nothing is downloaded.
+        let options = ToolOptions {
+            tool: "pip".to_string(),
+            from: None,
+            values: vec!["--version".to_string()],
+            install_options: InstallOptions { values: None },
+        };
+
+        run_tool(&config, &options).unwrap();
+    }
+
+    #[test]
+    fn test_run_tool_from() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.to_path_buf();
+        let config = test_config(root, cwd, Verbosity::Quiet);
+        let mut options = ToolOptions {
+            tool: "pip3".to_string(),
+            from: Some("pip".to_string()),
+            values: vec!["--version".to_string()],
+            install_options: InstallOptions { values: None },
+        };
+
+        run_tool(&config, &options).unwrap();
+        options.tool = "not-a-command".to_string();
+        let res = run_tool(&config, &options);
+
+        assert!(matches!(
+            res,
+            Err(Error::HuakConfigurationError(it))
+                if it == "pip doesn't provide a not-a-command command"
+        ));
+    }
+}
@@ -1,3 +1,6 @@
+use super::{
+    installed_dependency_releases, warn_yanked_releases, with_constraints,
+};
 use crate::{
     dependency::{dependency_iter, Dependency},
     Config, HuakResult, InstallOptions,
@@ -14,6 +17,8 @@ pub fn update_project_dependencies(
     options: &UpdateOptions,
 ) -> HuakResult<()> {
     let workspace = config.workspace();
+    let _lock = workspace.lock()?;
+    let _journal = workspace.journal("update")?;
     let package = workspace.current_package()?;
     let mut metadata = workspace.current_local_metadata()?;
     let python_env = workspace.resolve_python_environment()?;
@@ -38,7 +43,9 @@ pub fn update_project_dependencies(
             return Ok(());
         }
 
-        python_env.update_packages(&deps, &options.install_options, config)?;
+        with_constraints(&metadata, &options.install_options, config, |it| {
+            python_env.update_packages(&deps, it, config)
+        })?;
     } else {
         let mut deps = metadata
             .metadata()
@@ -55,7 +62,9 @@ pub fn update_project_dependencies(
         }
 
         deps.dedup();
-        python_env.update_packages(&deps, &options.install_options, config)?;
+        with_constraints(&metadata, &options.install_options, config, |it| {
+            python_env.update_packages(&deps, it, config)
+        })?;
     }
 
     // Get all groups from the metadata file to include in the removal process.
@@ -83,7 +92,10 @@ pub fn update_project_dependencies(
     if package.metadata() != metadata.metadata() {
         metadata.write_file()?;
     }
-    Ok(())
+
+    // Packages pinned by the project aren't updated past yanked releases.
+    let releases = installed_dependency_releases(&python_env, &metadata)?;
+    warn_yanked_releases(&releases, "installed", config)
 }
 
 #[cfg(test)]
@@ -0,0 +1,254 @@
+use super::{
+    env_interpreter, isolated_tools, project_tools, tool_environment,
+    with_constraints,
+};
+use crate::{
+    dependency::Dependency, package::canonical_package_name,
+    python_environment::PythonEnvironment, Config, Error, HuakResult,
+    InstallOptions,
+};
+use pep508_rs::VersionOrUrl;
+use std::{rc::Rc, str::FromStr};
+use termcolor::Color;
+use toml::Value;
+
+/// The dev tools huak installs and runs for the project.
+const DEV_TOOLS: [&str; 6] =
+    ["ruff", "black", "mypy", "pytest", "build", "twine"];
+
+pub struct UpgradeOptions {
+    /// The tools to upgrade. Defaults to every dev tool the project uses.
+    pub tools: Option<Vec<String>>,
+    pub install_options: InstallOptions,
+}
+
+/// Upgrade the dev tools huak manages (ruff, black, mypy, pytest, build, and twine) to
+/// their latest allowed versions without touching the project's other dependencies.
+///
+/// A tool is upgraded if it was requested, pinned in `[tool.huak.tools]`, declared in an
+/// optional dependency group, or installed in the project's environment. Exact pins in
+/// `[tool.huak.tools]` and exact versions in optional dependency groups are refreshed to
+/// the upgraded version. Version ranges in `[tool.huak.tools]` are kept and limit how far
+/// their tools are upgraded, like `[tool.huak.constraints]` do.
+pub fn upgrade_project_tools(
+    config: &Config,
+    options: &UpgradeOptions,
+) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let _lock = workspace.lock()?;
+    let _journal = workspace.journal("upgrade")?;
+    let mut metadata = workspace.current_local_metadata()?;
+    let python_env = workspace.resolve_python_environment()?;
+    let mut terminal = config.terminal();
+
+    let requested = match options.tools.as_ref() {
+        Some(tools) => {
+            for it in tools {
+                if !DEV_TOOLS.contains(&it.to_lowercase().as_str()) {
+                    return Err(Error::HuakConfigurationError(format!(
+                        "{it} isn't a dev tool huak manages (expected one of {})",
+                        DEV_TOOLS.join(", ")
+                    )));
+                }
+            }
+            tools.iter().map(|it| it.to_lowercase()).collect()
+        }
+        None => Vec::new(),
+    };
+    let isolated = isolated_tools(&metadata)?;
+    let pins = project_tools(&metadata)?;
+    let groups = metadata
+        .metadata()
+        .optional_dependencies()
+        .map(|it| it.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let mut changed = false;
+    let mut upgraded = 0;
+    for name in DEV_TOOLS {
+        if !requested.is_empty() && !requested.iter().any(|it| it == name) {
+            continue;
+        }
+        let tool = Dependency::from_str(name)?;
+        let pin = pins.iter().find(|it| it.name() == name);
+        let pin_prefix = metadata
+            .metadata()
+            .huak_setting("tools", name)
+            .and_then(Value::as_str)
+            .and_then(exact_pin_prefix);
+        let declared = groups
+            .iter()
+            .filter(|it| {
+                metadata
+                    .metadata()
+                    .contains_optional_dependency(&tool, it)
+                    .unwrap_or_default()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if requested.is_empty()
+            && pin.is_none()
+            && declared.is_empty()
+            && installed_version(&python_env, name)?.is_none()
+        {
+            continue;
+        }
+
+        // Exact pins are lifted so that the tool can move to its latest version.
+        let dependency = match pin {
+            Some(it) if pin_prefix.is_none() => it.clone(),
+            _ => tool.clone(),
+        };
+        // pytest always runs from the project's environment.
+        let tool_env = match isolated && name != "pytest" {
+            true => Rc::new(tool_environment(
+                &[dependency.clone()],
+                &env_interpreter(&python_env),
+                &options.install_options,
+                config,
+            )?),
+            false => Rc::clone(&python_env),
+        };
+        let before = installed_version(&tool_env, name)?;
+        with_constraints(&metadata, &options.install_options, config, |it| {
+            tool_env.update_packages(&[&dependency], it, config)
+        })?;
+        let Some(after) = installed_version(&tool_env, name)? else {
+            continue;
+        };
+
+        if let Some(prefix) = pin_prefix {
+            let value = format!("{prefix}{after}");
+            let current = metadata
+                .metadata()
+                .huak_setting("tools", name)
+                .and_then(Value::as_str);
+            if current != Some(value.as_str()) {
+                metadata.metadata_mut().set_huak_setting(
+                    "tools",
+                    name,
+                    Value::String(value),
+                );
+                changed = true;
+            }
+        } else if pin.is_none() {
+            let refreshed = Dependency::from_str(&format!("{name}=={after}"))?;
+            for group in declared.iter() {
+                let exact = metadata
+                    .metadata()
+                    .optional_dependency_group(group)
+                    .and_then(|it| it.iter().find(|it| it.name == name))
+                    .map_or(false, |it| match it.version_or_url.as_ref() {
+                        Some(VersionOrUrl::VersionSpecifier(it)) => {
+                            exact_pin_prefix(&it.to_string()).is_some()
+                        }
+                        _ => false,
+                    });
+                if !exact {
+                    continue;
+                }
+                metadata
+                    .metadata_mut()
+                    .remove_optional_dependency(&refreshed, group);
+                metadata
+                    .metadata_mut()
+                    .add_optional_dependency(refreshed.clone(), group);
+                changed = true;
+            }
+        }
+
+        match before {
+            Some(it) if it == after => terminal.print_custom(
+                "Unchanged",
+                format!("{name} {after} is the latest allowed version"),
+                Color::Green,
+                true,
+            )?,
+            Some(it) => {
+                upgraded += 1;
+                terminal.print_custom(
+                    "Upgraded",
+                    format!("{name} {it} -> {after}"),
+                    Color::Green,
+                    true,
+                )?
+            }
+            None => {
+                upgraded += 1;
+                terminal.print_custom(
+                    "Installed",
+                    format!("{name} {after}"),
+                    Color::Green,
+                    true,
+                )?
+            }
+        }
+    }
+
+    if changed {
+        metadata.write_file()?;
+    }
+
+    if upgraded == 0 && requested.is_empty() {
+        terminal.print_custom(
+            "Finished",
+            "the project's dev tools are up to date",
+            Color::Green,
+            true,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Get the version a tool is installed at in a `PythonEnvironment`, if it's installed.
+fn installed_version(
+    python_env: &PythonEnvironment,
+    name: &str,
+) -> HuakResult<Option<String>> {
+    let name = canonical_package_name(name)?.to_lowercase();
+    for it in python_env.installed_packages()? {
+        if canonical_package_name(it.name())?.to_lowercase() == name {
+            return Ok(Some(it.version().to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Get the prefix of version specifiers that pin an exact version, such as "==" for
+/// "==0.4.4" or "" for a bare "0.4.4", so that the pin can be refreshed in the same
+/// format. Ranges and wildcards aren't exact pins.
+fn exact_pin_prefix(specifiers: &str) -> Option<&'static str> {
+    let specifiers = specifiers.trim();
+    if specifiers.contains([',', '*', ';']) {
+        return None;
+    }
+    if specifiers.starts_with(|c: char| c.is_ascii_digit()) {
+        return Some("");
+    }
+    match specifiers.strip_prefix("==") {
+        Some(it)
+            if it.trim_start().starts_with(|c: char| c.is_ascii_digit()) =>
+        {
+            Some("==")
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_pin_prefix() {
+        assert_eq!(exact_pin_prefix("0.4.4"), Some(""));
+        assert_eq!(exact_pin_prefix("==0.4.4"), Some("=="));
+        assert_eq!(exact_pin_prefix(" == 1.10.0"), Some("=="));
+        assert_eq!(exact_pin_prefix(">=0.4,<0.5"), None);
+        assert_eq!(exact_pin_prefix("==0.4.*"), None);
+        assert_eq!(exact_pin_prefix("~=23.1"), None);
+        assert_eq!(exact_pin_prefix(""), None);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{
+    dist_info::{scan_site_packages, DistInfo},
+    Config, Error, HuakResult,
+};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use termcolor::Color;
+
+/// Verify the integrity of the packages installed in the project's Python environment.
+/// Each installed file is re-hashed and compared to its package's RECORD, and modified
+/// or missing files are reported along with files in site-packages no package installed.
+pub fn verify_environment(config: &Config) -> HuakResult<()> {
+    let workspace = config.workspace();
+    let python_env = workspace.resolve_python_environment()?;
+    let mut terminal = config.terminal();
+
+    let site_packages = python_env.site_packages_dir_path();
+    let dists = scan_site_packages(site_packages)?;
+    let mut owned = HashSet::new();
+    let mut problems = 0;
+    for dist in dists.iter() {
+        let package = format!("{}=={}", dist.name(), dist.version());
+        let record = dist.record()?;
+        if record.is_empty() {
+            // Legacy installs don't have a RECORD, so their files can only be owned by
+            // their top-level packages.
+            owned.extend(top_level_names(dist));
+            continue;
+        }
+        for entry in record {
+            let path = site_packages.join(&entry.path);
+            if !entry.path.starts_with("..") {
+                owned.insert(normalize(&entry.path));
+            }
+            if !path.is_file() {
+                // Bytecode is regenerated as needed.
+                if !entry.path.ends_with(".pyc") {
+                    terminal.print_custom(
+                        "Missing",
+                        format!("{} ({package})", entry.path),
+                        Color::Red,
+                        true,
+                    )?;
+                    problems += 1;
+                }
+                continue;
+            }
+            if !entry.matches(&std::fs::read(&path)?)? {
+                terminal.print_custom(
+                    "Modified",
+                    format!("{} ({package})", entry.path),
+                    Color::Yellow,
+                    true,
+                )?;
+                problems += 1;
+            }
+        }
+    }
+
+    for path in unowned_files(site_packages, &owned)? {
+        terminal.print_custom("Extra", path.display(), Color::Cyan, true)?;
+        problems += 1;
+    }
+
+    if problems > 0 {
+        return Err(Error::CheckFailed(format!(
+            "{problems} file(s) don't match the installed packages"
+        )));
+    }
+
+    terminal.print_custom(
+        "Verified",
+        format!("{} package(s)", dists.len()),
+        Color::Green,
+        true,
+    )
+}
+
+/// Get the files in site-packages that aren't `owned` by an installed package, relative
+/// to site-packages. Bytecode caches and distribution metadata directories are skipped,
+/// and a file is owned if it or any of its parent directories is.
+fn unowned_files(
+    site_packages: &Path,
+    owned: &HashSet<String>,
+) -> HuakResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![site_packages.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|it| it.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if name == "__pycache__"
+                || name.ends_with(".dist-info")
+                || name.ends_with(".egg-info")
+            {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(site_packages) else {
+                continue;
+            };
+            let relative = normalize(&relative.to_string_lossy());
+            if owned.contains(&relative) {
+                continue;
+            }
+            if path.is_dir() {
+                if !owned.contains(&format!("{relative}/")) {
+                    dirs.push(path);
+                }
+            } else if !name.ends_with(".pyc") {
+                files.push(PathBuf::from(relative));
+            }
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Get the top-level package directories of a legacy install as owned paths.
+fn top_level_names(dist: &DistInfo) -> Vec<String> {
+    std::fs::read_to_string(dist.path().join("top_level.txt"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|it| !it.is_empty())
+        .flat_map(|it| [format!("{it}/"), format!("{it}.py")])
+        .collect()
+}
+
+/// Normalize a RECORD path to use "/" separators without a leading "./".
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_unowned_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for path in [
+            "click/__init__.py",
+            "click/__pycache__/__init__.cpython-311.pyc",
+            "click-8.1.3.dist-info/RECORD",
+            "legacy/__init__.py",
+            "patch.py",
+            "extra/data.txt",
+        ] {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let owned = HashSet::from([
+            "click/__init__.py".to_string(),
+            "legacy/".to_string(),
+        ]);
+
+        let files = unowned_files(root, &owned).unwrap();
+
+        assert_eq!(
+            files,
+            vec![PathBuf::from("extra/data.txt"), PathBuf::from("patch.py")]
+        );
+    }
+}
@@ -4,6 +4,235 @@ use regex::Regex;
 use std::{fmt::Display, str::FromStr};
 
 const VERSION_OPERATOR_CHARACTERS: [char; 5] = ['=', '~', '!', '>', '<'];
+/// Python's reserved keywords, which can't be used as module names.
+const PYTHON_KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break",
+    "class", "continue", "def", "del", "elif", "else", "except", "finally",
+    "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal",
+    "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+/// The top-level modules of Python's standard library (`sys.stdlib_module_names`).
+const STDLIB_MODULES: [&str; 219] = [
+    "__future__",
+    "_thread",
+    "abc",
+    "aifc",
+    "antigravity",
+    "argparse",
+    "array",
+    "ast",
+    "asynchat",
+    "asyncio",
+    "asyncore",
+    "atexit",
+    "audioop",
+    "base64",
+    "bdb",
+    "binascii",
+    "bisect",
+    "builtins",
+    "bz2",
+    "cProfile",
+    "calendar",
+    "cgi",
+    "cgitb",
+    "chunk",
+    "cmath",
+    "cmd",
+    "code",
+    "codecs",
+    "codeop",
+    "collections",
+    "colorsys",
+    "compileall",
+    "concurrent",
+    "configparser",
+    "contextlib",
+    "contextvars",
+    "copy",
+    "copyreg",
+    "crypt",
+    "csv",
+    "ctypes",
+    "curses",
+    "dataclasses",
+    "datetime",
+    "dbm",
+    "decimal",
+    "difflib",
+    "dis",
+    "distutils",
+    "doctest",
+    "email",
+    "encodings",
+    "ensurepip",
+    "enum",
+    "errno",
+    "faulthandler",
+    "fcntl",
+    "filecmp",
+    "fileinput",
+    "fnmatch",
+    "fractions",
+    "ftplib",
+    "functools",
+    "gc",
+    "genericpath",
+    "getopt",
+    "getpass",
+    "gettext",
+    "glob",
+    "graphlib",
+    "grp",
+    "gzip",
+    "hashlib",
+    "heapq",
+    "hmac",
+    "html",
+    "http",
+    "idlelib",
+    "imaplib",
+    "imghdr",
+    "imp",
+    "importlib",
+    "inspect",
+    "io",
+    "ipaddress",
+    "itertools",
+    "json",
+    "keyword",
+    "lib2to3",
+    "linecache",
+    "locale",
+    "logging",
+    "lzma",
+    "mailbox",
+    "mailcap",
+    "marshal",
+    "math",
+    "mimetypes",
+    "mmap",
+    "modulefinder",
+    "msilib",
+    "msvcrt",
+    "multiprocessing",
+    "netrc",
+    "nis",
+    "nntplib",
+    "nt",
+    "ntpath",
+    "nturl2path",
+    "numbers",
+    "opcode",
+    "operator",
+    "optparse",
+    "os",
+    "ossaudiodev",
+    "pathlib",
+    "pdb",
+    "pickle",
+    "pickletools",
+    "pipes",
+    "pkgutil",
+    "platform",
+    "plistlib",
+    "poplib",
+    "posix",
+    "posixpath",
+    "pprint",
+    "profile",
+    "pstats",
+    "pty",
+    "pwd",
+    "py_compile",
+    "pyclbr",
+    "pydoc",
+    "pydoc_data",
+    "pyexpat",
+    "queue",
+    "quopri",
+    "random",
+    "re",
+    "readline",
+    "reprlib",
+    "resource",
+    "rlcompleter",
+    "runpy",
+    "sched",
+    "secrets",
+    "select",
+    "selectors",
+    "shelve",
+    "shlex",
+    "shutil",
+    "signal",
+    "site",
+    "smtpd",
+    "smtplib",
+    "sndhdr",
+    "socket",
+    "socketserver",
+    "spwd",
+    "sqlite3",
+    "sre_compile",
+    "sre_constants",
+    "sre_parse",
+    "ssl",
+    "stat",
+    "statistics",
+    "string",
+    "stringprep",
+    "struct",
+    "subprocess",
+    "sunau",
+    "symtable",
+    "sys",
+    "sysconfig",
+    "syslog",
+    "tabnanny",
+    "tarfile",
+    "telnetlib",
+    "tempfile",
+    "termios",
+    "textwrap",
+    "this",
+    "threading",
+    "time",
+    "timeit",
+    "tkinter",
+    "token",
+    "tokenize",
+    "tomllib",
+    "trace",
+    "traceback",
+    "tracemalloc",
+    "tty",
+    "turtle",
+    "turtledemo",
+    "types",
+    "typing",
+    "unicodedata",
+    "unittest",
+    "urllib",
+    "uu",
+    "uuid",
+    "venv",
+    "warnings",
+    "wave",
+    "weakref",
+    "webbrowser",
+    "winreg",
+    "winsound",
+    "wsgiref",
+    "xdrlib",
+    "xml",
+    "xmlrpc",
+    "zipapp",
+    "zipfile",
+    "zipimport",
+    "zlib",
+    "zoneinfo",
+];
 
 #[derive(Clone)]
 /// The `Package` contains data about a Python `Package`.
@@ -49,19 +278,6 @@ impl Display for Package {
     }
 }
 
-/// A wrapper for implementing iterables on `Package`s.
-struct PackageIter<'a> {
-    iter: std::slice::Iter<'a, Package>,
-}
-
-impl<'a> Iterator for PackageIter<'a> {
-    type Item = &'a Package;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next()
-    }
-}
-
 impl From<Metadata> for Package {
     fn from(value: Metadata) -> Self {
         Package {
@@ -170,9 +386,51 @@ pub fn importable_package_name(name: &str) -> HuakResult<String> {
     Ok(canonical_name.replace('-', "_"))
 }
 
+/// Check that an importable package name can be imported without shadowing a standard
+/// library module, describing the problem if it can't.
+pub fn importable_name_problem(name: &str) -> Option<String> {
+    let mut chars = name.chars();
+    let legal = chars
+        .next()
+        .map_or(false, |it| it.is_alphabetic() || it == '_')
+        && chars.all(|it| it.is_alphanumeric() || it == '_');
+    if !legal {
+        return Some(format!("{name:?} isn't a valid Python identifier"));
+    }
+    if PYTHON_KEYWORDS.contains(&name) {
+        return Some(format!("{name:?} is a reserved Python keyword"));
+    }
+    if STDLIB_MODULES.contains(&name) {
+        return Some(format!(
+            "{name:?} would shadow the standard library's {name} module"
+        ));
+    }
+
+    None
+}
+
 /// Normalize a name to a distributable and packagable name.
-fn canonical_package_name(name: &str) -> HuakResult<String> {
+pub fn canonical_package_name(name: &str) -> HuakResult<String> {
     let re = Regex::new("[-_. ]+")?;
     let res = re.replace_all(name, "-");
     Ok(res.into_owned())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_importable_name_problem() {
+        assert!(importable_name_problem("my_project").is_none());
+        assert!(importable_name_problem("_private2").is_none());
+        assert!(importable_name_problem("2fast").is_some());
+        assert!(importable_name_problem("my+project").is_some());
+        assert!(importable_name_problem("").is_some());
+        assert!(importable_name_problem("class").is_some());
+        assert_eq!(
+            importable_name_problem("json").unwrap(),
+            "\"json\" would shadow the standard library's json module"
+        );
+    }
+}
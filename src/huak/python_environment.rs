@@ -1,5 +1,8 @@
+use pep508_rs::{MarkerEnvironment, MarkerTree, Requirement};
 use std::{
+    cell::RefCell,
     cmp::Ordering,
+    collections::HashMap,
     env::consts::OS,
     fmt::Display,
     fs::File,
@@ -7,10 +10,23 @@ use std::{
     path::{Path, PathBuf},
     process::Command,
     str::FromStr,
+    time::Instant,
 };
+use termcolor::Color;
 
 use crate::{
-    environment::env_path_values, fs, package::Package, sys, version::Version,
+    cache,
+    checksums::check_locked_checksums,
+    dist_info::scan_site_packages,
+    environment::env_path_values,
+    fs,
+    lockfile::Lockfile,
+    metadata::Metadata,
+    network::{self, Download},
+    package::{canonical_package_name, Package},
+    sys,
+    version::Version,
+    wheel::{install_wheel, verify_hash, InstallScheme, WheelFilename},
     Config, Error, HuakResult,
 };
 
@@ -18,6 +34,46 @@ const DEFAULT_VENV_NAME: &str = ".venv";
 const VENV_CONFIG_FILE_NAME: &str = "pyvenv.cfg";
 const VIRTUAL_ENV_ENV_VAR: &str = "VIRTUAL_ENV";
 const CONDA_ENV_ENV_VAR: &str = "CONDA_PREFIX";
+/// A Python snippet used to print the PEP 508 environment marker values of an `Interpreter`.
+const MARKER_ENVIRONMENT_SCRIPT: &str = r#"
+import json, os, platform, sys
+v = sys.implementation.version
+iv = "{0.major}.{0.minor}.{0.micro}".format(v)
+if v.releaselevel != "final":
+    iv += v.releaselevel[0] + str(v.serial)
+print(json.dumps({
+    "implementation_name": sys.implementation.name,
+    "implementation_version": iv,
+    "os_name": os.name,
+    "platform_machine": platform.machine(),
+    "platform_python_implementation": platform.python_implementation(),
+    "platform_release": platform.release(),
+    "platform_system": platform.system(),
+    "platform_version": platform.version(),
+    "python_full_version": platform.python_version(),
+    "python_version": ".".join(platform.python_version_tuple()[:2]),
+    "sys_platform": sys.platform,
+}))
+"#;
+/// A Python snippet used to print the wheel tags an `Interpreter` supports, from most to
+/// least preferred.
+const SUPPORTED_TAGS_SCRIPT: &str = r#"
+import json
+try:
+    from packaging import tags
+except ImportError:
+    from pip._vendor.packaging import tags
+print(json.dumps([str(t) for t in tags.sys_tags()]))
+"#;
+
+/// A Python snippet used to check if an `Interpreter` runs in a virtual environment and if
+/// its installation is marked EXTERNALLY-MANAGED (PEP 668).
+const ENVIRONMENT_MANAGEMENT_SCRIPT: &str = r#"
+import os, sys, sysconfig
+base_prefix = getattr(sys, "real_prefix", None) or getattr(sys, "base_prefix", sys.prefix)
+marker = os.path.join(sysconfig.get_path("stdlib"), "EXTERNALLY-MANAGED")
+print(sys.prefix != base_prefix, os.path.isfile(marker))
+"#;
 
 /// The `PythonEnvironment` is a struct used to intereact with an environment
 /// containing an installed Python `Interpreter` and `Package`s.
@@ -69,12 +125,35 @@ pub struct PythonEnvironment {
     executables_dir_path: PathBuf,
     /// The site-packages directory contains all of the `PythonEnvironment`'s installed Python packages.
     site_packages_path: PathBuf,
+    /// A cache of the `Package`s found in the site-packages directory. The cache is cleared
+    /// whenever the `PythonEnvironment` installs, uninstalls, or updates `Package`s.
+    installed_packages: RefCell<Option<Vec<Package>>>,
+    /// A cache of the reason the `PythonEnvironment` is externally managed, if it is.
+    externally_managed: RefCell<Option<Option<String>>>,
 }
 
 impl PythonEnvironment {
     /// Initialize a new `PythonEnvironment`.
     pub fn new<T: AsRef<Path>>(path: T) -> HuakResult<Self> {
-        // Note that only virtual environments are supported at this time.
+        // Note that only virtual environments are supported at this time. Python
+        // installations without a pyvenv.cfg (like a system prefix set as VIRTUAL_ENV)
+        // aren't used so they aren't modified.
+        let root = path.as_ref();
+        #[cfg(unix)]
+        let python_paths = [
+            root.join("bin").join("python"),
+            root.join("bin").join("python3"),
+        ];
+        #[cfg(windows)]
+        let python_paths = [root.join("python.exe")];
+        if !root.join(VENV_CONFIG_FILE_NAME).exists()
+            && python_paths.iter().any(|it| it.exists())
+        {
+            return Err(Error::ExternallyManagedEnvironment(format!(
+                "{} is a Python installation, not a virtual environment",
+                root.display()
+            )));
+        }
         if !path.as_ref().join(VENV_CONFIG_FILE_NAME).exists() {
             return Err(Error::Unimplemented(format!(
                 "{} is not supported",
@@ -102,6 +181,12 @@ impl PythonEnvironment {
         self.interpreter.path()
     }
 
+    /// Get a reference to the `Version` of the Python `Interpreter` used by the
+    /// `PythonEnvironment`.
+    pub fn python_version(&self) -> &Version {
+        self.interpreter.version()
+    }
+
     /// Get a reference to the `PythonEnvironment`'s executables directory path.
     pub fn executables_dir_path(&self) -> &PathBuf {
         &self.executables_dir_path
@@ -112,6 +197,51 @@ impl PythonEnvironment {
         &self.site_packages_path
     }
 
+    /// Get the reason the `PythonEnvironment` is externally managed, if it is. Its
+    /// `Interpreter` is externally managed if it isn't running in a virtual environment,
+    /// like a system interpreter, especially if its installation is marked
+    /// EXTERNALLY-MANAGED (PEP 668).
+    pub fn externally_managed(&self) -> HuakResult<Option<String>> {
+        if let Some(it) = self.externally_managed.borrow().as_ref() {
+            return Ok(it.clone());
+        }
+
+        let mut cmd = Command::new(self.python_path());
+        cmd.args(["-c", ENVIRONMENT_MANAGEMENT_SCRIPT]);
+        let output = cmd.output()?;
+        if !output.status.success() {
+            return Err(Error::SubprocessFailure(sys::SubprocessError::new(
+                output.status,
+            )));
+        }
+        let stdout = std::str::from_utf8(&output.stdout)?;
+        let mut values = stdout.split_whitespace();
+        let in_venv = values.next() == Some("True");
+        let marked = values.next() == Some("True");
+        let reason = match (in_venv, marked) {
+            (true, _) => None,
+            (false, true) => Some(format!(
+                "{} is marked EXTERNALLY-MANAGED (PEP 668)",
+                self.python_path().display()
+            )),
+            (false, false) => Some(format!(
+                "{} is a system interpreter, not a virtual environment",
+                self.python_path().display()
+            )),
+        };
+        *self.externally_managed.borrow_mut() = Some(reason.clone());
+
+        Ok(reason)
+    }
+
+    /// Refuse to modify the `PythonEnvironment` if it's externally managed.
+    fn ensure_not_externally_managed(&self) -> HuakResult<()> {
+        match self.externally_managed()? {
+            Some(reason) => Err(Error::ExternallyManagedEnvironment(reason)),
+            None => Ok(()),
+        }
+    }
+
     /// Install Python `Package`s to the `PythonEnvironment`.
     pub fn install_packages<T>(
         &self,
@@ -122,15 +252,21 @@ impl PythonEnvironment {
     where
         T: Display,
     {
-        let mut cmd = Command::new(self.python_path());
-        cmd.args(["-m", "pip", "install"])
-            .args(packages.iter().map(|item| item.to_string()));
+        self.ensure_not_externally_managed()?;
 
+        let mut args = packages
+            .iter()
+            .map(|item| item.to_string())
+            .collect::<Vec<_>>();
         if let Some(v) = options.values.as_ref() {
-            cmd.args(v.iter().map(|item| item.as_str()));
+            args.extend(v.iter().cloned());
         }
+        self.clear_installed_packages();
 
-        config.terminal().run_command(&mut cmd)
+        self.run_pip_install(&args, config)?;
+        // pip only marks the packages it installs, so already installed packages that
+        // are now requested are marked too.
+        self.set_requested(&requested_names(packages), true)
     }
 
     /// Uninstall Python `Package`s from the `PythonEnvironment`.
@@ -143,6 +279,8 @@ impl PythonEnvironment {
     where
         T: Display,
     {
+        self.ensure_not_externally_managed()?;
+
         let mut cmd = Command::new(self.python_path());
         cmd.args(["-m", "pip", "uninstall"])
             .args(packages.iter().map(|item| item.to_string()))
@@ -151,6 +289,7 @@ impl PythonEnvironment {
         if let Some(v) = options.values.as_ref() {
             cmd.args(v.iter().map(|item| item.as_str()));
         }
+        self.clear_installed_packages();
 
         config.terminal().run_command(&mut cmd)
     }
@@ -165,15 +304,290 @@ impl PythonEnvironment {
     where
         T: Display,
     {
-        let mut cmd = Command::new(self.python_path());
-        cmd.args(["-m", "pip", "install", "--upgrade"])
-            .args(packages.iter().map(|item| item.to_string()));
+        self.ensure_not_externally_managed()?;
 
+        let mut args = vec!["--upgrade".to_string()];
+        args.extend(packages.iter().map(|item| item.to_string()));
         if let Some(v) = options.values.as_ref() {
-            cmd.args(v.iter().map(|item| item.as_str()));
+            args.extend(v.iter().cloned());
         }
+        self.clear_installed_packages();
 
-        config.terminal().run_command(&mut cmd)
+        self.run_pip_install(&args, config)?;
+        self.set_requested(&requested_names(packages), true)
+    }
+
+    /// Run `pip install` with `args`, retrying it with an exponential backoff when it fails
+    /// because the package index couldn't be reached. pip's own `--retries`, `--timeout`,
+    /// `--cert`, and `--trusted-host` are set from the `Config`'s `NetworkOptions`, and the
+    /// whole operation fails if it runs longer than their `operation_timeout`.
+    fn run_pip_install(
+        &self,
+        args: &[String],
+        config: &Config,
+    ) -> HuakResult<()> {
+        let options = config.network_options()?;
+        let deadline = options.operation_timeout.map(|it| Instant::now() + it);
+        let timed_out = || {
+            Error::OperationTimedOut(format!(
+                "{}s",
+                options.operation_timeout.unwrap_or_default().as_secs()
+            ))
+        };
+        let mut terminal = config.terminal();
+        let mut attempt = 0;
+
+        loop {
+            let mut cmd = Command::new(self.python_path());
+            cmd.args(["-m", "pip", "install", "--retries"])
+                .arg(options.retries.to_string())
+                .arg("--timeout")
+                .arg(options.timeout.as_secs().max(1).to_string());
+            if let Some(it) = options.ca_bundle() {
+                cmd.arg("--cert").arg(it);
+            }
+            for it in options.trusted_hosts.iter() {
+                cmd.args(["--trusted-host", it]);
+            }
+            cmd.args(args);
+            let timeout =
+                deadline.map(|it| it.saturating_duration_since(Instant::now()));
+            let (status, stderr) = terminal
+                .run_command_with_stderr(&mut cmd, timeout)
+                .map_err(|e| match e {
+                    Error::OperationTimedOut(_) => timed_out(),
+                    e => e,
+                })?;
+            if status.success() {
+                return Ok(());
+            }
+
+            let err = pip_install_error(&stderr).unwrap_or_else(|| {
+                Error::SubprocessFailure(sys::SubprocessError::new(status))
+            });
+            if !matches!(err, Error::InstallerNetworkError(_))
+                || attempt >= options.retries
+            {
+                return Err(err);
+            }
+            let delay = network::backoff(attempt);
+            if deadline.map_or(false, |it| Instant::now() + delay >= it) {
+                return Err(timed_out());
+            }
+            terminal.print_warning(format!(
+                "{err}; retrying in {:.2}s",
+                delay.as_secs_f64()
+            ))?;
+            std::thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    /// Install the packages pinned by a hash-pinned `Lockfile` without pip.
+    ///
+    /// The most preferred compatible wheel for each package is downloaded (or reused from
+    /// huak's cache), verified against its hash, and unpacked into the `PythonEnvironment`.
+    /// Packages already installed at their locked version are skipped, and other installed
+    /// versions are replaced. The packages locked as project requirements are marked as
+    /// explicitly requested so they can be told apart from their dependencies.
+    pub fn install_locked(
+        &self,
+        lockfile: &Lockfile,
+        config: &Config,
+    ) -> HuakResult<()> {
+        self.ensure_not_externally_managed()?;
+        let supported_tags = self.supported_tags()?;
+        let installed = scan_site_packages(self.site_packages_dir_path())?;
+        let cache_dir = fs::cache_dir()?.join("wheels");
+        let marker_env =
+            if lockfile.packages.iter().any(|it| it.markers.is_some()) {
+                Some(self.marker_environment()?)
+            } else {
+                None
+            };
+
+        // Select a wheel for each package that isn't installed at its locked version.
+        let mut wheels = Vec::new();
+        for pkg in lockfile.packages.iter() {
+            // Skip packages locked for other targets.
+            if let (Some(markers), Some(env)) =
+                (pkg.markers.as_ref(), marker_env.as_ref())
+            {
+                if !MarkerTree::from_str(markers)?.evaluate(env, &[]) {
+                    continue;
+                }
+            }
+            let name = canonical_package_name(&pkg.name)?.to_lowercase();
+            let version = pep440_rs::Version::from_str(&pkg.version)
+                .map_err(Error::InvalidVersionString)?;
+            let dist = installed.iter().find(|dist| {
+                canonical_package_name(dist.name())
+                    .map_or(false, |it| it.to_lowercase() == name)
+            });
+            if dist.map_or(false, |it| it.version() == &version) {
+                continue;
+            }
+            let wheel = pkg
+                .wheels
+                .iter()
+                .filter_map(|wheel| {
+                    let file_name =
+                        WheelFilename::from_str(wheel.file_name()).ok()?;
+                    Some((file_name.priority(&supported_tags)?, wheel))
+                })
+                .min_by_key(|(priority, _)| *priority)
+                .map(|(_, wheel)| wheel)
+                .ok_or(Error::WheelNotFound(format!(
+                    "{}=={}",
+                    pkg.name, pkg.version
+                )))?;
+            wheels.push((dist, wheel, cache_dir.join(wheel.file_name())));
+        }
+
+        if wheels.is_empty() {
+            return self.mark_requested(lockfile);
+        }
+        // Lockfiles can come from anywhere, so their hashes are checked against the ones
+        // first seen for the same files too.
+        check_locked_checksums(&lockfile.packages, &mut config.terminal())?;
+
+        // Download any wheels that aren't already cached.
+        let mut downloads = Vec::new();
+        let mut misses = Vec::new();
+        for (_, wheel, path) in wheels.iter() {
+            if !path.exists() || !verify_hash(path, &wheel.hash)? {
+                misses.push(path);
+                // Wheels locked from local directories (pip's `--find-links`) are copied.
+                if let Some(local) = wheel.url.strip_prefix("file://") {
+                    std::fs::create_dir_all(&cache_dir)?;
+                    std::fs::copy(local, path)?;
+                    continue;
+                }
+                downloads.push(Download {
+                    url: wheel.url.clone(),
+                    path: path.clone(),
+                });
+            }
+        }
+        if !downloads.is_empty() {
+            config.client()?.download_all(downloads)?;
+        }
+        for (_, wheel, path) in wheels.iter() {
+            if !verify_hash(path, &wheel.hash)? {
+                std::fs::remove_file(path)?;
+                return Err(Error::HashMismatch(path.clone()));
+            }
+        }
+
+        for (dist, _, _) in wheels.iter() {
+            if let Some(it) = dist {
+                it.remove()?;
+            }
+        }
+        self.clear_installed_packages();
+
+        // Unpack the wheels in parallel.
+        let scheme = InstallScheme {
+            purelib: self.site_packages_dir_path().clone(),
+            platlib: self.site_packages_dir_path().clone(),
+            scripts: self.executables_dir_path().clone(),
+            data: self.root.clone(),
+            headers: self.root.join("include").join("site"),
+            python: self.python_path().clone(),
+        };
+        std::thread::scope(|scope| {
+            let handles = wheels
+                .iter()
+                .map(|(_, _, path)| {
+                    let scheme = &scheme;
+                    scope.spawn(move || install_wheel(path, scheme))
+                })
+                .collect::<Vec<_>>();
+            handles.into_iter().try_for_each(|handle| {
+                handle.join().map_err(|_| {
+                    Error::InternalError(
+                        "a wheel installation panicked".to_string(),
+                    )
+                })?
+            })
+        })?;
+        self.mark_requested(lockfile)?;
+        cache::record_cache_use(
+            wheels
+                .iter()
+                .map(|(_, _, path)| (path.as_path(), !misses.contains(&path))),
+        )?;
+
+        config.terminal().print_custom(
+            "Installed",
+            format!("{} locked package(s)", wheels.len()),
+            Color::Green,
+            true,
+        )
+    }
+
+    /// Mark the installed packages locked by a `Lockfile` as explicitly requested when
+    /// they're one of its project requirements, and unmark the packages only locked as
+    /// dependencies of others.
+    pub fn mark_requested(&self, lockfile: &Lockfile) -> HuakResult<()> {
+        let requested = lockfile
+            .requirements
+            .iter()
+            .filter_map(|it| Requirement::from_str(it).ok())
+            .filter_map(|it| canonical_package_name(&it.name).ok())
+            .map(|it| it.to_lowercase())
+            .collect::<Vec<_>>();
+        let (requested, dependencies): (Vec<_>, Vec<_>) = lockfile
+            .packages
+            .iter()
+            .filter_map(|it| canonical_package_name(&it.name).ok())
+            .map(|it| it.to_lowercase())
+            .partition(|it| requested.contains(it));
+        self.set_requested(&requested, true)?;
+        self.set_requested(&dependencies, false)
+    }
+
+    /// Mark the installed packages named `names` as explicitly requested, or unmark them
+    /// so that they're reported as dependencies of other packages (see
+    /// `DistInfo::requested`). Packages that aren't installed are ignored.
+    pub fn set_requested<T: AsRef<str>>(
+        &self,
+        names: &[T],
+        requested: bool,
+    ) -> HuakResult<()> {
+        if names.is_empty() {
+            return Ok(());
+        }
+        let names = names
+            .iter()
+            .map(|it| {
+                canonical_package_name(it.as_ref()).map(|it| it.to_lowercase())
+            })
+            .collect::<HuakResult<Vec<_>>>()?;
+        for dist in scan_site_packages(self.site_packages_dir_path())? {
+            let name = canonical_package_name(dist.name())?.to_lowercase();
+            if names.contains(&name) {
+                dist.set_requested(requested)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the wheel tags supported by the `PythonEnvironment`'s `Interpreter`, ordered
+    /// from most to least preferred.
+    pub fn supported_tags(&self) -> HuakResult<Vec<String>> {
+        let mut cmd = Command::new(self.python_path());
+        cmd.args(["-c", SUPPORTED_TAGS_SCRIPT]);
+
+        let output = cmd.output()?;
+        if !output.status.success() {
+            return Err(Error::SubprocessFailure(sys::SubprocessError::new(
+                output.status,
+            )));
+        }
+
+        Ok(serde_json::from_str(std::str::from_utf8(&output.stdout)?)?)
     }
 
     /// Check if the `PythonEnvironment` has a module installed in the executables directory.
@@ -200,20 +614,72 @@ impl PythonEnvironment {
     }
 
     /// Get all of the `Package`s installed in the `PythonEnvironment`.
+    ///
+    /// `Package`s are read from the site-packages directory's distribution metadata and
+    /// cached for the lifetime of the `PythonEnvironment`.
     pub fn installed_packages(&self) -> HuakResult<Vec<Package>> {
+        if let Some(it) = self.installed_packages.borrow().as_ref() {
+            return Ok(it.clone());
+        }
+
+        let packages = scan_site_packages(self.site_packages_dir_path())?
+            .iter()
+            .map(|dist| {
+                Package::from_str(&format!(
+                    "{}=={}",
+                    dist.name(),
+                    dist.version()
+                ))
+            })
+            .collect::<HuakResult<Vec<_>>>()?;
+        *self.installed_packages.borrow_mut() = Some(packages.clone());
+
+        Ok(packages)
+    }
+
+    /// Clear the cache of installed `Package`s.
+    fn clear_installed_packages(&self) {
+        self.installed_packages.borrow_mut().take();
+    }
+
+    /// Get the PEP 508 `MarkerEnvironment` of the `PythonEnvironment`'s `Interpreter`.
+    pub fn marker_environment(&self) -> HuakResult<MarkerEnvironment> {
         let mut cmd = Command::new(self.python_path());
-        cmd.args(["-m", "pip", "freeze"]);
+        cmd.args(["-c", MARKER_ENVIRONMENT_SCRIPT]);
 
         let output = cmd.output()?;
-        let output = sys::parse_command_output(output)?;
-        let mut packages = Vec::new();
-        for line in output.split('\n') {
-            if !line.is_empty() {
-                packages.push(Package::from_str(line)?);
-            }
-        }
+        let values: HashMap<String, String> =
+            serde_json::from_str(std::str::from_utf8(&output.stdout)?)?;
+        let get = |key: &str| -> HuakResult<String> {
+            values.get(key).cloned().ok_or(Error::InternalError(format!(
+                "failed to resolve environment marker {key}"
+            )))
+        };
+        let versioned =
+            |key: &str| -> HuakResult<(String, pep440_rs::Version)> {
+                let value = get(key)?;
+                let version = pep440_rs::Version::from_str(&value)
+                    .map_err(Error::InvalidVersionString)?;
+                Ok((value, version))
+            };
+
+        let env = MarkerEnvironment {
+            implementation_name: get("implementation_name")?,
+            implementation_version: versioned("implementation_version")?,
+            os_name: get("os_name")?,
+            platform_machine: get("platform_machine")?,
+            platform_python_implementation: get(
+                "platform_python_implementation",
+            )?,
+            platform_release: get("platform_release")?,
+            platform_system: get("platform_system")?,
+            platform_version: get("platform_version")?,
+            python_full_version: versioned("python_full_version")?,
+            python_version: versioned("python_version")?,
+            sys_platform: get("sys_platform")?,
+        };
 
-        Ok(packages)
+        Ok(env)
     }
 
     /// Check if the `PythonEnvironment` is already activated.
@@ -256,9 +722,20 @@ fn new_venv<T: AsRef<Path>>(path: T) -> HuakResult<PythonEnvironment> {
     #[cfg(windows)]
     let site_packages_path = root.join("Lib").join("site-packages");
 
+    // The environment's python is usually a symlink to the interpreter that created it.
+    let implementation = python_path
+        .canonicalize()
+        .ok()
+        .and_then(|it| {
+            it.file_name()
+                .and_then(|it| it.to_str())
+                .and_then(implementation_from_file_name)
+        })
+        .unwrap_or_default();
     let interpreter = Interpreter {
         version,
         path: python_path,
+        implementation,
     };
 
     let venv = PythonEnvironment {
@@ -266,6 +743,8 @@ fn new_venv<T: AsRef<Path>>(path: T) -> HuakResult<PythonEnvironment> {
         interpreter,
         executables_dir_path,
         site_packages_path,
+        installed_packages: RefCell::new(None),
+        externally_managed: RefCell::new(None),
     };
 
     Ok(venv)
@@ -278,6 +757,83 @@ pub struct InstallOptions {
     pub values: Option<Vec<String>>,
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Options for creating virtual environments, which can be set in the `[tool.huak.venv]`
+/// table.
+///
+/// ```toml
+/// [tool.huak.venv]
+/// copies = true
+/// system-site-packages = true
+/// without-pip = false
+/// install-dependencies = true
+/// ```
+pub struct VenvOptions {
+    /// Copy the Python executable into the environment instead of symlinking it.
+    pub copies: bool,
+    /// Give the environment access to the interpreter's site-packages.
+    pub system_site_packages: bool,
+    /// Don't install pip into the environment.
+    pub without_pip: bool,
+    /// Install the project's dependencies into a newly created environment.
+    pub install_dependencies: bool,
+}
+
+impl VenvOptions {
+    /// Read the `[tool.huak.venv]` table.
+    pub fn from_metadata(metadata: &Metadata) -> HuakResult<VenvOptions> {
+        let setting = |key: &str| match metadata.huak_setting("venv", key) {
+            None => Ok(false),
+            Some(it) => it.as_bool().ok_or_else(|| {
+                Error::HuakConfigurationError(format!(
+                    "[tool.huak.venv] {key} must be a boolean"
+                ))
+            }),
+        };
+
+        Ok(VenvOptions {
+            copies: setting("copies")?,
+            system_site_packages: setting("system-site-packages")?,
+            without_pip: setting("without-pip")?,
+            install_dependencies: setting("install-dependencies")?,
+        })
+    }
+
+    /// Combine two `VenvOptions`, enabling each option enabled by either.
+    pub fn union(&self, other: &VenvOptions) -> VenvOptions {
+        VenvOptions {
+            copies: self.copies || other.copies,
+            system_site_packages: self.system_site_packages
+                || other.system_site_packages,
+            without_pip: self.without_pip || other.without_pip,
+            install_dependencies: self.install_dependencies
+                || other.install_dependencies,
+        }
+    }
+
+    /// Get a `Command` creating a virtual environment named `name` with the `venv` module
+    /// of a Python `Interpreter`.
+    pub fn venv_command<T: AsRef<Path>>(
+        &self,
+        python_path: T,
+        name: &str,
+    ) -> Command {
+        let mut cmd = Command::new(python_path.as_ref());
+        cmd.args(["-m", "venv", name]);
+        if self.copies {
+            cmd.arg("--copies");
+        }
+        if self.system_site_packages {
+            cmd.arg("--system-site-packages");
+        }
+        if self.without_pip {
+            cmd.arg("--without-pip");
+        }
+
+        cmd
+    }
+}
+
 /// Python virtual environment configuration data (pyvenv.cfg).
 ///
 /// See https://docs.python.org/3/library/venv.html.
@@ -294,7 +850,10 @@ impl VenvConfig {
             panic!("failed to open {}", value.as_ref().display())
         });
         let buff_reader = BufReader::new(file);
-        let lines = buff_reader.lines().flatten().collect::<Vec<String>>();
+        let lines = buff_reader
+            .lines()
+            .map_while(Result::ok)
+            .collect::<Vec<String>>();
 
         // Search for version = "X.X.X"
         let mut version = Version::from_str("0.0.0");
@@ -345,7 +904,6 @@ impl Interpreters {
         &self.interpreters
     }
 
-    #[allow(dead_code)]
     /// Get the latest Python `Interpreter` by `Version`.
     pub fn latest(&self) -> Option<&Interpreter> {
         self.interpreters.iter().max()
@@ -368,7 +926,7 @@ impl Interpreters {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// The Python `Interpreter` is used to interact with installed Python `Interpreter`s.
 ///
 /// `Interpreter` contains information like the `Interpreter`'s path, `Version`, etc.
@@ -383,18 +941,38 @@ pub struct Interpreter {
     version: Version,
     /// The absolute path to the Python `Interpreter`.
     path: PathBuf,
+    /// The `PythonImplementation` of the Python `Interpreter`.
+    implementation: PythonImplementation,
 }
 
 impl Interpreter {
+    /// Initialize a new `Interpreter`. Its `PythonImplementation` is inferred from its
+    /// file name (for example pypy3.10).
     pub fn new<T: AsRef<Path>>(path: T, version: Version) -> Interpreter {
+        let implementation = path
+            .as_ref()
+            .file_name()
+            .and_then(|it| it.to_str())
+            .and_then(implementation_from_file_name)
+            .unwrap_or_default();
         let interpreter = Interpreter {
             version,
             path: path.as_ref().to_path_buf(),
+            implementation,
         };
 
         interpreter
     }
 
+    /// Set the `PythonImplementation` of the `Interpreter`.
+    pub fn with_implementation(
+        mut self,
+        implementation: PythonImplementation,
+    ) -> Interpreter {
+        self.implementation = implementation;
+        self
+    }
+
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
@@ -402,11 +980,139 @@ impl Interpreter {
     pub fn version(&self) -> &Version {
         &self.version
     }
+
+    pub fn implementation(&self) -> PythonImplementation {
+        self.implementation
+    }
 }
 
 impl Display for Interpreter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}) {}", self.version(), self.path().display())
+        match self.implementation {
+            PythonImplementation::CPython => {
+                write!(f, "({}) {}", self.version(), self.path().display())
+            }
+            it => {
+                write!(f, "({it} {}) {}", self.version(), self.path().display())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The implementation of a Python `Interpreter`.
+pub enum PythonImplementation {
+    #[default]
+    CPython,
+    PyPy,
+    GraalPy,
+}
+
+impl PythonImplementation {
+    /// Get the prefix of the implementation's executable file names (for example pypy3.10).
+    fn executable_prefix(&self) -> &'static str {
+        match self {
+            PythonImplementation::CPython => "python",
+            PythonImplementation::PyPy => "pypy",
+            PythonImplementation::GraalPy => "graalpy",
+        }
+    }
+}
+
+impl FromStr for PythonImplementation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cpython" | "python" | "cp" => Ok(PythonImplementation::CPython),
+            "pypy" | "pp" => Ok(PythonImplementation::PyPy),
+            "graalpy" | "gp" => Ok(PythonImplementation::GraalPy),
+            _ => Err(Error::InvalidVersionString(format!(
+                "{s} is not a supported Python implementation"
+            ))),
+        }
+    }
+}
+
+impl Display for PythonImplementation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PythonImplementation::CPython => "cpython",
+            PythonImplementation::PyPy => "pypy",
+            PythonImplementation::GraalPy => "graalpy",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A request for a Python `Interpreter` such as "3.10", "pypy@3.10", or "pypy3.10". The
+/// `Version` only needs to start with the requested release, and requests without an
+/// implementation are for CPython.
+pub struct PythonRequest {
+    implementation: PythonImplementation,
+    release: Vec<usize>,
+}
+
+impl PythonRequest {
+    /// Check if an `Interpreter` satisfies the request.
+    pub fn matches(&self, interpreter: &Interpreter) -> bool {
+        interpreter.implementation() == self.implementation
+            && interpreter.version().release().starts_with(&self.release)
+    }
+}
+
+impl FromStr for PythonRequest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidVersionString(s.to_string());
+        let s = s.trim();
+        // Split names like pypy@3.10 or pypy3.10 (and pyenv's pypy3.10-7.3.12).
+        let (implementation, version) = match s.split_once('@') {
+            Some((name, version)) => (name.parse()?, version),
+            None => {
+                let i = s.find(|c: char| c.is_ascii_digit()).unwrap_or(s.len());
+                let implementation = match &s[..i] {
+                    "" => PythonImplementation::default(),
+                    name => name.parse()?,
+                };
+                let version = s[i..].split('-').next().unwrap_or_default();
+                (implementation, version)
+            }
+        };
+        let release = match version {
+            "" => Vec::new(),
+            it => it
+                .split('.')
+                .map(str::parse::<usize>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?,
+        };
+        if release.len() > 3 {
+            return Err(invalid());
+        }
+
+        Ok(PythonRequest {
+            implementation,
+            release,
+        })
+    }
+}
+
+impl Display for PythonRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let release = self
+            .release
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+        match self.implementation {
+            PythonImplementation::CPython => write!(f, "{release}"),
+            it if release.is_empty() => write!(f, "{it}"),
+            it => write!(f, "{it}@{release}"),
+        }
     }
 }
 
@@ -460,6 +1166,63 @@ pub fn active_conda_env_path() -> Option<PathBuf> {
     None
 }
 
+/// Get the names of the packages requested by requirement strings. Requirements that
+/// aren't named, like paths to local archives, are skipped.
+fn requested_names<T: Display>(packages: &[T]) -> Vec<String> {
+    packages
+        .iter()
+        .filter_map(|it| Requirement::from_str(&it.to_string()).ok())
+        .map(|it| it.name)
+        .collect()
+}
+
+/// Get the `Error` describing why `pip install` failed from its stderr. Network failures
+/// are checked first since pip also reports unreachable packages as unresolvable.
+fn pip_install_error(stderr: &str) -> Option<Error> {
+    // pip's network failures and how they're described, most specific first.
+    const NETWORK_FAILURES: [(&str, &str); 10] = [
+        (
+            "Temporary failure in name resolution",
+            "name resolution failed",
+        ),
+        ("Name or service not known", "name resolution failed"),
+        ("Connection refused", "the connection was refused"),
+        ("ProxyError", "the proxy connection failed"),
+        ("SSLError", "the TLS connection failed"),
+        ("ConnectTimeoutError", "the connection timed out"),
+        ("ReadTimeoutError", "the read timed out"),
+        ("NewConnectionError", "the connection failed"),
+        ("Max retries exceeded", "the connection failed"),
+        ("Could not fetch URL", "a URL couldn't be fetched"),
+    ];
+    const RESOLUTION_FAILURES: [&str; 4] = [
+        "Could not find a version that satisfies",
+        "No matching distribution found",
+        "ResolutionImpossible",
+        "conflicting dependencies",
+    ];
+
+    for (pattern, description) in NETWORK_FAILURES {
+        if let Some(line) = stderr.lines().find(|it| it.contains(pattern)) {
+            // pip's retry warnings end with the path that was requested.
+            return Some(Error::InstallerNetworkError(
+                match line.rsplit_once("': /") {
+                    Some((_, path)) => format!("{description} (/{path})"),
+                    None => description.to_string(),
+                },
+            ));
+        }
+    }
+    stderr
+        .lines()
+        .find(|line| RESOLUTION_FAILURES.iter().any(|it| line.contains(it)))
+        .map(|line| {
+            Error::ResolutionError(
+                sys::trim_error_prefix(line.trim()).to_string(),
+            )
+        })
+}
+
 pub fn venv_config_file_name() -> &'static str {
     VENV_CONFIG_FILE_NAME
 }
@@ -471,8 +1234,7 @@ pub fn default_venv_name() -> &'static str {
 /// Get an `Iterator` over available Python `Interpreter` paths parsed from the `PATH`
 /// environment variable (inspired by brettcannon/python-launcher).
 pub fn python_paths() -> impl Iterator<Item = (Option<Version>, PathBuf)> {
-    let paths =
-        fs::flatten_directories(env_path_values().unwrap_or(Vec::new()));
+    let paths = fs::flatten_directories(env_path_values().unwrap_or_default());
 
     python_interpreters_in_paths(paths)
 }
@@ -511,6 +1273,25 @@ fn python_interpreters_in_paths(
     })
 }
 
+/// Get the `PythonImplementation` an `Interpreter`'s file name is for.
+fn implementation_from_file_name(
+    file_name: &str,
+) -> Option<PythonImplementation> {
+    [
+        PythonImplementation::CPython,
+        PythonImplementation::PyPy,
+        PythonImplementation::GraalPy,
+    ]
+    .into_iter()
+    .find(|it| file_name.starts_with(it.executable_prefix()))
+}
+
+/// Strip the implementation's prefix from an `Interpreter`'s file name (python3.11 -> 3.11).
+fn strip_implementation_prefix(file_name: &str) -> Option<&str> {
+    implementation_from_file_name(file_name)
+        .map(|it| &file_name[it.executable_prefix().len()..])
+}
+
 #[cfg(unix)]
 /// A function for checking if a Python `Interpreter`'s file name is valid.
 fn valid_python_interpreter_file_name(file_name: &str) -> bool {
@@ -518,28 +1299,22 @@ fn valid_python_interpreter_file_name(file_name: &str) -> bool {
         return true;
     }
 
-    if !file_name.starts_with("python") {
+    let Some(version) = strip_implementation_prefix(file_name) else {
         return false;
-    }
+    };
 
-    file_name.len() >= "python3.0".len()
-        && file_name["python".len()..].parse::<f32>().is_ok()
+    version.len() >= "3.0".len() && version.parse::<f32>().is_ok()
 }
 
 #[cfg(windows)]
 /// A function for checking if a Python `Interpreter`'s file name is valid.
 fn valid_python_interpreter_file_name(file_name: &str) -> bool {
-    if file_name == "python.exe" || file_name == "python" {
-        return true;
-    }
-
-    if !file_name.starts_with("python") {
-        return false;
-    }
-
     let name = file_name.strip_suffix(".exe").unwrap_or(file_name);
+    let Some(version) = strip_implementation_prefix(name) else {
+        return false;
+    };
 
-    name.len() > "python".len() && name["python".len()..].parse::<f32>().is_ok()
+    version.is_empty() || version.parse::<f32>().is_ok()
 }
 
 /// Parse the `Version` from a Python `Interpreter`'s file name.
@@ -549,16 +1324,52 @@ fn valid_python_interpreter_file_name(file_name: &str) -> bool {
 fn version_from_python_interpreter_file_name(
     file_name: &str,
 ) -> HuakResult<Version> {
-    match OS {
-        "windows" => Version::from_str(
-            &file_name.strip_suffix(".exe").unwrap_or(file_name)
-                ["python".len()..],
-        ),
-        _ => Version::from_str(&file_name["python".len()..]),
-    }
-    .map_err(|_| {
-        Error::InternalError(format!("could not version from {file_name}"))
-    })
+    let name = match OS {
+        "windows" => file_name.strip_suffix(".exe").unwrap_or(file_name),
+        _ => file_name,
+    };
+    strip_implementation_prefix(name)
+        .ok_or_else(|| Error::InvalidVersionString(name.to_string()))
+        .and_then(Version::from_str)
+        .map_err(|_| {
+            Error::InternalError(format!("could not version from {file_name}"))
+        })
+}
+
+/// Parse the `PythonImplementation` and `Version` of a Python `Interpreter` from its
+/// `--version` output.
+pub fn parse_python_from_command<T: AsRef<Path>>(
+    path: T,
+) -> HuakResult<Option<(PythonImplementation, Version)>> {
+    let mut cmd = Command::new(path.as_ref());
+    cmd.arg("--version");
+    let output = sys::parse_command_output(cmd.output()?)?;
+
+    Ok(parse_python_version_output(&output))
+}
+
+/// Parse `--version` output like "Python 3.10.12 (...)\n[PyPy 7.3.12 ...]". Pre-release
+/// suffixes are dropped from the `Version`.
+fn parse_python_version_output(
+    output: &str,
+) -> Option<(PythonImplementation, Version)> {
+    let implementation = if output.contains("PyPy") {
+        PythonImplementation::PyPy
+    } else if output.contains("GraalPy") || output.contains("GraalVM") {
+        PythonImplementation::GraalPy
+    } else {
+        PythonImplementation::CPython
+    };
+    let mut words = output.split_whitespace();
+    words.find(|it| *it == "Python" || *it == "GraalPy")?;
+    let version = words.next()?;
+    let end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    let version =
+        Version::from_str(version[..end].trim_end_matches('.')).ok()?;
+
+    Some((implementation, version))
 }
 
 pub fn parse_python_version_from_command<T: AsRef<Path>>(
@@ -588,13 +1399,14 @@ mod tests {
     #[test]
     fn python_environment_executables_dir_name() {
         let dir = tempdir().unwrap();
-        let config = Config {
-            workspace_root: dir.path().to_path_buf(),
-            cwd: dir.path().to_path_buf(),
-            terminal_options: TerminalOptions {
+        let config = Config::new(
+            dir.path().to_path_buf(),
+            dir.path().to_path_buf(),
+            TerminalOptions {
                 verbosity: sys::Verbosity::Quiet,
+                assume_yes: true,
             },
-        };
+        );
         let ws = config.workspace();
         let venv = ws.resolve_python_environment().unwrap();
 
@@ -617,7 +1429,7 @@ mod tests {
     fn python_search() {
         let dir = tempdir().unwrap();
         std::fs::write(dir.path().join("python3.11"), "").unwrap();
-        let path_vals = vec![dir.path().to_str().unwrap().to_string()];
+        let path_vals = [dir.path().to_str().unwrap().to_string()];
         std::env::set_var("PATH", path_vals.join(":"));
         let mut interpreter_paths = python_paths();
 
@@ -641,4 +1453,154 @@ mod tests {
             dir.path().join("python.exe")
         );
     }
+
+    #[test]
+    fn test_python_request() {
+        let request = PythonRequest::from_str("pypy@3.10").unwrap();
+        let pypy = Interpreter::new(
+            "/usr/bin/pypy3.10",
+            Version::from_str("3.10.12").unwrap(),
+        );
+        let cpython = Interpreter::new(
+            "/usr/bin/python3.10",
+            Version::from_str("3.10.12").unwrap(),
+        );
+
+        assert_eq!(pypy.implementation(), PythonImplementation::PyPy);
+        assert!(request.matches(&pypy));
+        assert!(!request.matches(&cpython));
+        assert_eq!(
+            request,
+            PythonRequest::from_str("pypy3.10-7.3.12").unwrap()
+        );
+        assert_eq!(request.to_string(), "pypy@3.10");
+        assert!(PythonRequest::from_str("3.10").unwrap().matches(&cpython));
+        assert!(PythonRequest::from_str("jython@2.7").is_err());
+    }
+
+    #[test]
+    fn test_parse_python_version_output() {
+        assert_eq!(
+            parse_python_version_output(
+                "Python 3.10.12 (af44d0b8114c, Jun 15 2023, 15:28:58)\n[PyPy 7.3.12 with GCC 10.2.1]"
+            ),
+            Some((
+                PythonImplementation::PyPy,
+                Version::from_str("3.10.12").unwrap()
+            ))
+        );
+        assert_eq!(
+            parse_python_version_output("Python 3.13.0rc1\n"),
+            Some((
+                PythonImplementation::CPython,
+                Version::from_str("3.13.0").unwrap()
+            ))
+        );
+        assert!(valid_python_interpreter_file_name("pypy3.10"));
+        assert!(!valid_python_interpreter_file_name("pypy-config"));
+    }
+
+    #[test]
+    fn test_pip_install_error() {
+        let offline = "WARNING: Retrying (Retry(total=0, connect=None, read=None, redirect=None, status=None)) after connection broken by 'NewConnectionError('<pip._vendor.urllib3.connection.HTTPSConnection object>: Failed to establish a new connection: [Errno -3] Temporary failure in name resolution')': /simple/requests/
+ERROR: Could not find a version that satisfies the requirement requests (from versions: none)
+ERROR: No matching distribution found for requests
+";
+        let unresolvable = "ERROR: Could not find a version that satisfies the requirement requests==0.0.0 (from versions: 2.30.0, 2.31.0)
+ERROR: No matching distribution found for requests==0.0.0
+";
+
+        assert!(matches!(
+            pip_install_error(offline),
+            Some(Error::InstallerNetworkError(it))
+                if it == "name resolution failed (/simple/requests/)"
+        ));
+        assert!(matches!(
+            pip_install_error(unresolvable),
+            Some(Error::ResolutionError(it)) if it.starts_with("Could not find a version")
+        ));
+        assert!(pip_install_error("ERROR: setup.py failed").is_none());
+    }
+
+    #[test]
+    fn test_venv_options() {
+        let metadata: Metadata = toml::from_str(
+            r#"
+[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "mock-project"
+
+[tool.huak.venv]
+copies = true
+install-dependencies = true
+"#,
+        )
+        .unwrap();
+        let options = VenvOptions::from_metadata(&metadata).unwrap();
+        let cmd = options
+            .union(&VenvOptions {
+                without_pip: true,
+                ..Default::default()
+            })
+            .venv_command("python", ".venv");
+
+        assert!(options.install_dependencies);
+        assert_eq!(
+            cmd.get_args().collect::<Vec<_>>(),
+            ["-m", "venv", ".venv", "--copies", "--without-pip"]
+        );
+
+        let metadata: Metadata = toml::from_str(
+            "[build-system]\nrequires = []\n[project]\nname = \"mock-project\"\n[tool.huak.venv]\ncopies = \"yes\"\n",
+        )
+        .unwrap();
+        assert!(VenvOptions::from_metadata(&metadata).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_externally_managed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        // A fake interpreter reporting that it isn't running in a virtual environment and
+        // that its installation is marked EXTERNALLY-MANAGED.
+        let root = dir.path().join(".venv");
+        std::fs::create_dir_all(root.join("bin")).unwrap();
+        std::fs::write(root.join(VENV_CONFIG_FILE_NAME), "version = 3.11.2\n")
+            .unwrap();
+        let script = root.join("bin").join("python");
+        std::fs::write(&script, "#!/bin/sh\necho False True\n").unwrap();
+        std::fs::set_permissions(
+            &script,
+            std::fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+        let env = PythonEnvironment::new(&root).unwrap();
+        let config = Config::new(
+            dir.path().to_path_buf(),
+            dir.path().to_path_buf(),
+            TerminalOptions {
+                verbosity: sys::Verbosity::Quiet,
+                assume_yes: true,
+            },
+        );
+
+        assert!(env
+            .externally_managed()
+            .unwrap()
+            .unwrap()
+            .contains("EXTERNALLY-MANAGED"));
+        assert!(matches!(
+            env.install_packages(
+                &["six"],
+                &InstallOptions { values: None },
+                &config
+            ),
+            Err(Error::ExternallyManagedEnvironment(_))
+        ));
+    }
 }
@@ -0,0 +1,624 @@
+use crate::metadata::Metadata;
+use std::fmt::Display;
+use toml::{Table, Value};
+
+/// The tools with a bundled schema for their `[tool.<name>]` table.
+const SCHEMA_TOOLS: [&str; 4] = ["huak", "mypy", "pytest", "ruff"];
+
+/// The keys huak reads from its `[tool.huak]` table.
+const HUAK_SCHEMA: [(&str, Kind); 40] = [
+    ("exclude", Kind::Array),
+    ("isolated-tools", Kind::Bool),
+    ("changelog.breaking", Kind::String),
+    ("changelog.groups", Kind::Table),
+    ("changelog.pr-url", Kind::String),
+    ("ci.steps", Kind::Array),
+    ("constraints", Kind::Table),
+    ("env.*", Kind::Table),
+    ("fmt.docstrings", Kind::Bool),
+    ("fmt.notebooks", Kind::Bool),
+    ("hooks.git", Kind::Table),
+    ("install.no-binary", Kind::Array),
+    ("install.only-binary", Kind::Array),
+    ("lint.allowlist", Kind::String),
+    ("lint.dead-code", Kind::Bool),
+    ("lint.docstrings", Kind::Bool),
+    ("lint.notebooks", Kind::Bool),
+    ("network.ca-bundle", Kind::String),
+    ("network.connect-timeout", Kind::Integer),
+    ("network.operation-timeout", Kind::Integer),
+    ("network.retries", Kind::Integer),
+    ("network.timeout", Kind::Integer),
+    ("network.trusted-hosts", Kind::Array),
+    ("run.allowed", Kind::Array),
+    ("run.denied", Kind::Array),
+    ("scripts-dir", Kind::String),
+    ("security.severity", Kind::String),
+    ("security.skip", Kind::Array),
+    ("serve.args", Kind::Array),
+    ("serve.paths", Kind::Array),
+    ("serve.target", Kind::String),
+    ("serve.watch", Kind::Bool),
+    ("test.runner", Kind::String),
+    ("tools", Kind::Table),
+    ("venv.copies", Kind::Bool),
+    ("venv.install-dependencies", Kind::Bool),
+    ("venv.system-site-packages", Kind::Bool),
+    ("venv.without-pip", Kind::Bool),
+    ("workspace.dev-dependencies", Kind::Array),
+    ("workspace.members", Kind::Array),
+];
+
+/// The global options of mypy's `[tool.mypy]` table. Per-module options are given in
+/// `overrides`, which isn't checked.
+const MYPY_SCHEMA: [(&str, Kind); 76] = [
+    ("allow_redefinition", Kind::Bool),
+    ("allow_untyped_globals", Kind::Bool),
+    ("always_false", Kind::StringOrArray),
+    ("always_true", Kind::StringOrArray),
+    ("cache_dir", Kind::String),
+    ("cache_fine_grained", Kind::Bool),
+    ("check_untyped_defs", Kind::Bool),
+    ("color_output", Kind::Bool),
+    ("custom_typeshed_dir", Kind::String),
+    ("custom_typing_module", Kind::String),
+    ("disable_error_code", Kind::StringOrArray),
+    ("disallow_any_decorated", Kind::Bool),
+    ("disallow_any_explicit", Kind::Bool),
+    ("disallow_any_expr", Kind::Bool),
+    ("disallow_any_generics", Kind::Bool),
+    ("disallow_any_unimported", Kind::Bool),
+    ("disallow_incomplete_defs", Kind::Bool),
+    ("disallow_subclassing_any", Kind::Bool),
+    ("disallow_untyped_calls", Kind::Bool),
+    ("disallow_untyped_decorators", Kind::Bool),
+    ("disallow_untyped_defs", Kind::Bool),
+    ("enable_error_code", Kind::StringOrArray),
+    ("enable_incomplete_feature", Kind::StringOrArray),
+    ("error_summary", Kind::Bool),
+    ("exclude", Kind::StringOrArray),
+    ("explicit_package_bases", Kind::Bool),
+    ("extra_checks", Kind::Bool),
+    ("files", Kind::StringOrArray),
+    ("follow_imports", Kind::String),
+    ("follow_imports_for_stubs", Kind::Bool),
+    ("hide_error_codes", Kind::Bool),
+    ("ignore_errors", Kind::Bool),
+    ("ignore_missing_imports", Kind::Bool),
+    ("implicit_optional", Kind::Bool),
+    ("implicit_reexport", Kind::Bool),
+    ("incremental", Kind::Bool),
+    ("install_types", Kind::Bool),
+    ("junit_xml", Kind::String),
+    ("local_partial_types", Kind::Bool),
+    ("modules", Kind::StringOrArray),
+    ("mypy_path", Kind::StringOrArray),
+    ("namespace_packages", Kind::Bool),
+    ("no_implicit_optional", Kind::Bool),
+    ("no_implicit_reexport", Kind::Bool),
+    ("no_site_packages", Kind::Bool),
+    ("non_interactive", Kind::Bool),
+    ("overrides", Kind::Array),
+    ("packages", Kind::StringOrArray),
+    ("pdb", Kind::Bool),
+    ("platform", Kind::String),
+    ("plugins", Kind::StringOrArray),
+    ("pretty", Kind::Bool),
+    ("python_executable", Kind::String),
+    ("python_version", Kind::Any),
+    ("raise_exceptions", Kind::Bool),
+    ("scripts_are_modules", Kind::Bool),
+    ("show_absolute_path", Kind::Bool),
+    ("show_column_numbers", Kind::Bool),
+    ("show_error_code_links", Kind::Bool),
+    ("show_error_codes", Kind::Bool),
+    ("show_error_context", Kind::Bool),
+    ("show_traceback", Kind::Bool),
+    ("skip_cache_mtime_checks", Kind::Bool),
+    ("skip_version_check", Kind::Bool),
+    ("sqlite_cache", Kind::Bool),
+    ("strict", Kind::Bool),
+    ("strict_concatenate", Kind::Bool),
+    ("strict_equality", Kind::Bool),
+    ("strict_optional", Kind::Bool),
+    ("warn_incomplete_stub", Kind::Bool),
+    ("warn_no_return", Kind::Bool),
+    ("warn_redundant_casts", Kind::Bool),
+    ("warn_return_any", Kind::Bool),
+    ("warn_unreachable", Kind::Bool),
+    ("warn_unused_configs", Kind::Bool),
+    ("warn_unused_ignores", Kind::Bool),
+];
+
+/// The options pytest reads from `[tool.pytest.ini_options]`. Values are loosely typed
+/// since pytest accepts strings for most of them. Plugins add their own options, so
+/// unknown options may still be valid.
+const PYTEST_SCHEMA: [(&str, Kind); 38] = [
+    ("ini_options.addopts", Kind::StringOrArray),
+    ("ini_options.cache_dir", Kind::String),
+    ("ini_options.console_output_style", Kind::String),
+    ("ini_options.consider_namespace_packages", Kind::Any),
+    ("ini_options.doctest_encoding", Kind::String),
+    ("ini_options.doctest_optionflags", Kind::StringOrArray),
+    ("ini_options.empty_parameter_set_mark", Kind::String),
+    ("ini_options.faulthandler_timeout", Kind::Any),
+    ("ini_options.filterwarnings", Kind::StringOrArray),
+    ("ini_options.junit_duration_report", Kind::String),
+    ("ini_options.junit_family", Kind::String),
+    ("ini_options.junit_log_passing_tests", Kind::Any),
+    ("ini_options.junit_logging", Kind::String),
+    ("ini_options.junit_suite_name", Kind::String),
+    ("ini_options.log_auto_indent", Kind::Any),
+    ("ini_options.log_cli", Kind::Any),
+    ("ini_options.log_cli_date_format", Kind::String),
+    ("ini_options.log_cli_format", Kind::String),
+    ("ini_options.log_cli_level", Kind::Any),
+    ("ini_options.log_date_format", Kind::String),
+    ("ini_options.log_file", Kind::String),
+    ("ini_options.log_file_date_format", Kind::String),
+    ("ini_options.log_file_format", Kind::String),
+    ("ini_options.log_file_level", Kind::Any),
+    ("ini_options.log_format", Kind::String),
+    ("ini_options.log_level", Kind::Any),
+    ("ini_options.markers", Kind::StringOrArray),
+    ("ini_options.minversion", Kind::Any),
+    ("ini_options.norecursedirs", Kind::StringOrArray),
+    ("ini_options.python_classes", Kind::StringOrArray),
+    ("ini_options.python_files", Kind::StringOrArray),
+    ("ini_options.python_functions", Kind::StringOrArray),
+    ("ini_options.pythonpath", Kind::StringOrArray),
+    ("ini_options.required_plugins", Kind::StringOrArray),
+    ("ini_options.testpaths", Kind::StringOrArray),
+    ("ini_options.tmp_path_retention_count", Kind::Any),
+    ("ini_options.tmp_path_retention_policy", Kind::String),
+    ("ini_options.usefixtures", Kind::StringOrArray),
+];
+
+/// The options ruff reads from `[tool.ruff]` that aren't lint rule options.
+const RUFF_SCHEMA: [(&str, Kind); 28] = [
+    ("cache-dir", Kind::String),
+    ("exclude", Kind::Array),
+    ("extend", Kind::String),
+    ("extend-exclude", Kind::Array),
+    ("extend-include", Kind::Array),
+    ("fix", Kind::Bool),
+    ("fix-only", Kind::Bool),
+    ("force-exclude", Kind::Bool),
+    ("format.docstring-code-format", Kind::Bool),
+    ("format.docstring-code-line-length", Kind::Any),
+    ("format.exclude", Kind::Array),
+    ("format.indent-style", Kind::String),
+    ("format.line-ending", Kind::String),
+    ("format.preview", Kind::Bool),
+    ("format.quote-style", Kind::String),
+    ("format.skip-magic-trailing-comma", Kind::Bool),
+    ("include", Kind::Array),
+    ("indent-width", Kind::Integer),
+    ("line-length", Kind::Integer),
+    ("namespace-packages", Kind::Array),
+    ("output-format", Kind::String),
+    ("preview", Kind::Bool),
+    ("required-version", Kind::String),
+    ("respect-gitignore", Kind::Bool),
+    ("show-fixes", Kind::Bool),
+    ("src", Kind::Array),
+    ("target-version", Kind::String),
+    ("unsafe-fixes", Kind::Bool),
+];
+
+/// The lint rule options ruff reads from `[tool.ruff]` or `[tool.ruff.lint]`, including
+/// each plugin's table, such as `[tool.ruff.lint.isort]`.
+const RUFF_LINT_SCHEMA: [(&str, Kind); 31] = [
+    ("allowed-confusables", Kind::Array),
+    ("builtins", Kind::Array),
+    ("dummy-variable-rgx", Kind::String),
+    ("exclude", Kind::Array),
+    ("explicit-preview-rules", Kind::Bool),
+    ("extend-fixable", Kind::Array),
+    ("extend-ignore", Kind::Array),
+    ("extend-per-file-ignores", Kind::Table),
+    ("extend-safe-fixes", Kind::Array),
+    ("extend-select", Kind::Array),
+    ("extend-unsafe-fixes", Kind::Array),
+    ("external", Kind::Array),
+    ("fixable", Kind::Array),
+    ("ignore", Kind::Array),
+    ("ignore-init-module-imports", Kind::Bool),
+    ("logger-objects", Kind::Array),
+    ("per-file-ignores", Kind::Table),
+    ("preview", Kind::Bool),
+    ("select", Kind::Array),
+    ("task-tags", Kind::Array),
+    ("typing-modules", Kind::Array),
+    ("unfixable", Kind::Array),
+    ("flake8-*", Kind::Table),
+    ("isort", Kind::Table),
+    ("mccabe", Kind::Table),
+    ("pep8-naming", Kind::Table),
+    ("pycodestyle", Kind::Table),
+    ("pydocstyle", Kind::Table),
+    ("pyflakes", Kind::Table),
+    ("pylint", Kind::Table),
+    ("pyupgrade", Kind::Table),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The kind of value a key expects.
+pub enum Kind {
+    Any,
+    Array,
+    Bool,
+    Integer,
+    String,
+    StringOrArray,
+    /// A table whose keys aren't checked.
+    Table,
+}
+
+impl Kind {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Kind::Any => true,
+            Kind::Array => value.is_array(),
+            Kind::Bool => value.is_bool(),
+            Kind::Integer => value.is_integer(),
+            Kind::String => value.is_str(),
+            Kind::StringOrArray => value.is_str() || value.is_array(),
+            Kind::Table => value.is_table(),
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Kind::Any => "any value",
+            Kind::Array => "a list",
+            Kind::Bool => "a boolean",
+            Kind::Integer => "an integer",
+            Kind::String => "a string",
+            Kind::StringOrArray => "a string or a list",
+            Kind::Table => "a table",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// What's wrong with a key in a tool table.
+pub enum SchemaIssue {
+    /// The tool doesn't have the key. A similar key is suggested when it looks like a
+    /// typo.
+    UnknownKey(Option<String>),
+    /// The key's value isn't the `Kind` the tool expects.
+    InvalidType(Kind),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A problem with a key in a tool table, found at a 1-based line and column of the
+/// pyproject.toml.
+pub struct SchemaProblem {
+    /// The key's dotted path, such as "tool.ruff.line-length".
+    pub key: String,
+    pub line: usize,
+    pub column: usize,
+    pub issue: SchemaIssue,
+}
+
+impl Display for SchemaProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.issue {
+            SchemaIssue::UnknownKey(Some(it)) => {
+                write!(f, "unknown key {} (did you mean {it}?)", self.key)
+            }
+            SchemaIssue::UnknownKey(None) => {
+                write!(f, "unknown key {}", self.key)
+            }
+            SchemaIssue::InvalidType(it) => {
+                write!(f, "{} must be {it}", self.key)
+            }
+        }
+    }
+}
+
+/// The keys a tool's table accepts. Patterns are dotted paths relative to the tool's
+/// table, and a segment ending with `*` matches any key starting with the rest of it.
+struct Schema {
+    entries: Vec<(Vec<&'static str>, Kind)>,
+}
+
+impl Schema {
+    fn new(tool: &str) -> Option<Schema> {
+        let mut entries = Vec::new();
+        let mut extend =
+            |prefix: Option<&'static str>, schema: &[(&'static str, Kind)]| {
+                for (path, kind) in schema {
+                    let segments = prefix.into_iter().chain(path.split('.'));
+                    entries.push((segments.collect(), *kind));
+                }
+            };
+        match tool {
+            "huak" => extend(None, &HUAK_SCHEMA),
+            "mypy" => extend(None, &MYPY_SCHEMA),
+            "pytest" => extend(None, &PYTEST_SCHEMA),
+            "ruff" => {
+                extend(None, &RUFF_SCHEMA);
+                extend(None, &RUFF_LINT_SCHEMA);
+                extend(Some("lint"), &RUFF_LINT_SCHEMA);
+            }
+            _ => return None,
+        }
+
+        Some(Schema { entries })
+    }
+
+    /// Get the `Kind` of the key at `path`, if the schema has it.
+    fn kind(&self, path: &[String]) -> Option<Kind> {
+        self.entries
+            .iter()
+            .find(|(pattern, _)| {
+                pattern.len() == path.len() && segments_match(pattern, path)
+            })
+            .map(|(_, kind)| *kind)
+    }
+
+    /// Check if the key at `path` is a table the schema has keys for.
+    fn has_keys(&self, path: &[String]) -> bool {
+        self.entries.iter().any(|(pattern, _)| {
+            pattern.len() > path.len()
+                && segments_match(&pattern[..path.len()], path)
+        })
+    }
+
+    /// Get the keys the schema has for the table at `path`.
+    fn keys(&self, path: &[String]) -> Vec<&'static str> {
+        let mut keys = self
+            .entries
+            .iter()
+            .filter(|(pattern, _)| {
+                pattern.len() > path.len()
+                    && segments_match(&pattern[..path.len()], path)
+                    && !pattern[path.len()].ends_with('*')
+            })
+            .map(|(pattern, _)| pattern[path.len()])
+            .collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys.dedup();
+
+        keys
+    }
+}
+
+fn segments_match(pattern: &[&str], path: &[String]) -> bool {
+    pattern.iter().zip(path).all(|(pattern, key)| {
+        match pattern.strip_suffix('*') {
+            Some(prefix) => key.starts_with(prefix),
+            None => pattern == key,
+        }
+    })
+}
+
+/// Check the `[tool.*]` tables huak bundles a schema for (huak, mypy, pytest, and ruff)
+/// for unknown keys and values of the wrong type. `contents` is the pyproject.toml the
+/// `Metadata` was read from, used to locate each problem.
+pub fn tool_table_problems(
+    metadata: &Metadata,
+    contents: &str,
+) -> Vec<SchemaProblem> {
+    let positions = key_positions(contents);
+    let mut problems = Vec::new();
+    for tool in SCHEMA_TOOLS {
+        let (Some(table), Some(schema)) =
+            (metadata.tool_table(tool), Schema::new(tool))
+        else {
+            continue;
+        };
+        let mut path = Vec::new();
+        let mut report = |path: &[String], issue: SchemaIssue| {
+            let mut key = vec!["tool".to_string(), tool.to_string()];
+            key.extend(path.iter().cloned());
+            let (line, column) = position(&positions, &key);
+            problems.push(SchemaProblem {
+                key: key.join("."),
+                line,
+                column,
+                issue,
+            });
+        };
+        table_problems(&schema, table, &mut path, &mut report);
+    }
+
+    problems
+}
+
+fn table_problems(
+    schema: &Schema,
+    table: &Table,
+    path: &mut Vec<String>,
+    report: &mut dyn FnMut(&[String], SchemaIssue),
+) {
+    for (key, value) in table {
+        path.push(key.clone());
+        match schema.kind(path) {
+            Some(kind) if !kind.matches(value) => {
+                report(path, SchemaIssue::InvalidType(kind))
+            }
+            Some(_) => {}
+            None if schema.has_keys(path) => match value.as_table() {
+                Some(it) => table_problems(schema, it, path, report),
+                None => report(path, SchemaIssue::InvalidType(Kind::Table)),
+            },
+            None => {
+                let suggestion = schema
+                    .keys(&path[..path.len() - 1])
+                    .into_iter()
+                    .map(|it| (edit_distance(key, it), it))
+                    .filter(|(distance, _)| *distance <= 2)
+                    .min()
+                    .map(|(_, it)| it.to_string());
+                report(path, SchemaIssue::UnknownKey(suggestion))
+            }
+        }
+        path.pop();
+    }
+}
+
+/// Get the number of single-character edits needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(
+                substitution.min(previous[j + 1] + 1).min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Get the dotted path, line, and column of every table header and key in a TOML
+/// document. Keys in inline tables and arrays aren't included.
+fn key_positions(contents: &str) -> Vec<(Vec<String>, usize, usize)> {
+    let split = |key: &str| {
+        key.split('.')
+            .map(|it| {
+                it.trim()
+                    .trim_matches(|c: char| c == '"' || c == '\'')
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+    };
+    let key_column = |line: &str, key: &[String]| {
+        let indent = line.len() - line.trim_start().len();
+        key.last()
+            .and_then(|it| line.find(it.as_str()))
+            .unwrap_or(indent)
+            + 1
+    };
+    let mut positions = Vec::new();
+    let mut table = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let header = header.trim_start_matches('[');
+            let Some(end) = header.find(']') else {
+                continue;
+            };
+            table = split(&header[..end]);
+            positions.push((table.clone(), i + 1, key_column(line, &table)));
+        } else if let Some((key, _)) = trimmed.split_once('=') {
+            let is_key = !key.trim().is_empty()
+                && key.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || "-_.\"' \t".contains(c)
+                });
+            if is_key {
+                let mut path = table.clone();
+                path.extend(split(key));
+                let column = key_column(line, &path);
+                positions.push((path, i + 1, column));
+            }
+        }
+    }
+
+    positions
+}
+
+/// Get the line and column of the key at `path`. Keys that can't be found are located
+/// at their closest parent that can.
+fn position(
+    positions: &[(Vec<String>, usize, usize)],
+    path: &[String],
+) -> (usize, usize) {
+    for end in (1..=path.len()).rev() {
+        let found = positions
+            .iter()
+            .find(|(it, _, _)| it == &path[..end])
+            .or_else(|| {
+                positions
+                    .iter()
+                    .find(|(it, _, _)| it.starts_with(&path[..end]))
+            });
+        if let Some((_, line, column)) = found {
+            return (*line, *column);
+        }
+    }
+
+    (1, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_table_problems() {
+        let contents = r#"[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[project]
+name = "mock-project"
+version = "0.0.1"
+
+[tool.ruff]
+line-lenght = 100
+select = ["E", "F"]
+
+[tool.ruff.lint.isort]
+known-first-party = ["mock_project"]
+
+[tool.mypy]
+strict = "yes"
+
+[tool.huak.network]
+retries = 5
+timeout = 30
+
+[tool.huak.fmt]
+notebook = false
+
+[tool.black]
+anything = true
+"#;
+        let metadata: Metadata = toml::from_str(contents).unwrap();
+
+        let problems = tool_table_problems(&metadata, contents);
+
+        assert_eq!(
+            problems,
+            [
+                SchemaProblem {
+                    key: "tool.huak.fmt.notebook".to_string(),
+                    line: 24,
+                    column: 1,
+                    issue: SchemaIssue::UnknownKey(Some(
+                        "notebooks".to_string()
+                    )),
+                },
+                SchemaProblem {
+                    key: "tool.mypy.strict".to_string(),
+                    line: 17,
+                    column: 1,
+                    issue: SchemaIssue::InvalidType(Kind::Bool),
+                },
+                SchemaProblem {
+                    key: "tool.ruff.line-lenght".to_string(),
+                    line: 10,
+                    column: 1,
+                    issue: SchemaIssue::UnknownKey(Some(
+                        "line-length".to_string()
+                    )),
+                },
+            ]
+        );
+        assert_eq!(
+            problems[2].to_string(),
+            "unknown key tool.ruff.line-lenght (did you mean line-length?)"
+        );
+    }
+}
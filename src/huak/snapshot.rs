@@ -0,0 +1,116 @@
+use crate::{Error, HuakResult};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_FILE_NAME: &str = "huak-env.toml";
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A `Snapshot` records the exact packages installed in a Python environment so that an
+/// identical environment can be recreated elsewhere.
+///
+/// ```toml
+/// version = 1
+/// python = "3.11.4"
+///
+/// [[package]]
+/// name = "click"
+/// version = "8.1.3"
+/// hash = "sha256:2b2fa1b2d0e1b4e2c5b4e0c5e7f0f64f4c6d2a0f8b1a7f06f7d3e2f4d0c6a8e1"
+/// direct = true
+/// ```
+pub struct Snapshot {
+    /// The version of the snapshot format.
+    pub version: u32,
+    /// The version of the Python interpreter the environment used.
+    pub python: String,
+    /// The installed packages.
+    #[serde(default, rename = "package")]
+    pub packages: Vec<SnapshotPackage>,
+    /// The path to the snapshot.
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Snapshot {
+    /// Initialize an empty `Snapshot` to be written to a path.
+    pub fn new<T: AsRef<Path>>(path: T, python: String) -> Snapshot {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            python,
+            packages: Vec::new(),
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Read a `Snapshot` from a path.
+    pub fn from_path<T: AsRef<Path>>(path: T) -> HuakResult<Snapshot> {
+        let contents = std::fs::read_to_string(&path)?;
+        let mut snapshot: Snapshot = toml::from_str(&contents)?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(Error::HuakConfigurationError(format!(
+                "{} uses an unsupported snapshot version ({})",
+                path.as_ref().display(),
+                snapshot.version
+            )));
+        }
+        snapshot.path = path.as_ref().to_path_buf();
+
+        Ok(snapshot)
+    }
+
+    /// Get a reference to the path to the `Snapshot`.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Write the `Snapshot` to its path.
+    pub fn write_file(&self) -> HuakResult<()> {
+        let string = toml::to_string_pretty(self)?;
+        Ok(std::fs::write(&self.path, string)?)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A package installed in a `Snapshot`'s environment.
+pub struct SnapshotPackage {
+    /// The name of the package.
+    pub name: String,
+    /// The installed version of the package.
+    pub version: String,
+    /// The hash of the package's installed files (see `DistInfo::content_hash`).
+    pub hash: String,
+    /// Whether the package is a direct dependency of the project rather than a
+    /// transitive one.
+    pub direct: bool,
+}
+
+/// Get the file name used for `Snapshot`s.
+pub fn snapshot_file_name() -> &'static str {
+    SNAPSHOT_FILE_NAME
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(snapshot_file_name());
+        let mut snapshot = Snapshot::new(&path, "3.11.4".to_string());
+        snapshot.packages.push(SnapshotPackage {
+            name: "click".to_string(),
+            version: "8.1.3".to_string(),
+            hash: "sha256:2b2f".to_string(),
+            direct: true,
+        });
+        snapshot.write_file().unwrap();
+
+        let read = Snapshot::from_path(&path).unwrap();
+
+        assert_eq!(read, snapshot);
+        assert_eq!(read.path(), &path);
+    }
+}
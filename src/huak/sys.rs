@@ -1,14 +1,19 @@
 use crate::error::HuakResult;
 use crate::Error;
+use is_terminal::IsTerminal;
 use std::{
     fmt::Display,
-    io::Write,
-    path::Path,
-    process::{Command, ExitStatus},
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant},
 };
 use termcolor::{
     self, Color,
-    Color::{Red, Yellow},
+    Color::{Cyan, Green, Red, Yellow},
     ColorChoice, ColorSpec, StandardStream, WriteColor,
 };
 
@@ -33,6 +38,135 @@ impl Display for SubprocessError {
     }
 }
 
+/// The output of a command run with `Terminal::run_command_with_output`.
+#[derive(Debug)]
+pub struct CommandOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// A CI service huak is running on. Running in CI changes huak's defaults: prompts are
+/// never shown, output isn't colored unless colors are forced, a one-line summary of each
+/// command is printed, and the cache is kept where the service can cache it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiProvider {
+    GithubActions,
+    GitlabCi,
+    CircleCi,
+    Buildkite,
+    Jenkins,
+    AzurePipelines,
+    /// A service that only sets `CI`.
+    Other,
+}
+
+impl CiProvider {
+    /// Detect the CI service from the environment. `HUAK_CI=0` turns detection off, and
+    /// `HUAK_CI=1` treats any environment as CI.
+    pub fn detect() -> Option<CiProvider> {
+        CiProvider::from_vars(|key| {
+            std::env::var(key).ok().filter(|it| !it.is_empty())
+        })
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Option<CiProvider> {
+        let enabled = |key: &str| {
+            var(key).map_or(false, |it| {
+                !matches!(it.to_lowercase().as_str(), "0" | "false")
+            })
+        };
+        match var("HUAK_CI") {
+            Some(_) if !enabled("HUAK_CI") => return None,
+            _ => (),
+        }
+        let provider = if enabled("GITHUB_ACTIONS") {
+            CiProvider::GithubActions
+        } else if enabled("GITLAB_CI") {
+            CiProvider::GitlabCi
+        } else if enabled("CIRCLECI") {
+            CiProvider::CircleCi
+        } else if enabled("BUILDKITE") {
+            CiProvider::Buildkite
+        } else if var("JENKINS_URL").is_some() {
+            CiProvider::Jenkins
+        } else if enabled("TF_BUILD") {
+            CiProvider::AzurePipelines
+        } else if enabled("CI") || enabled("HUAK_CI") {
+            CiProvider::Other
+        } else {
+            return None;
+        };
+
+        Some(provider)
+    }
+
+    /// Get the directory huak's cache is kept in on the service, for services that can
+    /// only cache paths in certain places.
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        let var = |key: &str| {
+            std::env::var_os(key)
+                .filter(|it| !it.is_empty())
+                .map(PathBuf::from)
+        };
+        match self {
+            // GitLab only caches paths in the project's directory.
+            CiProvider::GitlabCi => var("CI_PROJECT_DIR"),
+            CiProvider::AzurePipelines => var("PIPELINE_WORKSPACE"),
+            _ => None,
+        }
+        .map(|it| it.join(".cache").join("huak"))
+    }
+}
+
+impl Display for CiProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CiProvider::GithubActions => "GitHub Actions",
+            CiProvider::GitlabCi => "GitLab CI",
+            CiProvider::CircleCi => "CircleCI",
+            CiProvider::Buildkite => "Buildkite",
+            CiProvider::Jenkins => "Jenkins",
+            CiProvider::AzurePipelines => "Azure Pipelines",
+            CiProvider::Other => "CI",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Check if huak can ask the user questions: stdin is a terminal and huak isn't running in
+/// CI (see `CiProvider`).
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && CiProvider::detect().is_none()
+}
+
+/// Get whether huak's output is colored regardless of where it's written, or `None` to
+/// color it only when it's written to a terminal. `NO_COLOR` turns colors off, and
+/// `FORCE_COLOR` or `CLICOLOR_FORCE` turn them on even in CI.
+pub fn color_override() -> Option<bool> {
+    let set = |key: &str| {
+        std::env::var(key).map_or(false, |it| !it.is_empty() && it != "0")
+    };
+    if std::env::var_os("NO_COLOR").map_or(false, |it| !it.is_empty()) {
+        Some(false)
+    } else if set("FORCE_COLOR") || set("CLICOLOR_FORCE") {
+        Some(true)
+    } else if CiProvider::detect().is_some() {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Get the `ColorChoice` for the terminal's streams (see `color_override`).
+fn color_choice() -> ColorChoice {
+    match color_override() {
+        Some(true) => ColorChoice::Always,
+        Some(false) => ColorChoice::Never,
+        None => ColorChoice::Auto,
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub enum Verbosity {
     #[default]
@@ -41,11 +175,6 @@ pub enum Verbosity {
     Quiet,
 }
 
-pub trait ToTerminal {
-    /// Get a `Terminal`.
-    fn to_terminal(&self) -> Terminal;
-}
-
 /// An abstraction around terminal output that remembers preferences for output
 /// verbosity and color (inspired by cargo's `Shell`).
 pub struct Terminal {
@@ -53,6 +182,15 @@ pub struct Terminal {
     output: TerminalOut,
     /// How verbose messages should be.
     verbosity: Verbosity,
+    /// Answer "yes" to every confirmation prompt.
+    assume_yes: bool,
+    /// Page long output (see `Terminal::print_paged`).
+    pager: bool,
+    /// Don't print the output of commands that fail when the `Terminal` is quiet, so that
+    /// only a summary of the op is reported.
+    quiet_errors: bool,
+    /// A file the full output of commands is appended to.
+    log_file: Option<PathBuf>,
 }
 
 impl Terminal {
@@ -60,9 +198,13 @@ impl Terminal {
     pub fn new() -> Terminal {
         Terminal {
             verbosity: Verbosity::Verbose,
+            assume_yes: false,
+            pager: false,
+            quiet_errors: false,
+            log_file: None,
             output: TerminalOut::Stream {
-                stdout: StandardStream::stdout(ColorChoice::Auto),
-                stderr: StandardStream::stderr(ColorChoice::Auto),
+                stdout: StandardStream::stdout(color_choice()),
+                stderr: StandardStream::stderr(color_choice()),
             },
         }
     }
@@ -96,6 +238,114 @@ impl Terminal {
         self.print(&title, Some(&message), color, justified)
     }
 
+    /// Writes text to stdout as-is. Unlike other messages it isn't silenced by
+    /// `Verbosity::Quiet` since it's the requested output of a command.
+    pub fn print_stdout(&mut self, text: &str) -> HuakResult<()> {
+        self.output.write_stdout(text)
+    }
+
+    /// Prints a `Table` to stdout. On a terminal, the header is bold, columns are aligned,
+    /// and cells are truncated to fit the terminal's width. When stdout is piped, rows
+    /// are printed as plain tab-separated values without the header so that they're easy
+    /// to process. The table's summary is printed like other messages.
+    pub fn print_table(&mut self, table: &Table) -> HuakResult<()> {
+        if std::io::stdout().is_terminal() {
+            let rendered = table.render(terminal_width());
+            if !self.print_paged(&rendered)? {
+                let mut lines = rendered.lines();
+                if let Some(header) = lines.next() {
+                    self.output
+                        .line_stdout(header, ColorSpec::new().set_bold(true))?;
+                }
+                for line in lines {
+                    self.output.write_stdout(&format!("{line}\n"))?;
+                }
+            }
+        } else {
+            self.output.write_stdout(&table.render_plain())?;
+        }
+
+        match table.summary.as_ref() {
+            Some((title, message)) => {
+                self.print(title, Some(message), Green, true)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Writes text to stdout through a pager if it's taller than the terminal, like git
+    /// does. The pager is `HUAK_PAGER`, then `PAGER`, and defaults to `less` (`more` on
+    /// Windows); setting either variable to "" or "cat" turns paging off. Returns `false`
+    /// without writing anything if the text wasn't paged, such as when stdout isn't a
+    /// terminal, paging is off, or the pager couldn't be started.
+    pub fn print_paged(&mut self, text: &str) -> HuakResult<bool> {
+        if !self.pager || !std::io::stdout().is_terminal() {
+            return Ok(false);
+        }
+        let Some(height) = terminal_height() else {
+            return Ok(false);
+        };
+        let Some(pager) =
+            pager_command(text, height, |key| std::env::var(key).ok())
+        else {
+            return Ok(false);
+        };
+
+        // The pager can be a command line with arguments, so it's run by a shell.
+        let mut cmd = match cfg!(windows) {
+            true => {
+                let mut cmd = Command::new("cmd");
+                cmd.args(["/C", &pager]);
+                cmd
+            }
+            false => {
+                let mut cmd = Command::new("sh");
+                cmd.args(["-c", &pager]);
+                cmd
+            }
+        };
+        // Like git, quit if the text fits on one screen and keep colors and the screen.
+        if std::env::var_os("LESS").is_none() {
+            cmd.env("LESS", "FRX");
+        }
+        let Ok(mut child) = cmd.stdin(Stdio::piped()).spawn() else {
+            return Ok(false);
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            // The pager closes its input when it's quit before reaching the end.
+            match stdin.write_all(text.as_bytes()) {
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+                res => res?,
+            }
+        }
+        child.wait()?;
+
+        Ok(true)
+    }
+
+    /// Prints a unified diff to stdout, colorizing its headers, hunks, and changed lines.
+    pub fn print_diff(&mut self, diff: &str) -> HuakResult<()> {
+        if self.verbosity == Verbosity::Quiet {
+            return Ok(());
+        }
+
+        for line in diff.lines() {
+            let mut spec = ColorSpec::new();
+            if line.starts_with("---") || line.starts_with("+++") {
+                spec.set_bold(true);
+            } else if line.starts_with("@@") {
+                spec.set_fg(Some(Cyan));
+            } else if line.starts_with('+') {
+                spec.set_fg(Some(Green));
+            } else if line.starts_with('-') {
+                spec.set_fg(Some(Red));
+            }
+            self.output.line_stdout(line, &spec)?;
+        }
+
+        Ok(())
+    }
+
     /// Prints a message, where the status will have `color` color, and can be justified.
     /// The messages follows without color.
     ///
@@ -116,33 +366,101 @@ impl Terminal {
         }
     }
 
+    /// Ask the user to confirm an action. Confirmation is assumed if the `Terminal` is set
+    /// to assume "yes". Otherwise a prompt is shown on interactive terminals, and
+    /// non-interactive contexts fail since nobody is there to answer.
+    pub fn confirm<T: Display>(&mut self, prompt: T) -> HuakResult<bool> {
+        if self.assume_yes {
+            return Ok(true);
+        }
+        if !is_interactive() {
+            return Err(Error::ConfirmationRequired(prompt.to_string()));
+        }
+
+        self.output.message_stderr(
+            &"confirm",
+            Some(&format!("{prompt}? [y/N]")),
+            Yellow,
+            false,
+        )?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    /// Ask the user a question on an interactive terminal. The `default` answer is used if
+    /// the response is empty.
+    pub fn prompt<T: Display>(
+        &mut self,
+        question: T,
+        default: Option<&str>,
+    ) -> HuakResult<String> {
+        if !is_interactive() {
+            return Err(Error::InteractiveTerminalRequired(
+                question.to_string(),
+            ));
+        }
+
+        let message = match default {
+            Some(it) if !it.is_empty() => format!("{question} [{it}]"),
+            _ => question.to_string(),
+        };
+        self.output
+            .message_stderr(&"?", Some(&message), Cyan, false)?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        let answer = answer.trim();
+
+        if answer.is_empty() {
+            Ok(default.unwrap_or_default().to_string())
+        } else {
+            Ok(answer.to_string())
+        }
+    }
+
+    /// Set whether confirmation prompts are assumed to be answered "yes".
+    pub fn set_assume_yes(&mut self, assume_yes: bool) {
+        self.assume_yes = assume_yes;
+    }
+
+    /// Set whether long output is paged (see `Terminal::print_paged`).
+    pub fn set_pager(&mut self, pager: bool) {
+        self.pager = pager;
+    }
+
+    /// Set whether the output of failed commands is left out when the `Terminal` is quiet.
+    pub fn set_quiet_errors(&mut self, quiet_errors: bool) {
+        self.quiet_errors = quiet_errors;
+    }
+
+    /// Set a file to append the full output of commands to.
+    pub fn set_log_file(&mut self, path: Option<PathBuf>) {
+        self.log_file = path;
+    }
+
     /// Set the verbosity level.
     pub fn set_verbosity(&mut self, verbosity: Verbosity) {
         self.verbosity = verbosity;
     }
 
+    /// Start a long-running command from the terminal's context without waiting for it.
+    /// Its output is streamed to the terminal, except for its stdout when the terminal is
+    /// quiet.
+    pub fn spawn_command(&mut self, cmd: &mut Command) -> HuakResult<Child> {
+        if self.verbosity == Verbosity::Quiet {
+            cmd.stdout(Stdio::null());
+        }
+
+        Ok(cmd.spawn()?)
+    }
+
     /// Run a command from the terminal's context.
     pub fn run_command(&mut self, cmd: &mut Command) -> HuakResult<()> {
         let status = match self.verbosity {
-            Verbosity::Quiet => {
-                let output = cmd.output()?;
-                let status = output.status;
-
-                let stdout =
-                    trim_error_prefix(std::str::from_utf8(&output.stdout)?);
-                let stderr =
-                    trim_error_prefix(std::str::from_utf8(&output.stderr)?);
-
-                if !status.success() {
-                    if !stdout.is_empty() {
-                        self.print_error(stdout)?;
-                    }
-                    if !stderr.is_empty() {
-                        self.print_error(stderr)?;
-                    }
-                }
-
-                status
+            Verbosity::Quiet => self.capture_command(cmd, false, None)?.status,
+            _ if self.log_file.is_some() => {
+                self.capture_command(cmd, false, None)?.status
             }
             _ => {
                 let mut child = cmd.spawn()?;
@@ -163,17 +481,166 @@ impl Terminal {
 
         Ok(())
     }
+
+    /// Run a command from the terminal's context like `run_command`, capturing its stdout
+    /// and stderr so that they can be post-processed. The output is still written to the
+    /// terminal as the command runs unless the `Terminal` is quiet. A failing command isn't
+    /// treated as an error; check the `CommandOutput`'s status.
+    pub fn run_command_with_output(
+        &mut self,
+        cmd: &mut Command,
+    ) -> HuakResult<CommandOutput> {
+        self.capture_command(cmd, true, None)
+    }
+
+    /// Run a command from the terminal's context like `run_command`, returning its exit
+    /// status and stderr so that failures can be diagnosed. stderr is still written to the
+    /// terminal as the command runs unless the `Terminal` is quiet. The command is killed
+    /// if it doesn't finish before `timeout`.
+    pub fn run_command_with_stderr(
+        &mut self,
+        cmd: &mut Command,
+        timeout: Option<Duration>,
+    ) -> HuakResult<(ExitStatus, String)> {
+        let output = self.capture_command(cmd, false, timeout)?;
+
+        Ok((output.status, output.stderr))
+    }
+
+    /// Run a command with its stderr, and its stdout if `capture_stdout` is set or it
+    /// otherwise needs to be read, piped through huak. Each stream is written to the
+    /// terminal as it's read unless the `Terminal` is quiet, appended to the log file if
+    /// there is one, and captured. If the `Terminal` is quiet the captured output of a
+    /// failing command is printed once it exits.
+    fn capture_command(
+        &mut self,
+        cmd: &mut Command,
+        capture_stdout: bool,
+        timeout: Option<Duration>,
+    ) -> HuakResult<CommandOutput> {
+        let quiet = self.verbosity == Verbosity::Quiet;
+        let log = match self.log_file.as_ref() {
+            Some(path) => {
+                let mut file =
+                    OpenOptions::new().create(true).append(true).open(path)?;
+                writeln!(file, "$ {cmd:?}")?;
+                Some(Arc::new(Mutex::new(file)))
+            }
+            None => None,
+        };
+        cmd.stderr(Stdio::piped());
+        if capture_stdout || quiet || log.is_some() {
+            cmd.stdout(Stdio::piped());
+        }
+        let mut child = cmd.spawn()?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .map(|it| tee(it, (!quiet).then(std::io::stdout), log.clone()));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|it| tee(it, (!quiet).then(std::io::stderr), log.clone()));
+
+        let status = match timeout {
+            Some(timeout) => {
+                let deadline = Instant::now() + timeout;
+                loop {
+                    if let Some(it) = child.try_wait()? {
+                        break it;
+                    }
+                    if Instant::now() >= deadline {
+                        child.kill().ok();
+                        child.wait().ok();
+                        return Err(Error::OperationTimedOut(format!(
+                            "{}s",
+                            timeout.as_secs()
+                        )));
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+            }
+            None => child.wait()?,
+        };
+        let join = |it: Option<JoinHandle<Vec<u8>>>| {
+            it.and_then(|it| it.join().ok())
+                .map(|it| String::from_utf8_lossy(&it).into_owned())
+                .unwrap_or_default()
+        };
+        let stdout = join(stdout);
+        let stderr = join(stderr);
+
+        if quiet && !status.success() && !self.quiet_errors {
+            for it in [stdout.as_str(), stderr.as_str()] {
+                let it = trim_error_prefix(it);
+                if !it.is_empty() {
+                    self.print_error(it)?;
+                }
+            }
+        }
+
+        Ok(CommandOutput {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+}
+
+/// Read a child's output stream on its own thread until it closes, writing it to `echo`
+/// and `log` as it's read. The thread returns everything that was read.
+fn tee<R, W>(
+    mut reader: R,
+    mut echo: Option<W>,
+    log: Option<Arc<Mutex<File>>>,
+) -> JoinHandle<Vec<u8>>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut buf = [0; 4096];
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            if let Some(it) = echo.as_mut() {
+                it.write_all(&buf[..n]).ok();
+                it.flush().ok();
+            }
+            if let Some(it) = log.as_ref() {
+                if let Ok(mut file) = it.lock() {
+                    file.write_all(&buf[..n]).ok();
+                }
+            }
+            captured.extend_from_slice(&buf[..n]);
+        }
+        captured
+    })
 }
 
 #[derive(Clone)]
 pub struct TerminalOptions {
     pub verbosity: Verbosity,
+    /// Answer "yes" to every confirmation prompt.
+    pub assume_yes: bool,
 }
 
 impl TerminalOptions {
     pub fn verbosity(&self) -> &Verbosity {
         &self.verbosity
     }
+
+    /// Get a `Terminal` configured with the `TerminalOptions`.
+    pub fn terminal(&self) -> Terminal {
+        let mut terminal = Terminal::new();
+        terminal.set_verbosity(self.verbosity);
+        terminal.set_assume_yes(self.assume_yes);
+
+        terminal
+    }
 }
 
 pub fn parse_command_output(
@@ -191,23 +658,217 @@ impl Default for Terminal {
     }
 }
 
-fn trim_error_prefix(msg: &str) -> &str {
+pub(crate) fn trim_error_prefix(msg: &str) -> &str {
     msg.trim_start_matches("error:")
         .trim_start_matches("ERROR:")
         .trim_start()
 }
 
+/// A `Table` of rows printed with aligned columns (see `Terminal::print_table`), with an
+/// optional summary line such as "3 package(s) installed".
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    summary: Option<(String, String)>,
+}
+
+impl Table {
+    /// Create a `Table` with column headers.
+    pub fn new<T: Display>(headers: impl IntoIterator<Item = T>) -> Table {
+        Table {
+            headers: headers.into_iter().map(|it| it.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a row. Missing cells are left empty and extra cells are ignored.
+    pub fn push_row<T: Display>(&mut self, row: impl IntoIterator<Item = T>) {
+        let mut row = row
+            .into_iter()
+            .map(|it| it.to_string())
+            .take(self.headers.len())
+            .collect::<Vec<_>>();
+        row.resize(self.headers.len(), String::new());
+        self.rows.push(row);
+    }
+
+    /// Set the summary printed after the rows, such as `("Found", "3 member(s)")`.
+    pub fn set_summary<T: Display, U: Display>(
+        &mut self,
+        title: T,
+        message: U,
+    ) {
+        self.summary = Some((title.to_string(), message.to_string()));
+    }
+
+    /// Check if the `Table` has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Render the header and rows with aligned columns. Columns are separated by two
+    /// spaces, and the widest columns are truncated with "…" until the lines fit in
+    /// `width` characters.
+    fn render(&self, width: Option<usize>) -> String {
+        let len = |it: &str| it.chars().count();
+        let mut widths = self
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                self.rows
+                    .iter()
+                    .map(|row| len(&row[i]))
+                    .chain(std::iter::once(len(header)))
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        if let Some(width) = width {
+            let separators = 2 * widths.len().saturating_sub(1);
+            while widths.iter().sum::<usize>() + separators > width {
+                let Some(widest) = widths
+                    .iter_mut()
+                    .filter(|it| **it > MIN_COLUMN_WIDTH)
+                    .max_by_key(|it| **it)
+                else {
+                    break;
+                };
+                *widest -= 1;
+            }
+        }
+
+        let mut rendered = String::new();
+        for row in std::iter::once(&self.headers).chain(self.rows.iter()) {
+            let cells = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| {
+                    let cell = match len(cell) > *width {
+                        true => {
+                            let mut it = cell
+                                .chars()
+                                .take(width.saturating_sub(1))
+                                .collect::<String>();
+                            it.push('…');
+                            it
+                        }
+                        false => cell.clone(),
+                    };
+                    format!("{cell:width$}")
+                })
+                .collect::<Vec<_>>();
+            rendered.push_str(cells.join("  ").trim_end());
+            rendered.push('\n');
+        }
+
+        rendered
+    }
+
+    /// Render the rows as tab-separated values.
+    fn render_plain(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| format!("{}\n", row.join("\t")))
+            .collect()
+    }
+}
+
+/// The narrowest a `Table`'s column is truncated to.
+const MIN_COLUMN_WIDTH: usize = 8;
+
+/// Get the width of the terminal stdout is connected to, from `COLUMNS` or else `stty`.
+/// `None` is returned if the width can't be found.
+fn terminal_width() -> Option<usize> {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|it| it.trim().parse().ok())
+        .or_else(|| stty_size().map(|(_, columns)| columns))
+}
+
+/// Get the height of the terminal stdout is connected to, from `LINES` or else `stty`.
+/// `None` is returned if the height can't be found.
+fn terminal_height() -> Option<usize> {
+    std::env::var("LINES")
+        .ok()
+        .and_then(|it| it.trim().parse().ok())
+        .or_else(|| stty_size().map(|(rows, _)| rows))
+}
+
+/// Get the pager command to page text with on a terminal `height` lines tall, or `None`
+/// if the text fits or paging is turned off (see `Terminal::print_paged`).
+fn pager_command(
+    text: &str,
+    height: usize,
+    var: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    if text.lines().count() < height {
+        return None;
+    }
+    let pager = ["HUAK_PAGER", "PAGER"]
+        .iter()
+        .find_map(|it| var(it))
+        .unwrap_or_else(|| match cfg!(windows) {
+            true => "more".to_string(),
+            false => "less".to_string(),
+        });
+
+    match pager.trim() {
+        "" | "cat" => None,
+        _ => Some(pager),
+    }
+}
+
+/// Get the number of rows and columns of the controlling terminal with `stty`.
+fn stty_size() -> Option<(usize, usize)> {
+    if cfg!(windows) {
+        return None;
+    }
+
+    // `stty` reads the terminal from stdin, so it's given the controlling terminal.
+    let tty = std::fs::File::open("/dev/tty").ok()?;
+    let output = Command::new("stty").arg("size").stdin(tty).output().ok()?;
+    let size = std::str::from_utf8(&output.stdout).ok()?;
+    let mut size = size.split_whitespace().map(|it| it.parse().ok());
+
+    Some((size.next()??, size.next()??))
+}
+
 /// Objects for writing terminal output to.
 enum TerminalOut {
     /// Color-enabled stdio with information on whether color should be used
     Stream {
-        #[allow(dead_code)]
         stdout: StandardStream,
         stderr: StandardStream,
     },
 }
 
 impl TerminalOut {
+    /// Writes text to stdout without any color.
+    fn write_stdout(&mut self, text: &str) -> HuakResult<()> {
+        match *self {
+            TerminalOut::Stream { ref mut stdout, .. } => {
+                write!(stdout, "{text}")?;
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints a line to stdout with a `ColorSpec`.
+    fn line_stdout(&mut self, line: &str, spec: &ColorSpec) -> HuakResult<()> {
+        match *self {
+            TerminalOut::Stream { ref mut stdout, .. } => {
+                stdout.set_color(spec)?;
+                write!(stdout, "{line}")?;
+                stdout.reset()?;
+                writeln!(stdout)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Prints out a message with a status. The status comes first, and is bold plus
     /// the given color. The status can be justified, in which case the max width that
     /// will right align is DEFAULT_MESSAGE_JUSTIFIED_CHARS chars.
@@ -242,6 +903,60 @@ impl TerminalOut {
     }
 }
 
+/// Check if a process with the given id is running.
+#[cfg(unix)]
+pub fn process_is_running(pid: u32) -> bool {
+    // `kill` is a shell builtin, so this doesn't depend on `PATH`.
+    Command::new("/bin/sh")
+        .args(["-c", &format!("kill -0 {pid}")])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_or(true, |it| it.success())
+}
+
+/// Check if a process with the given id is running.
+#[cfg(windows)]
+pub fn process_is_running(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output()
+        .map_or(true, |it| {
+            String::from_utf8_lossy(&it.stdout).contains(&pid.to_string())
+        })
+}
+
+/// Get descriptions of the running processes whose executables are in `dir`, such as
+/// Python processes running from an environment. Only Windows keeps files open by
+/// running processes from being removed, so nothing is reported on other platforms.
+#[cfg(unix)]
+pub fn processes_in(_dir: &Path) -> Vec<String> {
+    Vec::new()
+}
+
+/// Get descriptions of the running processes whose executables are in `dir`, such as
+/// Python processes running from an environment.
+#[cfg(windows)]
+pub fn processes_in(dir: &Path) -> Vec<String> {
+    let script = format!(
+        "Get-Process | Where-Object {{ $_.Path -like '{}\\*' }} | \
+        ForEach-Object {{ \"$($_.ProcessName) (pid $($_.Id))\" }}",
+        dir.display().to_string().replace('\'', "''")
+    );
+    Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .output()
+        .map(|it| {
+            String::from_utf8_lossy(&it.stdout)
+                .lines()
+                .map(str::trim)
+                .filter(|it| !it.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Gets the name of the current shell.
 pub fn shell_name() -> HuakResult<String> {
     let shell_path = shell_path()?;
@@ -266,3 +981,139 @@ pub fn shell_path() -> HuakResult<String> {
 pub fn shell_path() -> HuakResult<String> {
     Ok(std::env::var("COMSPEC")?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_table_render() {
+        let mut table = Table::new(["name", "kind", "manifest"]);
+        table.push_row([
+            "packages/api",
+            "python",
+            "packages/api/pyproject.toml",
+        ]);
+        table.push_row(["web", "node"]);
+
+        assert_eq!(
+            table.render(None),
+            "\
+name          kind    manifest
+packages/api  python  packages/api/pyproject.toml
+web           node
+"
+        );
+        assert_eq!(
+            table.render(Some(36)),
+            "\
+name          kind    manifest
+packages/api  python  packages/api/…
+web           node
+"
+        );
+        assert_eq!(
+            table.render_plain(),
+            "packages/api\tpython\tpackages/api/pyproject.toml\nweb\tnode\t\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_command_with_output() {
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("huak.log");
+        let mut terminal = Terminal::new();
+        terminal.set_verbosity(Verbosity::Quiet);
+        terminal.set_log_file(Some(log.clone()));
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2; exit 3"]);
+
+        let output = terminal.run_command_with_output(&mut cmd).unwrap();
+        let logged = std::fs::read_to_string(log).unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+        assert!(logged.starts_with("$ "));
+        assert!(logged.contains("out\n"));
+        assert!(logged.contains("err\n"));
+    }
+
+    #[test]
+    fn test_ci_provider() {
+        let detect = |vars: &[(&str, &str)]| {
+            CiProvider::from_vars(|key| {
+                vars.iter()
+                    .find(|(it, _)| *it == key)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+
+        assert_eq!(detect(&[]), None);
+        assert_eq!(
+            detect(&[("CI", "true"), ("GITHUB_ACTIONS", "true")]),
+            Some(CiProvider::GithubActions)
+        );
+        assert_eq!(detect(&[("CI", "1")]), Some(CiProvider::Other));
+        assert_eq!(detect(&[("CI", "false")]), None);
+        assert_eq!(detect(&[("CI", "true"), ("HUAK_CI", "0")]), None);
+        assert_eq!(detect(&[("HUAK_CI", "1")]), Some(CiProvider::Other));
+    }
+
+    #[test]
+    fn test_pager_command() {
+        let pager = |lines: usize, height: usize, vars: &[(&str, &str)]| {
+            pager_command(&"line\n".repeat(lines), height, |key| {
+                vars.iter()
+                    .find(|(it, _)| *it == key)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        let default = match cfg!(windows) {
+            true => "more",
+            false => "less",
+        };
+
+        assert_eq!(pager(23, 24, &[]), None);
+        assert_eq!(pager(24, 24, &[]).as_deref(), Some(default));
+        assert_eq!(
+            pager(30, 24, &[("PAGER", "most"), ("HUAK_PAGER", "less -S")])
+                .as_deref(),
+            Some("less -S")
+        );
+        assert_eq!(
+            pager(30, 24, &[("PAGER", "most")]).as_deref(),
+            Some("most")
+        );
+        assert_eq!(pager(30, 24, &[("PAGER", "cat")]), None);
+        assert_eq!(
+            pager(30, 24, &[("HUAK_PAGER", " "), ("PAGER", "less")]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_terminal_pager() {
+        let mut config = Config::new(
+            PathBuf::new(),
+            PathBuf::new(),
+            TerminalOptions {
+                verbosity: Verbosity::Quiet,
+                assume_yes: true,
+            },
+        );
+
+        assert!(config.terminal().pager);
+
+        config.no_pager = true;
+
+        assert!(!config.terminal().pager);
+        assert!(!config
+            .terminal()
+            .print_paged(&"line\n".repeat(1000))
+            .unwrap());
+    }
+}
@@ -0,0 +1,231 @@
+use crate::HuakResult;
+use std::path::{Path, PathBuf};
+
+/// The files generated for every new project.
+const LIB_TEMPLATES: [(&str, &str); 3] = [
+    (
+        "src/{{ importable_name }}/__init__.py",
+        "__version__ = \"{{ version }}\"\n",
+    ),
+    (
+        "tests/test_version.py",
+        r#"from {{ importable_name }} import __version__
+
+
+def test_version():
+    assert isinstance(__version__, str)
+"#,
+    ),
+    ("README.md", "# {{ project_name }}\n"),
+];
+/// The files generated for new applications, in addition to `LIB_TEMPLATES`.
+const APP_TEMPLATES: [(&str, &str); 1] = [(
+    "src/{{ importable_name }}/main.py",
+    r#"def main():
+    print("Hello, World!")
+
+
+if __name__ == "__main__":
+    main()
+"#,
+)];
+/// The files generated when scaffolding an existing project.
+const SCAFFOLD_TEMPLATES: [(&str, &str); 1] = [(
+    "tests/test_{{ importable_name }}.py",
+    r#"import {{ importable_name }}
+
+
+def test_import():
+    assert {{ importable_name }}
+"#,
+)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The kinds of files huak generates for projects.
+pub enum TemplateKind {
+    /// The files generated for every new project.
+    Lib,
+    /// The files generated for new applications, in addition to `TemplateKind::Lib`'s.
+    App,
+    /// The files generated when scaffolding an existing project.
+    Scaffold,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A file generated for a project. Both its path (relative to the project's root) and
+/// its contents can reference variables like `{{ project_name }}`.
+pub struct Template {
+    pub path: String,
+    pub contents: String,
+}
+
+impl Template {
+    /// Render the `Template` and write it to a project, returning the path written to.
+    /// Existing files are only replaced when `overwrite` is set.
+    pub fn write<T: AsRef<Path>>(
+        &self,
+        root: T,
+        vars: &[(&str, String)],
+        overwrite: bool,
+    ) -> HuakResult<Option<PathBuf>> {
+        let path = root.as_ref().join(render(&self.path, vars));
+        if path.exists() && !overwrite {
+            return Ok(None);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, render(&self.contents, vars))?;
+
+        Ok(Some(path))
+    }
+}
+
+/// Get the `Template`s for a kind of generated files.
+///
+/// Files in the user's templates directory override the built-in templates with the
+/// same path, like "src/{{ importable_name }}/main.py". The directory's other files,
+/// like ".github/workflows/ci.yml", are generated with every new or scaffolded project.
+pub fn templates(
+    kind: TemplateKind,
+    user_dir: Option<&Path>,
+) -> HuakResult<Vec<Template>> {
+    let builtin = match kind {
+        TemplateKind::Lib => LIB_TEMPLATES.as_slice(),
+        TemplateKind::App => APP_TEMPLATES.as_slice(),
+        TemplateKind::Scaffold => SCAFFOLD_TEMPLATES.as_slice(),
+    };
+    let mut templates = builtin
+        .iter()
+        .map(|(path, contents)| Template {
+            path: path.to_string(),
+            contents: contents.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let Some(dir) = user_dir.filter(|it| it.is_dir()) else {
+        return Ok(templates);
+    };
+
+    let is_builtin = |path: &str| {
+        LIB_TEMPLATES
+            .iter()
+            .chain(APP_TEMPLATES.iter())
+            .chain(SCAFFOLD_TEMPLATES.iter())
+            .any(|(it, _)| *it == path)
+    };
+    let pattern = dir.join("**").join("*").display().to_string();
+    for path in glob::glob(&pattern)?.flatten() {
+        if !path.is_file() {
+            continue;
+        }
+        let Ok(relative) = path.strip_prefix(dir) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let contents = std::fs::read_to_string(&path)?;
+        if let Some(it) = templates.iter_mut().find(|it| it.path == relative) {
+            it.contents = contents;
+        } else if !is_builtin(&relative) && kind != TemplateKind::App {
+            templates.push(Template {
+                path: relative,
+                contents,
+            });
+        }
+    }
+
+    Ok(templates)
+}
+
+/// Replace the `{{ name }}` variables in a template. Whitespace inside the braces is
+/// optional, and unknown variables are left as-is.
+pub fn render(template: &str, vars: &[(&str, String)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|it| start + it) else {
+            break;
+        };
+        let name = rest[start + 2..end].trim();
+        rendered.push_str(&rest[..start]);
+        match vars.iter().find(|(it, _)| *it == name) {
+            Some((_, value)) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_render() {
+        let vars = [
+            ("project_name", "my-project".to_string()),
+            ("importable_name", "my_project".to_string()),
+        ];
+
+        assert_eq!(
+            render("# {{ project_name }}\nimport {{importable_name}}\n", &vars),
+            "# my-project\nimport my_project\n"
+        );
+        assert_eq!(
+            render("f\"{{ unknown }}\" {{ project_name", &vars),
+            "f\"{{ unknown }}\" {{ project_name"
+        );
+    }
+
+    #[test]
+    fn test_templates() {
+        let dir = tempdir().unwrap();
+        let user_dir = dir.path().join("templates");
+        std::fs::create_dir_all(user_dir.join(".github").join("workflows"))
+            .unwrap();
+        std::fs::write(user_dir.join("README.md"), "# {{ project_name }}!\n")
+            .unwrap();
+        std::fs::write(
+            user_dir.join(".github").join("workflows").join("ci.yml"),
+            "name: {{ project_name }}\n",
+        )
+        .unwrap();
+        let vars = [
+            ("project_name", "my-project".to_string()),
+            ("importable_name", "my_project".to_string()),
+            ("version", "0.0.1".to_string()),
+        ];
+
+        let lib = templates(TemplateKind::Lib, Some(&user_dir)).unwrap();
+        let app = templates(TemplateKind::App, Some(&user_dir)).unwrap();
+        let root = dir.path().join("project");
+        for it in lib.iter() {
+            it.write(&root, &vars, false).unwrap();
+        }
+
+        assert_eq!(lib.len(), 4);
+        assert_eq!(app.len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(root.join("README.md")).unwrap(),
+            "# my-project!\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(
+                root.join(".github").join("workflows").join("ci.yml")
+            )
+            .unwrap(),
+            "name: my-project\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(
+                root.join("src").join("my_project").join("__init__.py")
+            )
+            .unwrap(),
+            "__version__ = \"0.0.1\"\n"
+        );
+        assert!(lib[2].write(&root, &vars, false).unwrap().is_none());
+    }
+}
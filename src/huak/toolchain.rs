@@ -0,0 +1,208 @@
+use crate::{environment::env_path_values, sys, Error, HuakResult};
+use serde::Deserialize;
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// A Python snippet used to find the native code an sdist (a tarball or zip archive)
+/// builds. Rust extensions are found by their build backend or a Cargo.toml.
+const SDIST_SCRIPT: &str = r#"
+import json, os, re, sys, tarfile, zipfile
+path = sys.argv[1]
+if zipfile.is_zipfile(path):
+    archive = zipfile.ZipFile(path)
+    names = archive.namelist()
+    read = archive.read
+else:
+    archive = tarfile.open(path)
+    names = archive.getnames()
+    read = lambda name: archive.extractfile(name).read()
+pyproject = [it for it in names if it.count("/") == 1 and it.endswith("/pyproject.toml")]
+backend = read(pyproject[0]).decode("utf-8", "replace") if pyproject else ""
+rust = bool(re.search(r"maturin|setuptools[-_]rust", backend)) or any(
+    it.endswith("/Cargo.toml") for it in names
+)
+c = any(os.path.splitext(it)[1] in (".c", ".cc", ".cpp", ".cxx", ".pyx") for it in names)
+print(json.dumps({"c": c, "rust": rust}))
+"#;
+
+/// A Python snippet used to check if an `Interpreter`'s development headers are installed.
+const HEADERS_SCRIPT: &str = r#"
+import os, sysconfig
+print(os.path.isfile(os.path.join(sysconfig.get_paths()["include"], "Python.h")))
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A tool needed to build an sdist's native code.
+pub enum BuildTool {
+    /// A C compiler, also used to link Rust extensions.
+    Compiler,
+    /// The Python development headers (Python.h).
+    PythonHeaders,
+    /// A Rust toolchain with `cargo` and `rustc`.
+    Rust,
+}
+
+impl Display for BuildTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            BuildTool::Compiler if cfg!(windows) => {
+                "a C compiler (the Microsoft C++ Build Tools)"
+            }
+            BuildTool::Compiler => "a C compiler (set CC or install gcc or clang)",
+            BuildTool::PythonHeaders => {
+                "the Python development headers (Python.h, usually in a python3-dev package)"
+            }
+            BuildTool::Rust => {
+                "a Rust toolchain (cargo and rustc, see https://rustup.rs)"
+            }
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+/// The native code an sdist builds.
+pub struct SdistBuild {
+    /// The sdist has C, C++, or Cython sources.
+    pub c: bool,
+    /// The sdist has a Rust extension.
+    pub rust: bool,
+}
+
+impl SdistBuild {
+    /// Inspect the sdist at `path` with a Python interpreter.
+    pub fn from_sdist(
+        python_path: &Path,
+        path: &Path,
+    ) -> HuakResult<SdistBuild> {
+        let output = Command::new(python_path)
+            .args(["-c", SDIST_SCRIPT])
+            .arg(path)
+            .output()?;
+        if !output.status.success() {
+            return Err(Error::SubprocessFailure(sys::SubprocessError::new(
+                output.status,
+            )));
+        }
+
+        Ok(serde_json::from_str(std::str::from_utf8(&output.stdout)?)?)
+    }
+
+    /// Get the `BuildTool`s the sdist needs that aren't installed. The Python headers
+    /// are checked for the interpreter at `python_path`.
+    pub fn missing_tools(
+        &self,
+        python_path: &Path,
+    ) -> HuakResult<Vec<BuildTool>> {
+        let mut missing = Vec::new();
+        if (self.c || self.rust) && !compiler_installed() {
+            missing.push(BuildTool::Compiler);
+        }
+        if self.c && !python_headers_installed(python_path)? {
+            missing.push(BuildTool::PythonHeaders);
+        }
+        if self.rust
+            && !(executable_installed("cargo") && executable_installed("rustc"))
+        {
+            missing.push(BuildTool::Rust);
+        }
+
+        Ok(missing)
+    }
+}
+
+/// Check if a C compiler can be found. `CC` takes precedence over the compilers on
+/// `PATH`. On Windows the Visual Studio installer's `vswhere` is accepted since build
+/// backends use it to find the compiler.
+fn compiler_installed() -> bool {
+    if std::env::var_os("CC").map_or(false, |it| !it.is_empty()) {
+        return true;
+    }
+    if cfg!(windows) {
+        return executable_installed("cl")
+            || std::env::var_os("ProgramFiles(x86)").map_or(false, |it| {
+                PathBuf::from(it)
+                    .join("Microsoft Visual Studio")
+                    .join("Installer")
+                    .join("vswhere.exe")
+                    .exists()
+            });
+    }
+
+    ["cc", "gcc", "clang"]
+        .iter()
+        .any(|it| executable_installed(it))
+}
+
+/// Check if an executable is on `PATH`.
+fn executable_installed(name: &str) -> bool {
+    let file_name = match cfg!(windows) {
+        true => format!("{name}.exe"),
+        false => name.to_string(),
+    };
+    env_path_values()
+        .unwrap_or_default()
+        .iter()
+        .any(|it| it.join(&file_name).is_file())
+}
+
+/// Check if the development headers of the interpreter at `python_path` are installed.
+fn python_headers_installed(python_path: &Path) -> HuakResult<bool> {
+    let output = Command::new(python_path)
+        .args(["-c", HEADERS_SCRIPT])
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::SubprocessFailure(sys::SubprocessError::new(
+            output.status,
+        )));
+    }
+
+    Ok(std::str::from_utf8(&output.stdout)?.trim() == "True")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+    use std::{fs::File, io::Write};
+    use tempfile::tempdir;
+    use zip::{write::FileOptions, ZipWriter};
+
+    #[test]
+    fn test_sdist_build() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("pkg-0.1.0.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let files = [
+            (
+                "pkg-0.1.0/pyproject.toml",
+                "[build-system]\nrequires = [\"maturin>=1,<2\"]\nbuild-backend = \"maturin\"\n",
+            ),
+            ("pkg-0.1.0/src/lib.rs", ""),
+        ];
+        for (name, contents) in files {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        let interpreters = Environment::resolve_python_interpreters();
+        let python_path = interpreters.latest().unwrap().path();
+
+        let build = SdistBuild::from_sdist(python_path, &path).unwrap();
+
+        assert_eq!(
+            build,
+            SdistBuild {
+                c: false,
+                rust: true
+            }
+        );
+        assert!(SdistBuild::default()
+            .missing_tools(python_path)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -4,13 +4,7 @@ use regex::{Captures, Regex};
 
 use crate::{Error, HuakResult};
 
-/// A trait used to convert a struct to `SemVer`.
-trait ToSemVer {
-    /// Convert to `SemVer` (MAJOR.MINOR.PATCH).
-    fn to_semver(self) -> SemVer;
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// A generic `Version` struct.
 ///
 /// This struct is mainly used for the Python `Interpreter`.
@@ -34,28 +28,6 @@ impl Display for Version {
     }
 }
 
-struct SemVer {
-    major: usize,
-    minor: usize,
-    patch: usize,
-}
-
-impl ToSemVer for Version {
-    fn to_semver(self) -> SemVer {
-        SemVer {
-            major: self.release[0],
-            minor: self.release[1],
-            patch: self.release[2],
-        }
-    }
-}
-
-impl Display for SemVer {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}.{}.{}", self.major, self.minor, self.patch) // TODO
-    }
-}
-
 /// Initialize a `Version` from a `&str`.
 ///
 /// ```
@@ -88,7 +60,7 @@ impl FromStr for Version {
 }
 
 /// Use regex to capture potential `Version` numbers from a `&str`.
-fn captures_version_str(s: &str) -> HuakResult<Captures> {
+fn captures_version_str(s: &str) -> HuakResult<Captures<'_>> {
     let re = Regex::new(r"^(\d+)(?:\.(\d+))?(?:\.(\d+))?$")?;
     let captures = match re.captures(s) {
         Some(captures) => captures,
@@ -0,0 +1,149 @@
+use crate::{fs, HuakResult};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Directories that never need to be watched, like caches and virtual environments.
+const IGNORED_DIRS: [&str; 8] = [
+    ".git",
+    ".huak",
+    ".mypy_cache",
+    ".pytest_cache",
+    ".ruff_cache",
+    ".venv",
+    "__pycache__",
+    "node_modules",
+];
+
+/// A `FileWatcher` polls files under a set of paths for changes. Files are compared by
+/// their modification times, so no platform file notification API is needed.
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    excluded: Box<dyn Fn(&Path) -> bool>,
+    snapshot: HashMap<PathBuf, SystemTime>,
+}
+
+impl FileWatcher {
+    /// Start watching the files under `paths`. Files `excluded` returns true for aren't
+    /// watched.
+    pub fn new(
+        paths: Vec<PathBuf>,
+        excluded: impl Fn(&Path) -> bool + 'static,
+    ) -> HuakResult<FileWatcher> {
+        let mut watcher = FileWatcher {
+            paths,
+            excluded: Box::new(excluded),
+            snapshot: HashMap::new(),
+        };
+        watcher.snapshot = watcher.scan()?;
+
+        Ok(watcher)
+    }
+
+    /// Get the paths of the files that were added, modified, or removed since the last
+    /// call (or since the `FileWatcher` was created), sorted.
+    pub fn changes(&mut self) -> HuakResult<Vec<PathBuf>> {
+        let snapshot = self.scan()?;
+        let mut changes = snapshot
+            .iter()
+            .filter(|(path, modified)| {
+                self.snapshot.get(*path) != Some(modified)
+            })
+            .map(|(path, _)| path.clone())
+            .chain(
+                self.snapshot
+                    .keys()
+                    .filter(|it| !snapshot.contains_key(*it))
+                    .cloned(),
+            )
+            .collect::<Vec<_>>();
+        changes.sort();
+        self.snapshot = snapshot;
+
+        Ok(changes)
+    }
+
+    /// Get the modification time of each watched file. Symlinked directories found under
+    /// the watched paths aren't followed, and virtual environments aren't watched.
+    fn scan(&self) -> HuakResult<HashMap<PathBuf, SystemTime>> {
+        let mut snapshot = HashMap::new();
+        // The watched paths themselves are followed even if they're symlinks.
+        let mut stack = self
+            .paths
+            .iter()
+            .map(|it| (it.clone(), true))
+            .collect::<Vec<_>>();
+        while let Some((path, is_root)) = stack.pop() {
+            if (self.excluded)(&path) {
+                continue;
+            }
+            let followed = is_root
+                || std::fs::symlink_metadata(&path)
+                    .map_or(false, |it| !fs::is_link(&it));
+            // Files can be removed while they're being scanned.
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                let ignored = path.file_name().map_or(false, |name| {
+                    IGNORED_DIRS.iter().any(|it| name == *it)
+                });
+                if ignored || !followed || path.join("pyvenv.cfg").exists() {
+                    continue;
+                }
+                let Ok(entries) = std::fs::read_dir(&path) else {
+                    continue;
+                };
+                stack.extend(
+                    entries
+                        .filter_map(|it| it.ok().map(|it| (it.path(), false))),
+                );
+            } else if let Ok(modified) = metadata.modified() {
+                snapshot.insert(path, modified);
+            }
+        }
+
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_file_watcher() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("__pycache__")).unwrap();
+        std::fs::write(root.join("src").join("app.py"), "").unwrap();
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+        let excluded_root = root.clone();
+        let mut watcher = FileWatcher::new(vec![root.clone()], move |it| {
+            it == excluded_root.join("notes.txt")
+        })
+        .unwrap();
+
+        assert!(watcher.changes().unwrap().is_empty());
+        // New files are used since some file systems only record modification times to
+        // the second.
+        std::fs::write(root.join("src").join("new.py"), "").unwrap();
+        std::fs::write(root.join("__pycache__").join("app.pyc"), "").unwrap();
+        std::fs::write(root.join("notes.txt"), "changed").unwrap();
+
+        assert_eq!(
+            watcher.changes().unwrap(),
+            [root.join("src").join("new.py")]
+        );
+        std::fs::remove_file(root.join("src").join("new.py")).unwrap();
+        assert_eq!(
+            watcher.changes().unwrap(),
+            [root.join("src").join("new.py")]
+        );
+        assert!(watcher.changes().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,501 @@
+use crate::{Error, HuakResult};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+use zip::ZipArchive;
+
+const INSTALLER_NAME: &str = "huak";
+
+/// A parsed wheel file name.
+///
+/// See https://packaging.python.org/en/latest/specifications/binary-distribution-format/#file-name-convention
+///
+/// ```
+/// use huak::WheelFilename;
+///
+/// let file_name = WheelFilename::from_str("click-8.1.3-py3-none-any.whl").unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WheelFilename {
+    /// The distribution's name.
+    pub name: String,
+    /// The distribution's version.
+    pub version: String,
+    /// The Python tags the wheel supports.
+    pub python_tags: Vec<String>,
+    /// The ABI tags the wheel supports.
+    pub abi_tags: Vec<String>,
+    /// The platform tags the wheel supports.
+    pub platform_tags: Vec<String>,
+}
+
+impl WheelFilename {
+    /// Get every `python-abi-platform` tag the wheel supports.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = Vec::new();
+        for py in self.python_tags.iter() {
+            for abi in self.abi_tags.iter() {
+                for plat in self.platform_tags.iter() {
+                    tags.push(format!("{py}-{abi}-{plat}"));
+                }
+            }
+        }
+
+        tags
+    }
+
+    /// Get the position of the wheel's best tag within a list of supported tags ordered
+    /// from most to least preferred. `None` is returned if the wheel isn't supported.
+    pub fn priority(&self, supported_tags: &[String]) -> Option<usize> {
+        let tags = self.tags();
+        supported_tags.iter().position(|it| tags.contains(it))
+    }
+}
+
+impl FromStr for WheelFilename {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            Error::InternalError(format!("{s} is not a valid wheel file name"))
+        };
+        let stem = s.strip_suffix(".whl").ok_or_else(invalid)?;
+        let parts = stem.split('-').collect::<Vec<_>>();
+        // An optional build tag may be included after the version.
+        let (name, version, tags) = match parts.as_slice() {
+            [name, version, _build, tags @ ..] if tags.len() == 3 => {
+                (name, version, tags)
+            }
+            [name, version, tags @ ..] if tags.len() == 3 => {
+                (name, version, tags)
+            }
+            _ => return Err(invalid()),
+        };
+        let split = |it: &str| it.split('.').map(str::to_string).collect();
+
+        Ok(WheelFilename {
+            name: name.to_string(),
+            version: version.to_string(),
+            python_tags: split(tags[0]),
+            abi_tags: split(tags[1]),
+            platform_tags: split(tags[2]),
+        })
+    }
+}
+
+/// The directories a wheel's contents are installed into.
+///
+/// See https://docs.python.org/3/library/sysconfig.html#installation-paths
+#[derive(Debug, Clone)]
+pub struct InstallScheme {
+    /// The directory for pure Python modules (site-packages).
+    pub purelib: PathBuf,
+    /// The directory for platform-specific modules (site-packages for virtual environments).
+    pub platlib: PathBuf,
+    /// The directory for scripts and executables.
+    pub scripts: PathBuf,
+    /// The directory for data files (the environment's root).
+    pub data: PathBuf,
+    /// The directory for C headers.
+    pub headers: PathBuf,
+    /// The Python interpreter scripts are rewritten to use.
+    pub python: PathBuf,
+}
+
+/// Install a wheel file into an `InstallScheme`.
+///
+/// Files are unpacked into their scheme directories, console and GUI entry points are
+/// generated as scripts, and the distribution's INSTALLER and RECORD files are written.
+pub fn install_wheel<T: AsRef<Path>>(
+    path: T,
+    scheme: &InstallScheme,
+) -> HuakResult<()> {
+    let path = path.as_ref();
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let dist_info = dist_info_dir_name(&mut archive).ok_or(
+        Error::InternalError(format!(
+            "{} doesn't contain a .dist-info directory",
+            path.display()
+        )),
+    )?;
+    let data_dir = format!("{}.data", dist_info.trim_end_matches(".dist-info"));
+    let wheel_metadata =
+        read_archive_file(&mut archive, &format!("{dist_info}/WHEEL"))?;
+    let root = if header_value(&wheel_metadata, "Root-Is-Purelib")
+        .map_or(true, |it| it.eq_ignore_ascii_case("true"))
+    {
+        &scheme.purelib
+    } else {
+        &scheme.platlib
+    };
+    let record_path = format!("{dist_info}/RECORD");
+    let mut record = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file
+            .enclosed_name()
+            .ok_or(Error::InternalError(format!(
+                "{} contains an unsafe path {}",
+                path.display(),
+                file.name()
+            )))?
+            .to_path_buf();
+        if name.to_string_lossy().replace('\\', "/") == record_path {
+            continue;
+        }
+
+        let (dest, is_script) = match data_path(&name, &data_dir) {
+            Some((key, rest)) => {
+                let dir = match key.as_str() {
+                    "purelib" => &scheme.purelib,
+                    "platlib" => &scheme.platlib,
+                    "scripts" => &scheme.scripts,
+                    "headers" => &scheme.headers,
+                    "data" => &scheme.data,
+                    _ => {
+                        return Err(Error::InternalError(format!(
+                            "{} contains an unknown data directory {key}",
+                            path.display()
+                        )))
+                    }
+                };
+                (dir.join(rest), key == "scripts")
+            }
+            None => (root.join(&name), false),
+        };
+
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        if is_script {
+            contents = rewrite_shebang(contents, &scheme.python);
+        }
+        let executable =
+            is_script || file.unix_mode().map_or(false, |it| it & 0o111 != 0);
+        write_file(&dest, &contents, executable)?;
+        record.push(record_row(&dest, root, &contents));
+    }
+
+    let entry_points_path = format!("{dist_info}/entry_points.txt");
+    if let Ok(entry_points) =
+        read_archive_file(&mut archive, &entry_points_path)
+    {
+        for (name, module, attr) in console_scripts(&entry_points) {
+            let dest = scheme.scripts.join(&name);
+            let contents = entry_point_script(&scheme.python, &module, &attr);
+            write_file(&dest, contents.as_bytes(), true)?;
+            record.push(record_row(&dest, root, contents.as_bytes()));
+        }
+    }
+
+    let installer = root.join(&dist_info).join("INSTALLER");
+    let contents = format!("{INSTALLER_NAME}\n");
+    write_file(&installer, contents.as_bytes(), false)?;
+    record.push(record_row(&installer, root, contents.as_bytes()));
+    record.push(format!("{record_path},,"));
+    record.push(String::new());
+    std::fs::write(root.join(&record_path), record.join("\n"))?;
+
+    Ok(())
+}
+
+/// Check that a file matches a hash formatted as `<algorithm>:<hex digest>` (or
+/// `<algorithm>=<hex digest>`).
+pub fn verify_hash<T: AsRef<Path>>(path: T, hash: &str) -> HuakResult<bool> {
+    let (algorithm, expected) = hash
+        .split_once([':', '='])
+        .ok_or(Error::InternalError(format!("{hash} is not a valid hash")))?;
+    let contents = std::fs::read(path)?;
+    let digest = match algorithm {
+        "sha256" => hex(&Sha256::digest(&contents)),
+        "sha384" => hex(&Sha384::digest(&contents)),
+        "sha512" => hex(&Sha512::digest(&contents)),
+        _ => {
+            return Err(Error::Unimplemented(format!(
+                "{algorithm} hashes are not supported"
+            )))
+        }
+    };
+
+    Ok(digest.eq_ignore_ascii_case(expected))
+}
+
+/// Read the METADATA file of a wheel.
+pub fn wheel_metadata<T: AsRef<Path>>(path: T) -> HuakResult<String> {
+    let path = path.as_ref();
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let dist_info = dist_info_dir_name(&mut archive).ok_or(
+        Error::InternalError(format!(
+            "{} doesn't contain a .dist-info directory",
+            path.display()
+        )),
+    )?;
+
+    read_archive_file(&mut archive, &format!("{dist_info}/METADATA"))
+}
+
+/// Find the name of the archive's .dist-info directory.
+fn dist_info_dir_name(archive: &mut ZipArchive<File>) -> Option<String> {
+    archive.file_names().find_map(|name| {
+        let (dir, file) = name.split_once('/')?;
+        (dir.ends_with(".dist-info") && file == "WHEEL")
+            .then(|| dir.to_string())
+    })
+}
+
+/// Read a file from the archive as a string.
+fn read_archive_file(
+    archive: &mut ZipArchive<File>,
+    name: &str,
+) -> HuakResult<String> {
+    let mut file = archive.by_name(name)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    Ok(contents)
+}
+
+/// Get the value of an RFC 822-style header.
+fn header_value<'a>(contents: &'a str, key: &str) -> Option<&'a str> {
+    contents.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        k.trim().eq_ignore_ascii_case(key).then(|| v.trim())
+    })
+}
+
+/// Split a path within the wheel's `.data` directory into its scheme key and the
+/// remaining path.
+fn data_path(path: &Path, data_dir: &str) -> Option<(String, PathBuf)> {
+    let mut components = path.components();
+    match components.next() {
+        Some(Component::Normal(it)) if it == data_dir => (),
+        _ => return None,
+    }
+    let key = components.next()?.as_os_str().to_string_lossy().to_string();
+
+    Some((key, components.as_path().to_path_buf()))
+}
+
+/// Rewrite a script's `#!python` shebang to use the environment's interpreter.
+fn rewrite_shebang(contents: Vec<u8>, python: &Path) -> Vec<u8> {
+    let Some(rest) = contents
+        .strip_prefix(b"#!pythonw")
+        .or_else(|| contents.strip_prefix(b"#!python"))
+    else {
+        return contents;
+    };
+    let mut rewritten = format!("#!{}", python.display()).into_bytes();
+    rewritten.extend_from_slice(rest);
+
+    rewritten
+}
+
+/// Parse the console and GUI scripts from an entry_points.txt file as
+/// `(name, module, attribute)`.
+fn console_scripts(contents: &str) -> Vec<(String, String, String)> {
+    let mut scripts = Vec::new();
+    let mut in_section = false;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = matches!(line, "[console_scripts]" | "[gui_scripts]");
+            continue;
+        }
+        if !in_section || line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, target)) = line.split_once('=') else {
+            continue;
+        };
+        // Extras (`module:attr [extra]`) don't affect the generated script.
+        let target = target.split('[').next().unwrap_or_default().trim();
+        let (module, attr) = target.split_once(':').unwrap_or((target, ""));
+        scripts.push((
+            name.trim().to_string(),
+            module.trim().to_string(),
+            attr.trim().to_string(),
+        ));
+    }
+
+    scripts
+}
+
+/// Generate the script for an entry point.
+fn entry_point_script(python: &Path, module: &str, attr: &str) -> String {
+    let (import, call) = match attr.split_once('.') {
+        Some((head, _)) => (head, attr),
+        None if attr.is_empty() => (module, module),
+        None => (attr, attr),
+    };
+    let import = if attr.is_empty() {
+        format!("import {module}")
+    } else {
+        format!("from {module} import {import}")
+    };
+
+    format!(
+        "#!{}\n# -*- coding: utf-8 -*-\nimport re\nimport sys\n{import}\nif __name__ == \"__main__\":\n    sys.argv[0] = re.sub(r\"(-script\\.pyw|\\.exe)?$\", \"\", sys.argv[0])\n    sys.exit({call}())\n",
+        python.display()
+    )
+}
+
+/// Write a file, creating its parent directories.
+fn write_file(
+    path: &Path,
+    contents: &[u8],
+    executable: bool,
+) -> HuakResult<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+
+    Ok(())
+}
+
+/// Format a RECORD row for an installed file.
+fn record_row(path: &Path, root: &Path, contents: &[u8]) -> String {
+    let path = relative_path(path, root)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let hash = URL_SAFE_NO_PAD.encode(Sha256::digest(contents));
+    let path = if path.contains([',', '"']) {
+        format!("\"{}\"", path.replace('"', "\"\""))
+    } else {
+        path
+    };
+
+    format!("{path},sha256={hash},{}", contents.len())
+}
+
+/// Get a path relative to a base directory, using `..` components if the path isn't
+/// within the base.
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path = path.components().collect::<Vec<_>>();
+    let base = base.components().collect::<Vec<_>>();
+    let common = path
+        .iter()
+        .zip(base.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component);
+    }
+
+    relative
+}
+
+/// Encode bytes as lowercase hex.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|it| format!("{it:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use zip::{write::FileOptions, ZipWriter};
+
+    fn build_wheel(path: &Path) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        let files = [
+            ("mock/__init__.py", "def main():\n    pass\n"),
+            ("mock-0.1.0.data/scripts/mock-tool", "#!python\nprint(1)\n"),
+            (
+                "mock-0.1.0.dist-info/METADATA",
+                "Metadata-Version: 2.1\nName: mock\nVersion: 0.1.0\n",
+            ),
+            (
+                "mock-0.1.0.dist-info/WHEEL",
+                "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n",
+            ),
+            (
+                "mock-0.1.0.dist-info/entry_points.txt",
+                "[console_scripts]\nmock = mock:main\n",
+            ),
+            ("mock-0.1.0.dist-info/RECORD", ""),
+        ];
+        for (name, contents) in files {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_wheel_filename() {
+        let file_name = WheelFilename::from_str(
+            "numpy-1.24.3-1-cp311-cp311-manylinux_2_17_x86_64.manylinux2014_x86_64.whl",
+        )
+        .unwrap();
+        let supported = ["cp311-cp311-manylinux_2_17_x86_64", "py3-none-any"]
+            .map(str::to_string);
+
+        assert_eq!(file_name.name, "numpy");
+        assert_eq!(file_name.version, "1.24.3");
+        assert_eq!(file_name.priority(&supported), Some(0));
+        assert!(WheelFilename::from_str("numpy-1.24.3.tar.gz").is_err());
+    }
+
+    #[test]
+    fn test_install_wheel() {
+        let dir = tempdir().unwrap();
+        let wheel = dir.path().join("mock-0.1.0-py3-none-any.whl");
+        build_wheel(&wheel);
+        let root = dir.path().join("venv");
+        let site_packages = root.join("lib").join("site-packages");
+        let scheme = InstallScheme {
+            purelib: site_packages.clone(),
+            platlib: site_packages.clone(),
+            scripts: root.join("bin"),
+            data: root.clone(),
+            headers: root.join("include"),
+            python: root.join("bin").join("python"),
+        };
+
+        install_wheel(&wheel, &scheme).unwrap();
+
+        let dist_info = site_packages.join("mock-0.1.0.dist-info");
+        let record = std::fs::read_to_string(dist_info.join("RECORD")).unwrap();
+        let script =
+            std::fs::read_to_string(root.join("bin").join("mock-tool"))
+                .unwrap();
+        assert!(site_packages.join("mock").join("__init__.py").exists());
+        assert!(root.join("bin").join("mock").exists());
+        assert!(script.starts_with(&format!("#!{}\n", scheme.python.display())));
+        assert_eq!(
+            std::fs::read_to_string(dist_info.join("INSTALLER")).unwrap(),
+            "huak\n"
+        );
+        assert!(record.contains("mock/__init__.py,sha256="));
+        assert!(record.contains("../../bin/mock,sha256="));
+        assert!(record.contains("mock-0.1.0.dist-info/RECORD,,"));
+        assert!(verify_hash(
+            &wheel,
+            &format!(
+                "sha256:{}",
+                hex(&Sha256::digest(std::fs::read(&wheel).unwrap()))
+            )
+        )
+        .unwrap());
+    }
+}
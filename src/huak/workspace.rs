@@ -2,20 +2,42 @@ use crate::package::Package;
 use crate::{
     environment::Environment,
     fs,
+    journal::Journal,
+    lockfile::{lockfile_file_name, Lockfile},
     metadata::LocalMetadata,
-    python_environment::{default_venv_name, venv_config_file_name},
-    Config, Error, HuakResult, PythonEnvironment,
+    python_environment::{
+        default_venv_name, venv_config_file_name, Interpreter, PythonRequest,
+        VenvOptions,
+    },
+    sys::{self, Terminal},
+    Config, Error, HuakResult, PythonEnvironment, TerminalOptions,
 };
 use std::{
+    cell::RefCell,
+    fmt::Display,
     path::{Path, PathBuf},
     process::Command,
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, SystemTime},
 };
+use termcolor::Color;
+
+/// The name of the file used to pin a project's Python version (for example "3.11").
+const PYTHON_VERSION_FILE_NAME: &str = ".python-version";
+/// The path to the `WorkspaceLock` file relative to the `Workspace` root.
+const LOCK_FILE_PATH: &str = ".huak/lock";
+/// How often a locked `Workspace` is checked while waiting for its lock.
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 /// The `Workspace` is a struct for resolving things like the current `Package`
 /// or the current `PythonEnvironment`. It can also provide a snapshot of the `Environment`,
 /// a more general struct containing information like environment variables, Python
 /// `Interpreters` found, etc.
 ///
+/// State resolved by the `Workspace` is cached for its lifetime. Cached `LocalMetadata`
+/// and `PythonEnvironment`s are invalidated when their files change on disk.
+///
 /// ```
 /// use huak::Workspace;
 ///
@@ -25,15 +47,31 @@ use std::{
 pub struct Workspace {
     /// The established `Workspace` root path.
     root: PathBuf,
-    /// The `Config` associated with the `Workspace`.
-    config: Config,
+    /// The current working directory used to resolve the `Workspace`'s state.
+    cwd: PathBuf,
+    /// `Terminal` options to use.
+    terminal_options: TerminalOptions,
+    /// Wait for another process's `WorkspaceLock` to be released instead of failing.
+    wait_for_lock: bool,
+    /// The cached `Environment`.
+    environment: RefCell<Option<Rc<Environment>>>,
+    /// The cached `LocalMetadata` and the `FileStamp` of its file.
+    local_metadata: RefCell<Option<(LocalMetadata, Option<FileStamp>)>>,
+    /// The cached `PythonEnvironment` and the `FileStamp` of its configuration file.
+    python_environment:
+        RefCell<Option<(Rc<PythonEnvironment>, Option<FileStamp>)>>,
 }
 
 impl Workspace {
     pub fn new<T: AsRef<Path>>(path: T, config: &Config) -> Self {
         let workspace = Workspace {
             root: path.as_ref().to_path_buf(),
-            config: config.clone(),
+            cwd: config.cwd.clone(),
+            terminal_options: config.terminal_options.clone(),
+            wait_for_lock: config.wait_for_lock,
+            environment: RefCell::new(None),
+            local_metadata: RefCell::new(None),
+            python_environment: RefCell::new(None),
         };
 
         workspace
@@ -44,9 +82,32 @@ impl Workspace {
         &self.root
     }
 
+    /// Get a reference to the current working directory used by the `Workspace`.
+    pub fn cwd(&self) -> &PathBuf {
+        &self.cwd
+    }
+
+    /// Get the directory searches for the `Workspace`'s state start from. Searches start
+    /// from the current working directory unless it's outside of the `Workspace`.
+    fn search_start(&self) -> &PathBuf {
+        if self.cwd.starts_with(&self.root) {
+            &self.cwd
+        } else {
+            &self.root
+        }
+    }
+
+    /// Get a `Terminal` based on the `Workspace`'s `TerminalOptions`.
+    fn terminal(&self) -> Terminal {
+        self.terminal_options.terminal()
+    }
+
     /// Get an `Environment` associated with the `Workspace`.
-    pub fn environment(&self) -> Environment {
-        Environment::new()
+    pub fn environment(&self) -> Rc<Environment> {
+        let mut cache = self.environment.borrow_mut();
+        let env = cache.get_or_insert_with(|| Rc::new(Environment::new()));
+
+        Rc::clone(env)
     }
 
     /// Get the current `Package`. The current `Package` is one found by its metadata file nearest based
@@ -62,17 +123,127 @@ impl Workspace {
 
     /// Get the current `LocalMetadata` based on the `Config` data.
     pub fn current_local_metadata(&self) -> HuakResult<LocalMetadata> {
-        let package_root = find_package_root(&self.config.cwd, &self.root)?;
+        let mut cache = self.local_metadata.borrow_mut();
+        if let Some((metadata, stamp)) = cache.as_ref() {
+            if metadata.path().exists() && &file_stamp(metadata.path()) == stamp
+            {
+                return Ok(metadata.clone());
+            }
+        }
+
+        let package_root = find_package_root(self.search_start(), &self.root)?;
 
         // Currently only pyproject.toml is supported.
         let path = package_root.join("pyproject.toml");
+        let stamp = file_stamp(&path);
         let metadata = LocalMetadata::new(path)?;
+        *cache = Some((metadata.clone(), stamp));
 
         Ok(metadata)
     }
 
+    /// Get the current `Lockfile` if one exists next to the current `LocalMetadata` file.
+    pub fn current_lockfile(&self) -> HuakResult<Option<Lockfile>> {
+        let metadata = self.current_local_metadata()?;
+        let path = match metadata.path().parent() {
+            Some(it) => it.join(lockfile_file_name()),
+            None => return Ok(None),
+        };
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(Lockfile::from_path(path)?))
+    }
+
+    /// Start a `Journal` recording what a mutating op changes in the `Workspace`, so that
+    /// it can be rolled back. The changes are written when the `Journal` is dropped.
+    pub fn journal(&self, operation: &str) -> HuakResult<Journal> {
+        Journal::begin(self, operation)
+    }
+
+    /// Acquire the `Workspace`'s `WorkspaceLock` so that mutating ops in other huak
+    /// processes don't modify the project's metadata or `PythonEnvironment` concurrently.
+    /// Locks left behind by processes that are no longer running, and lock files whose
+    /// owner can't be read, are removed. The lock is reentrant within a process. A Python
+    /// environment an interrupted huak process was replacing is restored once the lock is
+    /// acquired (see `fs::DirReplacement`).
+    pub fn lock(&self) -> HuakResult<WorkspaceLock> {
+        let path = self.root.join(LOCK_FILE_PATH);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let pid = std::process::id();
+        let mut waiting = false;
+        let mut unreadable = false;
+
+        loop {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    std::io::Write::write_all(
+                        &mut file,
+                        pid.to_string().as_bytes(),
+                    )?;
+                    let lock = WorkspaceLock { path: Some(path) };
+                    fs::recover_replaced_dir(
+                        self.root.join(default_venv_name()),
+                    )?;
+                    return Ok(lock);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => (),
+                Err(e) => return Err(Error::IOError(e)),
+            }
+
+            // The lock file may be mid-write, so an unreadable pid is given one poll
+            // interval to be written before the lock is treated as stale.
+            let owner = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|it| it.trim().parse::<u32>().ok());
+            if owner.is_some() {
+                unreadable = false;
+            }
+            match owner {
+                None if unreadable => {
+                    unreadable = false;
+                    remove_stale_lock(&path, owner);
+                    continue;
+                }
+                None => unreadable = true,
+                Some(it) if it == pid => {
+                    return Ok(WorkspaceLock { path: None })
+                }
+                Some(it) if !sys::process_is_running(it) => {
+                    remove_stale_lock(&path, owner);
+                    continue;
+                }
+                Some(it) if !self.wait_for_lock => {
+                    return Err(Error::WorkspaceLocked(it))
+                }
+                Some(it) if !waiting => {
+                    waiting = true;
+                    self.terminal().print_custom(
+                        "Waiting",
+                        format!(
+                            "for another huak process (pid {it}) to finish"
+                        ),
+                        Color::Yellow,
+                        true,
+                    )?;
+                }
+                _ => (),
+            }
+            std::thread::sleep(LOCK_POLL_INTERVAL);
+        }
+    }
+
     /// Resolve a `PythonEnvironment` pulling the current or creating one if none is found.
-    pub fn resolve_python_environment(&self) -> HuakResult<PythonEnvironment> {
+    pub fn resolve_python_environment(
+        &self,
+    ) -> HuakResult<Rc<PythonEnvironment>> {
         // NOTE: Currently only virtual environments are supported. We search for them, stopping
         // at the configured workspace root. If none is found we create a new one at the
         // workspace root.
@@ -89,24 +260,122 @@ impl Workspace {
 
     /// Get the current `PythonEnvironment`. The current `PythonEnvironment` is one
     /// found by its configuration file or `Interpreter` nearest baseed on `Config` data.
-    pub fn current_python_environment(&self) -> HuakResult<PythonEnvironment> {
-        let path = find_venv_root(&self.config.cwd, &self.root)?;
-        let env = PythonEnvironment::new(path)?;
+    pub fn current_python_environment(
+        &self,
+    ) -> HuakResult<Rc<PythonEnvironment>> {
+        let mut cache = self.python_environment.borrow_mut();
+        if let Some((env, stamp)) = cache.as_ref() {
+            let path = env.root().join(venv_config_file_name());
+            if path.exists() && &file_stamp(path) == stamp {
+                return Ok(Rc::clone(env));
+            }
+        }
+
+        let path = find_venv_root(self.search_start(), &self.root)?;
+        let stamp = file_stamp(path.join(venv_config_file_name()));
+        let env = Rc::new(PythonEnvironment::new(path)?);
+        *cache = Some((Rc::clone(&env), stamp));
 
         Ok(env)
     }
 
-    /// Create a `PythonEnvironment` for the `Workspace`.
-    fn new_python_environment(&self) -> HuakResult<PythonEnvironment> {
+    /// Select the Python `Interpreter` the `Workspace` uses from `interpreters` (in `PATH`
+    /// order). An existing `PythonEnvironment`'s `Interpreter` is used first, then the
+    /// version pinned by a .python-version file, then the latest `Interpreter` satisfying
+    /// the project's `requires-python`, and finally the first `Interpreter` on `PATH`.
+    pub fn select_python_interpreter(
+        &self,
+        interpreters: &[Interpreter],
+    ) -> HuakResult<(Interpreter, InterpreterSelection)> {
+        match self.current_python_environment() {
+            Ok(env) => {
+                let interpreter = Interpreter::new(
+                    env.python_path(),
+                    env.python_version().clone(),
+                );
+                return Ok((
+                    interpreter,
+                    InterpreterSelection::Environment(env.root().to_path_buf()),
+                ));
+            }
+            Err(Error::PythonEnvironmentNotFound) => (),
+            Err(e) => return Err(e),
+        }
+
+        let pin_file = self.root.join(PYTHON_VERSION_FILE_NAME);
+        if let Ok(contents) = std::fs::read_to_string(pin_file) {
+            let pin = contents
+                .lines()
+                .map(str::trim)
+                .find(|it| !it.is_empty() && !it.starts_with('#'))
+                .unwrap_or_default();
+            let request = PythonRequest::from_str(pin).map_err(|_| {
+                Error::HuakConfigurationError(format!(
+                    "{PYTHON_VERSION_FILE_NAME} pins an invalid Python version {pin:?}"
+                ))
+            })?;
+            return match interpreters
+                .iter()
+                .find(|it| request.matches(it))
+            {
+                Some(it) => Ok((
+                    it.clone(),
+                    InterpreterSelection::PinFile(pin.to_string()),
+                )),
+                None => Err(Error::HuakConfigurationError(format!(
+                    "no Python interpreter found for {pin} pinned by {PYTHON_VERSION_FILE_NAME}"
+                ))),
+            };
+        }
+
+        let requires_python = self
+            .current_local_metadata()
+            .ok()
+            .and_then(|it| it.metadata().project().requires_python.clone());
+        if let Some(specifiers) = requires_python {
+            return match interpreters
+                .iter()
+                .filter(|it| {
+                    pep440_rs::Version::from_str(&it.version().to_string())
+                        .map_or(false, |version| specifiers.contains(&version))
+                })
+                .max()
+            {
+                Some(it) => Ok((
+                    it.clone(),
+                    InterpreterSelection::RequiresPython(specifiers.to_string()),
+                )),
+                None => Err(Error::HuakConfigurationError(format!(
+                    "no Python interpreter found for requires-python {specifiers}"
+                ))),
+            };
+        }
+
+        match interpreters.first() {
+            Some(it) => Ok((it.clone(), InterpreterSelection::Path)),
+            None => Err(Error::PythonNotFound),
+        }
+    }
+
+    /// Create a `PythonEnvironment` for the `Workspace` with the `Interpreter` selected by
+    /// `select_python_interpreter`. The project's dependencies are installed into it if
+    /// `[tool.huak.venv] install-dependencies` is enabled.
+    fn new_python_environment(&self) -> HuakResult<Rc<PythonEnvironment>> {
+        // Another process may have created the environment while this one waited.
+        let _lock = self.lock()?;
+        match self.current_python_environment() {
+            Ok(it) => return Ok(it),
+            Err(Error::PythonEnvironmentNotFound) => (),
+            Err(e) => return Err(e),
+        }
+
         // Get a snapshot of the environment.
         let env = self.environment();
 
-        // Get the first Python `Interpreter` path found from the `PATH`
-        // environment variable.
-        let python_path = match env.python_paths().next() {
-            Some(it) => it,
-            None => return Err(Error::PythonNotFound),
-        };
+        // Select a Python `Interpreter` from the ones found on `PATH`.
+        let (interpreter, reason) =
+            self.select_python_interpreter(env.interpreters().interpreters())?;
+        let python_path = interpreter.path();
 
         // Set the name and path of the `PythonEnvironment. Note that we currently only
         // support virtual environments.
@@ -115,21 +384,183 @@ impl Workspace {
 
         // Create the `PythonEnvironment`. This uses the `venv` module distributed with Python.
         // Note that this will fail on systems with minimal Python distributions.
-        let args = ["-m", "venv", name];
-        let mut cmd = Command::new(python_path);
-        cmd.args(args).current_dir(&self.root);
-        self.config.terminal().run_command(&mut cmd)?;
+        let metadata = self.current_local_metadata().ok();
+        let options = match metadata.as_ref() {
+            Some(it) => VenvOptions::from_metadata(it.metadata())?,
+            None => VenvOptions::default(),
+        };
+        let mut cmd = options.venv_command(python_path, name);
+        cmd.current_dir(&self.root);
+        self.terminal().run_command(&mut cmd)?;
 
-        let python_env = PythonEnvironment::new(path)?;
+        let stamp = file_stamp(path.join(venv_config_file_name()));
+        let python_env = Rc::new(PythonEnvironment::new(path)?);
+        *self.python_environment.borrow_mut() =
+            Some((Rc::clone(&python_env), stamp));
+        let mut terminal = self.terminal();
+        terminal.print_custom(
+            "Created",
+            format!(
+                "environment {name} with {} {} (selected by {reason})",
+                interpreter.implementation(),
+                python_env.python_version()
+            ),
+            Color::Green,
+            true,
+        )?;
+
+        let dependencies = match metadata.as_ref() {
+            Some(it) if options.install_dependencies => {
+                it.metadata().dependencies().unwrap_or_default()
+            }
+            _ => &[],
+        };
+        if !dependencies.is_empty() {
+            let mut cmd = Command::new(python_env.python_path());
+            cmd.args(["-m", "pip", "install"])
+                .args(dependencies.iter().map(ToString::to_string));
+            terminal.run_command(&mut cmd)?;
+            terminal.print_custom(
+                "Installed",
+                format!("{} dependencies into {name}", dependencies.len()),
+                Color::Green,
+                true,
+            )?;
+        }
 
         Ok(python_env)
     }
 }
 
+/// Remove the stale lock file at `path` whose owner was read as `owner`. Other huak
+/// processes may find the same lock stale and take it over first, so the file is moved
+/// aside and only removed if it's still the stale lock. A new lock is moved back.
+fn remove_stale_lock(path: &Path, owner: Option<u32>) {
+    let mut stale = path.as_os_str().to_os_string();
+    stale.push(format!(".stale-{}", std::process::id()));
+    let stale = PathBuf::from(stale);
+    if std::fs::rename(path, &stale).is_err() {
+        return;
+    }
+    let current = std::fs::read_to_string(&stale)
+        .ok()
+        .and_then(|it| it.trim().parse::<u32>().ok());
+    if current != owner {
+        // Linking doesn't replace a lock created in the meantime.
+        std::fs::hard_link(&stale, path).ok();
+    }
+    std::fs::remove_file(&stale).ok();
+}
+
+/// A `WorkspaceLock` is held by an op while it modifies the `Workspace`. The lock file
+/// records the holding process's id and is removed when the `WorkspaceLock` is dropped.
+pub struct WorkspaceLock {
+    /// The path to the lock file, or `None` if the lock was already held by this process.
+    path: Option<PathBuf>,
+}
+
+impl Drop for WorkspaceLock {
+    fn drop(&mut self) {
+        if let Some(it) = self.path.as_ref() {
+            std::fs::remove_file(it).ok();
+        }
+    }
+}
+
+/// The reason the `Workspace` selects a Python `Interpreter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterpreterSelection {
+    /// The `Interpreter` of the `Workspace`'s existing `PythonEnvironment`.
+    Environment(PathBuf),
+    /// The `Interpreter` matching the version pinned by a .python-version file.
+    PinFile(String),
+    /// The latest `Interpreter` satisfying the project's `requires-python`.
+    RequiresPython(String),
+    /// The first `Interpreter` found on `PATH`.
+    Path,
+}
+
+impl Display for InterpreterSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpreterSelection::Environment(it) => {
+                write!(f, "environment {}", it.display())
+            }
+            InterpreterSelection::PinFile(it) => {
+                write!(f, "{PYTHON_VERSION_FILE_NAME} {it}")
+            }
+            InterpreterSelection::RequiresPython(it) => {
+                write!(f, "requires-python {it}")
+            }
+            InterpreterSelection::Path => write!(f, "PATH order"),
+        }
+    }
+}
+
+/// A file's modification time and length, used to detect changes to cached files.
+type FileStamp = (SystemTime, u64);
+
+/// Get the `FileStamp` of a file if it can be read.
+fn file_stamp<T: AsRef<Path>>(path: T) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[derive(Clone, Default)]
 /// A struct used to configure options for `Workspace`s.
 pub struct WorkspaceOptions {
     /// Inidcate the `Workspace` should use git.
     pub uses_git: bool,
+    /// The project's name. Defaults to the name of the `Workspace` root directory.
+    pub name: Option<String>,
+    /// The minimum Python version the project supports (for example "3.11").
+    pub python_version: Option<String>,
+    /// The project's license (for example "MIT").
+    pub license: Option<String>,
+    /// Tools added to the project's "dev" optional dependency group.
+    pub dev_dependencies: Vec<String>,
+    /// Populate the project's dependencies from its requirements files, or the active
+    /// Python environment's top-level packages, when initializing it.
+    pub from_requirements: bool,
+    /// Add the tests directory and .gitignore when initializing a project that's
+    /// missing them.
+    pub scaffold: bool,
+    /// Check that a new project's name isn't already taken on the package index.
+    pub check_index: bool,
+}
+
+/// Discover the root of the `Workspace` containing a directory.
+/// 1. Walk from the `from` dir upwards, searching for the nearest dir containing a pyproject.toml file.
+/// 2. Stop after searching a dir containing `.git` or the `boundary` dir.
+pub fn discover_workspace_root<T: AsRef<Path>>(
+    from: T,
+    boundary: Option<&Path>,
+) -> Option<PathBuf> {
+    for dir in from.as_ref().ancestors() {
+        if dir.join("pyproject.toml").exists() {
+            return Some(dir.to_path_buf());
+        }
+        if dir.join(".git").exists() || boundary == Some(dir) {
+            break;
+        }
+    }
+
+    None
+}
+
+/// Resolve a project directory passed with `-C`/`--project` relative to the current
+/// working directory. The directory is resolved canonically and must exist.
+pub fn project_root(cwd: &Path, path: &Path) -> HuakResult<PathBuf> {
+    let root = fs::canonical_path(cwd.join(path));
+    if !root.is_dir() {
+        return Err(Error::HuakConfigurationError(format!(
+            "{} is not a directory",
+            root.display()
+        )));
+    }
+
+    Ok(root)
 }
 
 /// Search for a Python virtual environment.
@@ -184,13 +615,14 @@ pub fn find_package_root<T: AsRef<Path>>(
     }
 
     // Currently only pyproject.toml is supported
+    let searched_from = from.as_ref().to_path_buf();
     let file_path = match fs::find_root_file_bottom_up(
         "pyproject.toml",
         from,
         stop_after,
     ) {
-        Ok(it) => it.ok_or(Error::MetadataFileNotFound)?,
-        Err(_) => return Err(Error::MetadataFileNotFound),
+        Ok(it) => it.ok_or(Error::MetadataFileNotFound(searched_from))?,
+        Err(_) => return Err(Error::MetadataFileNotFound(searched_from)),
     };
 
     // The root of the venv is always the parent dir to the pyvenv.cfg file.
@@ -203,3 +635,283 @@ pub fn find_package_root<T: AsRef<Path>>(
 
     Ok(root)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dependency::Dependency, test_resources_dir_path, Verbosity};
+    use std::{rc::Rc, str::FromStr};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_discover_workspace_root() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("project");
+        let sub = root.join("src").join("pkg").join("sub");
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::write(root.join("pyproject.toml"), "").unwrap();
+
+        assert_eq!(discover_workspace_root(&sub, None), Some(root.clone()));
+        assert_eq!(
+            discover_workspace_root(&sub, Some(&root.join("src"))),
+            None
+        );
+
+        std::fs::create_dir(root.join("src").join(".git")).unwrap();
+
+        assert_eq!(discover_workspace_root(&sub, None), None);
+    }
+
+    #[test]
+    fn test_project_root() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path().join("cwd");
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(&cwd).unwrap();
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(project.join("pyproject.toml"), "").unwrap();
+        let expected = fs::canonical_path(&project);
+
+        assert_eq!(
+            project_root(&cwd, Path::new("../project")).unwrap(),
+            expected
+        );
+        assert_eq!(project_root(&cwd, &project).unwrap(), expected);
+        assert_eq!(
+            project_root(&cwd, &project.join("src").join("..")).unwrap(),
+            expected
+        );
+        assert!(matches!(
+            project_root(&cwd, Path::new("missing")),
+            Err(Error::HuakConfigurationError(_))
+        ));
+        assert!(project_root(&cwd, &project.join("pyproject.toml")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_project_root_symlink() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        std::os::unix::fs::symlink(&project, dir.path().join("link")).unwrap();
+
+        assert_eq!(
+            project_root(dir.path(), &dir.path().join("link")).unwrap(),
+            fs::canonical_path(&project)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_workspace_lock() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let config = Config::new(
+            root.clone(),
+            root.clone(),
+            TerminalOptions {
+                verbosity: Verbosity::Quiet,
+                assume_yes: true,
+            },
+        );
+        let ws = config.workspace();
+        let path = root.join(LOCK_FILE_PATH);
+
+        let lock = ws.lock().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            std::process::id().to_string()
+        );
+        drop(ws.lock().unwrap());
+        assert!(path.exists());
+        drop(lock);
+        assert!(!path.exists());
+
+        let mut child = Command::new("/bin/sh")
+            .args(["-c", "read it"])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        std::fs::write(&path, child.id().to_string()).unwrap();
+        assert!(matches!(
+            ws.lock(),
+            Err(Error::WorkspaceLocked(it)) if it == child.id()
+        ));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(ws.lock().is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_workspace_lock_stale() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let config = Config::new(
+            root.clone(),
+            root.clone(),
+            TerminalOptions {
+                verbosity: Verbosity::Quiet,
+                assume_yes: true,
+            },
+        );
+        let ws = config.workspace();
+        let path = root.join(LOCK_FILE_PATH);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut child = Command::new("/bin/sh")
+            .args(["-c", "true"])
+            .spawn()
+            .unwrap();
+        child.wait().unwrap();
+        std::fs::write(&path, child.id().to_string()).unwrap();
+
+        let lock = ws.lock().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            std::process::id().to_string()
+        );
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+        drop(lock);
+    }
+
+    #[test]
+    fn test_remove_stale_lock() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("lock");
+        std::fs::write(&path, "1").unwrap();
+
+        // Another process took over the stale lock first.
+        remove_stale_lock(&path, Some(2));
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        remove_stale_lock(&path, Some(1));
+
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_workspace_lock_unreadable() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let config = Config::new(
+            root.clone(),
+            root.clone(),
+            TerminalOptions {
+                verbosity: Verbosity::Quiet,
+                assume_yes: true,
+            },
+        );
+        let ws = config.workspace();
+        let path = root.join(LOCK_FILE_PATH);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "").unwrap();
+
+        let lock = ws.lock().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            std::process::id().to_string()
+        );
+        drop(lock);
+    }
+
+    #[test]
+    fn test_workspace_cache() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let cwd = root.clone();
+        let config = Config::new(
+            root,
+            cwd,
+            TerminalOptions {
+                verbosity: Verbosity::Quiet,
+                assume_yes: true,
+            },
+        );
+
+        assert!(Rc::ptr_eq(&config.workspace(), &config.workspace()));
+
+        let ws = config.workspace();
+        let mut metadata = ws.current_local_metadata().unwrap();
+        let dep = Dependency::from_str("isort").unwrap();
+        metadata.metadata_mut().add_dependency(dep.clone());
+        metadata.write_file().unwrap();
+
+        assert!(ws
+            .current_local_metadata()
+            .unwrap()
+            .metadata()
+            .contains_dependency(&dep)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_select_python_interpreter() {
+        let dir = tempdir().unwrap();
+        fs::copy_dir(
+            &test_resources_dir_path().join("mock-project"),
+            &dir.path().join("mock-project"),
+        )
+        .unwrap();
+        let root = dir.path().join("mock-project");
+        let config = Config::new(
+            root.clone(),
+            root.clone(),
+            TerminalOptions {
+                verbosity: Verbosity::Quiet,
+                assume_yes: true,
+            },
+        );
+        let ws = config.workspace();
+        let interpreters = ["3.10.4", "3.12.1", "3.11.2"]
+            .into_iter()
+            .map(|it| {
+                Interpreter::new(
+                    format!("python{it}"),
+                    crate::Version::from_str(it).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let (interpreter, reason) =
+            ws.select_python_interpreter(&interpreters).unwrap();
+        assert_eq!(interpreter.version().to_string(), "3.10.4");
+        assert_eq!(reason, InterpreterSelection::Path);
+
+        let mut metadata = ws.current_local_metadata().unwrap();
+        metadata
+            .metadata_mut()
+            .set_requires_python(">=3.10,<3.12".parse().unwrap());
+        metadata.write_file().unwrap();
+        let (interpreter, reason) =
+            ws.select_python_interpreter(&interpreters).unwrap();
+        assert_eq!(interpreter.version().to_string(), "3.11.2");
+        assert_eq!(
+            reason,
+            InterpreterSelection::RequiresPython(">=3.10, <3.12".to_string())
+        );
+
+        std::fs::write(root.join(PYTHON_VERSION_FILE_NAME), "3.12\n").unwrap();
+        let (interpreter, reason) =
+            ws.select_python_interpreter(&interpreters).unwrap();
+        assert_eq!(interpreter.version().to_string(), "3.12.1");
+        assert_eq!(reason, InterpreterSelection::PinFile("3.12".to_string()));
+
+        std::fs::write(root.join(PYTHON_VERSION_FILE_NAME), "3.9").unwrap();
+        assert!(ws.select_python_interpreter(&interpreters).is_err());
+    }
+}